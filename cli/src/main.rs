@@ -1,49 +1,202 @@
-use clap::Parser;
+use clap::{CommandFactory, Parser, ValueEnum};
+use clap_complete::Shell;
+use dir_compare_core::ComparisonStrategyType;
+use dir_compare_core::block_diff::{DEFAULT_BLOCK_SIZE, locate_differences};
+use dir_compare_core::builder::Comparison;
+use dir_compare_core::chunk_dedupe::{
+    ChunkDedupeOptions, DEFAULT_AVG_CHUNK_SIZE, MAX_AVG_CHUNK_SIZE, MIN_AVG_CHUNK_SIZE,
+};
 use dir_compare_core::comparison::{
-    compare_directories, compare_directories_flat, ComparisonStrategy, FastHashStrategy,
-    FilenameOnlyStrategy, FilenameSizeStrategy, FlatComparisonOptions, SampledHashStrategy,
+    CachedHashStrategy, ComparisonError, ComparisonEstimate, ComparisonStats, ComparisonStrategy,
+    EntryFilter, FileWarning, HashAlgorithm, HashCache, Progress, ProgressPhase, ResultDetail,
+    StatMode,
+};
+use dir_compare_core::config::{config_to_args, parse_config};
+use dir_compare_core::data_profile::{DEFAULT_ADVICE_RAW_SHARE, DataProfiler, sampling_advice};
+use dir_compare_core::doctor::{
+    CheckStatus, DEFAULT_SCAN_LIMIT, RootScan, available_space, check_case_sensitivity,
+    check_clock, check_free_space, check_mtime_granularity, check_path_length,
+    check_read_throughput, check_root_readable, check_shared_device, check_symlink_support,
+    device_id, verdict,
 };
-use dir_compare_core::logger::{self, LoggerConfig, LogLevel, OutputDestination};
+use dir_compare_core::external::{ExternalCommandStrategy, parse_external_rules, split_command};
+use dir_compare_core::filesystem::{RealFileSystem, RootPaths, detect_case_sensitivity};
+use dir_compare_core::hardlinks::HardLinkReport;
+use dir_compare_core::history::{HistoryRecord, append_history, format_history, read_history};
+use dir_compare_core::logger::{
+    self, LogFormat, LogLevel, LoggerConfig, OutputDestination, TimestampFormat,
+};
+use dir_compare_core::manifest::{verify_manifest, write_manifest};
 use dir_compare_core::output::{
-    FlatHtmlFormatter, FlatMarkdownFormatter, FlatTextFormatter, Formatter, HtmlFormatter,
-    MarkdownFormatter, TextFormatter,
+    BundleHtmlFormatter, ColorTextFormatter, CsvFormatter, FlatCsvFormatter, FlatFormatter,
+    FlatHtmlFormatter, FlatJsonFormatter, FlatMarkdownFormatter, FlatScriptFormatter,
+    FlatTextFormatter, Formatter, HtmlFormatter, JsonFormatter, Labels, MarkdownFormatter,
+    PorcelainFormatter, ScriptShell, Section, SectionConfig, TextFormatter, escape_json,
+    format_bytes, format_diff_locations, group_digits,
+};
+use dir_compare_core::report_labels::REPORT_LANGUAGES;
+use dir_compare_core::sanitize::{
+    SanitizeMode, escape_non_ascii_json, sanitize_flat_result, sanitize_result,
 };
-use std::path::PathBuf;
-use std::process;
+use dir_compare_core::strategy_defaults::{self, StrategySettings};
+use dir_compare_core::sync::{SyncAction, SyncDirection, SyncOptions, apply_sync, plan_sync};
+use sha2::{Digest, Sha256};
+use std::ffi::OsString;
+use std::fmt;
+use std::io::{IsTerminal, Write};
+use std::path::{Path, PathBuf};
+use std::process::{self, Command};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime};
+
+mod terminal;
 
 #[derive(clap::Parser)]
 #[command(name = "dir-compare")]
 #[command(author = "dir-compare contributors")]
 #[command(version = "0.1.0")]
 #[command(about = "Compare two directories and report differences", long_about = None)]
+#[command(args_override_self = true)]
+#[command(args_conflicts_with_subcommands = true, subcommand_negates_reqs = true)]
+#[command(disable_help_subcommand = true)]
 struct Args {
+    #[command(subcommand)]
+    command: Option<Subcommand>,
+
     /// First directory to compare
-    #[arg(value_name = "DIR1")]
-    dir_a: PathBuf,
+    #[arg(value_name = "DIR1", required = true)]
+    dir_a: Option<PathBuf>,
 
     /// Second directory to compare
-    #[arg(value_name = "DIR2")]
-    dir_b: PathBuf,
+    #[arg(
+        value_name = "DIR2",
+        required_unless_present_any = ["write_manifest", "verify_manifest"]
+    )]
+    dir_b: Option<PathBuf>,
 
-    #[arg(short, long, default_value = "sampled")]
-    method: String,
+    /// Comparison method [default: sampled]
+    #[arg(short, long, value_enum, ignore_case = true)]
+    method: Option<MethodArg>,
 
+    /// Match names regardless of case [default: when both directories are on case-insensitive filesystems]
     #[arg(short, long)]
     case_insensitive: bool,
 
-    #[arg(short, long, default_value = "text")]
-    format: String,
+    /// Match names case-sensitively even on case-insensitive filesystems
+    #[arg(long, conflicts_with = "case_insensitive")]
+    case_sensitive: bool,
+
+    #[arg(short, long, value_enum, ignore_case = true, default_value_t = FormatArg::Text)]
+    format: FormatArg,
+
+    /// Language of the headings in text, Markdown and HTML reports: en, de
+    /// or ja. Defaults to DIR_COMPARE_LANG, then English
+    #[arg(long, value_name = "LANG")]
+    lang: Option<String>,
+
+    /// Rewrite report paths for systems that reject unusual characters: control (show control characters as symbols) or ascii (also approximate or escape non-ASCII); JSON is escaped instead
+    #[arg(long, value_name = "MODE")]
+    sanitize_paths: Option<SanitizeMode>,
 
     #[arg(short, long)]
     output: Option<PathBuf>,
 
+    /// Print nothing on stdout and exit with 1 if the directories differ and 2 on errors, as with --exit-code
+    #[arg(short, long, conflicts_with = "output")]
+    quiet: bool,
+
+    /// Color text reports on stdout: auto (when stdout is a terminal and NO_COLOR is not set), always or never; reports written with --output are never colored [default: auto]
+    #[arg(long, value_name = "WHEN")]
+    color: Option<terminal::ColorChoice>,
+
+    /// Also write <OUTPUT>.sha256 with the report's digest in sha256sum format
+    #[arg(long, requires = "output")]
+    report_digest: bool,
+
+    /// Command run on the written report, e.g. "gpg --detach-sign {file}"; {file} is replaced by its path
+    #[arg(long, value_name = "TEMPLATE", requires = "output")]
+    sign_cmd: Option<SignCommand>,
+
     /// Verify matches with full hash when using sampled-hash strategy
     #[arg(long)]
     verify: bool,
 
+    /// Do not log advice on the chosen options, such as when sampled hashing suits the data poorly
+    #[arg(long)]
+    no_advice: bool,
+
+    /// Log how many file pairs will be compared by content and how many bytes read, once the directories are listed
+    #[arg(long)]
+    estimate: bool,
+
+    /// Print the estimate of --estimate and stop before reading any file contents
+    #[arg(long)]
+    estimate_only: bool,
+
+    /// Share of bytes in large raw-format files above which --method sampled advises reading whole files [default: 50]
+    #[arg(
+        long,
+        value_name = "PERCENT",
+        conflicts_with = "no_advice",
+        value_parser = clap::value_parser!(u8).range(0..=100)
+    )]
+    advice_raw_share: Option<u8>,
+
+    /// Largest modification time difference treated as equal by --method mtime [default: 1]
+    #[arg(long, value_name = "SECONDS")]
+    mtime_tolerance: Option<u64>,
+
+    /// TOML file of rules running a command such as "pdf-compare {a} {b}" on the files matching a glob; exit status 0 means equal, and other files are compared by --method
+    #[arg(long, value_name = "FILE")]
+    external_rules: Option<PathBuf>,
+
+    /// Measure and hash files by reading them instead of trusting the sizes filesystems report
+    #[arg(long)]
+    distrust_sizes: bool,
+
+    /// Do not ask the OS to read ahead files that are hashed whole
+    #[arg(long)]
+    no_io_hints: bool,
+
+    /// Hash files in their order on disk (Linux, built with the fiemap feature); output order is unchanged
+    #[arg(long)]
+    order_by_extent: bool,
+
+    /// When file sizes and times are read: eager (while listing) or lazy (only for pairs the method compares by them) [default: eager]
+    #[arg(long, value_name = "MODE")]
+    stat_mode: Option<StatMode>,
+
     #[arg(long)]
     ignore: Option<PathBuf>,
 
+    /// Skip entries matched by .gitignore files in the compared trees, including ! re-includes
+    #[arg(long)]
+    respect_gitignore: bool,
+
+    /// Only list entries up to N levels below the directories; 1 compares their immediate children, and directories at the cutoff are listed without their contents
+    #[arg(
+        long,
+        value_name = "N",
+        value_parser = clap::builder::RangedU64ValueParser::<usize>::new().range(1..)
+    )]
+    max_depth: Option<usize>,
+
+    /// Skip hidden files and directories: names starting with '.', and on Windows entries with the hidden attribute
+    #[arg(long)]
+    ignore_hidden: bool,
+
+    /// Only compare files matching this gitignore-style glob; repeatable
+    #[arg(long, value_name = "GLOB")]
+    include: Vec<String>,
+
+    /// Skip entries matching this gitignore-style glob; repeatable
+    #[arg(long, value_name = "GLOB")]
+    exclude: Vec<String>,
+
+    /// Exclude .git, .hg and .svn wherever they appear and mark nested repositories
+    #[arg(long)]
+    vcs_aware: bool,
+
     /// Enable flat mode comparison (content-based matching across different structures)
     #[arg(long)]
     flat: bool,
@@ -52,196 +205,3001 @@ struct Args {
     #[arg(long)]
     full_hash: bool,
 
-    /// Log level (debug, info, warn, error)
-    #[arg(long, default_value = "info")]
-    log_level: String,
+    /// Hash algorithm grouping files in flat mode and of checksum manifests (sha256, blake3, fxhash) [default: sha256]
+    #[arg(long, value_name = "ALGO")]
+    hash_algo: Option<HashAlgorithm>,
+
+    /// Write the hash of every file below DIR1 to FILE in sha256sum format ("-" for stdout) instead of comparing
+    #[arg(
+        long,
+        value_name = "FILE",
+        conflicts_with_all = ["verify_manifest", "dir_b"]
+    )]
+    write_manifest: Option<PathBuf>,
+
+    /// Check the files below DIR1 against the manifest FILE ("-" for stdin) instead of comparing; exits with 1 if any file is modified, missing or extra
+    #[arg(long, value_name = "FILE", conflicts_with = "dir_b")]
+    verify_manifest: Option<PathBuf>,
+
+    /// Split every file into content-defined chunks and report how much data A and B share (flat mode; reads all files whole)
+    #[arg(long)]
+    chunk_dedupe: bool,
+
+    /// Average chunk size in bytes used by --chunk-dedupe [default: 65536]
+    #[arg(
+        long,
+        value_name = "BYTES",
+        value_parser = clap::builder::RangedU64ValueParser::<usize>::new()
+            .range(MIN_AVG_CHUNK_SIZE as u64..=MAX_AVG_CHUNK_SIZE as u64)
+    )]
+    avg_chunk_size: Option<usize>,
+
+    /// Only group files with these extensions in flat mode, e.g. jpg,png
+    #[arg(long, value_name = "LIST", value_delimiter = ',')]
+    flat_ext: Option<Vec<String>>,
+
+    /// Leave files smaller than this many bytes out of flat mode
+    #[arg(long, value_name = "BYTES")]
+    flat_min_size: Option<u64>,
+
+    /// Shell that --format script writes its move commands for (sh, powershell) [default: sh]
+    #[arg(long, value_name = "SHELL")]
+    script_shell: Option<ScriptShell>,
+
+    /// Log level
+    #[arg(long, value_enum, ignore_case = true, default_value_t = LogLevelArg::Info)]
+    log_level: LogLevelArg,
+
+    /// Log output destination
+    #[arg(long, value_enum, ignore_case = true, default_value_t = LogDestArg::Stderr)]
+    log_dest: LogDestArg,
+
+    /// Write log records as text or JSON lines (text, json) [default: text]
+    #[arg(long, value_name = "FORMAT")]
+    log_format: Option<LogFormat>,
+
+    /// Timestamp of log lines: rfc3339 (UTC), unix (seconds since the epoch) or none [default: rfc3339]
+    #[arg(long, value_name = "FORMAT")]
+    log_timestamp: Option<TimestampFormat>,
+
+    /// Log at most N warnings of each kind, such as unreadable files, then one line counting the rest; 0 logs all
+    #[arg(long, value_name = "N", default_value_t = 20)]
+    max_warnings: usize,
+
+    /// Report where same-path files differ, block by block (hierarchy mode, text format)
+    #[arg(long)]
+    locate_diffs: bool,
+
+    /// Block size in bytes used by --locate-diffs
+    #[arg(
+        long,
+        value_name = "BYTES",
+        default_value_t = DEFAULT_BLOCK_SIZE,
+        value_parser = clap::builder::RangedU64ValueParser::<usize>::new().range(1..)
+    )]
+    diff_block_size: usize,
+
+    /// Maximum number of differing files analyzed by --locate-diffs
+    #[arg(long, value_name = "N", default_value_t = 20)]
+    locate_limit: usize,
+
+    /// Collapse hard links within each side and report raw and unique byte totals (hierarchy mode, text format)
+    #[arg(long)]
+    hard_links: bool,
+
+    /// Show extra detail, such as the paths collapsed by --hard-links
+    #[arg(short, long)]
+    verbose: bool,
+
+    /// Show a progress line on stderr while comparing (hierarchy mode and --chunk-dedupe)
+    #[arg(long)]
+    progress: bool,
+
+    /// Do not resolve symlinks in DIR1/DIR2; use their normalized absolute paths
+    #[arg(long)]
+    no_canonicalize: bool,
+
+    /// Warn about meaningless flag combinations instead of rejecting them
+    #[arg(long)]
+    lenient_flags: bool,
+
+    /// Number of threads comparing same-path files (hierarchy mode) or chunking files (--chunk-dedupe) [default: all cores]
+    #[arg(
+        long,
+        value_name = "N",
+        value_parser = clap::builder::RangedU64ValueParser::<usize>::new().range(1..)
+    )]
+    threads: Option<usize>,
+
+    /// Report sections to show, in order: a-only, b-only, modified (or different), both [default: all]
+    #[arg(long, value_name = "LIST", value_delimiter = ',', value_parser = parse_section)]
+    sections: Option<Vec<Section>>,
+
+    /// Print only the summary: the number of entries in each category and the totals, without any path (hierarchy mode)
+    #[arg(long)]
+    summary: bool,
+
+    /// Entries kept per category: full, counts (totals only) or sampled:N (first N by path) [default: full]
+    #[arg(long, value_name = "DETAIL")]
+    result_detail: Option<ResultDetail>,
+
+    /// List a directory whose whole subtree matched as one line with its file count, instead of every entry below it
+    #[arg(long)]
+    rollup: bool,
+
+    /// List a file only in A and a file only in B with the same content as renamed, instead of as A-only and B-only
+    #[arg(long)]
+    detect_renames: bool,
+
+    /// Exit with 1 if the directories differ and 2 on errors, like diff
+    #[arg(long)]
+    exit_code: bool,
+
+    /// Print one stable `<X> <path>` line per difference for scripts; implies --exit-code
+    #[arg(long)]
+    porcelain: bool,
 
-    /// Log output destination (stdout, stderr)
-    #[arg(long, default_value = "stderr")]
-    log_dest: String,
+    /// With --porcelain, end records with NUL and do not quote paths
+    #[arg(short = 'z', requires = "porcelain")]
+    nul_terminated: bool,
+
+    /// After reporting, make the directories match: a-to-b (copy, overwrite and delete in B), b-to-a (the same in A) or two-way (copy both ways, newer file wins, nothing deleted)
+    #[arg(long, value_name = "DIRECTION")]
+    sync: Option<SyncDirection>,
+
+    /// With --sync, log the actions without changing any files
+    #[arg(long, requires = "sync")]
+    dry_run: bool,
+
+    /// Append a summary row for this run to a CSV history file
+    #[arg(long, value_name = "PATH")]
+    append_history: Option<PathBuf>,
+
+    /// Read options from a TOML file keyed by long option names; options given here win
+    #[arg(long, value_name = "PATH")]
+    config: Option<PathBuf>,
+
+    /// Print a completion script of every option and subcommand for SHELL
+    #[arg(long, value_name = "SHELL", hide = true, exclusive = true)]
+    generate_completions: Option<Shell>,
 }
 
-/// Initialize the logger from environment variables or CLI arguments.
-fn init_logger(args: &Args) {
-    // Parse log level from environment or CLI
-    let level_from_env = std::env::var("DIR_COMPARE_LOG_LEVEL")
-        .ok()
-        .and_then(|s| LogLevel::from_str(&s));
-
-    let level = match level_from_env {
-        Some(lvl) => lvl,
-        None => match LogLevel::from_str(&args.log_level) {
-            Some(lvl) => lvl,
-            None => {
-                eprintln!(
-                    "Warning: Invalid log level '{}'. Using default 'info'. Valid values: debug, info, warn, error",
-                    args.log_level
-                );
-                LogLevel::default()
-            }
-        }
-    };
+/// Tools run instead of a comparison. A directory named like one of them is
+/// compared by passing it as `./NAME`.
+#[derive(clap::Subcommand)]
+enum Subcommand {
+    /// Show recent runs recorded with --append-history
+    History(HistoryArgs),
+    /// Check that two directories can be compared well before a long run
+    Doctor(DoctorArgs),
+    /// Print the JSON Schema of --format json reports
+    Schema(SchemaArgs),
+}
 
-    // Parse output destination from environment or CLI
-    let dest_from_env = std::env::var("DIR_COMPARE_LOG_DEST")
-        .ok()
-        .and_then(|s| match s.to_lowercase().as_str() {
-            "stdout" => Some(OutputDestination::Stdout),
-            "stderr" => Some(OutputDestination::Stderr),
-            _ => None,
-        });
+/// Arguments of `dir-compare history`, which prints a history file.
+#[derive(clap::Args)]
+struct HistoryArgs {
+    /// History file written by --append-history
+    #[arg(value_name = "PATH")]
+    path: PathBuf,
 
-    let destination = match dest_from_env {
-        Some(dest) => dest,
-        None => match args.log_dest.to_lowercase().as_str() {
-            "stdout" => OutputDestination::Stdout,
-            "stderr" => OutputDestination::Stderr,
-            _ => {
-                eprintln!(
-                    "Warning: Invalid log destination '{}'. Using default 'stderr'. Valid values: stdout, stderr",
-                    args.log_dest
-                );
-                OutputDestination::default()
-            }
-        }
-    };
+    /// Number of most recent runs to show
+    #[arg(short = 'n', long, value_name = "N", default_value_t = 10)]
+    last: usize,
+}
 
-    logger::init(LoggerConfig {
-        level,
-        destination,
-        format: None,
-    });
+/// Arguments of `dir-compare schema`, which prints the JSON report schema.
+#[derive(clap::Args)]
+struct SchemaArgs {
+    /// Print the schema of flat mode reports
+    #[arg(long)]
+    flat: bool,
 }
 
-fn main() {
-    let args = Args::parse();
-    init_logger(&args);
+/// Arguments of `dir-compare doctor`, which checks the environment before a long run.
+#[derive(clap::Args)]
+struct DoctorArgs {
+    /// First directory
+    #[arg(value_name = "DIR_A")]
+    dir_a: PathBuf,
 
-    if !args.dir_a.exists() {
-        logger::error(&format!(
-            "First directory does not exist: {}",
-            args.dir_a.display()
-        ));
-        process::exit(1);
+    /// Second directory
+    #[arg(value_name = "DIR_B")]
+    dir_b: PathBuf,
+
+    /// Directory reports and caches will be written to, checked for free space
+    #[arg(long, value_name = "DIR", default_value = ".")]
+    output_dir: PathBuf,
+
+    /// Number of entries of each directory to scan
+    #[arg(long, value_name = "N", default_value_t = DEFAULT_SCAN_LIMIT)]
+    scan_limit: usize,
+}
+
+impl Args {
+    /// Returns the first directory, which clap requires without a subcommand.
+    fn dir_a(&self) -> &Path {
+        self.dir_a.as_deref().expect("DIR1 is required")
     }
 
-    if !args.dir_b.exists() {
-        logger::error(&format!(
-            "Second directory does not exist: {}",
-            args.dir_b.display()
-        ));
-        process::exit(1);
+    /// Returns the second directory, which clap requires without a subcommand.
+    fn dir_b(&self) -> &Path {
+        self.dir_b.as_deref().expect("DIR2 is required")
     }
 
-    if !args.dir_a.is_dir() {
-        logger::error(&format!(
-            "First path is not a directory: {}",
-            args.dir_a.display()
-        ));
-        process::exit(1);
+    /// Whether DIR1 is listed in or checked against a checksum manifest
+    /// instead of being compared.
+    fn manifest(&self) -> bool {
+        self.write_manifest.is_some() || self.verify_manifest.is_some()
     }
 
-    if !args.dir_b.is_dir() {
-        logger::error(&format!(
-            "Second path is not a directory: {}",
-            args.dir_b.display()
-        ));
-        process::exit(1);
+    /// Returns the comparison method, falling back to the default when not given.
+    fn method(&self) -> MethodArg {
+        self.method.unwrap_or(MethodArg::Sampled)
+    }
+
+    /// Whether the exit status follows `diff`: 1 for differences, 2 for errors.
+    fn exit_codes(&self) -> bool {
+        self.exit_code || self.porcelain || self.quiet
     }
 
-    let strategy: Box<dyn ComparisonStrategy> = match args.method.to_lowercase().as_str() {
-        "filename" | "name" => Box::new(FilenameOnlyStrategy::new(args.case_insensitive)),
-        "size" => Box::new(FilenameSizeStrategy::new(args.case_insensitive)),
-        "hash" | "fxhash" | "fasthash" => Box::new(FastHashStrategy::new(args.case_insensitive)),
-        "sampled" | "sampled-hash" => {
-            Box::new(SampledHashStrategy::new(args.case_insensitive, args.verify))
+    /// Returns how the compared roots should be resolved.
+    fn root_paths(&self) -> RootPaths {
+        if self.no_canonicalize {
+            RootPaths::Normalize
+        } else {
+            RootPaths::Canonicalize
         }
-        _ => {
-            logger::error(&format!("Invalid comparison method '{}'", args.method));
-            logger::error("Available methods: filename, size, hash, sampled");
-            process::exit(1);
+    }
+
+    /// Returns the comparison of both directories with the settings of
+    /// every mode applied, and `filter`.
+    fn comparison<'a>(&self, filter: Option<EntryFilter>) -> Comparison<'a> {
+        Comparison::new(self.dir_a(), self.dir_b())
+            .ignore_file(self.ignore.clone())
+            .filter(filter)
+            .threads(self.threads)
+            .root_paths(self.root_paths())
+            .result_detail(self.result_detail.unwrap_or_default())
+            .detect_renames(self.detect_renames)
+            .order_by_extent(self.order_by_extent)
+            .stat_mode(self.stat_mode.unwrap_or_default())
+            .respect_gitignore(self.respect_gitignore)
+            .max_depth(self.max_depth)
+            .ignore_hidden(self.ignore_hidden)
+    }
+}
+
+/// A fatal error, reported in the style of the selected output format.
+struct FatalError {
+    /// Machine-readable error kind, part of the JSON error contract
+    kind: &'static str,
+    /// Path the error refers to, if any
+    path: Option<PathBuf>,
+    /// Human-readable messages; the first describes the error, the rest give hints
+    messages: Vec<String>,
+}
+
+impl FatalError {
+    fn new(kind: &'static str, message: String) -> Self {
+        Self {
+            kind,
+            path: None,
+            messages: vec![message],
         }
-    };
+    }
 
-    if args.flat {
-        // Flat mode comparison
-        let options = FlatComparisonOptions {
-            use_full_hash: args.full_hash,
-            ..Default::default()
+    fn with_path(mut self, path: &Path) -> Self {
+        self.path = Some(path.to_path_buf());
+        self
+    }
+
+    fn with_hint(mut self, hint: &str) -> Self {
+        self.messages.push(hint.to_string());
+        self
+    }
+
+    /// Classifies an error from the comparison itself.
+    fn from_comparison(error: &ComparisonError, context: &str) -> Self {
+        let kind = match error {
+            ComparisonError::IgnoreFile { .. } => "invalid_ignore_file",
+            ComparisonError::Cancelled => "cancelled",
+            _ => match error.io_kind() {
+                std::io::ErrorKind::NotFound => "not_found",
+                std::io::ErrorKind::PermissionDenied => "permission_denied",
+                _ => "io_error",
+            },
         };
+        let fatal = Self::new(kind, format!("{}: {}", context, error));
+        match error.path() {
+            Some(path) => fatal.with_path(path),
+            None => fatal,
+        }
+    }
 
-        match compare_directories_flat(&args.dir_a, &args.dir_b, &options, args.ignore.as_deref()) {
-            Ok(result) => {
-                let output = match args.format.to_lowercase().as_str() {
-                    "text" | "txt" => FlatTextFormatter.format(&result),
-                    "html" => FlatHtmlFormatter.format(&result),
-                    "markdown" | "md" => FlatMarkdownFormatter.format(&result),
-                    _ => {
-                        logger::error(&format!("Invalid format '{}'", args.format));
-                        logger::error("Available formats: text, html, markdown");
-                        process::exit(1);
-                    }
-                };
+    /// Renders the error as `{"error": {"kind": ..., "path": ..., "message": ...}}`.
+    fn to_json(&self) -> String {
+        let path = match &self.path {
+            Some(path) => format!("\"{}\"", escape_json(&path.to_string_lossy())),
+            None => "null".to_string(),
+        };
+        format!(
+            "{{\"error\": {{\"kind\": \"{}\", \"path\": {}, \"message\": \"{}\"}}}}",
+            self.kind,
+            path,
+            escape_json(&self.messages.join(". "))
+        )
+    }
+}
 
-                match args.output {
-                    Some(path) => match std::fs::write(&path, &output) {
-                        Ok(_) => logger::info(&format!("Report written to: {}", path.display())),
-                        Err(e) => {
-                            logger::error(&format!("Error writing to file: {}", e));
-                            process::exit(1);
-                        }
-                    },
-                    None => {
-                        // Output the result directly to stdout (not through logger)
-                        println!("{}", output);
-                    }
+/// The `--sign-cmd` template, split into words the way a shell would but
+/// without running one.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct SignCommand {
+    words: Vec<String>,
+}
+
+impl std::str::FromStr for SignCommand {
+    type Err = String;
+
+    fn from_str(template: &str) -> Result<Self, Self::Err> {
+        let words = split_command(template)?;
+        if words.is_empty() {
+            return Err("the signing command is empty".to_string());
+        }
+        if !words.iter().any(|word| word.contains("{file}")) {
+            return Err("the signing command must contain {file}".to_string());
+        }
+        Ok(Self { words })
+    }
+}
+
+impl SignCommand {
+    /// Builds the command for `file`, replacing `{file}` in every word.
+    fn command(&self, file: &Path) -> Command {
+        let mut words = self.words.iter().map(|word| {
+            let mut arg = OsString::new();
+            for (i, part) in word.split("{file}").enumerate() {
+                if i > 0 {
+                    arg.push(file);
                 }
+                arg.push(part);
             }
-            Err(e) => {
-                logger::error(&format!("Error during comparison: {}", e));
-                process::exit(1);
-            }
+            arg
+        });
+        let mut command = Command::new(words.next().unwrap_or_default());
+        command.args(words);
+        command
+    }
+}
+
+/// Comparison method of `--method`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum MethodArg {
+    /// Names only
+    #[value(alias = "name")]
+    Filename,
+    /// Names and sizes
+    Size,
+    /// Whole-file hash
+    #[value(alias = "fxhash", alias = "fasthash")]
+    Hash,
+    /// Whole-file BLAKE3 hash
+    Blake3,
+    /// Hash of samples of each file
+    #[value(alias = "sampled-hash")]
+    Sampled,
+    /// Sizes and modification times
+    #[value(alias = "metadata")]
+    Mtime,
+}
+
+impl MethodArg {
+    /// The strategy type comparing by this method
+    fn strategy_type(self) -> ComparisonStrategyType {
+        match self {
+            MethodArg::Filename => ComparisonStrategyType::Filename,
+            MethodArg::Size => ComparisonStrategyType::FilenameSize,
+            MethodArg::Hash => ComparisonStrategyType::FastHash,
+            MethodArg::Blake3 => ComparisonStrategyType::Blake3,
+            MethodArg::Sampled => ComparisonStrategyType::SampledHash,
+            MethodArg::Mtime => ComparisonStrategyType::Mtime,
         }
-    } else {
-        // Hierarchy mode comparison
-        match compare_directories(
-            &args.dir_a,
-            &args.dir_b,
-            strategy.as_ref(),
-            args.ignore.as_deref(),
-        ) {
-            Ok(result) => {
-                let formatter: Box<dyn Formatter> = match args.format.to_lowercase().as_str() {
-                    "text" | "txt" => Box::new(TextFormatter),
-                    "html" => Box::new(HtmlFormatter),
-                    "markdown" | "md" => Box::new(MarkdownFormatter),
-                    _ => {
-                        logger::error(&format!("Invalid format '{}'", args.format));
-                        logger::error("Available formats: text, html, markdown");
-                        process::exit(1);
-                    }
-                };
+    }
+}
 
-                let output = formatter.format(&result);
+/// Report format of `--format`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum FormatArg {
+    #[value(alias = "txt")]
+    Text,
+    Html,
+    /// Self-contained HTML page with a hierarchy and a flat view
+    HtmlBundle,
+    #[value(alias = "md")]
+    Markdown,
+    Json,
+    Csv,
+    /// Commands moving the files of B to their paths in A (flat mode)
+    Script,
+}
 
-                match args.output {
-                    Some(path) => match std::fs::write(&path, &output) {
-                        Ok(_) => logger::info(&format!("Report written to: {}", path.display())),
-                        Err(e) => {
-                            logger::error(&format!("Error writing to file: {}", e));
-                            process::exit(1);
-                        }
-                    },
-                    None => {
-                        // Output the result directly to stdout (not through logger)
-                        println!("{}", output);
+/// Level of `--log-level`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum LogLevelArg {
+    Debug,
+    Info,
+    #[value(alias = "warning")]
+    Warn,
+    Error,
+}
+
+impl From<LogLevelArg> for LogLevel {
+    fn from(level: LogLevelArg) -> Self {
+        match level {
+            LogLevelArg::Debug => LogLevel::Debug,
+            LogLevelArg::Info => LogLevel::Info,
+            LogLevelArg::Warn => LogLevel::Warn,
+            LogLevelArg::Error => LogLevel::Error,
+        }
+    }
+}
+
+/// Destination of `--log-dest`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum LogDestArg {
+    Stdout,
+    Stderr,
+}
+
+impl From<LogDestArg> for OutputDestination {
+    fn from(dest: LogDestArg) -> Self {
+        match dest {
+            LogDestArg::Stdout => OutputDestination::Stdout,
+            LogDestArg::Stderr => OutputDestination::Stderr,
+        }
+    }
+}
+
+/// Writes the name the value is given by on the command line.
+macro_rules! display_value_name {
+    ($($value:ty),*) => {
+        $(impl fmt::Display for $value {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                let value = self.to_possible_value().expect("no value is skipped");
+                f.write_str(value.get_name())
+            }
+        })*
+    };
+}
+
+display_value_name!(MethodArg, FormatArg);
+
+/// Passes writes through to `inner` while hashing the bytes it accepted.
+struct DigestWriter<'a> {
+    inner: &'a mut dyn Write,
+    digest: &'a mut Sha256,
+}
+
+impl Write for DigestWriter<'_> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let written = self.inner.write(buf)?;
+        self.digest.update(&buf[..written]);
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// Writes `path` through a temporary file next to it, so readers never see a
+/// partial report and a failed write leaves any previous file in place.
+fn write_atomically(
+    path: &Path,
+    write: impl FnOnce(&mut dyn Write) -> std::io::Result<()>,
+) -> std::io::Result<()> {
+    let file_name = path.file_name().unwrap_or_default().to_string_lossy();
+    let temp = path.with_file_name(format!(".{}.tmp", file_name));
+    let written = std::fs::File::create(&temp).and_then(|file| {
+        let mut writer = std::io::BufWriter::new(file);
+        write(&mut writer)?;
+        writer.flush()?;
+        drop(writer);
+        std::fs::rename(&temp, path)
+    });
+    if written.is_err() {
+        let _ = std::fs::remove_file(&temp);
+    }
+    written
+}
+
+/// Writes `<report>.sha256` in `sha256sum` format, naming the report relative
+/// to the sidecar so `sha256sum -c` works from its directory.
+fn write_digest_sidecar(path: &Path, digest: &[u8]) -> std::io::Result<PathBuf> {
+    let file_name = path.file_name().unwrap_or_default().to_string_lossy();
+    let sidecar = path.with_file_name(format!("{}.sha256", file_name));
+    let hex: String = digest.iter().map(|byte| format!("{:02x}", byte)).collect();
+    let line = format!("{}  {}\n", hex, file_name);
+    write_atomically(&sidecar, |w| w.write_all(line.as_bytes()))?;
+    Ok(sidecar)
+}
+
+/// Runs `--sign-cmd` on the written report, exiting if it cannot be started
+/// or exits unsuccessfully. The report and its digest are kept either way.
+fn sign_report(args: &Args, sign_cmd: &SignCommand, path: &Path) {
+    let program = &sign_cmd.words[0];
+    let message = match sign_cmd.command(path).status() {
+        Ok(status) if status.success() => {
+            logger::info(&format!("Report signed by: {}", program));
+            return;
+        }
+        Ok(status) => format!("Signing command `{}` failed with {}", program, status),
+        Err(e) => format!("Could not run signing command `{}`: {}", program, e),
+    };
+    let error = FatalError::new("sign_failed", message)
+        .with_path(path)
+        .with_hint("The report was written but is not signed");
+    fail(args, error);
+}
+
+/// Writes a report to the `--output` file, or to stdout followed by a newline
+/// when `stdout_newline` is set, exiting on write errors.
+///
+/// A file is written atomically, then gets its `--report-digest` sidecar and
+/// is passed to `--sign-cmd`.
+fn write_report(
+    args: &Args,
+    stdout_newline: bool,
+    write: impl FnOnce(&mut dyn Write) -> std::io::Result<()>,
+) {
+    match &args.output {
+        Some(path) => {
+            let mut digest = Sha256::new();
+            let written = write_atomically(path, |inner| {
+                write(&mut DigestWriter {
+                    inner,
+                    digest: &mut digest,
+                })
+            });
+            if let Err(e) = written {
+                let error =
+                    FatalError::new("write_failed", format!("Error writing to file: {}", e));
+                fail(args, error.with_path(path));
+            }
+            logger::info(&format!("Report written to: {}", path.display()));
+            if args.report_digest {
+                match write_digest_sidecar(path, &digest.finalize()) {
+                    Ok(sidecar) => {
+                        logger::info(&format!("Digest written to: {}", sidecar.display()))
+                    }
+                    Err(e) => {
+                        let error = FatalError::new(
+                            "write_failed",
+                            format!("Error writing the report digest: {}", e),
+                        );
+                        fail(args, error.with_path(path));
                     }
                 }
             }
-            Err(e) => {
-                logger::error(&format!("Error during comparison: {}", e));
-                process::exit(1);
+            if let Some(sign_cmd) = &args.sign_cmd {
+                sign_report(args, sign_cmd, path);
             }
         }
+        None if args.quiet => {}
+        None => {
+            // Output the result directly to stdout (not through logger)
+            let mut stdout = std::io::stdout().lock();
+            let written = write(&mut stdout)
+                .and_then(|_| match stdout_newline {
+                    true => stdout.write_all(b"\n"),
+                    false => Ok(()),
+                })
+                .and_then(|_| stdout.flush());
+            if let Err(e) = written {
+                let error =
+                    FatalError::new("write_failed", format!("Error writing to stdout: {}", e));
+                fail(args, error);
+            }
+        }
+    }
+}
+
+/// Runs the hierarchy and the flat comparison of `--format html-bundle` and
+/// writes both into one HTML page.
+///
+/// Both comparisons take file hashes from one [`HashCache`], so each file is
+/// read once. Returns true if either comparison found differences.
+fn run_bundle(args: &Args, case_insensitive: bool, filter: Option<EntryFilter>) -> bool {
+    let cache = Arc::new(HashCache::new(
+        args.hash_algo.unwrap_or_default(),
+        args.full_hash,
+    ));
+    let strategy = CachedHashStrategy::new(case_insensitive, Arc::clone(&cache));
+    let started_at = SystemTime::now();
+    let timer = Instant::now();
+    let comparison = args
+        .comparison(filter)
+        .strategy(&strategy)
+        .hash_cache(Arc::clone(&cache));
+    let hierarchy = comparison.run().unwrap_or_else(|e| {
+        fail(
+            args,
+            FatalError::from_comparison(&e, "Error during comparison"),
+        )
+    });
+    let flat = comparison.run_flat().unwrap_or_else(|e| {
+        fail(
+            args,
+            FatalError::from_comparison(&e, "Error during comparison"),
+        )
+    });
+    logger::debug(&format!(
+        "Hashed {} files for both views",
+        cache.files_hashed()
+    ));
+
+    log_warnings(&hierarchy.warnings);
+    log_warnings(&flat.warnings);
+    log_stats(&hierarchy.stats);
+    log_stats(&flat.stats);
+    let record = HistoryRecord::from_comparison(
+        &hierarchy,
+        args.dir_a(),
+        args.dir_b(),
+        started_at,
+        timer.elapsed(),
+    );
+    write_report(args, true, |w| {
+        BundleHtmlFormatter.format_to(&hierarchy, &flat, w)
+    });
+    record_history(args, record);
+    hierarchy.has_differences() || flat.has_differences()
+}
+
+/// Logs what the comparison traversed and read, and how long it took, at
+/// INFO level.
+fn log_stats(stats: &ComparisonStats) {
+    logger::info(&format!("Compared {}", Labels::default().stats(stats)));
+}
+
+/// Returns the sections of the report chosen with `--summary` or
+/// `--sections`, or `None` for the whole report.
+fn report_sections(args: &Args) -> Option<SectionConfig> {
+    if args.summary {
+        Some(SectionConfig::summary_only())
+    } else {
+        args.sections
+            .as_ref()
+            .map(|sections| SectionConfig::new(sections.iter().copied()))
+    }
+}
+
+/// Returns true if `--sanitize-paths` rewrites the paths of the report;
+/// JSON output is escaped as a whole instead.
+fn sanitizes_paths(args: &Args) -> bool {
+    args.sanitize_paths.is_some() && args.format != FormatArg::Json
+}
+
+/// Returns true if `--sanitize-paths ascii` replaces the non-ASCII
+/// characters of a JSON report with `\u` escapes.
+fn escapes_json(args: &Args) -> bool {
+    args.sanitize_paths == Some(SanitizeMode::Ascii) && args.format == FormatArg::Json
+}
+
+/// Logs how many paths `--sanitize-paths` rewrote, if any.
+fn log_sanitized(stats: &ComparisonStats) {
+    if stats.sanitized_paths > 0 {
+        logger::info(&format!(
+            "Sanitized {} paths for output",
+            group_digits(stats.sanitized_paths)
+        ));
+    }
+}
+
+/// Applies the `--sync` actions, or logs them with `--dry-run`, then logs
+/// each failed action and a summary. Exits with an error status if any
+/// action failed.
+fn run_sync(args: &Args, actions: &[SyncAction]) {
+    let options = SyncOptions {
+        dry_run: args.dry_run,
+        ..SyncOptions::new(args.dir_a(), args.dir_b())
+    };
+    let report = apply_sync(&RealFileSystem, actions, &options);
+    for outcome in &report.outcomes {
+        match &outcome.error {
+            Some(error) => logger::error(&format!("Could not {}: {}", outcome.action, error)),
+            None if args.dry_run => logger::info(&format!("Would {}", outcome.action)),
+            None => logger::debug(&format!("Synced: {}", outcome.action)),
+        }
+    }
+    let summary = report.summary();
+    logger::info(&format!(
+        "Sync: {}",
+        summary.lines().next().unwrap_or_default()
+    ));
+    if report.failed().next().is_some() {
+        logger::flush_summaries();
+        process::exit(if args.exit_codes() { EXIT_ERROR } else { 1 });
+    }
+}
+
+/// Logs each entry the comparison skipped or could not read at WARN level,
+/// up to `--max-warnings` of each cause, with its path and cause as fields.
+fn log_warnings(warnings: &[FileWarning]) {
+    for warning in warnings {
+        let key = warning.cause.key();
+        let path = warning.path.display().to_string();
+        logger::warn_keyed_with(
+            key,
+            &warning.to_string(),
+            &[("path", &path), ("cause", key)],
+        );
+    }
+}
+
+/// Exit status for fatal errors with `--exit-code`, where 1 means "differences found".
+const EXIT_ERROR: i32 = 2;
+
+/// Exit status with `--exit-code` when the directories differ.
+const EXIT_DIFFERENCES: i32 = 1;
+
+/// Reports a fatal error and exits with status 1, or 2 with `--exit-code` or
+/// `--porcelain`.
+///
+/// With `--format json` the error is written to stderr as a single JSON
+/// object; otherwise each message is logged as plain text.
+fn fail(args: &Args, error: FatalError) -> ! {
+    report_fatal(args, &error);
+    process::exit(if args.exit_codes() { EXIT_ERROR } else { 1 });
+}
+
+/// Writes a fatal error in the style of the selected output format, after
+/// the pending warning summaries.
+fn report_fatal(args: &Args, error: &FatalError) {
+    logger::flush_summaries();
+    if args.format == FormatArg::Json {
+        eprintln!("{}", error.to_json());
+    } else {
+        for message in &error.messages {
+            logger::error(message);
+        }
+    }
+}
+
+/// Minimum time between two redraws of the progress line.
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Renders comparison progress on a single, repeatedly overwritten stderr line.
+#[derive(Default)]
+struct ProgressLine {
+    /// When the line was last drawn; `None` until the first draw
+    last_draw: Mutex<Option<Instant>>,
+}
+
+impl ProgressLine {
+    /// Redraws the line, at most every [`PROGRESS_INTERVAL`] except for the
+    /// final matching update.
+    fn update(&self, progress: &Progress) {
+        let mut last_draw = self.last_draw.lock().unwrap();
+        let is_last = progress.total == Some(progress.current);
+        if !is_last && last_draw.is_some_and(|t| t.elapsed() < PROGRESS_INTERVAL) {
+            return;
+        }
+        let width = terminal::width().saturating_sub(1);
+        eprint!("\r{:<width$}", progress_line(progress, width));
+        *last_draw = Some(Instant::now());
+    }
+
+    /// Ends the line so that later output starts on a fresh one.
+    fn finish(&self) {
+        if self.last_draw.lock().unwrap().take().is_some() {
+            eprintln!();
+        }
+    }
+}
+
+/// Describes a progress update, such as `Comparing: 120/4000 files`.
+fn progress_text(progress: &Progress) -> String {
+    match (progress.phase, progress.total) {
+        (ProgressPhase::Matching, Some(total)) => {
+            format!("Comparing: {}/{} files", progress.current, total)
+        }
+        (ProgressPhase::Chunking, Some(total)) => {
+            format!("Chunking: {}/{} files", progress.current, total)
+        }
+        _ => match progress.side {
+            Some(side) => format!("Scanning {}: {} entries", side, progress.current),
+            None => format!("Scanning: {} entries", progress.current),
+        },
+    }
+}
+
+/// Narrowest room left for the current path before the progress line
+/// leaves it out.
+const MIN_PROGRESS_PATH: usize = 12;
+
+/// Fits the progress text and the current path into `width` characters,
+/// shortening the path in the middle.
+fn progress_line(progress: &Progress, width: usize) -> String {
+    let text = progress_text(progress);
+    let path = progress.current_path.display().to_string();
+    let room = width.saturating_sub(text.chars().count() + 2);
+    if path.is_empty() || room < MIN_PROGRESS_PATH {
+        return terminal::truncate_middle(&text, width);
+    }
+    format!("{}  {}", text, terminal::truncate_middle(&path, room))
+}
+
+/// Bytes to read above which the estimate is logged as advice without
+/// `--estimate`: 10 GiB.
+const ADVICE_ESTIMATE_BYTES: u64 = 10 << 30;
+
+/// Describes an estimate, such as `Estimate: 4,000 pairs, 3,200 compared by
+/// content, about 1.2 GiB to read`.
+fn estimate_text(estimate: &ComparisonEstimate) -> String {
+    let mut text = format!(
+        "Estimate: {} pairs, {} compared by content, about {} to read",
+        group_digits(estimate.pairs),
+        group_digits(estimate.content_pairs),
+        format_bytes(estimate.bytes)
+    );
+    if estimate.unsized_pairs > 0 {
+        text.push_str(&format!(
+            " plus {} pairs of unknown size",
+            group_digits(estimate.unsized_pairs)
+        ));
+    }
+    text
+}
+
+/// Reports the estimate of a listed comparison before any file is read:
+/// printed and exiting with `--estimate-only`, logged with `--estimate`, and
+/// otherwise logged as advice when the comparison reads a lot.
+fn announce_estimate(args: &Args, estimate: &ComparisonEstimate) {
+    let text = estimate_text(estimate);
+    if args.estimate_only {
+        println!("{}", text);
+        logger::flush_summaries();
+        process::exit(0);
+    }
+    if args.estimate {
+        logger::info(&text);
+    } else if let Some(advice) = estimate_advice(args, estimate) {
+        logger::warn(&advice);
+    }
+}
+
+/// Advice logged without `--estimate` for a comparison reading more than
+/// [`ADVICE_ESTIMATE_BYTES`], unless `--no-advice` is given.
+fn estimate_advice(args: &Args, estimate: &ComparisonEstimate) -> Option<String> {
+    (!args.no_advice && estimate.bytes > ADVICE_ESTIMATE_BYTES).then(|| {
+        format!(
+            "{}. Pass --estimate-only to stop before reading (--no-advice silences this)",
+            estimate_text(estimate)
+        )
+    })
+}
+
+/// Logs the data profile of a sampled comparison, and the advice to read
+/// whole files when most of the compared bytes are in raw formats.
+fn advise_sampling(args: &Args, profiler: &DataProfiler) {
+    let profile = profiler.profile();
+    if profile.files == 0 {
+        return;
+    }
+    logger::debug(&format!(
+        "Data profile: {} files, {} bytes; {:.0}% in compressed formats, {:.0}% raw or other",
+        profile.files,
+        profile.total_bytes(),
+        profile.compressed_percent(),
+        profile.raw_percent()
+    ));
+    let max_raw_share = args
+        .advice_raw_share
+        .map_or(DEFAULT_ADVICE_RAW_SHARE, |percent| {
+            f64::from(percent) / 100.0
+        });
+    let reads_whole_files = args.verify || args.distrust_sizes;
+    if let Some(advice) = sampling_advice(&profile, reads_whole_files, max_raw_share) {
+        logger::warn(&format!("{} (--no-advice silences this)", advice));
+    }
+}
+
+/// Parses one `--sections` entry.
+fn parse_section(s: &str) -> Result<Section, String> {
+    s.parse()
+}
+
+/// Checks the parsed arguments for flag combinations that have no effect.
+///
+/// Returns one explanatory message per problem found; an empty vector means
+/// the combination is meaningful.
+fn validate_flags(args: &Args) -> Vec<String> {
+    let mut problems = Vec::new();
+    let sampled = args.method() == MethodArg::Sampled;
+    let mtime = args.method() == MethodArg::Mtime;
+    let bundle = args.format == FormatArg::HtmlBundle;
+    let script = args.format == FormatArg::Script;
+
+    if args.flat {
+        if let Some(method) = &args.method {
+            problems.push(format!(
+                "--method {} has no effect with --flat: flat mode always groups files by content hash. Drop --method, or use --full-hash to hash whole files",
+                method
+            ));
+        }
+        for (set, flag) in [
+            (args.case_insensitive, "--case-insensitive"),
+            (args.case_sensitive, "--case-sensitive"),
+        ] {
+            if set {
+                problems.push(format!(
+                    "{} has no effect with --flat: flat mode groups files by content, not by name. Drop {}",
+                    flag, flag
+                ));
+            }
+        }
+        if args.verify {
+            problems.push(
+                "--verify has no effect with --flat. Use --full-hash to compare whole-file hashes in flat mode".to_string(),
+            );
+        }
+        if args.locate_diffs {
+            problems.push(
+                "--locate-diffs only applies to hierarchy mode: flat mode has no same-path pairs to analyze. Drop --flat or --locate-diffs".to_string(),
+            );
+        }
+        if args.hard_links {
+            problems.push(
+                "--hard-links only applies to hierarchy mode: flat mode already groups identical content. Drop --flat or --hard-links".to_string(),
+            );
+        }
+        if args.threads.is_some() && !args.chunk_dedupe {
+            problems.push(
+                "--threads only applies to hierarchy mode and --chunk-dedupe: flat mode hashes files on a single thread. Drop --flat or --threads".to_string(),
+            );
+        }
+        if args.progress && !args.chunk_dedupe {
+            problems.push(
+                "--progress only applies to hierarchy mode and --chunk-dedupe. Drop --flat or --progress".to_string(),
+            );
+        }
+        if args.chunk_dedupe && args.format == FormatArg::Csv {
+            problems.push(
+                "--chunk-dedupe is not reported in csv output, which lists one row per file. Use --format text, markdown, html or json".to_string(),
+            );
+        }
+        if args.chunk_dedupe && script {
+            problems.push(
+                "--chunk-dedupe is not reported in script output, which only moves whole files. Use --format text, markdown, html or json".to_string(),
+            );
+        }
+        if args.sections.is_some() {
+            problems.push(
+                "--sections only applies to hierarchy mode: flat mode reports duplicate groups, not sections. Drop --flat or --sections".to_string(),
+            );
+        }
+        if args.summary {
+            problems.push(
+                "--summary only applies to hierarchy mode: flat mode reports duplicate groups, not categories. Drop --flat or --summary".to_string(),
+            );
+        }
+        if args.result_detail.is_some() {
+            problems.push(
+                "--result-detail only applies to hierarchy mode: flat mode reports content groups, not categories. Drop --flat or --result-detail".to_string(),
+            );
+        }
+        if args.stat_mode.is_some() {
+            problems.push(
+                "--stat-mode only applies to hierarchy mode: flat mode reads the size of every file. Drop --flat or --stat-mode".to_string(),
+            );
+        }
+        if args.advice_raw_share.is_some() {
+            problems.push(
+                "--advice-raw-share only applies to --method sampled in hierarchy mode: flat mode hashes every file it groups. Drop --flat or --advice-raw-share".to_string(),
+            );
+        }
+        if args.external_rules.is_some() {
+            problems.push(
+                "--external-rules only applies to hierarchy mode: flat mode groups files by content hash. Drop --flat or --external-rules".to_string(),
+            );
+        }
+        for (set, flag) in [
+            (args.estimate, "--estimate"),
+            (args.estimate_only, "--estimate-only"),
+        ] {
+            if set {
+                problems.push(format!(
+                    "{} only applies to hierarchy mode: flat mode reads every file it groups. Drop --flat or {}",
+                    flag, flag
+                ));
+            }
+        }
+        if args.flat_min_size.is_some() && args.distrust_sizes {
+            problems.push(
+                "--flat-min-size compares the sizes that --distrust-sizes does not trust. Drop one of them".to_string(),
+            );
+        }
+    } else {
+        for (set, flag) in [
+            (args.flat_ext.is_some(), "--flat-ext"),
+            (args.flat_min_size.is_some(), "--flat-min-size"),
+        ] {
+            if set {
+                problems.push(format!(
+                    "{} only applies to flat mode. Add --flat, or use --include to select files in hierarchy mode",
+                    flag
+                ));
+            }
+        }
+        if args.chunk_dedupe {
+            problems.push(
+                "--chunk-dedupe only applies to flat mode, which compares file contents regardless of path. Add --flat".to_string(),
+            );
+        }
+        if script {
+            problems.push(
+                "--format script only applies to flat mode, which finds the files that moved. Add --flat".to_string(),
+            );
+        }
+        if args.full_hash && !bundle {
+            problems.push(
+                "--full-hash only applies to flat mode. Add --flat, or use --method hash for full-content comparison by path".to_string(),
+            );
+        }
+        if args.hash_algo.is_some() && !bundle && !args.manifest() {
+            problems.push(
+                "--hash-algo only applies to flat mode. Add --flat, or use --method blake3 for BLAKE3 comparison by path".to_string(),
+            );
+        }
+        if args.verify && !sampled && !bundle {
+            problems.push(format!(
+                "--verify only applies to --method sampled, not '{}'. Use --method sampled --verify, or drop --verify",
+                args.method()
+            ));
+        }
+        if args.advice_raw_share.is_some() && !sampled && !bundle {
+            problems.push(format!(
+                "--advice-raw-share only applies to --method sampled, not '{}'. Drop --advice-raw-share",
+                args.method()
+            ));
+        }
+    }
+
+    if bundle && !args.flat {
+        if let Some(method) = &args.method {
+            problems.push(format!(
+                "--method {} has no effect with --format html-bundle: both views compare content hashes. Drop --method, or use --full-hash and --hash-algo to choose the hash",
+                method
+            ));
+        }
+        if args.verify {
+            problems.push(
+                "--verify has no effect with --format html-bundle. Use --full-hash to compare whole-file hashes".to_string(),
+            );
+        }
+        if args.external_rules.is_some() {
+            problems.push(
+                "--external-rules has no effect with --format html-bundle: both views compare content hashes. Drop --external-rules".to_string(),
+            );
+        }
+        if args.estimate || args.estimate_only {
+            problems.push(
+                "--estimate and --estimate-only do not apply to --format html-bundle, which also runs a flat comparison. Drop them".to_string(),
+            );
+        }
+    }
+    if bundle && args.distrust_sizes {
+        problems.push(
+            "--distrust-sizes cannot be combined with --format html-bundle, whose views share size-prefixed hashes. Drop --distrust-sizes".to_string(),
+        );
+    }
+
+    if args.manifest() {
+        for (set, flag) in [
+            (args.flat, "--flat"),
+            (args.method.is_some(), "--method"),
+            (args.output.is_some(), "--output"),
+            (args.porcelain, "--porcelain"),
+            (args.sync.is_some(), "--sync"),
+        ] {
+            if set {
+                problems.push(format!(
+                    "{} has no effect with --write-manifest or --verify-manifest, which hash the files of DIR1 instead of comparing. Drop {}",
+                    flag, flag
+                ));
+            }
+        }
+        let format_applies = args.verify_manifest.is_some()
+            && matches!(args.format, FormatArg::Text | FormatArg::Json);
+        if args.format != FormatArg::Text && !format_applies {
+            problems.push(format!(
+                "--format {} has no effect with manifests: --verify-manifest reports as text or json, and --write-manifest writes sha256sum lines. Drop --format",
+                args.format
+            ));
+        }
+    }
+
+    if args.sanitize_paths.is_some() {
+        for (set, flag, reason) in [
+            (
+                args.porcelain,
+                "--porcelain",
+                "which already quotes unusual characters like git",
+            ),
+            (
+                bundle,
+                "--format html-bundle",
+                "which embeds escaped JSON documents",
+            ),
+            (
+                script,
+                "--format script",
+                "whose commands need the real paths",
+            ),
+        ] {
+            if set {
+                problems.push(format!(
+                    "--sanitize-paths cannot be combined with {}, {}. Drop --sanitize-paths",
+                    flag, reason
+                ));
+            }
+        }
+    }
+
+    if args.color == Some(terminal::ColorChoice::Always) {
+        let text = args.format == FormatArg::Text;
+        for (set, flag, reason) in [
+            (
+                args.output.is_some(),
+                "--output",
+                "whose reports are never colored",
+            ),
+            (!text, "--format", "as only text reports are colored"),
+            (args.flat, "--flat", "whose reports are not colored"),
+            (
+                args.porcelain,
+                "--porcelain",
+                "whose output is meant for scripts",
+            ),
+        ] {
+            if set {
+                problems.push(format!(
+                    "--color always has no effect with {}, {}. Drop --color",
+                    flag, reason
+                ));
+            }
+        }
+    }
+
+    if args.sync.is_some() {
+        for (set, flag, reason) in [
+            (
+                args.flat,
+                "--flat",
+                "which pairs files by content, not by path",
+            ),
+            (
+                bundle,
+                "--format html-bundle",
+                "which also runs a flat comparison",
+            ),
+            (
+                args.porcelain,
+                "--porcelain",
+                "whose output is fixed to one line per difference",
+            ),
+            (
+                args.estimate_only,
+                "--estimate-only",
+                "which stops before comparing",
+            ),
+            (
+                args.max_depth.is_some(),
+                "--max-depth",
+                "which lists directories at the cutoff without their contents",
+            ),
+            (
+                args.result_detail
+                    .is_some_and(|detail| detail != ResultDetail::Full),
+                "--result-detail",
+                "which drops entries the sync needs",
+            ),
+        ] {
+            if set {
+                problems.push(format!(
+                    "--sync cannot be combined with {}, {}. Drop {} or --sync",
+                    flag, reason, flag
+                ));
+            }
+        }
+    }
+
+    if args.rollup {
+        for (set, flag, reason) in [
+            (
+                args.flat,
+                "--flat",
+                "which groups files by content, not by directory",
+            ),
+            (
+                bundle,
+                "--format html-bundle",
+                "whose tree view already collapses directories",
+            ),
+            (
+                args.format == FormatArg::Csv,
+                "--format csv",
+                "whose rows list every entry",
+            ),
+            (
+                args.porcelain,
+                "--porcelain",
+                "which lists only differences",
+            ),
+            (
+                args.hard_links,
+                "--hard-links",
+                "which groups links across every entry",
+            ),
+            (
+                args.result_detail
+                    .is_some_and(|detail| detail != ResultDetail::Full),
+                "--result-detail",
+                "which drops the entries that show a directory matched",
+            ),
+        ] {
+            if set {
+                problems.push(format!(
+                    "--rollup cannot be combined with {}, {}. Drop {} or --rollup",
+                    flag, reason, flag
+                ));
+            }
+        }
+    }
+
+    if args.detect_renames {
+        for (set, flag, reason) in [
+            (
+                args.flat,
+                "--flat",
+                "which already groups files by content wherever they are",
+            ),
+            (
+                bundle,
+                "--format html-bundle",
+                "whose flat view already shows moved files",
+            ),
+            (
+                args.format == FormatArg::Csv,
+                "--format csv",
+                "whose rows have no column for the new path",
+            ),
+        ] {
+            if set {
+                problems.push(format!(
+                    "--detect-renames cannot be combined with {}, {}. Drop {} or --detect-renames",
+                    flag, reason, flag
+                ));
+            }
+        }
+    }
+
+    if args.script_shell.is_some() && !script {
+        problems.push(format!(
+            "--script-shell only applies to --format script, not '{}'. Use --format script, or drop --script-shell",
+            args.format
+        ));
+    }
+
+    if args.avg_chunk_size.is_some() && !args.chunk_dedupe {
+        problems.push(
+            "--avg-chunk-size only applies to --chunk-dedupe. Add --chunk-dedupe, or drop --avg-chunk-size".to_string(),
+        );
+    }
+
+    if args.mtime_tolerance.is_some() && !mtime {
+        problems.push(format!(
+            "--mtime-tolerance only applies to --method mtime, not '{}'. Use --method mtime, or drop --mtime-tolerance",
+            args.method()
+        ));
+    }
+
+    let text_format = args.format == FormatArg::Text;
+    if args.locate_diffs && !text_format && args.format != FormatArg::Json {
+        problems.push(format!(
+            "--locate-diffs is only reported in text and json output, not '{}'. Use --format text or json",
+            args.format
+        ));
+    }
+    if args.hard_links && !text_format {
+        problems.push(format!(
+            "--hard-links is only reported in text output, not '{}'. Use --format text",
+            args.format
+        ));
+    }
+    if args.sections.is_some() {
+        if matches!(
+            args.format,
+            FormatArg::Json | FormatArg::Csv | FormatArg::HtmlBundle
+        ) {
+            problems.push(format!(
+                "--sections only applies to text, markdown and html output, not '{}'. Drop --sections, or filter the records downstream",
+                args.format
+            ));
+        }
+        if args.hard_links {
+            problems.push(
+                "--sections cannot be combined with --hard-links, which uses its own report layout. Drop one of them".to_string(),
+            );
+        }
+    }
+    if args.summary {
+        if matches!(args.format, FormatArg::Csv | FormatArg::HtmlBundle) {
+            problems.push(format!(
+                "--summary only applies to text, markdown, html and json output, not '{}'. Drop --summary, or count the records downstream",
+                args.format
+            ));
+        }
+        for (set, flag) in [
+            (args.sections.is_some(), "--sections"),
+            (args.hard_links, "--hard-links"),
+            (args.locate_diffs, "--locate-diffs"),
+        ] {
+            if set {
+                problems.push(format!(
+                    "{} cannot be combined with --summary, which lists no entries. Drop {} or --summary",
+                    flag, flag
+                ));
+            }
+        }
+    }
+    if args.hard_links
+        && args
+            .result_detail
+            .is_some_and(|detail| detail != ResultDetail::Full)
+    {
+        problems.push(
+            "--hard-links needs every entry to group links, but --result-detail drops entries. Drop --result-detail or --hard-links".to_string(),
+        );
+    }
+
+    if args.porcelain {
+        for (set, flag) in [
+            (args.flat, "--flat"),
+            (bundle, "--format html-bundle"),
+            (args.sections.is_some(), "--sections"),
+            (args.summary, "--summary"),
+            (args.locate_diffs, "--locate-diffs"),
+            (args.hard_links, "--hard-links"),
+            (args.estimate_only, "--estimate-only"),
+        ] {
+            if set {
+                problems.push(format!(
+                    "{} cannot be combined with --porcelain, whose output is fixed to one line per difference. Drop {}",
+                    flag, flag
+                ));
+            }
+        }
+        if args
+            .result_detail
+            .is_some_and(|detail| detail != ResultDetail::Full)
+        {
+            problems.push(
+                "--porcelain lists every difference, but --result-detail drops entries. Drop --result-detail".to_string(),
+            );
+        }
+        if args.log_dest == LogDestArg::Stdout {
+            problems.push(
+                "--porcelain keeps stdout for its records, but --log-dest stdout writes log lines there. Drop --log-dest stdout".to_string(),
+            );
+        }
+    }
+
+    if args.quiet {
+        for (set, flag) in [
+            (args.porcelain, "--porcelain"),
+            (args.summary, "--summary"),
+            (args.sections.is_some(), "--sections"),
+            (args.estimate_only, "--estimate-only"),
+        ] {
+            if set {
+                problems.push(format!(
+                    "{} has no effect with --quiet, which prints nothing on stdout. Drop {} or --quiet",
+                    flag, flag
+                ));
+            }
+        }
+        if args.log_dest == LogDestArg::Stdout {
+            problems.push(
+                "--quiet keeps stdout empty, but --log-dest stdout writes log lines there. Drop --log-dest stdout".to_string(),
+            );
+        }
+    }
+
+    problems
+}
+
+/// Initialize the logger from environment variables or CLI arguments.
+fn init_logger(args: &Args) {
+    logger::init(logger_config(args, |name| std::env::var(name).ok()));
+}
+
+/// Builds the logger configuration from CLI arguments, overridden by the
+/// variables `env` returns.
+fn logger_config(args: &Args, env: impl Fn(&str) -> Option<String>) -> LoggerConfig {
+    // Parse log level from environment or CLI
+    let level_from_env = env("DIR_COMPARE_LOG_LEVEL").and_then(|s| LogLevel::from_str(&s));
+
+    let level = level_from_env.unwrap_or(args.log_level.into());
+
+    // Parse output destination from environment or CLI
+    let dest_from_env = env("DIR_COMPARE_LOG_DEST").and_then(|s| match s.to_lowercase().as_str() {
+        "stdout" => Some(OutputDestination::Stdout),
+        "stderr" => Some(OutputDestination::Stderr),
+        _ => None,
+    });
+
+    let destination = dest_from_env.unwrap_or(args.log_dest.into());
+
+    let timestamp_from_env =
+        env("DIR_COMPARE_LOG_TIMESTAMP").and_then(|s| s.parse::<TimestampFormat>().ok());
+    let timestamp_format = timestamp_from_env
+        .or(args.log_timestamp)
+        .unwrap_or_default();
+
+    let log_format = env("DIR_COMPARE_LOG_FORMAT")
+        .and_then(|s| s.parse::<LogFormat>().ok())
+        .or(args.log_format)
+        .unwrap_or_default();
+
+    let is_tty = match destination {
+        OutputDestination::Stdout => std::io::stdout().is_terminal(),
+        OutputDestination::Stderr => std::io::stderr().is_terminal(),
+        OutputDestination::Capture => false,
+    };
+    let color = terminal::color_enabled(
+        env("NO_COLOR").as_deref(),
+        env("CLICOLOR_FORCE").as_deref(),
+        is_tty,
+    );
+
+    LoggerConfig {
+        level,
+        destination,
+        format: None,
+        log_format,
+        timestamp_format,
+        dedup_window: (args.max_warnings > 0).then_some(args.max_warnings),
+        color,
+    }
+}
+
+/// Picks the report labels from `--lang`, else from the `DIR_COMPARE_LANG`
+/// variable `env` returns. An unknown language is warned about and English
+/// is used.
+fn report_labels(args: &Args, env: impl Fn(&str) -> Option<String>) -> Labels {
+    let Some(lang) = args.lang.clone().or_else(|| env("DIR_COMPARE_LANG")) else {
+        return Labels::default();
+    };
+    let code = lang.trim().to_lowercase();
+    Labels::for_language(&code).unwrap_or_else(|| {
+        logger::warn(&format!(
+            "Unknown report language '{}', using English. Available languages: {}",
+            lang,
+            REPORT_LANGUAGES.join(", ")
+        ));
+        Labels::default()
+    })
+}
+
+/// Prints the last runs of a history file with deltas to the run before.
+fn run_history(args: HistoryArgs) {
+    logger::init(LoggerConfig::default());
+    match read_history(&args.path) {
+        Ok(records) => print!("{}", format_history(&records, args.last)),
+        Err(e) => {
+            logger::error(&format!(
+                "Error reading history file {}: {}",
+                args.path.display(),
+                e
+            ));
+            process::exit(1);
+        }
+    }
+}
+
+/// Inserts the options of the `--config` file, if one is given, before those
+/// of the command line, so that the command line wins.
+fn with_config_options(mut argv: Vec<OsString>) -> Vec<OsString> {
+    let Some(path) = config_path(&argv) else {
+        return argv;
+    };
+    let entries = std::fs::read_to_string(&path)
+        .map_err(|e| e.to_string())
+        .and_then(|text| parse_config(&text).map_err(|e| e.to_string()));
+    match entries {
+        Ok(entries) => {
+            let options = config_to_args(&entries).into_iter().map(OsString::from);
+            argv.splice(1..1, options);
+            argv
+        }
+        Err(e) => {
+            eprintln!("Error reading config file {}: {}", path.display(), e);
+            process::exit(1);
+        }
+    }
+}
+
+/// The value of `--config` in `argv`, before any `--`
+fn config_path(argv: &[OsString]) -> Option<PathBuf> {
+    let mut args = argv.iter().skip(1).take_while(|arg| *arg != "--");
+    while let Some(arg) = args.next() {
+        if arg == "--config" {
+            return args.next().map(PathBuf::from);
+        }
+        if let Some(path) = arg.to_str().and_then(|arg| arg.strip_prefix("--config=")) {
+            return Some(PathBuf::from(path));
+        }
+    }
+    None
+}
+
+/// Appends this run's summary to the `--append-history` file, if one was given.
+fn record_history(args: &Args, record: HistoryRecord) {
+    if let Some(path) = &args.append_history
+        && let Err(e) = append_history(path, &record)
+    {
+        let error = FatalError::new("write_failed", format!("Error writing history file: {}", e));
+        fail(args, error.with_path(path));
+    }
+}
+
+/// Runs the environment checks on both directories and prints one line per
+/// check and the verdict, exiting with 0 when all pass, 1 on warnings and 2
+/// on failures.
+fn run_doctor(args: DoctorArgs) {
+    logger::init(LoggerConfig::default());
+    let fs = RealFileSystem;
+    let roots = [("Directory A", &args.dir_a), ("Directory B", &args.dir_b)];
+    let scans = roots.map(|(_, root)| RootScan::scan(&fs, root, args.scan_limit));
+
+    let mut results = Vec::new();
+    for ((label, root), scan) in roots.iter().zip(&scans) {
+        results.push(check_root_readable(&fs, label, root, scan));
+    }
+    if verdict(&results) != CheckStatus::Fail {
+        for ((label, root), scan) in roots.iter().zip(&scans) {
+            results.push(check_read_throughput(&fs, label, &scan.sample_files()));
+            results.push(check_symlink_support(&fs, label, root));
+            results.push(check_mtime_granularity(label, &scan.mtimes));
+            results.push(check_path_length(label, scan));
+        }
+        results.push(check_case_sensitivity(
+            detect_case_sensitivity(&fs, &args.dir_a),
+            detect_case_sensitivity(&fs, &args.dir_b),
+        ));
+        results.push(check_shared_device(
+            device_id(&fs, &args.dir_a),
+            device_id(&fs, &args.dir_b),
+        ));
+    }
+    results.push(check_free_space(
+        &args.output_dir,
+        available_space(&args.output_dir),
+    ));
+    let latest_mtime = scans.iter().filter_map(|scan| scan.latest_mtime).max();
+    results.push(check_clock(SystemTime::now(), latest_mtime));
+
+    let color = terminal::use_color(&std::io::stdout());
+    let status = |status: CheckStatus| {
+        let style = match status {
+            CheckStatus::Pass => "32",
+            CheckStatus::Warn => "33",
+            CheckStatus::Fail => "1;31",
+        };
+        terminal::paint(&status.to_string(), style, color)
+    };
+    for result in &results {
+        println!(
+            "{}  {}: {}",
+            status(result.status),
+            result.name,
+            result.explanation
+        );
+    }
+    let verdict = verdict(&results);
+    println!();
+    println!("Verdict: {}", status(verdict));
+    process::exit(match verdict {
+        CheckStatus::Pass => 0,
+        CheckStatus::Warn => EXIT_DIFFERENCES,
+        CheckStatus::Fail => EXIT_ERROR,
+    });
+}
+
+/// Prints the JSON Schema of hierarchy or flat mode reports.
+#[cfg(feature = "schema")]
+fn run_schema(args: SchemaArgs) {
+    use dir_compare_core::schema::{flat_json_report_schema, json_report_schema};
+
+    if args.flat {
+        println!("{}", flat_json_report_schema());
+    } else {
+        println!("{}", json_report_schema());
+    }
+}
+
+/// Reports that this build does not include the schema.
+#[cfg(not(feature = "schema"))]
+fn run_schema(_args: SchemaArgs) {
+    logger::init(LoggerConfig::default());
+    logger::error("This build of dir-compare does not include the JSON schema");
+    logger::error("Rebuild with `--features schema` to enable `dir-compare schema`");
+    process::exit(1);
+}
+
+/// Returns where `file` lies below `dir`, if it does.
+fn path_within(dir: &Path, file: &Path) -> Option<PathBuf> {
+    let parent = match file.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+    let file = parent.canonicalize().ok()?.join(file.file_name()?);
+    let dir = dir.canonicalize().ok()?;
+    file.strip_prefix(dir).ok().map(Path::to_path_buf)
+}
+
+/// Writes or verifies a checksum manifest, exiting with 0 when the files
+/// match it, 1 on differences and 2 on errors.
+fn run_manifest(args: &Args) {
+    let dir = args.dir_a();
+    let hash_algo = args.hash_algo.unwrap_or_default();
+    if !dir.is_dir() {
+        let message = format!("Not a directory: {}", dir.display());
+        fail_manifest(
+            args,
+            FatalError::new("not_a_directory", message).with_path(dir),
+        );
+    }
+
+    if let Some(path) = &args.write_manifest {
+        let mut files = 0;
+        let written = if path.as_os_str() == "-" {
+            write_manifest(dir, hash_algo, &mut std::io::stdout().lock()).map(|count| files = count)
+        } else {
+            if path_within(dir, path).is_some() {
+                let message = format!("The manifest {} would list itself", path.display());
+                let error = FatalError::new("invalid_flags", message)
+                    .with_path(path)
+                    .with_hint(&format!("Write it outside of {}", dir.display()));
+                fail_manifest(args, error);
+            }
+            write_atomically(path, |writer| {
+                files = write_manifest(dir, hash_algo, writer)?;
+                Ok(())
+            })
+        };
+        if let Err(e) = written {
+            let message = format!("Error writing manifest {}: {}", path.display(), e);
+            fail_manifest(
+                args,
+                FatalError::new("write_failed", message).with_path(path),
+            );
+        }
+        if path.as_os_str() != "-" {
+            logger::info(&format!(
+                "Manifest of {} files written to: {}",
+                files,
+                path.display()
+            ));
+        }
+        return;
+    }
+
+    let Some(path) = &args.verify_manifest else {
+        return;
+    };
+    let verified = if path.as_os_str() == "-" {
+        verify_manifest(dir, hash_algo, std::io::stdin().lock())
+    } else {
+        std::fs::File::open(path)
+            .and_then(|file| verify_manifest(dir, hash_algo, std::io::BufReader::new(file)))
+    };
+    let mut result = verified.unwrap_or_else(|e| {
+        let kind = match e.kind() {
+            std::io::ErrorKind::InvalidData => "invalid_manifest",
+            std::io::ErrorKind::NotFound => "not_found",
+            std::io::ErrorKind::PermissionDenied => "permission_denied",
+            _ => "io_error",
+        };
+        let message = format!("Error verifying manifest {}: {}", path.display(), e);
+        fail_manifest(args, FatalError::new(kind, message).with_path(path))
+    });
+    // A manifest kept in the directory it describes does not list itself
+    if let Some(own) = path_within(dir, path) {
+        result.extra.retain(|extra| *extra != own);
+    }
+    for warning in &result.warnings {
+        logger::warn(&format!(
+            "Could not read {}: {}",
+            warning.path.display(),
+            warning.message
+        ));
+    }
+    if args.format == FormatArg::Json {
+        print!("{}", result.to_json());
+    } else {
+        print!("{}", result.to_text());
+    }
+    process::exit(if result.has_differences() {
+        EXIT_DIFFERENCES
+    } else {
+        0
+    });
+}
+
+/// Reports a fatal error of `--write-manifest` or `--verify-manifest` and
+/// exits with status 2, since status 1 means the directory differs from the
+/// manifest.
+fn fail_manifest(args: &Args, error: FatalError) -> ! {
+    report_fatal(args, &error);
+    process::exit(EXIT_ERROR);
+}
+
+/// Prints the completion script of every option and subcommand for the
+/// shell of `--generate-completions`.
+///
+/// A pipe closed early, as by `| head`, ends the script quietly; other
+/// write errors are reported as for a report written to stdout.
+fn run_completions(args: &Args, shell: Shell) {
+    let mut script = Vec::new();
+    clap_complete::generate(shell, &mut Args::command(), "dir-compare", &mut script);
+    let mut stdout = std::io::stdout().lock();
+    match stdout.write_all(&script).and_then(|_| stdout.flush()) {
+        Err(e) if e.kind() != std::io::ErrorKind::BrokenPipe => {
+            logger::init(LoggerConfig::default());
+            let error = FatalError::new("write_failed", format!("Error writing to stdout: {}", e));
+            fail(args, error);
+        }
+        _ => {}
+    }
+}
+
+fn main() {
+    let mut args = Args::parse_from(with_config_options(std::env::args_os().collect()));
+    if let Some(shell) = args.generate_completions {
+        run_completions(&args, shell);
+        return;
+    }
+    if let Some(command) = args.command.take() {
+        match command {
+            Subcommand::History(history) => run_history(history),
+            Subcommand::Doctor(doctor) => run_doctor(doctor),
+            Subcommand::Schema(schema) => run_schema(schema),
+        }
+        return;
+    }
+    init_logger(&args);
+
+    let flag_problems = validate_flags(&args);
+    if !flag_problems.is_empty() {
+        if args.lenient_flags {
+            for problem in &flag_problems {
+                logger::warn(problem);
+            }
+        } else {
+            let error = FatalError {
+                kind: "invalid_flags",
+                path: None,
+                messages: flag_problems,
+            };
+            fail(
+                &args,
+                error.with_hint("Pass --lenient-flags to continue anyway"),
+            );
+        }
+    }
+
+    if args.manifest() {
+        run_manifest(&args);
+        return;
+    }
+
+    if !args.dir_a().exists() {
+        let message = format!("First directory does not exist: {}", args.dir_a().display());
+        fail(
+            &args,
+            FatalError::new("directory_not_found", message).with_path(args.dir_a()),
+        );
+    }
+
+    if !args.dir_b().exists() {
+        let message = format!(
+            "Second directory does not exist: {}",
+            args.dir_b().display()
+        );
+        fail(
+            &args,
+            FatalError::new("directory_not_found", message).with_path(args.dir_b()),
+        );
+    }
+
+    if !args.dir_a().is_dir() {
+        let message = format!("First path is not a directory: {}", args.dir_a().display());
+        fail(
+            &args,
+            FatalError::new("not_a_directory", message).with_path(args.dir_a()),
+        );
+    }
+
+    if !args.dir_b().is_dir() {
+        let message = format!("Second path is not a directory: {}", args.dir_b().display());
+        fail(
+            &args,
+            FatalError::new("not_a_directory", message).with_path(args.dir_b()),
+        );
+    }
+
+    if let Some(ignore) = &args.ignore
+        && !ignore.is_file()
+    {
+        let message = format!("Ignore file does not exist: {}", ignore.display());
+        fail(
+            &args,
+            FatalError::new("ignore_file_not_found", message)
+                .with_path(ignore)
+                .with_hint("Relative --ignore paths are resolved against the current directory"),
+        );
+    }
+
+    let filter = if args.include.is_empty() && args.exclude.is_empty() && !args.vcs_aware {
+        None
+    } else {
+        match EntryFilter::new(&args.include, &args.exclude) {
+            Ok(filter) => Some(filter.with_vcs_aware(args.vcs_aware)),
+            Err(e) => fail(
+                &args,
+                FatalError::new("invalid_glob", e.to_string()).with_hint(
+                    "--include and --exclude take gitignore-style patterns such as '*.log' or '/build/'",
+                ),
+            ),
+        }
+    };
+
+    // Explicit flags win; otherwise follow the filesystems of both roots
+    let explicit_case = if args.case_insensitive {
+        Some(true)
+    } else if args.case_sensitive || args.flat {
+        Some(false)
+    } else {
+        None
+    };
+    let case_insensitive =
+        strategy_defaults::case_insensitive(explicit_case, args.dir_a(), args.dir_b());
+
+    let external_rules = args.external_rules.as_deref().map(|path| {
+        let rules = std::fs::read_to_string(path)
+            .map_err(|e| e.to_string())
+            .and_then(|text| parse_external_rules(&text).map_err(|e| e.to_string()));
+        rules.unwrap_or_else(|e| {
+            let message = format!("Error reading external rules {}: {}", path.display(), e);
+            fail(
+                &args,
+                FatalError::new("invalid_external_rules", message)
+                    .with_path(path)
+                    .with_hint(
+                        "Each [[rule]] needs a pattern and a command containing {a} and {b}",
+                    ),
+            )
+        })
+    });
+
+    // Classifies the files sampled hashing reads, for sampling_advice
+    let profiler = (!args.no_advice).then(|| Arc::new(DataProfiler::new(true)));
+    let mut settings = StrategySettings::new(args.method().strategy_type(), case_insensitive);
+    settings.verify |= args.verify;
+    if let Some(secs) = args.mtime_tolerance {
+        settings.mtime_tolerance = Duration::from_secs(secs);
+    }
+    settings.distrust_sizes = args.distrust_sizes;
+    settings.io_hints = !args.no_io_hints;
+    settings.data_profiler = profiler.clone();
+    let strategy: Box<dyn ComparisonStrategy> = settings.build();
+    let strategy: Box<dyn ComparisonStrategy> = match external_rules {
+        Some(rules) => Box::new(ExternalCommandStrategy::new(strategy, rules)),
+        None => strategy,
+    };
+
+    let has_differences = if args.format == FormatArg::HtmlBundle {
+        run_bundle(&args, case_insensitive, filter)
+    } else if args.flat {
+        // Flat mode comparison
+        let started_at = SystemTime::now();
+        let timer = Instant::now();
+        let progress_line = ProgressLine::default();
+        let on_progress = |progress: &Progress| {
+            if args.progress {
+                progress_line.update(progress);
+            }
+        };
+        let compared = args
+            .comparison(filter)
+            .full_hash(args.full_hash)
+            .hash_algorithm(args.hash_algo.unwrap_or_default())
+            .distrust_sizes(args.distrust_sizes)
+            .io_hints(!args.no_io_hints)
+            .chunk_dedupe(args.chunk_dedupe.then(|| ChunkDedupeOptions {
+                avg_chunk_size: args.avg_chunk_size.unwrap_or(DEFAULT_AVG_CHUNK_SIZE),
+                threads: args.threads,
+                ..Default::default()
+            }))
+            .extensions(args.flat_ext.clone())
+            .min_file_size(args.flat_min_size.unwrap_or(0))
+            .progress(&on_progress)
+            .run_flat();
+        progress_line.finish();
+        match compared {
+            Ok(result) => {
+                log_warnings(&result.warnings);
+                log_stats(&result.stats);
+                let record = HistoryRecord::from_flat(
+                    &result,
+                    args.dir_a(),
+                    args.dir_b(),
+                    started_at,
+                    timer.elapsed(),
+                );
+                let formatter: Box<dyn FlatFormatter> = match args.format {
+                    FormatArg::Text => Box::new(FlatTextFormatter),
+                    FormatArg::Html => Box::new(FlatHtmlFormatter),
+                    FormatArg::Markdown => Box::new(FlatMarkdownFormatter),
+                    FormatArg::Json => Box::new(FlatJsonFormatter),
+                    FormatArg::Csv => Box::new(FlatCsvFormatter),
+                    FormatArg::Script => Box::new(FlatScriptFormatter {
+                        shell: args.script_shell.unwrap_or_default(),
+                    }),
+                    // Runs both comparisons in run_bundle instead
+                    FormatArg::HtmlBundle => unreachable!("html-bundle is handled above"),
+                };
+
+                let result = match args.sanitize_paths {
+                    Some(mode) if sanitizes_paths(&args) => sanitize_flat_result(result, mode),
+                    _ => result,
+                };
+                log_sanitized(&result.stats);
+                let labels = report_labels(&args, |name| std::env::var(name).ok());
+                write_report(&args, true, |w| {
+                    if escapes_json(&args) {
+                        w.write_all(escape_non_ascii_json(&formatter.format(&result)).as_bytes())
+                    } else {
+                        formatter.format_to_with_labels(&result, &labels, w)
+                    }
+                });
+                record_history(&args, record);
+                result.has_differences()
+            }
+            Err(e) => fail(
+                &args,
+                FatalError::from_comparison(&e, "Error during comparison"),
+            ),
+        }
+    } else {
+        // Hierarchy mode comparison
+        let started_at = SystemTime::now();
+        let timer = Instant::now();
+        let progress_line = ProgressLine::default();
+        let on_progress = |progress: &Progress| {
+            if args.progress {
+                progress_line.update(progress);
+            }
+        };
+        let comparison = args
+            .comparison(filter)
+            .strategy(strategy.as_ref())
+            .progress(&on_progress);
+        let compared = comparison.plan().and_then(|plan| {
+            progress_line.finish();
+            announce_estimate(&args, &plan.estimate());
+            plan.run(&on_progress)
+        });
+        progress_line.finish();
+        match compared {
+            Ok(mut result) => {
+                log_warnings(&result.warnings);
+                log_stats(&result.stats);
+                if let Some(profiler) = &profiler {
+                    advise_sampling(&args, profiler);
+                }
+                let mut context = settings.report_context(args.dir_a(), args.dir_b());
+                if args.external_rules.is_some() {
+                    context.options.push("external rules".to_string());
+                }
+                result.context = Some(context);
+                let record = HistoryRecord::from_comparison(
+                    &result,
+                    args.dir_a(),
+                    args.dir_b(),
+                    started_at,
+                    timer.elapsed(),
+                );
+                let sync_actions = args.sync.map(|direction| plan_sync(&result, direction));
+                if args.porcelain {
+                    let formatter = PorcelainFormatter {
+                        nul_terminated: args.nul_terminated,
+                    };
+                    write_report(&args, false, |w| formatter.format_to(&result, w));
+                    record_history(&args, record);
+                    logger::flush_summaries();
+                    if result.has_differences() {
+                        process::exit(EXIT_DIFFERENCES);
+                    }
+                    return;
+                }
+                if args.rollup {
+                    result.rollup();
+                }
+                let result = match args.sanitize_paths {
+                    Some(mode) if sanitizes_paths(&args) => sanitize_result(result, mode),
+                    _ => result,
+                };
+                log_sanitized(&result.stats);
+                let color = args.output.is_none()
+                    && args
+                        .color
+                        .unwrap_or_default()
+                        .resolve(terminal::use_color(&std::io::stdout()));
+                let formatter: Box<dyn Formatter> = match args.format {
+                    FormatArg::Text if color => Box::new(ColorTextFormatter),
+                    FormatArg::Text => Box::new(TextFormatter),
+                    FormatArg::Html => Box::new(HtmlFormatter),
+                    FormatArg::Markdown => Box::new(MarkdownFormatter),
+                    FormatArg::Json => Box::new(JsonFormatter),
+                    FormatArg::Csv => Box::new(CsvFormatter),
+                    // Reached only with --lenient-flags
+                    FormatArg::Script => {
+                        let message =
+                            "--format script only applies to flat mode, which finds the files that moved"
+                                .to_string();
+                        fail(
+                            &args,
+                            FatalError::new("invalid_flags", message).with_hint("Add --flat"),
+                        );
+                    }
+                    FormatArg::HtmlBundle => unreachable!("html-bundle is handled above"),
+                };
+
+                let labels = report_labels(&args, |name| std::env::var(name).ok());
+                let text_format = args.format == FormatArg::Text;
+                let locations = args
+                    .locate_diffs
+                    .then(|| locate_differences(&result, args.diff_block_size, args.locate_limit));
+                write_report(&args, true, |w| {
+                    if let Some(locations) = &locations
+                        && args.format == FormatArg::Json
+                    {
+                        let sections = report_sections(&args).unwrap_or_default();
+                        let json =
+                            JsonFormatter.format_with_diff_locations(&result, &sections, locations);
+                        if escapes_json(&args) {
+                            w.write_all(escape_non_ascii_json(&json).as_bytes())?;
+                        } else {
+                            w.write_all(json.as_bytes())?;
+                        }
+                    } else if args.hard_links && text_format {
+                        let hard_links = HardLinkReport::collect(&result);
+                        let output = if color {
+                            ColorTextFormatter.format_with_hard_links(
+                                &result,
+                                &labels,
+                                &hard_links,
+                                args.verbose,
+                            )
+                        } else {
+                            TextFormatter.format_with_hard_links(
+                                &result,
+                                &labels,
+                                &hard_links,
+                                args.verbose,
+                            )
+                        };
+                        w.write_all(output.as_bytes())?;
+                    } else if let Some(sections) = report_sections(&args) {
+                        let output = match args.format {
+                            FormatArg::Text if color => {
+                                ColorTextFormatter.format_with_sections(&result, &labels, &sections)
+                            }
+                            FormatArg::Text => {
+                                TextFormatter.format_with_sections(&result, &labels, &sections)
+                            }
+                            FormatArg::Html => {
+                                HtmlFormatter.format_with_sections(&result, &labels, &sections)
+                            }
+                            FormatArg::Markdown => {
+                                MarkdownFormatter.format_with_sections(&result, &labels, &sections)
+                            }
+                            FormatArg::Json => {
+                                let json = JsonFormatter.format_with_sections(&result, &sections);
+                                if escapes_json(&args) {
+                                    escape_non_ascii_json(&json).into_owned()
+                                } else {
+                                    json
+                                }
+                            }
+                            _ => formatter.format(&result),
+                        };
+                        w.write_all(output.as_bytes())?;
+                    } else if escapes_json(&args) {
+                        let json = formatter.format(&result);
+                        w.write_all(escape_non_ascii_json(&json).as_bytes())?;
+                    } else {
+                        formatter.format_to_with_labels(&result, &labels, w)?;
+                    }
+                    if let Some(locations) = &locations
+                        && text_format
+                    {
+                        writeln!(w)?;
+                        w.write_all(format_diff_locations(locations).as_bytes())?;
+                    }
+                    Ok(())
+                });
+                record_history(&args, record);
+                if let Some(actions) = &sync_actions {
+                    run_sync(&args, actions);
+                }
+                result.has_differences()
+            }
+            Err(e) => fail(
+                &args,
+                FatalError::from_comparison(&e, "Error during comparison"),
+            ),
+        }
+    };
+
+    logger::flush_summaries();
+    if args.exit_codes() && has_differences {
+        process::exit(EXIT_DIFFERENCES);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use dir_compare_core::comparison::Side;
+
+    fn parse(extra: &[&str]) -> Args {
+        let mut argv = vec!["dir-compare", "a", "b"];
+        argv.extend_from_slice(extra);
+        Args::parse_from(argv)
+    }
+
+    fn assert_valid(extra: &[&str]) {
+        let problems = validate_flags(&parse(extra));
+        assert!(problems.is_empty(), "{:?} -> {:?}", extra, problems);
+    }
+
+    fn assert_rejected(extra: &[&str], expected: &str) {
+        let problems = validate_flags(&parse(extra));
+        assert_eq!(problems.len(), 1, "{:?} -> {:?}", extra, problems);
+        assert!(problems[0].contains(expected), "{}", problems[0]);
+    }
+
+    #[test]
+    fn test_validate_flags_defaults() {
+        assert_valid(&[]);
+        assert_valid(&["--flat"]);
+    }
+
+    #[test]
+    fn test_validate_flags_valid_hierarchy_combinations() {
+        for method in [
+            "filename", "name", "size", "hash", "fxhash", "fasthash", "sampled", "mtime",
+            "metadata",
+        ] {
+            assert_valid(&["--method", method]);
+            assert_valid(&["--method", method, "--case-insensitive"]);
+        }
+        assert_valid(&["--verify"]);
+        assert_valid(&["--method", "sampled", "--verify"]);
+        assert_valid(&["--method", "sampled-hash", "--verify"]);
+        assert_valid(&["--method", "mtime", "--mtime-tolerance", "2"]);
+    }
+
+    #[test]
+    fn test_validate_flags_valid_flat_combinations() {
+        assert_valid(&["--flat", "--full-hash"]);
+    }
+
+    #[test]
+    fn test_validate_flags_method_with_flat() {
+        assert_rejected(
+            &["--flat", "--method", "hash"],
+            "--method hash has no effect with --flat",
+        );
+        assert_rejected(
+            &["--flat", "--method", "sampled"],
+            "flat mode always groups",
+        );
+    }
+
+    #[test]
+    fn test_validate_flags_case_insensitive_with_flat() {
+        assert_rejected(&["--flat", "--case-insensitive"], "not by name");
+        assert_rejected(&["--flat", "--case-sensitive"], "Drop --case-sensitive");
+    }
+
+    #[test]
+    fn test_case_flags_conflict() {
+        assert!(
+            Args::try_parse_from([
+                "dir-compare",
+                "a",
+                "b",
+                "--case-insensitive",
+                "--case-sensitive",
+            ])
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn test_validate_flags_verify_with_flat() {
+        assert_rejected(&["--flat", "--verify"], "Use --full-hash");
+    }
+
+    #[test]
+    fn test_validate_flags_full_hash_without_flat() {
+        assert_rejected(&["--full-hash"], "--full-hash only applies to flat mode");
+    }
+
+    #[test]
+    fn test_validate_flags_advice_raw_share() {
+        assert_valid(&["--advice-raw-share", "80"]);
+        assert_valid(&["--method", "sampled", "--advice-raw-share", "0"]);
+        assert_rejected(
+            &["--method", "hash", "--advice-raw-share", "80"],
+            "--advice-raw-share only applies to --method sampled, not 'hash'",
+        );
+        assert_rejected(
+            &["--flat", "--advice-raw-share", "80"],
+            "--advice-raw-share only applies to --method sampled in hierarchy mode",
+        );
+        for invalid in [
+            &["--advice-raw-share", "101"][..],
+            &["--no-advice", "--advice-raw-share", "50"],
+        ] {
+            assert!(Args::try_parse_from(["dir-compare", "a", "b"].iter().chain(invalid)).is_err());
+        }
+    }
+
+    #[test]
+    fn test_validate_flags_external_rules() {
+        assert_valid(&["--external-rules", "rules.toml"]);
+        assert_valid(&["--external-rules", "rules.toml", "--method", "mtime"]);
+        assert_rejected(
+            &["--flat", "--external-rules", "rules.toml"],
+            "--external-rules only applies to hierarchy mode",
+        );
+        assert_rejected(
+            &["--format", "html-bundle", "--external-rules", "rules.toml"],
+            "--external-rules has no effect with --format html-bundle",
+        );
+    }
+
+    #[test]
+    fn test_validate_flags_verify_without_sampled() {
+        for method in ["filename", "size", "hash"] {
+            assert_rejected(
+                &["--verify", "--method", method],
+                "--verify only applies to --method sampled",
+            );
+        }
+    }
+
+    #[test]
+    fn test_validate_flags_mtime_tolerance_without_mtime() {
+        assert_rejected(
+            &["--mtime-tolerance", "2"],
+            "--mtime-tolerance only applies to --method mtime, not 'sampled'",
+        );
+        assert_rejected(
+            &["--mtime-tolerance", "2", "--method", "size"],
+            "Use --method mtime",
+        );
+    }
+
+    #[test]
+    fn test_validate_flags_locate_diffs() {
+        assert_valid(&["--locate-diffs"]);
+        assert_valid(&["--locate-diffs", "--format", "txt"]);
+        assert_rejected(
+            &["--locate-diffs", "--flat"],
+            "only applies to hierarchy mode",
+        );
+        assert_valid(&["--locate-diffs", "--format", "json"]);
+        assert_rejected(
+            &["--locate-diffs", "--format", "html"],
+            "only reported in text and json output",
+        );
+    }
+
+    #[test]
+    fn test_validate_flags_hard_links() {
+        assert_valid(&["--hard-links"]);
+        assert_valid(&["--hard-links", "--verbose"]);
+        assert_rejected(
+            &["--hard-links", "--flat"],
+            "only applies to hierarchy mode",
+        );
+        assert_rejected(
+            &["--hard-links", "--format", "markdown"],
+            "only reported in text output",
+        );
+    }
+
+    #[test]
+    fn test_validate_flags_threads() {
+        assert_valid(&["--threads", "4"]);
+        assert_valid(&["--threads", "1", "--method", "hash"]);
+        assert_rejected(
+            &["--threads", "4", "--flat"],
+            "only applies to hierarchy mode",
+        );
+        assert!(Args::try_parse_from(["dir-compare", "a", "b", "--threads", "0"]).is_err());
+    }
+
+    #[test]
+    fn test_validate_flags_progress() {
+        assert_valid(&["--progress"]);
+        assert_rejected(&["--progress", "--flat"], "only applies to hierarchy mode");
+    }
+
+    #[test]
+    fn test_validate_flags_chunk_dedupe() {
+        assert_valid(&["--flat", "--chunk-dedupe"]);
+        assert_valid(&[
+            "--flat",
+            "--chunk-dedupe",
+            "--avg-chunk-size",
+            "4096",
+            "--threads",
+            "2",
+            "--progress",
+        ]);
+        assert_rejected(&["--chunk-dedupe"], "only applies to flat mode");
+        assert_rejected(
+            &["--chunk-dedupe", "--format", "html-bundle"],
+            "only applies to flat mode",
+        );
+        assert_rejected(
+            &["--flat", "--avg-chunk-size", "4096"],
+            "only applies to --chunk-dedupe",
+        );
+        assert_rejected(
+            &["--flat", "--chunk-dedupe", "--format", "csv"],
+            "not reported in csv output",
+        );
+        assert!(
+            Args::try_parse_from(["dir-compare", "a", "b", "--avg-chunk-size", "100"]).is_err()
+        );
+    }
+
+    #[test]
+    fn test_validate_flags_estimate() {
+        assert_valid(&["--estimate", "--method", "hash"]);
+        assert_valid(&["--estimate-only", "--no-advice"]);
+        assert_valid(&["--estimate", "--porcelain"]);
+        assert_rejected(
+            &["--flat", "--estimate"],
+            "--estimate only applies to hierarchy mode",
+        );
+        assert_rejected(
+            &["--estimate-only", "--format", "html-bundle"],
+            "do not apply to --format html-bundle",
+        );
+        assert_rejected(
+            &["--estimate-only", "--porcelain"],
+            "--estimate-only cannot be combined with --porcelain",
+        );
+    }
+
+    #[test]
+    fn test_estimate_text() {
+        let estimate = ComparisonEstimate {
+            pairs: 4_000,
+            content_pairs: 3_200,
+            bytes: 3 << 29,
+            unsized_pairs: 0,
+        };
+        assert_eq!(
+            estimate_text(&estimate),
+            "Estimate: 4,000 pairs, 3,200 compared by content, about 1.5 GiB to read"
+        );
+        let lazy = ComparisonEstimate {
+            unsized_pairs: 12,
+            ..estimate
+        };
+        assert!(estimate_text(&lazy).ends_with(" plus 12 pairs of unknown size"));
+    }
+
+    #[test]
+    fn test_estimate_advice_above_threshold() {
+        let large = ComparisonEstimate {
+            pairs: 10,
+            content_pairs: 10,
+            bytes: ADVICE_ESTIMATE_BYTES + 1,
+            unsized_pairs: 0,
+        };
+        let small = ComparisonEstimate {
+            bytes: ADVICE_ESTIMATE_BYTES,
+            ..large
+        };
+        let advice = estimate_advice(&parse(&[]), &large).unwrap();
+        assert!(advice.contains("--no-advice silences this"), "{}", advice);
+        assert!(estimate_advice(&parse(&[]), &small).is_none());
+        assert!(estimate_advice(&parse(&["--no-advice"]), &large).is_none());
+    }
+
+    #[test]
+    fn test_validate_flags_flat_selection() {
+        assert_valid(&["--flat", "--flat-ext", "jpg,png", "--flat-min-size", "1"]);
+        assert_rejected(
+            &["--flat-ext", "jpg"],
+            "--flat-ext only applies to flat mode",
+        );
+        assert_rejected(
+            &["--flat-min-size", "1", "--format", "html-bundle"],
+            "--flat-min-size only applies to flat mode",
+        );
+        assert_rejected(
+            &["--flat", "--flat-min-size", "1", "--distrust-sizes"],
+            "does not trust",
+        );
+        let args = parse(&["--flat", "--flat-ext", "jpg,.PNG"]);
+        assert_eq!(
+            args.flat_ext,
+            Some(vec!["jpg".to_string(), ".PNG".to_string()])
+        );
+    }
+
+    #[test]
+    fn test_validate_flags_script() {
+        assert_valid(&["--flat", "--format", "script"]);
+        assert_valid(&[
+            "--flat",
+            "--format",
+            "script",
+            "--script-shell",
+            "powershell",
+        ]);
+        assert_rejected(
+            &["--format", "script"],
+            "--format script only applies to flat mode",
+        );
+        assert_rejected(
+            &["--flat", "--script-shell", "sh"],
+            "--script-shell only applies to --format script, not 'text'",
+        );
+        assert_rejected(
+            &["--flat", "--format", "script", "--chunk-dedupe"],
+            "not reported in script output",
+        );
+        let args = parse(&["--flat", "--format", "script", "--script-shell", "pwsh"]);
+        assert_eq!(args.script_shell, Some(ScriptShell::PowerShell));
+    }
+
+    #[test]
+    fn test_validate_flags_sanitize_paths() {
+        assert_valid(&["--sanitize-paths", "control"]);
+        assert_valid(&["--sanitize-paths", "ascii", "--format", "json"]);
+        assert_valid(&["--sanitize-paths", "ascii", "--flat", "--format", "csv"]);
+        assert_rejected(
+            &["--sanitize-paths", "control", "--porcelain"],
+            "--sanitize-paths cannot be combined with --porcelain",
+        );
+        assert_rejected(
+            &["--sanitize-paths", "ascii", "--format", "html-bundle"],
+            "--sanitize-paths cannot be combined with --format html-bundle",
+        );
+        assert_rejected(
+            &["--sanitize-paths", "ascii", "--flat", "--format", "script"],
+            "--sanitize-paths cannot be combined with --format script",
+        );
+        let args = parse(&["--sanitize-paths", "ASCII"]);
+        assert_eq!(args.sanitize_paths, Some(SanitizeMode::Ascii));
+    }
+
+    #[test]
+    fn test_validate_flags_color() {
+        assert_valid(&["--color", "always"]);
+        assert_valid(&["--color", "never", "--format", "json", "--output", "r.json"]);
+        for (extra, flag) in [
+            (&["--output", "r.txt"][..], "--output"),
+            (&["--format", "html"], "--format"),
+            (&["--flat"], "--flat"),
+            (&["--porcelain"], "--porcelain"),
+        ] {
+            let mut argv = vec!["--color", "always"];
+            argv.extend_from_slice(extra);
+            assert_rejected(
+                &argv,
+                &format!("--color always has no effect with {}", flag),
+            );
+        }
+        assert!(Args::try_parse_from(["dir-compare", "a", "b", "--color", "sometimes"]).is_err());
+    }
+
+    #[test]
+    fn test_validate_flags_sync() {
+        assert_valid(&["--sync", "a-to-b"]);
+        assert_valid(&["--sync", "two-way", "--dry-run", "--method", "mtime"]);
+        assert_valid(&["--sync", "b-to-a", "--result-detail", "full"]);
+        for (extra, flag) in [
+            (&["--flat"][..], "--flat"),
+            (&["--format", "html-bundle"], "--format html-bundle"),
+            (&["--porcelain"], "--porcelain"),
+            (&["--estimate-only"], "--estimate-only"),
+            (&["--max-depth", "2"], "--max-depth"),
+            (&["--result-detail", "counts"], "--result-detail"),
+        ] {
+            let mut argv = vec!["--sync", "a-to-b"];
+            argv.extend_from_slice(extra);
+            assert_rejected(&argv, &format!("--sync cannot be combined with {}", flag));
+        }
+        assert!(Args::try_parse_from(["dir-compare", "a", "b", "--dry-run"]).is_err());
+        assert!(Args::try_parse_from(["dir-compare", "a", "b", "--sync", "mirror"]).is_err());
+        assert_eq!(parse(&["--sync", "B-TO-A"]).sync, Some(SyncDirection::BToA));
+    }
+
+    #[test]
+    fn test_progress_text() {
+        let path = Path::new("docs/a.txt");
+        let scanning = Progress {
+            phase: ProgressPhase::Traversing,
+            current: 12,
+            total: None,
+            side: Some(Side::B),
+            current_path: path,
+        };
+        assert_eq!(progress_text(&scanning), "Scanning B: 12 entries");
+        let unknown_side = Progress {
+            side: None,
+            ..scanning
+        };
+        assert_eq!(progress_text(&unknown_side), "Scanning: 12 entries");
+        let matching = Progress {
+            phase: ProgressPhase::Matching,
+            current: 3,
+            total: Some(40),
+            side: None,
+            current_path: path,
+        };
+        assert_eq!(progress_text(&matching), "Comparing: 3/40 files");
+        let chunking = Progress {
+            phase: ProgressPhase::Chunking,
+            ..matching
+        };
+        assert_eq!(progress_text(&chunking), "Chunking: 3/40 files");
+    }
+
+    #[test]
+    fn test_progress_line_fits_width() {
+        let progress = Progress {
+            phase: ProgressPhase::Matching,
+            current: 3,
+            total: Some(40),
+            side: None,
+            current_path: Path::new("photos/2024/summer/beach/IMG_0001.jpg"),
+        };
+        assert_eq!(
+            progress_line(&progress, 80),
+            "Comparing: 3/40 files  photos/2024/summer/beach/IMG_0001.jpg"
+        );
+        assert_eq!(
+            progress_line(&progress, 40),
+            "Comparing: 3/40 files  pho…/IMG_0001.jpg"
+        );
+        // Too narrow for a useful path
+        assert_eq!(progress_line(&progress, 30), "Comparing: 3/40 files");
+        assert_eq!(progress_line(&progress, 10), "Compa…iles");
+    }
+
+    #[test]
+    fn test_sections_parsing() {
+        let args = parse(&["--sections", "b-only,different"]);
+        assert_eq!(args.sections, Some(vec![Section::BOnly, Section::Modified]));
+        assert!(parse(&[]).sections.is_none());
+        assert!(
+            Args::try_parse_from(["dir-compare", "a", "b", "--sections", "a-only,same"]).is_err()
+        );
+    }
+
+    #[test]
+    fn test_validate_flags_sections() {
+        assert_valid(&["--sections", "a-only,both", "--format", "html"]);
+        assert_valid(&["--sections", "modified", "--format", "md"]);
+        assert_rejected(
+            &["--sections", "a-only", "--flat"],
+            "only applies to hierarchy mode",
+        );
+        assert_rejected(&["--sections", "a-only", "--format", "json"], "not 'json'");
+        assert_rejected(&["--sections", "a-only", "--hard-links"], "--hard-links");
+    }
+
+    #[test]
+    fn test_validate_flags_result_detail() {
+        assert_valid(&["--result-detail", "counts", "--format", "json"]);
+        assert_valid(&["--result-detail", "sampled:100"]);
+        assert_valid(&["--result-detail", "full", "--hard-links"]);
+        assert_rejected(
+            &["--result-detail", "counts", "--flat"],
+            "only applies to hierarchy mode",
+        );
+        assert_rejected(
+            &["--result-detail", "sampled:10", "--hard-links"],
+            "drops entries",
+        );
+        for invalid in ["sampled", "sampled:x", "some"] {
+            assert!(
+                Args::try_parse_from(["dir-compare", "a", "b", "--result-detail", invalid])
+                    .is_err()
+            );
+        }
+    }
+
+    #[test]
+    fn test_validate_flags_stat_mode() {
+        assert_valid(&["--stat-mode", "lazy"]);
+        assert_valid(&["--stat-mode", "eager", "--method", "mtime"]);
+        assert_valid(&["--stat-mode", "lazy", "--format", "html-bundle"]);
+        assert_rejected(
+            &["--stat-mode", "lazy", "--flat"],
+            "--stat-mode only applies to hierarchy mode",
+        );
+        assert!(Args::try_parse_from(["dir-compare", "a", "b", "--stat-mode", "never"]).is_err());
+    }
+
+    #[test]
+    fn test_validate_flags_hash_algo() {
+        assert_valid(&["--flat", "--hash-algo", "blake3"]);
+        assert_valid(&["--flat", "--full-hash", "--hash-algo", "fxhash"]);
+        assert_valid(&["--method", "blake3"]);
+        assert_rejected(
+            &["--hash-algo", "blake3"],
+            "--hash-algo only applies to flat mode",
+        );
+        assert!(Args::try_parse_from(["dir-compare", "a", "b", "--hash-algo", "md5"]).is_err());
+    }
+
+    #[test]
+    fn test_validate_flags_manifest() {
+        let problems = |extra: &[&str]| {
+            let mut argv = vec!["dir-compare", "a"];
+            argv.extend_from_slice(extra);
+            validate_flags(&Args::parse_from(argv))
+        };
+        assert!(problems(&["--write-manifest", "m", "--hash-algo", "blake3"]).is_empty());
+        assert!(problems(&["--verify-manifest", "m", "--format", "json"]).is_empty());
+        let rejected = problems(&["--write-manifest", "m", "--format", "json"]);
+        assert_eq!(rejected.len(), 1, "{:?}", rejected);
+        assert!(rejected[0].contains("--format json has no effect with manifests"));
+        let rejected = problems(&["--verify-manifest", "m", "--method", "size"]);
+        assert_eq!(rejected.len(), 1, "{:?}", rejected);
+        assert!(rejected[0].contains("--method has no effect"));
+
+        // A manifest takes DIR1 alone, and only one of the flags
+        assert!(Args::try_parse_from(["dir-compare", "a"]).is_err());
+        assert!(Args::try_parse_from(["dir-compare", "a", "b", "--write-manifest", "m"]).is_err());
+        assert!(
+            Args::try_parse_from([
+                "dir-compare",
+                "a",
+                "--write-manifest",
+                "m",
+                "--verify-manifest",
+                "m",
+            ])
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn test_validate_flags_html_bundle() {
+        assert_valid(&["--format", "html-bundle"]);
+        assert_valid(&["--format", "html-bundle", "--flat"]);
+        assert_valid(&[
+            "--format",
+            "html-bundle",
+            "--full-hash",
+            "--hash-algo",
+            "blake3",
+        ]);
+        assert_valid(&["--format", "html-bundle", "--case-insensitive"]);
+        assert_rejected(
+            &["--format", "html-bundle", "--method", "size"],
+            "--method size has no effect with --format html-bundle",
+        );
+        assert_rejected(
+            &["--format", "html-bundle", "--verify"],
+            "--verify has no effect with --format html-bundle",
+        );
+        assert_rejected(
+            &["--format", "html-bundle", "--distrust-sizes"],
+            "--distrust-sizes cannot be combined",
+        );
+        assert_rejected(
+            &["--format", "html-bundle", "--sections", "a-only"],
+            "--sections only applies to text, markdown and html output",
+        );
+        assert_rejected(
+            &["--format", "html-bundle", "--porcelain"],
+            "--format html-bundle cannot be combined with --porcelain",
+        );
+    }
+
+    #[test]
+    fn test_split_command() {
+        assert_eq!(
+            split_command("gpg --detach-sign {file}").unwrap(),
+            ["gpg", "--detach-sign", "{file}"]
+        );
+        assert_eq!(
+            split_command(r#"  sign 'two words' "a \"q\" \\ \n" back\ slash  "#).unwrap(),
+            ["sign", "two words", r#"a "q" \ \n"#, "back slash"]
+        );
+        assert_eq!(split_command("''").unwrap(), [""]);
+        assert!(split_command("sign 'open").is_err());
+        assert!(split_command("sign \"open").is_err());
+        assert!(split_command("sign \\").is_err());
+    }
+
+    #[test]
+    fn test_sign_command_substitutes_file() {
+        let sign_cmd: SignCommand = "signer --out={file}.sig {file}".parse().unwrap();
+        let command = sign_cmd.command(Path::new("/tmp/my reports/report.html"));
+        assert_eq!(command.get_program(), "signer");
+        let args: Vec<_> = command.get_args().collect();
+        assert_eq!(
+            args,
+            [
+                "--out=/tmp/my reports/report.html.sig",
+                "/tmp/my reports/report.html"
+            ]
+        );
+
+        assert!("gpg --sign".parse::<SignCommand>().is_err());
+        assert!("  ".parse::<SignCommand>().is_err());
+        assert!(
+            Args::try_parse_from(["dir-compare", "a", "b", "--sign-cmd", "gpg {file}"]).is_err()
+        );
+        assert!(Args::try_parse_from(["dir-compare", "a", "b", "--report-digest"]).is_err());
+    }
+
+    #[test]
+    fn test_validate_flags_porcelain() {
+        assert_valid(&["--porcelain"]);
+        assert_valid(&["--porcelain", "-z", "--exit-code"]);
+        assert_rejected(&["--porcelain", "--flat"], "--flat cannot be combined");
+        assert_rejected(
+            &["--porcelain", "--result-detail", "counts"],
+            "lists every difference",
+        );
+        assert_rejected(
+            &["--porcelain", "--log-dest", "stdout"],
+            "--log-dest stdout",
+        );
+        assert!(Args::try_parse_from(["dir-compare", "a", "b", "-z"]).is_err());
+    }
+
+    #[test]
+    fn test_validate_flags_rollup() {
+        assert_valid(&["--rollup"]);
+        assert_valid(&["--rollup", "--format", "json", "--sync", "a-to-b"]);
+        for (extra, flag) in [
+            (&["--flat"][..], "--flat"),
+            (&["--format", "csv"], "--format csv"),
+            (&["--format", "html-bundle"], "--format html-bundle"),
+            (&["--porcelain"], "--porcelain"),
+            (&["--result-detail", "sampled:5"], "--result-detail"),
+        ] {
+            let mut argv = vec!["--rollup"];
+            argv.extend_from_slice(extra);
+            assert_rejected(&argv, &format!("--rollup cannot be combined with {}", flag));
+        }
+    }
+
+    #[test]
+    fn test_validate_flags_detect_renames() {
+        assert_valid(&["--detect-renames"]);
+        assert_valid(&["--detect-renames", "--porcelain"]);
+        assert_valid(&["--detect-renames", "--format", "json", "--sync", "a-to-b"]);
+        for (extra, flag) in [
+            (&["--flat"][..], "--flat"),
+            (&["--format", "csv"], "--format csv"),
+            (&["--format", "html-bundle"], "--format html-bundle"),
+        ] {
+            let mut argv = vec!["--detect-renames"];
+            argv.extend_from_slice(extra);
+            assert_rejected(
+                &argv,
+                &format!("--detect-renames cannot be combined with {}", flag),
+            );
+        }
+    }
+
+    #[test]
+    fn test_validate_flags_summary() {
+        assert_valid(&["--summary"]);
+        assert_valid(&["--summary", "--format", "json", "--rollup"]);
+        assert_valid(&["--summary", "--format", "html", "--exit-code"]);
+        assert_rejected(
+            &["--summary", "--flat"],
+            "--summary only applies to hierarchy mode",
+        );
+        assert_rejected(&["--summary", "--format", "csv"], "not 'csv'");
+        for (extra, flag) in [
+            (&["--sections", "a-only"][..], "--sections"),
+            (&["--hard-links"], "--hard-links"),
+            (&["--locate-diffs"], "--locate-diffs"),
+        ] {
+            let mut argv = vec!["--summary"];
+            argv.extend_from_slice(extra);
+            assert_rejected(
+                &argv,
+                &format!("{} cannot be combined with --summary", flag),
+            );
+        }
+        assert_rejected(
+            &["--summary", "--porcelain"],
+            "--summary cannot be combined with --porcelain",
+        );
+    }
+
+    #[test]
+    fn test_validate_flags_quiet() {
+        assert!(parse(&["-q"]).exit_codes());
+        assert_valid(&["--quiet", "--sync", "a-to-b"]);
+        assert!(Args::try_parse_from(["dir-compare", "a", "b", "--quiet", "-o", "r.txt"]).is_err());
+        for flag in ["--porcelain", "--summary", "--estimate-only"] {
+            assert_rejected(
+                &["--quiet", flag],
+                &format!("{} has no effect with --quiet", flag),
+            );
+        }
+        assert_rejected(
+            &["--quiet", "--log-dest", "stdout"],
+            "--quiet keeps stdout empty",
+        );
+    }
+
+    #[test]
+    fn test_fatal_error_to_json() {
+        let error = FatalError::new("directory_not_found", "No \"dir\"".to_string())
+            .with_path(Path::new("/tmp/a\\b"))
+            .with_hint("Check the path");
+        assert_eq!(
+            error.to_json(),
+            r#"{"error": {"kind": "directory_not_found", "path": "/tmp/a\\b", "message": "No \"dir\". Check the path"}}"#
+        );
+
+        let error = FatalError::new("invalid_flags", "bad".to_string());
+        assert_eq!(
+            error.to_json(),
+            r#"{"error": {"kind": "invalid_flags", "path": null, "message": "bad"}}"#
+        );
+    }
+
+    #[test]
+    fn test_validate_flags_reports_every_problem() {
+        let problems = validate_flags(&parse(&[
+            "--flat",
+            "--method",
+            "hash",
+            "--case-insensitive",
+            "--verify",
+        ]));
+        assert_eq!(problems.len(), 3);
+    }
+
+    #[test]
+    fn test_config_path() {
+        let argv = |args: &[&str]| -> Vec<OsString> { args.iter().map(OsString::from).collect() };
+        assert_eq!(config_path(&argv(&["dir-compare", "a", "b"])), None);
+        assert_eq!(
+            config_path(&argv(&["dir-compare", "--config", "c.toml", "a", "b"])),
+            Some(PathBuf::from("c.toml"))
+        );
+        assert_eq!(
+            config_path(&argv(&["dir-compare", "a", "b", "--config=c.toml"])),
+            Some(PathBuf::from("c.toml"))
+        );
+        assert_eq!(
+            config_path(&argv(&["dir-compare", "--", "--config", "c.toml"])),
+            None
+        );
+    }
+
+    #[test]
+    fn test_command_line_overrides_config_options() {
+        let config = ["--method", "size", "--flat", "--exclude", "*.tmp"];
+        let args = parse(&[&config[..], &["--method", "hash", "--exclude", "*.log"]].concat());
+        assert_eq!(args.method(), MethodArg::Hash);
+        assert!(args.flat);
+        assert_eq!(args.exclude, ["*.tmp", "*.log"]);
+    }
+
+    #[test]
+    fn test_logger_config_color_follows_the_environment() {
+        let args = parse(&[]);
+        let env = |no_color: &'static str, force: &'static str| {
+            move |name: &str| match name {
+                "NO_COLOR" => Some(no_color.to_string()),
+                "CLICOLOR_FORCE" => Some(force.to_string()),
+                _ => None,
+            }
+        };
+        assert!(logger_config(&args, env("", "1")).color);
+        assert!(!logger_config(&args, env("1", "1")).color);
+    }
+
+    #[test]
+    fn test_logger_config_prefers_the_environment() {
+        let args = parse(&[
+            "--log-level",
+            "error",
+            "--log-dest",
+            "stdout",
+            "--log-timestamp",
+            "unix",
+            "--log-format",
+            "json",
+        ]);
+        let config = logger_config(&args, |_| None);
+        assert_eq!(config.level, LogLevel::Error);
+        assert_eq!(config.destination, OutputDestination::Stdout);
+        assert_eq!(config.timestamp_format, TimestampFormat::Unix);
+        assert_eq!(config.log_format, LogFormat::Json);
+
+        let env = |name: &str| {
+            Some(
+                match name {
+                    "DIR_COMPARE_LOG_LEVEL" => "debug",
+                    "DIR_COMPARE_LOG_DEST" => "STDERR",
+                    "DIR_COMPARE_LOG_TIMESTAMP" => "none",
+                    "DIR_COMPARE_LOG_FORMAT" => "text",
+                    _ => return None,
+                }
+                .to_string(),
+            )
+        };
+        let config = logger_config(&args, env);
+        assert_eq!(config.level, LogLevel::Debug);
+        assert_eq!(config.destination, OutputDestination::Stderr);
+        assert_eq!(config.timestamp_format, TimestampFormat::None);
+        assert_eq!(config.log_format, LogFormat::Text);
+
+        // Values the environment cannot parse fall back to the flags
+        let config = logger_config(&args, |_| Some("verbose".to_string()));
+        assert_eq!(config.level, LogLevel::Error);
+        assert_eq!(config.destination, OutputDestination::Stdout);
+        assert_eq!(config.timestamp_format, TimestampFormat::Unix);
+        assert_eq!(config.log_format, LogFormat::Json);
+
+        let config = logger_config(&parse(&[]), |_| None);
+        assert_eq!(config.level, LogLevel::Info);
+        assert_eq!(config.destination, OutputDestination::Stderr);
+        assert_eq!(config.dedup_window, Some(20));
+        let config = logger_config(&parse(&["--max-warnings", "0"]), |_| None);
+        assert_eq!(config.dedup_window, None);
+    }
+
+    #[test]
+    fn test_report_labels_prefer_the_flag() {
+        let env = |name: &str| (name == "DIR_COMPARE_LANG").then(|| "ja".to_string());
+        assert_eq!(report_labels(&parse(&[]), |_| None).lang, "en");
+        assert_eq!(report_labels(&parse(&[]), env).lang, "ja");
+        assert_eq!(report_labels(&parse(&["--lang", "DE"]), env).lang, "de");
+        assert_eq!(report_labels(&parse(&["--lang", "fr"]), env).lang, "en");
     }
 }
@@ -0,0 +1,238 @@
+//! Terminal conventions shared by all human-oriented output: log lines, the
+//! progress line, the `doctor` checks and text reports on stdout.
+//!
+//! - `NO_COLOR` set to anything but the empty string disables ANSI styling.
+//! - Otherwise `CLICOLOR_FORCE` set to anything but `0` enables it, even
+//!   when the output is piped.
+//! - Otherwise output is styled only when it goes to a terminal.
+//!
+//! The width is taken from `COLUMNS`, then from the terminal on stderr,
+//! and is [`FALLBACK_WIDTH`] when neither is known.
+
+use std::io::IsTerminal;
+
+/// Width assumed when neither `COLUMNS` nor the terminal tells it.
+pub const FALLBACK_WIDTH: usize = 80;
+
+/// Decides whether to style output with the values of `NO_COLOR` and
+/// `CLICOLOR_FORCE`, for a stream that is a terminal if `is_tty`.
+pub fn color_enabled(no_color: Option<&str>, clicolor_force: Option<&str>, is_tty: bool) -> bool {
+    if no_color.is_some_and(|value| !value.is_empty()) {
+        return false;
+    }
+    if clicolor_force.is_some_and(|value| !value.is_empty() && value != "0") {
+        return true;
+    }
+    is_tty
+}
+
+/// Whether output written to `stream` is styled, following the environment.
+pub fn use_color(stream: &impl IsTerminal) -> bool {
+    color_enabled(
+        std::env::var("NO_COLOR").ok().as_deref(),
+        std::env::var("CLICOLOR_FORCE").ok().as_deref(),
+        stream.is_terminal(),
+    )
+}
+
+/// When `--color` styles the text report on stdout.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ColorChoice {
+    /// As [`use_color`] decides for stdout
+    #[default]
+    Auto,
+    /// Always, even when piped or with `NO_COLOR` set
+    Always,
+    /// Never
+    Never,
+}
+
+impl std::str::FromStr for ColorChoice {
+    type Err = String;
+
+    /// Parses `auto`, `always` or `never`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "auto" => Ok(ColorChoice::Auto),
+            "always" => Ok(ColorChoice::Always),
+            "never" => Ok(ColorChoice::Never),
+            other => Err(format!(
+                "unknown color choice '{}' (expected auto, always or never)",
+                other
+            )),
+        }
+    }
+}
+
+impl ColorChoice {
+    /// Whether to style, given what `auto` would decide.
+    pub fn resolve(self, auto: bool) -> bool {
+        match self {
+            ColorChoice::Auto => auto,
+            ColorChoice::Always => true,
+            ColorChoice::Never => false,
+        }
+    }
+}
+
+/// Wraps `text` in the SGR parameters `style`, such as `"32"` for green,
+/// if `color` is set.
+pub fn paint(text: &str, style: &str, color: bool) -> String {
+    if color {
+        format!("\x1b[{}m{}\x1b[0m", style, text)
+    } else {
+        text.to_string()
+    }
+}
+
+/// Picks the width from a `COLUMNS` value, then the `queried` terminal
+/// width, then [`FALLBACK_WIDTH`]. Zero and unparsable values are skipped.
+pub fn width_from(columns: Option<&str>, queried: Option<usize>) -> usize {
+    columns
+        .and_then(|value| value.trim().parse().ok())
+        .filter(|&width: &usize| width > 0)
+        .or(queried.filter(|&width| width > 0))
+        .unwrap_or(FALLBACK_WIDTH)
+}
+
+/// Number of columns available to the output.
+pub fn width() -> usize {
+    width_from(std::env::var("COLUMNS").ok().as_deref(), stderr_width())
+}
+
+/// Columns of the terminal on stderr; `None` if stderr is not a terminal.
+#[cfg(unix)]
+fn stderr_width() -> Option<usize> {
+    let mut size = std::mem::MaybeUninit::<libc::winsize>::uninit();
+    // SAFETY: TIOCGWINSZ writes a `struct winsize` to `size` on success
+    if unsafe { libc::ioctl(libc::STDERR_FILENO, libc::TIOCGWINSZ, size.as_mut_ptr()) } != 0 {
+        return None;
+    }
+    // SAFETY: the ioctl succeeded
+    let size = unsafe { size.assume_init() };
+    Some(usize::from(size.ws_col))
+}
+
+#[cfg(not(unix))]
+fn stderr_width() -> Option<usize> {
+    None
+}
+
+/// Shortens `path` to `max_chars` characters by replacing its middle with
+/// `…`, as in `src/…/file.txt`.
+///
+/// The file name is kept whole when it fits next to the `…`; otherwise the
+/// start and end are kept in equal parts. Characters are never split, and
+/// nothing is returned for a `max_chars` of zero.
+pub fn truncate_middle(path: &str, max_chars: usize) -> String {
+    let len = path.chars().count();
+    if len <= max_chars {
+        return path.to_string();
+    }
+    let kept = max_chars.saturating_sub(1);
+    if kept == 0 {
+        return "…".chars().take(max_chars).collect();
+    }
+    let name = path
+        .rfind('/')
+        .map_or(0, |index| path[index..].chars().count());
+    let tail = if name > 0 && name < kept {
+        name
+    } else {
+        kept / 2
+    };
+    let head = kept - tail;
+    let start: String = path.chars().take(head).collect();
+    let end: String = path.chars().skip(len - tail).collect();
+    format!("{}…{}", start, end)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_color_decision_matrix() {
+        // NO_COLOR, CLICOLOR_FORCE, TTY, expected
+        let cases = [
+            (None, None, true, true),
+            (None, None, false, false),
+            (Some("1"), None, true, false),
+            (Some("1"), Some("1"), true, false),
+            (Some("1"), Some("1"), false, false),
+            // An empty NO_COLOR is ignored
+            (Some(""), None, true, true),
+            (Some(""), Some("1"), false, true),
+            (None, Some("1"), false, true),
+            (None, Some("1"), true, true),
+            (None, Some("0"), false, false),
+            (None, Some("0"), true, true),
+            (None, Some(""), false, false),
+        ];
+        for (no_color, force, is_tty, expected) in cases {
+            assert_eq!(
+                color_enabled(no_color, force, is_tty),
+                expected,
+                "NO_COLOR={:?} CLICOLOR_FORCE={:?} tty={}",
+                no_color,
+                force,
+                is_tty
+            );
+        }
+    }
+
+    #[test]
+    fn test_color_choice() {
+        for auto in [true, false] {
+            assert_eq!(ColorChoice::Auto.resolve(auto), auto);
+            assert!(ColorChoice::Always.resolve(auto));
+            assert!(!ColorChoice::Never.resolve(auto));
+        }
+        assert_eq!("Always".parse(), Ok(ColorChoice::Always));
+        assert!("yes".parse::<ColorChoice>().is_err());
+    }
+
+    #[test]
+    fn test_paint() {
+        assert_eq!(paint("FAIL", "31", true), "\x1b[31mFAIL\x1b[0m");
+        assert_eq!(paint("FAIL", "31", false), "FAIL");
+    }
+
+    #[test]
+    fn test_width_from() {
+        assert_eq!(width_from(Some("120"), Some(100)), 120);
+        assert_eq!(width_from(None, Some(100)), 100);
+        assert_eq!(width_from(Some("wide"), Some(100)), 100);
+        assert_eq!(width_from(Some("0"), None), FALLBACK_WIDTH);
+        assert_eq!(width_from(None, Some(0)), FALLBACK_WIDTH);
+        assert_eq!(width_from(None, None), FALLBACK_WIDTH);
+    }
+
+    #[test]
+    fn test_truncate_middle_keeps_file_name() {
+        let path = "projects/long/nested/directory/file.txt";
+        assert_eq!(truncate_middle(path, 100), path);
+        assert_eq!(truncate_middle(path, path.len()), path);
+        assert_eq!(truncate_middle(path, 20), "projects/l…/file.txt");
+        assert_eq!(truncate_middle(path, 20).chars().count(), 20);
+        // Too narrow for the name: both ends are kept
+        assert_eq!(truncate_middle(path, 9), "proj….txt");
+        assert_eq!(
+            truncate_middle("a-very-long-file-name.txt", 11),
+            "a-ver…e.txt"
+        );
+    }
+
+    #[test]
+    fn test_truncate_middle_is_unicode_safe() {
+        let path = "données/日本語のディレクトリ/ファイル名.txt";
+        for max_chars in 0..=path.chars().count() {
+            let truncated = truncate_middle(path, max_chars);
+            assert_eq!(truncated.chars().count(), max_chars, "{}", truncated);
+        }
+        assert_eq!(truncate_middle(path, 14), "don…/ファイル名.txt");
+        assert_eq!(truncate_middle("ééééé", 4), "éé…é");
+        assert_eq!(truncate_middle("ééééé", 1), "…");
+        assert_eq!(truncate_middle("ééééé", 0), "");
+    }
+}
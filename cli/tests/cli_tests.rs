@@ -1,5 +1,6 @@
-use assert_cmd::cargo::cargo_bin_cmd;
 use assert_cmd::Command;
+use assert_cmd::cargo::cargo_bin_cmd;
+use dir_compare_core::comparison::{HashAlgorithm, compute_full_hash};
 use predicates::prelude::*;
 use std::fs::{self, File};
 
@@ -69,10 +70,15 @@ fn test_cli_invalid_method() {
     cmd.arg(temp_dir.path().join("dir_a").to_str().unwrap())
         .arg(temp_dir.path().join("dir_b").to_str().unwrap())
         .arg("--method")
-        .arg("invalid_method")
+        .arg("sample")
         .assert()
         .failure()
-        .stderr(predicate::str::contains("Invalid comparison method"));
+        .stderr(predicate::str::contains(
+            "invalid value 'sample' for '--method <METHOD>'",
+        ))
+        .stderr(predicate::str::contains(
+            "a similar value exists: 'sampled'",
+        ));
 }
 
 #[test]
@@ -86,7 +92,103 @@ fn test_cli_invalid_format() {
         .arg("invalid_format")
         .assert()
         .failure()
-        .stderr(predicate::str::contains("Invalid format"));
+        .stderr(predicate::str::contains(
+            "invalid value 'invalid_format' for '--format <FORMAT>'",
+        ))
+        .stderr(predicate::str::contains(
+            "[possible values: text, html, html-bundle",
+        ));
+}
+
+#[test]
+fn test_cli_format_and_log_aliases() {
+    let temp_dir = setup_test_dirs();
+
+    let mut cmd = cli_command();
+    cmd.arg(temp_dir.path().join("dir_a").to_str().unwrap())
+        .arg(temp_dir.path().join("dir_b").to_str().unwrap())
+        .args([
+            "--format",
+            "MD",
+            "--method",
+            "fxhash",
+            "--log-level",
+            "warning",
+        ])
+        .assert()
+        .success()
+        .stdout(predicate::str::starts_with("# "));
+
+    let mut cmd = cli_command();
+    cmd.arg(temp_dir.path().join("dir_a").to_str().unwrap())
+        .arg(temp_dir.path().join("dir_b").to_str().unwrap())
+        .args(["--log-dest", "file"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "invalid value 'file' for '--log-dest <LOG_DEST>'",
+        ));
+}
+
+#[test]
+fn test_cli_generate_completions() {
+    for (shell, expected) in [
+        ("bash", "--detect-renames"),
+        ("zsh", "html-bundle"),
+        ("fish", "sampled"),
+        ("powershell", "--log-dest"),
+    ] {
+        let mut cmd = cli_command();
+        cmd.args(["--generate-completions", shell])
+            .assert()
+            .success()
+            .stdout(predicate::str::contains("dir-compare"))
+            .stdout(predicate::str::contains(expected));
+    }
+
+    // Subcommands and every flag are completed
+    cli_command()
+        .args(["--generate-completions", "bash"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("history"))
+        .stdout(predicate::str::contains("--write-manifest"));
+
+    // After `--`, the flag is a directory name
+    cli_command()
+        .args(["dir_a", "--", "--generate-completions"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "First directory does not exist: dir_a",
+        ));
+
+    let mut cmd = cli_command();
+    cmd.args(["--generate-completions", "tcsh"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("invalid value 'tcsh'"));
+
+    let mut cmd = cli_command();
+    cmd.arg("--help")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("--generate-completions").not());
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn test_cli_generate_completions_reports_write_errors() {
+    let output = std::process::Command::new(assert_cmd::cargo::cargo_bin!("dir-compare"))
+        .args(["--generate-completions", "bash"])
+        .stdout(File::create("/dev/full").unwrap())
+        .output()
+        .unwrap();
+
+    assert_eq!(output.status.code(), Some(1));
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("Error writing to stdout"), "{}", stderr);
+    assert!(!stderr.contains("panicked"), "{}", stderr);
 }
 
 #[test]
@@ -127,6 +229,70 @@ fn test_cli_method_size() {
         .success();
 }
 
+#[test]
+fn test_cli_method_size_distrust_sizes() {
+    let temp_dir = setup_test_dirs();
+    let dir_a = temp_dir.path().join("dir_a");
+    let dir_b = temp_dir.path().join("dir_b");
+    fs::write(dir_a.join("same_size.txt"), "aaaa").unwrap();
+    fs::write(dir_b.join("same_size.txt"), "bbbb").unwrap();
+
+    // Reading only measures lengths, so equal sizes still match
+    cli_command()
+        .arg(&dir_a)
+        .arg(&dir_b)
+        .args(["--method", "size", "--distrust-sizes", "--format", "json"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(r#""modified": []"#));
+
+    cli_command()
+        .arg(&dir_a)
+        .arg(&dir_b)
+        .args(["--method", "sampled", "--distrust-sizes"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("same_size.txt != same_size.txt"));
+}
+
+#[test]
+fn test_cli_order_by_extent_keeps_the_report() {
+    let temp_dir = setup_test_dirs();
+    let dir_a = temp_dir.path().join("dir_a");
+    let dir_b = temp_dir.path().join("dir_b");
+    for name in ["b.txt", "a.txt", "c.txt"] {
+        fs::write(dir_a.join(name), name).unwrap();
+        fs::write(dir_b.join(name), "changed").unwrap();
+    }
+    let report = |extra: &[&str]| {
+        let output = cli_command()
+            .arg(&dir_a)
+            .arg(&dir_b)
+            .args(["--format", "json"])
+            .args(extra)
+            .output()
+            .unwrap();
+        assert!(output.status.code().is_some_and(|code| code <= 1));
+        let mut report = serde_json::from_slice::<serde_json::Value>(&output.stdout).unwrap();
+        // The duration differs between runs
+        report["stats"]
+            .as_object_mut()
+            .unwrap()
+            .remove("elapsed_ms");
+        report
+    };
+
+    let default = report(&["--method", "hash"]);
+    assert_eq!(
+        report(&["--method", "hash", "--order-by-extent", "--no-io-hints"]),
+        default
+    );
+    assert_eq!(
+        report(&["--order-by-extent", "--flat", "--full-hash"]),
+        report(&["--flat", "--full-hash"])
+    );
+}
+
 #[test]
 fn test_cli_method_hash() {
     let temp_dir = setup_test_dirs();
@@ -140,6 +306,44 @@ fn test_cli_method_hash() {
         .success();
 }
 
+#[test]
+fn test_cli_method_blake3() {
+    let temp_dir = setup_test_dirs();
+
+    let mut cmd = cli_command();
+    cmd.arg(temp_dir.path().join("dir_a").to_str().unwrap())
+        .arg(temp_dir.path().join("dir_b").to_str().unwrap())
+        .arg("--method")
+        .arg("blake3")
+        .assert()
+        .success();
+}
+
+#[test]
+fn test_cli_flat_hash_algo() {
+    let temp_dir = setup_test_dirs();
+
+    let output = cli_command()
+        .arg(temp_dir.path().join("dir_a").to_str().unwrap())
+        .arg(temp_dir.path().join("dir_b").to_str().unwrap())
+        .args([
+            "--flat",
+            "--full-hash",
+            "--hash-algo",
+            "blake3",
+            "--format",
+            "json",
+        ])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    for group in json["groups"].as_array().unwrap() {
+        assert_eq!(group["hash"].as_str().unwrap().len(), 64);
+    }
+}
+
 #[test]
 fn test_cli_case_insensitive() {
     let temp_dir = tempfile::tempdir().unwrap();
@@ -350,6 +554,69 @@ fn test_cli_flat_html_format() {
         .stdout(predicate::str::contains("Flat Mode Comparison"));
 }
 
+#[test]
+fn test_cli_html_bundle_holds_both_views() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let dir_a = temp_dir.path().join("dir_a");
+    let dir_b = temp_dir.path().join("dir_b");
+    fs::create_dir_all(dir_a.join("docs")).unwrap();
+    fs::create_dir_all(&dir_b).unwrap();
+    fs::write(dir_a.join("same.txt"), b"same").unwrap();
+    fs::write(dir_b.join("same.txt"), b"same").unwrap();
+    fs::write(dir_a.join("notes.txt"), b"old").unwrap();
+    fs::write(dir_b.join("notes.txt"), b"new").unwrap();
+    fs::write(dir_a.join("docs/<!--old<script>.md"), b"moved").unwrap();
+    fs::write(dir_b.join("renamed.md"), b"moved").unwrap();
+    let report = temp_dir.path().join("bundle.html");
+
+    let mut cmd = cli_command();
+    cmd.arg(dir_a.to_str().unwrap())
+        .arg(dir_b.to_str().unwrap())
+        .arg("--format")
+        .arg("html-bundle")
+        .arg("--output")
+        .arg(&report)
+        .arg("--log-level")
+        .arg("debug")
+        .assert()
+        .success()
+        // Two files per side, each hashed once for both views
+        .stderr(predicate::str::contains("Hashed 6 files for both views"));
+
+    let html = fs::read_to_string(&report).unwrap();
+    for section in ["a_only", "b_only", "modified", "both"] {
+        assert!(html.contains(&format!("data-section=\"{}\"", section)));
+    }
+    for status in ["a-only", "b-only", "moved", "duplicate"] {
+        assert!(html.contains(&format!("data-status=\"{}\"", status)));
+    }
+    assert!(!html.contains("src=\"http"));
+    assert!(!html.contains("href=\"http"));
+    // Markup in paths cannot end the embedded data early
+    assert!(!html.contains("<!--old"));
+
+    let embedded = |id: &str| -> serde_json::Value {
+        let start = format!("<script type=\"application/json\" id=\"{}\">", id);
+        let start = html.find(&start).unwrap() + start.len();
+        let end = start + html[start..].find("</script>").unwrap();
+        serde_json::from_str(&html[start..end]).unwrap()
+    };
+    let hierarchy = embedded("hierarchy-data");
+    assert_eq!(hierarchy["counts"]["modified"], 1);
+    assert_eq!(hierarchy["modified"][0]["a"]["path"], "notes.txt");
+    assert_eq!(hierarchy["a_only"][1]["path"], "docs/<!--old<script>.md");
+    let flat = embedded("flat-data");
+    assert_eq!(flat["total_files_a"], 3);
+    assert_eq!(flat["total_files_b"], 3);
+    let moved = flat["groups"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|group| group["files_in_b"][0] == "renamed.md")
+        .unwrap();
+    assert_eq!(moved["files_in_a"][0], "docs/<!--old<script>.md");
+}
+
 #[test]
 fn test_cli_flat_markdown_format() {
     let temp_dir = tempfile::tempdir().unwrap();
@@ -400,8 +667,8 @@ fn test_cli_flat_moved_files() {
     let temp_dir = tempfile::tempdir().unwrap();
     let dir_a = temp_dir.path().join("dir_a");
     let dir_b = temp_dir.path().join("dir_b");
-    fs::create_dir_all(&dir_a.join("documents")).unwrap();
-    fs::create_dir_all(&dir_b.join("archive")).unwrap();
+    fs::create_dir_all(dir_a.join("documents")).unwrap();
+    fs::create_dir_all(dir_b.join("archive")).unwrap();
 
     // Same content, different locations
     fs::write(
@@ -448,3 +715,1979 @@ fn test_cli_flat_with_output_file() {
     let content = fs::read_to_string(output_file).unwrap();
     assert!(content.contains("Flat Mode Comparison"));
 }
+
+#[test]
+fn test_cli_rejects_method_with_flat() {
+    let temp_dir = setup_test_dirs();
+
+    let mut cmd = cli_command();
+    cmd.arg(temp_dir.path().join("dir_a").to_str().unwrap())
+        .arg(temp_dir.path().join("dir_b").to_str().unwrap())
+        .arg("--flat")
+        .arg("--method")
+        .arg("hash")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "--method hash has no effect with --flat",
+        ));
+}
+
+#[test]
+fn test_cli_rejects_verify_without_sampled() {
+    let temp_dir = setup_test_dirs();
+
+    let mut cmd = cli_command();
+    cmd.arg(temp_dir.path().join("dir_a").to_str().unwrap())
+        .arg(temp_dir.path().join("dir_b").to_str().unwrap())
+        .arg("--verify")
+        .arg("--method")
+        .arg("filename")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "--verify only applies to --method sampled",
+        ));
+}
+
+#[test]
+fn test_cli_rejects_full_hash_without_flat() {
+    let temp_dir = setup_test_dirs();
+
+    let mut cmd = cli_command();
+    cmd.arg(temp_dir.path().join("dir_a").to_str().unwrap())
+        .arg(temp_dir.path().join("dir_b").to_str().unwrap())
+        .arg("--full-hash")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "--full-hash only applies to flat mode",
+        ));
+}
+
+#[test]
+fn test_cli_lenient_flags_warns_and_continues() {
+    let temp_dir = setup_test_dirs();
+
+    let mut cmd = cli_command();
+    cmd.arg(temp_dir.path().join("dir_a").to_str().unwrap())
+        .arg(temp_dir.path().join("dir_b").to_str().unwrap())
+        .arg("--full-hash")
+        .arg("--lenient-flags")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Both"))
+        .stderr(predicate::str::contains("[WARN]"));
+}
+
+#[test]
+fn test_cli_verify_with_sampled_is_accepted() {
+    let temp_dir = setup_test_dirs();
+
+    let mut cmd = cli_command();
+    cmd.arg(temp_dir.path().join("dir_a").to_str().unwrap())
+        .arg(temp_dir.path().join("dir_b").to_str().unwrap())
+        .arg("--method")
+        .arg("sampled")
+        .arg("--verify")
+        .assert()
+        .success();
+}
+
+#[test]
+fn test_cli_locate_diffs() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let dir_a = temp_dir.path().join("dir_a");
+    let dir_b = temp_dir.path().join("dir_b");
+    fs::create_dir_all(&dir_a).unwrap();
+    fs::create_dir_all(&dir_b).unwrap();
+
+    let content = vec![0u8; 4096];
+    let mut changed = content.clone();
+    changed[2048] = 1;
+    fs::write(dir_a.join("data.bin"), &content).unwrap();
+    fs::write(dir_b.join("data.bin"), &changed).unwrap();
+
+    let mut cmd = cli_command();
+    cmd.arg(dir_a.to_str().unwrap())
+        .arg(dir_b.to_str().unwrap())
+        .arg("--method")
+        .arg("hash")
+        .arg("--locate-diffs")
+        .arg("--diff-block-size")
+        .arg("1024")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Difference locations (1 files):"))
+        .stdout(predicate::str::contains(
+            "data.bin: 1 differing regions covering 1.0 KiB of 4.0 KiB",
+        ))
+        .stdout(predicate::str::contains("0x800..0xc00"));
+
+    let output = cli_command()
+        .arg(&dir_a)
+        .arg(&dir_b)
+        .args([
+            "--method",
+            "hash",
+            "--locate-diffs",
+            "--diff-block-size",
+            "1024",
+        ])
+        .args(["--format", "json"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(
+        json["diff_locations"],
+        serde_json::json!([{
+            "path": "data.bin",
+            "len_a": 4096,
+            "len_b": 4096,
+            "regions": [{"start": 2048, "end": 3072}]
+        }])
+    );
+}
+
+#[cfg(unix)]
+#[test]
+fn test_cli_hard_links() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let dir_a = temp_dir.path().join("dir_a");
+    let dir_b = temp_dir.path().join("dir_b");
+    fs::create_dir_all(&dir_a).unwrap();
+    fs::create_dir_all(&dir_b).unwrap();
+
+    fs::write(dir_a.join("a.txt"), vec![b'x'; 2048]).unwrap();
+    fs::hard_link(dir_a.join("a.txt"), dir_a.join("b.txt")).unwrap();
+    fs::hard_link(dir_a.join("a.txt"), dir_a.join("c.txt")).unwrap();
+
+    let mut cmd = cli_command();
+    cmd.arg(dir_a.to_str().unwrap())
+        .arg(dir_b.to_str().unwrap())
+        .arg("--hard-links")
+        .arg("--verbose")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("A-only (1 entries):"))
+        .stdout(predicate::str::contains("(+ 2 hard links)"))
+        .stdout(predicate::str::contains(
+            "Bytes: 6.0 KiB raw, 2.0 KiB unique",
+        ));
+}
+
+#[test]
+fn test_cli_format_json() {
+    let temp_dir = setup_test_dirs();
+    let dir_a = temp_dir.path().join("dir_a");
+    let dir_b = temp_dir.path().join("dir_b");
+    fs::create_dir_all(dir_a.join("only_a")).unwrap();
+
+    let output = cli_command()
+        .arg(dir_a.to_str().unwrap())
+        .arg(dir_b.to_str().unwrap())
+        .arg("--format")
+        .arg("json")
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(json["a_only"][0]["path"], "only_a");
+    assert_eq!(json["a_only"][0]["kind"], "directory");
+    assert_eq!(json["b_only"].as_array().unwrap().len(), 0);
+    assert_eq!(json["both"][0]["a"]["path"], "file1.txt");
+    assert_eq!(json["both"][0]["a"]["size"], 8);
+}
+
+#[test]
+fn test_cli_flat_format_json() {
+    let temp_dir = setup_test_dirs();
+
+    let output = cli_command()
+        .arg(temp_dir.path().join("dir_a").to_str().unwrap())
+        .arg(temp_dir.path().join("dir_b").to_str().unwrap())
+        .arg("--flat")
+        .arg("--format")
+        .arg("json")
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(json["total_files_a"], 1);
+    assert_eq!(json["groups"][0]["files_in_a"][0], "file1.txt");
+    assert_eq!(json["groups"][0]["files_in_b"][0], "file1.txt");
+}
+
+#[test]
+fn test_cli_flat_chunk_dedupe() {
+    let temp_dir = setup_test_dirs();
+    let report = |extra: &[&str]| {
+        let output = cli_command()
+            .arg(temp_dir.path().join("dir_a"))
+            .arg(temp_dir.path().join("dir_b"))
+            .args(["--flat", "--format", "json"])
+            .args(extra)
+            .output()
+            .unwrap();
+        assert!(output.status.success());
+        serde_json::from_slice::<serde_json::Value>(&output.stdout).unwrap()
+    };
+
+    assert!(report(&[]).get("chunk_dedupe").is_none());
+    let json = report(&[
+        "--chunk-dedupe",
+        "--avg-chunk-size",
+        "1024",
+        "--threads",
+        "2",
+    ]);
+    let chunk_dedupe = &json["chunk_dedupe"];
+    assert_eq!(chunk_dedupe["avg_chunk_size"], 1024);
+    assert_eq!(chunk_dedupe["top_files_a"][0]["path"], "file1.txt");
+    assert!(chunk_dedupe["shared_bytes"].as_u64().unwrap() > 0);
+}
+
+#[test]
+fn test_cli_flat_ext_and_min_size() {
+    let temp_dir = setup_test_dirs();
+    let dir_a = temp_dir.path().join("dir_a");
+    let dir_b = temp_dir.path().join("dir_b");
+    for dir in [&dir_a, &dir_b] {
+        fs::write(dir.join("photo.JPG"), "pixels").unwrap();
+        fs::write(dir.join("empty.jpg"), "").unwrap();
+    }
+
+    let output = cli_command()
+        .arg(&dir_a)
+        .arg(&dir_b)
+        .args(["--flat", "--format", "json"])
+        .args(["--flat-ext", "jpg,png", "--flat-min-size", "1"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(json["total_files_a"], 1);
+    assert_eq!(json["total_files_b"], 1);
+    assert_eq!(json["groups"].as_array().unwrap().len(), 1);
+    assert_eq!(json["groups"][0]["files_in_a"][0], "photo.JPG");
+
+    cli_command()
+        .arg(&dir_a)
+        .arg(&dir_b)
+        .args(["--flat-ext", "jpg"])
+        .assert()
+        .code(1)
+        .stderr(predicate::str::contains(
+            "--flat-ext only applies to flat mode",
+        ));
+}
+
+#[test]
+fn test_cli_flat_script() {
+    let temp_dir = setup_test_dirs();
+    let dir_a = temp_dir.path().join("dir_a");
+    let dir_b = temp_dir.path().join("dir_b");
+    fs::write(dir_a.join("old name.txt"), "moved").unwrap();
+    fs::create_dir(dir_b.join("new")).unwrap();
+    fs::write(dir_b.join("new/name.txt"), "moved").unwrap();
+
+    cli_command()
+        .arg(&dir_a)
+        .arg(&dir_b)
+        .args(["--flat", "--format", "script"])
+        .assert()
+        .success()
+        .stdout(predicate::str::starts_with("#!/bin/sh\n"))
+        .stdout(predicate::str::contains(
+            "mkdir -p -- 'new'\nmv -- 'old name.txt' 'new/name.txt'\n",
+        ));
+
+    cli_command()
+        .arg(&dir_a)
+        .arg(&dir_b)
+        .args([
+            "--flat",
+            "--format",
+            "script",
+            "--script-shell",
+            "powershell",
+        ])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Move-Item -LiteralPath 'old name.txt' -Destination 'new/name.txt'\n",
+        ));
+
+    cli_command()
+        .arg(&dir_a)
+        .arg(&dir_b)
+        .args(["--format", "script"])
+        .assert()
+        .code(1)
+        .stderr(predicate::str::contains(
+            "--format script only applies to flat mode",
+        ));
+}
+
+#[cfg(unix)]
+#[test]
+fn test_cli_sanitize_paths() {
+    let temp_dir = setup_test_dirs();
+    let dir_a = temp_dir.path().join("dir_a");
+    let dir_b = temp_dir.path().join("dir_b");
+    fs::write(dir_a.join("bell\x07.txt"), "a").unwrap();
+    fs::write(dir_b.join("日本.txt"), "b").unwrap();
+
+    cli_command()
+        .arg(&dir_a)
+        .arg(&dir_b)
+        .args(["--sanitize-paths", "ascii"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("  bell\\x07.txt\n"))
+        .stdout(predicate::str::contains("  \\u{65e5}\\u{672c}.txt\n"))
+        .stdout(predicate::str::contains("; paths sanitized for output: 2"));
+
+    cli_command()
+        .arg(&dir_a)
+        .arg(&dir_b)
+        .args(["--sanitize-paths", "control"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("  bell\u{2407}.txt\n"))
+        .stdout(predicate::str::contains("  日本.txt\n"));
+
+    let output = cli_command()
+        .arg(&dir_a)
+        .arg(&dir_b)
+        .args(["--sanitize-paths", "ascii", "--format", "json"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    assert!(output.stdout.is_ascii());
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(json["a_only"][0]["path"], "bell\x07.txt");
+    assert_eq!(json["b_only"][0]["path"], "日本.txt");
+}
+
+#[test]
+fn test_cli_sync() {
+    let temp_dir = setup_test_dirs();
+    let dir_a = temp_dir.path().join("dir_a");
+    let dir_b = temp_dir.path().join("dir_b");
+    fs::create_dir_all(dir_a.join("docs/2024")).unwrap();
+    fs::write(dir_a.join("docs/2024/report.txt"), "report").unwrap();
+    fs::create_dir_all(dir_b.join("old")).unwrap();
+    fs::write(dir_b.join("old/notes.txt"), "notes").unwrap();
+
+    cli_command()
+        .arg(&dir_a)
+        .arg(&dir_b)
+        .args(["--sync", "a-to-b", "--dry-run"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("docs/2024/report.txt"))
+        .stderr(predicate::str::contains(
+            "Would copy docs/2024/report.txt to B",
+        ))
+        .stderr(predicate::str::contains("Would delete old from B"))
+        .stderr(predicate::str::contains(
+            "Sync: 5 actions planned, nothing changed (dry run)",
+        ));
+    assert!(!dir_b.join("docs").exists());
+    assert!(dir_b.join("old/notes.txt").exists());
+
+    cli_command()
+        .arg(&dir_a)
+        .arg(&dir_b)
+        .args(["--sync", "a-to-b"])
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("Sync: 5 applied, 0 failed"));
+    assert_eq!(
+        fs::read_to_string(dir_b.join("docs/2024/report.txt")).unwrap(),
+        "report"
+    );
+    assert!(!dir_b.join("old").exists());
+
+    cli_command()
+        .arg(&dir_a)
+        .arg(&dir_b)
+        .arg("--exit-code")
+        .assert()
+        .code(0);
+}
+
+#[test]
+fn test_cli_color() {
+    let temp_dir = setup_test_dirs();
+    let dir_a = temp_dir.path().join("dir_a");
+    let dir_b = temp_dir.path().join("dir_b");
+    fs::write(dir_a.join("only_a.txt"), "a").unwrap();
+
+    cli_command()
+        .arg(&dir_a)
+        .arg(&dir_b)
+        .args(["--color", "always"])
+        .env("NO_COLOR", "1")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "\x1b[1mA-only (1 entries):\x1b[0m",
+        ))
+        .stdout(predicate::str::contains("  \x1b[31monly_a.txt\x1b[0m"));
+
+    for color in ["never", "auto"] {
+        cli_command()
+            .arg(&dir_a)
+            .arg(&dir_b)
+            .args(["--color", color])
+            .assert()
+            .success()
+            .stdout(predicate::str::contains("  only_a.txt\n"))
+            .stdout(predicate::str::contains("\x1b").not());
+    }
+
+    // CLICOLOR_FORCE colors piped output, but never a report file
+    cli_command()
+        .arg(&dir_a)
+        .arg(&dir_b)
+        .env("CLICOLOR_FORCE", "1")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("\x1b[31monly_a.txt"));
+    let report = temp_dir.path().join("report.txt");
+    cli_command()
+        .arg(&dir_a)
+        .arg(&dir_b)
+        .arg("--output")
+        .arg(&report)
+        .env("CLICOLOR_FORCE", "1")
+        .assert()
+        .success();
+    let written = fs::read_to_string(&report).unwrap();
+    assert!(written.contains("  only_a.txt\n"));
+    assert!(!written.contains('\x1b'));
+
+    cli_command()
+        .arg(&dir_a)
+        .arg(&dir_b)
+        .args(["--color", "always", "--output"])
+        .arg(&report)
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "--color always has no effect with --output",
+        ));
+}
+
+#[test]
+fn test_cli_write_and_verify_manifest() {
+    let temp_dir = setup_test_dirs();
+    let dir_a = temp_dir.path().join("dir_a");
+    let manifest = temp_dir.path().join("SHA256SUMS");
+    fs::write(dir_a.join("empty.txt"), "").unwrap();
+
+    cli_command()
+        .arg(&dir_a)
+        .arg("--write-manifest")
+        .arg(&manifest)
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("Manifest of 2 files written to"));
+    assert!(
+        fs::read_to_string(&manifest).unwrap().contains(
+            "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855  empty.txt\n"
+        )
+    );
+
+    cli_command()
+        .arg(&dir_a)
+        .arg("--verify-manifest")
+        .arg(&manifest)
+        .assert()
+        .code(0)
+        .stdout("2 matched, 0 modified, 0 missing, 0 extra\n");
+
+    fs::write(dir_a.join("file1.txt"), "changed").unwrap();
+    fs::write(dir_a.join("new.txt"), "new").unwrap();
+    let output = cli_command()
+        .arg(&dir_a)
+        .arg(format!("--verify-manifest={}", manifest.display()))
+        .args(["--format", "json"])
+        .assert()
+        .code(1)
+        .get_output()
+        .stdout
+        .clone();
+    let json: serde_json::Value = serde_json::from_slice(&output).unwrap();
+    assert_eq!(json["modified"][0], "file1.txt");
+    assert_eq!(json["extra"][0], "new.txt");
+
+    // A manifest inside the directory would list itself
+    cli_command()
+        .arg(&dir_a)
+        .arg("--write-manifest")
+        .arg(dir_a.join("SHA256SUMS"))
+        .assert()
+        .code(2)
+        .stderr(predicate::str::contains("would list itself"));
+
+    // The manifest flags take a single directory
+    cli_command()
+        .arg(&dir_a)
+        .arg(temp_dir.path().join("dir_b"))
+        .arg("--verify-manifest")
+        .arg(&manifest)
+        .assert()
+        .failure();
+
+    // Comparison options are rejected as with a comparison
+    cli_command()
+        .arg(&dir_a)
+        .arg("--verify-manifest")
+        .arg(&manifest)
+        .args(["--method", "hash"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("--method has no effect"));
+
+    // After `--`, the flag is a directory name
+    cli_command()
+        .current_dir(temp_dir.path())
+        .args(["dir_a", "--", "--write-manifest"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "Second directory does not exist: --write-manifest",
+        ));
+}
+
+#[test]
+fn test_cli_json_error_for_invalid_manifest() {
+    let temp_dir = setup_test_dirs();
+    let dir_a = temp_dir.path().join("dir_a");
+    let manifest = temp_dir.path().join("SHA256SUMS");
+    fs::write(&manifest, "not a manifest line\n").unwrap();
+
+    let output = cli_command()
+        .arg(&dir_a)
+        .arg("--verify-manifest")
+        .arg(&manifest)
+        .args(["--format", "json"])
+        .output()
+        .unwrap();
+
+    assert_eq!(output.status.code(), Some(2));
+    assert!(output.stdout.is_empty());
+    let json: serde_json::Value = serde_json::from_slice(&output.stderr).unwrap();
+    assert_eq!(json["error"]["kind"], "invalid_manifest");
+    assert_eq!(json["error"]["path"], manifest.to_str().unwrap());
+}
+
+#[test]
+fn test_cli_json_error_for_missing_directory() {
+    let temp_dir = setup_test_dirs();
+    let missing = temp_dir.path().join("missing");
+
+    let output = cli_command()
+        .arg(missing.to_str().unwrap())
+        .arg(temp_dir.path().join("dir_b").to_str().unwrap())
+        .arg("--format")
+        .arg("json")
+        .output()
+        .unwrap();
+
+    assert_eq!(output.status.code(), Some(1));
+    assert!(output.stdout.is_empty());
+    let json: serde_json::Value = serde_json::from_slice(&output.stderr).unwrap();
+    assert_eq!(json["error"]["kind"], "directory_not_found");
+    assert_eq!(json["error"]["path"], missing.to_str().unwrap());
+    assert!(
+        json["error"]["message"]
+            .as_str()
+            .unwrap()
+            .contains("First directory does not exist")
+    );
+}
+
+#[test]
+fn test_cli_json_error_for_invalid_flags() {
+    let temp_dir = setup_test_dirs();
+
+    let output = cli_command()
+        .arg(temp_dir.path().join("dir_a").to_str().unwrap())
+        .arg(temp_dir.path().join("dir_b").to_str().unwrap())
+        .arg("--format")
+        .arg("json")
+        .arg("--full-hash")
+        .output()
+        .unwrap();
+
+    assert_eq!(output.status.code(), Some(1));
+    let json: serde_json::Value = serde_json::from_slice(&output.stderr).unwrap();
+    assert_eq!(json["error"]["kind"], "invalid_flags");
+    assert!(json["error"]["path"].is_null());
+}
+
+#[test]
+fn test_cli_json_error_for_invalid_ignore_file() {
+    let temp_dir = setup_test_dirs();
+    let ignore_file = temp_dir.path().join(".dcignore");
+    fs::write(&ignore_file, "src/{a,b\n").unwrap();
+
+    let output = cli_command()
+        .arg(temp_dir.path().join("dir_a").to_str().unwrap())
+        .arg(temp_dir.path().join("dir_b").to_str().unwrap())
+        .args(["--format", "json", "--ignore"])
+        .arg(&ignore_file)
+        .output()
+        .unwrap();
+
+    assert_eq!(output.status.code(), Some(1));
+    let json: serde_json::Value = serde_json::from_slice(&output.stderr).unwrap();
+    assert_eq!(json["error"]["kind"], "invalid_ignore_file");
+    assert_eq!(json["error"]["path"], ignore_file.to_str().unwrap());
+}
+
+#[test]
+fn test_cli_text_error_for_missing_directory() {
+    let mut cmd = cli_command();
+    cmd.arg("/nonexistent")
+        .arg("/tmp")
+        .arg("--format")
+        .arg("text")
+        .assert()
+        .failure()
+        .code(1)
+        .stderr(predicate::str::contains(
+            "First directory does not exist: /nonexistent",
+        ))
+        .stderr(predicate::str::contains("{\"error\"").not());
+}
+
+#[test]
+fn test_cli_format_csv() {
+    let temp_dir = setup_test_dirs();
+    let dir_a = temp_dir.path().join("dir_a");
+    fs::write(dir_a.join("notes, draft.txt"), b"x").unwrap();
+
+    let mut cmd = cli_command();
+    cmd.arg(dir_a.to_str().unwrap())
+        .arg(temp_dir.path().join("dir_b").to_str().unwrap())
+        .arg("--format")
+        .arg("csv")
+        .assert()
+        .success()
+        .stdout(predicate::str::starts_with(
+            "category,path,kind,size,reason\n",
+        ))
+        .stdout(predicate::str::contains(
+            "a_only,\"notes, draft.txt\",file,1,\n",
+        ))
+        .stdout(predicate::str::contains("both,file1.txt,file,8,\n"));
+}
+
+#[test]
+fn test_cli_flat_format_csv() {
+    let temp_dir = setup_test_dirs();
+
+    let mut cmd = cli_command();
+    cmd.arg(temp_dir.path().join("dir_a").to_str().unwrap())
+        .arg(temp_dir.path().join("dir_b").to_str().unwrap())
+        .arg("--flat")
+        .arg("--format")
+        .arg("csv")
+        .assert()
+        .success()
+        .stdout(predicate::str::starts_with("hash,size,side,path,status\n"))
+        .stdout(predicate::str::contains(",8,a,file1.txt,duplicate\n"))
+        .stdout(predicate::str::contains(",8,b,file1.txt,duplicate\n"));
+}
+
+#[test]
+fn test_cli_no_canonicalize() {
+    let temp_dir = setup_test_dirs();
+    let dir_a = temp_dir.path().join("dir_b/../dir_a");
+    fs::write(temp_dir.path().join("dir_a/extra.txt"), b"x").unwrap();
+
+    let mut cmd = cli_command();
+    cmd.arg(dir_a.to_str().unwrap())
+        .arg(temp_dir.path().join("dir_b").to_str().unwrap())
+        .arg("--no-canonicalize")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("A-only (1 entries):"))
+        .stdout(predicate::str::contains("  extra.txt\n"))
+        .stdout(predicate::str::contains("file1.txt == file1.txt"));
+}
+
+#[test]
+fn test_cli_append_history_and_show() {
+    let temp_dir = setup_test_dirs();
+    let history = temp_dir.path().join("history.csv");
+
+    for _ in 0..2 {
+        cli_command()
+            .arg(temp_dir.path().join("dir_a").to_str().unwrap())
+            .arg(temp_dir.path().join("dir_b").to_str().unwrap())
+            .arg("--append-history")
+            .arg(history.to_str().unwrap())
+            .assert()
+            .success();
+    }
+
+    let content = fs::read_to_string(&history).unwrap();
+    assert_eq!(content.lines().count(), 3);
+    assert_eq!(content.matches("version,timestamp").count(), 1);
+    assert!(content.lines().nth(1).unwrap().starts_with("2,"));
+    assert!(content.contains(",hierarchy,"));
+
+    cli_command()
+        .arg("history")
+        .arg(history.to_str().unwrap())
+        .arg("--last")
+        .arg("5")
+        .assert()
+        .success()
+        .stdout(predicate::str::starts_with("Timestamp"))
+        .stdout(predicate::str::contains("100.00%"))
+        .stdout(predicate::str::contains("change"));
+}
+
+#[test]
+fn test_cli_history_missing_file() {
+    cli_command()
+        .arg("history")
+        .arg("/nonexistent/history.csv")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Error reading history file"));
+}
+
+/// Sets up `dir_b` next to a copy of `dir_a` named like the subcommand `name`.
+fn setup_dir_named(name: &str) -> tempfile::TempDir {
+    let temp_dir = setup_test_dirs();
+    fs::rename(temp_dir.path().join("dir_a"), temp_dir.path().join(name)).unwrap();
+    temp_dir
+}
+
+#[test]
+fn test_cli_compare_directory_named_history() {
+    let temp_dir = setup_dir_named("history");
+
+    cli_command()
+        .current_dir(temp_dir.path())
+        .args(["./history", "dir_b"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("file1.txt"));
+
+    cli_command()
+        .arg(temp_dir.path().join("history"))
+        .arg(temp_dir.path().join("dir_b"))
+        .assert()
+        .success();
+
+    // Comparison options cannot be mixed with the subcommand
+    cli_command()
+        .current_dir(temp_dir.path())
+        .args(["history", "dir_b", "--method", "hash"])
+        .assert()
+        .failure();
+}
+
+#[test]
+fn test_cli_threads() {
+    let temp_dir = setup_test_dirs();
+
+    let mut cmd = cli_command();
+    cmd.arg(temp_dir.path().join("dir_a").to_str().unwrap())
+        .arg(temp_dir.path().join("dir_b").to_str().unwrap())
+        .arg("--method")
+        .arg("hash")
+        .arg("--threads")
+        .arg("2")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("file1.txt == file1.txt"));
+}
+
+#[test]
+fn test_cli_progress() {
+    let temp_dir = setup_test_dirs();
+
+    let mut cmd = cli_command();
+    cmd.arg(temp_dir.path().join("dir_a").to_str().unwrap())
+        .arg(temp_dir.path().join("dir_b").to_str().unwrap())
+        .arg("--progress")
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("Comparing: 1/1 files"))
+        .stdout(predicate::str::contains("Comparing:").not());
+
+    let mut cmd = cli_command();
+    cmd.arg(temp_dir.path().join("dir_a").to_str().unwrap())
+        .arg(temp_dir.path().join("dir_b").to_str().unwrap())
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("Comparing:").not());
+}
+
+#[test]
+fn test_cli_report_header() {
+    let temp_dir = setup_test_dirs();
+    cli_command()
+        .current_dir(temp_dir.path())
+        .args(["dir_a", "dir_b", "--format", "html", "--case-insensitive"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(format!(
+            "<dt>Directory A</dt><dd>{}</dd>",
+            temp_dir.path().join("dir_a").display()
+        )))
+        .stdout(predicate::str::contains(
+            "<dt>Method</dt><dd>sampled (case-insensitive)</dd>",
+        ))
+        .stdout(predicate::str::contains(
+            "<input type=\"search\" id=\"filter\"",
+        ));
+
+    cli_command()
+        .current_dir(temp_dir.path())
+        .args([
+            "dir_a", "dir_b", "--method", "mtime", "--format", "markdown",
+        ])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "- **Method:** mtime (tolerance 1s)\n",
+        ));
+
+    // JSON has no header
+    cli_command()
+        .current_dir(temp_dir.path())
+        .args(["dir_a", "dir_b", "--format", "json"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Directory A").not());
+}
+
+#[test]
+fn test_cli_sections() {
+    let temp_dir = setup_test_dirs();
+
+    let mut cmd = cli_command();
+    cmd.arg(temp_dir.path().join("dir_a").to_str().unwrap())
+        .arg(temp_dir.path().join("dir_b").to_str().unwrap())
+        .arg("--sections")
+        .arg("both,a-only")
+        .assert()
+        .success()
+        // The first section follows the four lines of the header
+        .stdout(predicate::str::is_match(r"^Directory A: (?:[^\n]+\n){4}\nBoth \(").unwrap())
+        .stdout(predicate::str::contains("A-only ("))
+        .stdout(predicate::str::contains("B-only (").not());
+
+    let mut cmd = cli_command();
+    cmd.arg(temp_dir.path().join("dir_a").to_str().unwrap())
+        .arg(temp_dir.path().join("dir_b").to_str().unwrap())
+        .arg("--sections")
+        .arg("a-only,unchanged")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("unknown section 'unchanged'"));
+}
+
+#[test]
+fn test_cli_rollup() {
+    let temp_dir = setup_test_dirs();
+    for dir in ["dir_a", "dir_b"] {
+        let sub = temp_dir.path().join(dir).join("sub");
+        fs::create_dir_all(&sub).unwrap();
+        fs::write(sub.join("one.txt"), b"one").unwrap();
+        fs::write(sub.join("two.txt"), b"two").unwrap();
+    }
+
+    let mut cmd = cli_command();
+    cmd.arg(temp_dir.path().join("dir_a").to_str().unwrap())
+        .arg(temp_dir.path().join("dir_b").to_str().unwrap())
+        .arg("--rollup")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("sub/ (identical, 2 files)"))
+        .stdout(predicate::str::contains("one.txt").not())
+        .stdout(predicate::str::contains("Both (4 entries)"));
+
+    let mut cmd = cli_command();
+    cmd.arg(temp_dir.path().join("dir_a").to_str().unwrap())
+        .arg(temp_dir.path().join("dir_b").to_str().unwrap())
+        .args(["--rollup", "--format", "csv"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "--rollup cannot be combined with --format csv",
+        ));
+}
+
+#[test]
+fn test_cli_detect_renames() {
+    let temp_dir = setup_test_dirs();
+    fs::write(temp_dir.path().join("dir_a/old.txt"), b"moved content").unwrap();
+    fs::create_dir_all(temp_dir.path().join("dir_b/archive")).unwrap();
+    fs::write(
+        temp_dir.path().join("dir_b/archive/new.txt"),
+        b"moved content",
+    )
+    .unwrap();
+
+    let mut cmd = cli_command();
+    cmd.arg(temp_dir.path().join("dir_a").to_str().unwrap())
+        .arg(temp_dir.path().join("dir_b").to_str().unwrap())
+        .arg("--detect-renames")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Renamed/Moved (1 entries)"))
+        .stdout(predicate::str::contains("  old.txt -> archive/new.txt\n"));
+
+    let mut cmd = cli_command();
+    cmd.arg(temp_dir.path().join("dir_a").to_str().unwrap())
+        .arg(temp_dir.path().join("dir_b").to_str().unwrap())
+        .args(["--detect-renames", "--porcelain"])
+        .assert()
+        .code(1)
+        .stdout("B archive\nR old.txt -> archive/new.txt\n");
+}
+
+#[test]
+fn test_cli_summary_lists_no_paths() {
+    let temp_dir = setup_test_dirs();
+    fs::write(temp_dir.path().join("dir_a/only_a.txt"), b"a").unwrap();
+    fs::write(temp_dir.path().join("dir_b/only_b.txt"), b"b").unwrap();
+
+    for format in ["text", "markdown", "html", "json"] {
+        let output = cli_command()
+            .arg(temp_dir.path().join("dir_a").to_str().unwrap())
+            .arg(temp_dir.path().join("dir_b").to_str().unwrap())
+            .args(["--summary", "--format", format])
+            .output()
+            .unwrap();
+        assert!(output.status.success(), "{}", format);
+        let stdout = String::from_utf8(output.stdout).unwrap();
+        for path in ["file1.txt", "only_a.txt", "only_b.txt"] {
+            assert!(!stdout.contains(path), "{}: {}", format, stdout);
+        }
+        match format {
+            "text" => assert!(stdout.contains("A-only: 1 entries\n"), "{}", stdout),
+            "markdown" => assert!(stdout.contains("| A-only | 1 |\n"), "{}", stdout),
+            "html" => assert!(stdout.trim_end().ends_with("</html>"), "{}", stdout),
+            _ => {
+                let json: serde_json::Value = serde_json::from_str(&stdout).unwrap();
+                assert_eq!(json["counts"]["b_only"], 1);
+                assert_eq!(json["b_only"], serde_json::json!([]));
+            }
+        }
+    }
+}
+
+#[test]
+fn test_cli_quiet_prints_nothing() {
+    let temp_dir = setup_test_dirs();
+
+    let mut cmd = cli_command();
+    cmd.arg(temp_dir.path().join("dir_a").to_str().unwrap())
+        .arg(temp_dir.path().join("dir_b").to_str().unwrap())
+        .arg("--quiet")
+        .assert()
+        .success()
+        .stdout("");
+
+    fs::write(temp_dir.path().join("dir_b/extra.txt"), b"extra").unwrap();
+    let mut cmd = cli_command();
+    cmd.arg(temp_dir.path().join("dir_a").to_str().unwrap())
+        .arg(temp_dir.path().join("dir_b").to_str().unwrap())
+        .args(["-q", "--format", "json"])
+        .assert()
+        .code(1)
+        .stdout("");
+
+    let mut cmd = cli_command();
+    cmd.arg(temp_dir.path().join("dir_a").to_str().unwrap())
+        .arg(temp_dir.path().join("dir_b").to_str().unwrap())
+        .args(["--quiet", "--output", "report.txt"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("cannot be used with"));
+}
+
+#[test]
+fn test_cli_missing_ignore_file() {
+    let temp_dir = setup_test_dirs();
+
+    let mut cmd = cli_command();
+    cmd.arg(temp_dir.path().join("dir_a").to_str().unwrap())
+        .arg(temp_dir.path().join("dir_b").to_str().unwrap())
+        .arg("--ignore")
+        .arg(temp_dir.path().join("missing.ignore").to_str().unwrap())
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Ignore file does not exist"));
+
+    let mut cmd = cli_command();
+    cmd.arg(temp_dir.path().join("dir_a").to_str().unwrap())
+        .arg(temp_dir.path().join("dir_b").to_str().unwrap())
+        .arg("--ignore")
+        .arg("missing.ignore")
+        .arg("--format")
+        .arg("json")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "\"kind\": \"ignore_file_not_found\"",
+        ));
+}
+
+#[test]
+fn test_cli_exit_code() {
+    let temp_dir = setup_test_dirs();
+    let dir_a = temp_dir.path().join("dir_a");
+    let dir_b = temp_dir.path().join("dir_b");
+
+    // Identical directories
+    for mode in [None, Some("--flat")] {
+        let mut cmd = cli_command();
+        cmd.arg(&dir_a).arg(&dir_b).arg("--exit-code");
+        if let Some(mode) = mode {
+            cmd.arg(mode);
+        }
+        cmd.assert().code(0);
+    }
+
+    // A moved file: A-only/B-only in hierarchy mode, a differing path set in flat mode
+    fs::create_dir_all(dir_b.join("moved")).unwrap();
+    fs::rename(dir_b.join("file1.txt"), dir_b.join("moved/file1.txt")).unwrap();
+    for mode in [None, Some("--flat")] {
+        let mut cmd = cli_command();
+        cmd.arg(&dir_a).arg(&dir_b).arg("--exit-code");
+        if let Some(mode) = mode {
+            cmd.arg(mode);
+        }
+        cmd.assert()
+            .code(1)
+            .stdout(predicate::str::is_empty().not());
+    }
+
+    // Without --exit-code differences still exit 0
+    cli_command().arg(&dir_a).arg(&dir_b).assert().code(0);
+
+    // Errors
+    cli_command()
+        .arg("/nonexistent")
+        .arg(&dir_b)
+        .arg("--exit-code")
+        .assert()
+        .code(2)
+        .stderr(predicate::str::contains("does not exist"));
+    cli_command()
+        .arg("/nonexistent")
+        .arg(&dir_b)
+        .arg("--exit-code")
+        .arg("--flat")
+        .assert()
+        .code(2);
+    cli_command()
+        .arg("/nonexistent")
+        .arg(&dir_b)
+        .assert()
+        .code(1);
+}
+
+#[test]
+fn test_cli_method_mtime() {
+    let temp_dir = setup_test_dirs();
+    let dir_a = temp_dir.path().join("dir_a");
+    let dir_b = temp_dir.path().join("dir_b");
+    let hour_ago = std::time::SystemTime::now() - std::time::Duration::from_secs(3600);
+    File::options()
+        .write(true)
+        .open(dir_b.join("file1.txt"))
+        .unwrap()
+        .set_modified(hour_ago)
+        .unwrap();
+
+    cli_command()
+        .arg(&dir_a)
+        .arg(&dir_b)
+        .args(["--method", "mtime", "--exit-code"])
+        .assert()
+        .code(1)
+        .stdout(predicate::str::contains("Modified (1 entries)"));
+
+    cli_command()
+        .arg(&dir_a)
+        .arg(&dir_b)
+        .args([
+            "--method",
+            "mtime",
+            "--mtime-tolerance",
+            "7200",
+            "--exit-code",
+        ])
+        .assert()
+        .code(0);
+}
+
+#[cfg(feature = "schema")]
+#[test]
+fn test_cli_schema() {
+    for (args, title) in [
+        (&["schema"][..], "dir-compare JSON report"),
+        (&["schema", "--flat"][..], "dir-compare flat JSON report"),
+    ] {
+        let output = cli_command().args(args).output().unwrap();
+        assert!(output.status.success());
+        let schema: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+        assert_eq!(schema["title"], title);
+        assert_eq!(schema["properties"]["format_version"]["const"], 10);
+    }
+}
+
+#[cfg(not(feature = "schema"))]
+#[test]
+fn test_cli_schema_without_feature() {
+    cli_command()
+        .arg("schema")
+        .assert()
+        .code(1)
+        .stdout(predicate::str::is_empty())
+        .stderr(predicate::str::contains("--features schema"));
+}
+
+#[test]
+fn test_cli_compare_directory_named_schema() {
+    let temp_dir = setup_dir_named("schema");
+
+    cli_command()
+        .current_dir(temp_dir.path())
+        .args(["./schema", "dir_b"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("file1.txt"));
+
+    // Comparison options cannot be mixed with the subcommand
+    cli_command()
+        .current_dir(temp_dir.path())
+        .args(["schema", "--method", "hash"])
+        .assert()
+        .failure();
+}
+
+/// Creates dir_a/README.txt and dir_b/readme.txt
+fn setup_case_variant_dirs() -> tempfile::TempDir {
+    let temp_dir = tempfile::tempdir().unwrap();
+    fs::create_dir(temp_dir.path().join("dir_a")).unwrap();
+    fs::create_dir(temp_dir.path().join("dir_b")).unwrap();
+    fs::write(temp_dir.path().join("dir_a/README.txt"), "readme").unwrap();
+    fs::write(temp_dir.path().join("dir_b/readme.txt"), "readme").unwrap();
+    temp_dir
+}
+
+#[test]
+fn test_cli_case_flags_override_detection() {
+    let temp_dir = setup_case_variant_dirs();
+    let dir_a = temp_dir.path().join("dir_a");
+    let dir_b = temp_dir.path().join("dir_b");
+
+    cli_command()
+        .arg(&dir_a)
+        .arg(&dir_b)
+        .args(["--case-sensitive", "--exit-code"])
+        .assert()
+        .code(1)
+        .stdout(predicate::str::contains("A-only (1 entries):"));
+
+    cli_command()
+        .arg(&dir_a)
+        .arg(&dir_b)
+        .args(["--case-insensitive", "--exit-code"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Both (1 entries):"));
+
+    // Without either flag both roots are probed; the probe leaves nothing behind
+    cli_command().arg(&dir_a).arg(&dir_b).assert().success();
+    assert_eq!(fs::read_dir(&dir_a).unwrap().count(), 1);
+    assert_eq!(fs::read_dir(&dir_b).unwrap().count(), 1);
+}
+
+#[cfg(any(windows, target_os = "macos"))]
+#[test]
+fn test_cli_case_insensitive_filesystems_match_case_variants_by_default() {
+    let temp_dir = setup_case_variant_dirs();
+
+    cli_command()
+        .arg(temp_dir.path().join("dir_a"))
+        .arg(temp_dir.path().join("dir_b"))
+        .arg("--exit-code")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Both (1 entries):"))
+        .stderr(predicate::str::contains("case-insensitive filesystems"));
+
+    assert_eq!(
+        fs::read_dir(temp_dir.path().join("dir_a")).unwrap().count(),
+        1
+    );
+}
+
+#[test]
+fn test_cli_include_and_exclude() {
+    let temp_dir = setup_test_dirs();
+    let dir_a = temp_dir.path().join("dir_a");
+    let dir_b = temp_dir.path().join("dir_b");
+    for dir in [&dir_a, &dir_b] {
+        fs::create_dir_all(dir.join("logs/old")).unwrap();
+        fs::write(dir.join("logs/old/app.log"), "old").unwrap();
+        fs::write(dir.join("notes.md"), "notes").unwrap();
+    }
+    fs::write(dir_a.join("logs/today.log"), "a").unwrap();
+    fs::write(dir_b.join("scratch.tmp"), "b").unwrap();
+    let ignore_file = temp_dir.path().join(".dcignore");
+    fs::write(&ignore_file, "*.tmp\n").unwrap();
+
+    cli_command()
+        .arg(&dir_a)
+        .arg(&dir_b)
+        .args(["--include", "**/*.log", "--include", "*.tmp"])
+        .args(["--exclude", "old/", "--format", "json", "--ignore"])
+        .arg(&ignore_file)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(r#""path": "logs/today.log""#))
+        .stdout(predicate::str::contains("file1.txt").not())
+        .stdout(predicate::str::contains("old").not())
+        .stdout(predicate::str::contains("scratch.tmp").not());
+}
+
+#[test]
+fn test_cli_vcs_aware_marks_nested_repository() {
+    let temp_dir = setup_test_dirs();
+    let dir_a = temp_dir.path().join("dir_a");
+    let dir_b = temp_dir.path().join("dir_b");
+    for dir in [&dir_a, &dir_b] {
+        fs::create_dir_all(dir.join("vendor/lib/.git")).unwrap();
+        fs::write(dir.join("vendor/lib/.git/HEAD"), "ref").unwrap();
+    }
+
+    cli_command()
+        .arg(&dir_a)
+        .arg(&dir_b)
+        .arg("--vcs-aware")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "vendor/lib == vendor/lib [nested repository]",
+        ))
+        .stdout(predicate::str::contains(".git").not());
+
+    cli_command()
+        .arg(&dir_a)
+        .arg(&dir_b)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("nested repository").not());
+}
+
+#[test]
+fn test_cli_respect_gitignore() {
+    let temp_dir = setup_test_dirs();
+    let dir_a = temp_dir.path().join("dir_a");
+    let dir_b = temp_dir.path().join("dir_b");
+    fs::write(dir_a.join(".gitignore"), "*.log\n!keep.log\n").unwrap();
+    fs::write(dir_a.join("debug.log"), "noise").unwrap();
+    fs::write(dir_a.join("keep.log"), "kept").unwrap();
+
+    for flat in [false, true] {
+        let mut command = cli_command();
+        command.arg(&dir_a).arg(&dir_b).arg("--respect-gitignore");
+        if flat {
+            command.arg("--flat");
+        }
+        command
+            .assert()
+            .stdout(predicate::str::contains("keep.log"))
+            .stdout(predicate::str::contains("debug.log").not());
+    }
+
+    // Off by default
+    cli_command()
+        .arg(&dir_a)
+        .arg(&dir_b)
+        .assert()
+        .stdout(predicate::str::contains("debug.log"));
+}
+
+#[test]
+fn test_cli_ignore_hidden() {
+    let temp_dir = setup_test_dirs();
+    let dir_a = temp_dir.path().join("dir_a");
+    let dir_b = temp_dir.path().join("dir_b");
+    fs::create_dir_all(dir_a.join(".cache")).unwrap();
+    fs::write(dir_a.join(".cache/thumbs.db"), "cache").unwrap();
+    fs::write(dir_b.join(".bashrc"), "alias ll='ls -l'").unwrap();
+
+    for flat in [false, true] {
+        let mut command = cli_command();
+        command.arg(&dir_a).arg(&dir_b).arg("--ignore-hidden");
+        if flat {
+            command.arg("--flat");
+        }
+        command
+            .assert()
+            .stdout(predicate::str::contains("thumbs.db").not())
+            .stdout(predicate::str::contains(".bashrc").not());
+    }
+
+    // Listed by default
+    cli_command()
+        .arg(&dir_a)
+        .arg(&dir_b)
+        .assert()
+        .stdout(predicate::str::contains(".cache/thumbs.db"))
+        .stdout(predicate::str::contains(".bashrc"));
+}
+
+#[test]
+fn test_cli_lang_translates_report_headings() {
+    let temp_dir = setup_test_dirs();
+    let dir_a = temp_dir.path().join("dir_a");
+    let dir_b = temp_dir.path().join("dir_b");
+
+    cli_command()
+        .arg(&dir_a)
+        .arg(&dir_b)
+        .args(["--lang", "de"])
+        .assert()
+        .stdout(predicate::str::contains("Nur in A ("))
+        .stdout(predicate::str::contains("A-only").not());
+    cli_command()
+        .arg(&dir_a)
+        .arg(&dir_b)
+        .args(["--format", "markdown"])
+        .env("DIR_COMPARE_LANG", "de")
+        .assert()
+        .stdout(predicate::str::contains("# Verzeichnisvergleich"));
+    cli_command()
+        .arg(&dir_a)
+        .arg(&dir_b)
+        .args(["--flat", "--format", "html", "--lang", "ja"])
+        .assert()
+        .stdout(predicate::str::contains("<html lang=\"ja\">"));
+
+    // Unknown languages fall back to English
+    cli_command()
+        .arg(&dir_a)
+        .arg(&dir_b)
+        .args(["--lang", "xx"])
+        .assert()
+        .stdout(predicate::str::contains("A-only ("))
+        .stderr(predicate::str::contains("Unknown report language 'xx'"));
+
+    // JSON is the same in every language
+    let json = |lang: &str| {
+        let output = cli_command()
+            .arg(&dir_a)
+            .arg(&dir_b)
+            .args(["--format", "json", "--lang", lang])
+            .output()
+            .unwrap();
+        // All but the timing, which differs between runs
+        String::from_utf8(output.stdout)
+            .unwrap()
+            .lines()
+            .filter(|line| !line.contains("\"elapsed_ms\""))
+            .collect::<Vec<_>>()
+            .join("\n")
+    };
+    assert_eq!(json("de"), json("en"));
+}
+
+#[test]
+fn test_cli_estimate() {
+    let temp_dir = setup_test_dirs();
+    let dir_a = temp_dir.path().join("dir_a");
+    let dir_b = temp_dir.path().join("dir_b");
+    fs::write(dir_a.join("data.bin"), vec![0u8; 1000]).unwrap();
+    fs::write(dir_b.join("data.bin"), vec![1u8; 1000]).unwrap();
+
+    // Stops after the estimate, even though the directories differ
+    let output = cli_command()
+        .arg(&dir_a)
+        .arg(&dir_b)
+        .args(["--method", "hash", "--estimate-only", "--exit-code"])
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(0));
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(
+        stdout.starts_with("Estimate: 2 pairs, 2 compared by content, about "),
+        "{}",
+        stdout
+    );
+    assert!(!stdout.contains("A-only"));
+
+    cli_command()
+        .arg(&dir_a)
+        .arg(&dir_b)
+        .args(["--method", "filename", "--estimate-only"])
+        .assert()
+        .success()
+        .stdout("Estimate: 2 pairs, 0 compared by content, about 0 B to read\n");
+
+    // Logged before the report
+    cli_command()
+        .arg(&dir_a)
+        .arg(&dir_b)
+        .args(["--method", "hash", "--estimate"])
+        .assert()
+        .stderr(predicate::str::contains("Estimate: 2 pairs"))
+        .stdout(predicate::str::contains("Modified (1 entries)"));
+}
+
+#[test]
+fn test_cli_max_depth() {
+    let temp_dir = setup_test_dirs();
+    let dir_a = temp_dir.path().join("dir_a");
+    let dir_b = temp_dir.path().join("dir_b");
+    fs::create_dir_all(dir_a.join("top/nested")).unwrap();
+    fs::create_dir_all(dir_b.join("top")).unwrap();
+    fs::write(dir_a.join("top/nested/deep.txt"), "deep").unwrap();
+
+    for flat in [false, true] {
+        let mut command = cli_command();
+        command.arg(&dir_a).arg(&dir_b).args(["--max-depth", "1"]);
+        if flat {
+            command.arg("--flat");
+        }
+        command
+            .assert()
+            .stdout(predicate::str::contains("deep.txt").not());
+    }
+
+    cli_command()
+        .arg(&dir_a)
+        .arg(&dir_b)
+        .args(["--max-depth", "2"])
+        .assert()
+        .stdout(predicate::str::contains("top/nested"))
+        .stdout(predicate::str::contains("deep.txt").not());
+
+    cli_command()
+        .arg(&dir_a)
+        .arg(&dir_b)
+        .args(["--max-depth", "0"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("--max-depth"));
+}
+
+#[test]
+fn test_cli_invalid_glob() {
+    let temp_dir = setup_test_dirs();
+
+    cli_command()
+        .arg(temp_dir.path().join("dir_a"))
+        .arg(temp_dir.path().join("dir_b"))
+        .args(["--exclude", "{a,b"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Invalid glob '{a,b'"));
+}
+
+#[test]
+fn test_cli_result_detail_sampled() {
+    let temp_dir = setup_test_dirs();
+    let dir_a = temp_dir.path().join("dir_a");
+    for name in ["a1.txt", "a2.txt", "a3.txt"] {
+        fs::write(dir_a.join(name), name).unwrap();
+    }
+
+    cli_command()
+        .arg(&dir_a)
+        .arg(temp_dir.path().join("dir_b"))
+        .args(["--result-detail", "sampled:1", "--exit-code"])
+        .assert()
+        .code(1)
+        .stdout(predicate::str::contains("A-only (3 entries):"))
+        .stdout(predicate::str::contains("  a1.txt\n  … plus 2 more\n"))
+        .stdout(predicate::str::contains("a2.txt").not());
+
+    cli_command()
+        .arg(&dir_a)
+        .arg(temp_dir.path().join("dir_b"))
+        .args(["--result-detail", "counts", "--format", "json"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            r#""counts": {"a_only": 3, "b_only": 0, "both": 1, "modified": 0, "renamed": 0}"#,
+        ))
+        .stdout(predicate::str::contains(r#""a_only": []"#));
+}
+
+#[test]
+fn test_cli_porcelain() {
+    let temp_dir = setup_test_dirs();
+    let dir_a = temp_dir.path().join("dir_a");
+    let dir_b = temp_dir.path().join("dir_b");
+
+    cli_command()
+        .arg(&dir_a)
+        .arg(&dir_b)
+        .arg("--porcelain")
+        .assert()
+        .code(0)
+        .stdout("");
+
+    fs::write(dir_a.join("only a.txt"), b"a").unwrap();
+    fs::write(dir_b.join("line\nbreak.txt"), b"b").unwrap();
+    fs::write(dir_b.join("café.txt"), b"b").unwrap();
+    fs::write(dir_b.join("file1.txt"), b"changed").unwrap();
+
+    let output = cli_command()
+        .arg(&dir_a)
+        .arg(&dir_b)
+        .arg("--porcelain")
+        .arg("--method")
+        .arg("hash")
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(
+        output.stdout,
+        b"B \"caf\\303\\251.txt\"\nM file1.txt\nB \"line\\nbreak.txt\"\nA \"only a.txt\"\n"
+    );
+
+    let output = cli_command()
+        .arg(&dir_a)
+        .arg(&dir_b)
+        .arg("--porcelain")
+        .arg("-z")
+        .arg("--method")
+        .arg("hash")
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(
+        output.stdout,
+        "B café.txt\0M file1.txt\0B line\nbreak.txt\0A only a.txt\0".as_bytes()
+    );
+
+    cli_command()
+        .arg("/nonexistent")
+        .arg(&dir_b)
+        .arg("--porcelain")
+        .assert()
+        .code(2)
+        .stdout("");
+}
+
+#[test]
+fn test_cli_report_digest_sidecar() {
+    let temp_dir = setup_test_dirs();
+    let report = temp_dir.path().join("report.html");
+
+    cli_command()
+        .arg(temp_dir.path().join("dir_a"))
+        .arg(temp_dir.path().join("dir_b"))
+        .arg("--format")
+        .arg("html")
+        .arg("--output")
+        .arg(&report)
+        .arg("--report-digest")
+        .assert()
+        .success();
+
+    let expected = compute_full_hash(&report, HashAlgorithm::Sha256).unwrap();
+    let sidecar = fs::read_to_string(temp_dir.path().join("report.html.sha256")).unwrap();
+    assert_eq!(sidecar, format!("{}  report.html\n", expected));
+    let leftovers: Vec<_> = fs::read_dir(temp_dir.path())
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name())
+        .filter(|name| name.to_string_lossy().ends_with(".tmp"))
+        .collect();
+    assert!(leftovers.is_empty(), "{:?}", leftovers);
+}
+
+#[cfg(unix)]
+#[test]
+fn test_cli_sign_cmd_substitutes_path_with_spaces() {
+    let temp_dir = setup_test_dirs();
+    let out_dir = temp_dir.path().join("my reports");
+    fs::create_dir_all(&out_dir).unwrap();
+    let report = out_dir.join("report.txt");
+
+    cli_command()
+        .arg(temp_dir.path().join("dir_a"))
+        .arg(temp_dir.path().join("dir_b"))
+        .arg("--output")
+        .arg(&report)
+        .arg("--sign-cmd")
+        .arg("cp {file} {file}.sig")
+        .assert()
+        .success();
+
+    assert_eq!(
+        fs::read(out_dir.join("report.txt.sig")).unwrap(),
+        fs::read(&report).unwrap()
+    );
+}
+
+#[cfg(unix)]
+#[test]
+fn test_cli_failing_sign_cmd() {
+    let temp_dir = setup_test_dirs();
+    let report = temp_dir.path().join("report.json");
+
+    cli_command()
+        .arg(temp_dir.path().join("dir_a"))
+        .arg(temp_dir.path().join("dir_b"))
+        .arg("--format")
+        .arg("json")
+        .arg("--output")
+        .arg(&report)
+        .arg("--report-digest")
+        .arg("--sign-cmd")
+        .arg("false {file}")
+        .assert()
+        .code(1)
+        .stderr(predicate::str::contains("\"kind\": \"sign_failed\""))
+        .stderr(predicate::str::contains("Signing command `false` failed"));
+
+    assert!(report.exists());
+    assert!(temp_dir.path().join("report.json.sha256").exists());
+
+    cli_command()
+        .arg(temp_dir.path().join("dir_a"))
+        .arg(temp_dir.path().join("dir_b"))
+        .arg("--output")
+        .arg(&report)
+        .arg("--sign-cmd")
+        .arg("/nonexistent/signer {file}")
+        .arg("--exit-code")
+        .assert()
+        .code(2)
+        .stderr(predicate::str::contains("Could not run signing command"));
+}
+
+#[cfg(unix)]
+#[test]
+fn test_cli_external_rules() {
+    let temp_dir = setup_test_dirs();
+    let dir_a = temp_dir.path().join("dir_a");
+    let dir_b = temp_dir.path().join("dir_b");
+    fs::write(dir_a.join("report 1.pdf"), b"%PDF created 2023").unwrap();
+    fs::write(dir_b.join("report 1.pdf"), b"%PDF created 2024").unwrap();
+    fs::write(dir_a.join("broken.pdf"), b"%PDF a").unwrap();
+    fs::write(dir_b.join("broken.pdf"), b"%PDF b").unwrap();
+    let rules = temp_dir.path().join("rules.toml");
+    fs::write(
+        &rules,
+        r#"
+[[rule]]
+pattern = "*.pdf"
+command = "sh -c 'case \"$1\" in *broken.pdf) echo \"xref table differs\" >&2; exit 1;; esac' sh {a} {b}"
+"#,
+    )
+    .unwrap();
+
+    let output = cli_command()
+        .arg(&dir_a)
+        .arg(&dir_b)
+        .arg("--method")
+        .arg("hash")
+        .arg("--external-rules")
+        .arg(&rules)
+        .arg("--format")
+        .arg("json")
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let report: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let paths = |section: &str| -> Vec<String> {
+        report[section]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|pair| pair["a"]["path"].as_str().unwrap().to_string())
+            .collect()
+    };
+    assert_eq!(paths("both"), ["file1.txt", "report 1.pdf"]);
+    assert_eq!(paths("modified"), ["broken.pdf"]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("External comparison of") && stderr.contains("xref table differs"),
+        "{}",
+        stderr
+    );
+}
+
+#[test]
+fn test_cli_invalid_external_rules() {
+    let temp_dir = setup_test_dirs();
+    let rules = temp_dir.path().join("rules.toml");
+    fs::write(
+        &rules,
+        "[[rule]]\npattern = \"*.pdf\"\ncommand = \"cmp {a}\"\n",
+    )
+    .unwrap();
+
+    cli_command()
+        .arg(temp_dir.path().join("dir_a"))
+        .arg(temp_dir.path().join("dir_b"))
+        .arg("--external-rules")
+        .arg(&rules)
+        .arg("--format")
+        .arg("json")
+        .assert()
+        .code(1)
+        .stderr(predicate::str::contains(
+            "\"kind\": \"invalid_external_rules\"",
+        ))
+        .stderr(predicate::str::contains("must contain {b}"));
+}
+
+#[test]
+fn test_cli_doctor_passes_on_temp_dirs() {
+    let temp_dir = setup_test_dirs();
+
+    cli_command()
+        .arg("doctor")
+        .arg(temp_dir.path().join("dir_a").to_str().unwrap())
+        .arg(temp_dir.path().join("dir_b").to_str().unwrap())
+        .arg("--output-dir")
+        .arg(temp_dir.path().to_str().unwrap())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("PASS  Directory A readable"))
+        .stdout(predicate::str::contains("PASS  Free space"))
+        .stdout(predicate::str::contains("WARN").not())
+        .stdout(predicate::str::ends_with("Verdict: PASS\n"));
+
+    // The probes leave nothing behind
+    assert_eq!(
+        fs::read_dir(temp_dir.path().join("dir_b")).unwrap().count(),
+        1
+    );
+}
+
+#[test]
+fn test_cli_doctor_fails_on_a_missing_directory() {
+    let temp_dir = setup_test_dirs();
+
+    cli_command()
+        .arg("doctor")
+        .arg(temp_dir.path().join("dir_a").to_str().unwrap())
+        .arg(temp_dir.path().join("missing").to_str().unwrap())
+        .arg("--output-dir")
+        .arg(temp_dir.path().to_str().unwrap())
+        .assert()
+        .code(2)
+        .stdout(predicate::str::contains("FAIL  Directory B readable"))
+        .stdout(predicate::str::contains("Verdict: FAIL"));
+}
+
+#[test]
+fn test_cli_compare_directory_named_doctor() {
+    let temp_dir = setup_dir_named("doctor");
+
+    cli_command()
+        .current_dir(temp_dir.path())
+        .args(["./doctor", "dir_b"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("file1.txt"));
+
+    // Comparison options cannot be mixed with the subcommand
+    cli_command()
+        .current_dir(temp_dir.path())
+        .args(["doctor", "doctor", "dir_b", "--method", "hash"])
+        .assert()
+        .failure();
+}
+
+#[test]
+fn test_cli_config_file() {
+    let temp_dir = setup_test_dirs();
+    let config = temp_dir.path().join("dir-compare.toml");
+    fs::write(&config, "# From the GUI\nflat = true\nfull-hash = true\n").unwrap();
+
+    cli_command()
+        .arg(temp_dir.path().join("dir_a").to_str().unwrap())
+        .arg(temp_dir.path().join("dir_b").to_str().unwrap())
+        .arg("--config")
+        .arg(config.to_str().unwrap())
+        .arg("--format")
+        .arg("json")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("\"unique_hashes\""));
+
+    fs::write(&config, "[nested]\nflat = true\n").unwrap();
+    cli_command()
+        .arg(temp_dir.path().join("dir_a").to_str().unwrap())
+        .arg(temp_dir.path().join("dir_b").to_str().unwrap())
+        .arg(format!("--config={}", config.display()))
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Invalid config file"));
+}
+
+#[test]
+fn test_cli_sampling_advice_for_raw_data() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let dir_a = temp_dir.path().join("dir_a");
+    let dir_b = temp_dir.path().join("dir_b");
+    fs::create_dir_all(&dir_a).unwrap();
+    fs::create_dir_all(&dir_b).unwrap();
+    for name in ["disk.img", "server.log"] {
+        let content = vec![b'x'; 64 * 1024];
+        fs::write(dir_a.join(name), &content).unwrap();
+        fs::write(dir_b.join(name), &content).unwrap();
+    }
+    let run = |extra: &[&str]| {
+        let output = cli_command()
+            .arg(dir_a.to_str().unwrap())
+            .arg(dir_b.to_str().unwrap())
+            .args(extra)
+            .output()
+            .unwrap();
+        assert!(output.status.success());
+        String::from_utf8(output.stderr).unwrap()
+    };
+
+    let stderr = run(&[]);
+    assert_eq!(stderr.matches("Consider --verify").count(), 1, "{}", stderr);
+    assert!(stderr.contains("100% of the compared bytes"), "{}", stderr);
+
+    for extra in [
+        &["--no-advice"][..],
+        &["--verify"],
+        &["--advice-raw-share", "100"],
+    ] {
+        let stderr = run(extra);
+        assert!(
+            !stderr.contains("Consider --verify"),
+            "{:?}: {}",
+            extra,
+            stderr
+        );
+    }
+}
+
+#[test]
+fn test_cli_no_sampling_advice_for_compressed_data() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let dir_a = temp_dir.path().join("dir_a");
+    let dir_b = temp_dir.path().join("dir_b");
+    fs::create_dir_all(&dir_a).unwrap();
+    fs::create_dir_all(&dir_b).unwrap();
+    // An unknown extension is classified by its gzip signature
+    let mut content = b"\x1f\x8b\x08\x00".to_vec();
+    content.resize(64 * 1024, 7);
+    for dir in [&dir_a, &dir_b] {
+        fs::write(dir.join("photos.jpg"), &content).unwrap();
+        fs::write(dir.join("backup.data"), &content).unwrap();
+    }
+
+    cli_command()
+        .arg(dir_a.to_str().unwrap())
+        .arg(dir_b.to_str().unwrap())
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("Consider --verify").not());
+}
+
+#[test]
+fn test_cli_log_timestamp_formats() {
+    let temp_dir = setup_test_dirs();
+    let run = |extra: &[&str], env: Option<&str>| {
+        let mut cmd = cli_command();
+        cmd.arg(temp_dir.path().join("dir_a").to_str().unwrap())
+            .arg(temp_dir.path().join("dir_b").to_str().unwrap())
+            .args(["--full-hash", "--lenient-flags"])
+            .args(extra)
+            .env_remove("DIR_COMPARE_LOG_TIMESTAMP");
+        if let Some(env) = env {
+            cmd.env("DIR_COMPARE_LOG_TIMESTAMP", env);
+        }
+        let output = cmd.output().unwrap();
+        assert!(output.status.success());
+        let stderr = String::from_utf8(output.stderr).unwrap();
+        stderr
+            .lines()
+            .find(|line| line.starts_with("[WARN]"))
+            .unwrap()
+            .to_string()
+    };
+
+    let rfc3339 = predicate::str::is_match(
+        r"^\[WARN\] \d{4}-\d{2}-\d{2}T\d{2}:\d{2}:\d{2}\.\d{3}Z --full-hash",
+    )
+    .unwrap();
+    assert!(rfc3339.eval(&run(&[], None)));
+    let unix = predicate::str::is_match(r"^\[WARN\] \d+\.\d{6} --full-hash").unwrap();
+    assert!(unix.eval(&run(&["--log-timestamp", "unix"], None)));
+    assert!(run(&["--log-timestamp", "none"], None).starts_with("[WARN] --full-hash"));
+
+    // The environment wins, like DIR_COMPARE_LOG_LEVEL
+    assert!(unix.eval(&run(&["--log-timestamp", "none"], Some("unix"))));
+
+    cli_command()
+        .arg(temp_dir.path().join("dir_a").to_str().unwrap())
+        .arg(temp_dir.path().join("dir_b").to_str().unwrap())
+        .args(["--log-timestamp", "iso"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("expected rfc3339, unix or none"));
+}
+
+#[test]
+fn test_cli_json_log_format() {
+    let temp_dir = setup_test_dirs();
+    let run = |extra: &[&str], env: Option<&str>| {
+        let mut cmd = cli_command();
+        cmd.arg(temp_dir.path().join("dir_a").to_str().unwrap())
+            .arg(temp_dir.path().join("dir_b").to_str().unwrap())
+            .args(["--full-hash", "--lenient-flags"])
+            .args(extra)
+            .env_remove("DIR_COMPARE_LOG_FORMAT")
+            .env_remove("DIR_COMPARE_LOG_TIMESTAMP");
+        if let Some(env) = env {
+            cmd.env("DIR_COMPARE_LOG_FORMAT", env);
+        }
+        let output = cmd.output().unwrap();
+        assert!(output.status.success());
+        String::from_utf8(output.stderr).unwrap()
+    };
+
+    for stderr in [
+        run(&["--log-format", "json"], None),
+        run(&["--log-format", "text"], Some("json")),
+    ] {
+        let records: Vec<serde_json::Value> = stderr
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        let warning = records
+            .iter()
+            .find(|record| record["level"] == "WARN")
+            .unwrap();
+        assert!(warning["msg"].as_str().unwrap().starts_with("--full-hash"));
+        assert!(warning["ts"].as_str().unwrap().ends_with('Z'));
+    }
+
+    assert!(run(&[], None).starts_with("[WARN] "));
+}
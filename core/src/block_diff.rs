@@ -0,0 +1,212 @@
+//! Block-level localization of differences between two files.
+//!
+//! Both inputs are read in fixed-size blocks; each pair of blocks is hashed
+//! and mismatching blocks are merged into contiguous byte regions. This gives
+//! a rough picture of *where* two large files differ without a full diff.
+
+use std::hash::Hasher;
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+
+use crate::comparison::{ComparisonResult, Entry, EntryKind};
+
+/// Default block size used for difference localization (1 MiB).
+pub const DEFAULT_BLOCK_SIZE: usize = 1024 * 1024;
+
+/// A contiguous byte range `[start, end)` in which two files differ.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DiffRegion {
+    /// Offset of the first differing block
+    pub start: u64,
+    /// Offset just past the last differing byte
+    pub end: u64,
+}
+
+impl DiffRegion {
+    /// Returns the number of bytes covered by this region.
+    pub fn len(&self) -> u64 {
+        self.end - self.start
+    }
+
+    /// Returns true if the region covers no bytes.
+    pub fn is_empty(&self) -> bool {
+        self.start == self.end
+    }
+}
+
+/// The outcome of a block-level comparison of two files.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BlockDiff {
+    /// Differing regions, sorted by offset and merged where contiguous
+    pub regions: Vec<DiffRegion>,
+    /// Length of the first input in bytes
+    pub len_a: u64,
+    /// Length of the second input in bytes
+    pub len_b: u64,
+    /// Block size used for the comparison
+    pub block_size: usize,
+}
+
+impl BlockDiff {
+    /// Returns true if no differing regions were found.
+    pub fn is_identical(&self) -> bool {
+        self.regions.is_empty()
+    }
+
+    /// Returns the total number of bytes covered by differing regions.
+    pub fn differing_bytes(&self) -> u64 {
+        self.regions.iter().map(DiffRegion::len).sum()
+    }
+
+    /// Returns the length of the longer input.
+    pub fn total_bytes(&self) -> u64 {
+        self.len_a.max(self.len_b)
+    }
+}
+
+/// Compares two readers block by block and returns the differing regions.
+///
+/// The common prefix (up to the shorter length) is analyzed in blocks of
+/// `block_size` bytes. If the inputs have different lengths, the tail of the
+/// longer one is reported as an additional region.
+///
+/// # Errors
+///
+/// Returns an error if either reader fails or `block_size` is zero.
+pub fn diff_blocks<A: Read, B: Read>(
+    mut a: A,
+    mut b: B,
+    block_size: usize,
+) -> io::Result<BlockDiff> {
+    if block_size == 0 {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "block size must be greater than zero",
+        ));
+    }
+
+    let mut buf_a = vec![0u8; block_size];
+    let mut buf_b = vec![0u8; block_size];
+    let mut regions: Vec<DiffRegion> = Vec::new();
+    let mut offset: u64 = 0;
+
+    loop {
+        let read_a = read_block(&mut a, &mut buf_a)?;
+        let read_b = read_block(&mut b, &mut buf_b)?;
+        let common = read_a.min(read_b);
+
+        if common > 0 && hash_block(&buf_a[..common]) != hash_block(&buf_b[..common]) {
+            push_region(&mut regions, offset, offset + common as u64);
+        }
+        offset += common as u64;
+
+        if read_a != read_b {
+            // One input ended inside this block: everything past the common
+            // prefix is tail, including whatever is left in the longer input.
+            let (rest, longer_read): (&mut dyn Read, usize) = if read_a > read_b {
+                (&mut a, read_a)
+            } else {
+                (&mut b, read_b)
+            };
+            let remaining = io::copy(rest, &mut io::sink())?;
+            let tail_end = offset + (longer_read - common) as u64 + remaining;
+            push_region(&mut regions, offset, tail_end);
+            let (len_a, len_b) = if read_a > read_b {
+                (tail_end, offset)
+            } else {
+                (offset, tail_end)
+            };
+            return Ok(BlockDiff {
+                regions,
+                len_a,
+                len_b,
+                block_size,
+            });
+        }
+
+        if read_a < block_size {
+            // Both inputs reached EOF at the same offset
+            return Ok(BlockDiff {
+                regions,
+                len_a: offset,
+                len_b: offset,
+                block_size,
+            });
+        }
+    }
+}
+
+/// Compares two files block by block.
+///
+/// See [`diff_blocks`] for details.
+pub fn locate_file_differences(a: &Path, b: &Path, block_size: usize) -> io::Result<BlockDiff> {
+    let file_a = std::fs::File::open(a)?;
+    let file_b = std::fs::File::open(b)?;
+    diff_blocks(
+        io::BufReader::new(file_a),
+        io::BufReader::new(file_b),
+        block_size,
+    )
+}
+
+/// Returns the file pairs that exist at the same path on both sides but
+/// did not match under the comparison strategy.
+pub fn differing_pairs(result: &ComparisonResult) -> Vec<(&Entry, &Entry)> {
+    result
+        .modified
+        .iter()
+        .filter(|(a, b)| a.kind == EntryKind::File && b.kind == EntryKind::File)
+        .map(|(a, b)| (a, b))
+        .collect()
+}
+
+/// Runs block-level localization on at most `limit` differing pairs of a result.
+///
+/// Returns the relative path of each analyzed pair with its outcome.
+pub fn locate_differences(
+    result: &ComparisonResult,
+    block_size: usize,
+    limit: usize,
+) -> Vec<(PathBuf, io::Result<BlockDiff>)> {
+    differing_pairs(result)
+        .into_iter()
+        .take(limit)
+        .map(|(a, b)| {
+            (
+                a.path.clone(),
+                locate_file_differences(&a.abs_path, &b.abs_path, block_size),
+            )
+        })
+        .collect()
+}
+
+/// Fills `buf` from `reader`, returning fewer bytes only at end of input.
+fn read_block<R: Read>(reader: &mut R, buf: &mut [u8]) -> io::Result<usize> {
+    let mut filled = 0;
+    while filled < buf.len() {
+        match reader.read(&mut buf[filled..]) {
+            Ok(0) => break,
+            Ok(n) => filled += n,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(filled)
+}
+
+fn hash_block(data: &[u8]) -> u64 {
+    let mut hasher = fxhash::FxHasher::default();
+    hasher.write(data);
+    hasher.finish()
+}
+
+/// Appends a region, merging it with the previous one when contiguous.
+fn push_region(regions: &mut Vec<DiffRegion>, start: u64, end: u64) {
+    if start == end {
+        return;
+    }
+    match regions.last_mut() {
+        Some(last) if last.end == start => last.end = end,
+        _ => regions.push(DiffRegion { start, end }),
+    }
+}
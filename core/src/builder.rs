@@ -0,0 +1,318 @@
+//! A builder for directory comparisons.
+//!
+//! [`Comparison`] collects the directories, the strategy and every optional
+//! setting of a hierarchy or flat mode comparison, then runs it with
+//! [`Comparison::run`] or [`Comparison::run_flat`]. Settings that are not
+//! set keep the defaults of [`ComparisonOptions`] and
+//! [`FlatComparisonOptions`].
+//!
+//! The free functions of [`comparison`](crate::comparison), such as
+//! [`compare_directories`](crate::comparison::compare_directories), build a
+//! [`Comparison`] from their parameters; new settings are only added here.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use dir_compare_core::Comparison;
+//! use dir_compare_core::comparison::FastHashStrategy;
+//!
+//! let strategy = FastHashStrategy::new(false);
+//! let result = Comparison::new("test_data/original", "test_data/modified")
+//!     .strategy(&strategy)
+//!     .threads(4)
+//!     .max_depth(2)
+//!     .ignore_hidden(true)
+//!     .run()?;
+//! println!("{} A-only entries", result.a_only.len());
+//! # Ok::<(), dir_compare_core::ComparisonError>(())
+//! ```
+
+use std::path::PathBuf;
+use std::sync::{Arc, OnceLock};
+
+use crate::chunk_dedupe::ChunkDedupeOptions;
+use crate::comparison::{
+    CancellationToken, ComparisonError, ComparisonOptions, ComparisonPlan, ComparisonResult,
+    ComparisonStrategy, ComparisonStrategyType, EntryFilter, FlatComparisonOptions,
+    FlatComparisonResult, HashAlgorithm, HashCache, Progress, ResultDetail, StatMode,
+    flat_comparison, plan_comparison,
+};
+use crate::filesystem::RootPaths;
+use crate::strategy_defaults::{self, StrategySettings};
+
+/// A comparison of two directories, configured one setting at a time.
+///
+/// Settings taking an optional value, such as [`threads`](Self::threads),
+/// accept either the value or an `Option` of it, so `None` restores the
+/// default. Settings shared by both modes, such as
+/// [`max_depth`](Self::max_depth), apply to [`run`](Self::run) and
+/// [`run_flat`](Self::run_flat); the others name the mode they apply to.
+///
+/// # Examples
+///
+/// Grouping files by content, in flat mode:
+///
+/// ```no_run
+/// use dir_compare_core::Comparison;
+///
+/// let result = Comparison::new("test_data/original", "test_data/modified")
+///     .full_hash(true)
+///     .extensions(vec!["txt".to_string()])
+///     .run_flat()?;
+/// println!("{} content groups", result.groups.len());
+/// # Ok::<(), dir_compare_core::ComparisonError>(())
+/// ```
+pub struct Comparison<'a> {
+    dir_a: PathBuf,
+    dir_b: PathBuf,
+    strategy: Option<&'a dyn ComparisonStrategy>,
+    default_strategy: OnceLock<Box<dyn ComparisonStrategy + Send>>,
+    ignore_file: Option<PathBuf>,
+    progress: Option<&'a (dyn Fn(&Progress) + Send + Sync)>,
+    options: ComparisonOptions,
+    flat_options: FlatComparisonOptions,
+}
+
+/// Progress callback of comparisons without one
+fn no_progress(_: &Progress) {}
+
+impl<'a> Comparison<'a> {
+    /// Starts a comparison of `dir_a` and `dir_b` with default settings.
+    pub fn new(dir_a: impl Into<PathBuf>, dir_b: impl Into<PathBuf>) -> Self {
+        Self {
+            dir_a: dir_a.into(),
+            dir_b: dir_b.into(),
+            strategy: None,
+            default_strategy: OnceLock::new(),
+            ignore_file: None,
+            progress: None,
+            options: ComparisonOptions::default(),
+            flat_options: FlatComparisonOptions::default(),
+        }
+    }
+
+    /// Compares pairs of entries with `strategy` (hierarchy mode).
+    ///
+    /// Without a strategy, files are compared by sampled hash, as
+    /// `dir-compare` does without `--method`, and names case-insensitively
+    /// when both directories are on case-insensitive filesystems.
+    pub fn strategy(mut self, strategy: &'a dyn ComparisonStrategy) -> Self {
+        self.strategy = Some(strategy);
+        self
+    }
+
+    /// Leaves out the entries matched by the patterns of an ignore file.
+    pub fn ignore_file(mut self, path: impl Into<Option<PathBuf>>) -> Self {
+        self.ignore_file = path.into();
+        self
+    }
+
+    /// Compares only the entries `filter` selects.
+    pub fn filter(mut self, filter: impl Into<Option<EntryFilter>>) -> Self {
+        let filter = filter.into();
+        self.flat_options.filter = filter.clone();
+        self.options.filter = filter;
+        self
+    }
+
+    /// Evaluates same-path pairs on `threads` worker threads (hierarchy
+    /// mode); by default on as many as the machine runs in parallel.
+    pub fn threads(mut self, threads: impl Into<Option<usize>>) -> Self {
+        self.options.threads = threads.into();
+        self
+    }
+
+    /// Lists entries at most `depth` levels below the roots, see
+    /// [`TraversalOptions::max_depth`](crate::comparison::TraversalOptions::max_depth).
+    pub fn max_depth(mut self, depth: impl Into<Option<usize>>) -> Self {
+        let depth = depth.into();
+        self.options.max_depth = depth;
+        self.flat_options.max_depth = depth;
+        self
+    }
+
+    /// Leaves out the entries matched by `.gitignore` files in the trees.
+    pub fn respect_gitignore(mut self, respect: bool) -> Self {
+        self.options.respect_gitignore = respect;
+        self.flat_options.respect_gitignore = respect;
+        self
+    }
+
+    /// Leaves out hidden entries.
+    pub fn ignore_hidden(mut self, ignore: bool) -> Self {
+        self.options.ignore_hidden = ignore;
+        self.flat_options.ignore_hidden = ignore;
+        self
+    }
+
+    /// Chooses how the root directories are resolved.
+    pub fn root_paths(mut self, root_paths: RootPaths) -> Self {
+        self.options.root_paths = root_paths;
+        self.flat_options.root_paths = root_paths;
+        self
+    }
+
+    /// Reads files in their order on disk where the platform reports it;
+    /// the order of the result is unchanged.
+    pub fn order_by_extent(mut self, order: bool) -> Self {
+        self.options.order_by_extent = order;
+        self.flat_options.order_by_extent = order;
+        self
+    }
+
+    /// Reports the progress of the comparison to `progress`, see
+    /// [`compare_directories_with_progress`] and
+    /// [`compare_directories_flat_with_progress`].
+    ///
+    /// [`compare_directories_with_progress`]: crate::comparison::compare_directories_with_progress
+    /// [`compare_directories_flat_with_progress`]: crate::comparison::compare_directories_flat_with_progress
+    pub fn progress(mut self, progress: &'a (dyn Fn(&Progress) + Send + Sync)) -> Self {
+        self.progress = Some(progress);
+        self
+    }
+
+    /// Stops the comparison with [`ComparisonError::Cancelled`] when `cancel`
+    /// is cancelled (hierarchy mode).
+    pub fn cancel(mut self, cancel: impl Into<Option<CancellationToken>>) -> Self {
+        self.options.cancel = cancel.into();
+        self
+    }
+
+    /// Limits how many entries of each category the result keeps
+    /// (hierarchy mode).
+    pub fn result_detail(mut self, detail: ResultDetail) -> Self {
+        self.options.result_detail = detail;
+        self
+    }
+
+    /// Pairs files moved to another path by their content (hierarchy
+    /// mode), see [`ComparisonResult::renamed`].
+    pub fn detect_renames(mut self, detect: bool) -> Self {
+        self.options.detect_renames = detect;
+        self
+    }
+
+    /// Chooses when the metadata of files is read (hierarchy mode).
+    pub fn stat_mode(mut self, stat_mode: StatMode) -> Self {
+        self.options.stat_mode = stat_mode;
+        self
+    }
+
+    /// Replaces every hierarchy mode setting with `options`.
+    pub fn options(mut self, options: ComparisonOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Hashes whole files instead of samples (flat mode).
+    pub fn full_hash(mut self, full: bool) -> Self {
+        self.flat_options.use_full_hash = full;
+        self
+    }
+
+    /// Hashes files with `algorithm` (flat mode).
+    pub fn hash_algorithm(mut self, algorithm: HashAlgorithm) -> Self {
+        self.flat_options.hash_algorithm = algorithm;
+        self
+    }
+
+    /// Compares names case-insensitively (flat mode); in hierarchy mode the
+    /// strategy decides.
+    pub fn case_insensitive(mut self, case_insensitive: bool) -> Self {
+        self.flat_options.case_insensitive = case_insensitive;
+        self
+    }
+
+    /// Hashes files without trusting their metadata size (flat mode); in
+    /// hierarchy mode the strategy decides.
+    pub fn distrust_sizes(mut self, distrust: bool) -> Self {
+        self.flat_options.distrust_sizes = distrust;
+        self
+    }
+
+    /// Takes hashes from `cache` and adds them to it (flat mode).
+    pub fn hash_cache(mut self, cache: impl Into<Option<Arc<HashCache>>>) -> Self {
+        self.flat_options.hash_cache = cache.into();
+        self
+    }
+
+    /// Hints the OS to read ahead files hashed whole (flat mode); on by
+    /// default. In hierarchy mode the strategy decides.
+    pub fn io_hints(mut self, hints: bool) -> Self {
+        self.flat_options.io_hints = hints;
+        self
+    }
+
+    /// Also compares the files chunk by chunk (flat mode).
+    pub fn chunk_dedupe(mut self, options: impl Into<Option<ChunkDedupeOptions>>) -> Self {
+        self.flat_options.chunk_dedupe = options.into();
+        self
+    }
+
+    /// Only groups files with one of `extensions` (flat mode).
+    pub fn extensions(mut self, extensions: impl Into<Option<Vec<String>>>) -> Self {
+        self.flat_options.extensions = extensions.into();
+        self
+    }
+
+    /// Leaves out files smaller than `bytes` (flat mode).
+    pub fn min_file_size(mut self, bytes: u64) -> Self {
+        self.flat_options.min_file_size = bytes;
+        self
+    }
+
+    /// Replaces every flat mode setting with `options`.
+    pub fn flat_options(mut self, options: FlatComparisonOptions) -> Self {
+        self.flat_options = options;
+        self
+    }
+
+    /// Lists both directories and pairs their entries without comparing
+    /// them yet, see [`plan_comparison`].
+    pub fn plan(&self) -> Result<ComparisonPlan<'_>, ComparisonError> {
+        plan_comparison(
+            &self.dir_a,
+            &self.dir_b,
+            self.comparison_strategy(),
+            self.ignore_file.as_deref(),
+            &self.options,
+            self.progress_callback(),
+        )
+    }
+
+    /// Runs the comparison in hierarchy mode.
+    pub fn run(&self) -> Result<ComparisonResult, ComparisonError> {
+        self.plan()?.run(self.progress_callback())
+    }
+
+    /// Runs the comparison in flat mode, grouping files by content.
+    pub fn run_flat(&self) -> Result<FlatComparisonResult, ComparisonError> {
+        flat_comparison(
+            &self.dir_a,
+            &self.dir_b,
+            &self.flat_options,
+            self.ignore_file.as_deref(),
+            self.progress_callback(),
+        )
+    }
+
+    /// The strategy given, or the default one, created on first use
+    fn comparison_strategy(&self) -> &dyn ComparisonStrategy {
+        match self.strategy {
+            Some(strategy) => strategy,
+            None => self
+                .default_strategy
+                .get_or_init(|| {
+                    let case_insensitive =
+                        strategy_defaults::case_insensitive(None, &self.dir_a, &self.dir_b);
+                    StrategySettings::new(ComparisonStrategyType::SampledHash, case_insensitive)
+                        .build()
+                })
+                .as_ref(),
+        }
+    }
+
+    fn progress_callback(&self) -> &(dyn Fn(&Progress) + Send + Sync) {
+        self.progress.unwrap_or(&no_progress)
+    }
+}
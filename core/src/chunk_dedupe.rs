@@ -0,0 +1,397 @@
+//! Chunk-level dedupe statistics for flat mode.
+//!
+//! Files are split into chunks at content-defined boundaries, FastCDC-style:
+//! a gear hash rolls over the bytes and a chunk ends where its top bits are
+//! zero, so a region shared by two files yields the same chunks even at
+//! different offsets. Only a hash and the length of each chunk are kept,
+//! from which [`ChunkDedupeReport`] estimates how much data A and B share.
+
+use std::collections::HashMap;
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use crate::comparison::{
+    FileWarning, Progress, ProgressPhase, WarningCause, bytes_hashed_here, count_hashed,
+};
+use crate::filesystem::{FileSystem, RealFileSystem};
+
+/// Default average chunk size (64 KiB).
+pub const DEFAULT_AVG_CHUNK_SIZE: usize = 64 * 1024;
+
+/// Smallest accepted average chunk size; smaller values are raised to it.
+pub const MIN_AVG_CHUNK_SIZE: usize = 256;
+
+/// Largest accepted average chunk size (64 MiB); larger values are lowered
+/// to it.
+pub const MAX_AVG_CHUNK_SIZE: usize = 64 * 1024 * 1024;
+
+/// Default number of files listed per side in [`ChunkDedupeReport`].
+pub const DEFAULT_TOP_FILES: usize = 10;
+
+/// Random values the gear hash adds for each byte value.
+const GEAR: [u64; 256] = gear_table();
+
+/// Fills [`GEAR`] with the output of SplitMix64, so the table is fixed
+/// across builds and platforms.
+const fn gear_table() -> [u64; 256] {
+    let mut table = [0u64; 256];
+    let mut state: u64 = 0;
+    let mut i = 0;
+    while i < table.len() {
+        state = state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        table[i] = z ^ (z >> 31);
+        i += 1;
+    }
+    table
+}
+
+/// Splits data into content-defined chunks of about an average size.
+///
+/// Chunks are at least a quarter and at most four times the average. Below
+/// the average a boundary needs one more zero bit than above it, which keeps
+/// most chunks close to the average (FastCDC's normalized chunking).
+///
+/// # Examples
+///
+/// ```
+/// use dir_compare_core::chunk_dedupe::Chunker;
+///
+/// let data = vec![7u8; 10_000];
+/// let mut lengths = Vec::new();
+/// let total = Chunker::new(1024)
+///     .chunks(&data[..], |_, len| lengths.push(len))
+///     .unwrap();
+/// assert_eq!(total, 10_000);
+/// assert_eq!(lengths.iter().sum::<u64>(), 10_000);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Chunker {
+    min_size: usize,
+    avg_size: usize,
+    max_size: usize,
+    /// Zero bits a boundary needs at the average size
+    bits: u32,
+}
+
+impl Chunker {
+    /// Creates a chunker aiming at `avg_size` bytes per chunk, clamped to
+    /// [`MIN_AVG_CHUNK_SIZE`] and [`MAX_AVG_CHUNK_SIZE`].
+    pub fn new(avg_size: usize) -> Self {
+        let avg_size = avg_size.clamp(MIN_AVG_CHUNK_SIZE, MAX_AVG_CHUNK_SIZE);
+        Self {
+            min_size: avg_size / 4,
+            avg_size,
+            max_size: avg_size * 4,
+            bits: avg_size.ilog2(),
+        }
+    }
+
+    /// Smallest chunk, except for the last chunk of a file
+    pub fn min_size(&self) -> usize {
+        self.min_size
+    }
+
+    /// Largest chunk
+    pub fn max_size(&self) -> usize {
+        self.max_size
+    }
+
+    /// Reads `reader` to the end and calls `on_chunk` with the hash and
+    /// length of each chunk, in order. Returns the number of bytes read.
+    pub fn chunks(
+        &self,
+        mut reader: impl Read,
+        mut on_chunk: impl FnMut(u128, u64),
+    ) -> io::Result<u64> {
+        let mut buffer = vec![0u8; 64 * 1024];
+        let mut hasher = blake3::Hasher::new();
+        let mut len = 0usize;
+        let mut gear = 0u64;
+        let mut total = 0u64;
+        loop {
+            let bytes_read = match reader.read(&mut buffer) {
+                Ok(0) => break,
+                Ok(n) => n,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e),
+            };
+            count_hashed(bytes_read as u64);
+            total += bytes_read as u64;
+            let mut data = &buffer[..bytes_read];
+            while let Some(cut) = self.find_cut(&mut gear, len, data) {
+                hasher.update(&data[..cut]);
+                on_chunk(chunk_hash(&hasher), (len + cut) as u64);
+                hasher.reset();
+                len = 0;
+                gear = 0;
+                data = &data[cut..];
+            }
+            hasher.update(data);
+            len += data.len();
+        }
+        if len > 0 {
+            on_chunk(chunk_hash(&hasher), len as u64);
+        }
+        Ok(total)
+    }
+
+    /// Returns the offset in `data` just past the next boundary of a chunk
+    /// that already holds `len` bytes and whose gear hash is `gear`, or
+    /// `None` if the chunk continues past `data`.
+    fn find_cut(&self, gear: &mut u64, len: usize, data: &[u8]) -> Option<usize> {
+        // The first bytes of a chunk can never end it, so they are skipped
+        let skip = self.min_size.saturating_sub(len).min(data.len());
+        for (i, &byte) in data.iter().enumerate().skip(skip) {
+            let size = len + i + 1;
+            *gear = (*gear << 1).wrapping_add(GEAR[byte as usize]);
+            let bits = if size < self.avg_size {
+                self.bits + 1
+            } else {
+                self.bits - 1
+            };
+            if size >= self.max_size || *gear >> (64 - bits) == 0 {
+                return Some(i + 1);
+            }
+        }
+        None
+    }
+}
+
+/// The first 128 bits of the BLAKE3 hash of a chunk.
+fn chunk_hash(hasher: &blake3::Hasher) -> u128 {
+    let hash = hasher.finalize();
+    let mut bytes = [0u8; 16];
+    bytes.copy_from_slice(&hash.as_bytes()[..16]);
+    u128::from_le_bytes(bytes)
+}
+
+/// Options of the chunk-level dedupe analysis of flat mode.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChunkDedupeOptions {
+    /// Average chunk size in bytes; smaller chunks find more sharing but
+    /// keep more hashes
+    pub avg_chunk_size: usize,
+    /// Number of threads chunking files; `None` uses the available
+    /// parallelism
+    pub threads: Option<usize>,
+    /// Number of files listed per side in the report
+    pub top_files: usize,
+}
+
+impl Default for ChunkDedupeOptions {
+    fn default() -> Self {
+        Self {
+            avg_chunk_size: DEFAULT_AVG_CHUNK_SIZE,
+            threads: None,
+            top_files: DEFAULT_TOP_FILES,
+        }
+    }
+}
+
+/// A file with chunks that also occur on the other side.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SharedFile {
+    /// Path relative to the compared root
+    pub path: PathBuf,
+    /// Bytes read from the file
+    pub size: u64,
+    /// Bytes of the file in chunks that also occur on the other side
+    pub shared_bytes: u64,
+}
+
+impl SharedFile {
+    /// The share of the file found on the other side, from 0.0 to 1.0.
+    pub fn shared_ratio(&self) -> f64 {
+        if self.size == 0 {
+            0.0
+        } else {
+            self.shared_bytes as f64 / self.size as f64
+        }
+    }
+}
+
+/// How much data directories A and B share at the chunk level.
+///
+/// Byte totals count each distinct chunk once, so data repeated within one
+/// side counts once as well. Chunk boundaries next to a shared region fall
+/// in data that differs, so shared regions are found short by up to a chunk
+/// at each end.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ChunkDedupeReport {
+    /// Average chunk size the files were split with
+    pub avg_chunk_size: usize,
+    /// Distinct chunks in directory A
+    pub chunks_a: usize,
+    /// Distinct chunks in directory B
+    pub chunks_b: usize,
+    /// Bytes of the distinct chunks found in both directories
+    pub shared_bytes: u64,
+    /// Bytes of the distinct chunks found only in directory A
+    pub unique_bytes_a: u64,
+    /// Bytes of the distinct chunks found only in directory B
+    pub unique_bytes_b: u64,
+    /// Files of directory A sharing the largest part of their bytes with B,
+    /// highest ratio first
+    pub top_files_a: Vec<SharedFile>,
+    /// Files of directory B sharing the largest part of their bytes with A,
+    /// highest ratio first
+    pub top_files_b: Vec<SharedFile>,
+}
+
+/// The chunks of one file, as (hash, length)
+type FileChunks = Vec<(u128, u64)>;
+
+/// Chunks the files of both sides, given as (relative path, absolute path,
+/// size), and compares their chunk sets.
+///
+/// Files are spread over `options.threads` worker threads, each taking the
+/// next file from a shared counter, and a [`ProgressPhase::Chunking`] update
+/// is sent to `progress` after each file. Unreadable files are added to
+/// `warnings` and left out. Returns the report and the bytes read.
+pub(crate) fn analyze_chunks(
+    files_a: &[(PathBuf, PathBuf, u64)],
+    files_b: &[(PathBuf, PathBuf, u64)],
+    options: &ChunkDedupeOptions,
+    progress: &(dyn Fn(&Progress) + Send + Sync),
+    warnings: &mut Vec<FileWarning>,
+) -> (ChunkDedupeReport, u64) {
+    let chunker = Chunker::new(options.avg_chunk_size);
+    let paths: Vec<&Path> = files_a
+        .iter()
+        .chain(files_b)
+        .map(|(_, abs_path, _)| abs_path.as_path())
+        .collect();
+    let threads = options
+        .threads
+        .unwrap_or_else(|| {
+            std::thread::available_parallelism()
+                .map(|n| n.get())
+                .unwrap_or(1)
+        })
+        .clamp(1, paths.len().max(1));
+
+    let next = AtomicUsize::new(0);
+    let done = AtomicUsize::new(0);
+    let mut chunked: Vec<Option<(FileChunks, u64)>> = vec![None; paths.len()];
+    let mut bytes_read = 0;
+    std::thread::scope(|scope| {
+        let workers: Vec<_> = (0..threads)
+            .map(|_| {
+                scope.spawn(|| {
+                    let hashed_before = bytes_hashed_here();
+                    let mut files = Vec::new();
+                    let mut worker_warnings = Vec::new();
+                    loop {
+                        let index = next.fetch_add(1, Ordering::Relaxed);
+                        let Some(&path) = paths.get(index) else {
+                            break;
+                        };
+                        let mut chunks = Vec::new();
+                        let read = RealFileSystem.open_sequential(path).and_then(|reader| {
+                            chunker.chunks(reader, |hash, len| chunks.push((hash, len)))
+                        });
+                        match read {
+                            Ok(size) => files.push((index, Some((chunks, size)))),
+                            Err(e) => {
+                                worker_warnings.push(FileWarning::new(path, WarningCause::Hash, &e))
+                            }
+                        }
+                        progress(&Progress {
+                            phase: ProgressPhase::Chunking,
+                            current: done.fetch_add(1, Ordering::Relaxed) + 1,
+                            total: Some(paths.len()),
+                            side: None,
+                            current_path: path,
+                        });
+                    }
+                    let hashed = bytes_hashed_here() - hashed_before;
+                    (files, worker_warnings, hashed)
+                })
+            })
+            .collect();
+        for worker in workers {
+            let (files, worker_warnings, hashed) = worker
+                .join()
+                .unwrap_or_else(|panic| std::panic::resume_unwind(panic));
+            for (index, file) in files {
+                chunked[index] = file;
+            }
+            warnings.extend(worker_warnings);
+            bytes_read += hashed;
+        }
+    });
+
+    let chunked_b = chunked.split_off(files_a.len());
+    let set_a = chunk_set(&chunked);
+    let set_b = chunk_set(&chunked_b);
+    let shared_bytes: u64 = set_a
+        .iter()
+        .filter(|(hash, _)| set_b.contains_key(*hash))
+        .map(|(_, len)| *len)
+        .sum();
+    let side_bytes = |set: &HashMap<u128, u64>| -> u64 { set.values().sum() };
+    let report = ChunkDedupeReport {
+        avg_chunk_size: chunker.avg_size,
+        chunks_a: set_a.len(),
+        chunks_b: set_b.len(),
+        shared_bytes,
+        unique_bytes_a: side_bytes(&set_a) - shared_bytes,
+        unique_bytes_b: side_bytes(&set_b) - shared_bytes,
+        top_files_a: top_files(files_a, &chunked, &set_b, options.top_files),
+        top_files_b: top_files(files_b, &chunked_b, &set_a, options.top_files),
+    };
+    (report, bytes_read)
+}
+
+/// The distinct chunks of one side with their lengths.
+fn chunk_set(files: &[Option<(FileChunks, u64)>]) -> HashMap<u128, u64> {
+    files
+        .iter()
+        .flatten()
+        .flat_map(|(chunks, _)| chunks.iter().copied())
+        .collect()
+}
+
+/// The `limit` files with the highest share of bytes in chunks of `other`,
+/// leaving out files that share nothing.
+fn top_files(
+    files: &[(PathBuf, PathBuf, u64)],
+    chunked: &[Option<(FileChunks, u64)>],
+    other: &HashMap<u128, u64>,
+    limit: usize,
+) -> Vec<SharedFile> {
+    let mut shared: Vec<SharedFile> = files
+        .iter()
+        .zip(chunked)
+        .filter_map(|((path, _, _), file)| {
+            let (chunks, size) = file.as_ref()?;
+            let shared_bytes = chunks
+                .iter()
+                .filter(|(hash, _)| other.contains_key(hash))
+                .map(|(_, len)| *len)
+                .sum();
+            (shared_bytes > 0).then(|| SharedFile {
+                path: path.clone(),
+                size: *size,
+                shared_bytes,
+            })
+        })
+        .collect();
+    // Compares shared_bytes / size without rounding
+    shared.sort_by(|a, b| {
+        let ratio_a = u128::from(a.shared_bytes) * u128::from(b.size);
+        let ratio_b = u128::from(b.shared_bytes) * u128::from(a.size);
+        ratio_b
+            .cmp(&ratio_a)
+            .then(b.shared_bytes.cmp(&a.shared_bytes))
+            .then_with(|| a.path.cmp(&b.path))
+    });
+    shared.truncate(limit);
+    shared
+}
@@ -1,11 +1,209 @@
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
 use sha2::{Digest, Sha256};
+use std::cell::Cell;
 use std::collections::{HashMap, HashSet};
 use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime};
+
+use crate::builder::Comparison;
+use crate::chunk_dedupe::{ChunkDedupeOptions, ChunkDedupeReport, analyze_chunks};
+use crate::data_profile::DataProfiler;
+use crate::filesystem::{FileSystem, RealFileSystem, RootPaths, SharedFileSystem, resolve_root};
+use crate::hardlinks::{InodeKey, linked_inode};
+use crate::ignore_file::{IgnoreFile, retain_leading_dirs};
+use crate::logger;
+use crate::output::{ReportContext, Section};
+
+/// Why a comparison could not produce a result.
+#[derive(Debug)]
+pub enum ComparisonError {
+    /// A root directory could not be resolved, usually because it does not
+    /// exist
+    Canonicalize {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    /// A root directory exists but could not be listed
+    Traversal {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    /// The ignore file could not be read or contains an invalid pattern
+    IgnoreFile { path: PathBuf, message: String },
+    /// The comparison was stopped through [`ComparisonOptions::cancel`]
+    Cancelled,
+}
+
+impl ComparisonError {
+    /// The directory or ignore file the error is about, if any
+    pub fn path(&self) -> Option<&Path> {
+        match self {
+            ComparisonError::Canonicalize { path, .. }
+            | ComparisonError::Traversal { path, .. }
+            | ComparisonError::IgnoreFile { path, .. } => Some(path),
+            ComparisonError::Cancelled => None,
+        }
+    }
+
+    /// The closest [`std::io::ErrorKind`], for callers that classify errors
+    /// the way they classify I/O errors
+    pub fn io_kind(&self) -> std::io::ErrorKind {
+        match self {
+            ComparisonError::Canonicalize { source, .. }
+            | ComparisonError::Traversal { source, .. } => source.kind(),
+            ComparisonError::IgnoreFile { .. } => std::io::ErrorKind::InvalidInput,
+            ComparisonError::Cancelled => std::io::ErrorKind::Interrupted,
+        }
+    }
+}
+
+impl std::fmt::Display for ComparisonError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ComparisonError::Canonicalize { path, source } => {
+                write!(f, "Could not resolve {}: {}", path.display(), source)
+            }
+            ComparisonError::Traversal { path, source } => {
+                write!(f, "Could not read directory {}: {}", path.display(), source)
+            }
+            ComparisonError::IgnoreFile { path, message } => {
+                write!(f, "Invalid ignore file {}: {}", path.display(), message)
+            }
+            ComparisonError::Cancelled => write!(f, "Comparison cancelled"),
+        }
+    }
+}
+
+impl std::error::Error for ComparisonError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ComparisonError::Canonicalize { source, .. }
+            | ComparisonError::Traversal { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
+
+impl From<ComparisonError> for std::io::Error {
+    fn from(error: ComparisonError) -> Self {
+        std::io::Error::new(error.io_kind(), error)
+    }
+}
+
+/// What could not be done with the entry of a [`FileWarning`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum WarningCause {
+    /// The entry could not be listed; it is missing from the result
+    Access,
+    /// The size or modification time of the entry could not be read
+    Metadata,
+    /// The target of the symlink could not be read; it is missing from the
+    /// result
+    ReadLink,
+    /// The file contents could not be read for hashing; the pair is reported
+    /// as modified
+    Hash,
+    /// An external comparison command failed with a reason on stderr, could
+    /// not be started or timed out; the pair is reported as modified
+    External,
+}
+
+impl WarningCause {
+    /// Key of warnings with this cause for [`logger::warn_keyed`], so that
+    /// thousands of unreadable files are summarized instead of listed
+    pub fn key(self) -> &'static str {
+        match self {
+            WarningCause::Access => "inaccessible_entry",
+            WarningCause::Metadata => "unreadable_metadata",
+            WarningCause::ReadLink => "unreadable_link",
+            WarningCause::Hash => "unreadable_file",
+            WarningCause::External => "external_command",
+        }
+    }
+}
+
+/// An entry that was skipped or could not be compared fully.
+///
+/// Warnings do not fail a comparison; they are collected in
+/// [`ComparisonResult::warnings`] so callers can show which files were not
+/// read.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FileWarning {
+    /// Absolute path of the entry
+    pub path: PathBuf,
+    pub cause: WarningCause,
+    /// The underlying error message
+    pub message: String,
+}
+
+impl FileWarning {
+    pub(crate) fn new(path: &Path, cause: WarningCause, error: &dyn std::fmt::Display) -> Self {
+        Self {
+            path: path.to_path_buf(),
+            cause,
+            message: error.to_string(),
+        }
+    }
+
+    /// Converts an error of the directory walk below `root`, using the path
+    /// it carries when there is one.
+    fn from_walk(error: ignore::Error, root: &Path) -> Self {
+        let mut path = root.to_path_buf();
+        let mut error = error;
+        loop {
+            match error {
+                ignore::Error::WithDepth { err, .. } => error = *err,
+                ignore::Error::WithPath { path: inner, err } => {
+                    path = inner;
+                    error = *err;
+                }
+                ignore::Error::Loop { ref child, .. } => {
+                    path = child.clone();
+                    break;
+                }
+                _ => break,
+            }
+        }
+        Self::new(&path, WarningCause::Access, &error)
+    }
+}
+
+impl std::fmt::Display for FileWarning {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let action = match self.cause {
+            WarningCause::Access => "access",
+            WarningCause::Metadata => "get metadata for",
+            WarningCause::ReadLink => "read link",
+            WarningCause::Hash => "hash",
+            WarningCause::External => {
+                return write!(
+                    f,
+                    "External comparison of {}: {}",
+                    self.path.display(),
+                    self.message
+                );
+            }
+        };
+        write!(
+            f,
+            "Could not {} {}: {}",
+            action,
+            self.path.display(),
+            self.message
+        )
+    }
+}
 
 /// Represents a group of files with identical content in flat comparison mode.
 ///
 /// Used by flat mode to group files by their content hash regardless of path.
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct FlatContentGroup {
     /// Content hash (sampled or full, depending on configuration)
     pub hash: String,
@@ -17,6 +215,39 @@ pub struct FlatContentGroup {
     pub files_in_a: Vec<PathBuf>,
     /// Paths in directory B with this content
     pub files_in_b: Vec<PathBuf>,
+    /// Files of the group that are hard links to another file of the group,
+    /// in the order of `files_in_a` followed by `files_in_b`; always empty on
+    /// platforms without inode information
+    pub hard_links: Vec<FlatHardLink>,
+}
+
+impl FlatContentGroup {
+    /// Returns the hard-link group of `path` in directory A (`in_a`) or B,
+    /// or `None` if the file is not a hard link to another file of the group.
+    pub fn hardlink_group(&self, in_a: bool, path: &Path) -> Option<u64> {
+        self.hard_links
+            .iter()
+            .find(|link| link.in_a == in_a && link.path == path)
+            .map(|link| link.hardlink_group)
+    }
+}
+
+/// A file of a [`FlatContentGroup`] that shares its inode with other files.
+///
+/// Hard links take up the space of their content only once, unlike true
+/// duplicates. Flat mode hashes each inode once and reuses the hash for the
+/// other links, which may lie in either directory, as with backups made by
+/// `rsync --link-dest`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FlatHardLink {
+    /// Whether the file is in directory A rather than B
+    pub in_a: bool,
+    /// Path relative to the root of its directory
+    pub path: PathBuf,
+    /// Number shared by the links to the same inode, counting from 1 across
+    /// all groups of the result
+    pub hardlink_group: u64,
 }
 
 /// The result of a flat mode directory comparison.
@@ -24,6 +255,7 @@ pub struct FlatContentGroup {
 /// Groups files by content hash, showing which files are duplicates,
 /// moved, or unique across the two directories.
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct FlatComparisonResult {
     /// Content hash groups with files from both directories
     pub groups: Vec<FlatContentGroup>,
@@ -35,19 +267,582 @@ pub struct FlatComparisonResult {
     pub unique_hashes: usize,
     /// Number of content hashes with duplicates
     pub duplicate_count: usize,
+    /// Entries that were skipped or could not be hashed, sorted by path;
+    /// unhashed files are missing from `groups` and the totals
+    pub warnings: Vec<FileWarning>,
+    /// What the comparison read and how long it took
+    pub stats: ComparisonStats,
+    /// Chunk-level sharing between A and B; only analyzed when
+    /// [`FlatComparisonOptions::chunk_dedupe`] is set
+    pub chunk_dedupe: Option<ChunkDedupeReport>,
+}
+
+impl FlatComparisonResult {
+    /// Returns true if any content exists on one side only, or is stored
+    /// under different relative paths in A and B.
+    pub fn has_differences(&self) -> bool {
+        self.groups.iter().any(|group| {
+            let in_a: HashSet<&PathBuf> = group.files_in_a.iter().collect();
+            let in_b: HashSet<&PathBuf> = group.files_in_b.iter().collect();
+            in_a != in_b
+        })
+    }
+}
+
+/// Options for hierarchy mode comparison.
+#[derive(Debug, Clone, Default)]
+pub struct ComparisonOptions {
+    /// Number of worker threads evaluating same-path pairs; `None` uses the
+    /// available parallelism
+    pub threads: Option<usize>,
+    /// How the root directories are resolved
+    pub root_paths: RootPaths,
+    /// Token for stopping the comparison from another thread
+    pub cancel: Option<CancellationToken>,
+    /// Include and exclude patterns selecting the entries to compare
+    pub filter: Option<EntryFilter>,
+    /// How many entries of each category the result keeps
+    pub result_detail: ResultDetail,
+    /// Evaluate same-path pairs in the order of their files on disk, as
+    /// reported by [`FileSystem::physical_offset`] of the strategy's file
+    /// system; the order of the result is unchanged
+    pub order_by_extent: bool,
+    /// When the sizes and modification times of files are read
+    pub stat_mode: StatMode,
+    /// Leave out the entries matched by `.gitignore` files in the compared
+    /// trees, see [`TraversalOptions::respect_gitignore`]
+    pub respect_gitignore: bool,
+    /// How deep below the roots entries are listed, see
+    /// [`TraversalOptions::max_depth`]
+    pub max_depth: Option<usize>,
+    /// Leave out hidden entries, see [`TraversalOptions::ignore_hidden`]
+    pub ignore_hidden: bool,
+    /// Pair the A-only and B-only files holding the same content as
+    /// [`ComparisonResult::renamed`]
+    pub detect_renames: bool,
+}
+
+impl ComparisonOptions {
+    /// The options listing each root
+    fn traversal(&self) -> TraversalOptions {
+        TraversalOptions {
+            respect_gitignore: self.respect_gitignore,
+            max_depth: self.max_depth,
+            ignore_hidden: self.ignore_hidden,
+        }
+    }
+}
+
+/// Options of [`traverse_directory_with_options`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct TraversalOptions {
+    /// Leave out the entries matched by `.gitignore` files, as git does:
+    /// patterns are relative to the directory holding the file, later and
+    /// deeper files win, and `!` re-includes entries. Files are honored
+    /// without a `.git` directory; git's global and per-repository
+    /// excludes are not read.
+    pub respect_gitignore: bool,
+    /// Deepest level listed: 1 lists the immediate children of the root
+    /// only. Directories at the cutoff are listed without their contents;
+    /// `None` lists every level.
+    pub max_depth: Option<usize>,
+    /// Leave out entries whose name starts with `.`, and on Windows those
+    /// with the hidden attribute; hidden directories are not descended into.
+    pub ignore_hidden: bool,
+}
+
+/// When a hierarchy mode comparison reads the metadata of files.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum StatMode {
+    /// Read the size and modification time of every file while listing it,
+    /// with a single metadata call
+    #[default]
+    Eager,
+    /// Read no metadata while listing; strategies that compare sizes or
+    /// modification times look them up for same-path pairs only. Entries keep
+    /// no size, so reports show none and size filters keep no files.
+    /// Suited to network mounts, where every call is a round trip
+    Lazy,
+}
+
+impl std::str::FromStr for StatMode {
+    type Err = String;
+
+    /// Parses `eager` or `lazy`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "eager" => Ok(StatMode::Eager),
+            "lazy" => Ok(StatMode::Lazy),
+            other => Err(format!(
+                "unknown stat mode '{}' (expected eager or lazy)",
+                other
+            )),
+        }
+    }
+}
+
+/// How many entries of each category a [`ComparisonResult`] keeps.
+///
+/// Every entry is still listed and compared; the detail level only limits
+/// what is stored. Kept entries are always the first by path, so the same
+/// trees give the same sample.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ResultDetail {
+    /// Keep every entry
+    #[default]
+    Full,
+    /// Keep no entries, only [`ComparisonResult::exact_counts`]
+    CountsOnly,
+    /// Keep the first `per_category` entries of each category
+    Sampled { per_category: usize },
+}
+
+impl ResultDetail {
+    /// Returns the number of entries kept per category, or `None` for all.
+    pub fn limit(self) -> Option<usize> {
+        match self {
+            ResultDetail::Full => None,
+            ResultDetail::CountsOnly => Some(0),
+            ResultDetail::Sampled { per_category } => Some(per_category),
+        }
+    }
+}
+
+impl std::str::FromStr for ResultDetail {
+    type Err = String;
+
+    /// Parses `full`, `counts` or `sampled:N`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim().to_lowercase();
+        match s.as_str() {
+            "full" => return Ok(ResultDetail::Full),
+            "counts" | "counts-only" => return Ok(ResultDetail::CountsOnly),
+            _ => {}
+        }
+        match s.strip_prefix("sampled:").map(str::parse::<usize>) {
+            Some(Ok(per_category)) => Ok(ResultDetail::Sampled { per_category }),
+            Some(Err(_)) => Err(format!(
+                "invalid sample size in '{}' (expected a whole number)",
+                s
+            )),
+            None => Err(format!(
+                "unknown result detail '{}' (expected full, counts or sampled:N)",
+                s
+            )),
+        }
+    }
+}
+
+/// Stops a running hierarchy mode comparison from another thread.
+///
+/// Clones share the same flag. A cancelled comparison stops listing entries
+/// and evaluating pairs, and returns [`ComparisonError::Cancelled`] instead
+/// of a partial result.
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken(Arc<AtomicBool>);
+
+impl CancellationToken {
+    /// Creates a token that has not been cancelled.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Requests cancellation of every comparison using this token.
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    /// Returns true once [`cancel`](Self::cancel) was called on any clone.
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+/// The stage of a comparison reported by [`Progress`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProgressPhase {
+    /// Listing the entries of both directories
+    Traversing,
+    /// Evaluating the strategy for entries present on both sides
+    Matching,
+    /// Splitting files into chunks for the chunk-level dedupe analysis of
+    /// flat mode
+    Chunking,
+}
+
+/// One of the two compared directories.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Side {
+    /// The first directory
+    A,
+    /// The second directory
+    B,
+}
+
+impl std::fmt::Display for Side {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Side::A => write!(f, "A"),
+            Side::B => write!(f, "B"),
+        }
+    }
+}
+
+/// A progress update passed to the callback of
+/// [`compare_directories_with_progress`].
+#[derive(Debug, Clone, Copy)]
+pub struct Progress<'a> {
+    /// The current stage
+    pub phase: ProgressPhase,
+    /// Entries listed so far in the directory of `side` while traversing,
+    /// pairs evaluated so far while matching, or files read so far while
+    /// chunking
+    pub current: usize,
+    /// Number of pairs to evaluate or files to chunk; `None` while
+    /// traversing, where the total is not known in advance
+    pub total: Option<usize>,
+    /// The directory being listed while traversing; `None` in the other
+    /// stages
+    pub side: Option<Side>,
+    /// The entry just listed or evaluated
+    pub current_path: &'a Path,
+}
+
+/// Hash algorithm used by flat mode to group files by content.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum HashAlgorithm {
+    /// SHA-256, the algorithm of earlier versions
+    #[default]
+    Sha256,
+    /// BLAKE3: cryptographic like SHA-256, and several times faster
+    Blake3,
+    /// FxHash: fastest, but not collision-resistant
+    FxHash,
+}
+
+impl std::str::FromStr for HashAlgorithm {
+    type Err = String;
+
+    /// Parses `sha256`, `blake3` or `fxhash`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "sha256" | "sha-256" => Ok(HashAlgorithm::Sha256),
+            "blake3" => Ok(HashAlgorithm::Blake3),
+            "fxhash" => Ok(HashAlgorithm::FxHash),
+            other => Err(format!(
+                "unknown hash algorithm '{}' (expected sha256, blake3 or fxhash)",
+                other
+            )),
+        }
+    }
 }
 
 /// Options for flat mode comparison.
-#[derive(Debug, Clone, Copy, Default)]
+#[derive(Debug, Clone)]
 pub struct FlatComparisonOptions {
     /// Use full-file hash instead of sampled hash
     pub use_full_hash: bool,
+    /// Algorithm of the sampled or full-file hash
+    pub hash_algorithm: HashAlgorithm,
     /// Use case-insensitive filename comparison
     pub case_insensitive: bool,
+    /// How the root directories are resolved
+    pub root_paths: RootPaths,
+    /// Include and exclude patterns selecting the files to compare
+    pub filter: Option<EntryFilter>,
+    /// Hash whole files without their metadata size and group them by
+    /// content alone, for filesystems reporting 0 or stale sizes
+    pub distrust_sizes: bool,
+    /// Cache to take hashes from and add them to, shared with other
+    /// comparisons of the same files; its algorithm and mode replace
+    /// `use_full_hash` and `hash_algorithm`. Not used with `distrust_sizes`
+    pub hash_cache: Option<Arc<HashCache>>,
+    /// Hint the OS to read ahead files that are hashed whole; on by default
+    pub io_hints: bool,
+    /// Hash files in their order on disk where the platform reports it;
+    /// the order of the result is unchanged
+    pub order_by_extent: bool,
+    /// Also split every file into chunks and report how much data A and B
+    /// share at the chunk level; reads every file whole
+    pub chunk_dedupe: Option<ChunkDedupeOptions>,
+    /// Leave out the files matched by `.gitignore` files in the compared
+    /// trees, see [`TraversalOptions::respect_gitignore`]
+    pub respect_gitignore: bool,
+    /// How deep below the roots files are collected, see
+    /// [`TraversalOptions::max_depth`]
+    pub max_depth: Option<usize>,
+    /// Leave out hidden files and directories, see
+    /// [`TraversalOptions::ignore_hidden`]
+    pub ignore_hidden: bool,
+    /// Only group files with one of these extensions, such as `jpg`,
+    /// compared case-insensitively and with or without the leading dot
+    pub extensions: Option<Vec<String>>,
+    /// Leave out files smaller than this many bytes. The size is the one
+    /// the metadata reports, even with `distrust_sizes`
+    pub min_file_size: u64,
+}
+
+impl FlatComparisonOptions {
+    /// Whether a file at `path` of `size` bytes passes `extensions` and
+    /// `min_file_size`
+    fn selects(&self, path: &Path, size: u64) -> bool {
+        if size < self.min_file_size {
+            return false;
+        }
+        let Some(extensions) = &self.extensions else {
+            return true;
+        };
+        let Some(extension) = path.extension().and_then(|ext| ext.to_str()) else {
+            return false;
+        };
+        extensions.iter().any(|wanted| {
+            wanted
+                .trim_start_matches('.')
+                .eq_ignore_ascii_case(extension)
+        })
+    }
+}
+
+impl Default for FlatComparisonOptions {
+    fn default() -> Self {
+        Self {
+            use_full_hash: false,
+            hash_algorithm: HashAlgorithm::default(),
+            case_insensitive: false,
+            root_paths: RootPaths::default(),
+            filter: None,
+            distrust_sizes: false,
+            hash_cache: None,
+            io_hints: true,
+            order_by_extent: false,
+            chunk_dedupe: None,
+            respect_gitignore: false,
+            max_depth: None,
+            ignore_hidden: false,
+            extensions: None,
+            min_file_size: 0,
+        }
+    }
+}
+
+/// Returns the indices of `paths` sorted by the physical offset of each
+/// file on disk, as reported by `fs`.
+///
+/// The sort is stable, and files without a known offset keep their
+/// relative order after all others, so without offsets the order is
+/// unchanged. Reading files in this order saves seeks on spinning disks.
+///
+/// # Examples
+///
+/// ```
+/// use dir_compare_core::RealFileSystem;
+/// use dir_compare_core::comparison::extent_order;
+/// use std::path::Path;
+///
+/// let order = extent_order(&RealFileSystem, [Path::new("a"), Path::new("b")]);
+/// assert_eq!(order.len(), 2);
+/// ```
+pub fn extent_order<'a>(
+    fs: &dyn FileSystem,
+    paths: impl IntoIterator<Item = &'a Path>,
+) -> Vec<usize> {
+    let offsets: Vec<Option<u64>> = paths
+        .into_iter()
+        .map(|path| fs.physical_offset(path))
+        .collect();
+    let mut order: Vec<usize> = (0..offsets.len()).collect();
+    order.sort_by_key(|&index| (offsets[index].is_none(), offsets[index]));
+    order
+}
+
+/// Include and exclude glob patterns selecting the entries a comparison
+/// lists, without writing an ignore file.
+///
+/// Patterns use the gitignore syntax of ignore files and are matched
+/// against paths relative to each root: `*` stays within one path
+/// component, `**` spans any number of them, a leading `/` anchors a
+/// pattern to the root, and a trailing `/` matches directories only.
+///
+/// Include patterns narrow the listing first: only files matching one of
+/// them, or lying below a matching directory, are kept, together with the
+/// directories leading to them. Exclude patterns then remove entries, and an
+/// excluded directory is not descended into. Entries skipped by the ignore
+/// file stay skipped either way.
+///
+/// With [`with_vcs_aware`](Self::with_vcs_aware), version-control metadata
+/// ([`VCS_METADATA`]) is excluded at any depth, and directories holding their
+/// own `.git` are marked as [`Entry::nested_repository`].
+///
+/// # Examples
+///
+/// ```
+/// use dir_compare_core::comparison::EntryFilter;
+/// use std::path::Path;
+///
+/// let filter = EntryFilter::new(&["src/**/*.rs"], &["/target/"]).unwrap();
+/// assert!(filter.matches(Path::new("src/main.rs"), false));
+/// assert!(!filter.matches(Path::new("README.md"), false));
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct EntryFilter {
+    include: Option<Gitignore>,
+    exclude: Option<Gitignore>,
+    vcs_aware: bool,
+}
+
+/// Names of version-control metadata excluded by a VCS-aware [`EntryFilter`].
+pub const VCS_METADATA: [&str; 3] = [".git", ".hg", ".svn"];
+
+/// Directories found to hold their own `.git` while walking one root.
+type NestedRepositories = Arc<Mutex<HashSet<PathBuf>>>;
+
+impl EntryFilter {
+    /// Compiles the include and exclude patterns.
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`std::io::ErrorKind::InvalidInput`] error naming the
+    /// first pattern that is not a valid glob.
+    pub fn new<S: AsRef<str>>(include: &[S], exclude: &[S]) -> std::io::Result<Self> {
+        Ok(Self {
+            include: Self::compile(include)?,
+            exclude: Self::compile(exclude)?,
+            vcs_aware: false,
+        })
+    }
+
+    /// Sets whether version-control metadata is excluded and nested
+    /// repositories are marked.
+    pub fn with_vcs_aware(mut self, vcs_aware: bool) -> Self {
+        self.vcs_aware = vcs_aware;
+        self
+    }
+
+    /// Returns true if version-control metadata is excluded.
+    pub fn vcs_aware(&self) -> bool {
+        self.vcs_aware
+    }
+
+    fn compile<S: AsRef<str>>(patterns: &[S]) -> std::io::Result<Option<Gitignore>> {
+        if patterns.is_empty() {
+            return Ok(None);
+        }
+        // Paths are matched relative to the compared root, so the matcher
+        // root must not strip anything
+        let mut builder = GitignoreBuilder::new(".");
+        for pattern in patterns {
+            let pattern = pattern.as_ref();
+            builder.add_line(None, pattern).map_err(|e| {
+                std::io::Error::new(
+                    std::io::ErrorKind::InvalidInput,
+                    format!("Invalid glob '{}': {}", pattern, e),
+                )
+            })?;
+        }
+        builder
+            .build()
+            .map(Some)
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidInput, e.to_string()))
+    }
+
+    /// Returns true if neither include nor exclude patterns were given and
+    /// version-control metadata is kept.
+    pub fn is_empty(&self) -> bool {
+        self.include.is_none() && self.exclude.is_none() && !self.vcs_aware
+    }
+
+    /// Returns whether an entry at `path`, relative to the compared root,
+    /// is kept.
+    ///
+    /// A directory counts as kept here only if it or one of its parents
+    /// matches an include pattern; during a comparison, directories leading
+    /// to included files are kept as well.
+    pub fn matches(&self, path: &Path, is_dir: bool) -> bool {
+        self.includes(path, is_dir) && !self.excludes(path, is_dir)
+    }
+
+    fn includes(&self, path: &Path, is_dir: bool) -> bool {
+        self.include.as_ref().is_none_or(|include| {
+            include
+                .matched_path_or_any_parents(path, is_dir)
+                .is_ignore()
+        })
+    }
+
+    fn excludes(&self, path: &Path, is_dir: bool) -> bool {
+        self.exclude.as_ref().is_some_and(|exclude| {
+            exclude
+                .matched_path_or_any_parents(path, is_dir)
+                .is_ignore()
+        })
+    }
+
+    /// Returns whether the walk lists `entry`, at `path` relative to the
+    /// root, recording it in `found` if it holds its own `.git`.
+    fn keeps_walked(
+        &self,
+        entry: &ignore::DirEntry,
+        path: Option<&Path>,
+        is_dir: bool,
+        found: Option<&NestedRepositories>,
+    ) -> bool {
+        if let Some(found) = found {
+            let name = entry.file_name();
+            if VCS_METADATA.iter().any(|vcs| name == *vcs) {
+                return false;
+            }
+            // Hidden entries may not reach this filter, so look for the
+            // `.git` directory (or a submodule's `.git` file) directly
+            if is_dir && entry.path().join(".git").exists() {
+                found
+                    .lock()
+                    .unwrap_or_else(|e| e.into_inner())
+                    .insert(entry.path().to_path_buf());
+            }
+        }
+        path.is_none_or(|path| !self.excludes(path, is_dir))
+    }
+
+    /// Drops the entries that do not match an include pattern, keeping
+    /// directories that match or lead to a kept entry.
+    fn retain_included(&self, entries: &mut Vec<Entry>, root: &Path) {
+        if self.include.is_none() {
+            return;
+        }
+        let relative = |entry: &Entry| {
+            entry
+                .path
+                .strip_prefix(root)
+                .unwrap_or(&entry.path)
+                .to_path_buf()
+        };
+        let mut parents: HashSet<PathBuf> = HashSet::new();
+        entries.retain(|entry| {
+            if entry.kind == EntryKind::Directory {
+                return true;
+            }
+            let path = relative(entry);
+            let kept = self.includes(&path, false);
+            if kept {
+                parents.extend(path.ancestors().skip(1).map(Path::to_path_buf));
+            }
+            kept
+        });
+        entries.retain(|entry| {
+            if entry.kind != EntryKind::Directory {
+                return true;
+            }
+            let path = relative(entry);
+            parents.contains(&path) || self.includes(&path, true)
+        });
+    }
 }
 
 /// Represents the type of a directory entry.
 ///
+/// With the `serde` feature, files and directories serialize as `"file"` and
+/// `"directory"`, and symlinks as `{"symlink": {"target": "..."}}`.
+///
 /// # Examples
 ///
 /// ```
@@ -55,13 +850,21 @@ pub struct FlatComparisonOptions {
 ///
 /// let file_kind = EntryKind::File;
 /// let dir_kind = EntryKind::Directory;
+/// let link_kind = EntryKind::Symlink { target: "../shared/config.toml".into() };
 /// ```
 #[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
 pub enum EntryKind {
     /// A regular file entry
     File,
     /// A directory entry
     Directory,
+    /// A symbolic link, which is never followed
+    Symlink {
+        /// The link target as stored in the link, which may be relative or dangling
+        target: PathBuf,
+    },
 }
 
 /// Represents a single entry (file or directory) found during directory traversal.
@@ -79,9 +882,12 @@ pub enum EntryKind {
 ///     abs_path: PathBuf::from("/abs/documents/report.txt"),
 ///     kind: EntryKind::File,
 ///     size: Some(1024),
+///     nested_repository: false,
+///     mtime: None,
 /// };
 /// ```
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Entry {
     /// The relative path of the entry from the root directory
     pub path: PathBuf,
@@ -89,8 +895,90 @@ pub struct Entry {
     pub abs_path: PathBuf,
     /// The type of entry (file or directory)
     pub kind: EntryKind,
-    /// The file size in bytes (None for directories)
+    /// The file size in bytes (None for directories and symlinks, and for
+    /// files listed with [`StatMode::Lazy`])
     pub size: Option<u64>,
+    /// Whether this directory holds its own `.git`, making its subtree an
+    /// independently versioned working copy; only detected by a VCS-aware
+    /// [`EntryFilter`]
+    pub nested_repository: bool,
+    /// The modification time of a file, read together with its size while
+    /// listing; `None` if it was not read, in which case strategies that need
+    /// it look it up
+    pub mtime: Option<SystemTime>,
+}
+
+/// Whether a strategy matched a pair of entries, and why not if it did not.
+///
+/// Returned by [`ComparisonStrategy::compare`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MatchVerdict {
+    /// The entries match
+    Match,
+    /// The entries differ for the given reason
+    Mismatch(MismatchReason),
+}
+
+impl MatchVerdict {
+    /// Returns true for [`MatchVerdict::Match`].
+    pub fn is_match(&self) -> bool {
+        *self == MatchVerdict::Match
+    }
+}
+
+/// Why a strategy did not match a same-path pair, kept in
+/// [`ComparisonResult::mismatch_reasons`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum MismatchReason {
+    /// One entry is a file, directory or symbolic link and the other is not
+    KindDiffers,
+    /// The files have different sizes, in bytes
+    SizeDiffers {
+        /// Size of the file in A
+        a: u64,
+        /// Size of the file in B
+        b: u64,
+    },
+    /// The files have the same size but different contents
+    ContentDiffers,
+    /// The symbolic links point to different targets
+    TargetDiffers,
+    /// The modification times differ by more than the tolerance
+    /// ([`MetadataStrategy`])
+    ModifiedDiffers,
+    /// A file could not be read; see [`ComparisonResult::warnings`]
+    Unreadable,
+}
+
+impl MismatchReason {
+    /// The reason two entries a strategy did not match differ, told by how
+    /// they were listed: their kinds, link targets and sizes. Entries that
+    /// look alike differ in content.
+    pub fn between(a: &Entry, b: &Entry) -> MismatchReason {
+        match (&a.kind, &b.kind) {
+            (EntryKind::File, EntryKind::File) => match (a.size, b.size) {
+                (Some(a), Some(b)) if a != b => MismatchReason::SizeDiffers { a, b },
+                _ => MismatchReason::ContentDiffers,
+            },
+            (EntryKind::Symlink { target: a }, EntryKind::Symlink { target: b }) if a != b => {
+                MismatchReason::TargetDiffers
+            }
+            (EntryKind::Directory, EntryKind::Directory)
+            | (EntryKind::Symlink { .. }, EntryKind::Symlink { .. }) => {
+                MismatchReason::ContentDiffers
+            }
+            _ => MismatchReason::KindDiffers,
+        }
+    }
+
+    /// The reason with the sides of A and B exchanged.
+    pub fn swapped(self) -> MismatchReason {
+        match self {
+            MismatchReason::SizeDiffers { a, b } => MismatchReason::SizeDiffers { a: b, b: a },
+            other => other,
+        }
+    }
 }
 
 /// Trait for comparing entries between two directories.
@@ -110,17 +998,24 @@ pub struct Entry {
 ///     abs_path: PathBuf::from("/abs/file.txt"),
 ///     kind: EntryKind::File,
 ///     size: Some(100),
+///     nested_repository: false,
+///     mtime: None,
 /// };
 /// let entry2 = Entry {
 ///     path: PathBuf::from("file.txt"),
 ///     abs_path: PathBuf::from("/abs/file.txt"),
 ///     kind: EntryKind::File,
 ///     size: Some(200),
+///     nested_repository: false,
+///     mtime: None,
 /// };
 ///
 /// assert!(strategy.matches(&entry1, &entry2));
 /// ```
-pub trait ComparisonStrategy {
+///
+/// Strategies must be `Sync` because [`compare_directories`] evaluates
+/// same-path pairs on several worker threads.
+pub trait ComparisonStrategy: Sync {
     /// Determines whether two entries match according to this strategy.
     ///
     /// # Arguments
@@ -132,6 +1027,74 @@ pub trait ComparisonStrategy {
     ///
     /// `true` if the entries match, `false` otherwise
     fn matches(&self, a: &Entry, b: &Entry) -> bool;
+
+    /// Like [`matches`](Self::matches), additionally reporting files that
+    /// could not be read to `warnings`.
+    ///
+    /// The comparison functions call this method. Strategies that read file
+    /// contents or metadata override it; the default calls `matches` and
+    /// reports nothing.
+    fn matches_reporting(&self, a: &Entry, b: &Entry, warnings: &mut Vec<FileWarning>) -> bool {
+        let _ = warnings;
+        self.matches(a, b)
+    }
+
+    /// Like [`matches`](Self::matches), telling why the entries differ if
+    /// they do.
+    fn compare(&self, a: &Entry, b: &Entry) -> MatchVerdict {
+        self.compare_reporting(a, b, &mut Vec::new())
+    }
+
+    /// Like [`compare`](Self::compare), additionally reporting files that
+    /// could not be read to `warnings`.
+    ///
+    /// The comparison functions call this method. The default calls
+    /// [`matches_reporting`](Self::matches_reporting); a mismatch that
+    /// added warnings is [`MismatchReason::Unreadable`], any other is
+    /// [`MismatchReason::between`] the entries. Strategies that reject pairs
+    /// for other reasons override it.
+    fn compare_reporting(
+        &self,
+        a: &Entry,
+        b: &Entry,
+        warnings: &mut Vec<FileWarning>,
+    ) -> MatchVerdict {
+        let reported = warnings.len();
+        if self.matches_reporting(a, b, warnings) {
+            MatchVerdict::Match
+        } else if warnings.len() > reported {
+            MatchVerdict::Mismatch(MismatchReason::Unreadable)
+        } else {
+            MatchVerdict::Mismatch(MismatchReason::between(a, b))
+        }
+    }
+
+    /// Whether entries whose relative paths differ only in case are paired.
+    ///
+    /// Defaults to `false`, which pairs identical paths only.
+    fn case_insensitive(&self) -> bool {
+        false
+    }
+
+    /// Bytes [`matches`](Self::matches) is expected to read to compare
+    /// `a` and `b`, by the sizes they were listed with, or `None` if it
+    /// compares them without reading their contents.
+    ///
+    /// Used by [`ComparisonPlan::estimate`]. Defaults to `None`, for
+    /// strategies that compare names and metadata only.
+    fn estimated_read(&self, a: &Entry, b: &Entry) -> Option<u64> {
+        let _ = (a, b);
+        None
+    }
+
+    /// The file system the strategy reads files through. The comparison
+    /// lists file metadata through it as well, and asks it for physical
+    /// offsets with [`ComparisonOptions::order_by_extent`].
+    ///
+    /// Defaults to [`RealFileSystem`].
+    fn file_system(&self) -> &dyn FileSystem {
+        &RealFileSystem
+    }
 }
 
 /// Comparison strategy that matches files based on filename only.
@@ -180,6 +1143,10 @@ impl ComparisonStrategy for FilenameOnlyStrategy {
 
         path_a_normalized == path_b_normalized
     }
+
+    fn case_insensitive(&self) -> bool {
+        self.case_insensitive
+    }
 }
 
 /// Comparison strategy that matches files by filename and size.
@@ -187,6 +1154,11 @@ impl ComparisonStrategy for FilenameOnlyStrategy {
 /// Files match if they have the same filename and the same file size.
 /// Directories are matched by filename only.
 ///
+/// Sizes come from the metadata listed with each entry. With
+/// [`with_distrust_sizes`](Self::with_distrust_sizes) they are instead
+/// measured by reading both files, for filesystems whose metadata reports
+/// 0 or stale sizes.
+///
 /// # Examples
 ///
 /// ```
@@ -196,6 +1168,8 @@ impl ComparisonStrategy for FilenameOnlyStrategy {
 /// ```
 pub struct FilenameSizeStrategy {
     case_insensitive: bool,
+    distrust_sizes: bool,
+    fs: SharedFileSystem,
 }
 
 impl FilenameSizeStrategy {
@@ -205,12 +1179,46 @@ impl FilenameSizeStrategy {
     ///
     /// * `case_insensitive` - If true, filenames are compared case-insensitively
     pub fn new(case_insensitive: bool) -> Self {
-        Self { case_insensitive }
+        Self {
+            case_insensitive,
+            distrust_sizes: false,
+            fs: Arc::new(RealFileSystem),
+        }
+    }
+
+    /// Sets whether file sizes are measured by reading instead of taken
+    /// from metadata.
+    pub fn with_distrust_sizes(mut self, distrust_sizes: bool) -> Self {
+        self.distrust_sizes = distrust_sizes;
+        self
+    }
+
+    /// Reads files through `fs` instead of `std::fs`.
+    pub fn with_file_system(mut self, fs: SharedFileSystem) -> Self {
+        self.fs = fs;
+        self
     }
 }
 
 impl ComparisonStrategy for FilenameSizeStrategy {
     fn matches(&self, a: &Entry, b: &Entry) -> bool {
+        self.matches_reporting(a, b, &mut Vec::new())
+    }
+
+    fn estimated_read(&self, a: &Entry, b: &Entry) -> Option<u64> {
+        whole_files_read(a, b).filter(|_| self.distrust_sizes)
+    }
+
+    fn matches_reporting(&self, a: &Entry, b: &Entry, warnings: &mut Vec<FileWarning>) -> bool {
+        self.compare_reporting(a, b, warnings).is_match()
+    }
+
+    fn compare_reporting(
+        &self,
+        a: &Entry,
+        b: &Entry,
+        warnings: &mut Vec<FileWarning>,
+    ) -> MatchVerdict {
         let name_match = {
             let name_a = if self.case_insensitive {
                 a.path.to_string_lossy().to_lowercase()
@@ -226,14 +1234,183 @@ impl ComparisonStrategy for FilenameSizeStrategy {
         };
 
         if !name_match {
-            return false;
+            return MatchVerdict::Mismatch(MismatchReason::between(a, b));
         }
 
-        match (&a.kind, &b.kind) {
-            (EntryKind::Directory, EntryKind::Directory) => true,
-            (EntryKind::File, EntryKind::File) => a.size == b.size,
-            _ => false,
-        }
+        let (size_a, size_b) = match (&a.kind, &b.kind) {
+            (EntryKind::File, EntryKind::File) if self.distrust_sizes => {
+                let mut read_len = |entry: &Entry| {
+                    read_len(self.fs.as_ref(), &entry.abs_path)
+                        .map_err(|e| {
+                            warnings.push(FileWarning::new(&entry.abs_path, WarningCause::Hash, &e))
+                        })
+                        .ok()
+                };
+                (read_len(a), read_len(b))
+            }
+            (EntryKind::File, EntryKind::File) => {
+                let mut size = |entry: &Entry| {
+                    entry.size.or_else(|| {
+                        self.fs
+                            .len(&entry.abs_path)
+                            .map_err(|e| {
+                                warnings.push(FileWarning::new(
+                                    &entry.abs_path,
+                                    WarningCause::Metadata,
+                                    &e,
+                                ))
+                            })
+                            .ok()
+                    })
+                };
+                (size(a), size(b))
+            }
+            (EntryKind::Directory, EntryKind::Directory) => return MatchVerdict::Match,
+            (EntryKind::Symlink { target: target_a }, EntryKind::Symlink { target: target_b })
+                if target_a == target_b =>
+            {
+                return MatchVerdict::Match;
+            }
+            _ => return MatchVerdict::Mismatch(MismatchReason::between(a, b)),
+        };
+        match (size_a, size_b) {
+            (Some(size_a), Some(size_b)) if size_a == size_b => MatchVerdict::Match,
+            (Some(a), Some(b)) => MatchVerdict::Mismatch(MismatchReason::SizeDiffers { a, b }),
+            _ => MatchVerdict::Mismatch(MismatchReason::Unreadable),
+        }
+    }
+
+    fn case_insensitive(&self) -> bool {
+        self.case_insensitive
+    }
+
+    fn file_system(&self) -> &dyn FileSystem {
+        self.fs.as_ref()
+    }
+}
+
+/// Default mtime tolerance of [`MetadataStrategy`], which absorbs the
+/// sub-second precision that many copy tools do not preserve.
+pub const DEFAULT_MTIME_TOLERANCE: Duration = Duration::from_secs(1);
+
+/// Comparison strategy that matches files by filename, size and
+/// modification time, like rsync's default quick check.
+///
+/// Files match if they have the same filename and size, and their
+/// modification times differ by at most the tolerance. A file whose
+/// modification time cannot be read never matches. Directories are matched
+/// by filename only.
+///
+/// # Examples
+///
+/// ```
+/// use dir_compare_core::{ComparisonStrategy, MetadataStrategy};
+/// use std::time::Duration;
+///
+/// // FAT32 stores modification times with 2-second granularity
+/// let strategy = MetadataStrategy::new(false, Duration::from_secs(2));
+/// ```
+pub struct MetadataStrategy {
+    case_insensitive: bool,
+    tolerance: Duration,
+    distrust_sizes: bool,
+    fs: SharedFileSystem,
+}
+
+impl MetadataStrategy {
+    /// Creates a new MetadataStrategy.
+    ///
+    /// # Arguments
+    ///
+    /// * `case_insensitive` - If true, filenames are compared case-insensitively
+    /// * `tolerance` - Largest modification time difference still treated as equal
+    pub fn new(case_insensitive: bool, tolerance: Duration) -> Self {
+        Self {
+            case_insensitive,
+            tolerance,
+            distrust_sizes: false,
+            fs: Arc::new(RealFileSystem),
+        }
+    }
+
+    /// Sets whether file sizes are measured by reading instead of taken
+    /// from metadata, as in [`FilenameSizeStrategy::with_distrust_sizes`].
+    pub fn with_distrust_sizes(mut self, distrust_sizes: bool) -> Self {
+        self.distrust_sizes = distrust_sizes;
+        self
+    }
+
+    /// Reads files through `fs` instead of `std::fs`.
+    pub fn with_file_system(mut self, fs: SharedFileSystem) -> Self {
+        self.fs = fs;
+        self
+    }
+}
+
+impl ComparisonStrategy for MetadataStrategy {
+    fn matches(&self, a: &Entry, b: &Entry) -> bool {
+        self.matches_reporting(a, b, &mut Vec::new())
+    }
+
+    fn estimated_read(&self, a: &Entry, b: &Entry) -> Option<u64> {
+        whole_files_read(a, b).filter(|_| self.distrust_sizes)
+    }
+
+    fn matches_reporting(&self, a: &Entry, b: &Entry, warnings: &mut Vec<FileWarning>) -> bool {
+        self.compare_reporting(a, b, warnings).is_match()
+    }
+
+    fn compare_reporting(
+        &self,
+        a: &Entry,
+        b: &Entry,
+        warnings: &mut Vec<FileWarning>,
+    ) -> MatchVerdict {
+        let sizes = FilenameSizeStrategy::new(self.case_insensitive)
+            .with_distrust_sizes(self.distrust_sizes)
+            .with_file_system(self.fs.clone());
+        let verdict = sizes.compare_reporting(a, b, warnings);
+        if !verdict.is_match() || a.kind != EntryKind::File {
+            return verdict;
+        }
+        let mut modified = |entry: &Entry| {
+            if let Some(mtime) = entry.mtime {
+                return Some(mtime);
+            }
+            self.fs
+                .metadata(&entry.abs_path)
+                .and_then(|metadata| metadata.modified())
+                .map_err(|e| {
+                    warnings.push(FileWarning::new(
+                        &entry.abs_path,
+                        WarningCause::Metadata,
+                        &e,
+                    ))
+                })
+                .ok()
+        };
+        match (modified(a), modified(b)) {
+            (Some(mtime_a), Some(mtime_b)) => {
+                let difference = mtime_a
+                    .duration_since(mtime_b)
+                    .or_else(|_| mtime_b.duration_since(mtime_a))
+                    .unwrap_or_default();
+                if difference <= self.tolerance {
+                    MatchVerdict::Match
+                } else {
+                    MatchVerdict::Mismatch(MismatchReason::ModifiedDiffers)
+                }
+            }
+            _ => MatchVerdict::Mismatch(MismatchReason::Unreadable),
+        }
+    }
+
+    fn case_insensitive(&self) -> bool {
+        self.case_insensitive
+    }
+
+    fn file_system(&self) -> &dyn FileSystem {
+        self.fs.as_ref()
     }
 }
 
@@ -250,7 +1427,9 @@ impl ComparisonStrategy for FilenameSizeStrategy {
 ///
 /// The FxHash algorithm is very fast but not cryptographically secure.
 /// Suitable for comparing file contents where collision resistance
-/// is not critical.
+/// is not critical. Files whose listed sizes differ are reported as
+/// different without being read, unless sizes are distrusted with
+/// [`with_distrust_sizes`](Self::with_distrust_sizes).
 ///
 /// # Examples
 ///
@@ -261,6 +1440,9 @@ impl ComparisonStrategy for FilenameSizeStrategy {
 /// ```
 pub struct FastHashStrategy {
     case_insensitive: bool,
+    io_hints: bool,
+    distrust_sizes: bool,
+    fs: SharedFileSystem,
 }
 
 impl FastHashStrategy {
@@ -270,12 +1452,50 @@ impl FastHashStrategy {
     ///
     /// * `case_insensitive` - If true, filenames are compared case-insensitively
     pub fn new(case_insensitive: bool) -> Self {
-        Self { case_insensitive }
+        Self {
+            case_insensitive,
+            io_hints: true,
+            distrust_sizes: false,
+            fs: Arc::new(RealFileSystem),
+        }
+    }
+
+    /// Sets whether files are opened with
+    /// [`FileSystem::open_sequential`], hinting the OS to read them ahead.
+    /// On by default.
+    pub fn with_io_hints(mut self, io_hints: bool) -> Self {
+        self.io_hints = io_hints;
+        self
+    }
+
+    /// Sets whether files with different listed sizes are hashed anyway,
+    /// without trusting the sizes from their metadata.
+    pub fn with_distrust_sizes(mut self, distrust_sizes: bool) -> Self {
+        self.distrust_sizes = distrust_sizes;
+        self
+    }
+
+    /// Reads files through `fs` instead of `std::fs`.
+    pub fn with_file_system(mut self, fs: SharedFileSystem) -> Self {
+        self.fs = fs;
+        self
     }
 }
 
 impl ComparisonStrategy for FastHashStrategy {
     fn matches(&self, a: &Entry, b: &Entry) -> bool {
+        self.matches_reporting(a, b, &mut Vec::new())
+    }
+
+    /// Both files whole, or nothing when their listed sizes differ.
+    fn estimated_read(&self, a: &Entry, b: &Entry) -> Option<u64> {
+        if !self.distrust_sizes && sizes_differ(a, b) {
+            return None;
+        }
+        whole_files_read(a, b)
+    }
+
+    fn matches_reporting(&self, a: &Entry, b: &Entry, warnings: &mut Vec<FileWarning>) -> bool {
         let name_match = {
             let name_a = if self.case_insensitive {
                 a.path.to_string_lossy().to_lowercase()
@@ -297,18 +1517,240 @@ impl ComparisonStrategy for FastHashStrategy {
         match (&a.kind, &b.kind) {
             (EntryKind::Directory, EntryKind::Directory) => true,
             (EntryKind::File, EntryKind::File) => {
-                let hash_a = compute_file_hash(&a.abs_path);
-                let hash_b = compute_file_hash(&b.abs_path);
-                match (hash_a, hash_b) {
-                    (Ok(hash_a), Ok(hash_b)) => hash_a == hash_b,
-                    (Err(_), Err(_)) => false,
-                    (Ok(_), Err(_)) => false,
-                    (Err(_), Ok(_)) => false,
+                if !self.distrust_sizes && sizes_differ(a, b) {
+                    return false;
                 }
+                let hash = |path: &Path| {
+                    full_hash_in(self.fs.as_ref(), path, HashAlgorithm::FxHash, self.io_hints)
+                };
+                hash_pair(&a.abs_path, &b.abs_path, hash, warnings)
+                    .is_some_and(|(hash_a, hash_b)| hash_a == hash_b)
+            }
+            (EntryKind::Symlink { target: a }, EntryKind::Symlink { target: b }) => a == b,
+            _ => false,
+        }
+    }
+
+    fn case_insensitive(&self) -> bool {
+        self.case_insensitive
+    }
+
+    fn file_system(&self) -> &dyn FileSystem {
+        self.fs.as_ref()
+    }
+}
+
+/// Comparison strategy that matches files by filename and BLAKE3 content hash.
+///
+/// Files match if they have the same filename and identical content.
+/// Directories are matched by filename only.
+///
+/// # Performance
+///
+/// BLAKE3 is collision-resistant like SHA-256 and hashes several times
+/// faster. With the `rayon` feature of this crate, large files are
+/// memory-mapped and hashed on all cores, bypassing the file system set with
+/// [`with_file_system`](Self::with_file_system).
+///
+/// # Examples
+///
+/// ```
+/// use dir_compare_core::{Blake3Strategy, ComparisonStrategy};
+///
+/// let strategy = Blake3Strategy::new(false);
+/// ```
+pub struct Blake3Strategy {
+    case_insensitive: bool,
+    io_hints: bool,
+    fs: SharedFileSystem,
+}
+
+impl Blake3Strategy {
+    /// Creates a new Blake3Strategy.
+    ///
+    /// # Arguments
+    ///
+    /// * `case_insensitive` - If true, filenames are compared case-insensitively
+    pub fn new(case_insensitive: bool) -> Self {
+        Self {
+            case_insensitive,
+            io_hints: true,
+            fs: Arc::new(RealFileSystem),
+        }
+    }
+
+    /// Sets whether files are opened with
+    /// [`FileSystem::open_sequential`], hinting the OS to read them ahead.
+    /// On by default.
+    pub fn with_io_hints(mut self, io_hints: bool) -> Self {
+        self.io_hints = io_hints;
+        self
+    }
+
+    /// Reads files through `fs` instead of `std::fs`.
+    pub fn with_file_system(mut self, fs: SharedFileSystem) -> Self {
+        self.fs = fs;
+        self
+    }
+}
+
+impl ComparisonStrategy for Blake3Strategy {
+    fn matches(&self, a: &Entry, b: &Entry) -> bool {
+        self.matches_reporting(a, b, &mut Vec::new())
+    }
+
+    fn estimated_read(&self, a: &Entry, b: &Entry) -> Option<u64> {
+        whole_files_read(a, b)
+    }
+
+    fn matches_reporting(&self, a: &Entry, b: &Entry, warnings: &mut Vec<FileWarning>) -> bool {
+        if !FilenameOnlyStrategy::new(self.case_insensitive).matches(a, b) {
+            return false;
+        }
+
+        match (&a.kind, &b.kind) {
+            (EntryKind::Directory, EntryKind::Directory) => true,
+            (EntryKind::File, EntryKind::File) => {
+                let hash = |path: &Path| {
+                    full_hash_in(self.fs.as_ref(), path, HashAlgorithm::Blake3, self.io_hints)
+                };
+                hash_pair(&a.abs_path, &b.abs_path, hash, warnings)
+                    .is_some_and(|(hash_a, hash_b)| hash_a == hash_b)
             }
+            (EntryKind::Symlink { target: a }, EntryKind::Symlink { target: b }) => a == b,
+            _ => false,
+        }
+    }
+
+    fn case_insensitive(&self) -> bool {
+        self.case_insensitive
+    }
+
+    fn file_system(&self) -> &dyn FileSystem {
+        self.fs.as_ref()
+    }
+}
+
+/// Content hashes of files, each computed at most once and shared by the
+/// comparisons that read them.
+///
+/// A [`CachedHashStrategy`] and flat mode with
+/// [`FlatComparisonOptions::hash_cache`] look hashes up here before reading a
+/// file, so a hierarchy and a flat comparison of the same trees read every
+/// file once. Files are hashed whole or sampled, with the algorithm given to
+/// [`new`](Self::new); failed reads are not cached.
+///
+/// # Examples
+///
+/// ```
+/// use dir_compare_core::{CachedHashStrategy, HashAlgorithm, HashCache};
+/// use std::sync::Arc;
+///
+/// let cache = Arc::new(HashCache::new(HashAlgorithm::Blake3, true));
+/// let strategy = CachedHashStrategy::new(false, Arc::clone(&cache));
+/// assert_eq!(cache.files_hashed(), 0);
+/// ```
+#[derive(Debug, Default)]
+pub struct HashCache {
+    algorithm: HashAlgorithm,
+    use_full_hash: bool,
+    hashes: Mutex<HashMap<PathBuf, String>>,
+    files_hashed: AtomicUsize,
+}
+
+impl HashCache {
+    /// Creates an empty cache hashing with `algorithm`, whole files if
+    /// `use_full_hash` is set and samples otherwise.
+    pub fn new(algorithm: HashAlgorithm, use_full_hash: bool) -> Self {
+        Self {
+            algorithm,
+            use_full_hash,
+            ..Default::default()
+        }
+    }
+
+    /// Returns the hash of the file at `path`, reading it only on the first
+    /// call for that path.
+    pub fn hash(&self, path: &Path) -> std::io::Result<String> {
+        if let Some(hash) = self.hashes.lock().unwrap().get(path) {
+            return Ok(hash.clone());
+        }
+        let hash = if self.use_full_hash {
+            compute_full_hash(path, self.algorithm)?
+        } else {
+            sampled_hash_with(path, self.algorithm)?
+        };
+        self.files_hashed.fetch_add(1, Ordering::Relaxed);
+        self.hashes
+            .lock()
+            .unwrap()
+            .insert(path.to_path_buf(), hash.clone());
+        Ok(hash)
+    }
+
+    /// Number of files read so far; each path is counted once.
+    pub fn files_hashed(&self) -> usize {
+        self.files_hashed.load(Ordering::Relaxed)
+    }
+}
+
+/// Comparison strategy that matches files by filename and a content hash
+/// from a shared [`HashCache`].
+///
+/// Directories are matched by filename only. Use it to run a hierarchy
+/// comparison next to a flat one over the same directories without hashing
+/// any file twice.
+pub struct CachedHashStrategy {
+    case_insensitive: bool,
+    cache: Arc<HashCache>,
+}
+
+impl CachedHashStrategy {
+    /// Creates a new CachedHashStrategy.
+    ///
+    /// # Arguments
+    ///
+    /// * `case_insensitive` - If true, filenames are compared case-insensitively
+    /// * `cache` - The cache hashes are looked up in and added to
+    pub fn new(case_insensitive: bool, cache: Arc<HashCache>) -> Self {
+        Self {
+            case_insensitive,
+            cache,
+        }
+    }
+}
+
+impl ComparisonStrategy for CachedHashStrategy {
+    fn matches(&self, a: &Entry, b: &Entry) -> bool {
+        self.matches_reporting(a, b, &mut Vec::new())
+    }
+
+    fn estimated_read(&self, a: &Entry, b: &Entry) -> Option<u64> {
+        whole_files_read(a, b)
+    }
+
+    fn matches_reporting(&self, a: &Entry, b: &Entry, warnings: &mut Vec<FileWarning>) -> bool {
+        if !FilenameOnlyStrategy::new(self.case_insensitive).matches(a, b) {
+            return false;
+        }
+
+        match (&a.kind, &b.kind) {
+            (EntryKind::Directory, EntryKind::Directory) => true,
+            (EntryKind::File, EntryKind::File) => hash_pair(
+                &a.abs_path,
+                &b.abs_path,
+                |path| self.cache.hash(path),
+                warnings,
+            )
+            .is_some_and(|(hash_a, hash_b)| hash_a == hash_b),
+            (EntryKind::Symlink { target: a }, EntryKind::Symlink { target: b }) => a == b,
             _ => false,
         }
     }
+
+    fn case_insensitive(&self) -> bool {
+        self.case_insensitive
+    }
 }
 
 /// Comparison strategy that matches files by filename and sampled content hash.
@@ -317,6 +1759,12 @@ impl ComparisonStrategy for FastHashStrategy {
 /// Files match if they have the same filename and identical sampled hash.
 /// Directories are matched by filename only.
 ///
+/// Samples are placed by the size from the file's metadata, which also
+/// prefixes the hash, so files whose listed sizes differ are reported as
+/// different without being read. With
+/// [`with_distrust_sizes`](Self::with_distrust_sizes) the whole file is
+/// hashed instead, without a size prefix.
+///
 /// # Examples
 ///
 /// ```
@@ -327,6 +1775,10 @@ impl ComparisonStrategy for FastHashStrategy {
 pub struct SampledHashStrategy {
     case_insensitive: bool,
     verify_on_match: bool,
+    distrust_sizes: bool,
+    io_hints: bool,
+    fs: SharedFileSystem,
+    profiler: Option<Arc<DataProfiler>>,
 }
 
 impl SampledHashStrategy {
@@ -340,8 +1792,41 @@ impl SampledHashStrategy {
         Self {
             case_insensitive,
             verify_on_match,
+            distrust_sizes: false,
+            io_hints: true,
+            fs: Arc::new(RealFileSystem),
+            profiler: None,
         }
     }
+
+    /// Records the format class and size of the file from directory A of
+    /// every file pair sampled in `profiler`; pairs whose sizes differ are
+    /// not opened and not recorded.
+    pub fn with_data_profiler(mut self, profiler: Arc<DataProfiler>) -> Self {
+        self.profiler = Some(profiler);
+        self
+    }
+
+    /// Sets whether files read whole, to verify a match or without trusting
+    /// their size, are opened with [`FileSystem::open_sequential`]. The
+    /// samples themselves are read without hints. On by default.
+    pub fn with_io_hints(mut self, io_hints: bool) -> Self {
+        self.io_hints = io_hints;
+        self
+    }
+
+    /// Sets whether files are hashed whole, without trusting the size from
+    /// their metadata.
+    pub fn with_distrust_sizes(mut self, distrust_sizes: bool) -> Self {
+        self.distrust_sizes = distrust_sizes;
+        self
+    }
+
+    /// Reads files through `fs` instead of `std::fs`.
+    pub fn with_file_system(mut self, fs: SharedFileSystem) -> Self {
+        self.fs = fs;
+        self
+    }
 }
 
 /// Sample size in bytes for each sampled block.
@@ -358,6 +1843,10 @@ const SAMPLE_SIZE: u64 = 431;
 /// detection accuracy with I/O efficiency.
 const SAMPLE_COUNT: u64 = 7;
 
+/// Size in bytes from which [`SampledHashStrategy`] reads samples of a file;
+/// smaller files are hashed whole.
+pub const SAMPLING_THRESHOLD: u64 = SAMPLE_COUNT * SAMPLE_SIZE;
+
 /// Compute sampled hash for a file
 ///
 /// This function computes a SHA-256 hash using a hybrid approach that balances
@@ -399,45 +1888,108 @@ const SAMPLE_COUNT: u64 = 7;
 /// ```
 #[doc(hidden)]
 pub fn compute_sampled_hash(path: &Path) -> String {
-    _compute_sampled_hash_internal(path)
+    compute_sampled_hash_with(path, HashAlgorithm::Sha256)
 }
 
-fn _compute_sampled_hash_internal(path: &Path) -> String {
-    use std::fs::File;
-    use std::io::{Read, Seek, SeekFrom};
+/// Computes the sampled hash of [`compute_sampled_hash`] with another
+/// algorithm.
+///
+/// This function is exposed for testing purposes.
+#[doc(hidden)]
+pub fn compute_sampled_hash_with(path: &Path, algorithm: HashAlgorithm) -> String {
+    // A unique marker that does not match the hash of any other file
+    sampled_hash_with(path, algorithm).unwrap_or_else(|_| format!("ERROR:{}", path.display()))
+}
 
-    let mut file = match File::open(path) {
-        Ok(f) => f,
-        Err(e) => {
-            eprintln!("Warning: Could not open {}: {}", path.display(), e);
-            // Return a unique error marker that won't match another error
-            return format!("ERROR:{}", path.display());
-        }
-    };
+/// Computes the full-file hash used by flat mode with `--full-hash`.
+///
+/// This function is exposed for testing purposes.
+#[doc(hidden)]
+pub fn compute_full_hash(path: &Path, algorithm: HashAlgorithm) -> std::io::Result<String> {
+    full_hash_in(&RealFileSystem, path, algorithm, true)
+}
 
-    let size = match file.metadata() {
-        Ok(m) => m.len(),
-        Err(e) => {
-            eprintln!(
-                "Warning: Could not get metadata for {}: {}",
-                path.display(),
-                e
-            );
-            return format!("ERROR:{}", path.display());
+/// Opens a file that is read whole, with read-ahead hints if `io_hints`.
+fn open_whole(
+    fs: &dyn FileSystem,
+    path: &Path,
+    io_hints: bool,
+) -> std::io::Result<Box<dyn std::io::Read>> {
+    if io_hints {
+        fs.open_sequential(path)
+    } else {
+        fs.open(path)
+    }
+}
+
+/// Hashes a whole file read through `fs`.
+///
+/// With the `rayon` feature, BLAKE3 memory-maps a file of a
+/// [local](FileSystem::is_local) `fs` and hashes it on all cores instead;
+/// blake3 falls back to reading small files.
+pub(crate) fn full_hash_in(
+    fs: &dyn FileSystem,
+    path: &Path,
+    algorithm: HashAlgorithm,
+    io_hints: bool,
+) -> std::io::Result<String> {
+    use std::io::Read;
+
+    #[cfg(feature = "rayon")]
+    if algorithm == HashAlgorithm::Blake3 && fs.is_local() {
+        if io_hints {
+            // Starts the read-ahead, which the mapping then finds in the
+            // page cache
+            drop(fs.open_sequential(path)?);
         }
-    };
+        let mut hasher = blake3::Hasher::new();
+        hasher.update_mmap_rayon(path)?;
+        count_hashed(hasher.count());
+        return Ok(hasher.finalize().to_hex().to_string());
+    }
+    let mut reader = open_whole(fs, path, io_hints)?;
+    let mut hasher = ContentHasher::new(algorithm);
+    let mut buffer = [0u8; 8192];
+    loop {
+        let bytes_read = reader.read(&mut buffer)?;
+        if bytes_read == 0 {
+            break;
+        }
+        hasher.update(&buffer[..bytes_read]);
+        count_hashed(bytes_read as u64);
+    }
+    Ok(hasher.finalize())
+}
+
+fn sampled_hash_with(path: &Path, algorithm: HashAlgorithm) -> std::io::Result<String> {
+    sampled_hash_in(&RealFileSystem, path, algorithm)
+}
+
+/// Computes the sampled hash of [`compute_sampled_hash`], taking the size
+/// from `fs`.
+fn sampled_hash_in(
+    fs: &dyn FileSystem,
+    path: &Path,
+    algorithm: HashAlgorithm,
+) -> std::io::Result<String> {
+    use std::fs::File;
+    use std::io::{Read, Seek, SeekFrom};
+
+    let mut file = File::open(path)?;
+    let size = fs.len(path)?;
 
     // File size is u64 which is exactly 8 bytes, so no overflow check needed
     // Add file size (8 bytes, big-endian) to the beginning of the hash
-    let mut hasher = Sha256::new();
-    hasher.update(size.to_be_bytes());
+    let mut hasher = ContentHasher::new(algorithm);
+    hasher.update(&size.to_be_bytes());
 
-    if size < SAMPLE_COUNT * SAMPLE_SIZE {
+    if size < SAMPLING_THRESHOLD {
         // File is smaller than total sample size, read entire file
         let mut buffer = Vec::new();
-        if file.read_to_end(&mut buffer).is_ok() {
-            hasher.update(&buffer);
-        }
+        file.read_to_end(&mut buffer)?;
+        warn_if_misreported(path, size, buffer.len() as u64);
+        hasher.update(&buffer);
+        count_hashed(buffer.len() as u64);
     } else {
         // File is large enough for sampling strategy
         // Allocate buffer once before loop for better performance
@@ -452,42 +2004,168 @@ fn _compute_sampled_hash_internal(path: &Path) -> String {
         // 7*431 = 3017 bytes for full sampling. The condition above ensures this.
 
         // Sample 1: first 431 bytes
-        if file.seek(SeekFrom::Start(0)).is_ok() && file.read_exact(&mut buffer).is_ok() {
-            hasher.update(&buffer);
-        }
+        file.seek(SeekFrom::Start(0))?;
+        file.read_exact(&mut buffer)?;
+        hasher.update(&buffer);
 
         // Samples 2-6: interior samples evenly distributed
         for i in 1..=5 {
             // Calculate offset for interior sample
             // The min() clamps to prevent overlap with final sample
             let offset = std::cmp::min(SAMPLE_SIZE + i * step, size - 2 * SAMPLE_SIZE);
-            if file.seek(SeekFrom::Start(offset)).is_ok() && file.read_exact(&mut buffer).is_ok() {
-                hasher.update(&buffer);
-            }
+            file.seek(SeekFrom::Start(offset))?;
+            file.read_exact(&mut buffer)?;
+            hasher.update(&buffer);
         }
 
         // Sample 7: last 431 bytes
-        if file.seek(SeekFrom::Start(size - SAMPLE_SIZE)).is_ok()
-            && file.read_exact(&mut buffer).is_ok()
-        {
-            hasher.update(&buffer);
+        file.seek(SeekFrom::Start(size - SAMPLE_SIZE))?;
+        file.read_exact(&mut buffer)?;
+        hasher.update(&buffer);
+        count_hashed(SAMPLE_COUNT * SAMPLE_SIZE);
+    }
+
+    Ok(hasher.finalize())
+}
+
+/// Fed to the hasher in place of the size prefix by [`unsized_hash`], so
+/// that its hashes never equal size-prefixed ones.
+const UNSIZED_NAMESPACE: &[u8] = b"dir-compare:unsized\0";
+
+/// Hashes a whole file read through `fs`, without trusting the size from
+/// its metadata. Returns the hash and the number of bytes read.
+fn unsized_hash(
+    fs: &dyn FileSystem,
+    path: &Path,
+    algorithm: HashAlgorithm,
+    io_hints: bool,
+) -> std::io::Result<(String, u64)> {
+    use std::io::Read;
+
+    let mut reader = open_whole(fs, path, io_hints)?;
+    let mut hasher = ContentHasher::new(algorithm);
+    hasher.update(UNSIZED_NAMESPACE);
+    let mut buffer = [0u8; 8192];
+    let mut len = 0u64;
+    loop {
+        let bytes_read = reader.read(&mut buffer)?;
+        if bytes_read == 0 {
+            break;
         }
+        hasher.update(&buffer[..bytes_read]);
+        len += bytes_read as u64;
     }
+    count_hashed(len);
+    if let Ok(size) = fs.len(path) {
+        warn_if_misreported(path, size, len);
+    }
+    Ok((hasher.finalize(), len))
+}
 
-    format!("{:x}", hasher.finalize())
+/// Measures a file by reading it through `fs`.
+fn read_len(fs: &dyn FileSystem, path: &Path) -> std::io::Result<u64> {
+    let len = std::io::copy(&mut fs.open(path)?, &mut std::io::sink())?;
+    if let Ok(size) = fs.len(path) {
+        warn_if_misreported(path, size, len);
+    }
+    Ok(len)
 }
 
-impl ComparisonStrategy for SampledHashStrategy {
-    fn matches(&self, a: &Entry, b: &Entry) -> bool {
-        let name_match = {
-            let name_a = if self.case_insensitive {
-                a.path.to_string_lossy().to_lowercase()
-            } else {
-                a.path.to_string_lossy().to_string()
-            };
-            let name_b = if self.case_insensitive {
-                b.path.to_string_lossy().to_lowercase()
-            } else {
+/// Logs a file whose metadata reports 0 bytes although reading returned
+/// data, as procfs-like and freshly written files do.
+fn warn_if_misreported(path: &Path, size: u64, read: u64) {
+    if size == 0 && read > 0 {
+        logger::warn_keyed_with(
+            "misreported_size",
+            &format!(
+                "{} reports a size of 0 but {} bytes were read",
+                path.display(),
+                read
+            ),
+            &[("path", &path.display().to_string())],
+        );
+    }
+}
+
+/// One of the digests of [`HashAlgorithm`], fed incrementally.
+enum ContentHasher {
+    Sha256(Sha256),
+    Blake3(Box<blake3::Hasher>),
+    FxHash(fxhash::FxHasher),
+}
+
+impl ContentHasher {
+    fn new(algorithm: HashAlgorithm) -> Self {
+        match algorithm {
+            HashAlgorithm::Sha256 => ContentHasher::Sha256(Sha256::new()),
+            HashAlgorithm::Blake3 => ContentHasher::Blake3(Box::default()),
+            HashAlgorithm::FxHash => ContentHasher::FxHash(fxhash::FxHasher::default()),
+        }
+    }
+
+    fn update(&mut self, bytes: &[u8]) {
+        use std::hash::Hasher;
+
+        match self {
+            ContentHasher::Sha256(hasher) => hasher.update(bytes),
+            ContentHasher::Blake3(hasher) => {
+                hasher.update(bytes);
+            }
+            ContentHasher::FxHash(hasher) => hasher.write(bytes),
+        }
+    }
+
+    /// Returns the digest as lowercase hex.
+    fn finalize(self) -> String {
+        use std::hash::Hasher;
+
+        match self {
+            ContentHasher::Sha256(hasher) => format!("{:x}", hasher.finalize()),
+            ContentHasher::Blake3(hasher) => hasher.finalize().to_hex().to_string(),
+            ContentHasher::FxHash(hasher) => format!("{:016x}", hasher.finish()),
+        }
+    }
+}
+
+impl ComparisonStrategy for SampledHashStrategy {
+    fn matches(&self, a: &Entry, b: &Entry) -> bool {
+        self.matches_reporting(a, b, &mut Vec::new())
+    }
+
+    /// Samples of both files, or both files whole when sizes are not
+    /// trusted. With verification, matching pairs are read whole as well;
+    /// the estimate assumes every pair of equal sizes matches. Pairs whose
+    /// sizes differ are not read.
+    fn estimated_read(&self, a: &Entry, b: &Entry) -> Option<u64> {
+        let whole = whole_files_read(a, b)?;
+        if self.distrust_sizes {
+            return Some(whole);
+        }
+        if sizes_differ(a, b) {
+            return None;
+        }
+        let sampled = |entry: &Entry| {
+            let size = entry.size.unwrap_or(0);
+            if size < SAMPLING_THRESHOLD {
+                size
+            } else {
+                SAMPLE_COUNT * SAMPLE_SIZE
+            }
+        };
+        let verified = if self.verify_on_match { whole } else { 0 };
+        Some(sampled(a) + sampled(b) + verified)
+    }
+
+    fn matches_reporting(&self, a: &Entry, b: &Entry, warnings: &mut Vec<FileWarning>) -> bool {
+        let name_match = {
+            let name_a = if self.case_insensitive {
+                a.path.to_string_lossy().to_lowercase()
+            } else {
+                a.path.to_string_lossy().to_string()
+            };
+            let name_b = if self.case_insensitive {
+                b.path.to_string_lossy().to_lowercase()
+            } else {
                 b.path.to_string_lossy().to_string()
             };
             name_a == name_b
@@ -500,31 +2178,58 @@ impl ComparisonStrategy for SampledHashStrategy {
         match (&a.kind, &b.kind) {
             (EntryKind::Directory, EntryKind::Directory) => true,
             (EntryKind::File, EntryKind::File) => {
-                let hash_a = _compute_sampled_hash_internal(&a.abs_path);
-                let hash_b = _compute_sampled_hash_internal(&b.abs_path);
-
-                if hash_a != hash_b {
-                    false
-                } else if self.verify_on_match {
-                    let full_a = compute_file_hash_sha256(&a.abs_path);
-                    let full_b = compute_file_hash_sha256(&b.abs_path);
-                    full_a == full_b
-                } else {
-                    true
+                if !self.distrust_sizes && sizes_differ(a, b) {
+                    return false;
+                }
+                if let Some(profiler) = &self.profiler {
+                    let size = a.size.or_else(|| self.fs.len(&a.abs_path).ok());
+                    profiler.record(self.fs.as_ref(), &a.abs_path, size.unwrap_or(0));
+                }
+                let sampled = |path: &Path| {
+                    if self.distrust_sizes {
+                        unsized_hash(self.fs.as_ref(), path, HashAlgorithm::Sha256, self.io_hints)
+                            .map(|(hash, _)| hash)
+                    } else {
+                        sampled_hash_in(self.fs.as_ref(), path, HashAlgorithm::Sha256)
+                    }
+                };
+                match hash_pair(&a.abs_path, &b.abs_path, sampled, warnings) {
+                    Some((hash_a, hash_b)) if hash_a == hash_b => {
+                        let full = |path: &Path| {
+                            full_hash_in(
+                                self.fs.as_ref(),
+                                path,
+                                HashAlgorithm::Sha256,
+                                self.io_hints,
+                            )
+                        };
+                        !self.verify_on_match
+                            || hash_pair(&a.abs_path, &b.abs_path, full, warnings)
+                                .is_some_and(|(full_a, full_b)| full_a == full_b)
+                    }
+                    _ => false,
                 }
             }
+            (EntryKind::Symlink { target: a }, EntryKind::Symlink { target: b }) => a == b,
             _ => false,
         }
     }
+
+    fn case_insensitive(&self) -> bool {
+        self.case_insensitive
+    }
+
+    fn file_system(&self) -> &dyn FileSystem {
+        self.fs.as_ref()
+    }
 }
 
-fn compute_file_hash(path: &std::path::Path) -> std::io::Result<String> {
-    use std::fs::File;
+/// Hashes everything `reader` yields with the algorithm of [`FastHashStrategy`].
+pub(crate) fn hash_reader(reader: impl std::io::Read) -> std::io::Result<String> {
     use std::hash::Hasher;
     use std::io::{BufReader, Read};
 
-    let file = File::open(path)?;
-    let mut reader = BufReader::new(file);
+    let mut reader = BufReader::new(reader);
     let mut hasher = fxhash::FxHasher::default();
     let mut buffer = [0u8; 8192];
     loop {
@@ -537,33 +2242,36 @@ fn compute_file_hash(path: &std::path::Path) -> std::io::Result<String> {
     Ok(format!("{:016x}", hasher.finish()))
 }
 
-fn compute_file_hash_sha256(path: &std::path::Path) -> String {
-    use std::fs::File;
-    use std::io::{BufReader, Read};
+/// Hashes the files of a pair with `hash`, reporting each file that cannot
+/// be read. Returns `None` if either hash failed.
+fn hash_pair(
+    a: &Path,
+    b: &Path,
+    hash: impl Fn(&Path) -> std::io::Result<String>,
+    warnings: &mut Vec<FileWarning>,
+) -> Option<(String, String)> {
+    let mut hash_reporting = |path: &Path| {
+        hash(path)
+            .map_err(|e| warnings.push(FileWarning::new(path, WarningCause::Hash, &e)))
+            .ok()
+    };
+    let hash_a = hash_reporting(a);
+    let hash_b = hash_reporting(b);
+    Some((hash_a?, hash_b?))
+}
 
-    match File::open(path) {
-        Ok(file) => {
-            let mut reader = BufReader::new(file);
-            let mut hasher = Sha256::new();
-            let mut buffer = [0u8; 8192];
-            loop {
-                let bytes_read = match reader.read(&mut buffer) {
-                    Ok(0) => break,
-                    Ok(n) => n,
-                    Err(e) => {
-                        eprintln!("Warning: Error reading {}: {}", path.display(), e);
-                        break;
-                    }
-                };
-                hasher.update(&buffer[..bytes_read]);
-            }
-            format!("{:x}", hasher.finalize())
-        }
-        Err(e) => {
-            eprintln!("Warning: Could not open {}: {}", path.display(), e);
-            // Return a unique error marker that won't match another error
-            format!("ERROR:{}", path.display())
-        }
+/// Whether the listed sizes of `a` and `b` are both known and differ, which
+/// proves their contents differ without reading them.
+fn sizes_differ(a: &Entry, b: &Entry) -> bool {
+    matches!((a.size, b.size), (Some(size_a), Some(size_b)) if size_a != size_b)
+}
+
+/// Bytes read to hash `a` and `b` whole if both are files, by their listed
+/// sizes; unknown sizes count as 0.
+pub(crate) fn whole_files_read(a: &Entry, b: &Entry) -> Option<u64> {
+    match (&a.kind, &b.kind) {
+        (EntryKind::File, EntryKind::File) => Some(a.size.unwrap_or(0) + b.size.unwrap_or(0)),
+        _ => None,
     }
 }
 
@@ -573,6 +2281,7 @@ struct FileInfo {
     path: PathBuf,
     size: u64,
     hash: String,
+    inode: Option<InodeKey>,
 }
 
 /// Compares two directories using flat mode (content-based matching).
@@ -589,8 +2298,8 @@ struct FileInfo {
 ///
 /// # Returns
 ///
-/// A `FlatComparisonResult` containing content-based groupings, or an I/O error
-/// if directory traversal fails.
+/// A `FlatComparisonResult` containing content-based groupings, or a
+/// [`ComparisonError`] if a root directory or the ignore file cannot be read.
 ///
 /// # Example
 ///
@@ -610,75 +2319,184 @@ pub fn compare_directories_flat(
     dir_b: &Path,
     options: &FlatComparisonOptions,
     ignore_file_path: Option<&Path>,
-) -> std::io::Result<FlatComparisonResult> {
-    // Phase 1: Collect all files from both directories
-    let files_a = collect_files_flat(dir_a, ignore_file_path)?;
-    let files_b = collect_files_flat(dir_b, ignore_file_path)?;
+) -> Result<FlatComparisonResult, ComparisonError> {
+    Comparison::new(dir_a, dir_b)
+        .flat_options(options.clone())
+        .ignore_file(ignore_file_path.map(Path::to_path_buf))
+        .run_flat()
+}
 
-    // Phase 2: Compute hashes for all files
-    let files_a_with_hash: Vec<FileInfo> = files_a
-        .into_iter()
-        .map(|(path, abs_path, size)| {
-            let hash = if options.use_full_hash {
-                compute_file_hash_sha256(&abs_path)
-            } else {
-                _compute_sampled_hash_internal(&abs_path)
-            };
-            FileInfo { path, size, hash }
-        })
-        .collect();
+/// Compares two directories in flat mode like [`compare_directories_flat`],
+/// reporting progress to `progress`.
+///
+/// Only the chunk-level dedupe analysis of
+/// [`FlatComparisonOptions::chunk_dedupe`] reports progress: a
+/// [`ProgressPhase::Chunking`] update for every file read, possibly from
+/// several threads at once.
+pub fn compare_directories_flat_with_progress(
+    dir_a: &Path,
+    dir_b: &Path,
+    options: &FlatComparisonOptions,
+    ignore_file_path: Option<&Path>,
+    progress: &(dyn Fn(&Progress) + Send + Sync),
+) -> Result<FlatComparisonResult, ComparisonError> {
+    Comparison::new(dir_a, dir_b)
+        .flat_options(options.clone())
+        .ignore_file(ignore_file_path.map(Path::to_path_buf))
+        .progress(progress)
+        .run_flat()
+}
 
-    let files_b_with_hash: Vec<FileInfo> = files_b
-        .into_iter()
-        .map(|(path, abs_path, size)| {
-            let hash = if options.use_full_hash {
-                compute_file_hash_sha256(&abs_path)
-            } else {
-                _compute_sampled_hash_internal(&abs_path)
-            };
-            FileInfo { path, size, hash }
-        })
-        .collect();
+/// Runs a flat mode comparison, for [`Comparison::run_flat`].
+pub(crate) fn flat_comparison(
+    dir_a: &Path,
+    dir_b: &Path,
+    options: &FlatComparisonOptions,
+    ignore_file_path: Option<&Path>,
+    progress: &(dyn Fn(&Progress) + Send + Sync),
+) -> Result<FlatComparisonResult, ComparisonError> {
+    let started = Instant::now();
+    // Phase 1: Collect all files from both directories
+    let mut warnings = Vec::new();
+    let mut links = HashMap::new();
+    let (files_a, dirs_a) =
+        collect_files_flat(dir_a, ignore_file_path, options, &mut links, &mut warnings)?;
+    let (files_b, dirs_b) =
+        collect_files_flat(dir_b, ignore_file_path, options, &mut links, &mut warnings)?;
+    let listed_bytes =
+        |files: &[(PathBuf, PathBuf, u64)]| -> u64 { files.iter().map(|(_, _, size)| size).sum() };
+    let mut stats = ComparisonStats {
+        files_a: files_a.len(),
+        files_b: files_b.len(),
+        dirs_a,
+        dirs_b,
+        bytes_a: listed_bytes(&files_a),
+        bytes_b: listed_bytes(&files_b),
+        ..Default::default()
+    };
+
+    // Optionally, split all files into chunks and compare the chunk sets
+    let chunk_dedupe = options.chunk_dedupe.as_ref().map(|chunk_options| {
+        let (report, chunked) =
+            analyze_chunks(&files_a, &files_b, chunk_options, progress, &mut warnings);
+        stats.bytes_hashed += chunked;
+        report
+    });
+
+    // Phase 2: Compute hashes for all files, leaving out unreadable ones
+    let algorithm = options.hash_algorithm;
+    let hash = |path: &Path, size: u64| {
+        if options.distrust_sizes {
+            unsized_hash(&RealFileSystem, path, algorithm, options.io_hints)
+        } else if let Some(cache) = &options.hash_cache {
+            cache.hash(path).map(|hash| (hash, size))
+        } else if options.use_full_hash {
+            full_hash_in(&RealFileSystem, path, algorithm, options.io_hints)
+                .map(|hash| (hash, size))
+        } else {
+            sampled_hash_with(path, algorithm).map(|hash| (hash, size))
+        }
+    };
+    // Hard links are hashed once, whichever directory they are in
+    let mut linked_hashes: HashMap<InodeKey, (String, u64)> = HashMap::new();
+    let mut with_hash = |files: Vec<(PathBuf, PathBuf, u64)>| -> Vec<FileInfo> {
+        let order: Vec<usize> = if options.order_by_extent {
+            extent_order(
+                &RealFileSystem,
+                files.iter().map(|(_, abs_path, _)| abs_path.as_path()),
+            )
+        } else {
+            (0..files.len()).collect()
+        };
+        // Hashed in `order`, kept in listing order
+        let mut hashed: Vec<Option<FileInfo>> = vec![None; files.len()];
+        for index in order {
+            let (path, abs_path, size) = &files[index];
+            let inode = links.get(abs_path).copied();
+            let hashed_link = inode.and_then(|inode| linked_hashes.get(&inode).cloned());
+            match hashed_link.map_or_else(|| hash(abs_path, *size), Ok) {
+                Ok((hash, size)) => {
+                    if let Some(inode) = inode {
+                        linked_hashes.insert(inode, (hash.clone(), size));
+                    }
+                    hashed[index] = Some(FileInfo {
+                        path: path.clone(),
+                        size,
+                        hash,
+                        inode,
+                    })
+                }
+                Err(e) => warnings.push(FileWarning::new(abs_path, WarningCause::Hash, &e)),
+            }
+        }
+        hashed.into_iter().flatten().collect()
+    };
+    let hashed_before = bytes_hashed_here();
+    let files_a_with_hash = with_hash(files_a);
+    let files_b_with_hash = with_hash(files_b);
+    stats.bytes_hashed += bytes_hashed_here() - hashed_before;
+    warnings.sort_by(|a, b| a.path.cmp(&b.path));
+    // A file that could not be chunked usually cannot be hashed either
+    warnings.dedup();
 
     // Phase 3: Group files by content hash
-    let mut hash_groups: HashMap<String, (u64, Vec<PathBuf>, Vec<PathBuf>)> = HashMap::new();
+    let mut hash_groups: HashMap<&str, (u64, Vec<&FileInfo>, Vec<&FileInfo>)> = HashMap::new();
 
     for file_info in &files_a_with_hash {
-        let entry = hash_groups.entry(file_info.hash.clone()).or_insert((
-            file_info.size,
-            Vec::new(),
-            Vec::new(),
-        ));
-        entry.1.push(file_info.path.clone());
+        let entry =
+            hash_groups
+                .entry(&file_info.hash)
+                .or_insert((file_info.size, Vec::new(), Vec::new()));
+        entry.1.push(file_info);
     }
 
     for file_info in &files_b_with_hash {
-        let entry = hash_groups.entry(file_info.hash.clone()).or_insert((
-            file_info.size,
-            Vec::new(),
-            Vec::new(),
-        ));
-        entry.2.push(file_info.path.clone());
+        let entry =
+            hash_groups
+                .entry(&file_info.hash)
+                .or_insert((file_info.size, Vec::new(), Vec::new()));
+        entry.2.push(file_info);
     }
 
-    // Phase 4: Build result groups
-    let mut groups: Vec<FlatContentGroup> = hash_groups
+    // Phase 4: Build result groups, sorted by hash for consistent output
+    let mut hash_groups: Vec<_> = hash_groups.into_iter().collect();
+    hash_groups.sort_by(|a, b| a.0.cmp(b.0));
+    let mut hardlink_groups: HashMap<InodeKey, u64> = HashMap::new();
+    let groups: Vec<FlatContentGroup> = hash_groups
         .into_iter()
         .map(|(hash, (size, files_a, files_b))| {
             let file_count = files_a.len() + files_b.len();
+            let sides = || {
+                let a = files_a.iter().map(|file| (true, *file));
+                a.chain(files_b.iter().map(|file| (false, *file)))
+            };
+            // Links to one inode share a hash, so they all end up here
+            let mut link_counts: HashMap<InodeKey, usize> = HashMap::new();
+            for inode in sides().filter_map(|(_, file)| file.inode) {
+                *link_counts.entry(inode).or_default() += 1;
+            }
+            let hard_links = sides()
+                .filter_map(|(in_a, file)| {
+                    let inode = file.inode.filter(|inode| link_counts[inode] > 1)?;
+                    let next = hardlink_groups.len() as u64 + 1;
+                    Some(FlatHardLink {
+                        in_a,
+                        path: file.path.clone(),
+                        hardlink_group: *hardlink_groups.entry(inode).or_insert(next),
+                    })
+                })
+                .collect();
             FlatContentGroup {
-                hash,
+                hash: hash.to_string(),
                 size,
                 file_count,
-                files_in_a: files_a,
-                files_in_b: files_b,
+                files_in_a: files_a.iter().map(|file| file.path.clone()).collect(),
+                files_in_b: files_b.iter().map(|file| file.path.clone()).collect(),
+                hard_links,
             }
         })
         .collect();
 
-    // Sort groups by hash for consistent output
-    groups.sort_by(|a, b| a.hash.cmp(&b.hash));
-
     let total_files_a = files_a_with_hash.len();
     let total_files_b = files_b_with_hash.len();
     let unique_hashes = groups.len();
@@ -690,26 +2508,42 @@ pub fn compare_directories_flat(
         total_files_b,
         unique_hashes,
         duplicate_count,
+        warnings,
+        stats: ComparisonStats {
+            elapsed: started.elapsed(),
+            ..stats
+        },
+        chunk_dedupe,
     })
 }
 
+/// (relative_path, absolute_path, size) of the files collected for flat
+/// comparison
+type FlatFiles = Vec<(PathBuf, PathBuf, u64)>;
+
 /// Collects all files from a directory for flat comparison.
-/// Returns vector of (relative_path, absolute_path, size) tuples.
+/// Returns the files and the number of directories walked through, and adds
+/// the inodes of files with more than one link to `links`, by absolute path.
 fn collect_files_flat(
     dir: &Path,
     ignore_file_path: Option<&Path>,
-) -> std::io::Result<Vec<(PathBuf, PathBuf, u64)>> {
-    let dir = std::fs::canonicalize(dir)?;
+    options: &FlatComparisonOptions,
+    links: &mut HashMap<PathBuf, InodeKey>,
+    warnings: &mut Vec<FileWarning>,
+) -> Result<(FlatFiles, usize), ComparisonError> {
+    let dir = resolve_root_dir(dir, options.root_paths)?;
+    let filter = options.filter.as_ref();
     let mut files = Vec::new();
+    let mut dirs = 0;
 
-    let mut walk_builder = ignore::WalkBuilder::new(&dir);
-    walk_builder
-        .follow_links(false)
-        .sort_by_file_name(|a, b| a.cmp(b));
-
-    if let Some(ignore_path) = ignore_file_path {
-        walk_builder.add_ignore(ignore_path);
-    }
+    let ignore = load_ignore_file(ignore_file_path)?;
+    let traversal = TraversalOptions {
+        respect_gitignore: options.respect_gitignore,
+        max_depth: options.max_depth,
+        ignore_hidden: options.ignore_hidden,
+    };
+    let mut walk_builder = walk_builder(&dir, &traversal);
+    prune_walk(&mut walk_builder, &dir, ignore.as_ref(), filter);
 
     for result in walk_builder.build() {
         match result {
@@ -717,43 +2551,222 @@ fn collect_files_flat(
                 if entry.depth() == 0 {
                     continue;
                 }
+                let is_dir = entry.file_type().is_some_and(|ft| ft.is_dir());
+                let abs_path = entry.path().to_path_buf();
+                let rel_path = abs_path
+                    .strip_prefix(&dir)
+                    .map(|p| p.to_path_buf())
+                    .unwrap_or_else(|_| abs_path.clone());
+                if ignore
+                    .as_ref()
+                    .is_some_and(|ignore| ignore.is_ignored(&rel_path, is_dir))
+                {
+                    continue;
+                }
+                if is_dir {
+                    dirs += 1;
+                }
                 if entry.file_type().map(|ft| ft.is_file()).unwrap_or(false) {
-                    let abs_path = entry.path().to_path_buf();
-                    let rel_path = abs_path
-                        .strip_prefix(&dir)
-                        .map(|p| p.to_path_buf())
-                        .unwrap_or_else(|_| abs_path.clone());
-                    let size = entry.metadata().map(|m| m.len()).unwrap_or(0);
-                    files.push((rel_path, abs_path, size));
+                    if filter.is_some_and(|filter| !filter.includes(&rel_path, false)) {
+                        continue;
+                    }
+                    let (size, inode) = match entry.metadata() {
+                        Ok(metadata) => (metadata.len(), linked_inode(&metadata)),
+                        Err(e) => {
+                            warnings.push(FileWarning::new(&abs_path, WarningCause::Metadata, &e));
+                            (0, None)
+                        }
+                    };
+                    // Before hashing, so that left out files are never read
+                    if options.selects(&rel_path, size) {
+                        if let Some(inode) = inode {
+                            links.insert(abs_path.clone(), inode);
+                        }
+                        files.push((rel_path, abs_path, size));
+                    }
                 }
             }
-            Err(ref e) => {
-                eprintln!("Warning: Could not access entry: {}", e);
-            }
+            Err(e) => warnings.push(FileWarning::from_walk(e, &dir)),
         }
     }
 
-    Ok(files)
+    Ok((files, dirs))
 }
 
+/// Lists all entries below `dir`, ignoring entries that cannot be read.
 pub fn traverse_directory(
     dir: &std::path::Path,
     ignore_file_path: Option<&std::path::Path>,
-) -> std::io::Result<Vec<Entry>> {
-    // Canonicalize the directory path to ensure we work with absolute paths
-    let dir = std::fs::canonicalize(dir)?;
-    let mut entries = Vec::new();
+) -> Result<Vec<Entry>, ComparisonError> {
+    traverse_directory_with_options(dir, ignore_file_path, &TraversalOptions::default())
+}
 
-    let mut walk_builder = ignore::WalkBuilder::new(&dir);
+/// Lists all entries below `dir` like [`traverse_directory`], with
+/// `options`.
+///
+/// # Example
+///
+/// ```
+/// use dir_compare_core::comparison::{TraversalOptions, traverse_directory_with_options};
+///
+/// let options = TraversalOptions {
+///     respect_gitignore: true,
+///     max_depth: Some(2),
+///     ignore_hidden: true,
+/// };
+/// let entries =
+///     traverse_directory_with_options(std::path::Path::new("test_data/original"), None, &options);
+/// ```
+pub fn traverse_directory_with_options(
+    dir: &Path,
+    ignore_file_path: Option<&Path>,
+    options: &TraversalOptions,
+) -> Result<Vec<Entry>, ComparisonError> {
+    // Resolve the directory path to ensure we work with absolute paths
+    let dir = resolve_root_dir(dir, RootPaths::default())?;
+    let listing = Listing {
+        fs: &RealFileSystem,
+        stat_mode: StatMode::Eager,
+        filter: None,
+        cancel: None,
+        traversal: *options,
+    };
+    traverse_root(
+        &dir,
+        ignore_file_path,
+        &listing,
+        &mut |_| {},
+        &mut Vec::new(),
+    )
+}
+
+/// Resolves a root directory with [`resolve_root`] and checks that it can be
+/// listed.
+fn resolve_root_dir(dir: &Path, root_paths: RootPaths) -> Result<PathBuf, ComparisonError> {
+    let resolved = resolve_root(&RealFileSystem, dir, root_paths).map_err(|source| {
+        ComparisonError::Canonicalize {
+            path: dir.to_path_buf(),
+            source,
+        }
+    })?;
+    std::fs::read_dir(&resolved).map_err(|source| ComparisonError::Traversal {
+        path: resolved.clone(),
+        source,
+    })?;
+    Ok(resolved)
+}
+
+/// Creates a sorted walk of `dir` that does not follow symlinks, as
+/// `traversal` asks.
+fn walk_builder(dir: &Path, traversal: &TraversalOptions) -> ignore::WalkBuilder {
+    let mut walk_builder = ignore::WalkBuilder::new(dir);
     walk_builder
         .follow_links(false)
-        .sort_by_file_name(|a, b| a.cmp(b));
+        .hidden(traversal.ignore_hidden)
+        .sort_by_file_name(|a, b| a.cmp(b))
+        .max_depth(traversal.max_depth)
+        .git_ignore(traversal.respect_gitignore)
+        .require_git(false)
+        .git_global(false)
+        .git_exclude(false);
+    walk_builder
+}
+
+/// Reads the ignore file at `ignore_file_path`, if any
+fn load_ignore_file(
+    ignore_file_path: Option<&Path>,
+) -> Result<Option<Arc<IgnoreFile>>, ComparisonError> {
+    Ok(ignore_file_path
+        .map(IgnoreFile::load)
+        .transpose()?
+        .map(Arc::new))
+}
 
-    if let Some(ignore_path) = ignore_file_path {
-        walk_builder.add_ignore(ignore_path);
+/// Makes `walk_builder` skip the directories that `ignore` prunes and the
+/// entries that `filter` excludes, without listing their contents.
+///
+/// When `filter` is VCS-aware, returns the set that the walk fills with the
+/// directories below `root` holding their own `.git`.
+fn prune_walk(
+    walk_builder: &mut ignore::WalkBuilder,
+    root: &Path,
+    ignore: Option<&Arc<IgnoreFile>>,
+    filter: Option<&EntryFilter>,
+) -> Option<NestedRepositories> {
+    let filter = filter.filter(|filter| filter.exclude.is_some() || filter.vcs_aware);
+    if ignore.is_none() && filter.is_none() {
+        return None;
     }
+    let nested = filter
+        .is_some_and(|filter| filter.vcs_aware)
+        .then(NestedRepositories::default);
+    let found = nested.clone();
+    let ignore = ignore.cloned();
+    let filter = filter.cloned();
+    let root = root.to_path_buf();
+    walk_builder.filter_entry(move |entry| {
+        if entry.depth() == 0 {
+            return true;
+        }
+        let is_dir = entry.file_type().is_some_and(|t| t.is_dir());
+        let path = entry.path().strip_prefix(&root).ok();
+        if let (Some(ignore), Some(path)) = (&ignore, path)
+            && is_dir
+            && ignore.prunes(path)
+        {
+            return false;
+        }
+        filter
+            .as_ref()
+            .is_none_or(|filter| filter.keeps_walked(entry, path, is_dir, found.as_ref()))
+    });
+    nested
+}
+
+/// How [`traverse_root`] lists the entries of a root.
+struct Listing<'a> {
+    /// File system the metadata of files is read through
+    fs: &'a dyn FileSystem,
+    stat_mode: StatMode,
+    filter: Option<&'a EntryFilter>,
+    cancel: Option<&'a CancellationToken>,
+    traversal: TraversalOptions,
+}
+
+/// Lists all entries below an already resolved root directory.
+///
+/// Entry paths are absolute and start with `dir`. The type of each entry
+/// comes from the directory listing; files are stat'ed once with
+/// [`StatMode::Eager`] and not at all with [`StatMode::Lazy`]. `on_entry` is
+/// called with each entry as it is listed, before include patterns are
+/// applied. Entries that cannot be read are reported to `warnings`.
+fn traverse_root(
+    dir: &Path,
+    ignore_file_path: Option<&Path>,
+    listing: &Listing,
+    on_entry: &mut dyn FnMut(&Path),
+    warnings: &mut Vec<FileWarning>,
+) -> Result<Vec<Entry>, ComparisonError> {
+    let Listing {
+        fs,
+        stat_mode,
+        filter,
+        cancel,
+        traversal,
+    } = *listing;
+    let mut entries = Vec::new();
+    // Ignored directories descended into because a negation may re-include
+    // entries below them
+    let mut ignored_dirs = HashSet::new();
+
+    let ignore = load_ignore_file(ignore_file_path)?;
+    let mut walk_builder = walk_builder(dir, &traversal);
+    let nested_repositories = prune_walk(&mut walk_builder, dir, ignore.as_ref(), filter);
 
     for result in walk_builder.build() {
+        if cancel.is_some_and(CancellationToken::is_cancelled) {
+            break;
+        }
         match result {
             Ok(entry) => {
                 if entry.depth() == 0 {
@@ -761,39 +2774,67 @@ pub fn traverse_directory(
                 }
                 let path = entry.path().to_path_buf();
                 let abs_path = path.clone();
-                let kind = if entry.file_type().unwrap().is_dir() {
+                let file_type = entry.file_type().unwrap();
+                if let Some(ignore) = &ignore
+                    && let Ok(relative) = path.strip_prefix(dir)
+                    && ignore.is_ignored(relative, file_type.is_dir())
+                {
+                    if !file_type.is_dir() {
+                        continue;
+                    }
+                    ignored_dirs.insert(path.clone());
+                }
+                let kind = if file_type.is_symlink() {
+                    match std::fs::read_link(&path) {
+                        Ok(target) => EntryKind::Symlink { target },
+                        Err(e) => {
+                            warnings.push(FileWarning::new(&path, WarningCause::ReadLink, &e));
+                            continue;
+                        }
+                    }
+                } else if file_type.is_dir() {
                     EntryKind::Directory
                 } else {
                     EntryKind::File
                 };
-                let size = if entry.file_type().unwrap().is_file() {
-                    match entry.metadata() {
-                        Ok(metadata) => Some(metadata.len()),
+                let (size, mtime) = if file_type.is_file() && stat_mode == StatMode::Eager {
+                    match fs.metadata(&path) {
+                        Ok(metadata) => (Some(metadata.len()), metadata.modified().ok()),
                         Err(e) => {
-                            eprintln!(
-                                "Warning: Could not get metadata for {}: {}",
-                                entry.path().display(),
-                                e
-                            );
-                            None
+                            warnings.push(FileWarning::new(&path, WarningCause::Metadata, &e));
+                            (None, None)
                         }
                     }
                 } else {
-                    None
+                    (None, None)
                 };
+                on_entry(&path);
                 entries.push(Entry {
                     path,
                     abs_path,
                     kind,
                     size,
+                    nested_repository: false,
+                    mtime,
                 });
             }
-            Err(ref e) => {
-                eprintln!("Warning: Could not access entry: {}", e);
-            }
+            Err(e) => warnings.push(FileWarning::from_walk(e, dir)),
+        }
+    }
+
+    retain_leading_dirs(&mut entries, &ignored_dirs);
+
+    if let Some(nested) = nested_repositories {
+        let nested = nested.lock().unwrap_or_else(|e| e.into_inner());
+        for entry in &mut entries {
+            entry.nested_repository =
+                entry.kind == EntryKind::Directory && nested.contains(&entry.path);
         }
     }
 
+    if let Some(filter) = filter {
+        filter.retain_included(&mut entries, dir);
+    }
     Ok(entries)
 }
 
@@ -808,16 +2849,32 @@ pub enum ComparisonStrategyType {
     FilenameSize,
     /// Compare by filename and content hash
     FastHash,
+    /// Compare by filename and BLAKE3 content hash
+    Blake3,
     /// Compare by filename and sampled content hash
     SampledHash,
+    /// Compare by filename, size and modification time
+    Mtime,
 }
 
 /// The result of comparing two directories.
 ///
-/// Contains three categories of entries:
+/// Contains four categories of entries:
 /// - Entries only in directory A
 /// - Entries only in directory B
 /// - Entries present in both directories (matching according to the strategy)
+/// - Entries present at the same path in both directories that do not match
+///
+/// With [`ComparisonOptions::detect_renames`], files moved to another path
+/// are listed in `renamed` instead of `a_only` and `b_only`.
+///
+/// Before `modified` existed, same-path mismatches were reported in both
+/// `a_only` and `b_only`; [`ComparisonResult::split_modified`] restores that
+/// layout for callers that depend on it.
+///
+/// With the `serde` feature, results implement `Serialize` and `Deserialize`
+/// with their field names as keys and paths as strings; serializing a path
+/// that is not valid UTF-8 fails. The README describes the JSON layout.
 ///
 /// # Examples
 ///
@@ -838,10 +2895,12 @@ pub enum ComparisonStrategyType {
 ///         println!("A-only: {} entries", res.a_only.len());
 ///         println!("B-only: {} entries", res.b_only.len());
 ///         println!("Both: {} entries", res.both.len());
+///         println!("Modified: {} entries", res.modified.len());
 ///     }
 ///     Err(e) => println!("Error: {}", e),
 /// }
 /// ```
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ComparisonResult {
     /// Entries found only in directory A
     pub a_only: Vec<Entry>,
@@ -849,6 +2908,559 @@ pub struct ComparisonResult {
     pub b_only: Vec<Entry>,
     /// Entries found in both directories (tuple of A-entry, B-entry)
     pub both: Vec<(Entry, Entry)>,
+    /// Entries at the same path in both directories that the strategy did
+    /// not match (tuple of A-entry, B-entry)
+    pub modified: Vec<(Entry, Entry)>,
+    /// Files only in A whose content is found in exactly one file only in B
+    /// (tuple of A-entry, B-entry), sorted by the path in A; empty unless
+    /// [`ComparisonOptions::detect_renames`] is set. Always kept whole, and
+    /// not listed by [`iter_all`](Self::iter_all)
+    pub renamed: Vec<(Entry, Entry)>,
+    /// Totals of every category when the comparison ran with a
+    /// [`ResultDetail`] other than `Full`; `None` when the vectors above hold
+    /// every entry
+    pub exact_counts: Option<ResultCounts>,
+    /// Entries that were skipped or could not be compared fully, sorted by
+    /// path
+    pub warnings: Vec<FileWarning>,
+    /// What the comparison read and how long it took
+    pub stats: ComparisonStats,
+    /// Which directories were compared, how and when, for the header of
+    /// reports; set by the caller, `None` leaves the header out
+    pub context: Option<ReportContext>,
+    /// Directories of `both` listed without their contents because their
+    /// whole subtree matched, sorted by path; empty unless
+    /// [`rollup`](Self::rollup) collapsed any
+    pub identical_subtrees: Vec<IdenticalSubtree>,
+    /// Why the strategy did not match each pair kept in
+    /// [`modified`](Self::modified), by the path of its A-side entry,
+    /// sorted by path; see [`mismatch_reason`](Self::mismatch_reason)
+    pub mismatch_reasons: Vec<(PathBuf, MismatchReason)>,
+}
+
+/// A directory whose whole subtree matched, listed in
+/// [`ComparisonResult::both`] without the entries below it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct IdenticalSubtree {
+    /// Relative path of the directory, as listed in A
+    pub path: PathBuf,
+    /// Number of files below the directory
+    pub files: usize,
+    /// Number of entries below the directory, files included
+    pub entries: usize,
+}
+
+/// How much a comparison traversed and read, and how long it took.
+///
+/// Files and directories are counted as listed, before they are paired.
+/// `bytes_a` and `bytes_b` add up the sizes of the files known from their
+/// metadata, so they stay zero with [`StatMode::Lazy`]; `bytes_hashed`
+/// counts the file bytes read into content hashes, samples included.
+/// `sanitized_paths` is 0 unless the result was rewritten for a report by
+/// [`sanitize`](crate::sanitize).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ComparisonStats {
+    pub files_a: usize,
+    pub files_b: usize,
+    pub dirs_a: usize,
+    pub dirs_b: usize,
+    pub bytes_a: u64,
+    pub bytes_b: u64,
+    pub bytes_hashed: u64,
+    pub elapsed: Duration,
+    pub sanitized_paths: usize,
+}
+
+impl ComparisonStats {
+    /// Counts the listed entries of directory A
+    fn count_a(&mut self, entries: &[Entry]) {
+        let (files, dirs, bytes) = tally(entries);
+        (self.files_a, self.dirs_a, self.bytes_a) = (files, dirs, bytes);
+    }
+
+    /// Counts the listed entries of directory B
+    fn count_b(&mut self, entries: &[Entry]) {
+        let (files, dirs, bytes) = tally(entries);
+        (self.files_b, self.dirs_b, self.bytes_b) = (files, dirs, bytes);
+    }
+
+    /// Exchanges the numbers of both directories
+    pub fn swap_sides(&mut self) {
+        std::mem::swap(&mut self.files_a, &mut self.files_b);
+        std::mem::swap(&mut self.dirs_a, &mut self.dirs_b);
+        std::mem::swap(&mut self.bytes_a, &mut self.bytes_b);
+    }
+}
+
+/// Returns the number of files and directories among `entries` and the
+/// total size of the files.
+fn tally(entries: &[Entry]) -> (usize, usize, u64) {
+    entries
+        .iter()
+        .fold((0, 0, 0), |(files, dirs, bytes), entry| match entry.kind {
+            EntryKind::File => (files + 1, dirs, bytes + file_bytes(entry)),
+            EntryKind::Directory => (files, dirs + 1, bytes),
+            EntryKind::Symlink { .. } => (files, dirs, bytes),
+        })
+}
+
+thread_local! {
+    /// File bytes read into content hashes on this thread; comparisons
+    /// take the difference around the work they run on a thread.
+    static BYTES_HASHED: Cell<u64> = const { Cell::new(0) };
+}
+
+/// Adds `bytes` to the bytes hashed on this thread.
+pub(crate) fn count_hashed(bytes: u64) {
+    BYTES_HASHED.with(|hashed| hashed.set(hashed.get() + bytes));
+}
+
+/// Returns the bytes hashed on this thread so far.
+pub(crate) fn bytes_hashed_here() -> u64 {
+    BYTES_HASHED.with(Cell::get)
+}
+
+/// Number of entries and file bytes in one result category.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CategoryCounts {
+    /// Number of entries, or pairs for `both` and `modified`
+    pub entries: usize,
+    /// Total size of the files from directory A
+    pub bytes_a: u64,
+    /// Total size of the files from directory B
+    pub bytes_b: u64,
+}
+
+impl CategoryCounts {
+    fn add(&mut self, a: Option<&Entry>, b: Option<&Entry>) {
+        self.entries += 1;
+        self.bytes_a += a.map_or(0, file_bytes);
+        self.bytes_b += b.map_or(0, file_bytes);
+    }
+
+    fn swap_sides(&mut self) {
+        std::mem::swap(&mut self.bytes_a, &mut self.bytes_b);
+    }
+}
+
+/// Size in bytes of a file entry; directories and symlinks count as zero.
+fn file_bytes(entry: &Entry) -> u64 {
+    match entry.kind {
+        EntryKind::File => entry.size.unwrap_or(0),
+        _ => 0,
+    }
+}
+
+/// The totals of each category of a [`ComparisonResult`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ResultCounts {
+    pub a_only: CategoryCounts,
+    pub b_only: CategoryCounts,
+    pub both: CategoryCounts,
+    pub modified: CategoryCounts,
+}
+
+impl ResultCounts {
+    /// Returns the totals of one category.
+    pub fn get(&self, category: Section) -> CategoryCounts {
+        match category {
+            Section::AOnly => self.a_only,
+            Section::BOnly => self.b_only,
+            Section::Modified => self.modified,
+            Section::Both => self.both,
+        }
+    }
+}
+
+impl ComparisonResult {
+    /// Returns true if any entry is A-only, B-only, modified or renamed.
+    pub fn has_differences(&self) -> bool {
+        let counts = self.counts();
+        counts.a_only.entries + counts.b_only.entries + counts.modified.entries > 0
+            || !self.renamed.is_empty()
+    }
+
+    /// Returns the totals of every category, including entries that
+    /// [`ResultDetail`] left out of the vectors.
+    pub fn counts(&self) -> ResultCounts {
+        if let Some(counts) = self.exact_counts {
+            return counts;
+        }
+        let mut counts = ResultCounts::default();
+        for entry in &self.a_only {
+            counts.a_only.add(Some(entry), None);
+        }
+        for entry in &self.b_only {
+            counts.b_only.add(None, Some(entry));
+        }
+        for (a, b) in &self.both {
+            counts.both.add(Some(a), Some(b));
+        }
+        for (a, b) in &self.modified {
+            counts.modified.add(Some(a), Some(b));
+        }
+        counts
+    }
+
+    /// Turns the result into that of comparing B with A: the A-only and
+    /// B-only entries trade places, and so do the entries of every pair.
+    ///
+    /// Pairs stay sorted by the path of their A-side entry, which differs
+    /// from the former one only for case-insensitive pairs. Warnings are
+    /// kept as they are.
+    pub fn swap_sides(&mut self) {
+        std::mem::swap(&mut self.a_only, &mut self.b_only);
+        self.stats.swap_sides();
+        for subtree in &mut self.identical_subtrees {
+            if let Some((_, b)) = self.both.iter().find(|(a, _)| a.path == subtree.path) {
+                subtree.path = b.path.clone();
+            }
+        }
+        self.identical_subtrees.sort_by(|x, y| x.path.cmp(&y.path));
+        for (path, reason) in &mut self.mismatch_reasons {
+            if let Some((_, b)) = self.modified.iter().find(|(a, _)| a.path == *path) {
+                *path = b.path.clone();
+            }
+            *reason = reason.swapped();
+        }
+        self.mismatch_reasons.sort_by(|(x, _), (y, _)| x.cmp(y));
+        for pairs in [&mut self.both, &mut self.modified, &mut self.renamed] {
+            for (a, b) in pairs.iter_mut() {
+                std::mem::swap(a, b);
+            }
+            pairs.sort_by(|(a, _), (b, _)| a.path.cmp(&b.path));
+        }
+        if let Some(counts) = &mut self.exact_counts {
+            std::mem::swap(&mut counts.a_only, &mut counts.b_only);
+            for category in [
+                &mut counts.a_only,
+                &mut counts.b_only,
+                &mut counts.both,
+                &mut counts.modified,
+            ] {
+                category.swap_sides();
+            }
+        }
+        if let Some(context) = &mut self.context {
+            std::mem::swap(&mut context.dir_a, &mut context.dir_b);
+        }
+    }
+
+    /// Returns the number of entries in one category, kept or not.
+    pub fn count(&self, category: Section) -> usize {
+        match self.exact_counts {
+            Some(counts) => counts.get(category).entries,
+            None => self.kept(category),
+        }
+    }
+
+    /// Returns the number of entries of one category that were counted but
+    /// not kept; entries below [`identical_subtrees`](Self::identical_subtrees)
+    /// are not counted as omitted.
+    pub fn omitted(&self, category: Section) -> usize {
+        let rolled_up = match category {
+            Section::Both => self.identical_subtrees.iter().map(|s| s.entries).sum(),
+            _ => 0,
+        };
+        self.count(category)
+            .saturating_sub(self.kept(category) + rolled_up)
+    }
+
+    /// Returns the directories of `both` whose whole subtree matched, with
+    /// the number of entries below them, outermost only.
+    ///
+    /// A subtree matched if none of its entries is A-only, B-only or
+    /// modified and no warning was reported below it. Directories without
+    /// entries below them are left out. Results that keep only part of
+    /// their entries, see [`ResultDetail`], have none.
+    pub fn identical_directories(&self) -> Vec<IdenticalSubtree> {
+        if self.exact_counts.is_some() {
+            return Vec::new();
+        }
+        // Every directory above an entry that differs
+        let differing: HashSet<&Path> = self
+            .a_only
+            .iter()
+            .chain(&self.b_only)
+            .chain(
+                self.modified
+                    .iter()
+                    .chain(&self.renamed)
+                    .flat_map(|(a, b)| [a, b]),
+            )
+            .flat_map(|entry| entry.path.ancestors().skip(1))
+            .collect();
+        let identical = |a: &Entry, b: &Entry| {
+            a.kind == EntryKind::Directory
+                && b.kind == EntryKind::Directory
+                && !differing.contains(a.path.as_path())
+                && !differing.contains(b.path.as_path())
+                && !self.warnings.iter().any(|warning| {
+                    warning.path.starts_with(&a.abs_path) || warning.path.starts_with(&b.abs_path)
+                })
+        };
+
+        let mut subtrees: Vec<IdenticalSubtree> = Vec::new();
+        for (a, b) in &self.both {
+            if let Some(subtree) = subtrees.last_mut()
+                && a.path.starts_with(&subtree.path)
+            {
+                subtree.entries += 1;
+                subtree.files += usize::from(a.kind == EntryKind::File);
+            } else if identical(a, b) {
+                subtrees.push(IdenticalSubtree {
+                    path: a.path.clone(),
+                    files: 0,
+                    entries: 0,
+                });
+            }
+        }
+        subtrees.retain(|subtree| subtree.entries > 0);
+        subtrees
+    }
+
+    /// Lists the directories of `both` whose whole subtree matched without
+    /// the entries below them, and records them in
+    /// [`identical_subtrees`](Self::identical_subtrees), see
+    /// [`identical_directories`](Self::identical_directories).
+    ///
+    /// Only `both` changes; [`counts`](Self::counts) keep the entries that
+    /// were removed. Calling it again does nothing.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use dir_compare_core::Comparison;
+    ///
+    /// let mut result = Comparison::new("test_data/original", "test_data/modified").run()?;
+    /// result.rollup();
+    /// for subtree in &result.identical_subtrees {
+    ///     println!("{}/ (identical, {} files)", subtree.path.display(), subtree.files);
+    /// }
+    /// # Ok::<(), dir_compare_core::ComparisonError>(())
+    /// ```
+    pub fn rollup(&mut self) {
+        let subtrees = self.identical_directories();
+        if subtrees.is_empty() {
+            return;
+        }
+        self.exact_counts = Some(self.counts());
+        let mut rolled_up = subtrees.iter().peekable();
+        let mut current: Option<&Path> = None;
+        self.both.retain(|(a, _)| {
+            if current.is_some_and(|dir| a.path.starts_with(dir)) {
+                return false;
+            }
+            current = None;
+            if rolled_up
+                .peek()
+                .is_some_and(|subtree| subtree.path == a.path)
+            {
+                current = rolled_up.next().map(|subtree| subtree.path.as_path());
+            }
+            true
+        });
+        self.identical_subtrees = subtrees;
+    }
+
+    /// Returns the entry of [`identical_subtrees`](Self::identical_subtrees)
+    /// for the directory at `path`, as listed in A.
+    pub fn identical_subtree(&self, path: &Path) -> Option<&IdenticalSubtree> {
+        find_identical_subtree(&self.identical_subtrees, path)
+    }
+
+    /// Returns why the pair of [`modified`](Self::modified) at `path`, as
+    /// listed in A, did not match, see
+    /// [`mismatch_reasons`](Self::mismatch_reasons).
+    pub fn mismatch_reason(&self, path: &Path) -> Option<MismatchReason> {
+        find_mismatch_reason(&self.mismatch_reasons, path)
+    }
+
+    fn kept(&self, category: Section) -> usize {
+        match category {
+            Section::AOnly => self.a_only.len(),
+            Section::BOnly => self.b_only.len(),
+            Section::Modified => self.modified.len(),
+            Section::Both => self.both.len(),
+        }
+    }
+
+    /// Returns the relative paths in one category as `/`-separated strings,
+    /// in result order.
+    ///
+    /// Pairs are listed by the path of their A-side entry.
+    pub fn paths(&self, category: Section) -> Vec<String> {
+        let to_string = |entry: &Entry| {
+            entry
+                .path
+                .components()
+                .map(|c| c.as_os_str().to_string_lossy())
+                .collect::<Vec<_>>()
+                .join("/")
+        };
+        match category {
+            Section::AOnly => self.a_only.iter().map(to_string).collect(),
+            Section::BOnly => self.b_only.iter().map(to_string).collect(),
+            Section::Modified => self.modified.iter().map(|(a, _)| to_string(a)).collect(),
+            Section::Both => self.both.iter().map(|(a, _)| to_string(a)).collect(),
+        }
+    }
+
+    /// Moves modified pairs into `a_only` and `b_only`, as reported before
+    /// the modified category was introduced.
+    pub fn split_modified(&mut self) {
+        if let Some(counts) = &mut self.exact_counts {
+            let modified = std::mem::take(&mut counts.modified);
+            counts.a_only.entries += modified.entries;
+            counts.a_only.bytes_a += modified.bytes_a;
+            counts.b_only.entries += modified.entries;
+            counts.b_only.bytes_b += modified.bytes_b;
+        }
+        for (a, b) in self.modified.drain(..) {
+            self.a_only.push(a);
+            self.b_only.push(b);
+        }
+        self.mismatch_reasons.clear();
+        self.a_only.sort_by(|a, b| a.path.cmp(&b.path));
+        self.b_only.sort_by(|a, b| a.path.cmp(&b.path));
+    }
+
+    /// Returns every entry with its category, sorted by path across all
+    /// categories.
+    ///
+    /// Pairs are sorted by the path of their A-side entry, and entries with
+    /// the same path follow the order of [`Section::ALL`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use dir_compare_core::{FilenameOnlyStrategy, compare_directories};
+    ///
+    /// let strategy = FilenameOnlyStrategy::new(false);
+    /// if let Ok(result) = compare_directories(
+    ///     std::path::Path::new("test_data/original"),
+    ///     std::path::Path::new("test_data/modified"),
+    ///     &strategy,
+    ///     None,
+    /// ) {
+    ///     for entry in result.iter_all() {
+    ///         println!("{} {}", entry.category.name(), entry.path().display());
+    ///     }
+    /// }
+    /// ```
+    pub fn iter_all(&self) -> impl Iterator<Item = CategorizedEntry<'_>> {
+        let single = |category, a, b| CategorizedEntry { category, a, b };
+        let mut entries: Vec<CategorizedEntry<'_>> = self
+            .a_only
+            .iter()
+            .map(|e| single(Section::AOnly, Some(e), None))
+            .chain(
+                self.b_only
+                    .iter()
+                    .map(|e| single(Section::BOnly, None, Some(e))),
+            )
+            .chain(
+                self.modified
+                    .iter()
+                    .map(|(a, b)| single(Section::Modified, Some(a), Some(b))),
+            )
+            .chain(
+                self.both
+                    .iter()
+                    .map(|(a, b)| single(Section::Both, Some(a), Some(b))),
+            )
+            .collect();
+        // Stable, so equal paths keep the order of the chain above
+        entries.sort_by(|x, y| x.path().cmp(y.path()));
+        entries.into_iter()
+    }
+
+    /// Returns one owned row per entry, in the order of
+    /// [`iter_all`](Self::iter_all).
+    pub fn to_rows(&self) -> Vec<ResultRow> {
+        self.iter_all()
+            .map(|entry| {
+                let listed = entry.entry();
+                ResultRow {
+                    category: entry.category,
+                    path: listed.path.clone(),
+                    kind: listed.kind.clone(),
+                    size: listed.size,
+                    size_b: entry.a.and(entry.b).and_then(|b| b.size),
+                }
+            })
+            .collect()
+    }
+}
+
+/// Looks up the reason of the modified pair at `path` in `reasons`, sorted
+/// by path.
+pub(crate) fn find_mismatch_reason(
+    reasons: &[(PathBuf, MismatchReason)],
+    path: &Path,
+) -> Option<MismatchReason> {
+    reasons
+        .binary_search_by(|(probe, _)| probe.as_path().cmp(path))
+        .ok()
+        .map(|index| reasons[index].1)
+}
+
+/// Looks up the directory at `path` in `subtrees`, which are sorted by path.
+pub(crate) fn find_identical_subtree<'a>(
+    subtrees: &'a [IdenticalSubtree],
+    path: &Path,
+) -> Option<&'a IdenticalSubtree> {
+    subtrees
+        .binary_search_by(|subtree| subtree.path.as_path().cmp(path))
+        .ok()
+        .map(|index| &subtrees[index])
+}
+
+/// An entry of a [`ComparisonResult`] with its category, as returned by
+/// [`ComparisonResult::iter_all`].
+#[derive(Debug, Clone, Copy)]
+pub struct CategorizedEntry<'a> {
+    /// The category listing the entry
+    pub category: Section,
+    /// The entry from A; `None` for B-only entries
+    pub a: Option<&'a Entry>,
+    /// The entry from B; `None` for A-only entries
+    pub b: Option<&'a Entry>,
+}
+
+impl<'a> CategorizedEntry<'a> {
+    /// Returns the entry listed for the category: the A-side entry of
+    /// pairs and A-only entries, the B-side entry of B-only entries.
+    pub fn entry(&self) -> &'a Entry {
+        self.a
+            .or(self.b)
+            .expect("a categorized entry has at least one side")
+    }
+
+    /// Returns the relative path of [`entry`](Self::entry).
+    pub fn path(&self) -> &'a Path {
+        &self.entry().path
+    }
+}
+
+/// One entry of a [`ComparisonResult`] as a flat row, as returned by
+/// [`ComparisonResult::to_rows`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ResultRow {
+    pub category: Section,
+    /// Relative path of the listed entry, see [`CategorizedEntry::entry`]
+    pub path: PathBuf,
+    /// Kind of the listed entry
+    pub kind: EntryKind,
+    /// Size of the listed entry
+    pub size: Option<u64>,
+    /// Size of the B-side entry of pairs; `None` for A-only and B-only
+    /// entries
+    pub size_b: Option<u64>,
 }
 
 /// Compares two directories using the specified comparison strategy.
@@ -885,86 +3497,579 @@ pub fn compare_directories(
     dir_b: &Path,
     strategy: &dyn ComparisonStrategy,
     ignore_file_path: Option<&Path>,
-) -> std::io::Result<ComparisonResult> {
-    let entries_a = traverse_directory(dir_a, ignore_file_path)?;
-    let entries_b = traverse_directory(dir_b, ignore_file_path)?;
+) -> Result<ComparisonResult, ComparisonError> {
+    Comparison::new(dir_a, dir_b)
+        .strategy(strategy)
+        .ignore_file(ignore_file_path.map(Path::to_path_buf))
+        .run()
+}
+
+/// Compares two directories, choosing how their roots are resolved.
+///
+/// Behaves like [`compare_directories`]; with [`RootPaths::Normalize`] the
+/// roots are never canonicalized, which helps in sandboxes where resolving
+/// symlinks above the compared directories is not permitted.
+pub fn compare_directories_with_roots(
+    dir_a: &Path,
+    dir_b: &Path,
+    strategy: &dyn ComparisonStrategy,
+    ignore_file_path: Option<&Path>,
+    root_paths: RootPaths,
+) -> Result<ComparisonResult, ComparisonError> {
+    Comparison::new(dir_a, dir_b)
+        .strategy(strategy)
+        .ignore_file(ignore_file_path.map(Path::to_path_buf))
+        .root_paths(root_paths)
+        .run()
+}
+
+/// Compares two directories with explicit [`ComparisonOptions`].
+///
+/// Behaves like [`compare_directories`]. Strategy evaluation for paths
+/// present on both sides, which hashes file contents for the hash-based
+/// strategies, is spread across `options.threads` worker threads. The result
+/// is sorted exactly as in the single-threaded case.
+pub fn compare_directories_with_options(
+    dir_a: &Path,
+    dir_b: &Path,
+    strategy: &dyn ComparisonStrategy,
+    ignore_file_path: Option<&Path>,
+    options: &ComparisonOptions,
+) -> Result<ComparisonResult, ComparisonError> {
+    Comparison::new(dir_a, dir_b)
+        .strategy(strategy)
+        .ignore_file(ignore_file_path.map(Path::to_path_buf))
+        .options(options.clone())
+        .run()
+}
+
+/// Compares two directories, reporting progress to `progress`.
+///
+/// Behaves like [`compare_directories_with_options`]. The callback receives
+/// a [`ProgressPhase::Traversing`] update for every entry listed, first in
+/// directory A and then in B, counting the entries of each directory from
+/// one, then a [`ProgressPhase::Matching`] update for every same-path
+/// pair evaluated. Matching updates come from the worker threads, possibly
+/// concurrently, which is why the callback must be `Send + Sync`; updates
+/// are frequent, so callers that redraw should throttle.
+///
+/// If `options.cancel` is cancelled while the comparison runs, it stops
+/// early and returns [`ComparisonError::Cancelled`].
+///
+/// # Examples
+///
+/// ```
+/// use dir_compare_core::comparison::{
+///     ComparisonOptions, FilenameOnlyStrategy, compare_directories_with_progress,
+/// };
+///
+/// let result = compare_directories_with_progress(
+///     std::path::Path::new("test_data/original"),
+///     std::path::Path::new("test_data/modified"),
+///     &FilenameOnlyStrategy::new(false),
+///     None,
+///     &ComparisonOptions::default(),
+///     &|progress| eprintln!("{:?} {}", progress.phase, progress.current),
+/// );
+/// ```
+pub fn compare_directories_with_progress(
+    dir_a: &Path,
+    dir_b: &Path,
+    strategy: &dyn ComparisonStrategy,
+    ignore_file_path: Option<&Path>,
+    options: &ComparisonOptions,
+    progress: &(dyn Fn(&Progress) + Send + Sync),
+) -> Result<ComparisonResult, ComparisonError> {
+    Comparison::new(dir_a, dir_b)
+        .strategy(strategy)
+        .ignore_file(ignore_file_path.map(Path::to_path_buf))
+        .options(options.clone())
+        .progress(progress)
+        .run()
+}
+
+/// What comparing the pairs of a [`ComparisonPlan`] is expected to read.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ComparisonEstimate {
+    /// Same-path pairs of any kind
+    pub pairs: usize,
+    /// Pairs of files whose contents the strategy reads
+    pub content_pairs: usize,
+    /// Bytes read for the content pairs, by the sizes they were listed with
+    pub bytes: u64,
+    /// Content pairs with a file of unknown size, as with
+    /// [`StatMode::Lazy`]; such files count as 0 bytes
+    pub unsized_pairs: usize,
+}
+
+/// A hierarchy mode comparison whose directories are listed and whose
+/// entries are paired by path, but whose pairs are not compared yet.
+///
+/// Returned by [`plan_comparison`]. [`estimate`](Self::estimate) tells what
+/// comparing the pairs will read, before [`run`](Self::run) reads anything.
+pub struct ComparisonPlan<'a> {
+    strategy: &'a dyn ComparisonStrategy,
+    options: &'a ComparisonOptions,
+    started: Instant,
+    a_only: Vec<Entry>,
+    b_only: Vec<Entry>,
+    pairs: Vec<(Entry, Entry)>,
+    warnings: Vec<FileWarning>,
+    stats: ComparisonStats,
+}
+
+/// Lists both directories and pairs their entries by relative path, the
+/// first phase of [`compare_directories_with_progress`].
+///
+/// Only metadata is read, and `progress` receives the
+/// [`ProgressPhase::Traversing`] updates. The returned plan compares with
+/// `strategy` and `options` when run.
+///
+/// # Examples
+///
+/// ```no_run
+/// use dir_compare_core::comparison::{ComparisonOptions, FastHashStrategy, plan_comparison};
+///
+/// let strategy = FastHashStrategy::new(false);
+/// let options = ComparisonOptions::default();
+/// let plan = plan_comparison(
+///     std::path::Path::new("test_data/original"),
+///     std::path::Path::new("test_data/modified"),
+///     &strategy,
+///     None,
+///     &options,
+///     &|_| {},
+/// )
+/// .unwrap();
+/// println!("{} bytes to read", plan.estimate().bytes);
+/// let result = plan.run(&|_| {}).unwrap();
+/// ```
+pub fn plan_comparison<'a>(
+    dir_a: &Path,
+    dir_b: &Path,
+    strategy: &'a dyn ComparisonStrategy,
+    ignore_file_path: Option<&Path>,
+    options: &'a ComparisonOptions,
+    progress: &(dyn Fn(&Progress) + Send + Sync),
+) -> Result<ComparisonPlan<'a>, ComparisonError> {
+    let started = Instant::now();
+    let root_paths = options.root_paths;
+    let root_a = resolve_root_dir(dir_a, root_paths)?;
+    let root_b = resolve_root_dir(dir_b, root_paths)?;
+
+    let on_entry = |side: Side| {
+        let mut listed = 0;
+        move |path: &Path| {
+            listed += 1;
+            progress(&Progress {
+                phase: ProgressPhase::Traversing,
+                current: listed,
+                total: None,
+                side: Some(side),
+                current_path: path,
+            });
+        }
+    };
+    let cancel = options.cancel.as_ref();
+    let filter = options.filter.as_ref();
+    let mut warnings = Vec::new();
+    let listing = Listing {
+        fs: strategy.file_system(),
+        stat_mode: options.stat_mode,
+        filter,
+        cancel,
+        traversal: options.traversal(),
+    };
+    let entries_a = traverse_root(
+        &root_a,
+        ignore_file_path,
+        &listing,
+        &mut on_entry(Side::A),
+        &mut warnings,
+    )?;
+    let entries_b = traverse_root(
+        &root_b,
+        ignore_file_path,
+        &listing,
+        &mut on_entry(Side::B),
+        &mut warnings,
+    )?;
+    check_cancelled(cancel)?;
+    let mut stats = ComparisonStats::default();
+    stats.count_a(&entries_a);
+    stats.count_b(&entries_b);
 
     let mut a_only: Vec<Entry> = Vec::new();
     let mut b_only: Vec<Entry> = Vec::new();
-    let mut both: Vec<(Entry, Entry)> = Vec::new();
 
-    let dir_a_canonical = std::fs::canonicalize(dir_a)?;
-    let dir_b_canonical = std::fs::canonicalize(dir_b)?;
+    let case_insensitive = strategy.case_insensitive();
+    let map_a = key_by_relative_path(entries_a, &root_a, case_insensitive, &mut a_only);
+    let mut map_b = key_by_relative_path(entries_b, &root_b, case_insensitive, &mut b_only);
 
-    let map_a: HashMap<PathBuf, Entry> = entries_a
-        .into_iter()
-        .map(|e| {
-            let rel_path = if let Ok(stripped) = e.path.strip_prefix(&dir_a_canonical) {
-                stripped.to_path_buf()
-            } else {
-                e.path
-                    .file_name()
-                    .map(|n| PathBuf::from(n))
-                    .unwrap_or_else(|| e.path.clone())
-            };
-            let mut entry = e;
-            entry.path = rel_path.clone();
-            (rel_path, entry)
-        })
-        .collect();
+    let mut pairs: Vec<(Entry, Entry)> = Vec::new();
+    for (key, a) in map_a {
+        match map_b.remove(&key) {
+            Some(b) => pairs.push((a, b)),
+            None => a_only.push(a),
+        }
+    }
+    b_only.extend(map_b.into_values());
 
-    let map_b: HashMap<PathBuf, Entry> = entries_b
-        .into_iter()
-        .map(|e| {
-            let rel_path = if let Ok(stripped) = e.path.strip_prefix(&dir_b_canonical) {
-                stripped.to_path_buf()
-            } else {
-                e.path
-                    .file_name()
-                    .map(|n| PathBuf::from(n))
-                    .unwrap_or_else(|| e.path.clone())
+    // Sorted before anything is dropped, so limited results keep the first
+    // entries by path
+    a_only.sort_by(|a, b| a.path.cmp(&b.path));
+    b_only.sort_by(|a, b| a.path.cmp(&b.path));
+    pairs.sort_by(|(a, _), (b, _)| a.path.cmp(&b.path));
+
+    Ok(ComparisonPlan {
+        strategy,
+        options,
+        started,
+        a_only,
+        b_only,
+        pairs,
+        warnings,
+        stats,
+    })
+}
+
+impl ComparisonPlan<'_> {
+    /// Same-path pairs left to compare, sorted by path
+    pub fn pairs(&self) -> &[(Entry, Entry)] {
+        &self.pairs
+    }
+
+    /// Statistics of the listing; nothing is hashed yet
+    pub fn stats(&self) -> &ComparisonStats {
+        &self.stats
+    }
+
+    /// Sums what the strategy expects to read for each pair, see
+    /// [`ComparisonStrategy::estimated_read`]. Reads nothing.
+    pub fn estimate(&self) -> ComparisonEstimate {
+        let mut estimate = ComparisonEstimate {
+            pairs: self.pairs.len(),
+            ..Default::default()
+        };
+        for (a, b) in &self.pairs {
+            if let Some(bytes) = self.strategy.estimated_read(a, b) {
+                estimate.content_pairs += 1;
+                estimate.bytes += bytes;
+                if a.size.is_none() || b.size.is_none() {
+                    estimate.unsized_pairs += 1;
+                }
+            }
+        }
+        estimate
+    }
+
+    /// Compares the pairs, the second phase of
+    /// [`compare_directories_with_progress`], sending the
+    /// [`ProgressPhase::Matching`] updates to `progress`.
+    pub fn run(
+        self,
+        progress: &(dyn Fn(&Progress) + Send + Sync),
+    ) -> Result<ComparisonResult, ComparisonError> {
+        let ComparisonPlan {
+            strategy,
+            options,
+            started,
+            mut a_only,
+            mut b_only,
+            pairs,
+            mut warnings,
+            mut stats,
+        } = self;
+        let cancel = options.cancel.as_ref();
+
+        let threads = options.threads.unwrap_or_else(|| {
+            std::thread::available_parallelism()
+                .map(|n| n.get())
+                .unwrap_or(1)
+        });
+        let order: Vec<usize> = if options.order_by_extent {
+            extent_order(
+                strategy.file_system(),
+                pairs.iter().map(|(a, _)| a.abs_path.as_path()),
+            )
+        } else {
+            (0..pairs.len()).collect()
+        };
+        let verdicts = evaluate_pairs(
+            &pairs,
+            &order,
+            strategy,
+            threads,
+            cancel,
+            progress,
+            &mut warnings,
+            &mut stats.bytes_hashed,
+        );
+        check_cancelled(cancel)?;
+        let renamed = if options.detect_renames {
+            detect_renames(
+                &mut a_only,
+                &mut b_only,
+                strategy.file_system(),
+                cancel,
+                &mut warnings,
+                &mut stats.bytes_hashed,
+            )?
+        } else {
+            Vec::new()
+        };
+        warnings.sort_by(|a, b| a.path.cmp(&b.path));
+
+        let limit = options.result_detail.limit().unwrap_or(usize::MAX);
+        let mut counts = ResultCounts::default();
+        let mut result = ComparisonResult {
+            a_only: Vec::new(),
+            b_only: Vec::new(),
+            both: Vec::new(),
+            modified: Vec::new(),
+            renamed,
+            exact_counts: None,
+            warnings,
+            stats,
+            context: None,
+            identical_subtrees: Vec::new(),
+            mismatch_reasons: Vec::new(),
+        };
+        for entry in a_only {
+            counts.a_only.add(Some(&entry), None);
+            if result.a_only.len() < limit {
+                result.a_only.push(entry);
+            }
+        }
+        for entry in b_only {
+            counts.b_only.add(None, Some(&entry));
+            if result.b_only.len() < limit {
+                result.b_only.push(entry);
+            }
+        }
+        for ((a, b), verdict) in pairs.into_iter().zip(verdicts) {
+            let (category, kept) = match verdict {
+                MatchVerdict::Match => (&mut counts.both, &mut result.both),
+                MatchVerdict::Mismatch(reason) => {
+                    if result.modified.len() < limit {
+                        result.mismatch_reasons.push((a.path.clone(), reason));
+                    }
+                    (&mut counts.modified, &mut result.modified)
+                }
             };
-            let mut entry = e;
-            entry.path = rel_path.clone();
-            (rel_path, entry)
-        })
-        .collect();
+            category.add(Some(&a), Some(&b));
+            if kept.len() < limit {
+                kept.push((a, b));
+            }
+        }
 
-    let keys_a: HashSet<PathBuf> = map_a.keys().cloned().collect();
-    let keys_b: HashSet<PathBuf> = map_b.keys().cloned().collect();
+        if options.result_detail != ResultDetail::Full {
+            result.exact_counts = Some(counts);
+        }
+        result.stats.elapsed = started.elapsed();
+        Ok(result)
+    }
+}
 
-    for key in keys_a.difference(&keys_b) {
-        if let Some(entry) = map_a.get(key) {
-            a_only.push(entry.clone());
+/// Pairs the files of `a_only` and `b_only` holding the same content, for
+/// [`ComparisonOptions::detect_renames`], and removes them from both lists.
+///
+/// Only non-empty files whose size is found on both sides are hashed, with
+/// BLAKE3. A content held by exactly one file on each side makes a pair;
+/// contents held by several files on either side are ambiguous and stay in
+/// the lists. Returns the pairs sorted by the path in A.
+fn detect_renames(
+    a_only: &mut Vec<Entry>,
+    b_only: &mut Vec<Entry>,
+    fs: &dyn FileSystem,
+    cancel: Option<&CancellationToken>,
+    warnings: &mut Vec<FileWarning>,
+    bytes_hashed: &mut u64,
+) -> Result<Vec<(Entry, Entry)>, ComparisonError> {
+    let by_size = |entries: &[Entry]| {
+        let mut by_size: HashMap<u64, Vec<usize>> = HashMap::new();
+        for (index, entry) in entries.iter().enumerate() {
+            if entry.kind != EntryKind::File {
+                continue;
+            }
+            if let Some(size) = entry.size.or_else(|| fs.len(&entry.abs_path).ok())
+                && size > 0
+            {
+                by_size.entry(size).or_default().push(index);
+            }
+        }
+        by_size
+    };
+    let sizes_b = by_size(b_only);
+    // Indices into `a_only` and `b_only` by size and content hash
+    let mut by_content: HashMap<(u64, String), [Vec<usize>; 2]> = HashMap::new();
+    for (size, in_a) in by_size(a_only) {
+        let Some(in_b) = sizes_b.get(&size) else {
+            continue;
+        };
+        for (side, entries, indices) in [(0, &*a_only, &in_a), (1, &*b_only, in_b)] {
+            for &index in indices {
+                check_cancelled(cancel)?;
+                let entry = &entries[index];
+                match full_hash_in(fs, &entry.abs_path, HashAlgorithm::Blake3, true) {
+                    Ok(hash) => {
+                        *bytes_hashed += size;
+                        by_content.entry((size, hash)).or_default()[side].push(index);
+                    }
+                    Err(e) => {
+                        warnings.push(FileWarning::new(&entry.abs_path, WarningCause::Hash, &e))
+                    }
+                }
+            }
         }
     }
 
-    for key in keys_b.difference(&keys_a) {
-        if let Some(entry) = map_b.get(key) {
-            b_only.push(entry.clone());
+    // The B-side index of each renamed A-only file
+    let matches: HashMap<usize, usize> = by_content
+        .into_values()
+        .filter_map(|[in_a, in_b]| match (in_a.as_slice(), in_b.as_slice()) {
+            ([a], [b]) => Some((*a, *b)),
+            _ => None,
+        })
+        .collect();
+    if matches.is_empty() {
+        return Ok(Vec::new());
+    }
+    let mut unmatched_b: Vec<Option<Entry>> =
+        std::mem::take(b_only).into_iter().map(Some).collect();
+    let mut renamed = Vec::with_capacity(matches.len());
+    for (index, a) in std::mem::take(a_only).into_iter().enumerate() {
+        match matches.get(&index).and_then(|&b| unmatched_b[b].take()) {
+            Some(b) => renamed.push((a, b)),
+            None => a_only.push(a),
         }
     }
+    *b_only = unmatched_b.into_iter().flatten().collect();
+    Ok(renamed)
+}
 
-    for key in keys_a.intersection(&keys_b) {
-        let entry_a = map_a.get(key).cloned();
-        let entry_b = map_b.get(key).cloned();
-        if let (Some(a), Some(b)) = (entry_a, entry_b) {
-            if strategy.matches(&a, &b) {
-                both.push((a, b));
-            } else {
-                a_only.push(a);
-                b_only.push(b);
+/// Keys entries by their path relative to `root`, which also becomes their
+/// [`Entry::path`].
+///
+/// With `case_insensitive` the keys are lowercased so that case variants
+/// pair up. A second entry whose key is already taken on the same side has
+/// no counterpart to pair with and is pushed to `unpaired` instead.
+fn key_by_relative_path(
+    entries: Vec<Entry>,
+    root: &Path,
+    case_insensitive: bool,
+    unpaired: &mut Vec<Entry>,
+) -> HashMap<PathBuf, Entry> {
+    let mut map = HashMap::with_capacity(entries.len());
+    for mut entry in entries {
+        entry.path = match entry.path.strip_prefix(root) {
+            Ok(stripped) => stripped.to_path_buf(),
+            Err(_) => entry
+                .path
+                .file_name()
+                .map(PathBuf::from)
+                .unwrap_or_else(|| entry.path.clone()),
+        };
+        let key = if case_insensitive {
+            PathBuf::from(entry.path.to_string_lossy().to_lowercase())
+        } else {
+            entry.path.clone()
+        };
+        match map.entry(key) {
+            std::collections::hash_map::Entry::Vacant(slot) => {
+                slot.insert(entry);
             }
+            std::collections::hash_map::Entry::Occupied(_) => unpaired.push(entry),
         }
     }
+    map
+}
 
-    a_only.sort_by(|a, b| a.path.cmp(&b.path));
-    b_only.sort_by(|a, b| a.path.cmp(&b.path));
-    both.sort_by(|(a, _), (b, _)| a.path.cmp(&b.path));
+/// Returns [`ComparisonError::Cancelled`] if the comparison was cancelled.
+fn check_cancelled(cancel: Option<&CancellationToken>) -> Result<(), ComparisonError> {
+    if cancel.is_some_and(CancellationToken::is_cancelled) {
+        return Err(ComparisonError::Cancelled);
+    }
+    Ok(())
+}
 
-    Ok(ComparisonResult {
-        a_only,
-        b_only,
-        both,
-    })
+/// Evaluates `strategy` for each pair in `order`, a permutation of the pair
+/// indices, returning the results in input order.
+///
+/// Workers take the next unevaluated pair from a shared counter, so a few
+/// large files do not leave the other threads idle. A matching update is
+/// sent to `progress` after each pair. Once `cancel` is cancelled, the
+/// remaining pairs are skipped and reported as differing in content. Files the
+/// strategy could not read are added to `warnings`, and the bytes it hashed
+/// to `bytes_hashed`.
+#[allow(clippy::too_many_arguments)]
+fn evaluate_pairs(
+    pairs: &[(Entry, Entry)],
+    order: &[usize],
+    strategy: &dyn ComparisonStrategy,
+    threads: usize,
+    cancel: Option<&CancellationToken>,
+    progress: &(dyn Fn(&Progress) + Send + Sync),
+    warnings: &mut Vec<FileWarning>,
+    bytes_hashed: &mut u64,
+) -> Vec<MatchVerdict> {
+    let done = AtomicUsize::new(0);
+    let skipped = MatchVerdict::Mismatch(MismatchReason::ContentDiffers);
+    let evaluate = |a: &Entry, b: &Entry, warnings: &mut Vec<FileWarning>| {
+        if cancel.is_some_and(CancellationToken::is_cancelled) {
+            return skipped;
+        }
+        let verdict = strategy.compare_reporting(a, b, warnings);
+        progress(&Progress {
+            phase: ProgressPhase::Matching,
+            current: done.fetch_add(1, Ordering::Relaxed) + 1,
+            total: Some(pairs.len()),
+            side: None,
+            current_path: &a.path,
+        });
+        verdict
+    };
+
+    let threads = threads.clamp(1, pairs.len().max(1));
+    if threads == 1 {
+        let hashed_before = bytes_hashed_here();
+        let mut results = vec![skipped; pairs.len()];
+        for &index in order {
+            let (a, b) = &pairs[index];
+            results[index] = evaluate(a, b, warnings);
+        }
+        *bytes_hashed += bytes_hashed_here() - hashed_before;
+        return results;
+    }
+
+    let next = AtomicUsize::new(0);
+    let mut results = vec![skipped; pairs.len()];
+    std::thread::scope(|scope| {
+        let workers: Vec<_> = (0..threads)
+            .map(|_| {
+                scope.spawn(|| {
+                    let hashed_before = bytes_hashed_here();
+                    let mut evaluated = Vec::new();
+                    let mut worker_warnings = Vec::new();
+                    while let Some(&index) = order.get(next.fetch_add(1, Ordering::Relaxed)) {
+                        let (a, b) = &pairs[index];
+                        evaluated.push((index, evaluate(a, b, &mut worker_warnings)));
+                    }
+                    let hashed = bytes_hashed_here() - hashed_before;
+                    (evaluated, worker_warnings, hashed)
+                })
+            })
+            .collect();
+        for worker in workers {
+            let (evaluated, worker_warnings, hashed) = worker
+                .join()
+                .unwrap_or_else(|panic| std::panic::resume_unwind(panic));
+            for (index, verdict) in evaluated {
+                results[index] = verdict;
+            }
+            warnings.extend(worker_warnings);
+            *bytes_hashed += hashed;
+        }
+    });
+    results
 }
@@ -0,0 +1,149 @@
+//! Option files for `dir-compare --config`.
+//!
+//! A config file is a TOML table whose keys are long option names without
+//! the leading dashes, such as `method = "hash"` or `mtime-tolerance = 2`.
+//! The options are placed before those of the command line, so options
+//! given there win. `true` passes a flag, `false` leaves it out, and an
+//! array repeats the option once per element. The compared directories are
+//! never part of a config file.
+//!
+//! [`format_config`] writes the same format, so a front end can save the
+//! options of a comparison for the CLI.
+
+use std::fmt;
+
+/// The value of one option in a config file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConfigValue {
+    /// A flag, passed when `true`
+    Bool(bool),
+    Int(i64),
+    String(String),
+    /// An option given once per element, such as `--exclude`
+    List(Vec<String>),
+}
+
+/// Options of a config file, keyed by long option name
+pub type ConfigEntries = Vec<(String, ConfigValue)>;
+
+/// A config file that is not a flat TOML table of options
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConfigError {
+    pub message: String,
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Invalid config file: {}", self.message)
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+impl ConfigError {
+    fn new(message: String) -> Self {
+        Self { message }
+    }
+}
+
+/// Parses the options of a config file.
+///
+/// # Errors
+///
+/// Returns an error for text that is not TOML, a nested table, a float or
+/// date value, an array of anything but strings, and the `config` key.
+pub fn parse_config(text: &str) -> Result<ConfigEntries, ConfigError> {
+    let table: toml::Table = text
+        .parse()
+        .map_err(|e: toml::de::Error| ConfigError::new(e.to_string().trim_end().to_string()))?;
+    table
+        .into_iter()
+        .map(|(key, value)| {
+            if key == "config" {
+                return Err(ConfigError::new(
+                    "a config file cannot name another config file".to_string(),
+                ));
+            }
+            let value = match value {
+                toml::Value::Boolean(flag) => ConfigValue::Bool(flag),
+                toml::Value::Integer(n) => ConfigValue::Int(n),
+                toml::Value::String(s) => ConfigValue::String(s),
+                toml::Value::Array(items) => ConfigValue::List(
+                    items
+                        .into_iter()
+                        .map(|item| match item {
+                            toml::Value::String(s) => Ok(s),
+                            other => Err(ConfigError::new(format!(
+                                "'{}' lists a {}, not a string",
+                                key,
+                                other.type_str()
+                            ))),
+                        })
+                        .collect::<Result<_, _>>()?,
+                ),
+                other => {
+                    return Err(ConfigError::new(format!(
+                        "'{}' is a {}; options take booleans, integers, strings or arrays of strings",
+                        key,
+                        other.type_str()
+                    )));
+                }
+            };
+            Ok((key, value))
+        })
+        .collect()
+}
+
+/// Turns config entries into command-line arguments, such as
+/// `["--method", "hash", "--flat"]`.
+pub fn config_to_args(entries: &[(String, ConfigValue)]) -> Vec<String> {
+    let mut args = Vec::new();
+    for (key, value) in entries {
+        let option = format!("--{}", key);
+        match value {
+            ConfigValue::Bool(true) => args.push(option),
+            ConfigValue::Bool(false) => {}
+            ConfigValue::Int(n) => args.extend([option, n.to_string()]),
+            ConfigValue::String(s) => args.extend([option, s.clone()]),
+            ConfigValue::List(items) => {
+                for item in items {
+                    args.extend([option.clone(), item.clone()]);
+                }
+            }
+        }
+    }
+    args
+}
+
+/// Writes config entries as a config file that [`parse_config`] reads back.
+pub fn format_config(entries: &[(String, ConfigValue)]) -> String {
+    let mut text = String::new();
+    for (key, value) in entries {
+        let value = match value {
+            ConfigValue::Bool(flag) => toml::Value::Boolean(*flag),
+            ConfigValue::Int(n) => toml::Value::Integer(*n),
+            ConfigValue::String(s) => toml::Value::String(s.clone()),
+            ConfigValue::List(items) => toml::Value::Array(
+                items
+                    .iter()
+                    .map(|item| toml::Value::String(item.clone()))
+                    .collect(),
+            ),
+        };
+        text.push_str(&format!("{} = {}\n", toml_key(key), value));
+    }
+    text
+}
+
+/// `key`, quoted unless it is a bare TOML key
+fn toml_key(key: &str) -> String {
+    let bare = !key.is_empty()
+        && key
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+    if bare {
+        key.to_string()
+    } else {
+        toml::Value::String(key.to_string()).to_string()
+    }
+}
@@ -0,0 +1,229 @@
+//! Classification of compared files into compressed and raw formats.
+//!
+//! Sampled hashing reads a few small blocks of each file. In compressed
+//! formats such as zip, jpg or mp4 any change spreads over the rest of the
+//! stream, so the samples almost always catch it. Raw data such as text,
+//! logs or VM images keeps a changed byte local, and a change between two
+//! samples goes unnoticed. A [`DataProfile`] totals the bytes of each class
+//! so that [`sampling_advice`] can suggest reading whole files when most of
+//! the data is raw.
+
+use std::io::Read;
+use std::path::Path;
+use std::sync::Mutex;
+
+use crate::comparison::SAMPLING_THRESHOLD;
+use crate::filesystem::FileSystem;
+
+/// Number of leading bytes [`classify_magic`] looks at.
+pub const SNIFF_LEN: usize = 16;
+
+/// Share of the compared bytes in sampled raw files above which
+/// [`sampling_advice`] warns, as a fraction.
+pub const DEFAULT_ADVICE_RAW_SHARE: f64 = 0.5;
+
+/// Whether a file's format compresses its content.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FormatClass {
+    /// Archives and compressed media, where a change alters the rest of the
+    /// stream
+    Compressed,
+    /// Text, uncompressed images and disk images, and any format not known
+    /// to be compressed
+    Raw,
+}
+
+/// Extensions of compressed formats, in lowercase.
+const COMPRESSED_EXTENSIONS: &[&str] = &[
+    "7z", "aac", "apk", "avi", "br", "bz2", "cab", "deb", "docx", "epub", "flac", "gif", "gz",
+    "heic", "jar", "jpeg", "jpg", "lz", "lz4", "lzma", "m4a", "m4v", "mkv", "mov", "mp3", "mp4",
+    "odt", "ogg", "opus", "png", "pptx", "rar", "rpm", "tbz2", "tgz", "txz", "webm", "webp", "whl",
+    "xlsx", "xz", "zip", "zst",
+];
+
+/// Extensions of formats known to store their content uncompressed, in
+/// lowercase.
+const RAW_EXTENSIONS: &[&str] = &[
+    "bin", "bmp", "c", "csv", "dat", "db", "dmg", "h", "htm", "html", "img", "iso", "js", "json",
+    "log", "md", "qcow2", "raw", "rs", "sql", "sqlite", "tar", "tif", "tiff", "txt", "vdi", "vhd",
+    "vhdx", "vmdk", "wav", "xml", "yaml", "yml",
+];
+
+/// Classifies `path` by its extension; `None` for extensions in neither
+/// table and files without one.
+pub fn classify_extension(path: &Path) -> Option<FormatClass> {
+    let extension = path.extension()?.to_str()?.to_ascii_lowercase();
+    if COMPRESSED_EXTENSIONS.contains(&extension.as_str()) {
+        Some(FormatClass::Compressed)
+    } else if RAW_EXTENSIONS.contains(&extension.as_str()) {
+        Some(FormatClass::Raw)
+    } else {
+        None
+    }
+}
+
+/// Classifies a file by the signature at the start of its content;
+/// `None` unless `header` starts like a known compressed format.
+pub fn classify_magic(header: &[u8]) -> Option<FormatClass> {
+    const SIGNATURES: &[&[u8]] = &[
+        b"PK\x03\x04",         // zip and the formats built on it
+        b"\x1f\x8b",           // gzip
+        b"BZh",                // bzip2
+        b"\xfd7zXZ\x00",       // xz
+        b"\x28\xb5\x2f\xfd",   // zstd
+        b"7z\xbc\xaf\x27\x1c", // 7-Zip
+        b"Rar!\x1a\x07",       // RAR
+        b"\xff\xd8\xff",       // JPEG
+        b"\x89PNG\r\n\x1a\n",  // PNG
+        b"GIF8",               // GIF
+        b"OggS",               // Ogg
+        b"fLaC",               // FLAC
+        b"\x1a\x45\xdf\xa3",   // Matroska and WebM
+        b"ID3",                // MP3 with a tag
+    ];
+    let signed = SIGNATURES
+        .iter()
+        .any(|signature| header.starts_with(signature));
+    // MP4 and QuickTime carry their signature after the box size
+    let iso_media = header.get(4..8) == Some(b"ftyp");
+    let webp = header.starts_with(b"RIFF") && header.get(8..12) == Some(b"WEBP");
+    (signed || iso_media || webp).then_some(FormatClass::Compressed)
+}
+
+/// Classifies `path` by its extension, falling back to the signature in
+/// `header` when the extension is not known; files neither recognizes
+/// count as raw.
+pub fn classify(path: &Path, header: Option<&[u8]>) -> FormatClass {
+    classify_extension(path)
+        .or_else(|| header.and_then(classify_magic))
+        .unwrap_or(FormatClass::Raw)
+}
+
+/// Totals of the files of a comparison by [`FormatClass`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct DataProfile {
+    /// Number of files classified
+    pub files: usize,
+    /// Bytes in files of compressed formats
+    pub compressed_bytes: u64,
+    /// Bytes in files of raw formats
+    pub raw_bytes: u64,
+    /// Bytes in raw files of at least [`SAMPLING_THRESHOLD`] bytes, which
+    /// sampled hashing reads only in part
+    pub sampled_raw_bytes: u64,
+}
+
+impl DataProfile {
+    /// Adds a file of `size` bytes.
+    pub fn add(&mut self, class: FormatClass, size: u64) {
+        self.files += 1;
+        match class {
+            FormatClass::Compressed => self.compressed_bytes += size,
+            FormatClass::Raw => {
+                self.raw_bytes += size;
+                if size >= SAMPLING_THRESHOLD {
+                    self.sampled_raw_bytes += size;
+                }
+            }
+        }
+    }
+
+    /// Returns the bytes of every classified file.
+    pub fn total_bytes(&self) -> u64 {
+        self.compressed_bytes + self.raw_bytes
+    }
+
+    /// Returns the percentage of bytes in compressed formats, 0 when no
+    /// bytes were classified.
+    pub fn compressed_percent(&self) -> f64 {
+        percent(self.compressed_bytes, self.total_bytes())
+    }
+
+    /// Returns the percentage of bytes in raw formats, 0 when no bytes were
+    /// classified.
+    pub fn raw_percent(&self) -> f64 {
+        percent(self.raw_bytes, self.total_bytes())
+    }
+
+    /// Returns the share of bytes in raw files that sampled hashing reads
+    /// only in part, as a fraction.
+    pub fn sampled_raw_share(&self) -> f64 {
+        percent(self.sampled_raw_bytes, self.total_bytes()) / 100.0
+    }
+}
+
+fn percent(part: u64, total: u64) -> f64 {
+    if total == 0 {
+        0.0
+    } else {
+        part as f64 * 100.0 / total as f64
+    }
+}
+
+/// Returns the warning for a sampled comparison of the files in `profile`,
+/// or `None` when sampling is a sound choice for them.
+///
+/// Warns when more than `max_raw_share` of the bytes are in raw files large
+/// enough to be sampled, unless `reads_whole_files` because matches are
+/// verified or files are hashed whole anyway.
+pub fn sampling_advice(
+    profile: &DataProfile,
+    reads_whole_files: bool,
+    max_raw_share: f64,
+) -> Option<String> {
+    let share = profile.sampled_raw_share();
+    if reads_whole_files || profile.total_bytes() == 0 || share <= max_raw_share {
+        return None;
+    }
+    Some(format!(
+        "{:.0}% of the compared bytes are in raw formats such as text or disk images, where sampled hashing misses changes between its samples. Consider --verify or --method hash",
+        share * 100.0
+    ))
+}
+
+/// Builds a [`DataProfile`] from the files a strategy reads, from several
+/// threads.
+#[derive(Debug, Default)]
+pub struct DataProfiler {
+    sniff: bool,
+    profile: Mutex<DataProfile>,
+}
+
+impl DataProfiler {
+    /// Creates an empty profiler; with `sniff`, files whose extension is not
+    /// known are classified by their first [`SNIFF_LEN`] bytes.
+    pub fn new(sniff: bool) -> Self {
+        Self {
+            sniff,
+            ..Default::default()
+        }
+    }
+
+    /// Classifies the file at `path` of `size` bytes, reading its signature
+    /// through `fs` if needed.
+    pub fn record(&self, fs: &dyn FileSystem, path: &Path, size: u64) {
+        let class = match classify_extension(path) {
+            Some(class) => class,
+            None if self.sniff => classify(path, read_header(fs, path).as_deref()),
+            None => FormatClass::Raw,
+        };
+        self.profile.lock().unwrap().add(class, size);
+    }
+
+    /// Returns the totals recorded so far.
+    pub fn profile(&self) -> DataProfile {
+        *self.profile.lock().unwrap()
+    }
+}
+
+/// Reads up to [`SNIFF_LEN`] bytes from the start of a file; `None` if it
+/// cannot be read.
+fn read_header(fs: &dyn FileSystem, path: &Path) -> Option<Vec<u8>> {
+    let mut header = Vec::with_capacity(SNIFF_LEN);
+    fs.open(path)
+        .ok()?
+        .take(SNIFF_LEN as u64)
+        .read_to_end(&mut header)
+        .ok()?;
+    Some(header)
+}
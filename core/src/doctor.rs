@@ -0,0 +1,551 @@
+//! Checks of the environment a long comparison is about to run in, behind
+//! `dir-compare doctor`.
+//!
+//! Each check is a function returning a [`CheckResult`] that passes, warns
+//! or fails with an explanation, and [`verdict`] is the worst of them.
+//! Checks that touch the roots go through a [`FileSystem`], so tests can
+//! simulate slow or failing disks; the others judge values measured by the
+//! caller, such as a [`RootScan`] bounded to a number of entries.
+//!
+//! [`check_symlink_support`] creates a link in the root and removes it
+//! again, like [`detect_case_sensitivity`](crate::detect_case_sensitivity)
+//! does with a file.
+
+use std::collections::VecDeque;
+use std::fmt;
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime};
+
+use crate::comparison::DEFAULT_MTIME_TOLERANCE;
+use crate::filesystem::{CaseSensitivity, FileSystem};
+use crate::logger;
+use crate::output::format_bytes;
+
+/// Number of entries [`RootScan::scan`] visits before it stops
+pub const DEFAULT_SCAN_LIMIT: usize = 10_000;
+
+/// Bytes [`check_read_throughput`] reads in total
+pub const SAMPLE_BYTES: u64 = 16 * 1024 * 1024;
+
+/// Bytes [`check_read_throughput`] needs to read for a meaningful figure;
+/// for less, opening the files dominates the time
+pub const MIN_SAMPLE_BYTES: u64 = 1024 * 1024;
+
+/// Number of files, the largest found by a scan, read to measure throughput
+pub const SAMPLE_FILES: usize = 4;
+
+/// Read throughput in MB/s below which a run is expected to be slow
+pub const MIN_THROUGHPUT: f64 = 10.0;
+
+/// Free space below which reports and caches may not fit
+pub const MIN_FREE_SPACE: u64 = 100 * 1024 * 1024;
+
+/// Free space below which a large report or cache may fill the disk
+pub const LOW_FREE_SPACE: u64 = 1024 * 1024 * 1024;
+
+/// Path length, in characters, beyond which Windows tools without long path
+/// support fail
+pub const LONG_PATH: usize = 260;
+
+/// Path length, in bytes, beyond which Linux refuses a path
+pub const MAX_PATH: usize = 4096;
+
+/// Number of modification times a scan keeps for [`check_mtime_granularity`]
+const MTIME_SAMPLES: usize = 256;
+
+/// Start of the name of the link [`check_symlink_support`] creates
+const SYMLINK_PROBE_PREFIX: &str = ".dir-compare-symlink-probe";
+
+/// How far in the future a modification time may lie before the clock is
+/// blamed
+const FUTURE_SLACK: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// 2020-01-01; a system clock reading earlier is wrong
+const EARLIEST_PLAUSIBLE: Duration = Duration::from_secs(1_577_836_800);
+
+/// Outcome of a check, ordered from best to worst.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum CheckStatus {
+    Pass,
+    Warn,
+    Fail,
+}
+
+impl fmt::Display for CheckStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            CheckStatus::Pass => "PASS",
+            CheckStatus::Warn => "WARN",
+            CheckStatus::Fail => "FAIL",
+        })
+    }
+}
+
+/// A check's outcome and why.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CheckResult {
+    /// What was checked, such as "Directory A readable"
+    pub name: String,
+    pub status: CheckStatus,
+    pub explanation: String,
+}
+
+impl CheckResult {
+    pub fn pass(name: impl Into<String>, explanation: impl Into<String>) -> Self {
+        Self::new(name, CheckStatus::Pass, explanation)
+    }
+
+    pub fn warn(name: impl Into<String>, explanation: impl Into<String>) -> Self {
+        Self::new(name, CheckStatus::Warn, explanation)
+    }
+
+    pub fn fail(name: impl Into<String>, explanation: impl Into<String>) -> Self {
+        Self::new(name, CheckStatus::Fail, explanation)
+    }
+
+    fn new(name: impl Into<String>, status: CheckStatus, explanation: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            status,
+            explanation: explanation.into(),
+        }
+    }
+}
+
+/// The worst status among `results`; [`CheckStatus::Pass`] when empty.
+pub fn verdict(results: &[CheckResult]) -> CheckStatus {
+    results
+        .iter()
+        .map(|result| result.status)
+        .max()
+        .unwrap_or(CheckStatus::Pass)
+}
+
+/// What a bounded breadth-first walk of a root found.
+#[derive(Debug, Clone, Default)]
+pub struct RootScan {
+    /// Entries visited, not counting the root
+    pub entries: usize,
+    /// Whether the walk stopped at its limit before visiting every entry
+    pub truncated: bool,
+    /// Directories and entries whose metadata could not be read
+    pub unreadable: usize,
+    /// The visited path with the most characters
+    pub longest_path: Option<PathBuf>,
+    /// The largest files, up to [`SAMPLE_FILES`], largest first
+    pub largest_files: Vec<(PathBuf, u64)>,
+    /// Modification times of the first files visited
+    pub mtimes: Vec<SystemTime>,
+    /// Latest modification time of all files visited
+    pub latest_mtime: Option<SystemTime>,
+}
+
+impl RootScan {
+    /// Walks `root` without following symbolic links, stopping after
+    /// `limit` entries.
+    pub fn scan(fs: &dyn FileSystem, root: &Path, limit: usize) -> Self {
+        let mut scan = Self::default();
+        let mut directories = VecDeque::from([root.to_path_buf()]);
+        while let Some(directory) = directories.pop_front() {
+            let Ok(mut children) = fs.read_dir(&directory) else {
+                scan.unreadable += 1;
+                continue;
+            };
+            children.sort();
+            for path in children {
+                if scan.entries == limit {
+                    scan.truncated = true;
+                    return scan;
+                }
+                scan.entries += 1;
+                if scan
+                    .longest_path
+                    .as_deref()
+                    .is_none_or(|longest| path_length(&path) > path_length(longest))
+                {
+                    scan.longest_path = Some(path.clone());
+                }
+                match fs.symlink_metadata(&path) {
+                    Ok(metadata) if metadata.is_dir() => directories.push_back(path),
+                    Ok(metadata) if metadata.is_file() => {
+                        if let Ok(mtime) = metadata.modified() {
+                            if scan.mtimes.len() < MTIME_SAMPLES {
+                                scan.mtimes.push(mtime);
+                            }
+                            scan.latest_mtime = scan.latest_mtime.max(Some(mtime));
+                        }
+                        scan.keep_if_large(path, metadata.len());
+                    }
+                    Ok(_) => {}
+                    Err(_) => scan.unreadable += 1,
+                }
+            }
+        }
+        scan
+    }
+
+    fn keep_if_large(&mut self, path: PathBuf, size: u64) {
+        let index = self
+            .largest_files
+            .partition_point(|&(_, kept)| kept >= size);
+        if index < SAMPLE_FILES {
+            self.largest_files.insert(index, (path, size));
+            self.largest_files.truncate(SAMPLE_FILES);
+        }
+    }
+
+    /// Paths of [`largest_files`](Self::largest_files), largest first
+    pub fn sample_files(&self) -> Vec<PathBuf> {
+        self.largest_files
+            .iter()
+            .map(|(path, _)| path.clone())
+            .collect()
+    }
+}
+
+fn path_length(path: &Path) -> usize {
+    path.to_string_lossy().chars().count()
+}
+
+/// Checks that `root` is a directory whose entries can be listed, and
+/// warns about the entries `scan` could not read.
+pub fn check_root_readable(
+    fs: &dyn FileSystem,
+    label: &str,
+    root: &Path,
+    scan: &RootScan,
+) -> CheckResult {
+    let name = format!("{} readable", label);
+    match fs.metadata(root) {
+        Err(e) => return CheckResult::fail(name, format!("cannot read {}: {}", root.display(), e)),
+        Ok(metadata) if !metadata.is_dir() => {
+            return CheckResult::fail(name, format!("{} is not a directory", root.display()));
+        }
+        Ok(_) => {}
+    }
+    if let Err(e) = fs.read_dir(root) {
+        return CheckResult::fail(name, format!("cannot list {}: {}", root.display(), e));
+    }
+    let visited = if scan.truncated {
+        format!("the first {} entries", scan.entries)
+    } else {
+        format!("{} entries", scan.entries)
+    };
+    if scan.unreadable > 0 {
+        CheckResult::warn(
+            name,
+            format!(
+                "{} of {} could not be read and will be reported as warnings",
+                scan.unreadable, visited
+            ),
+        )
+    } else {
+        CheckResult::pass(name, format!("listed {}", visited))
+    }
+}
+
+/// Measures how fast `files` can be read, reading up to [`SAMPLE_BYTES`]
+/// from them in order, and warns below [`MIN_THROUGHPUT`]. Less than
+/// [`MIN_SAMPLE_BYTES`] passes unmeasured.
+///
+/// Files read recently may come from the page cache and read faster than
+/// the disk does.
+pub fn check_read_throughput(fs: &dyn FileSystem, label: &str, files: &[PathBuf]) -> CheckResult {
+    let name = format!("{} read throughput", label);
+    let start = Instant::now();
+    let mut total = 0;
+    let mut read = 0;
+    for path in files {
+        if total >= SAMPLE_BYTES {
+            break;
+        }
+        let copied = fs
+            .open_sequential(path)
+            .and_then(|file| io::copy(&mut file.take(SAMPLE_BYTES - total), &mut io::sink()));
+        match copied {
+            Ok(bytes) => total += bytes,
+            Err(e) => {
+                return CheckResult::warn(name, format!("cannot read {}: {}", path.display(), e));
+            }
+        }
+        read += 1;
+    }
+    if total < MIN_SAMPLE_BYTES {
+        return CheckResult::pass(
+            name,
+            format!(
+                "{} of file content is too little to measure",
+                format_bytes(total)
+            ),
+        );
+    }
+    let seconds = start.elapsed().as_secs_f64().max(f64::EPSILON);
+    let throughput = total as f64 / seconds / 1e6;
+    let explanation = format!(
+        "{:.1} MB/s reading {} from {} files",
+        throughput,
+        format_bytes(total),
+        read
+    );
+    if throughput < MIN_THROUGHPUT {
+        CheckResult::warn(
+            name,
+            format!(
+                "{}; hashing methods will be slow, consider --method size or --method mtime",
+                explanation
+            ),
+        )
+    } else {
+        CheckResult::pass(name, explanation)
+    }
+}
+
+/// Judges the `available` bytes on the filesystem holding `dir`, where
+/// reports and caches are written.
+pub fn check_free_space(dir: &Path, available: Option<u64>) -> CheckResult {
+    let name = "Free space";
+    let Some(available) = available else {
+        return CheckResult::warn(
+            name,
+            format!("cannot determine the free space in {}", dir.display()),
+        );
+    };
+    let explanation = format!("{} free in {}", format_bytes(available), dir.display());
+    if available < MIN_FREE_SPACE {
+        CheckResult::fail(
+            name,
+            format!("{}; reports and caches may not fit", explanation),
+        )
+    } else if available < LOW_FREE_SPACE {
+        CheckResult::warn(
+            name,
+            format!(
+                "{}; a large report or hash cache may fill the disk",
+                explanation
+            ),
+        )
+    } else {
+        CheckResult::pass(name, explanation)
+    }
+}
+
+/// Bytes available to unprivileged users on the filesystem holding `dir`;
+/// `None` where unknown.
+#[cfg(any(target_os = "linux", target_os = "android"))]
+pub fn available_space(dir: &Path) -> Option<u64> {
+    use std::os::unix::ffi::OsStrExt;
+
+    let path = std::ffi::CString::new(dir.as_os_str().as_bytes()).ok()?;
+    let mut stat = std::mem::MaybeUninit::<libc::statvfs>::uninit();
+    // SAFETY: `path` is NUL-terminated and `stat` is written on success
+    if unsafe { libc::statvfs(path.as_ptr(), stat.as_mut_ptr()) } != 0 {
+        return None;
+    }
+    // SAFETY: statvfs succeeded
+    let stat = unsafe { stat.assume_init() };
+    // The field types differ between targets
+    #[allow(clippy::useless_conversion)]
+    Some(u64::from(stat.f_bavail) * u64::from(stat.f_frsize))
+}
+
+/// Bytes available on the filesystem holding `dir`; unknown on this platform.
+#[cfg(not(any(target_os = "linux", target_os = "android")))]
+pub fn available_space(_dir: &Path) -> Option<u64> {
+    None
+}
+
+/// Compares the case sensitivity of both roots, probed with
+/// [`detect_case_sensitivity`](crate::detect_case_sensitivity).
+pub fn check_case_sensitivity(a: CaseSensitivity, b: CaseSensitivity) -> CheckResult {
+    let name = "Case sensitivity";
+    let describe = |sensitivity| match sensitivity {
+        CaseSensitivity::Sensitive => "case-sensitive",
+        CaseSensitivity::Insensitive => "case-insensitive",
+        CaseSensitivity::Unknown => "unknown",
+    };
+    match (a, b) {
+        (CaseSensitivity::Sensitive, CaseSensitivity::Sensitive) => {
+            CheckResult::pass(name, "both roots are case-sensitive")
+        }
+        (CaseSensitivity::Insensitive, CaseSensitivity::Insensitive) => CheckResult::pass(
+            name,
+            "both roots are case-insensitive; names are matched case-insensitively",
+        ),
+        _ if a == CaseSensitivity::Unknown || b == CaseSensitivity::Unknown => CheckResult::warn(
+            name,
+            format!(
+                "cannot probe a read-only root (A: {}, B: {}); names are matched case-sensitively",
+                describe(a),
+                describe(b)
+            ),
+        ),
+        _ => CheckResult::warn(
+            name,
+            format!(
+                "A is {} and B is {}; names differing only in case show as A-only and B-only",
+                describe(a),
+                describe(b)
+            ),
+        ),
+    }
+}
+
+/// Checks that symbolic links can be created in `root` by creating a
+/// dangling one and removing it again.
+///
+/// Links are compared as links and never followed, so a root without them
+/// only warns.
+pub fn check_symlink_support(fs: &dyn FileSystem, label: &str, root: &Path) -> CheckResult {
+    let name = format!("{} symbolic links", label);
+    let probe_name = format!("{}-{}", SYMLINK_PROBE_PREFIX, std::process::id());
+    let probe = root.join(&probe_name);
+    if fs.exists(&probe) {
+        return CheckResult::warn(name, format!("a probe is left over at {}", probe.display()));
+    }
+    if let Err(e) = fs.create_symlink(Path::new(&format!("{}-target", probe_name)), &probe) {
+        return CheckResult::warn(
+            name,
+            format!("cannot create a link in {}: {}", root.display(), e),
+        );
+    }
+    let is_link = fs
+        .symlink_metadata(&probe)
+        .is_ok_and(|metadata| metadata.file_type().is_symlink());
+    if let Err(e) = fs.remove_file(&probe) {
+        logger::warn(&format!(
+            "Could not remove symbolic link probe {}: {}",
+            probe.display(),
+            e
+        ));
+    }
+    if is_link {
+        CheckResult::pass(name, "supported; links are compared as links, not followed")
+    } else {
+        CheckResult::warn(name, "the filesystem did not keep the link")
+    }
+}
+
+/// Infers the modification time granularity from sampled `mtimes`, and
+/// warns when it is coarser than the default tolerance of `--method mtime`.
+pub fn check_mtime_granularity(label: &str, mtimes: &[SystemTime]) -> CheckResult {
+    let name = format!("{} modification times", label);
+    let since_epoch: Vec<Duration> = mtimes
+        .iter()
+        .filter_map(|mtime| mtime.duration_since(SystemTime::UNIX_EPOCH).ok())
+        .collect();
+    if since_epoch.is_empty() {
+        return CheckResult::pass(name, "no files to sample");
+    }
+    if since_epoch.iter().any(|time| time.subsec_nanos() != 0) {
+        return CheckResult::pass(name, "sub-second precision");
+    }
+    let granularity = if since_epoch.iter().any(|time| time.as_secs() % 2 == 1) {
+        Duration::from_secs(1)
+    } else {
+        Duration::from_secs(2)
+    };
+    let explanation = format!(
+        "all {} sampled times are multiples of {} s",
+        since_epoch.len(),
+        granularity.as_secs()
+    );
+    if granularity > DEFAULT_MTIME_TOLERANCE {
+        CheckResult::warn(
+            name,
+            format!(
+                "{}, as on FAT; pass --mtime-tolerance {} with --method mtime",
+                explanation,
+                granularity.as_secs()
+            ),
+        )
+    } else {
+        CheckResult::pass(name, explanation)
+    }
+}
+
+/// Judges the longest path a scan found against the limits of Windows
+/// tools and of Linux.
+pub fn check_path_length(label: &str, scan: &RootScan) -> CheckResult {
+    let name = format!("{} path length", label);
+    let Some(longest) = &scan.longest_path else {
+        return CheckResult::pass(name, "no entries");
+    };
+    let length = path_length(longest);
+    let scope = if scan.truncated {
+        format!(" among the first {} entries", scan.entries)
+    } else {
+        String::new()
+    };
+    let explanation = format!("longest path{} has {} characters", scope, length);
+    if longest.as_os_str().len() >= MAX_PATH {
+        CheckResult::fail(
+            name,
+            format!(
+                "{}, beyond the limit of {} bytes: {}",
+                explanation,
+                MAX_PATH,
+                longest.display()
+            ),
+        )
+    } else if length >= LONG_PATH {
+        CheckResult::warn(
+            name,
+            format!(
+                "{}, beyond what Windows tools without long path support open: {}",
+                explanation,
+                longest.display()
+            ),
+        )
+    } else {
+        CheckResult::pass(name, explanation)
+    }
+}
+
+/// Checks that the system clock reads `now` plausibly, and that no file
+/// was modified later than a day after it.
+pub fn check_clock(now: SystemTime, latest_mtime: Option<SystemTime>) -> CheckResult {
+    let name = "Clock";
+    if now < SystemTime::UNIX_EPOCH + EARLIEST_PLAUSIBLE {
+        return CheckResult::fail(name, "the system clock reads a time before 2020");
+    }
+    if let Some(ahead) = latest_mtime.and_then(|latest| latest.duration_since(now).ok())
+        && ahead > FUTURE_SLACK
+    {
+        return CheckResult::warn(
+            name,
+            format!(
+                "a file was modified {} hours in the future; this machine and the one that wrote it disagree on the time",
+                ahead.as_secs() / 3600
+            ),
+        );
+    }
+    CheckResult::pass(name, "the system clock and file times are plausible")
+}
+
+/// Compares the devices of both roots, as returned by [`device_id`].
+pub fn check_shared_device(a: Option<u64>, b: Option<u64>) -> CheckResult {
+    let name = "Shared device";
+    match (a, b) {
+        (Some(a), Some(b)) if a == b => CheckResult::pass(
+            name,
+            "both roots are on one device; their reads compete, so --order-by-extent helps on spinning disks",
+        ),
+        (Some(_), Some(_)) => CheckResult::pass(name, "the roots are on different devices"),
+        _ => CheckResult::warn(name, "cannot determine the devices of the roots"),
+    }
+}
+
+/// The device holding `path`; `None` where unknown.
+pub fn device_id(fs: &dyn FileSystem, path: &Path) -> Option<u64> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::MetadataExt;
+        fs.metadata(path).ok().map(|metadata| metadata.dev())
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = (fs, path);
+        None
+    }
+}
@@ -0,0 +1,543 @@
+//! Comparison of chosen files by external commands.
+//!
+//! Some formats need their own notion of equality, such as two PDFs that
+//! differ only in their creation date. An [`ExternalCommandStrategy`] runs
+//! the command of the first [`ExternalRule`] whose glob matches a file pair,
+//! and treats exit status 0 as a match; every other pair is compared by an
+//! inner strategy. Commands run without a shell: `{a}` and `{b}` are
+//! replaced inside each argument, so paths with spaces or any other
+//! characters reach the command as they are.
+//!
+//! With the `config` feature, [`parse_external_rules`] reads the rules from
+//! a TOML file such as
+//!
+//! ```toml
+//! timeout-secs = 30
+//! max-processes = 4
+//!
+//! [[rule]]
+//! pattern = "*.pdf"
+//! command = "pdf-compare {a} {b}"
+//! ```
+
+use std::ffi::OsString;
+use std::fmt;
+use std::io::Read;
+use std::path::Path;
+use std::process::{Command, Stdio};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Condvar, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::comparison::{
+    ComparisonStrategy, Entry, EntryFilter, EntryKind, FileWarning, FilenameOnlyStrategy,
+    MatchVerdict, MismatchReason, WarningCause, whole_files_read,
+};
+use crate::filesystem::FileSystem;
+
+/// Time a command may run before it is killed and its pair reported as
+/// modified
+pub const DEFAULT_EXTERNAL_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Longest stderr output kept for a warning, in bytes
+const MAX_STDERR: usize = 4096;
+
+/// A rule that compares the files matching a glob with a command.
+#[derive(Debug, Clone)]
+pub struct ExternalRule {
+    pattern: String,
+    filter: EntryFilter,
+    words: Vec<String>,
+}
+
+impl ExternalRule {
+    /// Creates a rule running the command `words` for files whose relative
+    /// path matches `pattern`, a gitignore-style glob like `--include`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`std::io::ErrorKind::InvalidInput`] error for an invalid
+    /// glob, an empty command, or a command without `{a}` and `{b}`.
+    pub fn new<S: AsRef<str>>(pattern: &str, words: &[S]) -> std::io::Result<Self> {
+        let words: Vec<String> = words.iter().map(|word| word.as_ref().to_string()).collect();
+        let invalid =
+            |message: String| std::io::Error::new(std::io::ErrorKind::InvalidInput, message);
+        if words.is_empty() {
+            return Err(invalid(format!("the command for '{}' is empty", pattern)));
+        }
+        for placeholder in ["{a}", "{b}"] {
+            if !words.iter().any(|word| word.contains(placeholder)) {
+                return Err(invalid(format!(
+                    "the command for '{}' must contain {}",
+                    pattern, placeholder
+                )));
+            }
+        }
+        Ok(Self {
+            pattern: pattern.to_string(),
+            filter: EntryFilter::new(&[pattern], &[])?,
+            words,
+        })
+    }
+
+    /// The glob this rule was created with.
+    pub fn pattern(&self) -> &str {
+        &self.pattern
+    }
+
+    /// The program the rule runs.
+    pub fn program(&self) -> &str {
+        &self.words[0]
+    }
+
+    /// Returns true if the file at `path`, relative to the compared root,
+    /// is compared by this rule.
+    pub fn matches(&self, path: &Path) -> bool {
+        self.filter.matches(path, false)
+    }
+
+    /// Builds the command comparing the files at `a` and `b`, replacing
+    /// `{a}` and `{b}` in every word.
+    pub fn command(&self, a: &Path, b: &Path) -> Command {
+        let mut words = self.words.iter().map(|word| substitute(word, a, b));
+        let mut command = Command::new(words.next().unwrap_or_default());
+        command.args(words);
+        command
+    }
+}
+
+/// Replaces `{a}` and `{b}` in `word` with the paths, which need not be
+/// valid UTF-8.
+fn substitute(word: &str, a: &Path, b: &Path) -> OsString {
+    let mut arg = OsString::new();
+    let mut rest = word;
+    while let Some(start) = rest.find('{') {
+        let path = match &rest[start..] {
+            tail if tail.starts_with("{a}") => a,
+            tail if tail.starts_with("{b}") => b,
+            _ => {
+                arg.push(&rest[..=start]);
+                rest = &rest[start + 1..];
+                continue;
+            }
+        };
+        arg.push(&rest[..start]);
+        arg.push(path);
+        rest = &rest[start + 3..];
+    }
+    arg.push(rest);
+    arg
+}
+
+/// The rules of an [`ExternalCommandStrategy`] and how their commands run.
+#[derive(Debug, Clone)]
+pub struct ExternalRules {
+    /// Rules in order; the first one matching a file applies
+    pub rules: Vec<ExternalRule>,
+    /// Time each command may run before it is killed
+    pub timeout: Duration,
+    /// Number of commands running at once
+    pub max_processes: usize,
+}
+
+impl Default for ExternalRules {
+    fn default() -> Self {
+        Self {
+            rules: Vec::new(),
+            timeout: DEFAULT_EXTERNAL_TIMEOUT,
+            max_processes: std::thread::available_parallelism().map_or(1, |n| n.get()),
+        }
+    }
+}
+
+impl ExternalRules {
+    /// Returns the first rule matching the file at `path`, relative to the
+    /// compared root.
+    pub fn rule_for(&self, path: &Path) -> Option<&ExternalRule> {
+        self.rules.iter().find(|rule| rule.matches(path))
+    }
+}
+
+/// A rules file that cannot be used
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExternalRulesError {
+    pub message: String,
+}
+
+impl fmt::Display for ExternalRulesError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Invalid external rules: {}", self.message)
+    }
+}
+
+impl std::error::Error for ExternalRulesError {}
+
+/// Parses a rules file: optional `timeout-secs` and `max-processes`, and a
+/// `[[rule]]` table per rule with a `pattern` and a `command`. The command
+/// is a template split into words like a shell would, or an array of words.
+///
+/// # Errors
+///
+/// Returns an error for text that is not TOML, unknown keys, values of the
+/// wrong type, and rules [`ExternalRule::new`] rejects.
+#[cfg(feature = "config")]
+pub fn parse_external_rules(text: &str) -> Result<ExternalRules, ExternalRulesError> {
+    let error = |message: String| ExternalRulesError { message };
+    let table: toml::Table = text
+        .parse()
+        .map_err(|e: toml::de::Error| error(e.to_string().trim_end().to_string()))?;
+    let positive = |key: &str, value: &toml::Value| match value.as_integer() {
+        Some(n) if n > 0 => Ok(n as u64),
+        _ => Err(error(format!("'{}' must be a positive integer", key))),
+    };
+
+    let mut rules = ExternalRules::default();
+    for (key, value) in &table {
+        match key.as_str() {
+            "timeout-secs" => rules.timeout = Duration::from_secs(positive(key, value)?),
+            "max-processes" => rules.max_processes = positive(key, value)? as usize,
+            "rule" => {
+                let tables = value
+                    .as_array()
+                    .ok_or_else(|| error("'rule' must be an array of tables".to_string()))?;
+                for (index, rule) in tables.iter().enumerate() {
+                    let rule = rule.as_table().ok_or_else(|| {
+                        error(format!(
+                            "rule {} is a {}, not a table",
+                            index + 1,
+                            rule.type_str()
+                        ))
+                    })?;
+                    rules.rules.push(parse_rule(rule, index + 1)?);
+                }
+            }
+            other => return Err(error(format!("unknown key '{}'", other))),
+        }
+    }
+    Ok(rules)
+}
+
+/// Parses the `number`th `[[rule]]` table.
+#[cfg(feature = "config")]
+fn parse_rule(rule: &toml::Table, number: usize) -> Result<ExternalRule, ExternalRulesError> {
+    let error = |message: String| ExternalRulesError {
+        message: format!("rule {}: {}", number, message),
+    };
+    if let Some(key) = rule
+        .keys()
+        .find(|key| !matches!(key.as_str(), "pattern" | "command"))
+    {
+        return Err(error(format!("unknown key '{}'", key)));
+    }
+    let pattern = rule
+        .get("pattern")
+        .and_then(toml::Value::as_str)
+        .ok_or_else(|| error("'pattern' must be a string".to_string()))?;
+    let words = match rule.get("command") {
+        Some(toml::Value::String(template)) => split_command(template).map_err(error)?,
+        Some(toml::Value::Array(items)) => items
+            .iter()
+            .map(|item| item.as_str().map(str::to_string))
+            .collect::<Option<Vec<_>>>()
+            .ok_or_else(|| error("'command' must list strings".to_string()))?,
+        _ => {
+            return Err(error(
+                "'command' must be a string or an array of strings".to_string(),
+            ));
+        }
+    };
+    ExternalRule::new(pattern, &words).map_err(|e| error(e.to_string()))
+}
+
+/// Splits a command template into words. Whitespace separates words; single
+/// quotes keep text literally, double quotes allow `\"` and `\\`, and a
+/// backslash outside quotes escapes the next character.
+pub fn split_command(template: &str) -> Result<Vec<String>, String> {
+    let mut words = Vec::new();
+    let mut word = String::new();
+    let mut in_word = false;
+    let mut chars = template.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            c if c.is_whitespace() => {
+                if in_word {
+                    words.push(std::mem::take(&mut word));
+                    in_word = false;
+                }
+            }
+            '\'' => {
+                in_word = true;
+                loop {
+                    match chars.next() {
+                        Some('\'') => break,
+                        Some(c) => word.push(c),
+                        None => return Err("unterminated single quote".to_string()),
+                    }
+                }
+            }
+            '"' => {
+                in_word = true;
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some('\\') => match chars.next() {
+                            Some(c @ ('"' | '\\')) => word.push(c),
+                            Some(c) => {
+                                word.push('\\');
+                                word.push(c);
+                            }
+                            None => return Err("unterminated double quote".to_string()),
+                        },
+                        Some(c) => word.push(c),
+                        None => return Err("unterminated double quote".to_string()),
+                    }
+                }
+            }
+            '\\' => {
+                in_word = true;
+                match chars.next() {
+                    Some(c) => word.push(c),
+                    None => return Err("trailing backslash".to_string()),
+                }
+            }
+            c => {
+                in_word = true;
+                word.push(c);
+            }
+        }
+    }
+    if in_word {
+        words.push(word);
+    }
+    Ok(words)
+}
+
+/// Limits the number of commands running at once.
+#[derive(Debug)]
+struct ProcessSlots {
+    free: Mutex<usize>,
+    freed: Condvar,
+}
+
+impl ProcessSlots {
+    fn new(count: usize) -> Self {
+        Self {
+            free: Mutex::new(count.max(1)),
+            freed: Condvar::new(),
+        }
+    }
+
+    /// Waits for a free slot and holds it until the guard is dropped.
+    fn acquire(&self) -> SlotGuard<'_> {
+        let mut free = self.free.lock().unwrap_or_else(|e| e.into_inner());
+        while *free == 0 {
+            free = self.freed.wait(free).unwrap_or_else(|e| e.into_inner());
+        }
+        *free -= 1;
+        SlotGuard(self)
+    }
+}
+
+struct SlotGuard<'a>(&'a ProcessSlots);
+
+impl Drop for SlotGuard<'_> {
+    fn drop(&mut self) {
+        *self.0.free.lock().unwrap_or_else(|e| e.into_inner()) += 1;
+        self.0.freed.notify_one();
+    }
+}
+
+/// Comparison strategy that compares the files matching an
+/// [`ExternalRule`] with its command, and everything else with an inner
+/// strategy.
+///
+/// A command's pair matches if it exits with status 0. A command that
+/// fails with output on stderr, cannot be started or runs longer than the
+/// timeout adds a [`WarningCause::External`] warning carrying the reason,
+/// and its pair is reported as modified. Files that match no rule never
+/// start a process.
+///
+/// # Examples
+///
+/// ```
+/// use dir_compare_core::external::{ExternalCommandStrategy, ExternalRule, ExternalRules};
+/// use dir_compare_core::FastHashStrategy;
+///
+/// let rules = ExternalRules {
+///     rules: vec![ExternalRule::new("*.pdf", &["pdf-compare", "{a}", "{b}"]).unwrap()],
+///     ..Default::default()
+/// };
+/// let strategy = ExternalCommandStrategy::new(Box::new(FastHashStrategy::new(false)), rules);
+/// ```
+pub struct ExternalCommandStrategy {
+    inner: Box<dyn ComparisonStrategy>,
+    rules: ExternalRules,
+    slots: ProcessSlots,
+    commands_run: AtomicUsize,
+}
+
+impl ExternalCommandStrategy {
+    /// Creates a strategy applying `rules`, comparing the files no rule
+    /// matches with `inner`, whose name matching is used throughout.
+    pub fn new(inner: Box<dyn ComparisonStrategy>, rules: ExternalRules) -> Self {
+        Self {
+            inner,
+            slots: ProcessSlots::new(rules.max_processes),
+            rules,
+            commands_run: AtomicUsize::new(0),
+        }
+    }
+
+    /// Number of commands started so far.
+    pub fn commands_run(&self) -> usize {
+        self.commands_run.load(Ordering::Relaxed)
+    }
+
+    /// Returns the rule comparing `a` and `b`, if they are files whose path
+    /// a rule matches.
+    fn rule(&self, a: &Entry, b: &Entry) -> Option<&ExternalRule> {
+        match (&a.kind, &b.kind) {
+            (EntryKind::File, EntryKind::File) => self.rules.rule_for(&a.path),
+            _ => None,
+        }
+    }
+
+    /// Runs the command of `rule` on the files `a` and `b`, returning
+    /// whether it reported them equal.
+    fn run(
+        &self,
+        rule: &ExternalRule,
+        a: &Path,
+        b: &Path,
+        warnings: &mut Vec<FileWarning>,
+    ) -> bool {
+        let _slot = self.slots.acquire();
+        let program = rule.program();
+        let mut command = rule.command(a, b);
+        command
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::piped());
+        self.commands_run.fetch_add(1, Ordering::Relaxed);
+        let mut child = match command.spawn() {
+            Ok(child) => child,
+            Err(e) => {
+                let message = format!("could not run `{}`: {}", program, e);
+                warnings.push(FileWarning::new(a, WarningCause::External, &message));
+                return false;
+            }
+        };
+
+        // Read stderr on its own thread so a chatty command cannot fill the
+        // pipe and stall
+        let stderr = child.stderr.take().map(|mut stderr| {
+            std::thread::spawn(move || {
+                let mut output = Vec::new();
+                let _ = stderr.read_to_end(&mut output);
+                output
+            })
+        });
+
+        let deadline = Instant::now() + self.rules.timeout;
+        let mut poll = Duration::from_millis(1);
+        let status = loop {
+            match child.try_wait() {
+                Ok(Some(status)) => break Ok(status),
+                Ok(None) if Instant::now() >= deadline => {
+                    let _ = child.kill();
+                    let _ = child.wait();
+                    break Err(format!(
+                        "`{}` did not finish within {:?} and was killed",
+                        program, self.rules.timeout
+                    ));
+                }
+                Ok(None) => {
+                    std::thread::sleep(
+                        poll.min(deadline.saturating_duration_since(Instant::now())),
+                    );
+                    poll = (poll * 2).min(Duration::from_millis(50));
+                }
+                Err(e) => {
+                    let _ = child.kill();
+                    break Err(format!("could not wait for `{}`: {}", program, e));
+                }
+            }
+        };
+
+        match status {
+            Ok(status) if status.success() => true,
+            Ok(status) => {
+                // A killed command may have left children holding stderr open,
+                // so its output is only read after a normal exit
+                let output = stderr
+                    .and_then(|reader| reader.join().ok())
+                    .unwrap_or_default();
+                let output = String::from_utf8_lossy(&output[..output.len().min(MAX_STDERR)])
+                    .trim()
+                    .to_string();
+                if !output.is_empty() {
+                    let message = format!("`{}` exited with {}: {}", program, status, output);
+                    warnings.push(FileWarning::new(a, WarningCause::External, &message));
+                }
+                false
+            }
+            Err(message) => {
+                warnings.push(FileWarning::new(a, WarningCause::External, &message));
+                false
+            }
+        }
+    }
+}
+
+impl ComparisonStrategy for ExternalCommandStrategy {
+    fn matches(&self, a: &Entry, b: &Entry) -> bool {
+        self.matches_reporting(a, b, &mut Vec::new())
+    }
+
+    /// Files a rule applies to count as read whole by its command.
+    fn estimated_read(&self, a: &Entry, b: &Entry) -> Option<u64> {
+        match self.rules.rule_for(&a.path) {
+            Some(_) => whole_files_read(a, b),
+            None => self.inner.estimated_read(a, b),
+        }
+    }
+
+    fn matches_reporting(&self, a: &Entry, b: &Entry, warnings: &mut Vec<FileWarning>) -> bool {
+        match self.rule(a, b) {
+            Some(rule) => {
+                FilenameOnlyStrategy::new(self.inner.case_insensitive()).matches(a, b)
+                    && self.run(rule, &a.abs_path, &b.abs_path, warnings)
+            }
+            None => self.inner.matches_reporting(a, b, warnings),
+        }
+    }
+
+    /// Pairs without a rule get the verdict of the inner strategy; the
+    /// command of a rule tells only whether files differ.
+    fn compare_reporting(
+        &self,
+        a: &Entry,
+        b: &Entry,
+        warnings: &mut Vec<FileWarning>,
+    ) -> MatchVerdict {
+        let reported = warnings.len();
+        if self.rule(a, b).is_none() {
+            return self.inner.compare_reporting(a, b, warnings);
+        }
+        if self.matches_reporting(a, b, warnings) {
+            MatchVerdict::Match
+        } else if warnings.len() > reported {
+            MatchVerdict::Mismatch(MismatchReason::Unreadable)
+        } else {
+            MatchVerdict::Mismatch(MismatchReason::ContentDiffers)
+        }
+    }
+
+    fn case_insensitive(&self) -> bool {
+        self.inner.case_insensitive()
+    }
+
+    fn file_system(&self) -> &dyn FileSystem {
+        self.inner.file_system()
+    }
+}
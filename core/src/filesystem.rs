@@ -0,0 +1,371 @@
+//! Filesystem access used to resolve comparison roots and by the file
+//! operations in [`crate::ops`] and [`crate::sync`].
+//!
+//! Roots are canonicalized so that entry paths can be made relative by
+//! stripping the root prefix. Some sandboxes refuse to resolve symlinks above
+//! the compared directories even though reading inside them works, so
+//! canonicalization is best-effort and falls back to a lexically normalized
+//! absolute path.
+//!
+//! [`detect_case_sensitivity`] probes whether a root's filesystem treats
+//! names that differ only in case as the same file.
+//!
+//! Files hashed whole are opened with [`FileSystem::open_sequential`], which
+//! asks the OS to read them ahead; [`FileSystem::physical_offset`] lets a
+//! comparison hash files in their order on disk. Both are hints, and do
+//! nothing where the platform does not support them.
+
+use std::io;
+use std::path::{Component, Path, PathBuf};
+
+use crate::logger;
+
+/// Filesystem operations that root resolution and file operations depend on.
+///
+/// Implemented by [`RealFileSystem`]; tests can substitute their own
+/// implementation to simulate failures. The methods used only by
+/// [`crate::ops`], [`crate::sync`] and [`crate::doctor`] default to `std::fs`.
+pub trait FileSystem {
+    /// Returns the canonical, absolute form of a path with all symlinks resolved.
+    fn canonicalize(&self, path: &Path) -> io::Result<PathBuf>;
+
+    /// Returns the metadata of a path, following symlinks.
+    fn metadata(&self, path: &Path) -> io::Result<std::fs::Metadata>;
+
+    /// Returns the size of a file as reported by its metadata, following
+    /// symlinks.
+    fn len(&self, path: &Path) -> io::Result<u64> {
+        Ok(self.metadata(path)?.len())
+    }
+
+    /// Opens a file for reading.
+    fn open(&self, path: &Path) -> io::Result<Box<dyn io::Read>> {
+        Ok(Box::new(std::fs::File::open(path)?))
+    }
+
+    /// Opens a file that is about to be read from start to end, hinting the
+    /// OS to read it ahead. Defaults to [`open`](Self::open).
+    fn open_sequential(&self, path: &Path) -> io::Result<Box<dyn io::Read>> {
+        self.open(path)
+    }
+
+    /// Returns the physical position on disk of the start of a file, used
+    /// to order hashing work on spinning disks; `None` where unknown.
+    fn physical_offset(&self, _path: &Path) -> Option<u64> {
+        None
+    }
+
+    /// Whether paths name local files that may also be read around this
+    /// filesystem, such as by memory-mapping them. Defaults to false, so the
+    /// contents of other implementations are always read through
+    /// [`open`](Self::open).
+    fn is_local(&self) -> bool {
+        false
+    }
+
+    /// Copies the contents of `from` to `to`, returning the number of bytes copied.
+    fn copy(&self, from: &Path, to: &Path) -> io::Result<u64> {
+        std::fs::copy(from, to)
+    }
+
+    /// Removes a file.
+    fn remove_file(&self, path: &Path) -> io::Result<()> {
+        std::fs::remove_file(path)
+    }
+
+    /// Creates a directory and all of its missing parents.
+    fn create_dir_all(&self, path: &Path) -> io::Result<()> {
+        std::fs::create_dir_all(path)
+    }
+
+    /// Removes an empty directory.
+    fn remove_dir(&self, path: &Path) -> io::Result<()> {
+        std::fs::remove_dir(path)
+    }
+
+    /// Sets the modification time of a file.
+    fn set_modified(&self, path: &Path, mtime: std::time::SystemTime) -> io::Result<()> {
+        // Unix only needs ownership to change the times, so read-only copies
+        // are opened for reading
+        #[cfg(unix)]
+        let file = std::fs::File::open(path)?;
+        #[cfg(not(unix))]
+        let file = std::fs::File::options().write(true).open(path)?;
+        file.set_modified(mtime)
+    }
+
+    /// Creates an empty file, failing if the path already exists.
+    fn create_new(&self, path: &Path) -> io::Result<()> {
+        std::fs::File::create_new(path).map(drop)
+    }
+
+    /// Returns whether a path exists, without following a final symlink.
+    fn exists(&self, path: &Path) -> bool {
+        std::fs::symlink_metadata(path).is_ok()
+    }
+
+    /// Returns the metadata of a path, without following a final symlink.
+    fn symlink_metadata(&self, path: &Path) -> io::Result<std::fs::Metadata> {
+        std::fs::symlink_metadata(path)
+    }
+
+    /// Returns the paths of the entries of a directory, in no particular order.
+    fn read_dir(&self, path: &Path) -> io::Result<Vec<PathBuf>> {
+        std::fs::read_dir(path)?
+            .map(|entry| entry.map(|entry| entry.path()))
+            .collect()
+    }
+
+    /// Creates a symbolic link at `link` pointing to `target`.
+    fn create_symlink(&self, target: &Path, link: &Path) -> io::Result<()> {
+        #[cfg(unix)]
+        return std::os::unix::fs::symlink(target, link);
+        #[cfg(windows)]
+        return std::os::windows::fs::symlink_file(target, link);
+        #[cfg(not(any(unix, windows)))]
+        {
+            let _ = (target, link);
+            Err(io::Error::from(io::ErrorKind::Unsupported))
+        }
+    }
+}
+
+/// A [`FileSystem`] shared by the worker threads of a comparison.
+pub type SharedFileSystem = std::sync::Arc<dyn FileSystem + Send + Sync>;
+
+/// The [`FileSystem`] backed by `std::fs`.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RealFileSystem;
+
+impl FileSystem for RealFileSystem {
+    fn canonicalize(&self, path: &Path) -> io::Result<PathBuf> {
+        std::fs::canonicalize(path)
+    }
+
+    fn metadata(&self, path: &Path) -> io::Result<std::fs::Metadata> {
+        std::fs::metadata(path)
+    }
+
+    /// Advises sequential access and read-ahead with `posix_fadvise` on
+    /// Linux and Android.
+    fn open_sequential(&self, path: &Path) -> io::Result<Box<dyn io::Read>> {
+        let file = std::fs::File::open(path)?;
+        advise_sequential(&file);
+        Ok(Box::new(file))
+    }
+
+    /// Asks Linux for the first extent with the `FS_IOC_FIEMAP` ioctl when
+    /// built with the `fiemap` feature.
+    fn physical_offset(&self, path: &Path) -> Option<u64> {
+        first_extent_offset(path)
+    }
+
+    fn is_local(&self) -> bool {
+        true
+    }
+}
+
+/// Tells the kernel that `file` will be read sequentially and soon. The
+/// advice is best-effort, so its result is ignored.
+#[cfg(any(target_os = "linux", target_os = "android"))]
+fn advise_sequential(file: &std::fs::File) {
+    use std::os::fd::AsRawFd;
+
+    let fd = file.as_raw_fd();
+    // SAFETY: `fd` is an open descriptor owned by `file` for the whole call
+    unsafe {
+        libc::posix_fadvise(fd, 0, 0, libc::POSIX_FADV_SEQUENTIAL);
+        libc::posix_fadvise(fd, 0, 0, libc::POSIX_FADV_WILLNEED);
+    }
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "android")))]
+fn advise_sequential(_file: &std::fs::File) {}
+
+/// `struct fiemap` of `linux/fiemap.h`, with room for a single extent
+#[cfg(all(feature = "fiemap", target_os = "linux"))]
+#[repr(C)]
+#[derive(Default)]
+struct Fiemap {
+    start: u64,
+    length: u64,
+    flags: u32,
+    mapped_extents: u32,
+    extent_count: u32,
+    reserved: u32,
+    // struct fiemap_extent
+    logical: u64,
+    physical: u64,
+    extent_length: u64,
+    extent_reserved64: [u64; 2],
+    extent_flags: u32,
+    extent_reserved: [u32; 3],
+}
+
+/// `_IOWR('f', 11, struct fiemap)`
+#[cfg(all(feature = "fiemap", target_os = "linux"))]
+const FS_IOC_FIEMAP: u32 = 0xC020_660B;
+
+/// Returns the physical byte offset of the first extent of the file at
+/// `path`, or `None` for empty files and filesystems without FIEMAP.
+#[cfg(all(feature = "fiemap", target_os = "linux"))]
+fn first_extent_offset(path: &Path) -> Option<u64> {
+    use std::os::fd::AsRawFd;
+
+    let file = std::fs::File::open(path).ok()?;
+    let mut map = Fiemap {
+        length: u64::MAX,
+        extent_count: 1,
+        ..Default::default()
+    };
+    // SAFETY: `map` is a `struct fiemap` followed by room for the one extent
+    // requested in `extent_count`, and outlives the call
+    let result = unsafe {
+        libc::ioctl(
+            file.as_raw_fd(),
+            FS_IOC_FIEMAP as _,
+            &mut map as *mut Fiemap,
+        )
+    };
+    (result == 0 && map.mapped_extents > 0).then_some(map.physical)
+}
+
+#[cfg(not(all(feature = "fiemap", target_os = "linux")))]
+fn first_extent_offset(_path: &Path) -> Option<u64> {
+    None
+}
+
+/// How the root directories of a comparison are turned into absolute paths.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RootPaths {
+    /// Canonicalize each root, falling back to [`RootPaths::Normalize`] with a
+    /// warning if that fails
+    #[default]
+    Canonicalize,
+    /// Make each root absolute and remove `.` and `..` lexically, without
+    /// resolving symlinks
+    Normalize,
+}
+
+/// Resolves a comparison root to the absolute path used for prefix stripping.
+///
+/// # Errors
+///
+/// Returns an error if the root does not exist or cannot be made absolute.
+/// Other canonicalization failures are logged and fall back to the
+/// normalized absolute path.
+pub fn resolve_root(fs: &dyn FileSystem, dir: &Path, mode: RootPaths) -> io::Result<PathBuf> {
+    if mode == RootPaths::Canonicalize {
+        match fs.canonicalize(dir) {
+            Ok(path) => return Ok(path),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Err(e),
+            Err(e) => logger::warn(&format!(
+                "Could not canonicalize {}: {}; using its normalized absolute path instead",
+                dir.display(),
+                e
+            )),
+        }
+    }
+    let normalized = normalize_absolute(dir)?;
+    fs.metadata(&normalized)?;
+    Ok(normalized)
+}
+
+/// Makes a path absolute and removes `.` and `..` components lexically.
+///
+/// `..` at the root is dropped, matching how the operating system resolves it.
+pub fn normalize_absolute(path: &Path) -> io::Result<PathBuf> {
+    let absolute = std::path::absolute(path)?;
+    let mut normalized = PathBuf::new();
+    for component in absolute.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                if normalized.file_name().is_some() {
+                    normalized.pop();
+                }
+            }
+            other => normalized.push(other),
+        }
+    }
+    Ok(normalized)
+}
+
+/// Whether a directory's filesystem distinguishes names that differ only in case.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CaseSensitivity {
+    /// `a.txt` and `A.txt` are different files, as on most Linux filesystems
+    Sensitive,
+    /// `a.txt` and `A.txt` are the same file, as on NTFS and default APFS volumes
+    Insensitive,
+    /// The probe failed, for example because the directory is read-only
+    Unknown,
+}
+
+/// Start of the file name [`detect_case_sensitivity`] creates; lowercase so
+/// that its uppercase form is a different name
+const CASE_PROBE_PREFIX: &str = ".dir-compare-case-probe";
+
+/// Probes whether the filesystem holding `dir` is case-sensitive.
+///
+/// Creates an empty lowercase-named file in `dir`, checks whether its
+/// uppercase name resolves, and removes it again. Any failure, or an
+/// existing file under either name, yields [`CaseSensitivity::Unknown`];
+/// callers should then keep case-sensitive matching.
+pub fn detect_case_sensitivity(fs: &dyn FileSystem, dir: &Path) -> CaseSensitivity {
+    let name = format!("{}-{}", CASE_PROBE_PREFIX, std::process::id());
+    let probe = dir.join(&name);
+    let flipped = dir.join(name.to_uppercase());
+    if fs.exists(&probe) || fs.exists(&flipped) {
+        logger::debug(&format!(
+            "Case sensitivity probe {} already exists",
+            probe.display()
+        ));
+        return CaseSensitivity::Unknown;
+    }
+    if let Err(e) = fs.create_new(&probe) {
+        logger::debug(&format!(
+            "Could not probe case sensitivity of {}: {}",
+            dir.display(),
+            e
+        ));
+        return CaseSensitivity::Unknown;
+    }
+    let sensitivity = if fs.exists(&flipped) {
+        CaseSensitivity::Insensitive
+    } else {
+        CaseSensitivity::Sensitive
+    };
+    if let Err(e) = fs.remove_file(&probe) {
+        logger::warn(&format!(
+            "Could not remove case sensitivity probe {}: {}",
+            probe.display(),
+            e
+        ));
+    }
+    sensitivity
+}
+
+/// Picks case-insensitive name matching when both roots are on
+/// case-insensitive filesystems, logging the outcome.
+///
+/// An unknown root counts as case-sensitive.
+pub fn detect_case_insensitive_roots(fs: &dyn FileSystem, dir_a: &Path, dir_b: &Path) -> bool {
+    let a = detect_case_sensitivity(fs, dir_a);
+    let b = detect_case_sensitivity(fs, dir_b);
+    let insensitive = a == CaseSensitivity::Insensitive && b == CaseSensitivity::Insensitive;
+    if insensitive {
+        logger::info(
+            "Both directories are on case-insensitive filesystems; matching names case-insensitively",
+        );
+    } else {
+        logger::debug(&format!(
+            "Matching names case-sensitively ({}: {:?}, {}: {:?})",
+            dir_a.display(),
+            a,
+            dir_b.display(),
+            b
+        ));
+    }
+    insensitive
+}
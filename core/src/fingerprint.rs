@@ -0,0 +1,48 @@
+//! Cheap summaries of a directory tree, telling whether it changed since a
+//! comparison without comparing it again.
+//!
+//! A [`TreeFingerprint`] counts the entries below a root and records the
+//! latest modification time among them. Adding or removing an entry changes
+//! the count and the modification time of its directory; writing to a file
+//! changes its own. Contents are never read, so capturing a fingerprint
+//! costs one metadata call per entry.
+
+use std::io;
+use std::path::Path;
+use std::time::SystemTime;
+
+use walkdir::WalkDir;
+
+/// Entry count and latest modification time of a directory tree.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TreeFingerprint {
+    /// Number of entries below the root, not counting the root itself
+    pub entries: u64,
+    /// Latest modification time of the root or any entry below it; `None`
+    /// if the platform reports no modification times
+    pub latest_mtime: Option<SystemTime>,
+}
+
+impl TreeFingerprint {
+    /// Walks the tree at `root` without following symbolic links.
+    ///
+    /// Entries that cannot be read are left out, so they do not make the
+    /// tree look changed as long as they stay unreadable.
+    ///
+    /// # Errors
+    ///
+    /// Returns the error of reading the metadata of `root` itself, such as
+    /// [`io::ErrorKind::NotFound`] once it was deleted.
+    pub fn capture(root: &Path) -> io::Result<Self> {
+        let mut fingerprint = Self {
+            entries: 0,
+            latest_mtime: std::fs::symlink_metadata(root)?.modified().ok(),
+        };
+        for entry in WalkDir::new(root).min_depth(1).into_iter().flatten() {
+            fingerprint.entries += 1;
+            let mtime = entry.metadata().ok().and_then(|meta| meta.modified().ok());
+            fingerprint.latest_mtime = fingerprint.latest_mtime.max(mtime);
+        }
+        Ok(fingerprint)
+    }
+}
@@ -0,0 +1,166 @@
+//! Grouping of hard-linked entries within one side of a comparison.
+//!
+//! Trees such as maildir stores can hold thousands of hard links to the same
+//! inode. Listing each link separately inflates entry counts and byte totals,
+//! so entries sharing a `(dev, inode)` pair are collapsed into one group
+//! represented by the first path seen.
+
+use std::collections::HashMap;
+use std::path::Path;
+
+use crate::comparison::{ComparisonResult, Entry, EntryKind};
+
+/// Identifies a file's inode on a particular device.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct InodeKey {
+    /// Device ID of the filesystem holding the file
+    pub dev: u64,
+    /// Inode number on that device
+    pub ino: u64,
+}
+
+/// Entries on one side that share an inode.
+#[derive(Debug, Clone)]
+pub struct HardLinkGroup {
+    /// The first entry seen for this inode; reported normally
+    pub primary: Entry,
+    /// Further paths linking to the same inode, in input order
+    pub links: Vec<Entry>,
+}
+
+impl HardLinkGroup {
+    /// Returns the size of the shared content in bytes.
+    pub fn unique_bytes(&self) -> u64 {
+        self.primary.size.unwrap_or(0)
+    }
+
+    /// Returns the size counted once per path, as a naive listing would.
+    pub fn raw_bytes(&self) -> u64 {
+        self.unique_bytes() + self.links.iter().map(|e| e.size.unwrap_or(0)).sum::<u64>()
+    }
+}
+
+/// Byte totals for a list of entries with and without hard-link deduplication.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct ByteTotals {
+    /// Sum of the sizes of every path
+    pub raw: u64,
+    /// Sum of the sizes of every distinct inode
+    pub unique: u64,
+}
+
+/// Hard-link groups for the one-sided sections of a comparison result.
+#[derive(Debug, Clone, Default)]
+pub struct HardLinkReport {
+    /// Groups built from `ComparisonResult::a_only`
+    pub a_only: Vec<HardLinkGroup>,
+    /// Groups built from `ComparisonResult::b_only`
+    pub b_only: Vec<HardLinkGroup>,
+}
+
+impl HardLinkReport {
+    /// Collects inode information from disk and groups both one-sided sections.
+    pub fn collect(result: &ComparisonResult) -> Self {
+        Self::with_keys(result, |entry| inode_key(&entry.abs_path))
+    }
+
+    /// Groups both one-sided sections using `key_of` to identify inodes.
+    pub fn with_keys<F>(result: &ComparisonResult, mut key_of: F) -> Self
+    where
+        F: FnMut(&Entry) -> Option<InodeKey>,
+    {
+        Self {
+            a_only: group_hard_links(&result.a_only, &mut key_of),
+            b_only: group_hard_links(&result.b_only, &mut key_of),
+        }
+    }
+}
+
+/// Groups entries that are hard links to each other.
+///
+/// Only files with a key are grouped; directories and files whose key is
+/// `None` each form a group of their own. Groups are returned in the order
+/// of their first entry, so a list without hard links comes back unchanged.
+pub fn group_hard_links<F>(entries: &[Entry], mut key_of: F) -> Vec<HardLinkGroup>
+where
+    F: FnMut(&Entry) -> Option<InodeKey>,
+{
+    let mut groups: Vec<HardLinkGroup> = Vec::new();
+    let mut index: HashMap<InodeKey, usize> = HashMap::new();
+
+    for entry in entries {
+        let key = match entry.kind {
+            EntryKind::File => key_of(entry),
+            EntryKind::Directory | EntryKind::Symlink { .. } => None,
+        };
+        match key.and_then(|k| index.get(&k).copied()) {
+            Some(i) => groups[i].links.push(entry.clone()),
+            None => {
+                if let Some(k) = key {
+                    index.insert(k, groups.len());
+                }
+                groups.push(HardLinkGroup {
+                    primary: entry.clone(),
+                    links: Vec::new(),
+                });
+            }
+        }
+    }
+
+    groups
+}
+
+/// Computes raw and unique-inode byte totals over a list of groups.
+pub fn byte_totals(groups: &[HardLinkGroup]) -> ByteTotals {
+    groups
+        .iter()
+        .fold(ByteTotals::default(), |acc, group| ByteTotals {
+            raw: acc.raw + group.raw_bytes(),
+            unique: acc.unique + group.unique_bytes(),
+        })
+}
+
+/// Reads the device and inode numbers of a file.
+///
+/// Returns `None` on platforms without inode information or if the metadata
+/// cannot be read.
+#[cfg(unix)]
+pub fn inode_key(path: &Path) -> Option<InodeKey> {
+    use std::os::unix::fs::MetadataExt;
+
+    let metadata = std::fs::symlink_metadata(path).ok()?;
+    Some(InodeKey {
+        dev: metadata.dev(),
+        ino: metadata.ino(),
+    })
+}
+
+/// Reads the device and inode numbers of a file.
+///
+/// Returns `None` on platforms without inode information or if the metadata
+/// cannot be read.
+#[cfg(not(unix))]
+pub fn inode_key(_path: &Path) -> Option<InodeKey> {
+    None
+}
+
+/// Returns the device and inode numbers of a file with more than one link.
+///
+/// Always `None` on platforms without inode information.
+#[cfg(unix)]
+pub(crate) fn linked_inode(metadata: &std::fs::Metadata) -> Option<InodeKey> {
+    use std::os::unix::fs::MetadataExt;
+
+    (metadata.nlink() > 1).then(|| InodeKey {
+        dev: metadata.dev(),
+        ino: metadata.ino(),
+    })
+}
+
+/// Returns the device and inode numbers of a file with more than one link.
+///
+/// Always `None` on platforms without inode information.
+#[cfg(not(unix))]
+pub(crate) fn linked_inode(_metadata: &std::fs::Metadata) -> Option<InodeKey> {
+    None
+}
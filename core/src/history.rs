@@ -0,0 +1,387 @@
+//! Append-only CSV history of comparison runs.
+//!
+//! Each run appends one summary row, so drift between two trees can be
+//! charted over months without keeping full reports. Every row starts with
+//! the column-set version it was written with; later versions only ever add
+//! columns at the end, so older readers can parse newer files by ignoring
+//! trailing fields.
+
+use std::fs::{File, OpenOptions};
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::Path;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::comparison::{ComparisonResult, FlatComparisonResult};
+use crate::logger;
+use crate::output::{escape_csv, format_bytes};
+
+/// Version of the history column set written by this build.
+pub const HISTORY_VERSION: u32 = 2;
+
+/// History columns in file order. New columns must be appended at the end.
+///
+/// Version 2 added `modified`.
+pub const HISTORY_COLUMNS: [&str; 13] = [
+    "version",
+    "timestamp",
+    "mode",
+    "dir_a",
+    "dir_b",
+    "identical_pct",
+    "a_only",
+    "b_only",
+    "both",
+    "bytes_a",
+    "bytes_b",
+    "duration_ms",
+    "modified",
+];
+
+/// Number of columns in version 1 rows, the minimum a row must have.
+const HISTORY_V1_COLUMNS: usize = 12;
+
+/// One comparison run as stored in a history file.
+#[derive(Debug, Clone, PartialEq)]
+pub struct HistoryRecord {
+    /// Column-set version the row was written with
+    pub version: u32,
+    /// Start of the run in seconds since the Unix epoch
+    pub timestamp: u64,
+    /// Comparison mode, `hierarchy` or `flat`
+    pub mode: String,
+    /// First compared directory as given on the command line
+    pub dir_a: String,
+    /// Second compared directory as given on the command line
+    pub dir_b: String,
+    /// Share of entries on both sides that have a match, from 0 to 100
+    pub identical_pct: f64,
+    /// Entries found only in A
+    pub a_only: usize,
+    /// Entries found only in B
+    pub b_only: usize,
+    /// Matched entries (pairs in hierarchy mode, A-side files in flat mode)
+    pub both: usize,
+    /// Total size of the files in A
+    pub bytes_a: u64,
+    /// Total size of the files in B
+    pub bytes_b: u64,
+    /// Wall-clock duration of the comparison in milliseconds
+    pub duration_ms: u64,
+    /// Same-path entries that differ (always 0 in flat mode and version 1 rows)
+    pub modified: usize,
+}
+
+/// Change of a run's figures relative to the previous run.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct HistoryDelta {
+    /// Change in percentage points
+    pub identical_pct: f64,
+    /// Change in A-only entries
+    pub a_only: i64,
+    /// Change in B-only entries
+    pub b_only: i64,
+    /// Change in matched entries
+    pub both: i64,
+    /// Change in bytes in A
+    pub bytes_a: i64,
+    /// Change in bytes in B
+    pub bytes_b: i64,
+    /// Change in duration in milliseconds
+    pub duration_ms: i64,
+    /// Change in modified entries
+    pub modified: i64,
+}
+
+impl HistoryRecord {
+    /// Summarizes a hierarchy-mode comparison.
+    pub fn from_comparison(
+        result: &ComparisonResult,
+        dir_a: &Path,
+        dir_b: &Path,
+        timestamp: SystemTime,
+        duration: Duration,
+    ) -> Self {
+        let counts = result.counts();
+        let bytes_a = counts.a_only.bytes_a + counts.both.bytes_a + counts.modified.bytes_a;
+        let bytes_b = counts.b_only.bytes_b + counts.both.bytes_b + counts.modified.bytes_b;
+
+        let matched = 2 * counts.both.entries;
+        let total =
+            counts.a_only.entries + counts.b_only.entries + matched + 2 * counts.modified.entries;
+        Self {
+            version: HISTORY_VERSION,
+            timestamp: unix_seconds(timestamp),
+            mode: "hierarchy".to_string(),
+            dir_a: dir_a.to_string_lossy().into_owned(),
+            dir_b: dir_b.to_string_lossy().into_owned(),
+            identical_pct: percentage(matched, total),
+            a_only: counts.a_only.entries,
+            b_only: counts.b_only.entries,
+            both: counts.both.entries,
+            bytes_a,
+            bytes_b,
+            duration_ms: duration.as_millis() as u64,
+            modified: counts.modified.entries,
+        }
+    }
+
+    /// Summarizes a flat-mode comparison.
+    ///
+    /// Files are matched when their content group has files on both sides.
+    pub fn from_flat(
+        result: &FlatComparisonResult,
+        dir_a: &Path,
+        dir_b: &Path,
+        timestamp: SystemTime,
+        duration: Duration,
+    ) -> Self {
+        let mut record = Self {
+            version: HISTORY_VERSION,
+            timestamp: unix_seconds(timestamp),
+            mode: "flat".to_string(),
+            dir_a: dir_a.to_string_lossy().into_owned(),
+            dir_b: dir_b.to_string_lossy().into_owned(),
+            identical_pct: 0.0,
+            a_only: 0,
+            b_only: 0,
+            both: 0,
+            bytes_a: 0,
+            bytes_b: 0,
+            duration_ms: duration.as_millis() as u64,
+            modified: 0,
+        };
+        let mut matched_b = 0;
+        for group in &result.groups {
+            let (a, b) = (group.files_in_a.len(), group.files_in_b.len());
+            record.bytes_a += group.size * a as u64;
+            record.bytes_b += group.size * b as u64;
+            if a > 0 && b > 0 {
+                record.both += a;
+                matched_b += b;
+            } else {
+                record.a_only += a;
+                record.b_only += b;
+            }
+        }
+        record.identical_pct = percentage(
+            record.both + matched_b,
+            result.total_files_a + result.total_files_b,
+        );
+        record
+    }
+
+    /// Renders the record as a CSV line without the trailing newline.
+    pub fn to_csv_row(&self) -> String {
+        [
+            self.version.to_string(),
+            self.timestamp.to_string(),
+            escape_csv(&self.mode),
+            escape_csv(&self.dir_a),
+            escape_csv(&self.dir_b),
+            format!("{:.2}", self.identical_pct),
+            self.a_only.to_string(),
+            self.b_only.to_string(),
+            self.both.to_string(),
+            self.bytes_a.to_string(),
+            self.bytes_b.to_string(),
+            self.duration_ms.to_string(),
+            self.modified.to_string(),
+        ]
+        .join(",")
+    }
+
+    /// Parses a CSV line written by [`HistoryRecord::to_csv_row`].
+    ///
+    /// Columns added after version 1 default to zero when missing, and fields
+    /// beyond the known columns, written by newer versions, are ignored.
+    /// Returns a description of the problem if the line is malformed.
+    pub fn parse_row(line: &str) -> Result<Self, String> {
+        let mut fields = split_csv_row(line)?;
+        if fields.len() < HISTORY_V1_COLUMNS {
+            return Err(format!(
+                "expected at least {} columns, found {}",
+                HISTORY_V1_COLUMNS,
+                fields.len()
+            ));
+        }
+        fields.resize(fields.len().max(HISTORY_COLUMNS.len()), "0".to_string());
+        fn num<T: std::str::FromStr>(fields: &[String], i: usize) -> Result<T, String> {
+            fields[i]
+                .parse()
+                .map_err(|_| format!("invalid {} '{}'", HISTORY_COLUMNS[i], fields[i]))
+        }
+        Ok(Self {
+            version: num(&fields, 0)?,
+            timestamp: num(&fields, 1)?,
+            mode: fields[2].clone(),
+            dir_a: fields[3].clone(),
+            dir_b: fields[4].clone(),
+            identical_pct: num(&fields, 5)?,
+            a_only: num(&fields, 6)?,
+            b_only: num(&fields, 7)?,
+            both: num(&fields, 8)?,
+            bytes_a: num(&fields, 9)?,
+            bytes_b: num(&fields, 10)?,
+            duration_ms: num(&fields, 11)?,
+            modified: num(&fields, 12)?,
+        })
+    }
+
+    /// Returns how this run differs from `previous`.
+    pub fn delta_from(&self, previous: &HistoryRecord) -> HistoryDelta {
+        let diff = |now: u64, before: u64| now as i64 - before as i64;
+        HistoryDelta {
+            identical_pct: self.identical_pct - previous.identical_pct,
+            a_only: diff(self.a_only as u64, previous.a_only as u64),
+            b_only: diff(self.b_only as u64, previous.b_only as u64),
+            both: diff(self.both as u64, previous.both as u64),
+            bytes_a: diff(self.bytes_a, previous.bytes_a),
+            bytes_b: diff(self.bytes_b, previous.bytes_b),
+            duration_ms: diff(self.duration_ms, previous.duration_ms),
+            modified: diff(self.modified as u64, previous.modified as u64),
+        }
+    }
+}
+
+/// Appends a record to a history file, creating it with a header if needed.
+///
+/// The file is locked exclusively while writing, so concurrent runs
+/// appending to the same file do not interleave or write the header twice.
+pub fn append_history(path: &Path, record: &HistoryRecord) -> io::Result<()> {
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    file.lock()?;
+
+    let mut line = String::new();
+    if file.metadata()?.len() == 0 {
+        line.push_str(&HISTORY_COLUMNS.join(","));
+        line.push('\n');
+    }
+    line.push_str(&record.to_csv_row());
+    line.push('\n');
+    file.write_all(line.as_bytes())?;
+    file.flush()
+}
+
+/// Reads every record from a history file.
+///
+/// Header lines and blank lines are skipped. Malformed rows are logged as
+/// warnings and skipped so one bad line does not hide the rest of the history.
+pub fn read_history(path: &Path) -> io::Result<Vec<HistoryRecord>> {
+    let file = File::open(path)?;
+    let mut records = Vec::new();
+    for (index, line) in BufReader::new(file).lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() || line.starts_with("version,") {
+            continue;
+        }
+        match HistoryRecord::parse_row(&line) {
+            Ok(record) => records.push(record),
+            Err(e) => logger::warn(&format!(
+                "Skipping malformed history row {} in {}: {}",
+                index + 1,
+                path.display(),
+                e
+            )),
+        }
+    }
+    Ok(records)
+}
+
+/// Formats the last `limit` records as a table with deltas to the run before.
+///
+/// # Output Format
+///
+/// ```text
+/// Timestamp    Mode       Identical  A-only  B-only Modified    Both  Bytes A      Duration
+/// 1760000000   hierarchy     98.50%       3       1        2     200  1.2 MiB        120 ms
+/// 1760086400   hierarchy     97.00%       6       1        4     197  1.3 MiB        131 ms
+///   change                   -1.50       +3      +0       +2      -3  +102400 B      +11 ms
+/// ```
+pub fn format_history(records: &[HistoryRecord], limit: usize) -> String {
+    let mut output = String::new();
+    output.push_str(&format!(
+        "{:<12} {:<10} {:>9} {:>7} {:>7} {:>8} {:>7}  {:<11} {:>9}\n",
+        "Timestamp",
+        "Mode",
+        "Identical",
+        "A-only",
+        "B-only",
+        "Modified",
+        "Both",
+        "Bytes A",
+        "Duration"
+    ));
+
+    let start = records.len().saturating_sub(limit);
+    for (i, record) in records.iter().enumerate().skip(start) {
+        output.push_str(&format!(
+            "{:<12} {:<10} {:>8.2}% {:>7} {:>7} {:>8} {:>7}  {:<11} {:>6} ms\n",
+            record.timestamp,
+            record.mode,
+            record.identical_pct,
+            record.a_only,
+            record.b_only,
+            record.modified,
+            record.both,
+            format_bytes(record.bytes_a),
+            record.duration_ms
+        ));
+        if let Some(previous) = i.checked_sub(1).map(|p| &records[p]) {
+            let delta = record.delta_from(previous);
+            output.push_str(&format!(
+                "{:<12} {:<10} {:>+9.2} {:>+7} {:>+7} {:>+8} {:>+7}  {:<11} {:>+6} ms\n",
+                "  change",
+                "",
+                delta.identical_pct,
+                delta.a_only,
+                delta.b_only,
+                delta.modified,
+                delta.both,
+                format!("{:+} B", delta.bytes_a),
+                delta.duration_ms
+            ));
+        }
+    }
+
+    output
+}
+
+fn unix_seconds(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+fn percentage(part: usize, total: usize) -> f64 {
+    if total == 0 {
+        100.0
+    } else {
+        part as f64 * 100.0 / total as f64
+    }
+}
+
+/// Splits one CSV line into fields, undoing RFC 4180 quoting.
+fn split_csv_row(line: &str) -> Result<Vec<String>, String> {
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut chars = line.chars().peekable();
+    let mut quoted = false;
+
+    while let Some(c) = chars.next() {
+        match (quoted, c) {
+            (true, '"') if chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            }
+            (true, '"') => quoted = false,
+            (false, '"') if field.is_empty() => quoted = true,
+            (false, ',') => fields.push(std::mem::take(&mut field)),
+            (_, c) => field.push(c),
+        }
+    }
+    if quoted {
+        return Err("unterminated quoted field".to_string());
+    }
+    fields.push(field);
+    Ok(fields)
+}
@@ -0,0 +1,165 @@
+//! Ignore files given to a comparison, such as `--ignore .dcignore`.
+//!
+//! Each line holds one gitignore-style rule, matched against paths relative
+//! to the compared root:
+//!
+//! - Blank lines and lines starting with `#` are skipped; `\#` and `\!`
+//!   start a pattern with a literal `#` or `!`.
+//! - `!pattern` re-includes what earlier rules ignored.
+//! - A trailing `/` matches directories only, and everything below them.
+//! - A leading `/` anchors the pattern at the root, as does a `/` inside it;
+//!   other patterns match at any depth.
+//!
+//! The last rule matching an entry or one of its parent directories decides
+//! whether the entry is ignored. Unlike git, a negation can therefore
+//! re-include a file inside an ignored directory:
+//!
+//! ```text
+//! build/
+//! !build/manifest.json
+//! ```
+//!
+//! ignores everything below `build/` except `build/manifest.json`. An
+//! ignored directory is not descended into unless a negation follows the
+//! rule that ignores it.
+
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
+
+use crate::comparison::{ComparisonError, Entry};
+
+/// The rules of an ignore file.
+///
+/// # Examples
+///
+/// ```
+/// use dir_compare_core::ignore_file::IgnoreFile;
+/// use std::path::Path;
+///
+/// let ignore = IgnoreFile::parse("build/\n!build/manifest.json\n").unwrap();
+/// assert!(ignore.is_ignored(Path::new("build/app.o"), false));
+/// assert!(!ignore.is_ignored(Path::new("build/manifest.json"), false));
+/// ```
+#[derive(Debug, Clone)]
+pub struct IgnoreFile {
+    /// All rules at once, which tells quickly that none applies
+    matcher: Gitignore,
+    /// Each rule on its own, in the order of the file
+    rules: Vec<Rule>,
+    /// Index of the last negation in `rules`
+    last_negation: Option<usize>,
+}
+
+#[derive(Debug, Clone)]
+struct Rule {
+    matcher: Gitignore,
+    negated: bool,
+}
+
+impl IgnoreFile {
+    /// Reads and parses the ignore file at `path`.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ComparisonError::IgnoreFile`] if the file cannot be read or
+    /// holds an invalid pattern.
+    pub fn load(path: &Path) -> Result<Self, ComparisonError> {
+        let error = |message: String| ComparisonError::IgnoreFile {
+            path: path.to_path_buf(),
+            message,
+        };
+        let contents = std::fs::read_to_string(path).map_err(|e| error(e.to_string()))?;
+        Self::parse(&contents).map_err(|e| error(e.to_string()))
+    }
+
+    /// Parses the rules in `contents`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`std::io::ErrorKind::InvalidInput`] error naming the line
+    /// of the first pattern that is not a valid glob.
+    pub fn parse(contents: &str) -> std::io::Result<Self> {
+        // Paths are matched relative to the compared root, so the matcher
+        // root must not strip anything
+        let mut all = GitignoreBuilder::new(".");
+        let mut rules = Vec::new();
+        for (index, line) in contents.lines().enumerate() {
+            let invalid = |e: ignore::Error| {
+                std::io::Error::new(
+                    std::io::ErrorKind::InvalidInput,
+                    format!("line {}: {}", index + 1, e),
+                )
+            };
+            let mut builder = GitignoreBuilder::new(".");
+            builder.add_line(None, line).map_err(invalid)?;
+            let matcher = builder.build().map_err(invalid)?;
+            if matcher.is_empty() {
+                continue;
+            }
+            all.add_line(None, line).map_err(invalid)?;
+            rules.push(Rule {
+                matcher,
+                negated: line.starts_with('!'),
+            });
+        }
+        let matcher = all
+            .build()
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidInput, e.to_string()))?;
+        let last_negation = rules.iter().rposition(|rule| rule.negated);
+        Ok(Self {
+            matcher,
+            rules,
+            last_negation,
+        })
+    }
+
+    /// Index of the last rule matching `path` or one of its parents
+    fn deciding_rule(&self, path: &Path, is_dir: bool) -> Option<usize> {
+        if self
+            .matcher
+            .matched_path_or_any_parents(path, is_dir)
+            .is_none()
+        {
+            return None;
+        }
+        self.rules.iter().rposition(|rule| {
+            !rule
+                .matcher
+                .matched_path_or_any_parents(path, is_dir)
+                .is_none()
+        })
+    }
+
+    /// Returns whether the entry at `path`, relative to the compared root,
+    /// is ignored.
+    pub fn is_ignored(&self, path: &Path, is_dir: bool) -> bool {
+        self.deciding_rule(path, is_dir)
+            .is_some_and(|index| !self.rules[index].negated)
+    }
+
+    /// Returns whether the directory at `path` is ignored with everything
+    /// below it, so that it need not be descended into.
+    pub fn prunes(&self, path: &Path) -> bool {
+        self.deciding_rule(path, true).is_some_and(|index| {
+            !self.rules[index].negated && self.last_negation.is_none_or(|last| last < index)
+        })
+    }
+}
+
+/// Drops the `ignored_dirs` that were only descended into because a negation
+/// may re-include entries below them, keeping those that lead to a listed
+/// entry.
+pub(crate) fn retain_leading_dirs(entries: &mut Vec<Entry>, ignored_dirs: &HashSet<PathBuf>) {
+    if ignored_dirs.is_empty() {
+        return;
+    }
+    let parents: HashSet<&Path> = entries
+        .iter()
+        .filter(|entry| !ignored_dirs.contains(&entry.path))
+        .flat_map(|entry| entry.path.ancestors().skip(1))
+        .collect();
+    let parents: HashSet<PathBuf> = parents.into_iter().map(Path::to_path_buf).collect();
+    entries.retain(|entry| !ignored_dirs.contains(&entry.path) || parents.contains(&entry.path));
+}
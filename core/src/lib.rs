@@ -1,15 +1,78 @@
+pub mod block_diff;
+pub mod builder;
+pub mod chunk_dedupe;
 pub mod comparison;
+#[cfg(feature = "config")]
+pub mod config;
+pub mod data_profile;
+pub mod doctor;
+pub mod external;
+pub mod filesystem;
+pub mod fingerprint;
+pub mod hardlinks;
+pub mod history;
+pub mod ignore_file;
 pub mod logger;
+pub mod manifest;
+pub mod ops;
 pub mod output;
+pub mod report_labels;
+pub mod result_filter;
+pub mod sanitize;
+#[cfg(feature = "schema")]
+pub mod schema;
+pub mod strategy_defaults;
+pub mod strategy_options;
+pub mod sync;
+#[cfg(feature = "testing")]
+pub mod testing;
+pub mod text_diff;
 
+pub use block_diff::{BlockDiff, DiffRegion, diff_blocks, locate_file_differences};
+pub use builder::Comparison;
+pub use chunk_dedupe::{ChunkDedupeOptions, ChunkDedupeReport, Chunker, SharedFile};
 pub use comparison::{
-    ComparisonResult, ComparisonStrategy, ComparisonStrategyType, Entry, EntryKind,
-    FastHashStrategy, FilenameOnlyStrategy, FilenameSizeStrategy, FlatComparisonOptions,
-    FlatComparisonResult, FlatContentGroup, SampledHashStrategy, compare_directories,
-    compare_directories_flat,
+    Blake3Strategy, CachedHashStrategy, CancellationToken, CategorizedEntry, CategoryCounts,
+    ComparisonError, ComparisonEstimate, ComparisonOptions, ComparisonPlan, ComparisonResult,
+    ComparisonStats, ComparisonStrategy, ComparisonStrategyType, DEFAULT_MTIME_TOLERANCE, Entry,
+    EntryFilter, EntryKind, FastHashStrategy, FileWarning, FilenameOnlyStrategy,
+    FilenameSizeStrategy, FlatComparisonOptions, FlatComparisonResult, FlatContentGroup,
+    FlatHardLink, HashAlgorithm, HashCache, IdenticalSubtree, MatchVerdict, MetadataStrategy,
+    MismatchReason, Progress, ProgressPhase, ResultCounts, ResultDetail, ResultRow,
+    SAMPLING_THRESHOLD, SampledHashStrategy, Side, StatMode, TraversalOptions, WarningCause,
+    compare_directories, compare_directories_flat, compare_directories_with_options,
+    compare_directories_with_progress, compare_directories_with_roots, extent_order,
+    plan_comparison,
 };
+pub use data_profile::{DataProfile, DataProfiler, FormatClass, sampling_advice};
+pub use doctor::{CheckResult, CheckStatus, RootScan, verdict};
+pub use external::{ExternalCommandStrategy, ExternalRule, ExternalRules};
+pub use filesystem::{
+    CaseSensitivity, FileSystem, RealFileSystem, RootPaths, SharedFileSystem,
+    detect_case_insensitive_roots, detect_case_sensitivity,
+};
+pub use fingerprint::TreeFingerprint;
+pub use hardlinks::{HardLinkGroup, HardLinkReport, InodeKey, group_hard_links};
+pub use history::{HistoryDelta, HistoryRecord, append_history, read_history};
 pub use logger::{
-    debug, error, info, init, set_destination, set_format, set_level, warn, Logger, LoggerConfig,
-    LogLevel, OutputDestination,
+    LogBuffer, LogEntry, LogFormat, LogLevel, LogSink, Logger, LoggerConfig, OutputDestination,
+    SinkId, TimestampFormat, add_sink, debug, debug_with, error, error_with, flush_summaries,
+    format_json_record, format_timestamp, info, info_with, init, remove_sink, set_dedup_window,
+    set_destination, set_format, set_level, set_log_format, set_timestamp_format, take_captured,
+    warn, warn_keyed, warn_keyed_with, warn_with,
+};
+pub use manifest::{ManifestResult, verify_manifest, write_manifest};
+pub use ops::{ActionOutcome, ActionResult, ActionResults, CopyOptions, copy_file};
+pub use output::{
+    ColorTextFormatter, FlatFormatter, FlatLabels, Formatter, Labels, ReportContext, Section,
+    SectionConfig, TextStyles,
+};
+pub use report_labels::REPORT_LANGUAGES;
+pub use result_filter::{KindFilter, ResultFilter};
+pub use sanitize::SanitizeMode;
+pub use strategy_defaults::{DEFAULT_VERIFY, StrategySettings};
+pub use strategy_options::{
+    OptionKind, OptionValue, OptionValues, StrategyOption, StrategyOptionError,
 };
-pub use output::Formatter;
+pub use sync::{SyncAction, SyncDirection, SyncOptions, SyncReport, apply_sync, plan_sync};
+pub use text_diff::{DiffLine, FileDiff, LineChange, TextDiff, diff_files, diff_text};
@@ -1,12 +1,35 @@
 //! Logger module for structured logging with level-based filtering.
 //!
 //! This module provides a global logger instance with configurable log levels,
-//! output formatting, and destination configuration.
+//! output formatting, and destination configuration. Additional [`LogSink`]s,
+//! such as the GUI's [`LogBuffer`], receive every record alongside the
+//! configured destination.
+//!
+//! Records are written as text lines following [`LoggerConfig::format`], or
+//! as JSON lines with [`LogFormat::Json`]. The `*_with` functions attach
+//! fields such as the path a warning is about; JSON lines carry them as
+//! members, while text lines show the message alone, so messages should
+//! read on their own.
+//!
+//! Warnings that can repeat once per file are logged with [`warn_keyed`].
+//! With [`LoggerConfig::dedup_window`] set, only the first warnings of each
+//! key are shown, and [`flush_summaries`] counts the rest.
+//!
+//! With [`OutputDestination::Capture`] the formatted lines are kept in memory
+//! instead of written, so tests and embedding applications can read them
+//! with [`take_captured`].
 
+use std::collections::{BTreeMap, VecDeque};
+use std::fmt;
 use std::io::{self, Write};
-use std::sync::{Mutex, OnceLock};
+use std::sync::{Arc, Mutex, OnceLock};
 use std::time::SystemTime;
 
+use crate::output::{escape_json, group_digits};
+
+/// Target of records logged without one
+pub const DEFAULT_TARGET: &str = "dir_compare";
+
 /// Log levels for filtering output.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default)]
 pub enum LogLevel {
@@ -32,7 +55,19 @@ impl LogLevel {
         }
     }
 
+    /// SGR parameters the level is shown with when colored: dim debug,
+    /// green info, yellow warnings and bold red errors.
+    fn ansi_style(&self) -> &'static str {
+        match self {
+            LogLevel::Debug => "2",
+            LogLevel::Info => "32",
+            LogLevel::Warn => "33",
+            LogLevel::Error => "1;31",
+        }
+    }
+
     /// Parses a log level from a string (case-insensitive).
+    #[allow(clippy::should_implement_trait)]
     pub fn from_str(s: &str) -> Option<Self> {
         match s.to_uppercase().as_str() {
             "DEBUG" => Some(LogLevel::Debug),
@@ -52,6 +87,113 @@ pub enum OutputDestination {
     /// Output to standard error (stderr)
     #[default]
     Stderr,
+    /// Keep the formatted lines in memory until [`Logger::take_captured`]
+    Capture,
+}
+
+/// How the logger writes the time of a record.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TimestampFormat {
+    /// Seconds since the Unix epoch with microseconds, e.g. `1708099200.123456`
+    Unix,
+    /// UTC date and time with milliseconds, e.g. `2024-02-16T14:00:00.123Z`
+    #[default]
+    Rfc3339,
+    /// No timestamp
+    None,
+}
+
+impl std::str::FromStr for TimestampFormat {
+    type Err = String;
+
+    /// Parses `rfc3339`, `unix` or `none`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "rfc3339" => Ok(TimestampFormat::Rfc3339),
+            "unix" => Ok(TimestampFormat::Unix),
+            "none" => Ok(TimestampFormat::None),
+            other => Err(format!(
+                "unknown timestamp format '{}' (expected rfc3339, unix or none)",
+                other
+            )),
+        }
+    }
+}
+
+/// Formats `time` as [`TimestampFormat`] `format`; empty for
+/// [`TimestampFormat::None`]. Times before the epoch are written as the
+/// epoch.
+pub fn format_timestamp(format: TimestampFormat, time: SystemTime) -> String {
+    let since_epoch = time
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap_or_default();
+    match format {
+        TimestampFormat::Unix => format!(
+            "{}.{:06}",
+            since_epoch.as_secs(),
+            since_epoch.subsec_micros()
+        ),
+        TimestampFormat::Rfc3339 => {
+            let secs = since_epoch.as_secs();
+            let (year, month, day) = civil_from_days((secs / 86_400) as i64);
+            let secs_of_day = secs % 86_400;
+            format!(
+                "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}.{:03}Z",
+                year,
+                month,
+                day,
+                secs_of_day / 3_600,
+                secs_of_day / 60 % 60,
+                secs_of_day % 60,
+                since_epoch.subsec_millis()
+            )
+        }
+        TimestampFormat::None => String::new(),
+    }
+}
+
+/// Converts days since the Unix epoch to a (year, month, day) civil date in
+/// the proleptic Gregorian calendar.
+pub fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719_468;
+    let era = if z >= 0 { z } else { z - 146_096 } / 146_097;
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+    (year, month, day)
+}
+
+/// How the logger writes each record to its destination.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LogFormat {
+    /// A text line following [`LoggerConfig::format`]
+    #[default]
+    Text,
+    /// A JSON object per line, such as
+    /// `{"level":"INFO","ts":"2024-02-16T14:00:00.123Z","msg":"...","path":"..."}`;
+    /// `ts` follows [`LoggerConfig::timestamp_format`], as a number for Unix
+    /// timestamps and left out with none, and fields follow `msg`
+    Json,
+}
+
+impl std::str::FromStr for LogFormat {
+    type Err = String;
+
+    /// Parses `text` or `json`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "text" => Ok(LogFormat::Text),
+            "json" => Ok(LogFormat::Json),
+            other => Err(format!(
+                "unknown log format '{}' (expected text or json)",
+                other
+            )),
+        }
+    }
 }
 
 /// Configuration for the logger.
@@ -59,10 +201,21 @@ pub enum OutputDestination {
 pub struct LoggerConfig {
     /// Minimum log level to output
     pub level: LogLevel,
-    /// Output destination (stdout or stderr)
+    /// Output destination (stdout, stderr or in-memory capture)
     pub destination: OutputDestination,
-    /// Custom format string (supports {level}, {timestamp}, {message})
+    /// Custom format string (supports {level}, {timestamp}, {timestamp_unix},
+    /// {message}); only used by [`LogFormat::Text`]
     pub format: Option<String>,
+    /// Whether records are written as text or JSON lines
+    pub log_format: LogFormat,
+    /// How `{timestamp}` and the default format write the time of a record
+    pub timestamp_format: TimestampFormat,
+    /// Number of warnings logged per key by [`warn_keyed`] before the rest
+    /// are only counted; `None` logs all of them
+    pub dedup_window: Option<usize>,
+    /// Whether the default text format colors the level with ANSI escapes;
+    /// custom formats and JSON lines stay plain
+    pub color: bool,
 }
 
 impl Default for LoggerConfig {
@@ -71,13 +224,48 @@ impl Default for LoggerConfig {
             level: LogLevel::Info,
             destination: OutputDestination::Stderr,
             format: None,
+            log_format: LogFormat::Text,
+            timestamp_format: TimestampFormat::Rfc3339,
+            dedup_window: None,
+            color: false,
         }
     }
 }
 
+/// Receives log records in addition to the configured destination.
+///
+/// Sinks are called from whichever thread logs, so they must be thread-safe.
+/// A sink must not log itself.
+pub trait LogSink: Send + Sync {
+    /// Minimum level passed to this sink, independent of the logger's level
+    /// for the destination. Defaults to every record.
+    fn level(&self) -> LogLevel {
+        LogLevel::Debug
+    }
+
+    /// Handles one record.
+    fn log(&self, level: LogLevel, timestamp: SystemTime, target: &str, message: &str);
+}
+
+/// Identifies a sink registered with [`Logger::add_sink`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct SinkId(u64);
+
+/// Registered sinks and the id of the next one.
+#[derive(Default)]
+struct Sinks {
+    next_id: u64,
+    sinks: Vec<(SinkId, Arc<dyn LogSink>)>,
+}
+
 /// Global logger instance for structured logging.
 pub struct Logger {
     config: Mutex<LoggerConfig>,
+    sinks: Mutex<Sinks>,
+    /// Lines written with [`OutputDestination::Capture`] and not yet taken
+    captured: Mutex<Vec<String>>,
+    /// Number of keyed warnings seen per key since the last summary
+    repeats: Mutex<BTreeMap<String, usize>>,
 }
 
 impl Logger {
@@ -85,6 +273,9 @@ impl Logger {
     fn new(config: LoggerConfig) -> Self {
         Self {
             config: Mutex::new(config),
+            sinks: Mutex::new(Sinks::default()),
+            captured: Mutex::new(Vec::new()),
+            repeats: Mutex::new(BTreeMap::new()),
         }
     }
 
@@ -109,7 +300,9 @@ impl Logger {
     /// # Example
     ///
     /// ```no_run
-    /// use dir_compare_core::logger::{LoggerConfig, LogLevel, OutputDestination};
+    /// use dir_compare_core::logger::{
+    ///     LogFormat, LoggerConfig, LogLevel, OutputDestination, TimestampFormat,
+    /// };
     ///
     /// fn main() {
     ///     // Initialize logger FIRST
@@ -117,6 +310,10 @@ impl Logger {
     ///         level: LogLevel::Info,
     ///         destination: OutputDestination::Stderr,
     ///         format: None,
+    ///         log_format: LogFormat::Text,
+    ///         timestamp_format: TimestampFormat::Rfc3339,
+    ///         dedup_window: None,
+    ///         color: false,
     ///     });
     ///
     ///     // Now use logging functions
@@ -151,13 +348,81 @@ impl Logger {
         }
     }
 
+    /// Sets whether records are written as text or JSON lines.
+    pub fn set_log_format(&self, log_format: LogFormat) {
+        if let Ok(mut config) = self.config.lock() {
+            config.log_format = log_format;
+        }
+    }
+
+    /// Sets how timestamps are written.
+    pub fn set_timestamp_format(&self, timestamp_format: TimestampFormat) {
+        if let Ok(mut config) = self.config.lock() {
+            config.timestamp_format = timestamp_format;
+        }
+    }
+
+    /// Sets how many warnings of each key are logged; see
+    /// [`LoggerConfig::dedup_window`].
+    pub fn set_dedup_window(&self, dedup_window: Option<usize>) {
+        if let Ok(mut config) = self.config.lock() {
+            config.dedup_window = dedup_window;
+        }
+    }
+
     /// Returns the current log level.
     pub fn level(&self) -> LogLevel {
         self.config.lock().map(|c| c.level).unwrap_or_default()
     }
 
+    /// Registers a sink that receives every record from now on, until it is
+    /// removed with [`remove_sink`](Self::remove_sink).
+    pub fn add_sink(&self, sink: Box<dyn LogSink>) -> SinkId {
+        let mut sinks = self.sinks.lock().unwrap_or_else(|e| e.into_inner());
+        let id = SinkId(sinks.next_id);
+        sinks.next_id += 1;
+        sinks.sinks.push((id, Arc::from(sink)));
+        id
+    }
+
+    /// Unregisters a sink; returns false if it was not registered.
+    pub fn remove_sink(&self, id: SinkId) -> bool {
+        let mut sinks = self.sinks.lock().unwrap_or_else(|e| e.into_inner());
+        let count = sinks.sinks.len();
+        sinks.sinks.retain(|(sink_id, _)| *sink_id != id);
+        sinks.sinks.len() != count
+    }
+
+    /// Removes and returns the lines written with
+    /// [`OutputDestination::Capture`] so far, formatted as they would have
+    /// been written, oldest first.
+    pub fn take_captured(&self) -> Vec<String> {
+        let mut captured = self.captured.lock().unwrap_or_else(|e| e.into_inner());
+        std::mem::take(&mut *captured)
+    }
+
     /// Logs a message at the specified level.
     pub fn log(&self, level: LogLevel, message: &str) {
+        self.log_with_target(level, DEFAULT_TARGET, message);
+    }
+
+    /// Logs a message at the specified level on behalf of `target`, which is
+    /// passed to the sinks but not shown by the destination.
+    pub fn log_with_target(&self, level: LogLevel, target: &str, message: &str) {
+        self.write_record(level, target, message, &[]);
+    }
+
+    /// Logs a message at the specified level with `fields`, such as
+    /// `[("path", "/data/a.txt")]`; see [`LogFormat`] for where they appear.
+    pub fn log_with_fields(&self, level: LogLevel, message: &str, fields: &[(&str, &str)]) {
+        self.write_record(level, DEFAULT_TARGET, message, fields);
+    }
+
+    /// Passes a record to the sinks, which receive the message without the
+    /// fields, and writes it to the destination.
+    fn write_record(&self, level: LogLevel, target: &str, message: &str, fields: &[(&str, &str)]) {
+        self.notify_sinks(level, target, message);
+
         let config = match self.config.lock() {
             Ok(cfg) => cfg.clone(),
             Err(_) => return,
@@ -169,7 +434,7 @@ impl Logger {
         }
 
         // Format the message
-        let formatted = self.format_message(&config, level, message);
+        let formatted = self.format_message(&config, level, message, fields);
 
         // Write to the appropriate destination
         let result = match config.destination {
@@ -181,39 +446,75 @@ impl Logger {
                 let mut stderr = io::stderr().lock();
                 writeln!(stderr, "{}", formatted)
             }
+            OutputDestination::Capture => {
+                let mut captured = self.captured.lock().unwrap_or_else(|e| e.into_inner());
+                captured.push(formatted);
+                Ok(())
+            }
         };
 
         // Ignore write errors (logging should not crash the program)
         let _ = result;
     }
 
-    /// Formats a log message according to the configuration.
-    fn format_message(&self, config: &LoggerConfig, level: LogLevel, message: &str) -> String {
-        let timestamp = self.get_timestamp();
+    /// Passes a record to the sinks that accept its level. The sinks are
+    /// called after the registry is unlocked, so they may add or remove sinks.
+    fn notify_sinks(&self, level: LogLevel, target: &str, message: &str) {
+        let sinks: Vec<Arc<dyn LogSink>> = match self.sinks.lock() {
+            Ok(sinks) if !sinks.sinks.is_empty() => {
+                sinks.sinks.iter().map(|(_, sink)| sink.clone()).collect()
+            }
+            _ => return,
+        };
+        let timestamp = SystemTime::now();
+        for sink in sinks {
+            if level >= sink.level() {
+                sink.log(level, timestamp, target, message);
+            }
+        }
+    }
 
-        if let Some(ref format) = config.format {
+    /// Formats a log message according to the configuration.
+    fn format_message(
+        &self,
+        config: &LoggerConfig,
+        level: LogLevel,
+        message: &str,
+        fields: &[(&str, &str)],
+    ) -> String {
+        let now = SystemTime::now();
+        let timestamp = self.get_timestamp(config.timestamp_format, now);
+
+        if config.log_format == LogFormat::Json {
+            format_json_record(config.timestamp_format, level, now, message, fields)
+        } else if let Some(ref format) = config.format {
             format
                 .replace("{level}", level.as_str())
+                .replace(
+                    "{timestamp_unix}",
+                    &self.get_timestamp(TimestampFormat::Unix, now),
+                )
                 .replace("{timestamp}", &timestamp)
                 .replace("{message}", message)
         } else {
-            // Default format: [LEVEL] timestamp message
-            format!("[{}] {} {}", level.as_str(), timestamp, message)
+            let level = if config.color {
+                format!("\x1b[{}m{}\x1b[0m", level.ansi_style(), level.as_str())
+            } else {
+                level.as_str().to_string()
+            };
+            if timestamp.is_empty() {
+                format!("[{}] {}", level, message)
+            } else {
+                // Default format: [LEVEL] timestamp message
+                format!("[{}] {} {}", level, timestamp, message)
+            }
         }
     }
 
-    /// Gets the current timestamp as a Unix timestamp with microsecond precision.
-    ///
-    /// Format: `seconds.microseconds` (e.g., "1708099200.123456")
-    fn get_timestamp(&self) -> String {
-        match SystemTime::now().duration_since(SystemTime::UNIX_EPOCH) {
-            Ok(duration) => {
-                let secs = duration.as_secs();
-                let micros = duration.subsec_micros();
-                format!("{}.{:06}", secs, micros)
-            }
-            Err(_) => "0.000000".to_string(),
-        }
+    /// Formats `now` as `format`, e.g. `2024-02-16T14:00:00.123Z` for RFC 3339
+    /// or `1708099200.123456` for Unix; see [`format_timestamp`].
+    fn get_timestamp(&self, format: TimestampFormat, now: SystemTime) -> String {
+        format_timestamp(format, now)
     }
 
     /// Logs a debug message.
@@ -221,6 +522,26 @@ impl Logger {
         self.log(LogLevel::Debug, message);
     }
 
+    /// Logs a debug message with fields.
+    pub fn debug_with(&self, message: &str, fields: &[(&str, &str)]) {
+        self.log_with_fields(LogLevel::Debug, message, fields);
+    }
+
+    /// Logs an info message with fields.
+    pub fn info_with(&self, message: &str, fields: &[(&str, &str)]) {
+        self.log_with_fields(LogLevel::Info, message, fields);
+    }
+
+    /// Logs a warning message with fields.
+    pub fn warn_with(&self, message: &str, fields: &[(&str, &str)]) {
+        self.log_with_fields(LogLevel::Warn, message, fields);
+    }
+
+    /// Logs an error message with fields.
+    pub fn error_with(&self, message: &str, fields: &[(&str, &str)]) {
+        self.log_with_fields(LogLevel::Error, message, fields);
+    }
+
     /// Logs an info message.
     pub fn info(&self, message: &str) {
         self.log(LogLevel::Info, message);
@@ -235,6 +556,95 @@ impl Logger {
     pub fn error(&self, message: &str) {
         self.log(LogLevel::Error, message);
     }
+
+    /// Logs a warning that may repeat with other details, such as one per
+    /// unreadable file, under `key`.
+    ///
+    /// Once [`LoggerConfig::dedup_window`] warnings of a key were logged,
+    /// further ones are only counted until [`flush_summaries`](Self::flush_summaries).
+    pub fn warn_keyed(&self, key: &str, message: &str) {
+        self.warn_keyed_with(key, message, &[]);
+    }
+
+    /// Like [`warn_keyed`](Self::warn_keyed), attaching `fields` to the
+    /// warnings that are logged.
+    pub fn warn_keyed_with(&self, key: &str, message: &str, fields: &[(&str, &str)]) {
+        let window = self
+            .config
+            .lock()
+            .map(|c| c.dedup_window)
+            .unwrap_or_default();
+        if let Some(window) = window {
+            let mut repeats = self.repeats.lock().unwrap_or_else(|e| e.into_inner());
+            let seen = repeats.entry(key.to_string()).or_default();
+            *seen += 1;
+            if *seen > window {
+                return;
+            }
+        }
+        self.warn_with(message, fields);
+    }
+
+    /// Logs one warning per key whose warnings were suppressed, with the
+    /// number left out, and starts counting afresh.
+    ///
+    /// Call it at the end of a run; calling it again logs nothing until new
+    /// warnings are suppressed.
+    pub fn flush_summaries(&self) {
+        let repeats = std::mem::take(&mut *self.repeats.lock().unwrap_or_else(|e| e.into_inner()));
+        let Some(window) = self
+            .config
+            .lock()
+            .map(|c| c.dedup_window)
+            .unwrap_or_default()
+        else {
+            return;
+        };
+        for (key, seen) in repeats {
+            if seen > window {
+                self.warn(&format!(
+                    "previous message repeated {} more times (key={})",
+                    group_digits(seen - window),
+                    key
+                ));
+            }
+        }
+    }
+}
+
+/// Formats a record as a [`LogFormat::Json`] line.
+///
+/// A field named like a member the record always has, such as `msg`, is
+/// written after it with the same name; JSON readers keep the last one.
+pub fn format_json_record(
+    timestamp_format: TimestampFormat,
+    level: LogLevel,
+    time: SystemTime,
+    message: &str,
+    fields: &[(&str, &str)],
+) -> String {
+    let mut line = format!("{{\"level\":\"{}\"", level.as_str());
+    match timestamp_format {
+        TimestampFormat::Rfc3339 => line.push_str(&format!(
+            ",\"ts\":\"{}\"",
+            format_timestamp(TimestampFormat::Rfc3339, time)
+        )),
+        TimestampFormat::Unix => line.push_str(&format!(
+            ",\"ts\":{}",
+            format_timestamp(TimestampFormat::Unix, time)
+        )),
+        TimestampFormat::None => {}
+    }
+    line.push_str(&format!(",\"msg\":\"{}\"", escape_json(message)));
+    for (name, value) in fields {
+        line.push_str(&format!(
+            ",\"{}\":\"{}\"",
+            escape_json(name),
+            escape_json(value)
+        ));
+    }
+    line.push('}');
+    line
 }
 
 // ============================================================================
@@ -261,6 +671,37 @@ pub fn set_format(format: Option<String>) {
     Logger::global().set_format(format);
 }
 
+/// Sets whether the global logger writes text or JSON lines.
+pub fn set_log_format(log_format: LogFormat) {
+    Logger::global().set_log_format(log_format);
+}
+
+/// Sets how the global logger writes timestamps.
+pub fn set_timestamp_format(timestamp_format: TimestampFormat) {
+    Logger::global().set_timestamp_format(timestamp_format);
+}
+
+/// Sets how many warnings of each key the global logger shows.
+pub fn set_dedup_window(dedup_window: Option<usize>) {
+    Logger::global().set_dedup_window(dedup_window);
+}
+
+/// Registers a sink with the global logger.
+pub fn add_sink(sink: Box<dyn LogSink>) -> SinkId {
+    Logger::global().add_sink(sink)
+}
+
+/// Unregisters a sink from the global logger.
+pub fn remove_sink(id: SinkId) -> bool {
+    Logger::global().remove_sink(id)
+}
+
+/// Removes and returns the lines captured by the global logger; see
+/// [`Logger::take_captured`].
+pub fn take_captured() -> Vec<String> {
+    Logger::global().take_captured()
+}
+
 /// Logs a debug message using the global logger.
 pub fn debug(message: &str) {
     Logger::global().debug(message);
@@ -281,6 +722,146 @@ pub fn error(message: &str) {
     Logger::global().error(message);
 }
 
+/// Logs a debug message with fields using the global logger.
+pub fn debug_with(message: &str, fields: &[(&str, &str)]) {
+    Logger::global().debug_with(message, fields);
+}
+
+/// Logs an info message with fields using the global logger.
+pub fn info_with(message: &str, fields: &[(&str, &str)]) {
+    Logger::global().info_with(message, fields);
+}
+
+/// Logs a warning message with fields using the global logger.
+pub fn warn_with(message: &str, fields: &[(&str, &str)]) {
+    Logger::global().warn_with(message, fields);
+}
+
+/// Logs an error message with fields using the global logger.
+pub fn error_with(message: &str, fields: &[(&str, &str)]) {
+    Logger::global().error_with(message, fields);
+}
+
+/// Logs a repeatable warning under `key` using the global logger; see
+/// [`Logger::warn_keyed`].
+pub fn warn_keyed(key: &str, message: &str) {
+    Logger::global().warn_keyed(key, message);
+}
+
+/// Logs a repeatable warning with fields under `key` using the global
+/// logger; see [`Logger::warn_keyed_with`].
+pub fn warn_keyed_with(key: &str, message: &str, fields: &[(&str, &str)]) {
+    Logger::global().warn_keyed_with(key, message, fields);
+}
+
+/// Logs the counts of suppressed warnings of the global logger; see
+/// [`Logger::flush_summaries`].
+pub fn flush_summaries() {
+    Logger::global().flush_summaries();
+}
+
+/// A record kept by a [`LogBuffer`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LogEntry {
+    pub level: LogLevel,
+    pub timestamp: SystemTime,
+    pub target: String,
+    pub message: String,
+}
+
+impl fmt::Display for LogEntry {
+    /// Formats the entry like the default destination format, with an
+    /// RFC 3339 timestamp and the target before the message.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "[{}] {} {}: {}",
+            self.level.as_str(),
+            format_timestamp(TimestampFormat::Rfc3339, self.timestamp),
+            self.target,
+            self.message
+        )
+    }
+}
+
+/// A sink keeping the most recent records in memory, for in-app log viewers.
+///
+/// Clones share the same records, so one clone can be registered with
+/// [`add_sink`] while another is read. Once `capacity` records are held,
+/// each new record evicts the oldest.
+#[derive(Clone)]
+pub struct LogBuffer {
+    capacity: usize,
+    entries: Arc<Mutex<VecDeque<LogEntry>>>,
+}
+
+impl LogBuffer {
+    /// Creates an empty buffer holding at most `capacity` records.
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            entries: Arc::new(Mutex::new(VecDeque::with_capacity(capacity))),
+        }
+    }
+
+    /// Maximum number of records kept.
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Adds a record, evicting the oldest one if the buffer is full.
+    pub fn push(&self, entry: LogEntry) {
+        if self.capacity == 0 {
+            return;
+        }
+        let mut entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        if entries.len() == self.capacity {
+            entries.pop_front();
+        }
+        entries.push_back(entry);
+    }
+
+    /// Number of records held.
+    pub fn len(&self) -> usize {
+        self.entries.lock().map(|e| e.len()).unwrap_or_default()
+    }
+
+    /// Whether no records are held.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Removes all records.
+    pub fn clear(&self) {
+        if let Ok(mut entries) = self.entries.lock() {
+            entries.clear();
+        }
+    }
+
+    /// Returns the records at `min_level` or above, oldest first.
+    pub fn entries(&self, min_level: LogLevel) -> Vec<LogEntry> {
+        match self.entries.lock() {
+            Ok(entries) => entries
+                .iter()
+                .filter(|entry| entry.level >= min_level)
+                .cloned()
+                .collect(),
+            Err(_) => Vec::new(),
+        }
+    }
+}
+
+impl LogSink for LogBuffer {
+    fn log(&self, level: LogLevel, timestamp: SystemTime, target: &str, message: &str) {
+        self.push(LogEntry {
+            level,
+            timestamp,
+            target: target.to_string(),
+            message: message.to_string(),
+        });
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
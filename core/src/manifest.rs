@@ -0,0 +1,335 @@
+//! Checksum manifests in `sha256sum` format.
+//!
+//! [`write_manifest`] records the hash of every file below a directory, one
+//! `<hex>  <relative path>` line per file, so the tree can be checked later
+//! with [`verify_manifest`] or with `sha256sum -c` from inside it. Only
+//! regular files are listed; directories, symlinks and empty directories are
+//! not recorded.
+//!
+//! Names are written as GNU coreutils writes them: a name containing a
+//! backslash, newline or carriage return is escaped as `\\`, `\n` and `\r`,
+//! and its line starts with a backslash. Paths always use `/` as the
+//! separator.
+
+use std::collections::BTreeMap;
+use std::io::{self, BufRead, Write};
+use std::path::{Component, Path, PathBuf};
+
+use crate::comparison::{
+    EntryKind, FileWarning, HashAlgorithm, WarningCause, full_hash_in, traverse_directory,
+};
+use crate::filesystem::{RealFileSystem, RootPaths, resolve_root};
+use crate::output::{escape_json, path_bytes};
+
+/// How the files below a directory compare with a manifest.
+///
+/// Paths are relative to the directory.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ManifestResult {
+    /// Files whose hash matches the manifest
+    pub matched: Vec<PathBuf>,
+    /// Files whose hash differs from the manifest, or that could not be read
+    pub modified: Vec<PathBuf>,
+    /// Files listed in the manifest that are not in the directory
+    pub missing: Vec<PathBuf>,
+    /// Files in the directory that the manifest does not list
+    pub extra: Vec<PathBuf>,
+    /// Files that could not be read; they are also listed as modified
+    pub warnings: Vec<FileWarning>,
+}
+
+impl ManifestResult {
+    /// Whether any file was modified, missing or extra.
+    pub fn has_differences(&self) -> bool {
+        !(self.modified.is_empty() && self.missing.is_empty() && self.extra.is_empty())
+    }
+
+    /// Returns `N matched, M modified, K missing, L extra`.
+    pub fn summary(&self) -> String {
+        format!(
+            "{} matched, {} modified, {} missing, {} extra",
+            self.matched.len(),
+            self.modified.len(),
+            self.missing.len(),
+            self.extra.len()
+        )
+    }
+
+    /// Renders one `MODIFIED`, `MISSING` or `EXTRA` line per differing file,
+    /// followed by the [`summary`](Self::summary).
+    pub fn to_text(&self) -> String {
+        let mut text = String::new();
+        for (label, paths) in [
+            ("MODIFIED", &self.modified),
+            ("MISSING", &self.missing),
+            ("EXTRA", &self.extra),
+        ] {
+            for path in paths {
+                // Escaped names start with a backslash, as `sha256sum -c` prints them
+                let (escaped, name) = escape_name(path);
+                let marker = if escaped { "\\" } else { "" };
+                let name = String::from_utf8_lossy(&name);
+                text.push_str(&format!("{}{} {}\n", marker, label, name));
+            }
+        }
+        text.push_str(&self.summary());
+        text.push('\n');
+        text
+    }
+
+    /// Renders the result as a JSON object with one array of paths per
+    /// category and the unreadable files under `warnings`.
+    pub fn to_json(&self) -> String {
+        let paths = |paths: &[PathBuf]| {
+            let quoted: Vec<String> = paths
+                .iter()
+                .map(|path| format!("\"{}\"", escape_json(&path.to_string_lossy())))
+                .collect();
+            format!("[{}]", quoted.join(", "))
+        };
+        let warnings: Vec<String> = self
+            .warnings
+            .iter()
+            .map(|warning| {
+                format!(
+                    "{{\"path\": \"{}\", \"message\": \"{}\"}}",
+                    escape_json(&warning.path.to_string_lossy()),
+                    escape_json(&warning.message)
+                )
+            })
+            .collect();
+        format!(
+            "{{\"matched\": {}, \"modified\": {}, \"missing\": {}, \"extra\": {}, \"warnings\": [{}]}}\n",
+            paths(&self.matched),
+            paths(&self.modified),
+            paths(&self.missing),
+            paths(&self.extra),
+            warnings.join(", ")
+        )
+    }
+}
+
+/// Writes the manifest of every file below `dir` to `writer`, sorted by
+/// path, and returns the number of files listed.
+///
+/// With [`HashAlgorithm::Sha256`] the output is what `sha256sum` prints for
+/// the same files; with [`HashAlgorithm::Blake3`], what `b3sum` prints.
+///
+/// # Examples
+///
+/// ```no_run
+/// use dir_compare_core::comparison::HashAlgorithm;
+/// use dir_compare_core::manifest::write_manifest;
+///
+/// let mut file = std::fs::File::create("SHA256SUMS")?;
+/// write_manifest(std::path::Path::new("release"), HashAlgorithm::Sha256, &mut file)?;
+/// # Ok::<(), std::io::Error>(())
+/// ```
+pub fn write_manifest(
+    dir: &Path,
+    algorithm: HashAlgorithm,
+    writer: &mut dyn Write,
+) -> io::Result<usize> {
+    let files = list_files(dir)?;
+    for (path, abs_path) in &files {
+        let hash = full_hash_in(&RealFileSystem, abs_path, algorithm, true)?;
+        writer.write_all(&manifest_line(&hash, path))?;
+    }
+    Ok(files.len())
+}
+
+/// Compares the files below `dir` with the manifest read from `reader`,
+/// hashing them with `algorithm`.
+///
+/// Accepts the lines [`write_manifest`] and `sha256sum` write, in text
+/// (`<hex>  <path>`) or binary (`<hex> *<path>`) mode; paths may start with
+/// `./`. Fails with [`io::ErrorKind::InvalidData`] on the first line that is
+/// not in this format, or whose path is absolute or leaves the directory.
+pub fn verify_manifest(
+    dir: &Path,
+    algorithm: HashAlgorithm,
+    reader: impl BufRead,
+) -> io::Result<ManifestResult> {
+    let expected = read_manifest(reader, algorithm)?;
+    let mut files = list_files(dir)?;
+    let mut result = ManifestResult::default();
+    for (path, hash) in expected {
+        let Some(abs_path) = files.remove(&path) else {
+            result.missing.push(path);
+            continue;
+        };
+        match full_hash_in(&RealFileSystem, &abs_path, algorithm, true) {
+            Ok(actual) if actual == hash => result.matched.push(path),
+            Ok(_) => result.modified.push(path),
+            Err(e) => {
+                result
+                    .warnings
+                    .push(FileWarning::new(&abs_path, WarningCause::Hash, &e));
+                result.modified.push(path);
+            }
+        }
+    }
+    result.extra = files.into_keys().collect();
+    Ok(result)
+}
+
+/// Lists the regular files below `dir` by relative path, with their
+/// absolute paths.
+fn list_files(dir: &Path) -> io::Result<BTreeMap<PathBuf, PathBuf>> {
+    let entries = traverse_directory(dir, None).map_err(|e| io::Error::new(e.io_kind(), e))?;
+    let root = resolve_root(&RealFileSystem, dir, RootPaths::default())?;
+    Ok(entries
+        .into_iter()
+        .filter(|entry| entry.kind == EntryKind::File)
+        .filter_map(|entry| {
+            let relative = entry.path.strip_prefix(&root).ok()?.to_path_buf();
+            Some((relative, entry.abs_path))
+        })
+        .collect())
+}
+
+/// Formats the manifest line of `path`.
+fn manifest_line(hash: &str, path: &Path) -> Vec<u8> {
+    let (escaped, name) = escape_name(path);
+    let mut line = Vec::with_capacity(hash.len() + name.len() + 4);
+    if escaped {
+        line.push(b'\\');
+    }
+    line.extend_from_slice(hash.as_bytes());
+    line.extend_from_slice(b"  ");
+    line.extend_from_slice(&name);
+    line.push(b'\n');
+    line
+}
+
+/// Returns the `/`-separated name of `path`, escaped as GNU coreutils
+/// escapes it if it contains a backslash, newline or carriage return, and
+/// whether it was.
+fn escape_name(path: &Path) -> (bool, Vec<u8>) {
+    let mut name = Vec::new();
+    for (i, component) in path.components().enumerate() {
+        if i > 0 {
+            name.push(b'/');
+        }
+        name.extend_from_slice(path_bytes(Path::new(component.as_os_str())));
+    }
+    if !name.iter().any(|b| matches!(b, b'\\' | b'\n' | b'\r')) {
+        return (false, name);
+    }
+    let mut escaped = Vec::with_capacity(name.len() + 2);
+    for b in name {
+        match b {
+            b'\\' => escaped.extend_from_slice(b"\\\\"),
+            b'\n' => escaped.extend_from_slice(b"\\n"),
+            b'\r' => escaped.extend_from_slice(b"\\r"),
+            b => escaped.push(b),
+        }
+    }
+    (true, escaped)
+}
+
+/// Reads the hashes of a manifest by relative path.
+fn read_manifest(
+    mut reader: impl BufRead,
+    algorithm: HashAlgorithm,
+) -> io::Result<BTreeMap<PathBuf, String>> {
+    let hash_len = match algorithm {
+        HashAlgorithm::Sha256 | HashAlgorithm::Blake3 => 64,
+        HashAlgorithm::FxHash => 16,
+    };
+    let mut hashes = BTreeMap::new();
+    let mut line = Vec::new();
+    let mut number = 0;
+    loop {
+        line.clear();
+        if reader.read_until(b'\n', &mut line)? == 0 {
+            break;
+        }
+        number += 1;
+        if line.last() == Some(&b'\n') {
+            line.pop();
+        }
+        if line.is_empty() {
+            continue;
+        }
+        let (hash, path) = parse_line(&line, hash_len).map_err(|reason| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("manifest line {}: {}", number, reason),
+            )
+        })?;
+        hashes.insert(path, hash);
+    }
+    Ok(hashes)
+}
+
+/// Splits a manifest line into its lowercase hash and relative path.
+fn parse_line(line: &[u8], hash_len: usize) -> Result<(String, PathBuf), String> {
+    let (escaped, line) = match line.strip_prefix(b"\\") {
+        Some(rest) => (true, rest),
+        None => (false, line),
+    };
+    let malformed = || "expected '<hash>  <path>'".to_string();
+    if line.len() < hash_len + 2 || !matches!(line[hash_len + 1], b' ' | b'*') {
+        return Err(malformed());
+    }
+    let (hash, name) = (&line[..hash_len], &line[hash_len + 2..]);
+    if line[hash_len] != b' ' || !hash.iter().all(u8::is_ascii_hexdigit) {
+        return Err(malformed());
+    }
+    let name = if escaped {
+        unescape_name(name)?
+    } else {
+        name.to_vec()
+    };
+    let hash = String::from_utf8_lossy(hash).to_lowercase();
+    Ok((hash, relative_path(name)?))
+}
+
+/// Reverses the escaping of [`escape_name`].
+fn unescape_name(name: &[u8]) -> Result<Vec<u8>, String> {
+    let mut unescaped = Vec::with_capacity(name.len());
+    let mut bytes = name.iter();
+    while let Some(&b) = bytes.next() {
+        if b != b'\\' {
+            unescaped.push(b);
+            continue;
+        }
+        match bytes.next() {
+            Some(b'\\') => unescaped.push(b'\\'),
+            Some(b'n') => unescaped.push(b'\n'),
+            Some(b'r') => unescaped.push(b'\r'),
+            _ => return Err("invalid escape sequence in path".to_string()),
+        }
+    }
+    Ok(unescaped)
+}
+
+/// Converts a `/`-separated manifest name to a relative path, rejecting
+/// absolute paths and paths leaving the directory.
+fn relative_path(name: Vec<u8>) -> Result<PathBuf, String> {
+    #[cfg(unix)]
+    let name = {
+        use std::os::unix::ffi::OsStringExt;
+        PathBuf::from(std::ffi::OsString::from_vec(name))
+    };
+    #[cfg(not(unix))]
+    let name = PathBuf::from(String::from_utf8(name).map_err(|_| "path is not valid UTF-8")?);
+    let mut path = PathBuf::new();
+    for component in name.components() {
+        match component {
+            Component::Normal(part) => path.push(part),
+            Component::CurDir => {}
+            _ => {
+                return Err(format!(
+                    "path {} is not inside the directory",
+                    name.display()
+                ));
+            }
+        }
+    }
+    if path.as_os_str().is_empty() {
+        return Err("empty path".to_string());
+    }
+    Ok(path)
+}
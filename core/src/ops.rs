@@ -0,0 +1,198 @@
+//! File operations that act on comparison results, such as copying an
+//! A-only file into B.
+//!
+//! The operating system reports a copy as successful once the bytes were
+//! handed to the destination device, which does not catch corruption on
+//! flaky transports such as USB bridges. Copies are therefore re-read and
+//! hashed by default, and a copy whose hash differs from the source is
+//! deleted and retried once.
+
+use std::fmt::Write as _;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::comparison::hash_reader;
+use crate::filesystem::FileSystem;
+
+/// Number of copies attempted before a file is reported as failed.
+const COPY_ATTEMPTS: u32 = 2;
+
+/// Options for [`copy_file`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CopyOptions {
+    /// Re-hash the destination after copying and compare it with the source hash
+    pub verify_after_copy: bool,
+}
+
+impl Default for CopyOptions {
+    fn default() -> Self {
+        Self {
+            verify_after_copy: true,
+        }
+    }
+}
+
+/// How a file operation ended.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ActionOutcome {
+    /// The file was copied, and verified if verification was enabled
+    Copied,
+    /// The operation failed; no destination copy is left behind on
+    /// verification failures
+    Failed(String),
+}
+
+/// The result of one file operation.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ActionResult {
+    /// The file that was copied
+    pub source: PathBuf,
+    /// Where it was copied to
+    pub destination: PathBuf,
+    /// How the operation ended
+    pub outcome: ActionOutcome,
+    /// Number of copies whose verification failed, including a failed retry
+    pub verification_failures: u32,
+}
+
+impl ActionResult {
+    /// Returns true if the operation failed.
+    pub fn is_failed(&self) -> bool {
+        matches!(self.outcome, ActionOutcome::Failed(_))
+    }
+}
+
+/// Results of a batch of file operations, in the order they ran.
+#[derive(Debug, Clone, Default)]
+pub struct ActionResults {
+    results: Vec<ActionResult>,
+}
+
+impl ActionResults {
+    /// Creates an empty result list.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Records the result of one operation.
+    pub fn push(&mut self, result: ActionResult) {
+        self.results.push(result);
+    }
+
+    /// Returns every recorded result.
+    pub fn results(&self) -> &[ActionResult] {
+        &self.results
+    }
+
+    /// Returns the results of operations that failed.
+    pub fn failed(&self) -> impl Iterator<Item = &ActionResult> {
+        self.results.iter().filter(|r| r.is_failed())
+    }
+
+    /// Summarizes the batch, listing each failed file with its reason and
+    /// each file that only verified after a retry.
+    pub fn summary(&self) -> String {
+        let failed = self.failed().count();
+        let mut summary = format!(
+            "{} copied, {} failed\n",
+            self.results.len() - failed,
+            failed
+        );
+        for result in &self.results {
+            match &result.outcome {
+                ActionOutcome::Failed(reason) => {
+                    let _ = writeln!(
+                        summary,
+                        "  FAILED {}: {}",
+                        result.destination.display(),
+                        reason
+                    );
+                }
+                ActionOutcome::Copied if result.verification_failures > 0 => {
+                    let _ = writeln!(
+                        summary,
+                        "  RETRIED {}: verification failed {} time(s) before a good copy",
+                        result.destination.display(),
+                        result.verification_failures
+                    );
+                }
+                ActionOutcome::Copied => {}
+            }
+        }
+        summary
+    }
+}
+
+/// Hashes a file with the algorithm used for copy verification.
+///
+/// This is the algorithm of [`crate::FastHashStrategy`], so hashes computed
+/// during a comparison can be passed to [`copy_file`] as the source hash.
+pub fn hash_file(fs: &dyn FileSystem, path: &Path) -> io::Result<String> {
+    hash_reader(fs.open(path)?)
+}
+
+/// Copies `source` to `destination` and records the result in `results`.
+///
+/// Missing parent directories of `destination` are created. With
+/// [`CopyOptions::verify_after_copy`], the destination is hashed after the
+/// copy and compared with `source_hash`, or with a fresh hash of `source` if
+/// no cached hash is given. A mismatching copy is deleted and the copy is
+/// retried once; if the retry also fails verification the file is recorded
+/// as failed.
+pub fn copy_file(
+    fs: &dyn FileSystem,
+    source: &Path,
+    destination: &Path,
+    source_hash: Option<&str>,
+    options: &CopyOptions,
+    results: &mut ActionResults,
+) {
+    let mut result = ActionResult {
+        source: source.to_path_buf(),
+        destination: destination.to_path_buf(),
+        outcome: ActionOutcome::Copied,
+        verification_failures: 0,
+    };
+    if let Err(e) = copy_verified(fs, source, destination, source_hash, options, &mut result) {
+        result.outcome = ActionOutcome::Failed(e.to_string());
+    }
+    results.push(result);
+}
+
+fn copy_verified(
+    fs: &dyn FileSystem,
+    source: &Path,
+    destination: &Path,
+    source_hash: Option<&str>,
+    options: &CopyOptions,
+    result: &mut ActionResult,
+) -> io::Result<()> {
+    if let Some(parent) = destination.parent().filter(|p| !p.as_os_str().is_empty()) {
+        fs.create_dir_all(parent)?;
+    }
+    if !options.verify_after_copy {
+        fs.copy(source, destination)?;
+        return Ok(());
+    }
+
+    let expected = match source_hash {
+        Some(hash) => hash.to_string(),
+        None => hash_file(fs, source)?,
+    };
+    for _ in 0..COPY_ATTEMPTS {
+        fs.copy(source, destination)?;
+        let actual = hash_file(fs, destination)?;
+        if actual == expected {
+            return Ok(());
+        }
+        result.verification_failures += 1;
+        fs.remove_file(destination)?;
+    }
+    Err(io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!(
+            "verification failed after {} attempts: destination hash differs from source",
+            COPY_ATTEMPTS
+        ),
+    ))
+}
@@ -1,4 +1,15 @@
-use crate::comparison::{ComparisonResult, EntryKind, FlatComparisonResult};
+use crate::block_diff::BlockDiff;
+use crate::chunk_dedupe::{ChunkDedupeReport, SharedFile};
+use crate::comparison::{
+    ComparisonResult, ComparisonStats, Entry, EntryKind, FlatComparisonResult, FlatContentGroup,
+    FlatHardLink, IdenticalSubtree, MismatchReason, find_identical_subtree, find_mismatch_reason,
+};
+use crate::hardlinks::{HardLinkGroup, HardLinkReport, byte_totals};
+use crate::logger::{TimestampFormat, format_timestamp};
+use std::collections::{HashMap, HashSet};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
 
 /// Trait for formatting directory comparison results.
 ///
@@ -25,12 +36,565 @@ pub trait Formatter {
     ///
     /// A formatted string representation of the comparison result
     fn format(&self, result: &ComparisonResult) -> String;
+
+    /// Writes the formatted result to `w`.
+    ///
+    /// The default writes the output of [`format`](Self::format);
+    /// formatters that can produce their output piece by piece override it so
+    /// large reports stream to a file without being built in memory first.
+    fn format_to(&self, result: &ComparisonResult, w: &mut dyn io::Write) -> io::Result<()> {
+        w.write_all(self.format(result).as_bytes())
+    }
+
+    /// Writes the formatted result to `w` with `labels` in place of the
+    /// English headings.
+    ///
+    /// The Text, Markdown and HTML formatters override it; machine-readable
+    /// formats ignore `labels` and write what [`format_to`](Self::format_to)
+    /// writes.
+    fn format_to_with_labels(
+        &self,
+        result: &ComparisonResult,
+        labels: &Labels,
+        w: &mut dyn io::Write,
+    ) -> io::Result<()> {
+        let _ = labels;
+        self.format_to(result, w)
+    }
+}
+
+/// Trait for formatting flat mode comparison results.
+///
+/// The flat counterpart of [`Formatter`], implemented by the `Flat*`
+/// formatters so front-ends can pick one at runtime.
+///
+/// # Examples
+///
+/// ```
+/// use dir_compare_core::output::{FlatFormatter, FlatTextFormatter};
+///
+/// let formatter: Box<dyn FlatFormatter> = Box::new(FlatTextFormatter);
+/// // Assuming `result` is a valid FlatComparisonResult
+/// // let output = formatter.format(&result);
+/// ```
+pub trait FlatFormatter {
+    /// Formats the flat comparison result into a string representation.
+    fn format(&self, result: &FlatComparisonResult) -> String;
+
+    /// Writes the formatted result to `w`; see [`Formatter::format_to`].
+    fn format_to(&self, result: &FlatComparisonResult, w: &mut dyn io::Write) -> io::Result<()> {
+        w.write_all(self.format(result).as_bytes())
+    }
+
+    /// Writes the formatted result to `w` with `labels` in place of the
+    /// English headings; see [`Formatter::format_to_with_labels`].
+    fn format_to_with_labels(
+        &self,
+        result: &FlatComparisonResult,
+        labels: &Labels,
+        w: &mut dyn io::Write,
+    ) -> io::Result<()> {
+        let _ = labels;
+        self.format_to(result, w)
+    }
+}
+
+/// Section headings and words used by the Text, Markdown and HTML
+/// formatters.
+///
+/// The default is English and produces the same output as
+/// [`Formatter::format`]; [`Labels::for_language`] returns the built-in
+/// translations, and other front-ends (such as the GUI's string catalog) can
+/// supply their own. JSON, CSV and porcelain output never use labels.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Labels {
+    /// Language code of the labels, written as the `lang` of HTML reports
+    pub lang: String,
+    /// Report title (Markdown)
+    pub report_title: String,
+    /// Summary section heading (Markdown)
+    pub summary: String,
+    /// "Category" column header of the summary table (Markdown)
+    pub category: String,
+    /// "Count" column header of the summary table (Markdown)
+    pub count: String,
+    /// Heading for entries only in directory A
+    pub a_only: String,
+    /// Heading for entries only in directory B
+    pub b_only: String,
+    /// Heading for entries in both directories
+    pub both: String,
+    /// Heading for same-path entries that differ
+    pub modified: String,
+    /// Heading for files found at another path on the other side
+    pub renamed: String,
+    /// Placeholder for an empty A-only/B-only section (Markdown)
+    pub no_entries: String,
+    /// Placeholder for an empty Both section (Markdown)
+    pub no_matching_entries: String,
+    /// Noun used after a count of exactly one
+    pub entry_one: String,
+    /// Noun used after any other count
+    pub entry_other: String,
+    /// Line ending a section whose entries were not all kept; `{count}` is
+    /// replaced by the number of omitted entries
+    pub more: String,
+    /// Note after a directory holding its own `.git` (`--vcs-aware`)
+    pub nested_repository: String,
+    /// Note after a directory whose whole subtree matched, with one file
+    /// below it; `{count}` is replaced by the number of files
+    pub identical_one: String,
+    /// Note after a directory whose whole subtree matched, with any other
+    /// number of files below it; `{count}` is replaced by the number
+    pub identical_other: String,
+    /// Reason after a modified pair of a file and a directory or symbolic
+    /// link, see [`MismatchReason`]
+    pub reason_kind: String,
+    /// Reason after a modified pair of files of different sizes; `{a}` and
+    /// `{b}` are replaced by the sizes in bytes
+    pub reason_size: String,
+    /// Reason after a modified pair of files of the same size
+    pub reason_content: String,
+    /// Reason after a modified pair of symbolic links to different targets
+    pub reason_target: String,
+    /// Reason after a modified pair of files modified at different times
+    pub reason_modified: String,
+    /// Reason after a modified pair with a file that could not be read
+    pub reason_unreadable: String,
+    /// Footer with the [`ComparisonStats`]; `{files_a}`, `{dirs_a}`,
+    /// `{bytes_a}`, the same for B, `{bytes_hashed}` and `{elapsed}` are
+    /// replaced
+    pub stats: String,
+    /// Appended to the footer when paths were rewritten for the report
+    /// (`--sanitize-paths`); `{count}` is replaced by their number
+    pub sanitized_paths: String,
+    /// Name of directory A in the [`ReportContext`] header
+    pub directory_a: String,
+    /// Name of directory B in the [`ReportContext`] header
+    pub directory_b: String,
+    /// Name of the comparison method in the [`ReportContext`] header
+    pub method: String,
+    /// Name of the generation time in the [`ReportContext`] header
+    pub generated: String,
+    /// Placeholder of the filter box of HTML reports
+    pub filter: String,
+    /// Headings and words of flat mode reports
+    pub flat: FlatLabels,
+}
+
+impl Default for Labels {
+    fn default() -> Self {
+        Self {
+            lang: "en".to_string(),
+            report_title: "Directory Comparison Report".to_string(),
+            summary: "Summary".to_string(),
+            category: "Category".to_string(),
+            count: "Count".to_string(),
+            a_only: "A-only".to_string(),
+            b_only: "B-only".to_string(),
+            both: "Both".to_string(),
+            modified: "Modified".to_string(),
+            renamed: "Renamed/Moved".to_string(),
+            no_entries: "No entries".to_string(),
+            no_matching_entries: "No matching entries".to_string(),
+            entry_one: "entries".to_string(),
+            entry_other: "entries".to_string(),
+            more: "… plus {count} more".to_string(),
+            nested_repository: "nested repository".to_string(),
+            identical_one: "identical, {count} file".to_string(),
+            identical_other: "identical, {count} files".to_string(),
+            reason_kind: "kind differs".to_string(),
+            reason_size: "size {a} vs {b}".to_string(),
+            reason_content: "content differs".to_string(),
+            reason_target: "link target differs".to_string(),
+            reason_modified: "modification time differs".to_string(),
+            reason_unreadable: "unreadable".to_string(),
+            stats: "A: {files_a} files, {dirs_a} directories, {bytes_a}; \
+                    B: {files_b} files, {dirs_b} directories, {bytes_b}; \
+                    {bytes_hashed} hashed in {elapsed}"
+                .to_string(),
+            sanitized_paths: "paths sanitized for output: {count}".to_string(),
+            directory_a: "Directory A".to_string(),
+            directory_b: "Directory B".to_string(),
+            method: "Method".to_string(),
+            generated: "Generated".to_string(),
+            filter: "Filter paths".to_string(),
+            flat: FlatLabels::default(),
+        }
+    }
+}
+
+/// Headings and words of the flat mode Text, Markdown and HTML formatters.
+///
+/// Some appear in several forms, as the English formats word them
+/// differently.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FlatLabels {
+    /// Report title (Markdown and HTML)
+    pub report_title: String,
+    /// Title of the HTML document
+    pub document_title: String,
+    /// Summary heading (Text)
+    pub summary: String,
+    /// "Metric" column header of the summary table (Markdown)
+    pub metric: String,
+    /// "Value" column header of the summary table (Markdown)
+    pub value: String,
+    /// Summary row counting the files in directory A (Text and Markdown)
+    pub files_in_a: String,
+    /// Summary row counting the files in directory B (Text and Markdown)
+    pub files_in_b: String,
+    /// Summary row counting the distinct contents (Text and Markdown)
+    pub unique_hashes: String,
+    /// Summary row counting the duplicate groups (Text)
+    pub duplicate_groups: String,
+    /// Summary row counting the duplicate groups (Markdown)
+    pub duplicate_groups_short: String,
+    /// Summary box counting the files in directory A (HTML)
+    pub files_a_short: String,
+    /// Summary box counting the files in directory B (HTML)
+    pub files_b_short: String,
+    /// Summary box counting the distinct contents (HTML)
+    pub unique_hashes_short: String,
+    /// Summary box counting the duplicate groups (HTML)
+    pub duplicates_short: String,
+    /// Heading above the content groups (Markdown)
+    pub content_groups: String,
+    /// Heading of a group's files in directory A (Markdown)
+    pub directory_a: String,
+    /// Heading of a group's files in directory B (Markdown)
+    pub directory_b: String,
+    /// Status of a group holding several files
+    pub duplicate: String,
+    /// Status of a group found in both directories (Text)
+    pub matched: String,
+    /// Status of a group found in both directories (Markdown and HTML)
+    pub moved: String,
+    /// Status of a group found only in directory A
+    pub a_only: String,
+    /// Status of a group found only in directory B
+    pub b_only: String,
+    /// Note after a file of A whose content is also in B
+    pub moved_to_b: String,
+    /// Note after a file of B whose content is also in A
+    pub moved_from_a: String,
+    /// "Hash" before a group's content hash (Text and Markdown)
+    pub hash: String,
+    /// "Size" before a group's size (HTML)
+    pub size: String,
+    /// Unit after a group's size
+    pub bytes: String,
+    /// Noun after a group's file count (Text and Markdown)
+    pub files: String,
+    /// Noun after a group's file count (HTML)
+    pub files_html: String,
+    /// Note after a file that is a hard link to another file of its group,
+    /// followed by the hard-link group number
+    pub hard_link: String,
+}
+
+impl Default for FlatLabels {
+    fn default() -> Self {
+        Self {
+            report_title: "Flat Mode Comparison Report".to_string(),
+            document_title: "Flat Mode Directory Comparison Report".to_string(),
+            summary: "Flat Mode Comparison Summary".to_string(),
+            metric: "Metric".to_string(),
+            value: "Value".to_string(),
+            files_in_a: "Files in directory A".to_string(),
+            files_in_b: "Files in directory B".to_string(),
+            unique_hashes: "Unique content hashes".to_string(),
+            duplicate_groups: "Duplicate content groups".to_string(),
+            duplicate_groups_short: "Duplicate groups".to_string(),
+            files_a_short: "Files in A".to_string(),
+            files_b_short: "Files in B".to_string(),
+            unique_hashes_short: "Unique Hashes".to_string(),
+            duplicates_short: "Duplicates".to_string(),
+            content_groups: "Content Groups".to_string(),
+            directory_a: "Directory A".to_string(),
+            directory_b: "Directory B".to_string(),
+            duplicate: "DUPLICATE".to_string(),
+            matched: "MATCHED".to_string(),
+            moved: "MOVED".to_string(),
+            a_only: "A-ONLY".to_string(),
+            b_only: "B-ONLY".to_string(),
+            moved_to_b: "moved/copied to B".to_string(),
+            moved_from_a: "moved/copied from A".to_string(),
+            hash: "Hash".to_string(),
+            size: "Size".to_string(),
+            bytes: "bytes".to_string(),
+            files: "files".to_string(),
+            files_html: "file(s)".to_string(),
+            hard_link: "hard link".to_string(),
+        }
+    }
+}
+
+impl Labels {
+    /// Formats a count followed by the singular or plural noun.
+    pub fn entries(&self, count: usize) -> String {
+        let noun = if count == 1 {
+            &self.entry_one
+        } else {
+            &self.entry_other
+        };
+        format!("{} {}", count, noun)
+    }
+
+    /// Formats the line noting `omitted` entries, with thousands separators.
+    pub fn more(&self, omitted: usize) -> String {
+        self.more.replace("{count}", &group_digits(omitted))
+    }
+
+    /// Formats the note after a directory whose whole subtree matched, with
+    /// `files` files below it, with thousands separators.
+    pub fn identical(&self, files: usize) -> String {
+        let note = if files == 1 {
+            &self.identical_one
+        } else {
+            &self.identical_other
+        };
+        note.replace("{count}", &group_digits(files))
+    }
+
+    /// Formats why a modified pair did not match, with thousands separators
+    /// in sizes.
+    pub fn mismatch_reason(&self, reason: MismatchReason) -> String {
+        match reason {
+            MismatchReason::KindDiffers => self.reason_kind.clone(),
+            MismatchReason::SizeDiffers { a, b } => self
+                .reason_size
+                .replace("{a}", &group_number(&a.to_string()))
+                .replace("{b}", &group_number(&b.to_string())),
+            MismatchReason::ContentDiffers => self.reason_content.clone(),
+            MismatchReason::TargetDiffers => self.reason_target.clone(),
+            MismatchReason::ModifiedDiffers => self.reason_modified.clone(),
+            MismatchReason::Unreadable => self.reason_unreadable.clone(),
+        }
+    }
+
+    /// Formats the footer line of `stats`, noting the sanitized paths if
+    /// there are any.
+    pub fn stats(&self, stats: &ComparisonStats) -> String {
+        let mut line = [
+            ("{files_a}", group_digits(stats.files_a)),
+            ("{files_b}", group_digits(stats.files_b)),
+            ("{dirs_a}", group_digits(stats.dirs_a)),
+            ("{dirs_b}", group_digits(stats.dirs_b)),
+            ("{bytes_a}", format_bytes(stats.bytes_a)),
+            ("{bytes_b}", format_bytes(stats.bytes_b)),
+            ("{bytes_hashed}", format_bytes(stats.bytes_hashed)),
+            ("{elapsed}", format_elapsed(stats.elapsed)),
+        ]
+        .iter()
+        .fold(self.stats.clone(), |line, (placeholder, value)| {
+            line.replace(placeholder, value)
+        });
+        if stats.sanitized_paths > 0 {
+            line.push_str("; ");
+            line.push_str(
+                &self
+                    .sanitized_paths
+                    .replace("{count}", &group_digits(stats.sanitized_paths)),
+            );
+        }
+        line
+    }
+}
+
+/// Formats a count with `,` between groups of three digits.
+pub fn group_digits(count: usize) -> String {
+    group_number(&count.to_string())
+}
+
+/// Puts `,` between groups of three of `digits`.
+fn group_number(digits: &str) -> String {
+    let mut grouped = String::with_capacity(digits.len() + digits.len() / 3);
+    for (i, digit) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i).is_multiple_of(3) {
+            grouped.push(',');
+        }
+        grouped.push(digit);
+    }
+    grouped
+}
+
+/// A section of a hierarchy-mode report, and the [`ComparisonResult`]
+/// category it lists.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Section {
+    /// Entries only in directory A
+    AOnly,
+    /// Entries only in directory B
+    BOnly,
+    /// Same-path entries that differ
+    Modified,
+    /// Entries matching in both directories
+    Both,
+}
+
+impl Section {
+    /// All sections in the default report order.
+    pub const ALL: [Section; 4] = [
+        Section::AOnly,
+        Section::BOnly,
+        Section::Modified,
+        Section::Both,
+    ];
+
+    /// Returns the name used on the command line.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Section::AOnly => "a-only",
+            Section::BOnly => "b-only",
+            Section::Modified => "modified",
+            Section::Both => "both",
+        }
+    }
+}
+
+impl std::str::FromStr for Section {
+    type Err = String;
+
+    /// Parses a section name; `different` is accepted for `modified`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "a-only" | "a_only" => Ok(Section::AOnly),
+            "b-only" | "b_only" => Ok(Section::BOnly),
+            "modified" | "different" => Ok(Section::Modified),
+            "both" => Ok(Section::Both),
+            other => Err(format!(
+                "unknown section '{}' (expected a-only, b-only, modified or both)",
+                other
+            )),
+        }
+    }
+}
+
+/// Which report sections the Text, Markdown and HTML formatters render, and
+/// in which order.
+///
+/// Summary counts are not affected; they always cover every category. The
+/// default lists every section in the order of [`Section::ALL`], and
+/// [`SectionConfig::summary_only`] renders the summary alone.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SectionConfig {
+    sections: Vec<Section>,
+    summary_only: bool,
+}
+
+impl Default for SectionConfig {
+    fn default() -> Self {
+        Self {
+            sections: Section::ALL.to_vec(),
+            summary_only: false,
+        }
+    }
+}
+
+impl SectionConfig {
+    /// Creates a configuration showing `sections` in the given order.
+    ///
+    /// Repeated sections are shown once, at their first position.
+    pub fn new(sections: impl IntoIterator<Item = Section>) -> Self {
+        let mut unique = Vec::new();
+        for section in sections {
+            if !unique.contains(&section) {
+                unique.push(section);
+            }
+        }
+        Self {
+            sections: unique,
+            summary_only: false,
+        }
+    }
+
+    /// Creates a configuration showing only the summary: the count of each
+    /// category and the [`ComparisonStats`] footer, without any entry.
+    ///
+    /// Text reports list the counts one per line; Markdown and HTML reports
+    /// keep their summary table or boxes and stay complete documents.
+    pub fn summary_only() -> Self {
+        Self {
+            sections: Vec::new(),
+            summary_only: true,
+        }
+    }
+
+    /// Returns the sections to render, in order.
+    pub fn sections(&self) -> &[Section] {
+        &self.sections
+    }
+
+    /// Returns true if only the summary is rendered, see
+    /// [`SectionConfig::summary_only`].
+    pub fn is_summary_only(&self) -> bool {
+        self.summary_only
+    }
+}
+
+/// Which directories a report compares, how and when, shown in a header
+/// above the sections of text, Markdown and HTML reports.
+///
+/// Set it as [`ComparisonResult::context`]; reports of results without one
+/// have no header.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ReportContext {
+    /// Absolute path of directory A
+    pub dir_a: PathBuf,
+    /// Absolute path of directory B
+    pub dir_b: PathBuf,
+    /// Name of the comparison method, such as `sampled`
+    pub strategy_name: String,
+    /// Options changing what the method matches, such as `case-insensitive`
+    pub options: Vec<String>,
+    /// When the report was generated
+    pub timestamp: SystemTime,
+}
+
+impl ReportContext {
+    /// Returns the method followed by its options, as in
+    /// `sampled (case-insensitive, verify)`.
+    pub fn method(&self) -> String {
+        if self.options.is_empty() {
+            self.strategy_name.clone()
+        } else {
+            format!("{} ({})", self.strategy_name, self.options.join(", "))
+        }
+    }
+
+    /// Returns the labelled lines of the header: both directories, the
+    /// method and the generation time in RFC 3339 UTC.
+    pub fn rows(&self, labels: &Labels) -> [(String, String); 4] {
+        [
+            (labels.directory_a.clone(), self.dir_a.display().to_string()),
+            (labels.directory_b.clone(), self.dir_b.display().to_string()),
+            (labels.method.clone(), self.method()),
+            (
+                labels.generated.clone(),
+                format_timestamp(TimestampFormat::Rfc3339, self.timestamp),
+            ),
+        ]
+    }
+}
+
+/// Appends the `Label: value` lines of the context of `result`, if any, and
+/// a blank line.
+fn push_text_context(output: &mut String, labels: &Labels, result: &ComparisonResult) {
+    let Some(context) = &result.context else {
+        return;
+    };
+    for (label, value) in context.rows(labels) {
+        output.push_str(&format!("{}: {}\n", label, value));
+    }
+    output.push('\n');
 }
 
 /// Formatter that outputs human-readable text format.
 ///
 /// Produces a simple text output with section headers for A-only,
-/// B-only, and both entries.
+/// B-only, modified and both entries.
 ///
 /// # Output Format
 ///
@@ -45,6 +609,10 @@ pub trait Formatter {
 ///   dir_b-only/
 ///   file_b.txt
 ///
+/// Modified (1 entries):
+/// ----------------------------------------
+///   notes.txt != notes.txt
+///
 /// Both (1 entries):
 /// ----------------------------------------
 ///   common.txt == common.txt
@@ -53,41 +621,535 @@ pub struct TextFormatter;
 
 impl Formatter for TextFormatter {
     fn format(&self, result: &ComparisonResult) -> String {
-        let mut output = String::new();
+        self.format_with_labels(result, &Labels::default())
+    }
 
-        output.push_str(&format!("A-only ({} entries):\n", result.a_only.len()));
-        output.push_str(&"-".repeat(40));
-        output.push('\n');
-        for entry in &result.a_only {
-            let indicator = match entry.kind {
-                EntryKind::Directory => "/",
-                EntryKind::File => "",
-            };
-            output.push_str(&format!("  {}{}\n", entry.path.display(), indicator));
+    fn format_to_with_labels(
+        &self,
+        result: &ComparisonResult,
+        labels: &Labels,
+        w: &mut dyn io::Write,
+    ) -> io::Result<()> {
+        w.write_all(self.format_with_labels(result, labels).as_bytes())
+    }
+}
+
+impl TextFormatter {
+    /// Formats the comparison result using the given section labels.
+    pub fn format_with_labels(&self, result: &ComparisonResult, labels: &Labels) -> String {
+        self.format_with_sections(result, labels, &SectionConfig::default())
+    }
+
+    /// Formats only the configured sections, in the configured order.
+    pub fn format_with_sections(
+        &self,
+        result: &ComparisonResult,
+        labels: &Labels,
+        sections: &SectionConfig,
+    ) -> String {
+        text_report(result, labels, sections, &TextStyles::PLAIN)
+    }
+
+    /// Formats the comparison result with same-side hard links collapsed.
+    ///
+    /// Each group of hard links is listed once under its first path and
+    /// annotated with the number of extra links; with `verbose` the extra
+    /// paths are listed beneath it. The A-only and B-only sections end with
+    /// raw and unique-inode byte totals.
+    ///
+    /// # Output Format
+    ///
+    /// ```text
+    /// A-only (1 entries):
+    /// ----------------------------------------
+    ///   mail/cur/1 (+ 2 hard links)
+    ///   Bytes: 3.0 KiB raw, 1.0 KiB unique
+    /// ```
+    pub fn format_with_hard_links(
+        &self,
+        result: &ComparisonResult,
+        labels: &Labels,
+        hard_links: &HardLinkReport,
+        verbose: bool,
+    ) -> String {
+        text_report_with_hard_links(result, labels, hard_links, verbose, &TextStyles::PLAIN)
+    }
+}
+
+/// ANSI escape sequences a text report is styled with.
+///
+/// Every sequence of [`TextStyles::PLAIN`] is empty, so styling with it
+/// changes nothing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TextStyles {
+    /// Entries only in directory A
+    pub a_only: &'static str,
+    /// Entries only in directory B
+    pub b_only: &'static str,
+    /// Same-path pairs that differ
+    pub modified: &'static str,
+    /// Section headers
+    pub header: &'static str,
+    /// Ends each styled span
+    pub reset: &'static str,
+}
+
+impl TextStyles {
+    /// Red A-only entries, green B-only entries, yellow modified pairs and
+    /// bold section headers.
+    pub const ANSI: TextStyles = TextStyles {
+        a_only: "\x1b[31m",
+        b_only: "\x1b[32m",
+        modified: "\x1b[33m",
+        header: "\x1b[1m",
+        reset: "\x1b[0m",
+    };
+
+    /// No styling.
+    pub const PLAIN: TextStyles = TextStyles {
+        a_only: "",
+        b_only: "",
+        modified: "",
+        header: "",
+        reset: "",
+    };
+
+    /// Returns the style of the entries listed in `section`; entries in
+    /// both directories are not styled.
+    pub fn entries(&self, section: Section) -> &'static str {
+        match section {
+            Section::AOnly => self.a_only,
+            Section::BOnly => self.b_only,
+            Section::Modified => self.modified,
+            Section::Both => "",
         }
-        output.push('\n');
+    }
 
-        output.push_str(&format!("B-only ({} entries):\n", result.b_only.len()));
-        output.push_str(&"-".repeat(40));
-        output.push('\n');
-        for entry in &result.b_only {
-            let indicator = match entry.kind {
-                EntryKind::Directory => "/",
-                EntryKind::File => "",
-            };
-            output.push_str(&format!("  {}{}\n", entry.path.display(), indicator));
+    /// Wraps `text` in `style`, followed by [`reset`](Self::reset) unless
+    /// `style` is empty.
+    pub fn paint(&self, style: &str, text: &str) -> String {
+        if style.is_empty() {
+            text.to_string()
+        } else {
+            format!("{}{}{}", style, text, self.reset)
+        }
+    }
+}
+
+/// Formatter that outputs the text of [`TextFormatter`] styled with
+/// [`TextStyles::ANSI`], for terminals.
+///
+/// The characters between the escape sequences are those of
+/// [`TextFormatter`]; only whole lines are styled, after their indentation.
+pub struct ColorTextFormatter;
+
+impl Formatter for ColorTextFormatter {
+    fn format(&self, result: &ComparisonResult) -> String {
+        self.format_with_labels(result, &Labels::default())
+    }
+
+    fn format_to_with_labels(
+        &self,
+        result: &ComparisonResult,
+        labels: &Labels,
+        w: &mut dyn io::Write,
+    ) -> io::Result<()> {
+        w.write_all(self.format_with_labels(result, labels).as_bytes())
+    }
+}
+
+impl ColorTextFormatter {
+    /// Formats the comparison result using the given section labels, see
+    /// [`TextFormatter::format_with_labels`].
+    pub fn format_with_labels(&self, result: &ComparisonResult, labels: &Labels) -> String {
+        self.format_with_sections(result, labels, &SectionConfig::default())
+    }
+
+    /// Formats only the configured sections, see
+    /// [`TextFormatter::format_with_sections`].
+    pub fn format_with_sections(
+        &self,
+        result: &ComparisonResult,
+        labels: &Labels,
+        sections: &SectionConfig,
+    ) -> String {
+        text_report(result, labels, sections, &TextStyles::ANSI)
+    }
+
+    /// Formats the comparison result with same-side hard links collapsed,
+    /// see [`TextFormatter::format_with_hard_links`].
+    pub fn format_with_hard_links(
+        &self,
+        result: &ComparisonResult,
+        labels: &Labels,
+        hard_links: &HardLinkReport,
+        verbose: bool,
+    ) -> String {
+        text_report_with_hard_links(result, labels, hard_links, verbose, &TextStyles::ANSI)
+    }
+}
+
+/// Renders the configured sections of a text report.
+fn text_report(
+    result: &ComparisonResult,
+    labels: &Labels,
+    sections: &SectionConfig,
+    styles: &TextStyles,
+) -> String {
+    let mut output = String::new();
+    push_text_context(&mut output, labels, result);
+
+    if sections.is_summary_only() {
+        push_text_summary(&mut output, labels, result, styles);
+    }
+    for (i, section) in sections.sections().iter().enumerate() {
+        if i > 0 {
+            output.push('\n');
+        }
+        match section {
+            Section::AOnly => push_entry_section(
+                &mut output,
+                Section::AOnly,
+                &labels.a_only,
+                labels,
+                &result.a_only,
+                result.omitted(Section::AOnly),
+                styles,
+            ),
+            Section::BOnly => push_entry_section(
+                &mut output,
+                Section::BOnly,
+                &labels.b_only,
+                labels,
+                &result.b_only,
+                result.omitted(Section::BOnly),
+                styles,
+            ),
+            Section::Modified => push_pair_section(
+                &mut output,
+                Section::Modified,
+                &labels.modified,
+                labels,
+                &result.modified,
+                &[],
+                &result.mismatch_reasons,
+                result.omitted(Section::Modified),
+                styles,
+            ),
+            Section::Both => push_pair_section(
+                &mut output,
+                Section::Both,
+                &labels.both,
+                labels,
+                &result.both,
+                &result.identical_subtrees,
+                &[],
+                result.omitted(Section::Both),
+                styles,
+            ),
         }
+        if Some(*section) == renamed_anchor(sections) && !result.renamed.is_empty() {
+            output.push('\n');
+            push_renamed_section(&mut output, labels, &result.renamed, styles);
+        }
+    }
+    output.push('\n');
+    output.push_str(&labels.stats(&result.stats));
+    output.push('\n');
+
+    output
+}
+
+/// Appends one line per category with its count, and one for the renamed
+/// pairs if there are any.
+fn push_text_summary(
+    output: &mut String,
+    labels: &Labels,
+    result: &ComparisonResult,
+    styles: &TextStyles,
+) {
+    for (title, section) in [
+        (&labels.a_only, Section::AOnly),
+        (&labels.b_only, Section::BOnly),
+        (&labels.modified, Section::Modified),
+        (&labels.both, Section::Both),
+    ] {
+        let line = format!("{}: {}", title, labels.entries(result.count(section)));
+        output.push_str(&styles.paint(styles.entries(section), &line));
+        output.push('\n');
+    }
+    if !result.renamed.is_empty() {
+        let line = format!(
+            "{}: {}",
+            labels.renamed,
+            labels.entries(result.renamed.len())
+        );
+        output.push_str(&styles.paint(styles.modified, &line));
         output.push('\n');
+    }
+}
+
+/// Renders a text report with same-side hard links collapsed.
+fn text_report_with_hard_links(
+    result: &ComparisonResult,
+    labels: &Labels,
+    hard_links: &HardLinkReport,
+    verbose: bool,
+    styles: &TextStyles,
+) -> String {
+    let mut output = String::new();
+    push_text_context(&mut output, labels, result);
 
-        output.push_str(&format!("Both ({} entries):\n", result.both.len()));
-        output.push_str(&"-".repeat(40));
+    push_hard_link_section(
+        &mut output,
+        Section::AOnly,
+        labels,
+        &labels.a_only,
+        &hard_links.a_only,
+        verbose,
+        styles,
+    );
+    push_hard_link_section(
+        &mut output,
+        Section::BOnly,
+        labels,
+        &labels.b_only,
+        &hard_links.b_only,
+        verbose,
+        styles,
+    );
+    if !result.renamed.is_empty() {
+        push_renamed_section(&mut output, labels, &result.renamed, styles);
         output.push('\n');
-        for (a, b) in &result.both {
-            output.push_str(&format!("  {} == {}\n", a.path.display(), b.path.display()));
+    }
+
+    push_pair_section(
+        &mut output,
+        Section::Modified,
+        &labels.modified,
+        labels,
+        &result.modified,
+        &[],
+        &result.mismatch_reasons,
+        result.omitted(Section::Modified),
+        styles,
+    );
+    output.push('\n');
+    push_pair_section(
+        &mut output,
+        Section::Both,
+        &labels.both,
+        labels,
+        &result.both,
+        &result.identical_subtrees,
+        &[],
+        result.omitted(Section::Both),
+        styles,
+    );
+    output.push('\n');
+    output.push_str(&labels.stats(&result.stats));
+    output.push('\n');
+
+    output
+}
+
+/// Returns the suffix shown after an entry's path: `/` for directories and
+/// `-> target` for symlinks.
+fn kind_suffix(kind: &EntryKind) -> String {
+    match kind {
+        EntryKind::Directory => "/".to_string(),
+        EntryKind::File => String::new(),
+        EntryKind::Symlink { .. } => target_suffix(kind),
+    }
+}
+
+/// Returns ` -> target` for symlinks and an empty string otherwise.
+///
+/// Same-path pairs use this instead of [`kind_suffix`] so that mismatched
+/// link targets are visible without changing how files and directories print.
+fn target_suffix(kind: &EntryKind) -> String {
+    match kind {
+        EntryKind::Symlink { target } => format!(" -> {}", target.display()),
+        _ => String::new(),
+    }
+}
+
+/// Returns ` [nested repository]` if either entry is a nested repository.
+fn nested_note(labels: &Labels, entries: &[&Entry]) -> String {
+    if entries.iter().any(|entry| entry.nested_repository) {
+        format!(" [{}]", labels.nested_repository)
+    } else {
+        String::new()
+    }
+}
+
+/// Returns ` — reason` for the pair at `path` if `reasons` has one.
+fn reason_note(labels: &Labels, reasons: &[(PathBuf, MismatchReason)], path: &Path) -> String {
+    match find_mismatch_reason(reasons, path) {
+        Some(reason) => format!(" — {}", labels.mismatch_reason(reason)),
+        None => String::new(),
+    }
+}
+
+/// Appends the header of a text section and its underline.
+fn push_section_header(output: &mut String, title: &str, count: &str, styles: &TextStyles) {
+    let header = format!("{} ({}):", title, count);
+    output.push_str(&styles.paint(styles.header, &header));
+    output.push('\n');
+    output.push_str(&"-".repeat(40));
+    output.push('\n');
+}
+
+/// Returns the section the renamed pairs are listed after: B-only, or
+/// A-only if B-only is not shown. Without either they are not listed.
+fn renamed_anchor(sections: &SectionConfig) -> Option<Section> {
+    [Section::BOnly, Section::AOnly]
+        .into_iter()
+        .find(|section| sections.sections().contains(section))
+}
+
+/// Appends a text section listing renamed files as `old -> new`.
+fn push_renamed_section(
+    output: &mut String,
+    labels: &Labels,
+    renamed: &[(Entry, Entry)],
+    styles: &TextStyles,
+) {
+    push_section_header(
+        output,
+        &labels.renamed,
+        &labels.entries(renamed.len()),
+        styles,
+    );
+    for (a, b) in renamed {
+        output.push_str(&format!("  {} -> {}\n", a.path.display(), b.path.display()));
+    }
+}
+
+/// Appends a text section listing one-sided entries, followed by a note on
+/// the `omitted` entries that were counted but not kept.
+fn push_entry_section(
+    output: &mut String,
+    section: Section,
+    title: &str,
+    labels: &Labels,
+    entries: &[Entry],
+    omitted: usize,
+    styles: &TextStyles,
+) {
+    let count = entries.len() + omitted;
+    push_section_header(output, title, &labels.entries(count), styles);
+    for entry in entries {
+        let indicator = kind_suffix(&entry.kind);
+        let line = format!(
+            "{}{}{}",
+            entry.path.display(),
+            indicator,
+            nested_note(labels, &[entry])
+        );
+        output.push_str(&format!(
+            "  {}\n",
+            styles.paint(styles.entries(section), &line)
+        ));
+    }
+    if omitted > 0 {
+        output.push_str(&format!("  {}\n", labels.more(omitted)));
+    }
+}
+
+/// Appends a text section listing same-path pairs, joined by `!=` in the
+/// modified section and `==` otherwise, followed by a note on the `omitted`
+/// pairs that were counted but not kept. Directories of `subtrees` are
+/// listed once, with the number of files below them.
+#[allow(clippy::too_many_arguments)]
+fn push_pair_section(
+    output: &mut String,
+    section: Section,
+    title: &str,
+    labels: &Labels,
+    pairs: &[(Entry, Entry)],
+    subtrees: &[IdenticalSubtree],
+    reasons: &[(PathBuf, MismatchReason)],
+    omitted: usize,
+    styles: &TextStyles,
+) {
+    let rolled_up: usize = subtrees.iter().map(|subtree| subtree.entries).sum();
+    let count = pairs.len() + rolled_up + omitted;
+    let separator = if section == Section::Modified {
+        "!="
+    } else {
+        "=="
+    };
+    push_section_header(output, title, &labels.entries(count), styles);
+    for (a, b) in pairs {
+        if let Some(subtree) = find_identical_subtree(subtrees, &a.path) {
+            let line = format!(
+                "{}/ ({})",
+                a.path.display(),
+                labels.identical(subtree.files)
+            );
+            output.push_str(&format!(
+                "  {}\n",
+                styles.paint(styles.entries(section), &line)
+            ));
+            continue;
         }
+        let line = format!(
+            "{}{} {} {}{}{}{}",
+            a.path.display(),
+            target_suffix(&a.kind),
+            separator,
+            b.path.display(),
+            target_suffix(&b.kind),
+            nested_note(labels, &[a, b]),
+            reason_note(labels, reasons, &a.path)
+        );
+        output.push_str(&format!(
+            "  {}\n",
+            styles.paint(styles.entries(section), &line)
+        ));
+    }
+    if omitted > 0 {
+        output.push_str(&format!("  {}\n", labels.more(omitted)));
+    }
+}
 
-        output
+/// Appends one one-sided text section listing hard-link groups.
+fn push_hard_link_section(
+    output: &mut String,
+    section: Section,
+    labels: &Labels,
+    title: &str,
+    groups: &[HardLinkGroup],
+    verbose: bool,
+    styles: &TextStyles,
+) {
+    push_section_header(output, title, &labels.entries(groups.len()), styles);
+    for group in groups {
+        let indicator = kind_suffix(&group.primary.kind);
+        let mut line = format!("{}{}", group.primary.path.display(), indicator);
+        match group.links.len() {
+            0 => {}
+            1 => line.push_str(" (+ 1 hard link)"),
+            n => line.push_str(&format!(" (+ {} hard links)", n)),
+        }
+        output.push_str(&format!(
+            "  {}\n",
+            styles.paint(styles.entries(section), &line)
+        ));
+        if verbose {
+            for link in &group.links {
+                output.push_str(&format!("      = {}\n", link.path.display()));
+            }
+        }
     }
+    let totals = byte_totals(groups);
+    output.push_str(&format!(
+        "  Bytes: {} raw, {} unique\n",
+        format_bytes(totals.raw),
+        format_bytes(totals.unique)
+    ));
+    output.push('\n');
 }
 
 /// Formatter that outputs HTML format with inline CSS styling.
@@ -116,23 +1178,51 @@ pub struct HtmlFormatter;
 
 impl Formatter for HtmlFormatter {
     fn format(&self, result: &ComparisonResult) -> String {
+        self.format_with_labels(result, &Labels::default())
+    }
+
+    fn format_to_with_labels(
+        &self,
+        result: &ComparisonResult,
+        labels: &Labels,
+        w: &mut dyn io::Write,
+    ) -> io::Result<()> {
+        w.write_all(self.format_with_labels(result, labels).as_bytes())
+    }
+}
+
+impl HtmlFormatter {
+    /// Formats the comparison result using the given section labels.
+    pub fn format_with_labels(&self, result: &ComparisonResult, labels: &Labels) -> String {
+        self.format_with_sections(result, labels, &SectionConfig::default())
+    }
+
+    /// Formats only the configured sections, in the configured order.
+    ///
+    /// The summary boxes always show every category. A
+    /// [summary](SectionConfig::summary_only) has neither the filter box nor
+    /// the navigation script.
+    pub fn format_with_sections(
+        &self,
+        result: &ComparisonResult,
+        labels: &Labels,
+        sections: &SectionConfig,
+    ) -> String {
         let mut html = String::new();
 
-        html.push_str(r#"<!DOCTYPE html>
-<html lang="en">
-<head>
-    <meta charset="UTF-8">
-    <meta name="viewport" content="width=device-width, initial-scale=1.0">
-    <title>Directory Comparison Report</title>
-    <style>
+        push_html_head(&mut html, &labels.lang, &labels.report_title);
+        html.push_str(r#"    <style>
         body { font-family: -apple-system, BlinkMacSystemFont, 'Segoe UI', Roboto, sans-serif; margin: 20px; background: #f5f5f5; }
         .container { max-width: 1200px; margin: 0 auto; background: white; padding: 20px; border-radius: 8px; box-shadow: 0 2px 4px rgba(0,0,0,0.1); }
         h1 { color: #333; border-bottom: 2px solid #4a90d9; padding-bottom: 10px; }
-        .summary { display: grid; grid-template-columns: repeat(3, 1fr); gap: 20px; margin-bottom: 30px; }
+        .summary { display: grid; grid-template-columns: repeat(4, 1fr); gap: 20px; margin-bottom: 30px; }
         .summary-box { background: #f8f9fa; padding: 15px; border-radius: 6px; text-align: center; border-left: 4px solid #4a90d9; }
         .summary-box.a-only { border-left-color: #dc3545; }
         .summary-box.b-only { border-left-color: #28a745; }
         .summary-box.both { border-left-color: #6c757d; }
+        .summary-box.modified { border-left-color: #fd7e14; }
+        .summary-box.renamed { border-left-color: #6f42c1; }
+        .reason { color: #fd7e14; }
         .count { font-size: 2em; font-weight: bold; color: #333; }
         .label { color: #666; font-size: 0.9em; }
         .section { margin-bottom: 30px; }
@@ -142,165 +1232,349 @@ impl Formatter for HtmlFormatter {
         .entry-list li:last-child { border-bottom: none; }
         .dir { color: #0066cc; }
         .file { color: #333; }
+        .symlink { color: #6f42c1; }
         .empty { color: #999; font-style: italic; }
+        .more { color: #666; font-style: italic; margin: 8px 12px; }
         .comparison { color: #666; }
+        .stats { color: #666; font-size: 0.9em; border-top: 1px solid #eee; padding-top: 10px; }
+        .context { display: grid; grid-template-columns: max-content 1fr; gap: 4px 16px; margin: 0 0 20px; color: #444; }
+        .context dt { font-weight: bold; }
+        .context dd { margin: 0; font-family: monospace; word-break: break-all; }
+        .filter { width: 100%; box-sizing: border-box; padding: 8px 12px; margin-bottom: 20px; border: 1px solid #ccc; border-radius: 4px; font-size: 1em; }
+        .section h2 { cursor: pointer; user-select: none; }
+        .section h2::before { content: "\25BE  "; }
+        .section.collapsed h2::before { content: "\25B8  "; }
+        .section.collapsed > :not(h2) { display: none; }
     </style>
 </head>
 <body>
     <div class="container">
-        <h1>Directory Comparison Report</h1>
-        <div class="summary">
-            <div class="summary-box a-only">
-                <div class="count">"#);
-        html.push_str(&result.a_only.len().to_string());
-        html.push_str(
-            r#"</div>
-                <div class="label">A-only</div>
-            </div>
-            <div class="summary-box b-only">
-                <div class="count">"#,
-        );
-        html.push_str(&result.b_only.len().to_string());
-        html.push_str(
-            r#"</div>
-                <div class="label">B-only</div>
-            </div>
-            <div class="summary-box both">
-                <div class="count">"#,
-        );
-        html.push_str(&result.both.len().to_string());
-        html.push_str(
-            r#"</div>
-                <div class="label">Both</div>
-            </div>
-        </div>
-"#,
-        );
-
-        html.push_str(
-            r#"
-        <div class="section">
-            <h2>A-only</h2>
-"#,
-        );
-        if result.a_only.is_empty() {
-            html.push_str(
-                r#"            <p class="empty">No entries</p>
-"#,
-            );
-        } else {
-            html.push_str(
-                r#"            <ul class="entry-list">
-"#,
-            );
-            for entry in &result.a_only {
-                let class = match entry.kind {
-                    EntryKind::Directory => "dir",
-                    EntryKind::File => "file",
-                };
-                let indicator = match entry.kind {
-                    EntryKind::Directory => "/",
-                    EntryKind::File => "",
-                };
+"#);
+        html.push_str(&format!(
+            "        <h1>{}</h1>\n",
+            escape_html(&labels.report_title)
+        ));
+        if let Some(context) = &result.context {
+            html.push_str("        <dl class=\"context\">\n");
+            for (label, value) in context.rows(labels) {
                 html.push_str(&format!(
-                    r#"                <li class="{}">{}{}</li>
-"#,
-                    class,
-                    escape_html(&entry.path.display().to_string()),
-                    indicator
+                    "            <dt>{}</dt><dd>{}</dd>\n",
+                    escape_html(&label),
+                    escape_html(&value)
                 ));
             }
-            html.push_str(
-                r#"            </ul>
-"#,
+            html.push_str("        </dl>\n");
+        }
+        html.push_str("        <div class=\"summary\">\n");
+        for (class, label, section) in [
+            ("a-only", &labels.a_only, Section::AOnly),
+            ("b-only", &labels.b_only, Section::BOnly),
+            ("modified", &labels.modified, Section::Modified),
+            ("both", &labels.both, Section::Both),
+        ] {
+            push_html_summary_box(
+                &mut html,
+                &format!("summary-box {}", class),
+                label,
+                result.count(section),
             );
         }
-        html.push_str(
-            r#"        </div>
+        if !result.renamed.is_empty() {
+            push_html_summary_box(
+                &mut html,
+                "summary-box renamed",
+                &labels.renamed,
+                result.renamed.len(),
+            );
+        }
+        html.push_str("        </div>\n");
+        let summary_only = sections.is_summary_only();
+        if !summary_only {
+            html.push_str(&format!(
+                "        <input type=\"search\" id=\"filter\" class=\"filter\" placeholder=\"{}\" hidden>\n",
+                escape_html(&labels.filter)
+            ));
+        }
+
+        for section in sections.sections() {
+            match section {
+                Section::AOnly => push_html_entries(
+                    &mut html,
+                    &labels.a_only,
+                    labels,
+                    &result.a_only,
+                    result.omitted(Section::AOnly),
+                ),
+                Section::BOnly => push_html_entries(
+                    &mut html,
+                    &labels.b_only,
+                    labels,
+                    &result.b_only,
+                    result.omitted(Section::BOnly),
+                ),
+                Section::Modified => push_html_pairs(
+                    &mut html,
+                    &labels.modified,
+                    &labels.no_entries,
+                    labels,
+                    &result.modified,
+                    &[],
+                    &result.mismatch_reasons,
+                    result.omitted(Section::Modified),
+                    "!=",
+                ),
+                Section::Both => push_html_pairs(
+                    &mut html,
+                    &labels.both,
+                    &labels.no_matching_entries,
+                    labels,
+                    &result.both,
+                    &result.identical_subtrees,
+                    &[],
+                    result.omitted(Section::Both),
+                    "==",
+                ),
+            }
+            if Some(*section) == renamed_anchor(sections) && !result.renamed.is_empty() {
+                push_html_pairs(
+                    &mut html,
+                    &labels.renamed,
+                    &labels.no_entries,
+                    labels,
+                    &result.renamed,
+                    &[],
+                    &[],
+                    0,
+                    "-&gt;",
+                );
+            }
+        }
+        push_html_stats(&mut html, labels, &result.stats);
+        html.push_str("    </div>\n");
+        // A summary has no entries to filter or sections to collapse
+        if !summary_only {
+            html.push_str(HTML_NAVIGATION_SCRIPT);
+        }
+        html.push_str("</body>\n</html>\n");
+
+        html
+    }
+}
+
+/// Script of HTML reports that shows the filter box, hides the entries not
+/// containing its text, and collapses a section when its heading is
+/// clicked. Reports opened without scripts show everything.
+const HTML_NAVIGATION_SCRIPT: &str = r#"    <script>
+    (function () {
+        var filter = document.getElementById("filter");
+        filter.hidden = false;
+        filter.addEventListener("input", function () {
+            var text = filter.value.toLowerCase();
+            document.querySelectorAll(".entry-list li").forEach(function (item) {
+                item.hidden = text !== "" && item.textContent.toLowerCase().indexOf(text) < 0;
+            });
+        });
+        document.querySelectorAll(".section h2").forEach(function (heading) {
+            heading.addEventListener("click", function () {
+                heading.parentNode.classList.toggle("collapsed");
+            });
+        });
+    })();
+    </script>
+"#;
+
+/// Appends the start of an HTML document in `lang`, up to its title.
+fn push_html_head(html: &mut String, lang: &str, title: &str) {
+    html.push_str(&format!(
+        r#"<!DOCTYPE html>
+<html lang="{}">
+<head>
+    <meta charset="UTF-8">
+    <meta name="viewport" content="width=device-width, initial-scale=1.0">
+    <title>{}</title>
 "#,
-        );
+        escape_html(lang),
+        escape_html(title)
+    ));
+}
 
-        html.push_str(
-            r#"
-        <div class="section">
-            <h2>B-only</h2>
+/// Appends a summary box showing `count` above `label`.
+fn push_html_summary_box(html: &mut String, class: &str, label: &str, count: usize) {
+    html.push_str(&format!(
+        r#"            <div class="{}">
+                <div class="count">{}</div>
+                <div class="label">{}</div>
+            </div>
 "#,
-        );
-        if result.b_only.is_empty() {
-            html.push_str(
-                r#"            <p class="empty">No entries</p>
+        class,
+        count,
+        escape_html(label)
+    ));
+}
+
+/// Appends the paragraph with the [`Labels::stats`] footer of `stats`.
+fn push_html_stats(html: &mut String, labels: &Labels, stats: &ComparisonStats) {
+    html.push_str(&format!(
+        "        <p class=\"stats\">{}</p>\n",
+        escape_html(&labels.stats(stats))
+    ));
+}
+
+/// Appends an HTML section listing one-sided entries, followed by a note on
+/// the `omitted` entries that were counted but not kept.
+fn push_html_entries(
+    html: &mut String,
+    title: &str,
+    labels: &Labels,
+    entries: &[Entry],
+    omitted: usize,
+) {
+    html.push_str(&format!(
+        r#"
+        <div class="section">
+            <h2>{}</h2>
 "#,
-            );
-        } else {
-            html.push_str(
-                r#"            <ul class="entry-list">
+        escape_html(title)
+    ));
+    if entries.is_empty() && omitted == 0 {
+        html.push_str(&format!(
+            r#"            <p class="empty">{}</p>
 "#,
-            );
-            for entry in &result.b_only {
-                let class = match entry.kind {
-                    EntryKind::Directory => "dir",
-                    EntryKind::File => "file",
-                };
-                let indicator = match entry.kind {
-                    EntryKind::Directory => "/",
-                    EntryKind::File => "",
-                };
-                html.push_str(&format!(
-                    r#"                <li class="{}">{}{}</li>
+            escape_html(&labels.no_entries)
+        ));
+    } else if !entries.is_empty() {
+        html.push_str(
+            r#"            <ul class="entry-list">
 "#,
-                    class,
-                    escape_html(&entry.path.display().to_string()),
-                    indicator
-                ));
-            }
-            html.push_str(
-                r#"            </ul>
+        );
+        for entry in entries {
+            let class = match entry.kind {
+                EntryKind::Directory => "dir",
+                EntryKind::File => "file",
+                EntryKind::Symlink { .. } => "symlink",
+            };
+            let indicator = kind_suffix(&entry.kind);
+            html.push_str(&format!(
+                r#"                <li class="{}">{}{}{}</li>
 "#,
-            );
+                class,
+                escape_html(&entry.path.display().to_string()),
+                escape_html(&indicator),
+                html_nested_note(labels, &[entry])
+            ));
         }
         html.push_str(
-            r#"        </div>
+            r#"            </ul>
 "#,
         );
+    }
+    push_html_more(html, labels, omitted);
+    html.push_str(
+        r#"        </div>
+"#,
+    );
+}
 
-        html.push_str(
-            r#"
+/// Appends an HTML section listing same-path pairs joined by `separator`,
+/// followed by a note on the `omitted` pairs that were counted but not kept.
+/// Directories of `subtrees` are listed once, with the number of files below
+/// them.
+#[allow(clippy::too_many_arguments)]
+fn push_html_pairs(
+    html: &mut String,
+    title: &str,
+    empty: &str,
+    labels: &Labels,
+    pairs: &[(Entry, Entry)],
+    subtrees: &[IdenticalSubtree],
+    reasons: &[(PathBuf, MismatchReason)],
+    omitted: usize,
+    separator: &str,
+) {
+    html.push_str(&format!(
+        r#"
         <div class="section">
-            <h2>Both</h2>
+            <h2>{}</h2>
 "#,
-        );
-        if result.both.is_empty() {
-            html.push_str(
-                r#"            <p class="empty">No matching entries</p>
+        escape_html(title)
+    ));
+    if pairs.is_empty() && omitted == 0 {
+        html.push_str(&format!(
+            r#"            <p class="empty">{}</p>
 "#,
-            );
-        } else {
-            html.push_str(
-                r#"            <ul class="entry-list">
+            escape_html(empty)
+        ));
+    } else if !pairs.is_empty() {
+        html.push_str(
+            r#"            <ul class="entry-list">
 "#,
-            );
-            for (a, b) in &result.both {
+        );
+        for (a, b) in pairs {
+            if let Some(subtree) = find_identical_subtree(subtrees, &a.path) {
                 html.push_str(&format!(
-                    r#"                <li class="comparison">{} == {}</li>
+                    r#"                <li class="comparison">{}/ <em class="identical">({})</em></li>
 "#,
                     escape_html(&a.path.display().to_string()),
-                    escape_html(&b.path.display().to_string())
+                    escape_html(&labels.identical(subtree.files))
                 ));
+                continue;
             }
-            html.push_str(
-                r#"            </ul>
+            html.push_str(&format!(
+                r#"                <li class="comparison">{}{} {} {}{}{}{}</li>
 "#,
-            );
+                escape_html(&a.path.display().to_string()),
+                escape_html(&target_suffix(&a.kind)),
+                separator,
+                escape_html(&b.path.display().to_string()),
+                escape_html(&target_suffix(&b.kind)),
+                html_nested_note(labels, &[a, b]),
+                html_reason_note(labels, reasons, &a.path)
+            ));
         }
         html.push_str(
-            r#"        </div>
-    </div>
-</body>
-</html>
+            r#"            </ul>
 "#,
         );
+    }
+    push_html_more(html, labels, omitted);
+    html.push_str(
+        r#"        </div>
+"#,
+    );
+}
 
-        html
+/// Returns the HTML note with the reason the pair at `path` differs, if
+/// `reasons` has one.
+fn html_reason_note(labels: &Labels, reasons: &[(PathBuf, MismatchReason)], path: &Path) -> String {
+    match find_mismatch_reason(reasons, path) {
+        Some(reason) => format!(
+            r#" <em class="reason">— {}</em>"#,
+            escape_html(&labels.mismatch_reason(reason))
+        ),
+        None => String::new(),
+    }
+}
+
+/// Returns the HTML note marking a nested repository, if either entry is one.
+fn html_nested_note(labels: &Labels, entries: &[&Entry]) -> String {
+    if entries.iter().any(|entry| entry.nested_repository) {
+        format!(
+            r#" <em class="nested-repo">({})</em>"#,
+            escape_html(&labels.nested_repository)
+        )
+    } else {
+        String::new()
+    }
+}
+
+/// Appends the note on `omitted` entries, if any.
+fn push_html_more(html: &mut String, labels: &Labels, omitted: usize) {
+    if omitted > 0 {
+        html.push_str(&format!(
+            r#"            <p class="more">{}</p>
+"#,
+            escape_html(&labels.more(omitted))
+        ));
     }
 }
 
@@ -335,6 +1609,7 @@ fn escape_html(s: &str) -> String {
 /// |---------|-------|
 /// | A-only | 2 |
 /// | B-only | 2 |
+/// | Modified | 1 |
 /// | Both | 1 |
 ///
 /// ## A-only
@@ -347,6 +1622,10 @@ fn escape_html(s: &str) -> String {
 /// - `dir_b-only/`
 /// - `file_b.txt`
 ///
+/// ## Modified
+///
+/// - `notes.txt` != `notes.txt`
+///
 /// ## Both
 ///
 /// - `common.txt` == `common.txt`
@@ -355,70 +1634,243 @@ pub struct MarkdownFormatter;
 
 impl Formatter for MarkdownFormatter {
     fn format(&self, result: &ComparisonResult) -> String {
-        let mut md = String::new();
+        self.format_with_labels(result, &Labels::default())
+    }
+
+    fn format_to_with_labels(
+        &self,
+        result: &ComparisonResult,
+        labels: &Labels,
+        w: &mut dyn io::Write,
+    ) -> io::Result<()> {
+        w.write_all(self.format_with_labels(result, labels).as_bytes())
+    }
+}
 
-        md.push_str("# Directory Comparison Report\n\n");
+impl MarkdownFormatter {
+    /// Formats the comparison result using the given section labels.
+    pub fn format_with_labels(&self, result: &ComparisonResult, labels: &Labels) -> String {
+        self.format_with_sections(result, labels, &SectionConfig::default())
+    }
 
-        md.push_str("## Summary\n\n");
-        md.push_str(&format!("| Category | Count |\n|---------|-------|\n"));
-        md.push_str(&format!("| A-only | {} |\n", result.a_only.len()));
-        md.push_str(&format!("| B-only | {} |\n", result.b_only.len()));
-        md.push_str(&format!("| Both | {} |\n\n", result.both.len()));
+    /// Formats only the configured sections, in the configured order.
+    ///
+    /// The summary table always lists every category.
+    pub fn format_with_sections(
+        &self,
+        result: &ComparisonResult,
+        labels: &Labels,
+        sections: &SectionConfig,
+    ) -> String {
+        let mut md = String::new();
 
-        md.push_str("## A-only\n\n");
-        if result.a_only.is_empty() {
-            md.push_str("*No entries*\n\n");
-        } else {
-            for entry in &result.a_only {
-                let indicator = match entry.kind {
-                    EntryKind::Directory => "/",
-                    EntryKind::File => "",
+        md.push_str(&format!("# {}\n\n", labels.report_title));
+        if let Some(context) = &result.context {
+            for (i, (label, value)) in context.rows(labels).into_iter().enumerate() {
+                // The directories are paths, written as code like the entries
+                let value = if i < 2 {
+                    format!("`{}`", escape_markdown(&value))
+                } else {
+                    escape_markdown(&value)
                 };
-                md.push_str(&format!(
-                    "- `{}{}`\n",
-                    escape_markdown(&entry.path.display().to_string()),
-                    indicator
-                ));
+                md.push_str(&format!("- **{}:** {}\n", label, value));
             }
             md.push('\n');
         }
 
-        md.push_str("## B-only\n\n");
-        if result.b_only.is_empty() {
-            md.push_str("*No entries*\n\n");
-        } else {
-            for entry in &result.b_only {
-                let indicator = match entry.kind {
-                    EntryKind::Directory => "/",
-                    EntryKind::File => "",
-                };
-                md.push_str(&format!(
-                    "- `{}{}`\n",
-                    escape_markdown(&entry.path.display().to_string()),
-                    indicator
-                ));
-            }
-            md.push('\n');
+        md.push_str(&format!("## {}\n\n", labels.summary));
+        md.push_str(&format!(
+            "| {} | {} |\n|---------|-------|\n",
+            labels.category, labels.count
+        ));
+        md.push_str(&format!(
+            "| {} | {} |\n",
+            labels.a_only,
+            result.count(Section::AOnly)
+        ));
+        md.push_str(&format!(
+            "| {} | {} |\n",
+            labels.b_only,
+            result.count(Section::BOnly)
+        ));
+        md.push_str(&format!(
+            "| {} | {} |\n",
+            labels.modified,
+            result.count(Section::Modified)
+        ));
+        md.push_str(&format!(
+            "| {} | {} |\n",
+            labels.both,
+            result.count(Section::Both)
+        ));
+        if !result.renamed.is_empty() {
+            md.push_str(&format!(
+                "| {} | {} |\n",
+                labels.renamed,
+                result.renamed.len()
+            ));
         }
+        md.push('\n');
 
-        md.push_str("## Both\n\n");
-        if result.both.is_empty() {
-            md.push_str("*No matching entries*\n\n");
-        } else {
-            for (a, b) in &result.both {
-                md.push_str(&format!(
-                    "- `{}` == `{}`\n",
-                    escape_markdown(&a.path.display().to_string()),
-                    escape_markdown(&b.path.display().to_string())
-                ));
+        for section in sections.sections() {
+            match section {
+                Section::AOnly => push_markdown_entries(
+                    &mut md,
+                    &labels.a_only,
+                    labels,
+                    &result.a_only,
+                    result.omitted(Section::AOnly),
+                ),
+                Section::BOnly => push_markdown_entries(
+                    &mut md,
+                    &labels.b_only,
+                    labels,
+                    &result.b_only,
+                    result.omitted(Section::BOnly),
+                ),
+                Section::Modified => push_markdown_pairs(
+                    &mut md,
+                    &labels.modified,
+                    &labels.no_entries,
+                    labels,
+                    &result.modified,
+                    &[],
+                    &result.mismatch_reasons,
+                    result.omitted(Section::Modified),
+                    "!=",
+                ),
+                Section::Both => push_markdown_pairs(
+                    &mut md,
+                    &labels.both,
+                    &labels.no_matching_entries,
+                    labels,
+                    &result.both,
+                    &result.identical_subtrees,
+                    &[],
+                    result.omitted(Section::Both),
+                    "==",
+                ),
+            }
+            if Some(*section) == renamed_anchor(sections) && !result.renamed.is_empty() {
+                push_markdown_pairs(
+                    &mut md,
+                    &labels.renamed,
+                    &labels.no_entries,
+                    labels,
+                    &result.renamed,
+                    &[],
+                    &[],
+                    0,
+                    "->",
+                );
             }
-            md.push('\n');
         }
+        md.push_str(&format!("---\n\n{}\n", labels.stats(&result.stats)));
 
         md
     }
 }
 
+/// Appends a Markdown section listing one-sided entries, followed by a note
+/// on the `omitted` entries that were counted but not kept.
+fn push_markdown_entries(
+    md: &mut String,
+    title: &str,
+    labels: &Labels,
+    entries: &[Entry],
+    omitted: usize,
+) {
+    md.push_str(&format!("## {}\n\n", title));
+    if entries.is_empty() && omitted == 0 {
+        md.push_str(&format!("*{}*\n\n", labels.no_entries));
+        return;
+    }
+    for entry in entries {
+        let indicator = kind_suffix(&entry.kind);
+        md.push_str(&format!(
+            "- `{}{}`{}\n",
+            escape_markdown(&entry.path.display().to_string()),
+            escape_markdown(&indicator),
+            markdown_nested_note(labels, &[entry])
+        ));
+    }
+    push_markdown_more(md, labels, omitted);
+    md.push('\n');
+}
+
+/// Appends a Markdown section listing same-path pairs joined by `separator`,
+/// followed by a note on the `omitted` pairs that were counted but not kept.
+/// Directories of `subtrees` are listed once, with the number of files below
+/// them.
+#[allow(clippy::too_many_arguments)]
+fn push_markdown_pairs(
+    md: &mut String,
+    title: &str,
+    empty: &str,
+    labels: &Labels,
+    pairs: &[(Entry, Entry)],
+    subtrees: &[IdenticalSubtree],
+    reasons: &[(PathBuf, MismatchReason)],
+    omitted: usize,
+    separator: &str,
+) {
+    md.push_str(&format!("## {}\n\n", title));
+    if pairs.is_empty() && omitted == 0 {
+        md.push_str(&format!("*{}*\n\n", empty));
+        return;
+    }
+    for (a, b) in pairs {
+        if let Some(subtree) = find_identical_subtree(subtrees, &a.path) {
+            md.push_str(&format!(
+                "- `{}/` *({})*\n",
+                escape_markdown(&a.path.display().to_string()),
+                escape_markdown(&labels.identical(subtree.files))
+            ));
+            continue;
+        }
+        md.push_str(&format!(
+            "- `{}{}` {} `{}{}`{}{}\n",
+            escape_markdown(&a.path.display().to_string()),
+            escape_markdown(&target_suffix(&a.kind)),
+            separator,
+            escape_markdown(&b.path.display().to_string()),
+            escape_markdown(&target_suffix(&b.kind)),
+            markdown_nested_note(labels, &[a, b]),
+            markdown_reason_note(labels, reasons, &a.path)
+        ));
+    }
+    push_markdown_more(md, labels, omitted);
+    md.push('\n');
+}
+
+/// Returns ` — *reason*` for the pair at `path` if `reasons` has one.
+fn markdown_reason_note(
+    labels: &Labels,
+    reasons: &[(PathBuf, MismatchReason)],
+    path: &Path,
+) -> String {
+    match find_mismatch_reason(reasons, path) {
+        Some(reason) => format!(" — *{}*", escape_markdown(&labels.mismatch_reason(reason))),
+        None => String::new(),
+    }
+}
+
+/// Returns ` *(nested repository)*` if either entry is a nested repository.
+fn markdown_nested_note(labels: &Labels, entries: &[&Entry]) -> String {
+    if entries.iter().any(|entry| entry.nested_repository) {
+        format!(" *({})*", escape_markdown(&labels.nested_repository))
+    } else {
+        String::new()
+    }
+}
+
+/// Appends the note on `omitted` entries, if any.
+fn push_markdown_more(md: &mut String, labels: &Labels, omitted: usize) {
+    if omitted > 0 {
+        md.push_str(&format!("- *{}*\n", labels.more(omitted)));
+    }
+}
+
 fn escape_markdown(s: &str) -> String {
     s.replace('\\', r"\\")
         .replace('`', r"\`")
@@ -426,31 +1878,74 @@ fn escape_markdown(s: &str) -> String {
         .replace('_', r"\_")
 }
 
+/// Returns the note marking `path` as a hard link, such as "hard link 2",
+/// if it shares its inode with another file of `group`.
+fn hard_link_note(
+    group: &FlatContentGroup,
+    in_a: bool,
+    path: &Path,
+    flat: &FlatLabels,
+) -> Option<String> {
+    group
+        .hardlink_group(in_a, path)
+        .map(|number| format!("{} {}", flat.hard_link, number))
+}
+
+/// Returns the badge marking `path` as a hard link, or an empty string.
+fn hard_link_badge(group: &FlatContentGroup, in_a: bool, path: &Path, flat: &FlatLabels) -> String {
+    hard_link_note(group, in_a, path, flat)
+        .map(|note| {
+            format!(
+                r#"<span class="badge badge-link">{}</span>"#,
+                escape_html(&note)
+            )
+        })
+        .unwrap_or_default()
+}
+
 /// Formatter for flat mode comparison results (text format).
 ///
 /// Groups files by content hash and shows duplicate/moved files.
 pub struct FlatTextFormatter;
 
+impl FlatFormatter for FlatTextFormatter {
+    fn format(&self, result: &FlatComparisonResult) -> String {
+        self.format_with_labels(result, &Labels::default())
+    }
+
+    fn format_to_with_labels(
+        &self,
+        result: &FlatComparisonResult,
+        labels: &Labels,
+        w: &mut dyn io::Write,
+    ) -> io::Result<()> {
+        w.write_all(self.format_with_labels(result, labels).as_bytes())
+    }
+}
+
 impl FlatTextFormatter {
-    /// Formats the flat comparison result into a string representation.
-    pub fn format(&self, result: &FlatComparisonResult) -> String {
+    /// Formats the flat comparison result using the given labels.
+    pub fn format_with_labels(&self, result: &FlatComparisonResult, labels: &Labels) -> String {
+        let flat = &labels.flat;
         let mut output = String::new();
 
         // Summary section
-        output.push_str("Flat Mode Comparison Summary\n");
+        output.push_str(&flat.summary);
+        output.push('\n');
         output.push_str(&"=".repeat(50));
         output.push('\n');
-        output.push_str(&format!("Files in directory A: {}\n", result.total_files_a));
-        output.push_str(&format!("Files in directory B: {}\n", result.total_files_b));
-        output.push_str(&format!(
-            "Unique content hashes: {}\n",
-            result.unique_hashes
-        ));
-        output.push_str(&format!(
-            "Duplicate content groups: {}\n",
-            result.duplicate_count
-        ));
+        for (name, value) in [
+            (&flat.files_in_a, result.total_files_a),
+            (&flat.files_in_b, result.total_files_b),
+            (&flat.unique_hashes, result.unique_hashes),
+            (&flat.duplicate_groups, result.duplicate_count),
+        ] {
+            output.push_str(&format!("{}: {}\n", name, value));
+        }
         output.push('\n');
+        if let Some(report) = &result.chunk_dedupe {
+            push_text_chunk_dedupe(&mut output, report);
+        }
 
         // Group details
         for group in &result.groups {
@@ -460,21 +1955,24 @@ impl FlatTextFormatter {
 
             // Header line with hash and metadata
             let status = if is_duplicate {
-                "[DUPLICATE]"
+                &flat.duplicate
             } else if in_a && in_b {
-                "[MATCHED]"
+                &flat.matched
             } else if in_a {
-                "[A-ONLY]"
+                &flat.a_only
             } else {
-                "[B-ONLY]"
+                &flat.b_only
             };
 
             output.push_str(&format!(
-                "Hash: {} {} ({} bytes, {} files)\n",
+                "{}: {} [{}] ({} {}, {} {})\n",
+                flat.hash,
                 &group.hash[..16.min(group.hash.len())],
                 status,
                 group.size,
-                group.file_count
+                flat.bytes,
+                group.file_count,
+                flat.files
             ));
             output.push_str(&"-".repeat(50));
             output.push('\n');
@@ -483,28 +1981,40 @@ impl FlatTextFormatter {
             for path in &group.files_in_a {
                 if in_b {
                     output.push_str(&format!(
-                        "  [A] {} -> (moved/copied to B)\n",
-                        path.display()
+                        "  [A] {} -> ({})",
+                        path.display(),
+                        flat.moved_to_b
                     ));
                 } else {
-                    output.push_str(&format!("  [A] {}\n", path.display()));
+                    output.push_str(&format!("  [A] {}", path.display()));
+                }
+                if let Some(note) = hard_link_note(group, true, path, flat) {
+                    output.push_str(&format!(" ({})", note));
                 }
+                output.push('\n');
             }
 
             // Files in B
             for path in &group.files_in_b {
                 if in_a {
                     output.push_str(&format!(
-                        "  [B] {} <- (moved/copied from A)\n",
-                        path.display()
+                        "  [B] {} <- ({})",
+                        path.display(),
+                        flat.moved_from_a
                     ));
                 } else {
-                    output.push_str(&format!("  [B] {}\n", path.display()));
+                    output.push_str(&format!("  [B] {}", path.display()));
                 }
+                if let Some(note) = hard_link_note(group, false, path, flat) {
+                    output.push_str(&format!(" ({})", note));
+                }
+                output.push('\n');
             }
 
             output.push('\n');
         }
+        output.push_str(&labels.stats(&result.stats));
+        output.push('\n');
 
         output
     }
@@ -513,18 +2023,29 @@ impl FlatTextFormatter {
 /// Formatter for flat mode comparison results (HTML format).
 pub struct FlatHtmlFormatter;
 
+impl FlatFormatter for FlatHtmlFormatter {
+    fn format(&self, result: &FlatComparisonResult) -> String {
+        self.format_with_labels(result, &Labels::default())
+    }
+
+    fn format_to_with_labels(
+        &self,
+        result: &FlatComparisonResult,
+        labels: &Labels,
+        w: &mut dyn io::Write,
+    ) -> io::Result<()> {
+        w.write_all(self.format_with_labels(result, labels).as_bytes())
+    }
+}
+
 impl FlatHtmlFormatter {
-    /// Formats the flat comparison result into an HTML representation.
-    pub fn format(&self, result: &FlatComparisonResult) -> String {
+    /// Formats the flat comparison result using the given labels.
+    pub fn format_with_labels(&self, result: &FlatComparisonResult, labels: &Labels) -> String {
+        let flat = &labels.flat;
         let mut html = String::new();
 
-        html.push_str(r#"<!DOCTYPE html>
-<html lang="en">
-<head>
-    <meta charset="UTF-8">
-    <meta name="viewport" content="width=device-width, initial-scale=1.0">
-    <title>Flat Mode Directory Comparison Report</title>
-    <style>
+        push_html_head(&mut html, &labels.lang, &flat.document_title);
+        html.push_str(r#"    <style>
         body { font-family: -apple-system, BlinkMacSystemFont, 'Segoe UI', Roboto, sans-serif; margin: 20px; background: #f5f5f5; }
         .container { max-width: 1200px; margin: 0 auto; background: white; padding: 20px; border-radius: 8px; box-shadow: 0 2px 4px rgba(0,0,0,0.1); }
         h1 { color: #333; border-bottom: 2px solid #4a90d9; padding-bottom: 10px; }
@@ -550,46 +2071,32 @@ impl FlatHtmlFormatter {
         .badge-b { background: #28a745; color: white; }
         .badge-moved { background: #17a2b8; color: white; }
         .badge-dup { background: #ffc107; color: #333; }
+        .badge-link { background: #6c757d; color: white; margin-left: 8px; }
+        .stats { color: #666; font-size: 0.9em; border-top: 1px solid #eee; padding-top: 10px; }
+        .chunk-dedupe { margin-bottom: 30px; }
+        .chunk-dedupe table { border-collapse: collapse; width: 100%; }
+        .chunk-dedupe th, .chunk-dedupe td { text-align: left; padding: 6px 10px; border-bottom: 1px solid #eee; }
     </style>
 </head>
 <body>
     <div class="container">
-        <h1>Flat Mode Comparison Report</h1>
-        <div class="summary">
-            <div class="summary-box">
-                <div class="count">"#);
-        html.push_str(&result.total_files_a.to_string());
-        html.push_str(
-            r#"</div>
-                <div class="label">Files in A</div>
-            </div>
-            <div class="summary-box">
-                <div class="count">"#,
-        );
-        html.push_str(&result.total_files_b.to_string());
-        html.push_str(
-            r#"</div>
-                <div class="label">Files in B</div>
-            </div>
-            <div class="summary-box">
-                <div class="count">"#,
-        );
-        html.push_str(&result.unique_hashes.to_string());
-        html.push_str(
-            r#"</div>
-                <div class="label">Unique Hashes</div>
-            </div>
-            <div class="summary-box">
-                <div class="count">"#,
-        );
-        html.push_str(&result.duplicate_count.to_string());
-        html.push_str(
-            r#"</div>
-                <div class="label">Duplicates</div>
-            </div>
-        </div>
-"#,
-        );
+"#);
+        html.push_str(&format!(
+            "        <h1>{}</h1>\n        <div class=\"summary\">\n",
+            escape_html(&flat.report_title)
+        ));
+        for (label, count) in [
+            (&flat.files_a_short, result.total_files_a),
+            (&flat.files_b_short, result.total_files_b),
+            (&flat.unique_hashes_short, result.unique_hashes),
+            (&flat.duplicates_short, result.duplicate_count),
+        ] {
+            push_html_summary_box(&mut html, "summary-box", label, count);
+        }
+        html.push_str("        </div>\n");
+        if let Some(report) = &result.chunk_dedupe {
+            push_html_chunk_dedupe(&mut html, report);
+        }
 
         for group in &result.groups {
             let in_a = !group.files_in_a.is_empty();
@@ -606,29 +2113,33 @@ impl FlatHtmlFormatter {
                 "b-only"
             };
 
-            let status_badge = if is_duplicate {
-                r#"<span class="badge badge-dup">DUPLICATE</span>"#
+            let (badge_class, status) = if is_duplicate {
+                ("badge-dup", &flat.duplicate)
             } else if in_a && in_b {
-                r#"<span class="badge badge-moved">MOVED</span>"#
+                ("badge-moved", &flat.moved)
             } else if in_a {
-                r#"<span class="badge badge-a">A-ONLY</span>"#
+                ("badge-a", &flat.a_only)
             } else {
-                r#"<span class="badge badge-b">B-ONLY</span>"#
+                ("badge-b", &flat.b_only)
             };
 
             html.push_str(&format!(
                 r#"        <div class="hash-group">
             <div class="hash-header {}">
-                <div class="hash-value">{}{}</div>
-                <div class="hash-meta">Size: {} bytes | {} file(s)</div>
+                <div class="hash-value"><span class="badge {}">{}</span>{}</div>
+                <div class="hash-meta">{}: {} {} | {} {}</div>
             </div>
             <ul class="file-list">
 "#,
                 header_class,
-                status_badge,
+                badge_class,
+                escape_html(status),
                 escape_html(&group.hash),
+                escape_html(&flat.size),
                 group.size,
-                group.file_count
+                escape_html(&flat.bytes),
+                group.file_count,
+                escape_html(&flat.files_html)
             ));
 
             for path in &group.files_in_a {
@@ -638,10 +2149,11 @@ impl FlatHtmlFormatter {
                     ""
                 };
                 html.push_str(&format!(
-                    r#"                <li class="file-a"><span class="badge badge-a">A</span>{}{}</li>
+                    r#"                <li class="file-a"><span class="badge badge-a">A</span>{}{}{}</li>
 "#,
                     escape_html(&path.display().to_string()),
-                    moved_indicator
+                    moved_indicator,
+                    hard_link_badge(group, true, path, flat)
                 ));
             }
 
@@ -652,10 +2164,11 @@ impl FlatHtmlFormatter {
                     ""
                 };
                 html.push_str(&format!(
-                    r#"                <li class="file-b"><span class="badge badge-b">B</span>{}{}</li>
+                    r#"                <li class="file-b"><span class="badge badge-b">B</span>{}{}{}</li>
 "#,
                     escape_html(&path.display().to_string()),
-                    moved_indicator
+                    moved_indicator,
+                    hard_link_badge(group, false, path, flat)
                 ));
             }
 
@@ -666,6 +2179,7 @@ impl FlatHtmlFormatter {
             );
         }
 
+        push_html_stats(&mut html, labels, &result.stats);
         html.push_str(
             r#"    </div>
 </body>
@@ -680,34 +2194,46 @@ impl FlatHtmlFormatter {
 /// Formatter for flat mode comparison results (Markdown format).
 pub struct FlatMarkdownFormatter;
 
+impl FlatFormatter for FlatMarkdownFormatter {
+    fn format(&self, result: &FlatComparisonResult) -> String {
+        self.format_with_labels(result, &Labels::default())
+    }
+
+    fn format_to_with_labels(
+        &self,
+        result: &FlatComparisonResult,
+        labels: &Labels,
+        w: &mut dyn io::Write,
+    ) -> io::Result<()> {
+        w.write_all(self.format_with_labels(result, labels).as_bytes())
+    }
+}
+
 impl FlatMarkdownFormatter {
-    /// Formats the flat comparison result into a Markdown representation.
-    pub fn format(&self, result: &FlatComparisonResult) -> String {
+    /// Formats the flat comparison result using the given labels.
+    pub fn format_with_labels(&self, result: &FlatComparisonResult, labels: &Labels) -> String {
+        let flat = &labels.flat;
         let mut md = String::new();
 
-        md.push_str("# Flat Mode Comparison Report\n\n");
+        md.push_str(&format!("# {}\n\n", flat.report_title));
 
-        md.push_str("## Summary\n\n");
-        md.push_str("| Metric | Value |\n");
+        md.push_str(&format!("## {}\n\n", labels.summary));
+        md.push_str(&format!("| {} | {} |\n", flat.metric, flat.value));
         md.push_str("|--------|-------|\n");
-        md.push_str(&format!(
-            "| Files in directory A | {} |\n",
-            result.total_files_a
-        ));
-        md.push_str(&format!(
-            "| Files in directory B | {} |\n",
-            result.total_files_b
-        ));
-        md.push_str(&format!(
-            "| Unique content hashes | {} |\n",
-            result.unique_hashes
-        ));
-        md.push_str(&format!(
-            "| Duplicate groups | {} |\n\n",
-            result.duplicate_count
-        ));
+        for (name, value) in [
+            (&flat.files_in_a, result.total_files_a),
+            (&flat.files_in_b, result.total_files_b),
+            (&flat.unique_hashes, result.unique_hashes),
+            (&flat.duplicate_groups_short, result.duplicate_count),
+        ] {
+            md.push_str(&format!("| {} | {} |\n", name, value));
+        }
+        md.push('\n');
+        if let Some(report) = &result.chunk_dedupe {
+            push_markdown_chunk_dedupe(&mut md, report);
+        }
 
-        md.push_str("## Content Groups\n\n");
+        md.push_str(&format!("## {}\n\n", flat.content_groups));
 
         for group in &result.groups {
             let in_a = !group.files_in_a.is_empty();
@@ -715,60 +2241,1549 @@ impl FlatMarkdownFormatter {
             let is_duplicate = group.file_count > 1;
 
             let status = if is_duplicate {
-                "DUPLICATE"
+                &flat.duplicate
             } else if in_a && in_b {
-                "MOVED"
+                &flat.moved
             } else if in_a {
-                "A-ONLY"
+                &flat.a_only
             } else {
-                "B-ONLY"
+                &flat.b_only
             };
 
             md.push_str(&format!(
-                "### Hash: `{}...` ({} - {} bytes, {} files)\n\n",
+                "### {}: `{}...` ({} - {} {}, {} {})\n\n",
+                flat.hash,
                 &group.hash[..16.min(group.hash.len())],
                 status,
                 group.size,
-                group.file_count
+                flat.bytes,
+                group.file_count,
+                flat.files
             ));
 
             if !group.files_in_a.is_empty() {
-                md.push_str("**Directory A:**\n\n");
+                md.push_str(&format!("**{}:**\n\n", flat.directory_a));
                 for path in &group.files_in_a {
                     if in_b {
                         md.push_str(&format!(
-                            "- `{}` *(moved/copied to B)*\n",
-                            escape_markdown(&path.display().to_string())
+                            "- `{}` *({})*",
+                            escape_markdown(&path.display().to_string()),
+                            flat.moved_to_b
                         ));
                     } else {
                         md.push_str(&format!(
-                            "- `{}`\n",
+                            "- `{}`",
                             escape_markdown(&path.display().to_string())
                         ));
                     }
+                    if let Some(note) = hard_link_note(group, true, path, flat) {
+                        md.push_str(&format!(" *({})*", note));
+                    }
+                    md.push('\n');
                 }
                 md.push('\n');
             }
 
             if !group.files_in_b.is_empty() {
-                md.push_str("**Directory B:**\n\n");
+                md.push_str(&format!("**{}:**\n\n", flat.directory_b));
                 for path in &group.files_in_b {
                     if in_a {
                         md.push_str(&format!(
-                            "- `{}` *(moved/copied from A)*\n",
-                            escape_markdown(&path.display().to_string())
+                            "- `{}` *({})*",
+                            escape_markdown(&path.display().to_string()),
+                            flat.moved_from_a
                         ));
                     } else {
                         md.push_str(&format!(
-                            "- `{}`\n",
+                            "- `{}`",
                             escape_markdown(&path.display().to_string())
                         ));
                     }
+                    if let Some(note) = hard_link_note(group, false, path, flat) {
+                        md.push_str(&format!(" *({})*", note));
+                    }
+                    md.push('\n');
                 }
                 md.push('\n');
             }
         }
+        md.push_str(&format!("---\n\n{}\n", labels.stats(&result.stats)));
 
         md
     }
 }
+
+/// Version of the JSON report layout, written as `format_version`.
+///
+/// Bumped whenever keys are added, removed or change meaning; the JSON
+/// Schema of the `schema` feature pins `format_version` to this value.
+pub const JSON_FORMAT_VERSION: u32 = 10;
+
+/// Formatter that outputs JSON for piping into other tools.
+///
+/// The document has a `format_version` (see [`JSON_FORMAT_VERSION`]), a
+/// `counts` object with the number of entries in each category, renamed
+/// pairs included, a `stats`
+/// object with the [`ComparisonStats`] (the elapsed time as `elapsed_ms`),
+/// and five arrays, `a_only`, `b_only`, `both`, `modified` and `renamed`. The
+/// first four hold only the entries kept by
+/// [`ResultDetail`](crate::comparison::ResultDetail), so they can be shorter
+/// than `counts`; `renamed` is always whole and empty unless renames were
+/// detected. Each entry is an object with these
+/// keys, which form a stable contract:
+///
+/// - `path`: path relative to the compared root, as UTF-8 (invalid sequences
+///   are replaced with U+FFFD)
+/// - `kind`: `"file"`, `"directory"` or `"symlink"`; paths never carry a
+///   trailing slash
+/// - `size`: size in bytes, or `null` for directories and symbolic links
+/// - `target`: the link target, only present for symbolic links
+///
+/// Items of `both`, `modified` and `renamed` are objects with an `a` and a
+/// `b` entry.
+/// Items of `both` listing a directory without the entries below it, see
+/// [`ComparisonResult::rollup`], also have `identical_files`, the number of
+/// files below it. Items of `modified` have `reason`, why the comparison
+/// method did not match them: `"kind"`, `"size"`, `"content"`, `"target"`,
+/// `"modified"` or `"unreadable"` (see [`MismatchReason`]). A
+/// `diff_locations` array follows `renamed` in the documents of
+/// [`JsonFormatter::format_with_diff_locations`].
+///
+/// # Output Format
+///
+/// ```json
+/// {
+///   "format_version": 10,
+///   "counts": {"a_only": 1, "b_only": 0, "both": 1, "modified": 0, "renamed": 0},
+///   "stats": {"files_a": 1, "files_b": 1, "dirs_a": 1, "dirs_b": 0, "bytes_a": 3, "bytes_b": 3, "bytes_hashed": 6, "elapsed_ms": 2},
+///   "a_only": [
+///     {"path": "docs", "kind": "directory", "size": null}
+///   ],
+///   "b_only": [],
+///   "both": [
+///     {"a": {"path": "x.txt", "kind": "file", "size": 3}, "b": {"path": "x.txt", "kind": "file", "size": 3}}
+///   ],
+///   "modified": [],
+///   "renamed": []
+/// }
+/// ```
+pub struct JsonFormatter;
+
+impl Formatter for JsonFormatter {
+    fn format(&self, result: &ComparisonResult) -> String {
+        json_report(result, &SectionConfig::default(), None)
+    }
+}
+
+impl JsonFormatter {
+    /// Formats the document with every array empty when `sections` is
+    /// [summary only](SectionConfig::summary_only), so only `counts` and
+    /// `stats` describe the result; the document keeps its layout.
+    ///
+    /// JSON has no sections to choose from; otherwise this is
+    /// [`Formatter::format`].
+    pub fn format_with_sections(
+        &self,
+        result: &ComparisonResult,
+        sections: &SectionConfig,
+    ) -> String {
+        json_report(result, sections, None)
+    }
+
+    /// Formats the document with a trailing `diff_locations` array holding
+    /// the block-level difference `locations` of
+    /// [`locate_differences`](crate::block_diff::locate_differences).
+    ///
+    /// Each item has the `path` of the pair and either `len_a`, `len_b` and
+    /// `regions`, an array of `{"start", "end"}` byte offsets, or `error`
+    /// when the files could not be analyzed.
+    pub fn format_with_diff_locations(
+        &self,
+        result: &ComparisonResult,
+        sections: &SectionConfig,
+        locations: &[(PathBuf, io::Result<BlockDiff>)],
+    ) -> String {
+        json_report(result, sections, Some(locations))
+    }
+}
+
+/// The `reason` value of a modified pair in the JSON report.
+fn json_reason(reason: MismatchReason) -> &'static str {
+    match reason {
+        MismatchReason::KindDiffers => "kind",
+        MismatchReason::SizeDiffers { .. } => "size",
+        MismatchReason::ContentDiffers => "content",
+        MismatchReason::TargetDiffers => "target",
+        MismatchReason::ModifiedDiffers => "modified",
+        MismatchReason::Unreadable => "unreadable",
+    }
+}
+
+/// Renders the JSON document of [`JsonFormatter`].
+fn json_report(
+    result: &ComparisonResult,
+    sections: &SectionConfig,
+    diff_locations: Option<&[(PathBuf, io::Result<BlockDiff>)]>,
+) -> String {
+    // A summary keeps the arrays, empty
+    let limit = if sections.is_summary_only() {
+        0
+    } else {
+        usize::MAX
+    };
+    let mut json = String::from("{\n");
+
+    json.push_str(&format!("  \"format_version\": {},\n", JSON_FORMAT_VERSION));
+    json.push_str(&format!(
+        "  \"counts\": {{\"a_only\": {}, \"b_only\": {}, \"both\": {}, \"modified\": {}, \"renamed\": {}}},\n",
+        result.count(Section::AOnly),
+        result.count(Section::BOnly),
+        result.count(Section::Both),
+        result.count(Section::Modified),
+        result.renamed.len()
+    ));
+    json.push_str(&format!("  \"stats\": {},\n", json_stats(&result.stats)));
+    json.push_str("  \"a_only\": ");
+    push_json_array(&mut json, result.a_only.iter().take(limit).map(json_entry));
+    json.push_str(",\n  \"b_only\": ");
+    push_json_array(&mut json, result.b_only.iter().take(limit).map(json_entry));
+    json.push_str(",\n  \"both\": ");
+    push_json_array(
+        &mut json,
+        result.both.iter().take(limit).map(|(a, b)| {
+            match find_identical_subtree(&result.identical_subtrees, &a.path) {
+                Some(subtree) => format!(
+                    "{{\"a\": {}, \"b\": {}, \"identical_files\": {}}}",
+                    json_entry(a),
+                    json_entry(b),
+                    subtree.files
+                ),
+                None => format!("{{\"a\": {}, \"b\": {}}}", json_entry(a), json_entry(b)),
+            }
+        }),
+    );
+    json.push_str(",\n  \"modified\": ");
+    push_json_array(
+        &mut json,
+        result.modified.iter().take(limit).map(|(a, b)| {
+            match find_mismatch_reason(&result.mismatch_reasons, &a.path) {
+                Some(reason) => format!(
+                    "{{\"a\": {}, \"b\": {}, \"reason\": \"{}\"}}",
+                    json_entry(a),
+                    json_entry(b),
+                    json_reason(reason)
+                ),
+                None => format!("{{\"a\": {}, \"b\": {}}}", json_entry(a), json_entry(b)),
+            }
+        }),
+    );
+    json.push_str(",\n  \"renamed\": ");
+    push_json_array(
+        &mut json,
+        result
+            .renamed
+            .iter()
+            .take(limit)
+            .map(|(a, b)| format!("{{\"a\": {}, \"b\": {}}}", json_entry(a), json_entry(b))),
+    );
+    if let Some(locations) = diff_locations {
+        json.push_str(",\n  \"diff_locations\": ");
+        push_json_array(
+            &mut json,
+            locations
+                .iter()
+                .map(|(path, diff)| json_diff_location(path, diff)),
+        );
+    }
+    json.push_str("\n}\n");
+
+    json
+}
+
+/// Formats the block-level difference locations of one pair as a JSON
+/// object.
+fn json_diff_location(path: &Path, diff: &io::Result<BlockDiff>) -> String {
+    let path = escape_json(&path.to_string_lossy());
+    match diff {
+        Ok(diff) => {
+            let regions: Vec<String> = diff
+                .regions
+                .iter()
+                .map(|region| format!("{{\"start\": {}, \"end\": {}}}", region.start, region.end))
+                .collect();
+            format!(
+                "{{\"path\": \"{}\", \"len_a\": {}, \"len_b\": {}, \"regions\": [{}]}}",
+                path,
+                diff.len_a,
+                diff.len_b,
+                regions.join(", ")
+            )
+        }
+        Err(e) => format!(
+            "{{\"path\": \"{}\", \"error\": \"{}\"}}",
+            path,
+            escape_json(&e.to_string())
+        ),
+    }
+}
+
+/// Formatter for flat mode comparison results (JSON format).
+///
+/// The document carries a `format_version` (see [`JSON_FORMAT_VERSION`]),
+/// the summary counts `total_files_a`, `total_files_b`,
+/// `unique_hashes` and `duplicate_count`, a `stats` object as in
+/// [`JsonFormatter`], plus a `groups` array whose items
+/// have `hash`, `size`, `file_count`, `files_in_a` and `files_in_b` (arrays
+/// of UTF-8 paths) and `hard_links`, an array of the group's hard-linked files
+/// as objects with `side` (`a` or `b`), `path` and `hardlink_group`. These
+/// keys form a stable contract. A `chunk_dedupe`
+/// object with the [`ChunkDedupeReport`] follows `stats` when the analysis
+/// ran.
+pub struct FlatJsonFormatter;
+
+impl FlatFormatter for FlatJsonFormatter {
+    fn format(&self, result: &FlatComparisonResult) -> String {
+        let mut json = String::from("{\n");
+
+        json.push_str(&format!("  \"format_version\": {},\n", JSON_FORMAT_VERSION));
+        json.push_str(&format!("  \"total_files_a\": {},\n", result.total_files_a));
+        json.push_str(&format!("  \"total_files_b\": {},\n", result.total_files_b));
+        json.push_str(&format!("  \"unique_hashes\": {},\n", result.unique_hashes));
+        json.push_str(&format!(
+            "  \"duplicate_count\": {},\n",
+            result.duplicate_count
+        ));
+        json.push_str(&format!("  \"stats\": {},\n", json_stats(&result.stats)));
+        if let Some(report) = &result.chunk_dedupe {
+            json.push_str(&format!(
+                "  \"chunk_dedupe\": {},\n",
+                json_chunk_dedupe(report)
+            ));
+        }
+        json.push_str("  \"groups\": ");
+        push_json_array(
+            &mut json,
+            result.groups.iter().map(|group| {
+                format!(
+                    "{{\"hash\": \"{}\", \"size\": {}, \"file_count\": {}, \"files_in_a\": [{}], \"files_in_b\": [{}], \"hard_links\": [{}]}}",
+                    escape_json(&group.hash),
+                    group.size,
+                    group.file_count,
+                    json_paths(&group.files_in_a),
+                    json_paths(&group.files_in_b),
+                    json_hard_links(&group.hard_links)
+                )
+            }),
+        );
+        json.push_str("\n}\n");
+
+        json
+    }
+}
+
+/// Formatter for `--format html-bundle`: one self-contained page holding the
+/// hierarchy and the flat result of the same run.
+///
+/// Both results are embedded as the documents of [`JsonFormatter`] and
+/// [`FlatJsonFormatter`], in `<script type="application/json">` elements
+/// with the ids `hierarchy-data` and `flat-data`. Inline script switches
+/// between a "Hierarchy" tab, listing each category as a collapsible
+/// directory tree, and a "Content groups" tab, listing the flat groups by
+/// status as the GUI does. The page makes no external requests.
+///
+/// The static markup carries one `data-section` container per category
+/// (`a_only`, `b_only`, `modified`, `both`) and one `data-status` container
+/// per group status (`a-only`, `b-only`, `moved`, `duplicate`); the script
+/// fills them from the embedded data.
+///
+/// # Usage
+///
+/// ```
+/// use dir_compare_core::output::BundleHtmlFormatter;
+///
+/// let formatter = BundleHtmlFormatter;
+/// // let html = formatter.format(&hierarchy_result, &flat_result);
+/// ```
+pub struct BundleHtmlFormatter;
+
+impl BundleHtmlFormatter {
+    /// Formats both results into one HTML document.
+    pub fn format(&self, hierarchy: &ComparisonResult, flat: &FlatComparisonResult) -> String {
+        let mut html = String::new();
+
+        html.push_str(r#"<!DOCTYPE html>
+<html lang="en">
+<head>
+    <meta charset="UTF-8">
+    <meta name="viewport" content="width=device-width, initial-scale=1.0">
+    <title>Directory Comparison Bundle</title>
+    <style>
+        body { font-family: -apple-system, BlinkMacSystemFont, 'Segoe UI', Roboto, sans-serif; margin: 20px; background: #f5f5f5; }
+        .container { max-width: 1200px; margin: 0 auto; background: white; padding: 20px; border-radius: 8px; box-shadow: 0 2px 4px rgba(0,0,0,0.1); }
+        h1 { color: #333; border-bottom: 2px solid #4a90d9; padding-bottom: 10px; }
+        .tabs { display: flex; gap: 8px; margin-bottom: 20px; }
+        .tab { border: 1px solid #4a90d9; background: white; color: #4a90d9; padding: 8px 16px; border-radius: 4px; cursor: pointer; font-size: 1em; }
+        .tab.active { background: #4a90d9; color: white; }
+        .view { display: none; }
+        .view.active { display: block; }
+        .summary { display: grid; grid-template-columns: repeat(4, 1fr); gap: 20px; margin-bottom: 30px; }
+        .summary-box { background: #f8f9fa; padding: 15px; border-radius: 6px; text-align: center; border-left: 4px solid #4a90d9; }
+        .summary-box.a-only { border-left-color: #dc3545; }
+        .summary-box.b-only { border-left-color: #28a745; }
+        .summary-box.both { border-left-color: #6c757d; }
+        .summary-box.modified { border-left-color: #fd7e14; }
+        .summary-box.moved { border-left-color: #17a2b8; }
+        .summary-box.duplicate { border-left-color: #ffc107; }
+        .count { font-size: 2em; font-weight: bold; color: #333; }
+        .label { color: #666; font-size: 0.9em; }
+        .section { margin-bottom: 30px; }
+        .section h2 { color: #444; border-bottom: 1px solid #eee; padding-bottom: 8px; }
+        .tree, .tree ul { list-style: none; padding-left: 18px; margin: 0; }
+        .tree li { padding: 3px 0; }
+        .tree summary { cursor: pointer; }
+        .dir { color: #0066cc; }
+        .file { color: #333; }
+        .symlink { color: #6f42c1; }
+        .empty { color: #999; font-style: italic; }
+        .more { color: #666; font-style: italic; margin: 8px 12px; }
+        .hash-group { margin-bottom: 12px; border: 1px solid #ddd; border-radius: 6px; overflow: hidden; }
+        .hash-header { background: #f8f9fa; padding: 10px 15px; cursor: pointer; font-family: monospace; }
+        .hash-header.duplicate { background: #fff3cd; border-left: 4px solid #ffc107; }
+        .hash-header.matched { background: #d1ecf1; border-left: 4px solid #17a2b8; }
+        .hash-header.a-only { background: #f8d7da; border-left: 4px solid #dc3545; }
+        .hash-header.b-only { background: #d4edda; border-left: 4px solid #28a745; }
+        .file-list { list-style: none; padding: 0; margin: 0; }
+        .file-list li { padding: 8px 15px; border-top: 1px solid #eee; }
+        .file-a { color: #dc3545; }
+        .file-b { color: #28a745; }
+        .badge { display: inline-block; padding: 2px 8px; border-radius: 4px; font-size: 0.75em; font-weight: bold; margin-right: 8px; color: white; }
+        .badge-a { background: #dc3545; }
+        .badge-b { background: #28a745; }
+        .stats { color: #666; font-size: 0.9em; border-top: 1px solid #eee; padding-top: 10px; }
+    </style>
+</head>
+<body>
+    <div class="container">
+        <h1>Directory Comparison Bundle</h1>
+        <div class="tabs">
+            <button class="tab active" data-tab="hierarchy">Hierarchy</button>
+            <button class="tab" data-tab="flat">Content groups</button>
+        </div>
+        <div class="view active" id="hierarchy">
+            <div class="summary">
+"#);
+        for (class, label, count) in [
+            ("a-only", "A-only", hierarchy.count(Section::AOnly)),
+            ("b-only", "B-only", hierarchy.count(Section::BOnly)),
+            ("modified", "Modified", hierarchy.count(Section::Modified)),
+            ("both", "Both", hierarchy.count(Section::Both)),
+        ] {
+            push_bundle_summary_box(&mut html, class, label, count);
+        }
+        html.push_str(
+            r#"            </div>
+"#,
+        );
+        for (key, title) in [
+            ("a_only", "A-only"),
+            ("b_only", "B-only"),
+            ("modified", "Modified"),
+            ("both", "Both"),
+        ] {
+            push_bundle_section(&mut html, "data-section", key, title);
+        }
+        push_html_stats(&mut html, &Labels::default(), &hierarchy.stats);
+        html.push_str(
+            r#"        </div>
+        <div class="view" id="flat">
+            <div class="summary">
+"#,
+        );
+        for (class, label, count) in [
+            ("", "Files in A", flat.total_files_a),
+            ("", "Files in B", flat.total_files_b),
+            ("", "Unique Hashes", flat.unique_hashes),
+            ("duplicate", "Duplicates", flat.duplicate_count),
+        ] {
+            push_bundle_summary_box(&mut html, class, label, count);
+        }
+        html.push_str(
+            r#"            </div>
+"#,
+        );
+        for (status, title) in [
+            ("a-only", "A-only"),
+            ("b-only", "B-only"),
+            ("moved", "Moved"),
+            ("duplicate", "Duplicates"),
+        ] {
+            push_bundle_section(&mut html, "data-status", status, title);
+        }
+        push_html_stats(&mut html, &Labels::default(), &flat.stats);
+        html.push_str(
+            r#"        </div>
+    </div>
+    <script type="application/json" id="hierarchy-data">
+"#,
+        );
+        html.push_str(&escape_script_json(&JsonFormatter.format(hierarchy)));
+        html.push_str(
+            r#"    </script>
+    <script type="application/json" id="flat-data">
+"#,
+        );
+        html.push_str(&escape_script_json(&FlatJsonFormatter.format(flat)));
+        html.push_str("    </script>\n    <script>\n");
+        html.push_str(BUNDLE_SCRIPT);
+        html.push_str(
+            r#"    </script>
+</body>
+</html>
+"#,
+        );
+
+        html
+    }
+
+    /// Writes both results to `w`; see [`Formatter::format_to`].
+    pub fn format_to(
+        &self,
+        hierarchy: &ComparisonResult,
+        flat: &FlatComparisonResult,
+        w: &mut dyn io::Write,
+    ) -> io::Result<()> {
+        w.write_all(self.format(hierarchy, flat).as_bytes())
+    }
+}
+
+/// Tab switching and rendering of the embedded data of
+/// [`BundleHtmlFormatter`].
+const BUNDLE_SCRIPT: &str = r#"(function () {
+    var hierarchy = JSON.parse(document.getElementById('hierarchy-data').textContent);
+    var flat = JSON.parse(document.getElementById('flat-data').textContent);
+
+    function el(tag, className, text) {
+        var node = document.createElement(tag);
+        if (className) node.className = className;
+        if (text !== undefined) node.textContent = text;
+        return node;
+    }
+
+    document.querySelectorAll('.tab').forEach(function (tab) {
+        tab.addEventListener('click', function () {
+            document.querySelectorAll('.tab, .view').forEach(function (node) {
+                node.classList.remove('active');
+            });
+            tab.classList.add('active');
+            document.getElementById(tab.dataset.tab).classList.add('active');
+        });
+    });
+
+    function label(entry) {
+        return entry.kind === 'symlink' ? ' -> ' + entry.target : '';
+    }
+
+    function renderTree(items, list) {
+        var root = { dirs: {}, leaves: [] };
+        items.forEach(function (item) {
+            var parts = item.path.split(/[\\/]/);
+            var node = root;
+            parts.slice(0, -1).forEach(function (part) {
+                node = node.dirs[part] = node.dirs[part] || { dirs: {}, leaves: [] };
+            });
+            var name = parts[parts.length - 1];
+            if (item.kind === 'directory') {
+                node.dirs[name] = node.dirs[name] || { dirs: {}, leaves: [] };
+            } else {
+                node.leaves.push({ name: name, item: item });
+            }
+        });
+        (function render(node, ul) {
+            Object.keys(node.dirs).sort().forEach(function (name) {
+                var li = el('li');
+                var details = el('details');
+                details.open = true;
+                details.appendChild(el('summary', 'dir', name + '/'));
+                var children = el('ul');
+                render(node.dirs[name], children);
+                details.appendChild(children);
+                li.appendChild(details);
+                ul.appendChild(li);
+            });
+            node.leaves.sort(function (x, y) { return x.name < y.name ? -1 : 1; });
+            node.leaves.forEach(function (leaf) {
+                ul.appendChild(el('li', leaf.item.kind, leaf.name + leaf.item.text));
+            });
+        })(root, list);
+    }
+
+    ['a_only', 'b_only', 'modified', 'both'].forEach(function (key) {
+        var container = document.querySelector('[data-section="' + key + '"] .content');
+        var items = hierarchy[key].map(function (item) {
+            if (item.a) {
+                var separator = key === 'modified' ? ' != ' : ' == ';
+                return { path: item.a.path, kind: item.a.kind, text: label(item.a) + separator + item.b.path + label(item.b) };
+            }
+            return { path: item.path, kind: item.kind, text: label(item) };
+        });
+        if (items.length === 0 && hierarchy.counts[key] === 0) {
+            container.appendChild(el('p', 'empty', 'No entries'));
+            return;
+        }
+        var tree = el('ul', 'tree');
+        renderTree(items, tree);
+        container.appendChild(tree);
+        var omitted = hierarchy.counts[key] - items.length;
+        if (omitted > 0) {
+            container.appendChild(el('p', 'more', omitted + ' more not listed'));
+        }
+    });
+
+    function status(group) {
+        var a = group.files_in_a, b = group.files_in_b;
+        if (a.length === 1 && b.length === 0) return 'a-only';
+        if (a.length === 0 && b.length === 1) return 'b-only';
+        if (a.length === 1 && b.length === 1 && a[0] !== b[0]) return 'moved';
+        return 'duplicate';
+    }
+
+    var headerClass = { 'a-only': 'a-only', 'b-only': 'b-only', 'moved': 'matched', 'duplicate': 'duplicate' };
+    flat.groups.forEach(function (group) {
+        var kind = status(group);
+        var details = el('details', 'hash-group');
+        details.appendChild(el('summary', 'hash-header ' + headerClass[kind],
+            group.hash.slice(0, 16) + ' · ' + group.size + ' bytes · ' + group.file_count + ' file(s)'));
+        var files = el('ul', 'file-list');
+        [['A', 'a', group.files_in_a], ['B', 'b', group.files_in_b]].forEach(function (side) {
+            side[2].forEach(function (path) {
+                var li = el('li', 'file-' + side[1]);
+                li.appendChild(el('span', 'badge badge-' + side[1], side[0]));
+                li.appendChild(document.createTextNode(path));
+                files.appendChild(li);
+            });
+        });
+        details.appendChild(files);
+        document.querySelector('[data-status="' + kind + '"] .content').appendChild(details);
+    });
+    document.querySelectorAll('[data-status] .content').forEach(function (container) {
+        if (!container.hasChildNodes()) container.appendChild(el('p', 'empty', 'No groups'));
+    });
+})();
+"#;
+
+/// Appends a summary box of [`BundleHtmlFormatter`].
+fn push_bundle_summary_box(html: &mut String, class: &str, label: &str, count: usize) {
+    html.push_str(&format!(
+        r#"                <div class="summary-box {}">
+                    <div class="count">{}</div>
+                    <div class="label">{}</div>
+                </div>
+"#,
+        class, count, label
+    ));
+}
+
+/// Appends an empty section of [`BundleHtmlFormatter`], marked with
+/// `attribute="value"` for its script to fill.
+fn push_bundle_section(html: &mut String, attribute: &str, value: &str, title: &str) {
+    html.push_str(&format!(
+        r#"            <div class="section" {}="{}">
+                <h2>{}</h2>
+                <div class="content"></div>
+            </div>
+"#,
+        attribute, value, title
+    ));
+}
+
+/// Makes a JSON document safe to embed in a `<script>` element.
+///
+/// `<` only occurs inside strings, where the escape `\u003c` keeps a path
+/// containing `</script>` or `<!--` from ending the element.
+fn escape_script_json(json: &str) -> String {
+    json.replace('<', "\\u003c")
+}
+
+/// Formatter that outputs CSV for spreadsheets.
+///
+/// Emits a header row followed by one row per entry with the columns
+/// `category,path,kind,size,reason`. `category` is `a_only`, `b_only`,
+/// `both` or `modified`, `kind` is `file` or `directory`, and `size` is
+/// empty for directories. Same-path pairs are listed once with the entry
+/// from A. `reason` is only set on `modified` rows, with the values of the
+/// JSON report's `reason`.
+/// Fields are quoted according to RFC 4180. Only the entries kept by
+/// [`ResultDetail`](crate::comparison::ResultDetail) are listed.
+///
+/// # Output Format
+///
+/// ```text
+/// category,path,kind,size,reason
+/// a_only,docs,directory,,
+/// b_only,"report, final.txt",file,512,
+/// both,common.txt,file,150,
+/// modified,notes.txt,file,98,size
+/// ```
+pub struct CsvFormatter;
+
+impl Formatter for CsvFormatter {
+    fn format(&self, result: &ComparisonResult) -> String {
+        let mut csv = String::from("category,path,kind,size,reason\n");
+
+        // Rows are grouped by category, each sorted by path
+        const ORDER: [Section; 4] = [
+            Section::AOnly,
+            Section::BOnly,
+            Section::Both,
+            Section::Modified,
+        ];
+        let mut rows = result.to_rows();
+        rows.sort_by_key(|row| ORDER.iter().position(|&section| section == row.category));
+        for row in rows {
+            let category = match row.category {
+                Section::AOnly => "a_only",
+                Section::BOnly => "b_only",
+                Section::Both => "both",
+                Section::Modified => "modified",
+            };
+            let kind = match row.kind {
+                EntryKind::Directory => "directory",
+                EntryKind::File => "file",
+                EntryKind::Symlink { .. } => "symlink",
+            };
+            let size = row.size.map(|s| s.to_string()).unwrap_or_default();
+            let reason = match row.category {
+                Section::Modified => find_mismatch_reason(&result.mismatch_reasons, &row.path)
+                    .map(json_reason)
+                    .unwrap_or_default(),
+                _ => "",
+            };
+            csv.push_str(&format!(
+                "{},{},{},{},{}\n",
+                category,
+                escape_csv(&row.path.to_string_lossy()),
+                kind,
+                size,
+                reason
+            ));
+        }
+
+        csv
+    }
+}
+
+/// Formatter for flat mode comparison results (CSV format).
+///
+/// Emits a header row followed by one row per file with the columns
+/// `hash,size,side,path,status`. `side` is `a` or `b`; `status` is
+/// `duplicate`, `matched`, `a_only` or `b_only`, as in the text report.
+pub struct FlatCsvFormatter;
+
+impl FlatFormatter for FlatCsvFormatter {
+    fn format(&self, result: &FlatComparisonResult) -> String {
+        let mut csv = String::from("hash,size,side,path,status\n");
+
+        for group in &result.groups {
+            let in_a = !group.files_in_a.is_empty();
+            let in_b = !group.files_in_b.is_empty();
+            let status = if group.file_count > 1 {
+                "duplicate"
+            } else if in_a && in_b {
+                "matched"
+            } else if in_a {
+                "a_only"
+            } else {
+                "b_only"
+            };
+
+            let files = group
+                .files_in_a
+                .iter()
+                .map(|p| ("a", p))
+                .chain(group.files_in_b.iter().map(|p| ("b", p)));
+            for (side, path) in files {
+                csv.push_str(&format!(
+                    "{},{},{},{},{}\n",
+                    escape_csv(&group.hash),
+                    group.size,
+                    side,
+                    escape_csv(&path.to_string_lossy()),
+                    status
+                ));
+            }
+        }
+
+        csv
+    }
+}
+
+/// Shell that [`FlatScriptFormatter`] writes commands for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ScriptShell {
+    /// POSIX `sh`, with `mkdir -p` and `mv`
+    #[default]
+    Sh,
+    /// PowerShell, with `New-Item` and `Move-Item`
+    PowerShell,
+}
+
+impl std::str::FromStr for ScriptShell {
+    type Err = String;
+
+    /// Parses `sh` or `powershell` (or `pwsh`).
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "sh" => Ok(ScriptShell::Sh),
+            "powershell" | "pwsh" => Ok(ScriptShell::PowerShell),
+            other => Err(format!(
+                "unknown script shell '{}' (expected sh or powershell)",
+                other
+            )),
+        }
+    }
+}
+
+impl ScriptShell {
+    /// Quotes `s` as one word for the shell.
+    ///
+    /// Both shells take single-quoted strings literally, so spaces, `$`,
+    /// line breaks and non-ASCII characters need no escaping. `sh` closes the
+    /// quotes around an escaped `'`; PowerShell doubles `'` and the curly
+    /// single quotes it treats alike.
+    pub fn quote(self, s: &str) -> String {
+        let mut quoted = String::with_capacity(s.len() + 2);
+        quoted.push('\'');
+        for c in s.chars() {
+            match (self, c) {
+                (ScriptShell::Sh, '\'') => quoted.push_str(r"'\''"),
+                (
+                    ScriptShell::PowerShell,
+                    '\'' | '\u{2018}' | '\u{2019}' | '\u{201A}' | '\u{201B}',
+                ) => {
+                    quoted.push(c);
+                    quoted.push(c);
+                }
+                _ => quoted.push(c),
+            }
+        }
+        quoted.push('\'');
+        quoted
+    }
+
+    fn header(self) -> &'static str {
+        match self {
+            ScriptShell::Sh => "#!/bin/sh\nset -e\n",
+            ScriptShell::PowerShell => "$ErrorActionPreference = 'Stop'\n",
+        }
+    }
+
+    fn create_dir(self, dir: &str) -> String {
+        match self {
+            ScriptShell::Sh => format!("mkdir -p -- {}\n", self.quote(dir)),
+            ScriptShell::PowerShell => format!(
+                "New-Item -ItemType Directory -Force -Path {} | Out-Null\n",
+                self.quote(dir)
+            ),
+        }
+    }
+
+    fn move_file(self, from: &str, to: &str) -> String {
+        match self {
+            ScriptShell::Sh => format!("mv -- {} {}\n", self.quote(from), self.quote(to)),
+            ScriptShell::PowerShell => format!(
+                "Move-Item -LiteralPath {} -Destination {}\n",
+                self.quote(from),
+                self.quote(to)
+            ),
+        }
+    }
+}
+
+/// Formatter for `--format script` in flat mode: a shell script that moves
+/// the files of directory A into the layout of directory B.
+///
+/// The script is run from the root of directory A. Every content group with
+/// exactly one file on each side, at different paths, becomes a move.
+/// Moves are ordered so that none overwrites a file another move still has to
+/// take away, and files moving in a cycle are first renamed with a
+/// `.dir-compare-move` suffix. Groups found on both sides with several files
+/// are listed as comments, since any of the files could be moved, as are
+/// moves onto a file of A that stays and paths that are not valid UTF-8.
+/// Files found on one side only are left alone, and emptied directories
+/// are not removed.
+///
+/// # Output Format
+///
+/// ```text
+/// #!/bin/sh
+/// set -e
+/// # Run from the root of directory A.
+/// mkdir -p -- 'docs/2024'
+/// mv -- 'report.txt' 'docs/2024/report.txt'
+/// ```
+#[derive(Debug, Clone, Copy, Default)]
+pub struct FlatScriptFormatter {
+    /// Shell the commands are written for
+    pub shell: ScriptShell,
+}
+
+impl FlatFormatter for FlatScriptFormatter {
+    fn format(&self, result: &FlatComparisonResult) -> String {
+        let shell = self.shell;
+        let mut moves: Vec<(String, String)> = Vec::new();
+        let mut skipped: Vec<String> = Vec::new();
+        let mut ambiguous: Vec<&FlatContentGroup> = Vec::new();
+        for group in &result.groups {
+            match (group.files_in_a.as_slice(), group.files_in_b.as_slice()) {
+                ([], _) | (_, []) => {}
+                ([from], [to]) if from == to => {}
+                ([from], [to]) => match (script_path(from), script_path(to)) {
+                    (Some(from), Some(to)) => moves.push((from, to)),
+                    _ => skipped.push(format!(
+                        "{:?} -> {:?}: not valid UTF-8",
+                        from.to_string_lossy(),
+                        to.to_string_lossy()
+                    )),
+                },
+                (in_a, in_b) => {
+                    if in_b.iter().any(|path| !in_a.contains(path)) {
+                        ambiguous.push(group);
+                    }
+                }
+            }
+        }
+
+        // A move onto a file of A is only safe if that file moves away first;
+        // dropping a move can leave the file at another move's target
+        let files_a: HashSet<String> = result
+            .groups
+            .iter()
+            .flat_map(|group| &group.files_in_a)
+            .filter_map(|path| script_path(path))
+            .collect();
+        loop {
+            let sources: HashSet<&str> = moves.iter().map(|(from, _)| from.as_str()).collect();
+            let (safe, blocked): (Vec<_>, Vec<_>) = moves
+                .iter()
+                .cloned()
+                .partition(|(_, to)| sources.contains(to.as_str()) || !files_a.contains(to));
+            if blocked.is_empty() {
+                break;
+            }
+            for (from, to) in blocked {
+                skipped.push(format!("{:?} -> {:?}: target exists in A", from, to));
+            }
+            moves = safe;
+        }
+
+        let mut script = String::from(shell.header());
+        script.push_str("# Run from the root of directory A.\n");
+        let mut created: HashSet<&str> = HashSet::new();
+        let steps = ordered_moves(&moves);
+        for (from, to) in &steps {
+            if let Some((dir, _)) = to.rsplit_once('/')
+                && created.insert(dir)
+            {
+                script.push_str(&shell.create_dir(dir));
+            }
+            script.push_str(&shell.move_file(from, to));
+        }
+
+        if !skipped.is_empty() {
+            script.push_str("\n# Not moved:\n");
+            for line in &skipped {
+                script.push_str(&format!("#   {}\n", line));
+            }
+        }
+        if !ambiguous.is_empty() {
+            script.push_str("\n# Ambiguous, several files with the same content:\n");
+            for group in ambiguous {
+                script.push_str(&format!("#   {} ({} bytes)\n", group.hash, group.size));
+                let files = group
+                    .files_in_a
+                    .iter()
+                    .map(|path| ("A", path))
+                    .chain(group.files_in_b.iter().map(|path| ("B", path)));
+                for (side, path) in files {
+                    script.push_str(&format!("#     {} {:?}\n", side, path.to_string_lossy()));
+                }
+            }
+        }
+
+        script
+    }
+}
+
+/// Joins the components of a relative path with `/`, or returns `None` if
+/// the path is not valid UTF-8.
+fn script_path(path: &Path) -> Option<String> {
+    let components: Option<Vec<&str>> = path
+        .components()
+        .map(|component| component.as_os_str().to_str())
+        .collect();
+    Some(components?.join("/"))
+}
+
+/// Orders `moves` so that each runs after the move taking the file at its
+/// target away, breaking cycles through a temporary name.
+fn ordered_moves(moves: &[(String, String)]) -> Vec<(String, String)> {
+    #[derive(Clone, Copy, PartialEq)]
+    enum Visit {
+        New,
+        Pending,
+        Done,
+    }
+
+    let by_source: HashMap<&str, usize> = moves
+        .iter()
+        .enumerate()
+        .map(|(index, (from, _))| (from.as_str(), index))
+        .collect();
+    let parked = |from: &str| format!("{}.dir-compare-move", from);
+    let mut visits = vec![Visit::New; moves.len()];
+    let mut in_cycle = vec![false; moves.len()];
+    let mut steps = Vec::with_capacity(moves.len());
+    for start in 0..moves.len() {
+        if visits[start] != Visit::New {
+            continue;
+        }
+        // Follow the moves whose files stand in the way
+        let mut chain = vec![start];
+        visits[start] = Visit::Pending;
+        while let Some(&next) = by_source.get(moves[chain[chain.len() - 1]].1.as_str()) {
+            match visits[next] {
+                Visit::New => {
+                    visits[next] = Visit::Pending;
+                    chain.push(next);
+                }
+                Visit::Pending => {
+                    let from = &moves[next].0;
+                    steps.push((from.clone(), parked(from)));
+                    in_cycle[next] = true;
+                    break;
+                }
+                Visit::Done => break,
+            }
+        }
+        for &index in chain.iter().rev() {
+            let (from, to) = &moves[index];
+            let from = if in_cycle[index] {
+                parked(from)
+            } else {
+                from.clone()
+            };
+            steps.push((from, to.clone()));
+            visits[index] = Visit::Done;
+        }
+    }
+    steps
+}
+
+/// Quotes a CSV field if it contains a comma, quote or line break (RFC 4180).
+pub(crate) fn escape_csv(s: &str) -> String {
+    if s.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", s.replace('"', "\"\""))
+    } else {
+        s.to_string()
+    }
+}
+
+/// Appends a JSON array with one pre-rendered item per line.
+fn push_json_array(json: &mut String, items: impl Iterator<Item = String>) {
+    let items: Vec<String> = items.collect();
+    if items.is_empty() {
+        json.push_str("[]");
+        return;
+    }
+    json.push_str("[\n");
+    json.push_str(
+        &items
+            .iter()
+            .map(|item| format!("    {}", item))
+            .collect::<Vec<_>>()
+            .join(",\n"),
+    );
+    json.push_str("\n  ]");
+}
+
+/// Renders an entry as a JSON object with `path`, `kind` and `size`, plus
+/// `target` for symlinks and `nested_repository` for nested repositories.
+fn json_entry(entry: &Entry) -> String {
+    let kind = match entry.kind {
+        EntryKind::Directory => "directory",
+        EntryKind::File => "file",
+        EntryKind::Symlink { .. } => "symlink",
+    };
+    let size = match entry.size {
+        Some(size) => size.to_string(),
+        None => "null".to_string(),
+    };
+    let target = match &entry.kind {
+        EntryKind::Symlink { target } => {
+            format!(
+                ", \"target\": \"{}\"",
+                escape_json(&target.to_string_lossy())
+            )
+        }
+        _ => String::new(),
+    };
+    let nested = if entry.nested_repository {
+        ", \"nested_repository\": true"
+    } else {
+        ""
+    };
+    format!(
+        "{{\"path\": \"{}\", \"kind\": \"{}\", \"size\": {}{}{}}}",
+        escape_json(&entry.path.to_string_lossy()),
+        kind,
+        size,
+        target,
+        nested
+    )
+}
+
+/// Formatter for `--porcelain`: one record per difference, for scripts and
+/// shell prompts.
+///
+/// The format is frozen; changes to the other formatters never affect it.
+/// Each record is `<X> <path>` followed by a newline, where `X` is `A` for
+/// entries only in A, `B` for entries only in B and `M` for same-path pairs
+/// that differ. Files found in [`ComparisonResult::renamed`] are written as
+/// `R <old path> -> <new path>`, or `R <new path>` NUL `<old path>` with
+/// `nul_terminated`, in the order git uses. Matching entries are not listed,
+/// and records are sorted by path, renamed files by their path in A.
+///
+/// Paths containing a space, `"`, `\`, a control character or non-ASCII
+/// bytes are wrapped in double quotes, with `"`, `\` and control characters
+/// escaped as in C (`\n`, `\t`, …) and other bytes as three-digit octal
+/// escapes (`é` is `\303\251`), as git does. With `nul_terminated` records
+/// end with a NUL byte instead, and paths are written unquoted.
+///
+/// # Output Format
+///
+/// ```text
+/// A docs/old.txt
+/// M "caf\303\251.txt"
+/// B "new report.txt"
+/// R src/util.rs -> src/helpers.rs
+/// ```
+#[derive(Debug, Clone, Copy, Default)]
+pub struct PorcelainFormatter {
+    /// End records with NUL and leave paths unquoted (`-z`)
+    pub nul_terminated: bool,
+}
+
+impl Formatter for PorcelainFormatter {
+    /// Returns the records of [`format_bytes`](Self::format_bytes), replacing
+    /// path bytes that are not valid UTF-8.
+    fn format(&self, result: &ComparisonResult) -> String {
+        String::from_utf8_lossy(&self.format_bytes(result)).into_owned()
+    }
+
+    fn format_to(&self, result: &ComparisonResult, w: &mut dyn io::Write) -> io::Result<()> {
+        w.write_all(&self.format_bytes(result))
+    }
+}
+
+/// The path, the code and, for renamed files, the path in B of a
+/// porcelain record
+type PorcelainRecord<'a> = (&'a [u8], u8, Option<&'a [u8]>);
+
+impl PorcelainFormatter {
+    /// Formats the records as raw bytes, keeping paths that are not valid
+    /// UTF-8 intact.
+    pub fn format_bytes(&self, result: &ComparisonResult) -> Vec<u8> {
+        let mut records: Vec<PorcelainRecord> = result
+            .a_only
+            .iter()
+            .map(|e| (path_bytes(&e.path), b'A', None))
+            .chain(
+                result
+                    .b_only
+                    .iter()
+                    .map(|e| (path_bytes(&e.path), b'B', None)),
+            )
+            .chain(
+                result
+                    .modified
+                    .iter()
+                    .map(|(a, _)| (path_bytes(&a.path), b'M', None)),
+            )
+            .chain(
+                result
+                    .renamed
+                    .iter()
+                    .map(|(a, b)| (path_bytes(&a.path), b'R', Some(path_bytes(&b.path)))),
+            )
+            .collect();
+        records.sort();
+
+        let mut output = Vec::new();
+        for (path, code, new_path) in records {
+            output.push(code);
+            output.push(b' ');
+            match (self.nul_terminated, new_path) {
+                (true, Some(new_path)) => {
+                    output.extend_from_slice(new_path);
+                    output.push(0);
+                    output.extend_from_slice(path);
+                    output.push(0);
+                }
+                (true, None) => {
+                    output.extend_from_slice(path);
+                    output.push(0);
+                }
+                (false, new_path) => {
+                    push_quoted_path(&mut output, path);
+                    if let Some(new_path) = new_path {
+                        output.extend_from_slice(b" -> ");
+                        push_quoted_path(&mut output, new_path);
+                    }
+                    output.push(b'\n');
+                }
+            }
+        }
+        output
+    }
+}
+
+/// Returns the bytes of a path; on platforms without byte paths, its UTF-8
+/// text with invalid characters replaced.
+pub(crate) fn path_bytes(path: &Path) -> &[u8] {
+    #[cfg(unix)]
+    {
+        use std::os::unix::ffi::OsStrExt;
+        path.as_os_str().as_bytes()
+    }
+    #[cfg(not(unix))]
+    {
+        path.to_str().map(str::as_bytes).unwrap_or(b"?")
+    }
+}
+
+/// Appends `path`, C-quoted if it contains anything but printable ASCII
+/// other than `"` and `\`.
+fn push_quoted_path(output: &mut Vec<u8>, path: &[u8]) {
+    let plain = |b: u8| b.is_ascii_graphic() && b != b'"' && b != b'\\';
+    if path.iter().all(|&b| plain(b)) {
+        output.extend_from_slice(path);
+        return;
+    }
+    output.push(b'"');
+    for &b in path {
+        match b {
+            b'"' => output.extend_from_slice(b"\\\""),
+            b'\\' => output.extend_from_slice(b"\\\\"),
+            b'\n' => output.extend_from_slice(b"\\n"),
+            b'\t' => output.extend_from_slice(b"\\t"),
+            b'\r' => output.extend_from_slice(b"\\r"),
+            0x07 => output.extend_from_slice(b"\\a"),
+            0x08 => output.extend_from_slice(b"\\b"),
+            0x0b => output.extend_from_slice(b"\\v"),
+            0x0c => output.extend_from_slice(b"\\f"),
+            b' ' => output.push(b' '),
+            b if plain(b) => output.push(b),
+            b => output.extend_from_slice(format!("\\{:03o}", b).as_bytes()),
+        }
+    }
+    output.push(b'"');
+}
+
+/// Describes a file of [`ChunkDedupeReport`], such as
+/// `75.0%  3.0 MiB of 4.0 MiB  disk.img`.
+fn shared_file_line(file: &SharedFile) -> String {
+    format!(
+        "{:.1}%  {} of {}  {}",
+        file.shared_ratio() * 100.0,
+        format_bytes(file.shared_bytes),
+        format_bytes(file.size),
+        file.path.display()
+    )
+}
+
+/// The sides of a [`ChunkDedupeReport`] with their top files.
+fn chunk_dedupe_sides(report: &ChunkDedupeReport) -> [(&'static str, &[SharedFile]); 2] {
+    [("A", &report.top_files_a), ("B", &report.top_files_b)]
+}
+
+/// Appends the chunk-level dedupe section of the flat text report.
+fn push_text_chunk_dedupe(output: &mut String, report: &ChunkDedupeReport) {
+    output.push_str(&format!(
+        "Chunk-Level Dedupe ({} average chunks)\n",
+        format_bytes(report.avg_chunk_size as u64)
+    ));
+    output.push_str(&"-".repeat(50));
+    output.push('\n');
+    output.push_str(&format!(
+        "Shared bytes: {}\n",
+        format_bytes(report.shared_bytes)
+    ));
+    output.push_str(&format!(
+        "Unique bytes in A: {}\n",
+        format_bytes(report.unique_bytes_a)
+    ));
+    output.push_str(&format!(
+        "Unique bytes in B: {}\n",
+        format_bytes(report.unique_bytes_b)
+    ));
+    for (side, files) in chunk_dedupe_sides(report) {
+        if files.is_empty() {
+            continue;
+        }
+        output.push_str(&format!("Top files in {} by shared ratio:\n", side));
+        for file in files {
+            output.push_str(&format!("  {}\n", shared_file_line(file)));
+        }
+    }
+    output.push('\n');
+}
+
+/// Appends the chunk-level dedupe section of the flat Markdown report.
+fn push_markdown_chunk_dedupe(md: &mut String, report: &ChunkDedupeReport) {
+    md.push_str("## Chunk-Level Dedupe\n\n");
+    md.push_str("| Metric | Value |\n");
+    md.push_str("|--------|-------|\n");
+    md.push_str(&format!(
+        "| Average chunk size | {} |\n",
+        format_bytes(report.avg_chunk_size as u64)
+    ));
+    md.push_str(&format!(
+        "| Shared bytes | {} |\n",
+        format_bytes(report.shared_bytes)
+    ));
+    md.push_str(&format!(
+        "| Unique bytes in A | {} |\n",
+        format_bytes(report.unique_bytes_a)
+    ));
+    md.push_str(&format!(
+        "| Unique bytes in B | {} |\n\n",
+        format_bytes(report.unique_bytes_b)
+    ));
+    for (side, files) in chunk_dedupe_sides(report) {
+        if files.is_empty() {
+            continue;
+        }
+        md.push_str(&format!("### Top Files in {} by Shared Ratio\n\n", side));
+        md.push_str("| File | Shared | Size | Ratio |\n");
+        md.push_str("|------|--------|------|-------|\n");
+        for file in files {
+            md.push_str(&format!(
+                "| `{}` | {} | {} | {:.1}% |\n",
+                escape_markdown(&file.path.display().to_string()),
+                format_bytes(file.shared_bytes),
+                format_bytes(file.size),
+                file.shared_ratio() * 100.0
+            ));
+        }
+        md.push('\n');
+    }
+}
+
+/// Appends the chunk-level dedupe section of the flat HTML report.
+fn push_html_chunk_dedupe(html: &mut String, report: &ChunkDedupeReport) {
+    html.push_str(&format!(
+        r#"        <div class="chunk-dedupe">
+            <h2>Chunk-Level Dedupe</h2>
+            <p>Shared: {} | Unique to A: {} | Unique to B: {} ({} average chunks)</p>
+"#,
+        format_bytes(report.shared_bytes),
+        format_bytes(report.unique_bytes_a),
+        format_bytes(report.unique_bytes_b),
+        format_bytes(report.avg_chunk_size as u64)
+    ));
+    for (side, files) in chunk_dedupe_sides(report) {
+        if files.is_empty() {
+            continue;
+        }
+        html.push_str(&format!(
+            r#"            <h3>Top files in {} by shared ratio</h3>
+            <table>
+                <tr><th>File</th><th>Shared</th><th>Size</th><th>Ratio</th></tr>
+"#,
+            side
+        ));
+        for file in files {
+            html.push_str(&format!(
+                "                <tr><td>{}</td><td>{}</td><td>{}</td><td>{:.1}%</td></tr>\n",
+                escape_html(&file.path.display().to_string()),
+                format_bytes(file.shared_bytes),
+                format_bytes(file.size),
+                file.shared_ratio() * 100.0
+            ));
+        }
+        html.push_str("            </table>\n");
+    }
+    html.push_str("        </div>\n");
+}
+
+/// Renders the `chunk_dedupe` object of the flat JSON report.
+fn json_chunk_dedupe(report: &ChunkDedupeReport) -> String {
+    let files = |files: &[SharedFile]| {
+        files
+            .iter()
+            .map(|file| {
+                format!(
+                    "{{\"path\": \"{}\", \"size\": {}, \"shared_bytes\": {}}}",
+                    escape_json(&file.path.to_string_lossy()),
+                    file.size,
+                    file.shared_bytes
+                )
+            })
+            .collect::<Vec<_>>()
+            .join(", ")
+    };
+    format!(
+        "{{\"avg_chunk_size\": {}, \"chunks_a\": {}, \"chunks_b\": {}, \"shared_bytes\": {}, \"unique_bytes_a\": {}, \"unique_bytes_b\": {}, \"top_files_a\": [{}], \"top_files_b\": [{}]}}",
+        report.avg_chunk_size,
+        report.chunks_a,
+        report.chunks_b,
+        report.shared_bytes,
+        report.unique_bytes_a,
+        report.unique_bytes_b,
+        files(&report.top_files_a),
+        files(&report.top_files_b)
+    )
+}
+
+/// Renders the `stats` object of the JSON reports, with the elapsed time in
+/// whole milliseconds.
+fn json_stats(stats: &ComparisonStats) -> String {
+    format!(
+        "{{\"files_a\": {}, \"files_b\": {}, \"dirs_a\": {}, \"dirs_b\": {}, \"bytes_a\": {}, \"bytes_b\": {}, \"bytes_hashed\": {}, \"elapsed_ms\": {}}}",
+        stats.files_a,
+        stats.files_b,
+        stats.dirs_a,
+        stats.dirs_b,
+        stats.bytes_a,
+        stats.bytes_b,
+        stats.bytes_hashed,
+        stats.elapsed.as_millis()
+    )
+}
+
+/// Renders paths as comma-separated JSON strings.
+fn json_paths(paths: &[PathBuf]) -> String {
+    paths
+        .iter()
+        .map(|path| format!("\"{}\"", escape_json(&path.to_string_lossy())))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+fn json_hard_links(links: &[FlatHardLink]) -> String {
+    links
+        .iter()
+        .map(|link| {
+            format!(
+                "{{\"side\": \"{}\", \"path\": \"{}\", \"hardlink_group\": {}}}",
+                if link.in_a { "a" } else { "b" },
+                escape_json(&link.path.to_string_lossy()),
+                link.hardlink_group
+            )
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Escapes a string for use inside a JSON string literal.
+pub fn escape_json(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\t' => escaped.push_str("\\t"),
+            c if (c as u32) < 0x20 => escaped.push_str(&format!("\\u{:04x}", c as u32)),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Formats a byte count with binary units (e.g. `5.2 MiB`).
+pub fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 5] = ["KiB", "MiB", "GiB", "TiB", "PiB"];
+    if bytes < 1024 {
+        return format!("{} B", bytes);
+    }
+    let mut value = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    format!("{:.1} {}", value, UNITS[unit])
+}
+
+/// Formats a duration with two decimals below a minute (e.g. `1.25s`) and
+/// as minutes and seconds above (e.g. `2m 05s`).
+pub fn format_elapsed(elapsed: Duration) -> String {
+    let secs = elapsed.as_secs();
+    if secs < 60 {
+        return format!("{:.2}s", elapsed.as_secs_f64());
+    }
+    format!("{}m {:02}s", secs / 60, secs % 60)
+}
+
+/// Formats block-level difference locations as a text section.
+///
+/// # Output Format
+///
+/// ```text
+/// Difference locations (1 files):
+/// ----------------------------------------
+///   db.dump: 2 differing regions covering 2.0 MiB of 2.0 GiB
+///     0x100000..0x200000 (1.0 MiB)
+///     0x7ff00000..0x80000000 (1.0 MiB, end of file)
+/// ```
+pub fn format_diff_locations(locations: &[(PathBuf, std::io::Result<BlockDiff>)]) -> String {
+    let mut output = String::new();
+
+    output.push_str(&format!(
+        "Difference locations ({} files):\n",
+        locations.len()
+    ));
+    output.push_str(&"-".repeat(40));
+    output.push('\n');
+    for (path, diff) in locations {
+        match diff {
+            Ok(diff) if diff.is_identical() => {
+                output.push_str(&format!(
+                    "  {}: no differing blocks ({})\n",
+                    path.display(),
+                    format_bytes(diff.total_bytes())
+                ));
+            }
+            Ok(diff) => {
+                output.push_str(&format!(
+                    "  {}: {} differing regions covering {} of {}\n",
+                    path.display(),
+                    diff.regions.len(),
+                    format_bytes(diff.differing_bytes()),
+                    format_bytes(diff.total_bytes())
+                ));
+                for region in &diff.regions {
+                    let end_marker = if region.end == diff.total_bytes() {
+                        ", end of file"
+                    } else {
+                        ""
+                    };
+                    output.push_str(&format!(
+                        "    {:#x}..{:#x} ({}{})\n",
+                        region.start,
+                        region.end,
+                        format_bytes(region.len()),
+                        end_marker
+                    ));
+                }
+            }
+            Err(e) => {
+                output.push_str(&format!("  {}: could not analyze: {}\n", path.display(), e));
+            }
+        }
+    }
+
+    output
+}
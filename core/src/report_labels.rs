@@ -0,0 +1,165 @@
+//! Built-in translations of the report [`Labels`].
+//!
+//! Each language other than English has a catalog in
+//! `core/locales/<code>.txt` with one `key = value` pair per line; `#`
+//! starts a comment. Keys are the [`Labels`] field names, and the
+//! [`FlatLabels`] fields are prefixed with `flat.`. Catalogs are embedded
+//! at compile time, and keys missing from a catalog keep the English value.
+
+use crate::output::{FlatLabels, Labels};
+
+const DE_CATALOG: &str = include_str!("../locales/de.txt");
+const JA_CATALOG: &str = include_str!("../locales/ja.txt");
+
+/// Language codes with built-in report labels; English is the default.
+pub const REPORT_LANGUAGES: &[&str] = &["en", "de", "ja"];
+
+impl Labels {
+    /// Returns the built-in labels for the language `code`, or `None` if
+    /// there are none.
+    pub fn for_language(code: &str) -> Option<Labels> {
+        let catalog = match code {
+            "en" => return Some(Labels::default()),
+            "de" => DE_CATALOG,
+            "ja" => JA_CATALOG,
+            _ => return None,
+        };
+        let mut labels = Labels {
+            lang: code.to_string(),
+            ..Labels::default()
+        };
+        for (key, value) in parse_catalog(catalog) {
+            if let Some(field) = labels.field(key) {
+                *field = value.to_string();
+            }
+        }
+        Some(labels)
+    }
+
+    /// Returns the label named by the catalog key `key`
+    fn field(&mut self, key: &str) -> Option<&mut String> {
+        if let Some(key) = key.strip_prefix("flat.") {
+            return self.flat.field(key);
+        }
+        Some(match key {
+            "report_title" => &mut self.report_title,
+            "summary" => &mut self.summary,
+            "category" => &mut self.category,
+            "count" => &mut self.count,
+            "a_only" => &mut self.a_only,
+            "b_only" => &mut self.b_only,
+            "both" => &mut self.both,
+            "modified" => &mut self.modified,
+            "renamed" => &mut self.renamed,
+            "no_entries" => &mut self.no_entries,
+            "no_matching_entries" => &mut self.no_matching_entries,
+            "entry_one" => &mut self.entry_one,
+            "entry_other" => &mut self.entry_other,
+            "more" => &mut self.more,
+            "identical_one" => &mut self.identical_one,
+            "identical_other" => &mut self.identical_other,
+            "reason_kind" => &mut self.reason_kind,
+            "reason_size" => &mut self.reason_size,
+            "reason_content" => &mut self.reason_content,
+            "reason_target" => &mut self.reason_target,
+            "reason_modified" => &mut self.reason_modified,
+            "reason_unreadable" => &mut self.reason_unreadable,
+            "nested_repository" => &mut self.nested_repository,
+            "stats" => &mut self.stats,
+            "sanitized_paths" => &mut self.sanitized_paths,
+            "directory_a" => &mut self.directory_a,
+            "directory_b" => &mut self.directory_b,
+            "method" => &mut self.method,
+            "generated" => &mut self.generated,
+            "filter" => &mut self.filter,
+            _ => return None,
+        })
+    }
+}
+
+impl FlatLabels {
+    fn field(&mut self, key: &str) -> Option<&mut String> {
+        Some(match key {
+            "report_title" => &mut self.report_title,
+            "document_title" => &mut self.document_title,
+            "summary" => &mut self.summary,
+            "metric" => &mut self.metric,
+            "value" => &mut self.value,
+            "files_in_a" => &mut self.files_in_a,
+            "files_in_b" => &mut self.files_in_b,
+            "unique_hashes" => &mut self.unique_hashes,
+            "duplicate_groups" => &mut self.duplicate_groups,
+            "duplicate_groups_short" => &mut self.duplicate_groups_short,
+            "files_a_short" => &mut self.files_a_short,
+            "files_b_short" => &mut self.files_b_short,
+            "unique_hashes_short" => &mut self.unique_hashes_short,
+            "duplicates_short" => &mut self.duplicates_short,
+            "content_groups" => &mut self.content_groups,
+            "directory_a" => &mut self.directory_a,
+            "directory_b" => &mut self.directory_b,
+            "duplicate" => &mut self.duplicate,
+            "matched" => &mut self.matched,
+            "moved" => &mut self.moved,
+            "a_only" => &mut self.a_only,
+            "b_only" => &mut self.b_only,
+            "moved_to_b" => &mut self.moved_to_b,
+            "moved_from_a" => &mut self.moved_from_a,
+            "hash" => &mut self.hash,
+            "size" => &mut self.size,
+            "bytes" => &mut self.bytes,
+            "files" => &mut self.files,
+            "files_html" => &mut self.files_html,
+            "hard_link" => &mut self.hard_link,
+            _ => return None,
+        })
+    }
+}
+
+fn parse_catalog(source: &str) -> impl Iterator<Item = (&str, &str)> {
+    source
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .filter_map(|line| line.split_once('='))
+        .map(|(key, value)| (key.trim(), value.trim()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_catalogs_translate_every_label() {
+        let english = Labels::default();
+        for code in REPORT_LANGUAGES.iter().filter(|code| **code != "en") {
+            let catalog = match *code {
+                "de" => DE_CATALOG,
+                _ => JA_CATALOG,
+            };
+            let mut keys: Vec<&str> = Vec::new();
+            for (key, _) in parse_catalog(catalog) {
+                assert!(
+                    Labels::default().field(key).is_some(),
+                    "unknown key '{}' in '{}'",
+                    key,
+                    code
+                );
+                keys.push(key);
+            }
+            // 30 labels and 30 flat labels
+            assert_eq!(keys.len(), 60, "'{}' misses labels", code);
+
+            let labels = Labels::for_language(code).unwrap();
+            assert_eq!(labels.lang, *code);
+            assert_ne!(labels.report_title, english.report_title);
+            assert_ne!(labels.flat.report_title, english.flat.report_title);
+        }
+    }
+
+    #[test]
+    fn test_unknown_language() {
+        assert_eq!(Labels::for_language("en"), Some(Labels::default()));
+        assert_eq!(Labels::for_language("fr"), None);
+        assert_eq!(Labels::for_language("DE"), None);
+    }
+}
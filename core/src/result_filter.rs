@@ -0,0 +1,156 @@
+//! Narrowing a finished [`ComparisonResult`] without comparing again.
+//!
+//! A [`ResultFilter`] combines the constraints a front end offers after a
+//! comparison: the glob patterns of `--include` and `--exclude`, a path
+//! prefix, a size range, an entry kind and the categories to keep. Every
+//! constraint that is set must hold; unset constraints keep everything.
+
+use std::path::PathBuf;
+
+use crate::comparison::{ComparisonResult, Entry, EntryFilter, EntryKind};
+use crate::output::Section;
+
+/// The kind of entry a [`ResultFilter`] keeps
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KindFilter {
+    File,
+    Directory,
+    Symlink,
+}
+
+impl KindFilter {
+    /// Returns true if `kind` is of this kind.
+    pub fn matches(self, kind: &EntryKind) -> bool {
+        matches!(
+            (self, kind),
+            (KindFilter::File, EntryKind::File)
+                | (KindFilter::Directory, EntryKind::Directory)
+                | (KindFilter::Symlink, EntryKind::Symlink { .. })
+        )
+    }
+}
+
+/// Constraints selecting the entries of a [`ComparisonResult`] to keep.
+///
+/// A pair from `both`, `modified` or `renamed` is kept if either of its
+/// entries satisfies every constraint. Renamed pairs belong to the A-only and
+/// B-only categories, which they were taken from; keeping either keeps them.
+///
+/// # Examples
+///
+/// ```
+/// use dir_compare_core::comparison::EntryFilter;
+/// use dir_compare_core::result_filter::{KindFilter, ResultFilter};
+///
+/// // Files of at least 10 MB whose name ends in .dll
+/// let filter = ResultFilter {
+///     patterns: Some(EntryFilter::new(&["*.dll"], &[]).unwrap()),
+///     min_size: Some(10 * 1024 * 1024),
+///     kind: Some(KindFilter::File),
+///     ..Default::default()
+/// };
+/// assert!(!filter.is_empty());
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct ResultFilter {
+    /// Include and exclude globs, matched like `--include` and `--exclude`
+    pub patterns: Option<EntryFilter>,
+    /// Keep only entries at or below this relative path
+    pub path_prefix: Option<PathBuf>,
+    /// Keep only entries of at least this many bytes; entries without a
+    /// size, such as directories, are dropped
+    pub min_size: Option<u64>,
+    /// Keep only entries of at most this many bytes; entries without a
+    /// size, such as directories, are dropped
+    pub max_size: Option<u64>,
+    /// Keep only entries of this kind
+    pub kind: Option<KindFilter>,
+    /// Keep only these categories; `None` keeps all of them
+    pub sections: Option<Vec<Section>>,
+}
+
+impl ResultFilter {
+    /// Returns true if no constraint is set.
+    pub fn is_empty(&self) -> bool {
+        self.patterns.as_ref().is_none_or(EntryFilter::is_empty)
+            && self.path_prefix.is_none()
+            && self.min_size.is_none()
+            && self.max_size.is_none()
+            && self.kind.is_none()
+            && self.sections.is_none()
+    }
+
+    /// Returns true if `entry` satisfies every constraint except the
+    /// category.
+    pub fn matches(&self, entry: &Entry) -> bool {
+        let is_dir = entry.kind == EntryKind::Directory;
+        let in_range = |bound: Option<u64>, within: fn(u64, u64) -> bool| {
+            bound.is_none_or(|bound| entry.size.is_some_and(|size| within(size, bound)))
+        };
+        self.patterns
+            .as_ref()
+            .is_none_or(|patterns| patterns.matches(&entry.path, is_dir))
+            && self
+                .path_prefix
+                .as_ref()
+                .is_none_or(|prefix| entry.path.starts_with(prefix))
+            && in_range(self.min_size, |size, min| size >= min)
+            && in_range(self.max_size, |size, max| size <= max)
+            && self.kind.is_none_or(|kind| kind.matches(&entry.kind))
+    }
+
+    fn keeps(&self, section: Section) -> bool {
+        self.sections
+            .as_ref()
+            .is_none_or(|sections| sections.contains(&section))
+    }
+}
+
+impl ComparisonResult {
+    /// Returns the entries of this result that `filter` keeps, in the same
+    /// order.
+    ///
+    /// Only the entries held in the vectors are filtered, so the counts of
+    /// the returned result are those of its vectors even if this result
+    /// was limited by a [`ResultDetail`](crate::comparison::ResultDetail).
+    /// Warnings are kept unchanged.
+    pub fn filter(&self, filter: &ResultFilter) -> ComparisonResult {
+        let entries = |section: Section, entries: &[Entry]| -> Vec<Entry> {
+            if !filter.keeps(section) {
+                return Vec::new();
+            }
+            entries
+                .iter()
+                .filter(|entry| filter.matches(entry))
+                .cloned()
+                .collect()
+        };
+        let pairs = |section: Section, pairs: &[(Entry, Entry)]| -> Vec<(Entry, Entry)> {
+            if !filter.keeps(section) {
+                return Vec::new();
+            }
+            pairs
+                .iter()
+                .filter(|(a, b)| filter.matches(a) || filter.matches(b))
+                .cloned()
+                .collect()
+        };
+        ComparisonResult {
+            a_only: entries(Section::AOnly, &self.a_only),
+            b_only: entries(Section::BOnly, &self.b_only),
+            both: pairs(Section::Both, &self.both),
+            modified: pairs(Section::Modified, &self.modified),
+            renamed: if filter.keeps(Section::AOnly) {
+                pairs(Section::AOnly, &self.renamed)
+            } else {
+                pairs(Section::BOnly, &self.renamed)
+            },
+            exact_counts: None,
+            warnings: self.warnings.clone(),
+            stats: self.stats,
+            context: None,
+            identical_subtrees: self.identical_subtrees.clone(),
+            mismatch_reasons: self.mismatch_reasons.clone(),
+        }
+    }
+}
@@ -0,0 +1,215 @@
+//! Rewriting the paths of a finished result for reports read by systems
+//! that reject unusual characters.
+//!
+//! Damaged file names can hold control characters, which break line-based
+//! tools, and some ticketing systems accept ASCII only. [`sanitize_result`]
+//! and [`sanitize_flat_result`] rewrite the relative paths and link targets
+//! of a finished result for a [`SanitizeMode`], with the
+//! number of rewritten paths in [`ComparisonStats::sanitized_paths`], so the
+//! report footer shows that information was lost. Comparing never sees the
+//! rewritten paths.
+//!
+//! JSON output escapes control characters itself and is given the result
+//! unchanged; [`escape_non_ascii_json`] makes a JSON document ASCII-only
+//! without losing anything.
+//!
+//! [`ComparisonStats::sanitized_paths`]: crate::comparison::ComparisonStats::sanitized_paths
+
+use std::borrow::Cow;
+use std::path::PathBuf;
+
+use crate::comparison::{ComparisonResult, Entry, EntryKind, FlatComparisonResult};
+
+/// How [`sanitize`] rewrites a path.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SanitizeMode {
+    /// Show C0 control characters and DEL as their Unicode control pictures
+    /// (`␇` for a bell, `␊` for a line feed) and C1 control characters as
+    /// `\u{..}` escapes
+    Control,
+    /// Like `Control`, but write control characters as `\xNN` escapes and
+    /// replace non-ASCII characters with an ASCII approximation: Latin letters
+    /// lose their accents and typographic punctuation becomes its plain
+    /// counterpart, while other characters, such as CJK text, become
+    /// `\u{..}` escapes
+    Ascii,
+}
+
+impl std::str::FromStr for SanitizeMode {
+    type Err = String;
+
+    /// Parses `control` or `ascii`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "control" => Ok(SanitizeMode::Control),
+            "ascii" => Ok(SanitizeMode::Ascii),
+            other => Err(format!(
+                "unknown sanitize mode '{}' (expected control or ascii)",
+                other
+            )),
+        }
+    }
+}
+
+/// Rewrites `s` for `mode`, borrowing it if nothing needs rewriting.
+///
+/// # Examples
+///
+/// ```
+/// use dir_compare_core::sanitize::{SanitizeMode, sanitize};
+///
+/// assert_eq!(sanitize("bell\x07.txt", SanitizeMode::Control), "bell\u{2407}.txt");
+/// assert_eq!(sanitize("bell\x07.txt", SanitizeMode::Ascii), "bell\\x07.txt");
+/// assert_eq!(sanitize("café 日本.txt", SanitizeMode::Ascii), "cafe \\u{65e5}\\u{672c}.txt");
+/// assert_eq!(sanitize("café.txt", SanitizeMode::Control), "café.txt");
+/// ```
+pub fn sanitize(s: &str, mode: SanitizeMode) -> Cow<'_, str> {
+    let keeps = |c: char| match mode {
+        SanitizeMode::Control => !c.is_control(),
+        SanitizeMode::Ascii => c.is_ascii() && !c.is_ascii_control(),
+    };
+    if s.chars().all(keeps) {
+        return Cow::Borrowed(s);
+    }
+    let mut sanitized = String::with_capacity(s.len() + 8);
+    for c in s.chars() {
+        match (mode, c) {
+            _ if keeps(c) => sanitized.push(c),
+            (SanitizeMode::Control, '\u{0}'..='\u{1f}') => {
+                sanitized.push(char::from_u32(0x2400 + c as u32).unwrap_or(c))
+            }
+            (SanitizeMode::Control, '\u{7f}') => sanitized.push('\u{2421}'),
+            (SanitizeMode::Ascii, '\u{0}'..='\u{7f}') => {
+                sanitized.push_str(&format!("\\x{:02x}", c as u32))
+            }
+            (SanitizeMode::Ascii, _) if !c.is_control() => match transliterate(c) {
+                Some(ascii) => sanitized.push_str(ascii),
+                None => sanitized.push_str(&c.escape_unicode().to_string()),
+            },
+            _ => sanitized.push_str(&c.escape_unicode().to_string()),
+        }
+    }
+    Cow::Owned(sanitized)
+}
+
+/// Returns the ASCII approximation of a Latin letter or a typographic
+/// punctuation mark, if there is one.
+fn transliterate(c: char) -> Option<&'static str> {
+    let latin = matches!(c, '\u{a0}'..='\u{24f}' | '\u{1e00}'..='\u{1eff}');
+    let punctuation = matches!(c, '\u{2000}'..='\u{206f}');
+    if !latin && !punctuation {
+        return None;
+    }
+    deunicode::deunicode_char(c).filter(|ascii| {
+        !ascii.is_empty() && ascii.chars().all(|c| c.is_ascii_graphic() || c == ' ')
+    })
+}
+
+/// Returns `result` with every path rewritten for `mode`.
+///
+/// Relative paths, symbolic link targets and the directories of the report
+/// header are rewritten; the absolute paths of entries, which reports do not
+/// show, are kept. The number of entries, or pairs of entries, whose path or
+/// target changed is stored in `stats.sanitized_paths`.
+pub fn sanitize_result(mut result: ComparisonResult, mode: SanitizeMode) -> ComparisonResult {
+    let mut count = 0;
+    for entry in result.a_only.iter_mut().chain(&mut result.b_only) {
+        count += usize::from(sanitize_entry(entry, mode));
+    }
+    for (a, b) in result
+        .both
+        .iter_mut()
+        .chain(&mut result.modified)
+        .chain(&mut result.renamed)
+    {
+        let changed_a = sanitize_entry(a, mode);
+        let changed_b = sanitize_entry(b, mode);
+        count += usize::from(changed_a || changed_b);
+    }
+    for subtree in &mut result.identical_subtrees {
+        sanitize_path(&mut subtree.path, mode);
+    }
+    for (path, _) in &mut result.mismatch_reasons {
+        sanitize_path(path, mode);
+    }
+    if let Some(context) = &mut result.context {
+        sanitize_path(&mut context.dir_a, mode);
+        sanitize_path(&mut context.dir_b, mode);
+    }
+    result.stats.sanitized_paths = count;
+    result
+}
+
+/// Returns `result` with every path rewritten for `mode`.
+///
+/// The files of the content groups and of the chunk-level dedupe report are
+/// rewritten; `stats.sanitized_paths` counts the changed paths of the
+/// content groups.
+pub fn sanitize_flat_result(
+    mut result: FlatComparisonResult,
+    mode: SanitizeMode,
+) -> FlatComparisonResult {
+    let mut count = 0;
+    for group in &mut result.groups {
+        for path in group.files_in_a.iter_mut().chain(&mut group.files_in_b) {
+            count += usize::from(sanitize_path(path, mode));
+        }
+        for link in &mut group.hard_links {
+            sanitize_path(&mut link.path, mode);
+        }
+    }
+    if let Some(report) = &mut result.chunk_dedupe {
+        for file in report.top_files_a.iter_mut().chain(&mut report.top_files_b) {
+            sanitize_path(&mut file.path, mode);
+        }
+    }
+    result.stats.sanitized_paths = count;
+    result
+}
+
+/// Rewrites the path and link target of `entry`; returns true if either
+/// changed.
+fn sanitize_entry(entry: &mut Entry, mode: SanitizeMode) -> bool {
+    let changed_path = sanitize_path(&mut entry.path, mode);
+    let changed_target = match &mut entry.kind {
+        EntryKind::Symlink { target } => sanitize_path(target, mode),
+        EntryKind::File | EntryKind::Directory => false,
+    };
+    changed_path || changed_target
+}
+
+/// Rewrites `path` as reports show it; returns true if it changed.
+///
+/// Paths that are not valid UTF-8 count as changed, since reports already
+/// replace their invalid bytes.
+fn sanitize_path(path: &mut PathBuf, mode: SanitizeMode) -> bool {
+    let lossy = path.to_string_lossy();
+    let changed = match sanitize(&lossy, mode) {
+        Cow::Borrowed(_) if path.to_str().is_some() => return false,
+        sanitized => sanitized.into_owned(),
+    };
+    *path = PathBuf::from(changed);
+    true
+}
+
+/// Replaces every non-ASCII character of a JSON document with `\uXXXX`
+/// escapes, as surrogate pairs above U+FFFF.
+///
+/// JSON syntax is ASCII, so non-ASCII characters only occur inside strings,
+/// where the escapes decode to the same text.
+pub fn escape_non_ascii_json(json: &str) -> Cow<'_, str> {
+    if json.is_ascii() {
+        return Cow::Borrowed(json);
+    }
+    let mut escaped = String::with_capacity(json.len() + 16);
+    for c in json.chars() {
+        if c.is_ascii() {
+            escaped.push(c);
+        } else {
+            for unit in c.encode_utf16(&mut [0; 2]) {
+                escaped.push_str(&format!("\\u{:04x}", unit));
+            }
+        }
+    }
+    Cow::Owned(escaped)
+}
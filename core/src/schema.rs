@@ -0,0 +1,280 @@
+//! JSON Schema for the JSON reports of [`JsonFormatter`] and
+//! [`FlatJsonFormatter`].
+//!
+//! The types in this module describe the documents the formatters write;
+//! they are only used to derive the schema. Each schema pins
+//! `format_version` to [`JSON_FORMAT_VERSION`], so a change to the report
+//! layout bumps the version and the schema together.
+//!
+//! Requires the `schema` feature.
+//!
+//! [`JsonFormatter`]: crate::output::JsonFormatter
+//! [`FlatJsonFormatter`]: crate::output::FlatJsonFormatter
+
+use schemars::{JsonSchema, schema_for};
+
+use crate::output::JSON_FORMAT_VERSION;
+
+/// A hierarchy mode report, as written by `--format json`
+#[derive(JsonSchema)]
+#[schemars(title = "dir-compare JSON report")]
+#[serde(deny_unknown_fields)]
+pub struct JsonReport {
+    /// Version of the report layout
+    #[schemars(extend("const" = JSON_FORMAT_VERSION))]
+    pub format_version: u32,
+    /// Number of entries in each category, including those not listed
+    pub counts: JsonCounts,
+    pub stats: JsonStats,
+    /// Entries only in directory A
+    pub a_only: Vec<JsonEntry>,
+    /// Entries only in directory B
+    pub b_only: Vec<JsonEntry>,
+    /// Same-path entries the comparison method matched
+    pub both: Vec<JsonPair>,
+    /// Same-path entries the comparison method did not match
+    pub modified: Vec<JsonPair>,
+    /// Files only in A paired with the file only in B holding the same
+    /// content, empty unless renames were detected
+    pub renamed: Vec<JsonPair>,
+    /// Where the files of `modified` pairs differ; only present with
+    /// `--locate-diffs`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub diff_locations: Option<Vec<JsonDiffLocation>>,
+}
+
+/// The number of entries in each category of a hierarchy mode report
+#[derive(JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct JsonCounts {
+    pub a_only: usize,
+    pub b_only: usize,
+    pub both: usize,
+    pub modified: usize,
+    /// Pairs of files moved or renamed, which `--summary` leaves out of
+    /// `renamed`
+    pub renamed: usize,
+}
+
+/// What a comparison traversed and read, and how long it took
+#[derive(JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct JsonStats {
+    /// Files listed in directory A
+    pub files_a: usize,
+    /// Files listed in directory B
+    pub files_b: usize,
+    /// Directories listed in directory A
+    pub dirs_a: usize,
+    /// Directories listed in directory B
+    pub dirs_b: usize,
+    /// Total size of the files in directory A whose size was read
+    pub bytes_a: u64,
+    /// Total size of the files in directory B whose size was read
+    pub bytes_b: u64,
+    /// File bytes read into content hashes
+    pub bytes_hashed: u64,
+    /// Duration of the comparison in milliseconds
+    pub elapsed_ms: u64,
+}
+
+/// An entry of a hierarchy mode report
+#[derive(JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct JsonEntry {
+    /// Path relative to the compared root, as UTF-8 without a trailing slash
+    pub path: String,
+    pub kind: JsonEntryKind,
+    /// Size in bytes; `null` for directories and symbolic links
+    pub size: Option<u64>,
+    /// Link target as stored in the link; only present for symbolic links
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub target: Option<String>,
+    /// `true` for a directory holding its own `.git` (`--vcs-aware`); absent
+    /// otherwise
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub nested_repository: bool,
+}
+
+/// The type of an entry
+#[derive(JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum JsonEntryKind {
+    File,
+    Directory,
+    Symlink,
+}
+
+/// The entries at the same path in directory A and directory B
+#[derive(JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct JsonPair {
+    pub a: JsonEntry,
+    pub b: JsonEntry,
+    /// Number of files below a directory listed without its identical
+    /// contents; only present with `--rollup`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub identical_files: Option<usize>,
+    /// Why the comparison method did not match the entries; only present
+    /// on items of `modified`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reason: Option<JsonMismatchReason>,
+}
+
+/// Why the entries of a modified pair differ
+#[derive(JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum JsonMismatchReason {
+    /// One entry is a file, directory or symbolic link and the other is not
+    Kind,
+    /// The files have different sizes
+    Size,
+    /// The entries have the same size but different contents
+    Content,
+    /// The symbolic links point to different targets
+    Target,
+    /// The modification times differ by more than the tolerance
+    Modified,
+    /// A file could not be read
+    Unreadable,
+}
+
+/// The differing regions of a modified pair of files
+#[derive(JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct JsonDiffLocation {
+    /// Path relative to the compared root
+    pub path: String,
+    /// Size of the file in directory A; absent when the files could not be
+    /// analyzed
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub len_a: Option<u64>,
+    /// Size of the file in directory B; absent when the files could not be
+    /// analyzed
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub len_b: Option<u64>,
+    /// Differing byte ranges, sorted and merged where contiguous; absent
+    /// when the files could not be analyzed
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub regions: Option<Vec<JsonDiffRegion>>,
+    /// Why the files could not be analyzed; only present then
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// A range of differing bytes
+#[derive(JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct JsonDiffRegion {
+    /// Offset of the first differing byte
+    pub start: u64,
+    /// Offset just past the last differing byte
+    pub end: u64,
+}
+
+/// A flat mode report, as written by `--flat --format json`
+#[derive(JsonSchema)]
+#[schemars(title = "dir-compare flat JSON report")]
+#[serde(deny_unknown_fields)]
+pub struct FlatJsonReport {
+    /// Version of the report layout
+    #[schemars(extend("const" = JSON_FORMAT_VERSION))]
+    pub format_version: u32,
+    /// Number of files in directory A
+    pub total_files_a: usize,
+    /// Number of files in directory B
+    pub total_files_b: usize,
+    /// Number of distinct contents
+    pub unique_hashes: usize,
+    /// Number of contents shared by more than one file
+    pub duplicate_count: usize,
+    pub stats: JsonStats,
+    /// Chunk-level sharing between A and B; only present with
+    /// `--chunk-dedupe`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub chunk_dedupe: Option<JsonChunkDedupe>,
+    pub groups: Vec<JsonContentGroup>,
+}
+
+/// How much data A and B share at the chunk level, counting each distinct
+/// chunk once
+#[derive(JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct JsonChunkDedupe {
+    /// Average chunk size in bytes
+    pub avg_chunk_size: usize,
+    /// Distinct chunks in directory A
+    pub chunks_a: usize,
+    /// Distinct chunks in directory B
+    pub chunks_b: usize,
+    /// Bytes of the chunks found in both directories
+    pub shared_bytes: u64,
+    /// Bytes of the chunks found only in directory A
+    pub unique_bytes_a: u64,
+    /// Bytes of the chunks found only in directory B
+    pub unique_bytes_b: u64,
+    /// Files of directory A sharing the largest part of their bytes with B
+    pub top_files_a: Vec<JsonSharedFile>,
+    /// Files of directory B sharing the largest part of their bytes with A
+    pub top_files_b: Vec<JsonSharedFile>,
+}
+
+/// A file with chunks that also occur on the other side
+#[derive(JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct JsonSharedFile {
+    /// Path relative to the compared root
+    pub path: String,
+    /// Bytes read from the file
+    pub size: u64,
+    /// Bytes of the file in chunks found on the other side
+    pub shared_bytes: u64,
+}
+
+/// Files with identical content
+#[derive(JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct JsonContentGroup {
+    /// Content hash
+    pub hash: String,
+    /// File size in bytes
+    pub size: u64,
+    /// Number of files in both directories with this content
+    pub file_count: usize,
+    /// Paths in directory A, relative to its root
+    pub files_in_a: Vec<String>,
+    /// Paths in directory B, relative to its root
+    pub files_in_b: Vec<String>,
+    /// Files of the group that are hard links to another file of the group
+    pub hard_links: Vec<JsonHardLink>,
+}
+
+/// A file sharing its inode with other files of its group
+#[derive(JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct JsonHardLink {
+    /// Directory holding the file
+    pub side: JsonSide,
+    /// Path relative to the compared root
+    pub path: String,
+    /// Number shared by the links to the same inode, counting from 1
+    pub hardlink_group: u64,
+}
+
+/// One of the compared directories
+#[derive(JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum JsonSide {
+    A,
+    B,
+}
+
+/// Returns the JSON Schema of hierarchy mode reports, pretty-printed.
+pub fn json_report_schema() -> String {
+    serde_json::to_string_pretty(&schema_for!(JsonReport)).expect("schemas serialize to JSON")
+}
+
+/// Returns the JSON Schema of flat mode reports, pretty-printed.
+pub fn flat_json_report_schema() -> String {
+    serde_json::to_string_pretty(&schema_for!(FlatJsonReport)).expect("schemas serialize to JSON")
+}
@@ -0,0 +1,181 @@
+//! Defaults of the comparison strategies, shared by every front end.
+//!
+//! The CLI and the GUI used to create strategies separately and drifted
+//! apart: the same "Sampled Hash" method verified matches in the GUI but not
+//! in the CLI. Both now describe the strategy they want as a
+//! [`StrategySettings`], whose defaults are defined here once, and build it
+//! with [`StrategySettings::build`]. [`ComparisonStrategyType::build`]
+//! applies the GUI's option values to the same settings.
+//!
+//! Flat mode takes its defaults from [`FlatComparisonOptions::default`] in
+//! both front ends, and never matches names case-insensitively.
+//!
+//! [`FlatComparisonOptions::default`]: crate::comparison::FlatComparisonOptions
+
+use std::path::Path;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+
+use crate::comparison::{
+    Blake3Strategy, ComparisonStrategy, ComparisonStrategyType, DEFAULT_MTIME_TOLERANCE,
+    FastHashStrategy, FilenameOnlyStrategy, FilenameSizeStrategy, MetadataStrategy,
+    SampledHashStrategy,
+};
+use crate::data_profile::DataProfiler;
+use crate::filesystem::{RealFileSystem, detect_case_insensitive_roots};
+use crate::output::ReportContext;
+
+/// Whether sampled hashing confirms files whose samples match with a
+/// full-content hash unless asked to
+pub const DEFAULT_VERIFY: bool = false;
+
+/// Everything that decides which strategy a comparison uses.
+#[derive(Debug, Clone)]
+pub struct StrategySettings {
+    /// The comparison method
+    pub method: ComparisonStrategyType,
+    /// Match names regardless of case
+    pub case_insensitive: bool,
+    /// Confirm sample matches with a full hash (sampled hashing only)
+    pub verify: bool,
+    /// Largest modification time difference treated as equal (mtime only)
+    pub mtime_tolerance: Duration,
+    /// Measure and hash files by reading them instead of trusting their
+    /// metadata size (size, sampled hashing and mtime)
+    pub distrust_sizes: bool,
+    /// Ask the OS to read ahead files hashed whole (the hashing methods)
+    pub io_hints: bool,
+    /// Classifies the files sampled hashing reads, for
+    /// [`sampling_advice`](crate::data_profile::sampling_advice)
+    pub data_profiler: Option<Arc<DataProfiler>>,
+}
+
+impl StrategySettings {
+    /// Creates the default settings of `method`.
+    pub fn new(method: ComparisonStrategyType, case_insensitive: bool) -> Self {
+        Self {
+            method,
+            case_insensitive,
+            verify: DEFAULT_VERIFY,
+            mtime_tolerance: DEFAULT_MTIME_TOLERANCE,
+            distrust_sizes: false,
+            io_hints: true,
+            data_profiler: None,
+        }
+    }
+
+    /// Describes the comparison of `dir_a` and `dir_b` with these settings
+    /// for the header of reports, as generated now.
+    ///
+    /// The options are those that change what matches: case-insensitive
+    /// names, verification, the mtime tolerance and distrusted sizes.
+    pub fn report_context(&self, dir_a: &Path, dir_b: &Path) -> ReportContext {
+        let mut options = Vec::new();
+        if self.case_insensitive {
+            options.push("case-insensitive".to_string());
+        }
+        match self.method {
+            ComparisonStrategyType::SampledHash if self.verify => {
+                options.push("verify".to_string());
+            }
+            ComparisonStrategyType::Mtime => {
+                options.push(format!("tolerance {}s", self.mtime_tolerance.as_secs()));
+            }
+            _ => {}
+        }
+        let reads_sizes = !matches!(
+            self.method,
+            ComparisonStrategyType::Filename | ComparisonStrategyType::Blake3
+        );
+        if self.distrust_sizes && reads_sizes {
+            options.push("distrust sizes".to_string());
+        }
+        let absolute = |dir: &Path| std::path::absolute(dir).unwrap_or_else(|_| dir.to_path_buf());
+        ReportContext {
+            dir_a: absolute(dir_a),
+            dir_b: absolute(dir_b),
+            strategy_name: method_name(self.method).to_string(),
+            options,
+            timestamp: SystemTime::now(),
+        }
+    }
+
+    /// Creates the strategy.
+    pub fn build(&self) -> Box<dyn ComparisonStrategy + Send> {
+        let case_insensitive = self.case_insensitive;
+        match self.method {
+            ComparisonStrategyType::Filename => {
+                Box::new(FilenameOnlyStrategy::new(case_insensitive))
+            }
+            ComparisonStrategyType::FilenameSize => Box::new(
+                FilenameSizeStrategy::new(case_insensitive)
+                    .with_distrust_sizes(self.distrust_sizes),
+            ),
+            ComparisonStrategyType::FastHash => Box::new(
+                FastHashStrategy::new(case_insensitive)
+                    .with_distrust_sizes(self.distrust_sizes)
+                    .with_io_hints(self.io_hints),
+            ),
+            ComparisonStrategyType::Blake3 => {
+                Box::new(Blake3Strategy::new(case_insensitive).with_io_hints(self.io_hints))
+            }
+            ComparisonStrategyType::SampledHash => {
+                let strategy = SampledHashStrategy::new(case_insensitive, self.verify)
+                    .with_distrust_sizes(self.distrust_sizes)
+                    .with_io_hints(self.io_hints);
+                match &self.data_profiler {
+                    Some(profiler) => Box::new(strategy.with_data_profiler(Arc::clone(profiler))),
+                    None => Box::new(strategy),
+                }
+            }
+            ComparisonStrategyType::Mtime => Box::new(
+                MetadataStrategy::new(case_insensitive, self.mtime_tolerance)
+                    .with_distrust_sizes(self.distrust_sizes),
+            ),
+        }
+    }
+}
+
+/// Returns the method named `name` as `dir-compare --method` accepts it,
+/// including aliases such as `name` and `sampled-hash`, case-insensitively.
+///
+/// # Examples
+///
+/// ```
+/// use dir_compare_core::ComparisonStrategyType;
+/// use dir_compare_core::strategy_defaults::method_from_name;
+///
+/// assert_eq!(method_from_name("Sampled-Hash"), Some(ComparisonStrategyType::SampledHash));
+/// assert_eq!(method_from_name("md5"), None);
+/// ```
+pub fn method_from_name(name: &str) -> Option<ComparisonStrategyType> {
+    Some(match name.to_lowercase().as_str() {
+        "filename" | "name" => ComparisonStrategyType::Filename,
+        "size" => ComparisonStrategyType::FilenameSize,
+        "hash" | "fxhash" | "fasthash" => ComparisonStrategyType::FastHash,
+        "blake3" => ComparisonStrategyType::Blake3,
+        "sampled" | "sampled-hash" => ComparisonStrategyType::SampledHash,
+        "mtime" | "metadata" => ComparisonStrategyType::Mtime,
+        _ => return None,
+    })
+}
+
+/// Returns the name `dir-compare --method` knows `method` by; the inverse
+/// of [`method_from_name`].
+pub fn method_name(method: ComparisonStrategyType) -> &'static str {
+    match method {
+        ComparisonStrategyType::Filename => "filename",
+        ComparisonStrategyType::FilenameSize => "size",
+        ComparisonStrategyType::FastHash => "hash",
+        ComparisonStrategyType::Blake3 => "blake3",
+        ComparisonStrategyType::SampledHash => "sampled",
+        ComparisonStrategyType::Mtime => "mtime",
+    }
+}
+
+/// Returns whether names are matched case-insensitively: as `explicit`
+/// says if given, otherwise when both roots are on case-insensitive
+/// filesystems.
+pub fn case_insensitive(explicit: Option<bool>, dir_a: &Path, dir_b: &Path) -> bool {
+    explicit.unwrap_or_else(|| detect_case_insensitive_roots(&RealFileSystem, dir_a, dir_b))
+}
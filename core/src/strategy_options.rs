@@ -0,0 +1,210 @@
+//! Configurable options of the comparison strategies.
+//!
+//! Each [`ComparisonStrategyType`] describes its options as
+//! [`StrategyOption`]s, so front ends such as the GUI can render matching
+//! widgets without knowing the individual strategies.
+//! [`ComparisonStrategyType::build`] validates the chosen values and creates
+//! the strategy from the [`StrategySettings`] they describe.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::time::Duration;
+
+use crate::comparison::{ComparisonStrategy, ComparisonStrategyType, DEFAULT_MTIME_TOLERANCE};
+use crate::strategy_defaults::{DEFAULT_VERIFY, StrategySettings};
+
+/// The values of a strategy's options, keyed by [`StrategyOption::name`]
+pub type OptionValues = HashMap<String, OptionValue>;
+
+/// The type of value an option takes
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum OptionKind {
+    /// On or off
+    Bool,
+    /// A whole number within `min..=max`
+    Int { min: i64, max: i64 },
+    /// One of a fixed set of names
+    Choice(&'static [&'static str]),
+}
+
+/// The value of an option
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum OptionValue {
+    Bool(bool),
+    Int(i64),
+    Choice(String),
+}
+
+/// Describes one option of a comparison strategy
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StrategyOption {
+    /// Key of the option in [`OptionValues`]
+    pub name: &'static str,
+    /// Short English label
+    pub label: &'static str,
+    pub kind: OptionKind,
+    /// Value used when none is given
+    pub default: OptionValue,
+    /// One-sentence English explanation
+    pub help: &'static str,
+}
+
+impl StrategyOption {
+    /// Checks that `value` has this option's kind and lies within its range.
+    pub fn validate(&self, value: &OptionValue) -> Result<(), StrategyOptionError> {
+        let problem = match (&self.kind, value) {
+            (OptionKind::Bool, OptionValue::Bool(_)) => return Ok(()),
+            (OptionKind::Int { min, max }, OptionValue::Int(n)) => {
+                if (min..=max).contains(&n) {
+                    return Ok(());
+                }
+                format!("{} is outside {}..={}", n, min, max)
+            }
+            (OptionKind::Choice(choices), OptionValue::Choice(choice)) => {
+                if choices.contains(&choice.as_str()) {
+                    return Ok(());
+                }
+                format!("'{}' is not one of {}", choice, choices.join(", "))
+            }
+            (kind, value) => format!("{:?} does not fit an option of kind {:?}", value, kind),
+        };
+        Err(StrategyOptionError {
+            option: self.name.to_string(),
+            message: problem,
+        })
+    }
+}
+
+/// An option value that a strategy does not accept
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StrategyOptionError {
+    /// Name of the offending option
+    pub option: String,
+    pub message: String,
+}
+
+impl fmt::Display for StrategyOptionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Invalid option '{}': {}", self.option, self.message)
+    }
+}
+
+impl std::error::Error for StrategyOptionError {}
+
+/// Upper bound of the mtime tolerance option: one day
+const MAX_MTIME_TOLERANCE_SECS: i64 = 24 * 60 * 60;
+
+impl ComparisonStrategyType {
+    /// All strategy types, in menu order
+    pub const ALL: [ComparisonStrategyType; 6] = [
+        ComparisonStrategyType::Filename,
+        ComparisonStrategyType::FilenameSize,
+        ComparisonStrategyType::FastHash,
+        ComparisonStrategyType::Blake3,
+        ComparisonStrategyType::SampledHash,
+        ComparisonStrategyType::Mtime,
+    ];
+
+    /// Returns the options this strategy accepts
+    pub fn options(self) -> Vec<StrategyOption> {
+        match self {
+            ComparisonStrategyType::Filename
+            | ComparisonStrategyType::FilenameSize
+            | ComparisonStrategyType::FastHash
+            | ComparisonStrategyType::Blake3 => Vec::new(),
+            ComparisonStrategyType::SampledHash => vec![StrategyOption {
+                name: "verify",
+                label: "Verify matches",
+                kind: OptionKind::Bool,
+                default: OptionValue::Bool(DEFAULT_VERIFY),
+                help: "Confirm files whose samples match with a full-content hash",
+            }],
+            ComparisonStrategyType::Mtime => vec![StrategyOption {
+                name: "tolerance_secs",
+                label: "Time tolerance (seconds)",
+                kind: OptionKind::Int {
+                    min: 0,
+                    max: MAX_MTIME_TOLERANCE_SECS,
+                },
+                default: OptionValue::Int(DEFAULT_MTIME_TOLERANCE.as_secs() as i64),
+                help: "Largest modification time difference treated as equal",
+            }],
+        }
+    }
+
+    /// Returns the default value of every option
+    pub fn default_options(self) -> OptionValues {
+        self.options()
+            .into_iter()
+            .map(|option| (option.name.to_string(), option.default))
+            .collect()
+    }
+
+    /// Creates the strategy with the given option values.
+    ///
+    /// Options missing from `values` take their default, and everything
+    /// else the defaults of [`StrategySettings::new`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error for an option the strategy does not have, or a value
+    /// of the wrong kind or out of range.
+    pub fn build(
+        self,
+        case_insensitive: bool,
+        values: &OptionValues,
+    ) -> Result<Box<dyn ComparisonStrategy + Send>, StrategyOptionError> {
+        Ok(self.settings(case_insensitive, values)?.build())
+    }
+
+    /// Returns the settings [`build`](Self::build) creates the strategy
+    /// from, with the same errors.
+    pub fn settings(
+        self,
+        case_insensitive: bool,
+        values: &OptionValues,
+    ) -> Result<StrategySettings, StrategyOptionError> {
+        let options = self.options();
+        for (name, value) in values {
+            match options.iter().find(|option| option.name == name) {
+                Some(option) => option.validate(value)?,
+                None => {
+                    return Err(StrategyOptionError {
+                        option: name.clone(),
+                        message: format!("not an option of {:?}", self),
+                    });
+                }
+            }
+        }
+        let value = |name: &str| {
+            values.get(name).cloned().unwrap_or_else(|| {
+                options
+                    .iter()
+                    .find(|option| option.name == name)
+                    .map(|option| option.default.clone())
+                    .expect("options are looked up by their declared names")
+            })
+        };
+
+        let mut settings = StrategySettings::new(self, case_insensitive);
+        match self {
+            ComparisonStrategyType::SampledHash => {
+                let OptionValue::Bool(verify) = value("verify") else {
+                    unreachable!("validated as a bool option")
+                };
+                settings.verify = verify;
+            }
+            ComparisonStrategyType::Mtime => {
+                let OptionValue::Int(secs) = value("tolerance_secs") else {
+                    unreachable!("validated as an int option")
+                };
+                settings.mtime_tolerance = Duration::from_secs(secs as u64);
+            }
+            ComparisonStrategyType::Filename
+            | ComparisonStrategyType::FilenameSize
+            | ComparisonStrategyType::FastHash
+            | ComparisonStrategyType::Blake3 => {}
+        }
+        Ok(settings)
+    }
+}
@@ -0,0 +1,393 @@
+//! Planning and applying the file operations that make two compared
+//! directories match.
+//!
+//! [`plan_sync`] turns a [`ComparisonResult`] into a list of
+//! [`SyncAction`]s for a [`SyncDirection`], and [`apply_sync`] performs
+//! them. Files are copied with [`copy_file`], so they are verified after
+//! copying unless [`SyncOptions::copy`] says otherwise. A failed action is
+//! recorded in the [`SyncReport`] and the remaining actions still run.
+//!
+//! Actions are only as complete as the result they were planned from:
+//! entries left out by filters, ignore files, a depth limit or a
+//! [`ResultDetail`](crate::comparison::ResultDetail) other than `Full` are
+//! neither copied nor deleted, and a directory that still holds such
+//! entries cannot be deleted.
+
+use std::fmt;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::comparison::{ComparisonResult, Entry, EntryKind};
+use crate::filesystem::FileSystem;
+use crate::ops::{ActionOutcome, ActionResults, CopyOptions, copy_file};
+
+/// Which directory [`plan_sync`] changes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SyncDirection {
+    /// Make B match A: copy A-only entries to B, overwrite modified files in
+    /// B and delete B-only entries
+    AToB,
+    /// Make A match B, the mirror image of `AToB`
+    BToA,
+    /// Copy the entries each side lacks to the other and overwrite the older
+    /// file of each modified pair; nothing is deleted
+    TwoWay,
+}
+
+impl std::str::FromStr for SyncDirection {
+    type Err = String;
+
+    /// Parses `a-to-b`, `b-to-a` or `two-way`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "a-to-b" => Ok(SyncDirection::AToB),
+            "b-to-a" => Ok(SyncDirection::BToA),
+            "two-way" => Ok(SyncDirection::TwoWay),
+            other => Err(format!(
+                "unknown sync direction '{}' (expected a-to-b, b-to-a or two-way)",
+                other
+            )),
+        }
+    }
+}
+
+/// One file operation planned by [`plan_sync`].
+#[derive(Debug, Clone)]
+pub enum SyncAction {
+    /// Copy an entry of A to the same relative path in B
+    CopyToB(Entry),
+    /// Copy an entry of B to the same relative path in A
+    CopyToA(Entry),
+    /// Delete an entry of B
+    DeleteFromB(Entry),
+    /// Delete an entry of A
+    DeleteFromA(Entry),
+    /// Replace `to` with a copy of `from`, the other entry of a modified pair
+    Overwrite {
+        /// The entry copied
+        from: Entry,
+        /// The entry replaced
+        to: Entry,
+        /// Whether `to` is the entry in B
+        to_b: bool,
+    },
+}
+
+impl fmt::Display for SyncAction {
+    /// Describes the action, e.g. `copy docs/a.txt to B`.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SyncAction::CopyToB(entry) => write!(f, "copy {} to B", entry.path.display()),
+            SyncAction::CopyToA(entry) => write!(f, "copy {} to A", entry.path.display()),
+            SyncAction::DeleteFromB(entry) => write!(f, "delete {} from B", entry.path.display()),
+            SyncAction::DeleteFromA(entry) => write!(f, "delete {} from A", entry.path.display()),
+            SyncAction::Overwrite { to, to_b, .. } => write!(
+                f,
+                "overwrite {} in {}",
+                to.path.display(),
+                if *to_b { "B" } else { "A" }
+            ),
+        }
+    }
+}
+
+/// Plans the actions that make the compared directories match in
+/// `direction`, in the order they should be applied.
+///
+/// Copies come first, parents before their contents, then overwrites, then
+/// deletions, contents before their parents, so nothing is deleted before
+/// everything was copied. With [`SyncDirection::TwoWay`], the newer file of
+/// each modified pair overwrites the older one; pairs whose modification
+/// times are equal or unknown are left out, since neither side can be
+/// preferred.
+///
+/// # Examples
+///
+/// ```
+/// use std::path::PathBuf;
+/// use dir_compare_core::comparison::{ComparisonResult, Entry, EntryKind};
+/// use dir_compare_core::sync::{SyncDirection, plan_sync};
+///
+/// let entry = |root: &str, path: &str| Entry {
+///     path: PathBuf::from(path),
+///     abs_path: PathBuf::from(root).join(path),
+///     kind: EntryKind::File,
+///     size: Some(1),
+///     nested_repository: false,
+///     mtime: None,
+/// };
+/// let result = ComparisonResult {
+///     a_only: vec![entry("/a", "new.txt")],
+///     b_only: vec![entry("/b", "old.txt")],
+///     both: Vec::new(),
+///     modified: Vec::new(),
+///     renamed: Vec::new(),
+///     exact_counts: None,
+///     warnings: Vec::new(),
+///     stats: Default::default(),
+///     context: None,
+///     identical_subtrees: Vec::new(),
+///     mismatch_reasons: Vec::new(),
+/// };
+/// let actions: Vec<String> = plan_sync(&result, SyncDirection::AToB)
+///     .iter()
+///     .map(|action| action.to_string())
+///     .collect();
+/// assert_eq!(actions, ["copy new.txt to B", "delete old.txt from B"]);
+/// assert_eq!(plan_sync(&result, SyncDirection::TwoWay).len(), 2);
+/// ```
+pub fn plan_sync(result: &ComparisonResult, direction: SyncDirection) -> Vec<SyncAction> {
+    let a_side = missing_on_other_side(&result.a_only, result.renamed.iter().map(|(a, _)| a));
+    let b_side = missing_on_other_side(&result.b_only, result.renamed.iter().map(|(_, b)| b));
+    let mut actions = Vec::new();
+    if direction != SyncDirection::BToA {
+        actions.extend(a_side.iter().copied().cloned().map(SyncAction::CopyToB));
+    }
+    if direction != SyncDirection::AToB {
+        actions.extend(b_side.iter().copied().cloned().map(SyncAction::CopyToA));
+    }
+    for (a, b) in &result.modified {
+        let to_b = match direction {
+            SyncDirection::AToB => true,
+            SyncDirection::BToA => false,
+            SyncDirection::TwoWay => match (a.mtime, b.mtime) {
+                (Some(mtime_a), Some(mtime_b)) if mtime_a != mtime_b => mtime_a > mtime_b,
+                _ => continue,
+            },
+        };
+        let (from, to) = if to_b { (a, b) } else { (b, a) };
+        actions.push(SyncAction::Overwrite {
+            from: from.clone(),
+            to: to.clone(),
+            to_b,
+        });
+    }
+    match direction {
+        SyncDirection::AToB => actions.extend(
+            b_side
+                .iter()
+                .rev()
+                .copied()
+                .cloned()
+                .map(SyncAction::DeleteFromB),
+        ),
+        SyncDirection::BToA => actions.extend(
+            a_side
+                .iter()
+                .rev()
+                .copied()
+                .cloned()
+                .map(SyncAction::DeleteFromA),
+        ),
+        SyncDirection::TwoWay => {}
+    }
+    actions
+}
+
+/// The entries of one side missing at their path on the other: the
+/// one-sided entries and the renamed files, which are copied and deleted
+/// like them, sorted by path
+fn missing_on_other_side<'a>(
+    only: &'a [Entry],
+    renamed: impl Iterator<Item = &'a Entry>,
+) -> Vec<&'a Entry> {
+    let mut entries: Vec<&Entry> = only.iter().chain(renamed).collect();
+    entries.sort_by(|a, b| a.path.cmp(&b.path));
+    entries
+}
+
+/// Options for [`apply_sync`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SyncOptions {
+    /// Root of directory A, which copies to A are made below
+    pub root_a: PathBuf,
+    /// Root of directory B, which copies to B are made below
+    pub root_b: PathBuf,
+    /// Record the actions without touching the filesystem
+    pub dry_run: bool,
+    /// Give copied files the modification time of their source
+    pub preserve_mtimes: bool,
+    /// How files are copied
+    pub copy: CopyOptions,
+}
+
+impl SyncOptions {
+    /// Creates options for syncing `root_a` and `root_b` that apply the
+    /// actions, preserve modification times and verify copies.
+    pub fn new(root_a: impl Into<PathBuf>, root_b: impl Into<PathBuf>) -> Self {
+        Self {
+            root_a: root_a.into(),
+            root_b: root_b.into(),
+            dry_run: false,
+            preserve_mtimes: true,
+            copy: CopyOptions::default(),
+        }
+    }
+}
+
+/// How one action of [`apply_sync`] ended.
+#[derive(Debug, Clone)]
+pub struct SyncOutcome {
+    /// The action
+    pub action: SyncAction,
+    /// Why the action failed; `None` if it succeeded or was not run
+    pub error: Option<String>,
+}
+
+/// The outcomes of [`apply_sync`], in the order the actions ran.
+#[derive(Debug, Clone, Default)]
+pub struct SyncReport {
+    /// Whether the actions were only recorded
+    pub dry_run: bool,
+    /// One outcome per action
+    pub outcomes: Vec<SyncOutcome>,
+}
+
+impl SyncReport {
+    /// Returns the outcomes of actions that failed.
+    pub fn failed(&self) -> impl Iterator<Item = &SyncOutcome> {
+        self.outcomes.iter().filter(|o| o.error.is_some())
+    }
+
+    /// Summarizes the report, listing each failed action with its reason,
+    /// or with a dry run each planned action.
+    pub fn summary(&self) -> String {
+        let mut summary = String::new();
+        if self.dry_run {
+            summary.push_str(&format!(
+                "{} actions planned, nothing changed (dry run)\n",
+                self.outcomes.len()
+            ));
+            for outcome in &self.outcomes {
+                summary.push_str(&format!("  {}\n", outcome.action));
+            }
+            return summary;
+        }
+        let failed = self.failed().count();
+        summary.push_str(&format!(
+            "{} applied, {} failed\n",
+            self.outcomes.len() - failed,
+            failed
+        ));
+        for outcome in self.failed() {
+            summary.push_str(&format!(
+                "  FAILED {}: {}\n",
+                outcome.action,
+                outcome.error.as_deref().unwrap_or_default()
+            ));
+        }
+        summary
+    }
+}
+
+/// Applies `actions` in order and records how each one ended.
+///
+/// Missing parent directories are created. A failed action does not stop
+/// the others; it is recorded with its error. Directories are only deleted
+/// once empty, so a directory holding entries the comparison did not list
+/// is reported as failed rather than deleted with them. With
+/// [`SyncOptions::dry_run`] nothing is touched and every action is recorded
+/// as not failed.
+pub fn apply_sync(
+    fs: &dyn FileSystem,
+    actions: &[SyncAction],
+    options: &SyncOptions,
+) -> SyncReport {
+    let outcomes = actions
+        .iter()
+        .map(|action| SyncOutcome {
+            action: action.clone(),
+            error: if options.dry_run {
+                None
+            } else {
+                apply_action(fs, action, options)
+                    .err()
+                    .map(|e| e.to_string())
+            },
+        })
+        .collect();
+    SyncReport {
+        dry_run: options.dry_run,
+        outcomes,
+    }
+}
+
+fn apply_action(fs: &dyn FileSystem, action: &SyncAction, options: &SyncOptions) -> io::Result<()> {
+    match action {
+        SyncAction::CopyToB(entry) => {
+            copy_entry(fs, entry, &options.root_b.join(&entry.path), options)
+        }
+        SyncAction::CopyToA(entry) => {
+            copy_entry(fs, entry, &options.root_a.join(&entry.path), options)
+        }
+        SyncAction::DeleteFromB(entry) | SyncAction::DeleteFromA(entry) => match entry.kind {
+            EntryKind::Directory => fs.remove_dir(&entry.abs_path),
+            EntryKind::File | EntryKind::Symlink { .. } => fs.remove_file(&entry.abs_path),
+        },
+        SyncAction::Overwrite { from, to, .. } => match (&from.kind, &to.kind) {
+            (EntryKind::Directory, _) | (_, EntryKind::Directory) => Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "a directory and a file cannot replace each other",
+            )),
+            (EntryKind::File, EntryKind::File) => copy_entry(fs, from, &to.abs_path, options),
+            // Copying onto a link would write to its target, and a link
+            // cannot be created over an existing entry
+            _ => {
+                fs.remove_file(&to.abs_path)?;
+                copy_entry(fs, from, &to.abs_path, options)
+            }
+        },
+    }
+}
+
+/// Copies `entry` to `destination`: files with [`copy_file`], directories
+/// as new empty directories and links as new links to the same target.
+fn copy_entry(
+    fs: &dyn FileSystem,
+    entry: &Entry,
+    destination: &Path,
+    options: &SyncOptions,
+) -> io::Result<()> {
+    let target = match &entry.kind {
+        EntryKind::Directory => return fs.create_dir_all(destination),
+        EntryKind::Symlink { target } => target,
+        EntryKind::File => return copy_file_entry(fs, entry, destination, options),
+    };
+    if let Some(parent) = destination.parent().filter(|p| !p.as_os_str().is_empty()) {
+        fs.create_dir_all(parent)?;
+    }
+    fs.create_symlink(target, destination)
+}
+
+fn copy_file_entry(
+    fs: &dyn FileSystem,
+    entry: &Entry,
+    destination: &Path,
+    options: &SyncOptions,
+) -> io::Result<()> {
+    let mut results = ActionResults::new();
+    copy_file(
+        fs,
+        &entry.abs_path,
+        destination,
+        None,
+        &options.copy,
+        &mut results,
+    );
+    if let Some(ActionOutcome::Failed(reason)) = results.results().first().map(|r| &r.outcome) {
+        return Err(io::Error::other(reason.clone()));
+    }
+    if !options.preserve_mtimes {
+        return Ok(());
+    }
+    let mtime = match entry.mtime {
+        Some(mtime) => mtime,
+        None => fs.metadata(&entry.abs_path)?.modified()?,
+    };
+    fs.set_modified(destination, mtime).map_err(|e| {
+        io::Error::new(
+            e.kind(),
+            format!("copied, but the modification time could not be set: {}", e),
+        )
+    })
+}
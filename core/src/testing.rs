@@ -0,0 +1,5 @@
+//! Helpers for tests written against comparison results.
+//!
+//! Only compiled with the `testing` feature.
+
+pub mod assertions;
@@ -0,0 +1,105 @@
+//! Assertions comparing a [`ComparisonResult`] with expected path lists.
+//!
+//! Paths are relative, `/`-separated strings as returned by
+//! [`ComparisonResult::paths`]. Order is ignored, so tests do not depend on
+//! how results are sorted. A failure lists every category that differs, with
+//! `-` marking expected paths that are missing and `+` marking paths that
+//! were not expected:
+//!
+//! ```text
+//! comparison result does not match (- missing, + unexpected):
+//!   a_only:
+//!     - notes.txt
+//!     + notes.bak
+//! ```
+
+use std::collections::BTreeSet;
+
+use crate::comparison::ComparisonResult;
+use crate::output::Section;
+
+/// Asserts that the listed categories of a result hold exactly the given paths.
+///
+/// Categories are `a_only`, `b_only`, `both` and `modified` (also accepted
+/// as `different`); categories that are not listed are not checked.
+///
+/// # Examples
+///
+/// ```
+/// use dir_compare_core::{ComparisonResult, assert_result_matches};
+///
+/// let result = ComparisonResult {
+///     a_only: vec![],
+///     b_only: vec![],
+///     both: vec![],
+///     modified: vec![],
+///     renamed: vec![],
+///     exact_counts: None,
+///     warnings: Vec::new(),
+///     stats: Default::default(),
+///     context: None,
+///     identical_subtrees: Vec::new(),
+///     mismatch_reasons: Vec::new(),
+/// };
+/// assert_result_matches!(result, a_only: [], b_only: [], both: [], different: []);
+/// ```
+#[macro_export]
+macro_rules! assert_result_matches {
+    ($result:expr, $($category:ident : [$($path:expr),* $(,)?]),+ $(,)?) => {
+        $crate::testing::assertions::assert_result_matches(
+            &$result,
+            &[$((stringify!($category), &[$($path),*][..])),+],
+        )
+    };
+}
+
+/// Asserts that each `(category, paths)` pair matches the result exactly.
+///
+/// This is the function behind [`assert_result_matches!`].
+///
+/// # Panics
+///
+/// Panics with a message listing missing and unexpected paths per category
+/// if any category differs, or if a category name is unknown.
+#[track_caller]
+pub fn assert_result_matches(result: &ComparisonResult, expected: &[(&str, &[&str])]) {
+    if let Some(message) = result_mismatch(result, expected) {
+        panic!("{}", message);
+    }
+}
+
+/// Describes how a result differs from the expected paths, or returns
+/// `None` if every listed category matches.
+///
+/// # Panics
+///
+/// Panics if a category name is unknown.
+pub fn result_mismatch(result: &ComparisonResult, expected: &[(&str, &[&str])]) -> Option<String> {
+    let mut message = String::new();
+    for (name, paths) in expected {
+        let category: Section = name
+            .parse()
+            .unwrap_or_else(|e| panic!("assert_result_matches: {}", e));
+        let expected: BTreeSet<&str> = paths.iter().copied().collect();
+        let actual = result.paths(category);
+        let actual: BTreeSet<&str> = actual.iter().map(String::as_str).collect();
+        if expected == actual {
+            continue;
+        }
+        message.push_str(&format!("  {}:\n", name));
+        for path in expected.difference(&actual) {
+            message.push_str(&format!("    - {}\n", path));
+        }
+        for path in actual.difference(&expected) {
+            message.push_str(&format!("    + {}\n", path));
+        }
+    }
+    if message.is_empty() {
+        None
+    } else {
+        Some(format!(
+            "comparison result does not match (- missing, + unexpected):\n{}",
+            message
+        ))
+    }
+}
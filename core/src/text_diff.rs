@@ -0,0 +1,254 @@
+//! Line-level diffs of two text files.
+//!
+//! The lines of both files are matched with a longest common subsequence,
+//! after trimming the lines they share at the start and the end, which
+//! keeps the table small for files that differ in a few places. Only the
+//! first lines of large files are compared; binary files are described by
+//! their sizes and hashes instead.
+
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Read};
+use std::path::Path;
+
+use crate::comparison::{HashAlgorithm, compute_full_hash};
+
+/// Number of leading bytes searched for a NUL byte by [`is_binary`] (8 KiB).
+pub const BINARY_SNIFF_LEN: usize = 8 * 1024;
+
+/// Default number of lines of each file compared by [`diff_files`].
+pub const DEFAULT_MAX_DIFF_LINES: usize = 2_000;
+
+/// How a line of a [`TextDiff`] relates the two inputs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LineChange {
+    /// The line is in both inputs
+    Same,
+    /// The line is only in the first input
+    Removed,
+    /// The line is only in the second input
+    Added,
+}
+
+/// A line of a [`TextDiff`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DiffLine {
+    pub change: LineChange,
+    /// Line number in the first input, starting at 1; `None` for added
+    /// lines
+    pub line_a: Option<usize>,
+    /// Line number in the second input, starting at 1; `None` for removed
+    /// lines
+    pub line_b: Option<usize>,
+    /// The line without its line terminator
+    pub text: String,
+}
+
+/// The lines of two texts in order, each marked as kept, removed or added.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TextDiff {
+    pub lines: Vec<DiffLine>,
+    /// True if either input had more lines than were compared
+    pub truncated: bool,
+}
+
+impl TextDiff {
+    /// Returns true if no line was removed or added.
+    pub fn is_identical(&self) -> bool {
+        self.lines
+            .iter()
+            .all(|line| line.change == LineChange::Same)
+    }
+
+    /// Returns the number of removed and added lines.
+    pub fn changed_lines(&self) -> (usize, usize) {
+        let count = |change| self.lines.iter().filter(|l| l.change == change).count();
+        (count(LineChange::Removed), count(LineChange::Added))
+    }
+}
+
+/// How two files differ, as returned by [`diff_files`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FileDiff {
+    /// Both files are text
+    Text(TextDiff),
+    /// At least one file is binary, see [`is_binary`]
+    Binary {
+        size_a: u64,
+        size_b: u64,
+        /// BLAKE3 hash of the first file, in hex
+        hash_a: String,
+        /// BLAKE3 hash of the second file, in hex
+        hash_b: String,
+    },
+}
+
+/// Returns true if `bytes` hold a NUL byte within their first
+/// [`BINARY_SNIFF_LEN`] bytes, which text files do not.
+pub fn is_binary(bytes: &[u8]) -> bool {
+    bytes[..bytes.len().min(BINARY_SNIFF_LEN)].contains(&0)
+}
+
+/// Diffs the lines of `a` against those of `b`.
+///
+/// # Examples
+///
+/// ```
+/// use dir_compare_core::text_diff::{LineChange, diff_lines};
+///
+/// let lines = diff_lines(&["one", "two"], &["one", "2"]);
+/// let changes: Vec<LineChange> = lines.iter().map(|line| line.change).collect();
+/// assert_eq!(
+///     changes,
+///     [LineChange::Same, LineChange::Removed, LineChange::Added]
+/// );
+/// ```
+pub fn diff_lines<S: AsRef<str>>(a: &[S], b: &[S]) -> Vec<DiffLine> {
+    let prefix = a
+        .iter()
+        .zip(b)
+        .take_while(|(a, b)| a.as_ref() == b.as_ref())
+        .count();
+    let suffix = a[prefix..]
+        .iter()
+        .rev()
+        .zip(b[prefix..].iter().rev())
+        .take_while(|(a, b)| a.as_ref() == b.as_ref())
+        .count();
+    let middle_a = &a[prefix..a.len() - suffix];
+    let middle_b = &b[prefix..b.len() - suffix];
+
+    let mut lines = Vec::with_capacity(a.len().max(b.len()));
+    let same = |i: usize, j: usize, text: &str| DiffLine {
+        change: LineChange::Same,
+        line_a: Some(i + 1),
+        line_b: Some(j + 1),
+        text: text.to_string(),
+    };
+    lines.extend(
+        a[..prefix]
+            .iter()
+            .enumerate()
+            .map(|(i, text)| same(i, i, text.as_ref())),
+    );
+
+    // lengths[i][j]: longest common subsequence of middle_a[i..] and
+    // middle_b[j..]
+    let width = middle_b.len() + 1;
+    let mut lengths = vec![0u32; (middle_a.len() + 1) * width];
+    for i in (0..middle_a.len()).rev() {
+        for j in (0..middle_b.len()).rev() {
+            lengths[i * width + j] = if middle_a[i].as_ref() == middle_b[j].as_ref() {
+                lengths[(i + 1) * width + j + 1] + 1
+            } else {
+                lengths[(i + 1) * width + j].max(lengths[i * width + j + 1])
+            };
+        }
+    }
+    let (mut i, mut j) = (0, 0);
+    let removed = |i: usize| DiffLine {
+        change: LineChange::Removed,
+        line_a: Some(prefix + i + 1),
+        line_b: None,
+        text: middle_a[i].as_ref().to_string(),
+    };
+    let added = |j: usize| DiffLine {
+        change: LineChange::Added,
+        line_a: None,
+        line_b: Some(prefix + j + 1),
+        text: middle_b[j].as_ref().to_string(),
+    };
+    while i < middle_a.len() && j < middle_b.len() {
+        if middle_a[i].as_ref() == middle_b[j].as_ref() {
+            lines.push(same(prefix + i, prefix + j, middle_a[i].as_ref()));
+            i += 1;
+            j += 1;
+        } else if lengths[(i + 1) * width + j] >= lengths[i * width + j + 1] {
+            lines.push(removed(i));
+            i += 1;
+        } else {
+            lines.push(added(j));
+            j += 1;
+        }
+    }
+    lines.extend((i..middle_a.len()).map(removed));
+    lines.extend((j..middle_b.len()).map(added));
+
+    let start_a = a.len() - suffix;
+    let start_b = b.len() - suffix;
+    lines.extend(
+        a[start_a..]
+            .iter()
+            .enumerate()
+            .map(|(k, text)| same(start_a + k, start_b + k, text.as_ref())),
+    );
+    lines
+}
+
+/// Diffs the first `max_lines` lines of `a` against those of `b`.
+///
+/// Lines end at `\n`; a `\r` before it stays part of the line, so a change
+/// of line endings shows as changed lines.
+pub fn diff_text(a: &str, b: &str, max_lines: usize) -> TextDiff {
+    let (lines_a, truncated_a) = first_lines(a.split_terminator('\n'), max_lines);
+    let (lines_b, truncated_b) = first_lines(b.split_terminator('\n'), max_lines);
+    TextDiff {
+        lines: diff_lines(&lines_a, &lines_b),
+        truncated: truncated_a || truncated_b,
+    }
+}
+
+/// Diffs the files at `a` and `b`.
+///
+/// If either file holds a NUL byte in its first [`BINARY_SNIFF_LEN`] bytes,
+/// the result lists the sizes and BLAKE3 hashes of both. Otherwise only the
+/// first `max_lines` lines of each file are read and compared, with invalid
+/// UTF-8 replaced by U+FFFD.
+pub fn diff_files(a: &Path, b: &Path, max_lines: usize) -> io::Result<FileDiff> {
+    let (head_a, file_a) = read_head(a)?;
+    let (head_b, file_b) = read_head(b)?;
+    if is_binary(&head_a) || is_binary(&head_b) {
+        return Ok(FileDiff::Binary {
+            size_a: file_a.metadata()?.len(),
+            size_b: file_b.metadata()?.len(),
+            hash_a: compute_full_hash(a, HashAlgorithm::Blake3)?,
+            hash_b: compute_full_hash(b, HashAlgorithm::Blake3)?,
+        });
+    }
+    let (lines_a, truncated_a) = read_lines(head_a, file_a, max_lines)?;
+    let (lines_b, truncated_b) = read_lines(head_b, file_b, max_lines)?;
+    Ok(FileDiff::Text(TextDiff {
+        lines: diff_lines(&lines_a, &lines_b),
+        truncated: truncated_a || truncated_b,
+    }))
+}
+
+/// Takes up to `max_lines` of `lines`; the flag is true if more followed.
+fn first_lines<'a>(
+    mut lines: impl Iterator<Item = &'a str>,
+    max_lines: usize,
+) -> (Vec<&'a str>, bool) {
+    let taken: Vec<&str> = lines.by_ref().take(max_lines).collect();
+    (taken, lines.next().is_some())
+}
+
+/// Opens `path` and reads its first [`BINARY_SNIFF_LEN`] bytes.
+fn read_head(path: &Path) -> io::Result<(Vec<u8>, File)> {
+    let mut file = File::open(path)?;
+    let mut head = Vec::with_capacity(BINARY_SNIFF_LEN);
+    (&mut file)
+        .take(BINARY_SNIFF_LEN as u64)
+        .read_to_end(&mut head)?;
+    Ok((head, file))
+}
+
+/// Reads up to `max_lines` lines of a file whose first bytes, `head`, were
+/// already read; the flag is true if more lines followed.
+fn read_lines(head: Vec<u8>, file: File, max_lines: usize) -> io::Result<(Vec<String>, bool)> {
+    let mut lines = BufReader::new(io::Cursor::new(head).chain(file)).split(b'\n');
+    let mut taken = Vec::new();
+    for line in lines.by_ref().take(max_lines) {
+        taken.push(String::from_utf8_lossy(&line?).into_owned());
+    }
+    let more = lines.next().transpose()?.is_some();
+    Ok((taken, more))
+}
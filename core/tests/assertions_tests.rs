@@ -0,0 +1,117 @@
+use dir_compare_core::assert_result_matches;
+use dir_compare_core::comparison::{ComparisonResult, Entry, EntryKind};
+use dir_compare_core::output::Section;
+use dir_compare_core::testing::assertions::result_mismatch;
+use std::panic::{self, AssertUnwindSafe};
+use std::path::PathBuf;
+
+fn entry(path: &str) -> Entry {
+    Entry {
+        path: PathBuf::from(path),
+        abs_path: PathBuf::from(path),
+        kind: EntryKind::File,
+        size: Some(1),
+        nested_repository: false,
+        mtime: None,
+    }
+}
+
+fn sample_result() -> ComparisonResult {
+    ComparisonResult {
+        a_only: vec![entry("only_a.txt"), entry("docs/guide.md")],
+        b_only: vec![entry("only_b.txt")],
+        both: vec![(entry("common.txt"), entry("common.txt"))],
+        modified: vec![(entry("changed.txt"), entry("changed.txt"))],
+        exact_counts: None,
+        warnings: Vec::new(),
+        stats: Default::default(),
+        context: None,
+        identical_subtrees: Vec::new(),
+        mismatch_reasons: Vec::new(),
+        renamed: Vec::new(),
+    }
+}
+
+/// Runs `f`, expecting it to panic, and returns the panic message
+fn panic_message(f: impl FnOnce()) -> String {
+    let payload =
+        panic::catch_unwind(AssertUnwindSafe(f)).expect_err("assertion should have failed");
+    payload
+        .downcast_ref::<String>()
+        .cloned()
+        .or_else(|| payload.downcast_ref::<&str>().map(|s| s.to_string()))
+        .unwrap()
+}
+
+#[test]
+fn test_paths_per_category() {
+    let result = sample_result();
+    assert_eq!(
+        result.paths(Section::AOnly),
+        vec!["only_a.txt", "docs/guide.md"]
+    );
+    assert_eq!(result.paths(Section::BOnly), vec!["only_b.txt"]);
+    assert_eq!(result.paths(Section::Both), vec!["common.txt"]);
+    assert_eq!(result.paths(Section::Modified), vec!["changed.txt"]);
+}
+
+#[test]
+fn test_matching_result_passes_in_any_order() {
+    assert_result_matches!(
+        sample_result(),
+        a_only: ["docs/guide.md", "only_a.txt"],
+        b_only: ["only_b.txt"],
+        both: ["common.txt"],
+        different: ["changed.txt"],
+    );
+}
+
+#[test]
+fn test_unlisted_categories_are_not_checked() {
+    assert_result_matches!(sample_result(), modified: ["changed.txt"]);
+}
+
+#[test]
+fn test_failure_lists_missing_and_unexpected_paths() {
+    let message = panic_message(|| {
+        assert_result_matches!(
+            sample_result(),
+            a_only: ["only_a.txt", "missing.txt"],
+            b_only: [],
+            both: ["common.txt"],
+        );
+    });
+
+    assert_eq!(
+        message,
+        "comparison result does not match (- missing, + unexpected):\n\
+         \x20 a_only:\n\
+         \x20   - missing.txt\n\
+         \x20   + docs/guide.md\n\
+         \x20 b_only:\n\
+         \x20   + only_b.txt\n"
+    );
+}
+
+#[test]
+fn test_failure_names_category_alias_as_written() {
+    let message = panic_message(|| {
+        assert_result_matches!(sample_result(), different: []);
+    });
+    assert!(message.contains("  different:\n    + changed.txt\n"));
+}
+
+#[test]
+fn test_unknown_category_panics() {
+    let message = panic_message(|| {
+        assert_result_matches!(sample_result(), same: []);
+    });
+    assert!(message.contains("unknown section 'same'"));
+}
+
+#[test]
+fn test_result_mismatch_without_panicking() {
+    let result = sample_result();
+    assert_eq!(result_mismatch(&result, &[("both", &["common.txt"])]), None);
+    assert!(result_mismatch(&result, &[("both", &[])]).is_some());
+}
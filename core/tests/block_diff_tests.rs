@@ -0,0 +1,140 @@
+use dir_compare_core::block_diff::{DiffRegion, diff_blocks, differing_pairs};
+use dir_compare_core::comparison::{ComparisonResult, Entry, EntryKind};
+use std::io::Cursor;
+use std::path::PathBuf;
+
+const BLOCK: usize = 16;
+
+fn diff(a: &[u8], b: &[u8]) -> dir_compare_core::BlockDiff {
+    diff_blocks(Cursor::new(a), Cursor::new(b), BLOCK).unwrap()
+}
+
+fn region(start: u64, end: u64) -> DiffRegion {
+    DiffRegion { start, end }
+}
+
+#[test]
+fn test_identical_buffers() {
+    let data = vec![7u8; 100];
+    let result = diff(&data, &data);
+    assert!(result.is_identical());
+    assert_eq!(result.len_a, 100);
+    assert_eq!(result.len_b, 100);
+    assert_eq!(result.differing_bytes(), 0);
+}
+
+#[test]
+fn test_empty_buffers() {
+    let result = diff(b"", b"");
+    assert!(result.is_identical());
+    assert_eq!(result.total_bytes(), 0);
+}
+
+#[test]
+fn test_single_byte_change() {
+    let a = vec![0u8; 64];
+    let mut b = a.clone();
+    b[20] = 1;
+    let result = diff(&a, &b);
+    assert_eq!(result.regions, vec![region(16, 32)]);
+    assert_eq!(result.differing_bytes(), 16);
+}
+
+#[test]
+fn test_change_spanning_block_boundary() {
+    let a = vec![0u8; 64];
+    let mut b = a.clone();
+    b[15] = 1;
+    b[16] = 1;
+    let result = diff(&a, &b);
+    // Two adjacent differing blocks are merged into one region
+    assert_eq!(result.regions, vec![region(0, 32)]);
+}
+
+#[test]
+fn test_separate_regions_are_not_merged() {
+    let a = vec![0u8; 64];
+    let mut b = a.clone();
+    b[0] = 1;
+    b[63] = 1;
+    let result = diff(&a, &b);
+    assert_eq!(result.regions, vec![region(0, 16), region(48, 64)]);
+}
+
+#[test]
+fn test_appended_tail() {
+    let a = vec![0u8; 40];
+    let mut b = a.clone();
+    b.extend_from_slice(&[9u8; 30]);
+    let result = diff(&a, &b);
+    assert_eq!(result.len_a, 40);
+    assert_eq!(result.len_b, 70);
+    assert_eq!(result.regions, vec![region(40, 70)]);
+}
+
+#[test]
+fn test_truncated_tail_with_change_in_prefix() {
+    let mut a = vec![0u8; 70];
+    a[3] = 5;
+    let b = vec![0u8; 40];
+    let result = diff(&a, &b);
+    assert_eq!(result.len_a, 70);
+    assert_eq!(result.len_b, 40);
+    assert_eq!(result.regions, vec![region(0, 16), region(40, 70)]);
+}
+
+#[test]
+fn test_tail_merges_with_differing_last_block() {
+    let a = vec![0u8; 40];
+    let mut b = a.clone();
+    b[39] = 1;
+    b.extend_from_slice(&[0u8; 10]);
+    let result = diff(&a, &b);
+    assert_eq!(result.regions, vec![region(32, 50)]);
+}
+
+#[test]
+fn test_zero_block_size_is_rejected() {
+    assert!(diff_blocks(Cursor::new(b"a"), Cursor::new(b"a"), 0).is_err());
+}
+
+#[test]
+fn test_differing_pairs_only_same_path_files() {
+    let entry = |path: &str, kind: EntryKind| Entry {
+        path: PathBuf::from(path),
+        abs_path: PathBuf::from(path),
+        kind,
+        size: None,
+        nested_repository: false,
+        mtime: None,
+    };
+    let result = ComparisonResult {
+        a_only: vec![entry("only_a.txt", EntryKind::File)],
+        b_only: vec![entry("only_b.txt", EntryKind::File)],
+        both: vec![],
+        modified: vec![
+            (
+                entry("changed.txt", EntryKind::File),
+                entry("changed.txt", EntryKind::File),
+            ),
+            (
+                entry("dir", EntryKind::Directory),
+                entry("dir", EntryKind::Directory),
+            ),
+            (
+                entry("became_dir", EntryKind::File),
+                entry("became_dir", EntryKind::Directory),
+            ),
+        ],
+        exact_counts: None,
+        warnings: Vec::new(),
+        stats: Default::default(),
+        context: None,
+        identical_subtrees: Vec::new(),
+        mismatch_reasons: Vec::new(),
+        renamed: Vec::new(),
+    };
+    let pairs = differing_pairs(&result);
+    assert_eq!(pairs.len(), 1);
+    assert_eq!(pairs[0].0.path, PathBuf::from("changed.txt"));
+}
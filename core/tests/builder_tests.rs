@@ -0,0 +1,337 @@
+use dir_compare_core::Comparison;
+use dir_compare_core::comparison::{
+    ComparisonOptions, ComparisonResult, ComparisonStrategy, ComparisonStrategyType, EntryFilter,
+    FastHashStrategy, FilenameOnlyStrategy, FlatComparisonOptions, FlatComparisonResult,
+    ResultDetail, compare_directories, compare_directories_flat, plan_comparison,
+};
+use dir_compare_core::strategy_defaults::{self, StrategySettings};
+use std::fs;
+use std::path::{Path, PathBuf};
+use tempfile::TempDir;
+
+/// Optional settings of hierarchy mode, as bits of a combination
+const STRATEGY: u32 = 1;
+const IGNORE_FILE: u32 = 1 << 1;
+const FILTER: u32 = 1 << 2;
+const THREADS: u32 = 1 << 3;
+const MAX_DEPTH: u32 = 1 << 4;
+const RESPECT_GITIGNORE: u32 = 1 << 5;
+const IGNORE_HIDDEN: u32 = 1 << 6;
+const RESULT_DETAIL: u32 = 1 << 7;
+const HIERARCHY_SETTINGS: u32 = 8;
+
+/// Optional settings of flat mode, as bits of a combination; the shared
+/// ones keep their hierarchy mode bits
+const FULL_HASH: u32 = 1;
+const EXTENSIONS: u32 = 1 << 3;
+const MIN_FILE_SIZE: u32 = 1 << 7;
+const FLAT_SETTINGS: u32 = 8;
+
+fn write(root: &Path, path: &str, contents: &[u8]) {
+    let path = root.join(path);
+    fs::create_dir_all(path.parent().unwrap()).unwrap();
+    fs::write(path, contents).unwrap();
+}
+
+/// A fixture on which every setting but the thread count changes some
+/// result:
+/// - `changed.txt` differs in content, so only matches by name
+/// - `big.bin` differs only between the samples, so only matches by sample
+/// - `only.log` in A and `only.tmp` in B are excluded by the filter and the
+///   ignore file
+/// - `sub/deep/nested.txt` in A is below depth 1
+/// - `.hidden.txt` in A is hidden
+/// - `gitignored.txt` in A is ignored by A's `.gitignore`
+/// - `tiny.txt` holds a single byte
+fn setup() -> TempDir {
+    let temp = TempDir::new().unwrap();
+    let (a, b) = (temp.path().join("a"), temp.path().join("b"));
+    for dir in [&a, &b] {
+        write(dir, "same.txt", b"same");
+        write(dir, "tiny.txt", b"t");
+    }
+    write(&a, "changed.txt", b"first");
+    write(&b, "changed.txt", b"second version");
+    let mut content = vec![b'x'; 5000];
+    write(&a, "big.bin", &content);
+    content[500] = b'y';
+    write(&b, "big.bin", &content);
+    write(&a, "only.log", b"log");
+    write(&b, "only.tmp", b"tmp");
+    write(&a, "sub/deep/nested.txt", b"nested");
+    write(&a, ".hidden.txt", b"hidden");
+    write(&a, ".gitignore", b"gitignored.txt\n");
+    write(&a, "gitignored.txt", b"ignored");
+    fs::write(temp.path().join("ignore"), "*.tmp\n").unwrap();
+    temp
+}
+
+fn filter() -> EntryFilter {
+    EntryFilter::new(&[], &["*.log"]).unwrap()
+}
+
+/// The strategy of comparisons without one: sampled hashing, as
+/// `dir-compare` compares without `--method`
+fn default_strategy(root: &Path) -> Box<dyn ComparisonStrategy + Send> {
+    let case_insensitive =
+        strategy_defaults::case_insensitive(None, &root.join("a"), &root.join("b"));
+    StrategySettings::new(ComparisonStrategyType::SampledHash, case_insensitive).build()
+}
+
+/// The paths of each category, sorted, for comparing results
+fn categories(result: &ComparisonResult) -> [Vec<PathBuf>; 4] {
+    let sorted = |mut paths: Vec<PathBuf>| {
+        paths.sort();
+        paths
+    };
+    [
+        sorted(result.a_only.iter().map(|e| e.path.clone()).collect()),
+        sorted(result.b_only.iter().map(|e| e.path.clone()).collect()),
+        sorted(result.both.iter().map(|(a, _)| a.path.clone()).collect()),
+        sorted(
+            result
+                .modified
+                .iter()
+                .map(|(a, _)| a.path.clone())
+                .collect(),
+        ),
+    ]
+}
+
+/// The files of each content group, sorted, for comparing results
+fn groups(result: &FlatComparisonResult) -> Vec<(Vec<PathBuf>, Vec<PathBuf>)> {
+    let mut groups: Vec<_> = result
+        .groups
+        .iter()
+        .map(|group| {
+            let (mut a, mut b) = (group.files_in_a.clone(), group.files_in_b.clone());
+            a.sort();
+            b.sort();
+            (a, b)
+        })
+        .collect();
+    groups.sort();
+    groups
+}
+
+/// Runs the hierarchy mode comparison of `settings` with the builder
+fn build_hierarchy(root: &Path, settings: u32) -> [Vec<PathBuf>; 4] {
+    let strategy = FilenameOnlyStrategy::new(false);
+    let mut comparison = Comparison::new(root.join("a"), root.join("b"));
+    if settings & STRATEGY != 0 {
+        comparison = comparison.strategy(&strategy);
+    }
+    if settings & IGNORE_FILE != 0 {
+        comparison = comparison.ignore_file(root.join("ignore"));
+    }
+    if settings & FILTER != 0 {
+        comparison = comparison.filter(filter());
+    }
+    if settings & THREADS != 0 {
+        comparison = comparison.threads(1);
+    }
+    if settings & MAX_DEPTH != 0 {
+        comparison = comparison.max_depth(1);
+    }
+    if settings & RESPECT_GITIGNORE != 0 {
+        comparison = comparison.respect_gitignore(true);
+    }
+    if settings & IGNORE_HIDDEN != 0 {
+        comparison = comparison.ignore_hidden(true);
+    }
+    if settings & RESULT_DETAIL != 0 {
+        comparison = comparison.result_detail(ResultDetail::Sampled { per_category: 1 });
+    }
+    categories(&comparison.run().unwrap())
+}
+
+/// Runs the hierarchy mode comparison of `settings` with [`plan_comparison`]
+/// and hand-written options
+fn plan_hierarchy(root: &Path, settings: u32) -> [Vec<PathBuf>; 4] {
+    let strategy: Box<dyn ComparisonStrategy + Send> = if settings & STRATEGY != 0 {
+        Box::new(FilenameOnlyStrategy::new(false))
+    } else {
+        default_strategy(root)
+    };
+    let ignore_file = (settings & IGNORE_FILE != 0).then(|| root.join("ignore"));
+    let options = ComparisonOptions {
+        filter: (settings & FILTER != 0).then(filter),
+        threads: (settings & THREADS != 0).then_some(1),
+        max_depth: (settings & MAX_DEPTH != 0).then_some(1),
+        respect_gitignore: settings & RESPECT_GITIGNORE != 0,
+        ignore_hidden: settings & IGNORE_HIDDEN != 0,
+        result_detail: if settings & RESULT_DETAIL != 0 {
+            ResultDetail::Sampled { per_category: 1 }
+        } else {
+            ResultDetail::Full
+        },
+        ..Default::default()
+    };
+    let plan = plan_comparison(
+        &root.join("a"),
+        &root.join("b"),
+        strategy.as_ref(),
+        ignore_file.as_deref(),
+        &options,
+        &|_| {},
+    )
+    .unwrap();
+    categories(&plan.run(&|_| {}).unwrap())
+}
+
+/// Runs the flat mode comparison of `settings` with the builder
+fn build_flat(root: &Path, settings: u32) -> Vec<(Vec<PathBuf>, Vec<PathBuf>)> {
+    let mut comparison = Comparison::new(root.join("a"), root.join("b"));
+    if settings & FULL_HASH != 0 {
+        comparison = comparison.full_hash(true);
+    }
+    if settings & IGNORE_FILE != 0 {
+        comparison = comparison.ignore_file(root.join("ignore"));
+    }
+    if settings & FILTER != 0 {
+        comparison = comparison.filter(filter());
+    }
+    if settings & EXTENSIONS != 0 {
+        comparison = comparison.extensions(vec!["txt".to_string()]);
+    }
+    if settings & MAX_DEPTH != 0 {
+        comparison = comparison.max_depth(1);
+    }
+    if settings & RESPECT_GITIGNORE != 0 {
+        comparison = comparison.respect_gitignore(true);
+    }
+    if settings & IGNORE_HIDDEN != 0 {
+        comparison = comparison.ignore_hidden(true);
+    }
+    if settings & MIN_FILE_SIZE != 0 {
+        comparison = comparison.min_file_size(2);
+    }
+    groups(&comparison.run_flat().unwrap())
+}
+
+/// Runs the flat mode comparison of `settings` with hand-written options
+fn options_flat(root: &Path, settings: u32) -> Vec<(Vec<PathBuf>, Vec<PathBuf>)> {
+    let ignore_file = (settings & IGNORE_FILE != 0).then(|| root.join("ignore"));
+    let options = FlatComparisonOptions {
+        use_full_hash: settings & FULL_HASH != 0,
+        filter: (settings & FILTER != 0).then(filter),
+        extensions: (settings & EXTENSIONS != 0).then(|| vec!["txt".to_string()]),
+        max_depth: (settings & MAX_DEPTH != 0).then_some(1),
+        respect_gitignore: settings & RESPECT_GITIGNORE != 0,
+        ignore_hidden: settings & IGNORE_HIDDEN != 0,
+        min_file_size: if settings & MIN_FILE_SIZE != 0 { 2 } else { 0 },
+        ..Default::default()
+    };
+    let result = compare_directories_flat(
+        &root.join("a"),
+        &root.join("b"),
+        &options,
+        ignore_file.as_deref(),
+    );
+    groups(&result.unwrap())
+}
+
+#[test]
+fn test_every_combination_of_hierarchy_settings() {
+    let temp = setup();
+    for settings in 0..1 << HIERARCHY_SETTINGS {
+        assert_eq!(
+            build_hierarchy(temp.path(), settings),
+            plan_hierarchy(temp.path(), settings),
+            "settings {:#010b}",
+            settings
+        );
+    }
+}
+
+#[test]
+fn test_every_combination_of_flat_settings() {
+    let temp = setup();
+    for settings in 0..1 << FLAT_SETTINGS {
+        assert_eq!(
+            build_flat(temp.path(), settings),
+            options_flat(temp.path(), settings),
+            "settings {:#010b}",
+            settings
+        );
+    }
+}
+
+#[test]
+fn test_fixture_catches_dropped_settings() {
+    let temp = setup();
+    let root = temp.path();
+    let defaults = build_hierarchy(root, 0);
+    for bit in 0..HIERARCHY_SETTINGS {
+        let settings = 1 << bit;
+        // The thread count never changes the result
+        if settings != THREADS {
+            assert_ne!(build_hierarchy(root, settings), defaults, "{:#b}", settings);
+        }
+    }
+    let defaults = build_flat(root, 0);
+    for bit in 0..FLAT_SETTINGS {
+        let settings = 1 << bit;
+        assert_ne!(build_flat(root, settings), defaults, "{:#b}", settings);
+    }
+}
+
+#[test]
+fn test_none_restores_defaults() {
+    let temp = setup();
+    let (a, b) = (temp.path().join("a"), temp.path().join("b"));
+    let reset = Comparison::new(&a, &b)
+        .ignore_file(temp.path().join("ignore"))
+        .ignore_file(None)
+        .filter(filter())
+        .filter(None)
+        .max_depth(1)
+        .max_depth(None)
+        .extensions(vec!["txt".to_string()])
+        .extensions(None);
+    assert_eq!(
+        categories(&reset.run().unwrap()),
+        build_hierarchy(temp.path(), 0)
+    );
+    assert_eq!(
+        groups(&reset.run_flat().unwrap()),
+        build_flat(temp.path(), 0)
+    );
+}
+
+#[test]
+fn test_free_functions_match_builder() {
+    let temp = setup();
+    let (a, b) = (temp.path().join("a"), temp.path().join("b"));
+    let strategy = FastHashStrategy::new(false);
+    let ignore = temp.path().join("ignore");
+    let result = compare_directories(&a, &b, &strategy, Some(&ignore)).unwrap();
+    let built = Comparison::new(&a, &b)
+        .strategy(&strategy)
+        .ignore_file(ignore)
+        .run()
+        .unwrap();
+    assert_eq!(categories(&result), categories(&built));
+    // Without a strategy, big.bin matches by its samples
+    let defaults = build_hierarchy(temp.path(), 0);
+    assert!(
+        defaults[2].contains(&PathBuf::from("big.bin")),
+        "{:?}",
+        defaults
+    );
+    assert_ne!(categories(&result), defaults);
+}
+
+#[test]
+fn test_plan_estimates_before_running() {
+    let temp = setup();
+    let strategy = FastHashStrategy::new(false);
+    let comparison =
+        Comparison::new(temp.path().join("a"), temp.path().join("b")).strategy(&strategy);
+    let plan = comparison.plan().unwrap();
+    // same.txt, tiny.txt and big.bin; changed.txt differs in size and is
+    // not read
+    assert_eq!(plan.estimate().content_pairs, 3);
+    let result = plan.run(&|_| {}).unwrap();
+    assert_eq!(result.modified.len(), 2);
+}
@@ -0,0 +1,157 @@
+use dir_compare_core::filesystem::{
+    CaseSensitivity, FileSystem, RealFileSystem, detect_case_insensitive_roots,
+    detect_case_sensitivity,
+};
+use std::cell::RefCell;
+use std::collections::HashSet;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// An in-memory directory that may fold case and may refuse new files
+#[derive(Default)]
+struct MockFileSystem {
+    files: RefCell<HashSet<PathBuf>>,
+    case_insensitive: bool,
+    read_only: bool,
+}
+
+impl MockFileSystem {
+    fn key(&self, path: &Path) -> PathBuf {
+        if self.case_insensitive {
+            PathBuf::from(path.to_string_lossy().to_lowercase())
+        } else {
+            path.to_path_buf()
+        }
+    }
+}
+
+impl FileSystem for MockFileSystem {
+    fn canonicalize(&self, path: &Path) -> io::Result<PathBuf> {
+        Ok(path.to_path_buf())
+    }
+
+    fn metadata(&self, _path: &Path) -> io::Result<fs::Metadata> {
+        Err(io::Error::other("metadata is not simulated"))
+    }
+
+    fn remove_file(&self, path: &Path) -> io::Result<()> {
+        if self.files.borrow_mut().remove(&self.key(path)) {
+            Ok(())
+        } else {
+            Err(io::ErrorKind::NotFound.into())
+        }
+    }
+
+    fn create_new(&self, path: &Path) -> io::Result<()> {
+        if self.read_only {
+            return Err(io::ErrorKind::PermissionDenied.into());
+        }
+        if !self.files.borrow_mut().insert(self.key(path)) {
+            return Err(io::ErrorKind::AlreadyExists.into());
+        }
+        Ok(())
+    }
+
+    fn exists(&self, path: &Path) -> bool {
+        self.files.borrow().contains(&self.key(path))
+    }
+}
+
+#[test]
+fn test_detects_case_sensitive_filesystem() {
+    let fs = MockFileSystem::default();
+    assert_eq!(
+        detect_case_sensitivity(&fs, Path::new("/data")),
+        CaseSensitivity::Sensitive
+    );
+    assert!(fs.files.borrow().is_empty(), "probe must be removed");
+}
+
+#[test]
+fn test_detects_case_insensitive_filesystem() {
+    let fs = MockFileSystem {
+        case_insensitive: true,
+        ..Default::default()
+    };
+    assert_eq!(
+        detect_case_sensitivity(&fs, Path::new("/data")),
+        CaseSensitivity::Insensitive
+    );
+    assert!(fs.files.borrow().is_empty(), "probe must be removed");
+}
+
+#[test]
+fn test_failed_probe_is_unknown() {
+    let fs = MockFileSystem {
+        case_insensitive: true,
+        read_only: true,
+        ..Default::default()
+    };
+    assert_eq!(
+        detect_case_sensitivity(&fs, Path::new("/data")),
+        CaseSensitivity::Unknown
+    );
+}
+
+#[test]
+fn test_existing_probe_name_is_unknown() {
+    let fs = MockFileSystem::default();
+    let name = format!(".DIR-COMPARE-CASE-PROBE-{}", std::process::id());
+    fs.files.borrow_mut().insert(Path::new("/data").join(name));
+
+    assert_eq!(
+        detect_case_sensitivity(&fs, Path::new("/data")),
+        CaseSensitivity::Unknown
+    );
+    assert_eq!(fs.files.borrow().len(), 1, "existing files are left alone");
+}
+
+#[test]
+fn test_roots_default_to_case_sensitive_unless_both_fold_case() {
+    let insensitive = MockFileSystem {
+        case_insensitive: true,
+        ..Default::default()
+    };
+    assert!(detect_case_insensitive_roots(
+        &insensitive,
+        Path::new("/a"),
+        Path::new("/b")
+    ));
+
+    let sensitive = MockFileSystem::default();
+    assert!(!detect_case_insensitive_roots(
+        &sensitive,
+        Path::new("/a"),
+        Path::new("/b")
+    ));
+
+    let unknown = MockFileSystem {
+        case_insensitive: true,
+        read_only: true,
+        ..Default::default()
+    };
+    assert!(!detect_case_insensitive_roots(
+        &unknown,
+        Path::new("/a"),
+        Path::new("/b")
+    ));
+}
+
+#[test]
+fn test_real_probe_cleans_up() {
+    let temp = tempfile::tempdir().unwrap();
+    let sensitivity = detect_case_sensitivity(&RealFileSystem, temp.path());
+
+    assert_ne!(sensitivity, CaseSensitivity::Unknown);
+    assert_eq!(fs::read_dir(temp.path()).unwrap().count(), 0);
+}
+
+#[test]
+fn test_real_probe_of_missing_directory_is_unknown() {
+    let temp = tempfile::tempdir().unwrap();
+    assert_eq!(
+        detect_case_sensitivity(&RealFileSystem, &temp.path().join("missing")),
+        CaseSensitivity::Unknown
+    );
+}
@@ -0,0 +1,245 @@
+use dir_compare_core::chunk_dedupe::{ChunkDedupeOptions, Chunker, DEFAULT_AVG_CHUNK_SIZE};
+use dir_compare_core::comparison::{
+    FlatComparisonOptions, FlatComparisonResult, ProgressPhase, compare_directories_flat,
+    compare_directories_flat_with_progress,
+};
+use dir_compare_core::output::{
+    FlatFormatter, FlatHtmlFormatter, FlatJsonFormatter, FlatMarkdownFormatter, FlatTextFormatter,
+};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use tempfile::TempDir;
+
+const MIB: usize = 1024 * 1024;
+
+/// `len` pseudo-random bytes, the same for the same `seed`
+fn noise(seed: u64, len: usize) -> Vec<u8> {
+    let mut state = seed.wrapping_mul(0x9E37_79B9_7F4A_7C15) | 1;
+    (0..len)
+        .map(|_| {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            state as u8
+        })
+        .collect()
+}
+
+/// A and B each hold `disk.img`, embedding the same 1 MiB block between
+/// different data at different offsets, and an unrelated `other.bin`.
+fn setup() -> TempDir {
+    let temp = TempDir::new().unwrap();
+    let (a, b) = (temp.path().join("a"), temp.path().join("b"));
+    fs::create_dir_all(&a).unwrap();
+    fs::create_dir_all(&b).unwrap();
+    let block = noise(1, MIB);
+    fs::write(
+        a.join("disk.img"),
+        [noise(2, 300_000), block.clone(), noise(3, 300_000)].concat(),
+    )
+    .unwrap();
+    fs::write(
+        b.join("disk.img"),
+        [noise(4, 500_000), block, noise(5, 100_000)].concat(),
+    )
+    .unwrap();
+    fs::write(a.join("other.bin"), noise(6, 200_000)).unwrap();
+    fs::write(b.join("other.bin"), noise(7, 200_000)).unwrap();
+    temp
+}
+
+fn compare(root: &Path, chunk_dedupe: Option<ChunkDedupeOptions>) -> FlatComparisonResult {
+    let options = FlatComparisonOptions {
+        chunk_dedupe,
+        ..Default::default()
+    };
+    compare_directories_flat(&root.join("a"), &root.join("b"), &options, None).unwrap()
+}
+
+/// Chunk lengths of `data`
+fn chunk_lengths(chunker: &Chunker, data: &[u8]) -> Vec<(u128, u64)> {
+    let mut chunks = Vec::new();
+    chunker
+        .chunks(data, |hash, len| chunks.push((hash, len)))
+        .unwrap();
+    chunks
+}
+
+#[test]
+fn test_chunker_bounds_chunk_sizes() {
+    let chunker = Chunker::new(4096);
+    let data = noise(8, 500_000);
+    let chunks = chunk_lengths(&chunker, &data);
+
+    assert_eq!(chunks.iter().map(|(_, len)| len).sum::<u64>(), 500_000);
+    let (last, rest) = chunks.split_last().unwrap();
+    for (_, len) in rest {
+        assert!(
+            (chunker.min_size() as u64..=chunker.max_size() as u64).contains(len),
+            "{}",
+            len
+        );
+    }
+    assert!(last.1 <= chunker.max_size() as u64);
+    // About the average size
+    let average = 500_000 / chunks.len();
+    assert!((2048..=8192).contains(&average), "{}", average);
+
+    // Repetitive data is cut at the maximum size
+    let zeros = chunk_lengths(&chunker, &[0u8; 40_000]);
+    assert!(
+        zeros[..zeros.len() - 1]
+            .iter()
+            .all(|(_, len)| *len == chunker.max_size() as u64)
+    );
+}
+
+#[test]
+fn test_chunker_boundaries_follow_content() {
+    let chunker = Chunker::new(4096);
+    let data = noise(9, 200_000);
+    let chunks = chunk_lengths(&chunker, &data);
+    let shifted = chunk_lengths(&chunker, &[noise(10, 777), data].concat());
+
+    // After the first boundaries the same chunks are found again
+    let tail = &chunks[2..];
+    assert!(
+        shifted.windows(tail.len()).any(|window| window == tail),
+        "{:?}\n{:?}",
+        chunks,
+        shifted
+    );
+}
+
+#[test]
+fn test_shared_block_is_found() {
+    let temp = setup();
+    let result = compare(temp.path(), Some(ChunkDedupeOptions::default()));
+    let report = result.chunk_dedupe.unwrap();
+
+    // Chunks straddling the ends of the block are not shared
+    let max_chunk = 4 * DEFAULT_AVG_CHUNK_SIZE as u64;
+    let block = MIB as u64;
+    assert!(
+        (block - 2 * max_chunk..=block).contains(&report.shared_bytes),
+        "{:?}",
+        report
+    );
+    let total_a = (2 * 300_000 + MIB + 200_000) as u64;
+    let total_b = (500_000 + MIB + 100_000 + 200_000) as u64;
+    assert_eq!(report.unique_bytes_a, total_a - report.shared_bytes);
+    assert_eq!(report.unique_bytes_b, total_b - report.shared_bytes);
+    assert_eq!(report.avg_chunk_size, DEFAULT_AVG_CHUNK_SIZE);
+
+    // Only the disk images share anything
+    assert_eq!(report.top_files_a.len(), 1);
+    let disk_a = &report.top_files_a[0];
+    assert_eq!(disk_a.path, PathBuf::from("disk.img"));
+    assert_eq!(disk_a.size, (2 * 300_000 + MIB) as u64);
+    assert_eq!(disk_a.shared_bytes, report.shared_bytes);
+    assert!(disk_a.shared_ratio() > 0.4, "{}", disk_a.shared_ratio());
+    assert_eq!(report.top_files_b[0].path, PathBuf::from("disk.img"));
+
+    // Smaller chunks find the block more closely
+    let small = ChunkDedupeOptions {
+        avg_chunk_size: 4096,
+        ..Default::default()
+    };
+    let report = compare(temp.path(), Some(small)).chunk_dedupe.unwrap();
+    assert!(
+        (block - 2 * 4 * 4096..=block).contains(&report.shared_bytes),
+        "{:?}",
+        report
+    );
+}
+
+#[test]
+fn test_analysis_is_skipped_without_options() {
+    let temp = setup();
+    let result = compare(temp.path(), None);
+    assert!(result.chunk_dedupe.is_none());
+    // Sampled hashes read only a few KiB of each file
+    assert!(result.stats.bytes_hashed < 64 * 1024, "{:?}", result.stats);
+    assert!(!FlatTextFormatter.format(&result).contains("Chunk-Level"));
+    let json: serde_json::Value = serde_json::from_str(&FlatJsonFormatter.format(&result)).unwrap();
+    assert!(json.get("chunk_dedupe").is_none());
+
+    let result = compare(temp.path(), Some(ChunkDedupeOptions::default()));
+    let total = result.stats.bytes_a + result.stats.bytes_b;
+    assert!(result.stats.bytes_hashed >= total, "{:?}", result.stats);
+}
+
+#[test]
+fn test_threads_and_progress() {
+    let temp = setup();
+    let single = ChunkDedupeOptions {
+        threads: Some(1),
+        ..Default::default()
+    };
+    let expected = compare(temp.path(), Some(single)).chunk_dedupe;
+
+    let updates = Mutex::new(Vec::new());
+    let options = FlatComparisonOptions {
+        chunk_dedupe: Some(ChunkDedupeOptions {
+            threads: Some(4),
+            ..Default::default()
+        }),
+        ..Default::default()
+    };
+    let result = compare_directories_flat_with_progress(
+        &temp.path().join("a"),
+        &temp.path().join("b"),
+        &options,
+        None,
+        &|progress| {
+            assert_eq!(progress.phase, ProgressPhase::Chunking);
+            updates
+                .lock()
+                .unwrap()
+                .push((progress.current, progress.total));
+        },
+    )
+    .unwrap();
+    assert_eq!(result.chunk_dedupe, expected);
+
+    let mut updates = updates.into_inner().unwrap();
+    updates.sort();
+    assert_eq!(updates, (1..=4).map(|n| (n, Some(4))).collect::<Vec<_>>());
+}
+
+#[test]
+fn test_formatters_render_chunk_dedupe() {
+    let temp = setup();
+    let result = compare(temp.path(), Some(ChunkDedupeOptions::default()));
+    let report = result.chunk_dedupe.as_ref().unwrap();
+
+    let text = FlatTextFormatter.format(&result);
+    assert!(
+        text.contains("Chunk-Level Dedupe (64.0 KiB average chunks)"),
+        "{}",
+        text
+    );
+    assert!(
+        text.contains("Top files in A by shared ratio:\n"),
+        "{}",
+        text
+    );
+    assert!(text.contains(" of 1.6 MiB  disk.img\n"), "{}", text);
+
+    let markdown = FlatMarkdownFormatter.format(&result);
+    assert!(markdown.contains("## Chunk-Level Dedupe\n"), "{}", markdown);
+    assert!(markdown.contains("| `disk.img` |"), "{}", markdown);
+    let html = FlatHtmlFormatter.format(&result);
+    assert!(html.contains(r#"<div class="chunk-dedupe">"#), "{}", html);
+
+    let json: serde_json::Value = serde_json::from_str(&FlatJsonFormatter.format(&result)).unwrap();
+    let chunk_dedupe = &json["chunk_dedupe"];
+    assert_eq!(chunk_dedupe["shared_bytes"], report.shared_bytes);
+    assert_eq!(chunk_dedupe["avg_chunk_size"], DEFAULT_AVG_CHUNK_SIZE);
+    assert_eq!(chunk_dedupe["top_files_b"][0]["path"], "disk.img");
+    assert_eq!(
+        chunk_dedupe["top_files_b"][0]["size"],
+        500_000 + MIB + 100_000
+    );
+}
@@ -0,0 +1,69 @@
+use dir_compare_core::config::{ConfigValue, config_to_args, format_config, parse_config};
+
+fn entry(key: &str, value: ConfigValue) -> (String, ConfigValue) {
+    (key.to_string(), value)
+}
+
+#[test]
+fn test_parse_config_turns_options_into_args() {
+    let entries = parse_config(
+        r#"
+        method = "mtime"
+        mtime-tolerance = 3
+        flat = false
+        verify = true
+        exclude = ["*.tmp", "build/"]
+        "#,
+    )
+    .unwrap();
+    assert_eq!(
+        config_to_args(&entries),
+        [
+            "--exclude",
+            "*.tmp",
+            "--exclude",
+            "build/",
+            "--method",
+            "mtime",
+            "--mtime-tolerance",
+            "3",
+            "--verify"
+        ]
+    );
+}
+
+#[test]
+fn test_parse_config_rejects_what_is_no_option() {
+    for (text, expected) in [
+        ("method = ", "line 1"),
+        ("[compare]\nmethod = \"hash\"", "table"),
+        ("threads = 1.5", "float"),
+        ("exclude = [1, 2]", "integer"),
+        ("config = \"other.toml\"", "another config file"),
+    ] {
+        let error = parse_config(text).unwrap_err();
+        assert!(error.to_string().contains(expected), "{}: {}", text, error);
+        assert!(error.to_string().starts_with("Invalid config file: "));
+    }
+}
+
+#[test]
+fn test_format_config_round_trips() {
+    let entries = vec![
+        entry(
+            "exclude",
+            ConfigValue::List(vec!["a b".into(), "it's \"x\"".into()]),
+        ),
+        entry("flat", ConfigValue::Bool(true)),
+        entry(
+            "ignore",
+            ConfigValue::String("C:\\data\\写真\n.ignore".into()),
+        ),
+        entry("mtime-tolerance", ConfigValue::Int(2)),
+        entry("odd key", ConfigValue::Bool(false)),
+    ];
+    let text = format_config(&entries);
+    assert!(text.contains("flat = true\n"));
+    assert!(text.contains("\"odd key\" = false\n"));
+    assert_eq!(parse_config(&text).unwrap(), entries);
+}
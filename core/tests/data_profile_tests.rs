@@ -0,0 +1,200 @@
+use dir_compare_core::comparison::{
+    ComparisonOptions, ComparisonStrategy, Entry, EntryKind, SAMPLING_THRESHOLD,
+    SampledHashStrategy, compare_directories_with_options,
+};
+use dir_compare_core::data_profile::{
+    DEFAULT_ADVICE_RAW_SHARE, DataProfile, DataProfiler, FormatClass, classify, classify_extension,
+    classify_magic, sampling_advice,
+};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+#[test]
+fn test_classify_extension() {
+    for name in [
+        "a.zip",
+        "photo.JPG",
+        "clip.mp4",
+        "backup.tar.gz",
+        "doc.docx",
+    ] {
+        assert_eq!(
+            classify_extension(Path::new(name)),
+            Some(FormatClass::Compressed),
+            "{}",
+            name
+        );
+    }
+    for name in [
+        "notes.txt",
+        "server.log",
+        "disk.vmdk",
+        "vm.QCOW2",
+        "dump.sql",
+    ] {
+        assert_eq!(
+            classify_extension(Path::new(name)),
+            Some(FormatClass::Raw),
+            "{}",
+            name
+        );
+    }
+    for name in ["Makefile", "data.xyz", ".hidden"] {
+        assert_eq!(classify_extension(Path::new(name)), None, "{}", name);
+    }
+}
+
+#[test]
+fn test_classify_magic() {
+    let mut mp4 = vec![0, 0, 0, 0x20];
+    mp4.extend_from_slice(b"ftypisom");
+    for header in [
+        &b"PK\x03\x04\x14\x00"[..],
+        b"\x1f\x8b\x08\x00",
+        b"\xff\xd8\xff\xe0",
+        b"\x89PNG\r\n\x1a\n\x00",
+        b"RIFF\x00\x00\x00\x00WEBPVP8 ",
+        &mp4,
+    ] {
+        assert_eq!(
+            classify_magic(header),
+            Some(FormatClass::Compressed),
+            "{:?}",
+            header
+        );
+    }
+    for header in [
+        &b""[..],
+        b"hello world",
+        b"RIFF\x00\x00\x00\x00WAVE",
+        b"\x1f",
+    ] {
+        assert_eq!(classify_magic(header), None, "{:?}", header);
+    }
+}
+
+#[test]
+fn test_classify_prefers_the_extension() {
+    let gzip = &b"\x1f\x8b\x08\x00"[..];
+    assert_eq!(classify(Path::new("a.txt"), Some(gzip)), FormatClass::Raw);
+    assert_eq!(
+        classify(Path::new("a.bak"), Some(gzip)),
+        FormatClass::Compressed
+    );
+    assert_eq!(classify(Path::new("a.bak"), None), FormatClass::Raw);
+    assert_eq!(
+        classify(Path::new("a.bak"), Some(b"plain")),
+        FormatClass::Raw
+    );
+}
+
+#[test]
+fn test_profile_totals() {
+    let mut profile = DataProfile::default();
+    assert_eq!(profile.compressed_percent(), 0.0);
+    assert_eq!(profile.sampled_raw_share(), 0.0);
+
+    profile.add(FormatClass::Compressed, 6000);
+    profile.add(FormatClass::Raw, 3000);
+    profile.add(FormatClass::Raw, SAMPLING_THRESHOLD - 1);
+    profile.add(FormatClass::Raw, SAMPLING_THRESHOLD);
+    assert_eq!(profile.files, 4);
+    assert_eq!(
+        profile.total_bytes(),
+        6000 + 3000 + 2 * SAMPLING_THRESHOLD - 1
+    );
+    assert_eq!(profile.sampled_raw_bytes, SAMPLING_THRESHOLD);
+    assert!((profile.compressed_percent() + profile.raw_percent() - 100.0).abs() < 1e-9);
+}
+
+#[test]
+fn test_sampling_advice_decision() {
+    let mut raw = DataProfile::default();
+    raw.add(FormatClass::Raw, 1 << 30);
+    raw.add(FormatClass::Compressed, 1 << 20);
+    let advice = sampling_advice(&raw, false, DEFAULT_ADVICE_RAW_SHARE).unwrap();
+    assert!(
+        advice.starts_with("100% of the compared bytes"),
+        "{}",
+        advice
+    );
+    assert!(advice.contains("--verify"));
+
+    // Matches that are verified, or files hashed whole, are safe
+    assert_eq!(sampling_advice(&raw, true, DEFAULT_ADVICE_RAW_SHARE), None);
+    assert_eq!(sampling_advice(&raw, false, 1.0), None);
+
+    let mut compressed = DataProfile::default();
+    compressed.add(FormatClass::Compressed, 1 << 30);
+    compressed.add(FormatClass::Raw, 1 << 20);
+    assert_eq!(
+        sampling_advice(&compressed, false, DEFAULT_ADVICE_RAW_SHARE),
+        None
+    );
+    assert!(sampling_advice(&compressed, false, 0.0).is_some());
+
+    // Small raw files are hashed whole, so they never need advice
+    let mut small = DataProfile::default();
+    small.add(FormatClass::Raw, SAMPLING_THRESHOLD - 1);
+    assert_eq!(sampling_advice(&small, false, 0.0), None);
+    assert_eq!(sampling_advice(&DataProfile::default(), false, 0.0), None);
+}
+
+#[test]
+fn test_sampled_strategy_feeds_the_profiler() {
+    let temp = tempfile::tempdir().unwrap();
+    let gzip: Vec<u8> = b"\x1f\x8b\x08\x00"
+        .iter()
+        .copied()
+        .chain(std::iter::repeat_n(1, 9996))
+        .collect();
+    for side in ["dir_a", "dir_b"] {
+        let dir = temp.path().join(side);
+        fs::create_dir(&dir).unwrap();
+        fs::write(dir.join("disk.img"), vec![0u8; 20_000]).unwrap();
+        fs::write(dir.join("archive.bak"), &gzip).unwrap();
+        fs::create_dir(dir.join("sub")).unwrap();
+    }
+    fs::write(temp.path().join("dir_a/only_a.img"), vec![0u8; 5000]).unwrap();
+
+    for (sniff, compressed_bytes) in [(true, 10_000), (false, 0)] {
+        let profiler = Arc::new(DataProfiler::new(sniff));
+        let strategy =
+            SampledHashStrategy::new(false, false).with_data_profiler(Arc::clone(&profiler));
+        compare_directories_with_options(
+            &temp.path().join("dir_a"),
+            &temp.path().join("dir_b"),
+            &strategy,
+            None,
+            &ComparisonOptions::default(),
+        )
+        .unwrap();
+
+        // One file per pair; directories and unpaired files are not read
+        let profile = profiler.profile();
+        assert_eq!(profile.files, 2);
+        assert_eq!(profile.compressed_bytes, compressed_bytes);
+        assert_eq!(profile.total_bytes(), 30_000);
+    }
+}
+
+#[test]
+fn test_sampled_strategy_skips_the_profiler_when_sizes_differ() {
+    let entry = |name: &str, size| Entry {
+        path: PathBuf::from("data"),
+        abs_path: PathBuf::from("/nonexistent").join(name).join("data"),
+        kind: EntryKind::File,
+        size: Some(size),
+        nested_repository: false,
+        mtime: None,
+    };
+    let profiler = Arc::new(DataProfiler::new(true));
+    let strategy = SampledHashStrategy::new(false, false).with_data_profiler(Arc::clone(&profiler));
+
+    // Neither file is opened, so the files that do not exist go unnoticed
+    let mut warnings = Vec::new();
+    assert!(!strategy.matches_reporting(&entry("a", 10), &entry("b", 20), &mut warnings));
+    assert!(warnings.is_empty());
+    assert_eq!(profiler.profile().files, 0);
+}
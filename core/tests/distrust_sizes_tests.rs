@@ -0,0 +1,155 @@
+use dir_compare_core::comparison::{
+    ComparisonStrategy, Entry, EntryKind, FilenameSizeStrategy, FlatComparisonOptions,
+    MetadataStrategy, SampledHashStrategy, compare_directories_flat,
+};
+use dir_compare_core::filesystem::{FileSystem, RealFileSystem};
+use dir_compare_core::logger::{self, LogBuffer, LogLevel};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// A filesystem reporting a size of 0 for every file below `root`, like
+/// procfs or a file still being written on a network share
+struct ZeroSizes {
+    root: PathBuf,
+}
+
+impl FileSystem for ZeroSizes {
+    fn canonicalize(&self, path: &Path) -> io::Result<PathBuf> {
+        RealFileSystem.canonicalize(path)
+    }
+
+    fn metadata(&self, path: &Path) -> io::Result<fs::Metadata> {
+        RealFileSystem.metadata(path)
+    }
+
+    fn len(&self, path: &Path) -> io::Result<u64> {
+        if path.starts_with(&self.root) {
+            Ok(0)
+        } else {
+            RealFileSystem.len(path)
+        }
+    }
+}
+
+/// Writes `name` with `a` in dir_a and `b` in dir_b
+fn setup_pair(name: &str, a: &[u8], b: &[u8]) -> tempfile::TempDir {
+    let temp = tempfile::tempdir().unwrap();
+    for (side, content) in [("dir_a", a), ("dir_b", b)] {
+        fs::create_dir_all(temp.path().join(side)).unwrap();
+        fs::write(temp.path().join(side).join(name), content).unwrap();
+    }
+    temp
+}
+
+/// The entry for `name` in `side`, with the size 0 its filesystem reports
+fn zero_size_entry(root: &Path, side: &str, name: &str) -> Entry {
+    Entry {
+        path: PathBuf::from(name),
+        abs_path: root.join(side).join(name),
+        kind: EntryKind::File,
+        size: Some(0),
+        nested_repository: false,
+        mtime: None,
+    }
+}
+
+fn zero_sizes(root: &Path) -> Arc<ZeroSizes> {
+    Arc::new(ZeroSizes {
+        root: root.to_path_buf(),
+    })
+}
+
+#[test]
+fn test_size_strategy_reads_lengths_only_when_distrusting_sizes() {
+    let temp = setup_pair("status", b"running", b"stopped after 3 retries");
+    let a = zero_size_entry(temp.path(), "dir_a", "status");
+    let b = zero_size_entry(temp.path(), "dir_b", "status");
+
+    // Both sides report 0 bytes, so the reported sizes agree
+    assert!(FilenameSizeStrategy::new(false).matches(&a, &b));
+
+    let strategy = FilenameSizeStrategy::new(false)
+        .with_distrust_sizes(true)
+        .with_file_system(zero_sizes(temp.path()));
+    assert!(!strategy.matches(&a, &b));
+    assert!(strategy.matches(&a, &a.clone()));
+
+    let strategy = MetadataStrategy::new(false, Duration::from_secs(3600))
+        .with_distrust_sizes(true)
+        .with_file_system(zero_sizes(temp.path()));
+    assert!(!strategy.matches(&a, &b));
+}
+
+#[test]
+fn test_sampled_hash_ignores_reported_sizes_when_distrusting_sizes() {
+    let content: Vec<u8> = (0..5000u32).map(|i| (i % 251) as u8).collect();
+    let temp = setup_pair("data.bin", &content, &content);
+    let a = zero_size_entry(temp.path(), "dir_a", "data.bin");
+    let b = zero_size_entry(temp.path(), "dir_b", "data.bin");
+    // Only dir_a misreports its size
+    let fs = zero_sizes(&temp.path().join("dir_a"));
+
+    let trusting = SampledHashStrategy::new(false, false).with_file_system(fs.clone());
+    assert!(!trusting.matches(&a, &b));
+
+    let distrusting = SampledHashStrategy::new(false, false)
+        .with_distrust_sizes(true)
+        .with_file_system(fs);
+    assert!(distrusting.matches(&a, &b));
+}
+
+#[test]
+fn test_misreported_size_is_logged_per_file() {
+    let temp = setup_pair("fresh.log", b"first line\n", b"first line\n");
+    let a = zero_size_entry(temp.path(), "dir_a", "fresh.log");
+    let b = zero_size_entry(temp.path(), "dir_b", "fresh.log");
+    let buffer = LogBuffer::new(100);
+    let id = logger::add_sink(Box::new(buffer.clone()));
+
+    let strategy = FilenameSizeStrategy::new(false)
+        .with_distrust_sizes(true)
+        .with_file_system(zero_sizes(temp.path()));
+    assert!(strategy.matches(&a, &b));
+    logger::remove_sink(id);
+
+    let warnings: Vec<String> = buffer
+        .entries(LogLevel::Warn)
+        .into_iter()
+        .map(|entry| entry.message)
+        .filter(|message| message.contains("fresh.log"))
+        .collect();
+    assert_eq!(warnings.len(), 2, "{:?}", warnings);
+    assert!(warnings[0].contains("reports a size of 0 but 11 bytes were read"));
+    assert!(warnings[0].contains("dir_a"));
+    assert!(warnings[1].contains("dir_b"));
+}
+
+#[test]
+fn test_flat_mode_groups_by_content_when_distrusting_sizes() {
+    let temp = setup_pair("same.txt", b"identical", b"identical");
+    fs::write(temp.path().join("dir_b/copy.txt"), b"identical").unwrap();
+    let compare = |distrust_sizes| {
+        let options = FlatComparisonOptions {
+            distrust_sizes,
+            ..Default::default()
+        };
+        compare_directories_flat(
+            &temp.path().join("dir_a"),
+            &temp.path().join("dir_b"),
+            &options,
+            None,
+        )
+        .unwrap()
+    };
+
+    let trusting = compare(false);
+    let distrusting = compare(true);
+    assert_eq!(distrusting.groups.len(), 1);
+    assert_eq!(distrusting.groups[0].file_count, 3);
+    assert_eq!(distrusting.groups[0].size, 9);
+    // Hashes without the size prefix live in their own namespace
+    assert_ne!(distrusting.groups[0].hash, trusting.groups[0].hash);
+}
@@ -0,0 +1,382 @@
+use dir_compare_core::doctor::{
+    CheckResult, CheckStatus, LONG_PATH, LOW_FREE_SPACE, MAX_PATH, MIN_FREE_SPACE, RootScan,
+    SAMPLE_FILES, available_space, check_case_sensitivity, check_clock, check_free_space,
+    check_mtime_granularity, check_path_length, check_read_throughput, check_root_readable,
+    check_shared_device, check_symlink_support, device_id, verdict,
+};
+use dir_compare_core::filesystem::{CaseSensitivity, FileSystem, RealFileSystem};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::{Duration, SystemTime};
+
+/// A filesystem failing to list `unlistable`, delaying the first read of
+/// each file by `read_delay`, and without symbolic links
+#[derive(Default)]
+struct Simulated {
+    unlistable: Vec<PathBuf>,
+    read_delay: Duration,
+    no_symlinks: bool,
+}
+
+/// A reader sleeping before its first read
+struct SlowStart {
+    inner: Box<dyn io::Read>,
+    delay: Option<Duration>,
+}
+
+impl io::Read for SlowStart {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if let Some(delay) = self.delay.take() {
+            thread::sleep(delay);
+        }
+        self.inner.read(buf)
+    }
+}
+
+impl FileSystem for Simulated {
+    fn canonicalize(&self, path: &Path) -> io::Result<PathBuf> {
+        RealFileSystem.canonicalize(path)
+    }
+
+    fn metadata(&self, path: &Path) -> io::Result<fs::Metadata> {
+        RealFileSystem.metadata(path)
+    }
+
+    fn open(&self, path: &Path) -> io::Result<Box<dyn io::Read>> {
+        Ok(Box::new(SlowStart {
+            inner: RealFileSystem.open(path)?,
+            delay: Some(self.read_delay),
+        }))
+    }
+
+    fn read_dir(&self, path: &Path) -> io::Result<Vec<PathBuf>> {
+        if self.unlistable.iter().any(|unlistable| unlistable == path) {
+            return Err(io::Error::from(io::ErrorKind::PermissionDenied));
+        }
+        RealFileSystem.read_dir(path)
+    }
+
+    fn create_symlink(&self, target: &Path, link: &Path) -> io::Result<()> {
+        if self.no_symlinks {
+            return Err(io::Error::from(io::ErrorKind::Unsupported));
+        }
+        RealFileSystem.create_symlink(target, link)
+    }
+}
+
+fn status(result: &CheckResult) -> CheckStatus {
+    result.status
+}
+
+/// A tree of a few small files, a larger one and a nested directory
+fn setup() -> tempfile::TempDir {
+    let temp = tempfile::tempdir().unwrap();
+    let root = temp.path();
+    fs::create_dir_all(root.join("nested/deeper")).unwrap();
+    fs::write(root.join("small.txt"), "small").unwrap();
+    fs::write(root.join("nested/medium.txt"), "medium content").unwrap();
+    fs::write(root.join("nested/deeper/large.bin"), vec![1u8; 2_000_000]).unwrap();
+    temp
+}
+
+fn epoch(secs: u64, nanos: u32) -> SystemTime {
+    SystemTime::UNIX_EPOCH + Duration::new(secs, nanos)
+}
+
+#[test]
+fn test_verdict_is_the_worst_status() {
+    let pass = CheckResult::pass("a", "fine");
+    let warn = CheckResult::warn("b", "hmm");
+    let fail = CheckResult::fail("c", "broken");
+    assert_eq!(verdict(&[]), CheckStatus::Pass);
+    assert_eq!(verdict(std::slice::from_ref(&pass)), CheckStatus::Pass);
+    assert_eq!(verdict(&[pass.clone(), warn.clone()]), CheckStatus::Warn);
+    assert_eq!(verdict(&[fail, pass, warn]), CheckStatus::Fail);
+    assert_eq!(CheckStatus::Warn.to_string(), "WARN");
+}
+
+#[test]
+fn test_scan_finds_largest_files_and_longest_path() {
+    let temp = setup();
+    let scan = RootScan::scan(&RealFileSystem, temp.path(), 100);
+    assert_eq!(scan.entries, 5);
+    assert!(!scan.truncated);
+    assert_eq!(scan.unreadable, 0);
+    assert_eq!(scan.mtimes.len(), 3);
+    assert!(scan.latest_mtime.is_some());
+    assert_eq!(
+        scan.longest_path,
+        Some(temp.path().join("nested/deeper/large.bin"))
+    );
+    let sizes: Vec<u64> = scan.largest_files.iter().map(|(_, size)| *size).collect();
+    assert_eq!(sizes, [2_000_000, 14, 5]);
+
+    let scan = RootScan::scan(&RealFileSystem, temp.path(), 2);
+    assert_eq!(scan.entries, 2);
+    assert!(scan.truncated);
+}
+
+#[test]
+fn test_scan_keeps_only_the_largest_samples() {
+    let temp = tempfile::tempdir().unwrap();
+    for size in 1..=SAMPLE_FILES + 3 {
+        fs::write(temp.path().join(format!("{}.bin", size)), vec![0u8; size]).unwrap();
+    }
+    let scan = RootScan::scan(&RealFileSystem, temp.path(), 100);
+    let sizes: Vec<u64> = scan.largest_files.iter().map(|(_, size)| *size).collect();
+    assert_eq!(sizes, [7, 6, 5, 4]);
+    assert_eq!(scan.sample_files().len(), SAMPLE_FILES);
+}
+
+#[test]
+fn test_root_readable() {
+    let temp = setup();
+    let root = temp.path();
+    let scan = RootScan::scan(&RealFileSystem, root, 100);
+    let result = check_root_readable(&RealFileSystem, "Directory A", root, &scan);
+    assert_eq!(status(&result), CheckStatus::Pass);
+    assert_eq!(result.name, "Directory A readable");
+    assert_eq!(result.explanation, "listed 5 entries");
+
+    // Missing, or a file
+    let missing = root.join("missing");
+    let result = check_root_readable(&RealFileSystem, "A", &missing, &RootScan::default());
+    assert_eq!(status(&result), CheckStatus::Fail);
+    let file = root.join("small.txt");
+    let result = check_root_readable(&RealFileSystem, "A", &file, &RootScan::default());
+    assert_eq!(status(&result), CheckStatus::Fail);
+    assert!(result.explanation.contains("not a directory"));
+
+    // The root cannot be listed
+    let fs = Simulated {
+        unlistable: vec![root.to_path_buf()],
+        ..Default::default()
+    };
+    let result = check_root_readable(&fs, "A", root, &RootScan::default());
+    assert_eq!(status(&result), CheckStatus::Fail);
+    assert!(result.explanation.contains("cannot list"));
+}
+
+#[test]
+fn test_root_readable_warns_about_unreadable_subdirectories() {
+    let temp = setup();
+    let fs = Simulated {
+        unlistable: vec![temp.path().join("nested")],
+        ..Default::default()
+    };
+    let scan = RootScan::scan(&fs, temp.path(), 100);
+    assert_eq!(scan.unreadable, 1);
+    let result = check_root_readable(&fs, "A", temp.path(), &scan);
+    assert_eq!(status(&result), CheckStatus::Warn);
+    assert!(result.explanation.starts_with("1 of 2 entries"));
+}
+
+#[test]
+fn test_read_throughput() {
+    let temp = setup();
+    let scan = RootScan::scan(&RealFileSystem, temp.path(), 100);
+    let result = check_read_throughput(&RealFileSystem, "A", &scan.sample_files());
+    assert_eq!(status(&result), CheckStatus::Pass, "{}", result.explanation);
+    assert!(result.explanation.contains("from 3 files"));
+
+    // 2 MB after a second is well below the minimum
+    let slow = Simulated {
+        read_delay: Duration::from_secs(1),
+        ..Default::default()
+    };
+    let large = scan.sample_files()[..1].to_vec();
+    let result = check_read_throughput(&slow, "A", &large);
+    assert_eq!(status(&result), CheckStatus::Warn);
+    assert!(result.explanation.contains("will be slow"));
+
+    // A file that cannot be read
+    let missing = [temp.path().join("missing.bin")];
+    let result = check_read_throughput(&RealFileSystem, "A", &missing);
+    assert_eq!(status(&result), CheckStatus::Warn);
+    assert!(result.explanation.contains("cannot read"));
+}
+
+#[test]
+fn test_read_throughput_skips_too_little_content() {
+    let temp = setup();
+    let small = [temp.path().join("small.txt")];
+    let slow = Simulated {
+        read_delay: Duration::from_millis(50),
+        ..Default::default()
+    };
+    let result = check_read_throughput(&slow, "A", &small);
+    assert_eq!(status(&result), CheckStatus::Pass);
+    assert!(result.explanation.contains("too little"));
+
+    let result = check_read_throughput(&RealFileSystem, "A", &[]);
+    assert_eq!(status(&result), CheckStatus::Pass);
+}
+
+#[test]
+fn test_free_space() {
+    let dir = Path::new("/reports");
+    assert_eq!(status(&check_free_space(dir, None)), CheckStatus::Warn);
+    assert_eq!(
+        status(&check_free_space(dir, Some(MIN_FREE_SPACE - 1))),
+        CheckStatus::Fail
+    );
+    assert_eq!(
+        status(&check_free_space(dir, Some(LOW_FREE_SPACE - 1))),
+        CheckStatus::Warn
+    );
+    let result = check_free_space(dir, Some(LOW_FREE_SPACE * 4));
+    assert_eq!(status(&result), CheckStatus::Pass);
+    assert_eq!(result.explanation, "4.0 GiB free in /reports");
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn test_available_space_is_known_on_linux() {
+    let temp = tempfile::tempdir().unwrap();
+    assert!(available_space(temp.path()).is_some());
+    assert!(available_space(&temp.path().join("missing")).is_none());
+}
+
+#[test]
+fn test_case_sensitivity() {
+    use CaseSensitivity::{Insensitive, Sensitive, Unknown};
+
+    assert_eq!(
+        status(&check_case_sensitivity(Sensitive, Sensitive)),
+        CheckStatus::Pass
+    );
+    assert_eq!(
+        status(&check_case_sensitivity(Insensitive, Insensitive)),
+        CheckStatus::Pass
+    );
+    let mixed = check_case_sensitivity(Sensitive, Insensitive);
+    assert_eq!(status(&mixed), CheckStatus::Warn);
+    assert!(mixed.explanation.contains("A-only and B-only"));
+    let unknown = check_case_sensitivity(Unknown, Sensitive);
+    assert_eq!(status(&unknown), CheckStatus::Warn);
+    assert!(unknown.explanation.contains("read-only"));
+}
+
+#[cfg(unix)]
+#[test]
+fn test_symlink_support() {
+    let temp = tempfile::tempdir().unwrap();
+    let result = check_symlink_support(&RealFileSystem, "A", temp.path());
+    assert_eq!(status(&result), CheckStatus::Pass);
+    // The probe is removed
+    assert_eq!(fs::read_dir(temp.path()).unwrap().count(), 0);
+
+    let fs = Simulated {
+        no_symlinks: true,
+        ..Default::default()
+    };
+    let result = check_symlink_support(&fs, "A", temp.path());
+    assert_eq!(status(&result), CheckStatus::Warn);
+    assert!(result.explanation.contains("cannot create a link"));
+}
+
+#[cfg(unix)]
+#[test]
+fn test_symlink_support_leaves_an_existing_probe() {
+    let temp = tempfile::tempdir().unwrap();
+    let probe = temp
+        .path()
+        .join(format!(".dir-compare-symlink-probe-{}", std::process::id()));
+    fs::write(&probe, "mine").unwrap();
+    let result = check_symlink_support(&RealFileSystem, "A", temp.path());
+    assert_eq!(status(&result), CheckStatus::Warn);
+    assert_eq!(fs::read_to_string(&probe).unwrap(), "mine");
+}
+
+#[test]
+fn test_mtime_granularity() {
+    let result = check_mtime_granularity("A", &[]);
+    assert_eq!(status(&result), CheckStatus::Pass);
+
+    let precise = [epoch(1_700_000_000, 0), epoch(1_700_000_002, 123)];
+    let result = check_mtime_granularity("A", &precise);
+    assert_eq!(status(&result), CheckStatus::Pass);
+    assert_eq!(result.explanation, "sub-second precision");
+
+    let seconds = [epoch(1_700_000_000, 0), epoch(1_700_000_003, 0)];
+    let result = check_mtime_granularity("A", &seconds);
+    assert_eq!(status(&result), CheckStatus::Pass);
+    assert!(result.explanation.contains("multiples of 1 s"));
+
+    // FAT keeps even seconds, beyond the default tolerance
+    let fat = [epoch(1_700_000_000, 0), epoch(1_700_000_004, 0)];
+    let result = check_mtime_granularity("A", &fat);
+    assert_eq!(status(&result), CheckStatus::Warn);
+    assert!(result.explanation.contains("--mtime-tolerance 2"));
+}
+
+#[test]
+fn test_path_length() {
+    let scan = |path: PathBuf, truncated: bool| RootScan {
+        entries: 10,
+        truncated,
+        longest_path: Some(path),
+        ..Default::default()
+    };
+    assert_eq!(
+        status(&check_path_length("A", &RootScan::default())),
+        CheckStatus::Pass
+    );
+
+    let result = check_path_length("A", &scan(PathBuf::from("/short/path"), true));
+    assert_eq!(status(&result), CheckStatus::Pass);
+    assert_eq!(
+        result.explanation,
+        "longest path among the first 10 entries has 11 characters"
+    );
+
+    let long = PathBuf::from("/").join("d".repeat(LONG_PATH));
+    let result = check_path_length("A", &scan(long, false));
+    assert_eq!(status(&result), CheckStatus::Warn);
+    assert!(result.explanation.contains("Windows"));
+
+    let too_long = PathBuf::from("/").join("d/".repeat(MAX_PATH / 2));
+    let result = check_path_length("A", &scan(too_long, false));
+    assert_eq!(status(&result), CheckStatus::Fail);
+}
+
+#[test]
+fn test_clock() {
+    let now = epoch(1_800_000_000, 0);
+    assert_eq!(status(&check_clock(now, None)), CheckStatus::Pass);
+    assert_eq!(
+        status(&check_clock(now, Some(now + Duration::from_secs(3600)))),
+        CheckStatus::Pass
+    );
+
+    let future = check_clock(now, Some(now + Duration::from_secs(3 * 24 * 3600)));
+    assert_eq!(status(&future), CheckStatus::Warn);
+    assert!(future.explanation.contains("72 hours"));
+
+    let result = check_clock(epoch(86_400, 0), None);
+    assert_eq!(status(&result), CheckStatus::Fail);
+    assert!(result.explanation.contains("before 2020"));
+}
+
+#[test]
+fn test_shared_device() {
+    assert_eq!(
+        status(&check_shared_device(Some(1), Some(1))),
+        CheckStatus::Pass
+    );
+    let different = check_shared_device(Some(1), Some(2));
+    assert_eq!(status(&different), CheckStatus::Pass);
+    assert!(different.explanation.contains("different devices"));
+    assert_eq!(
+        status(&check_shared_device(Some(1), None)),
+        CheckStatus::Warn
+    );
+
+    let temp = tempfile::tempdir().unwrap();
+    if cfg!(unix) {
+        assert!(device_id(&RealFileSystem, temp.path()).is_some());
+    }
+    assert!(device_id(&RealFileSystem, &temp.path().join("missing")).is_none());
+}
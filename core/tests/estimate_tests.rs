@@ -0,0 +1,248 @@
+use dir_compare_core::comparison::{
+    Blake3Strategy, ComparisonEstimate, ComparisonOptions, ComparisonStrategy,
+    DEFAULT_MTIME_TOLERANCE, FastHashStrategy, FilenameOnlyStrategy, FilenameSizeStrategy,
+    MetadataStrategy, SAMPLING_THRESHOLD, SampledHashStrategy, StatMode,
+    compare_directories_with_options, plan_comparison,
+};
+use dir_compare_core::filesystem::{FileSystem, RealFileSystem};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use tempfile::TempDir;
+
+/// A filesystem counting the files opened for reading
+#[derive(Default)]
+struct OpenCounter {
+    opened: AtomicUsize,
+}
+
+impl FileSystem for OpenCounter {
+    fn canonicalize(&self, path: &Path) -> io::Result<PathBuf> {
+        RealFileSystem.canonicalize(path)
+    }
+
+    fn metadata(&self, path: &Path) -> io::Result<fs::Metadata> {
+        RealFileSystem.metadata(path)
+    }
+
+    fn open(&self, path: &Path) -> io::Result<Box<dyn io::Read>> {
+        self.opened.fetch_add(1, Ordering::Relaxed);
+        RealFileSystem.open(path)
+    }
+
+    fn open_sequential(&self, path: &Path) -> io::Result<Box<dyn io::Read>> {
+        self.opened.fetch_add(1, Ordering::Relaxed);
+        RealFileSystem.open_sequential(path)
+    }
+}
+
+/// Both sides hold `big.bin` (10,000 bytes), `small.txt` (100 bytes) and
+/// `sub/part.bin` (5,000 bytes), with different contents; A alone holds
+/// `a.txt` and B alone `b.txt`.
+fn setup() -> TempDir {
+    let temp = TempDir::new().unwrap();
+    for (side, fill) in [("a", b'a'), ("b", b'b')] {
+        let dir = temp.path().join(side);
+        fs::create_dir_all(dir.join("sub")).unwrap();
+        fs::write(dir.join("big.bin"), vec![fill; 10_000]).unwrap();
+        fs::write(dir.join("small.txt"), vec![fill; 100]).unwrap();
+        fs::write(dir.join("sub/part.bin"), vec![fill; 5_000]).unwrap();
+        fs::write(dir.join(format!("{}.txt", side)), "only here").unwrap();
+    }
+    temp
+}
+
+fn estimate(
+    root: &Path,
+    strategy: &dyn ComparisonStrategy,
+    options: &ComparisonOptions,
+) -> ComparisonEstimate {
+    plan_comparison(
+        &root.join("a"),
+        &root.join("b"),
+        strategy,
+        None,
+        options,
+        &|_| {},
+    )
+    .unwrap()
+    .estimate()
+}
+
+/// Bytes sampled from a file of `size` bytes
+fn sampled(size: u64) -> u64 {
+    size.min(SAMPLING_THRESHOLD)
+}
+
+#[test]
+fn test_estimate_per_strategy() {
+    let temp = setup();
+    let options = ComparisonOptions::default();
+    let whole = 2 * (10_000 + 100 + 5_000);
+    let cases: [(&str, Box<dyn ComparisonStrategy>, usize, u64); 8] = [
+        ("filename", Box::new(FilenameOnlyStrategy::new(false)), 0, 0),
+        ("size", Box::new(FilenameSizeStrategy::new(false)), 0, 0),
+        (
+            "size, distrusted",
+            Box::new(FilenameSizeStrategy::new(false).with_distrust_sizes(true)),
+            3,
+            whole,
+        ),
+        (
+            "mtime",
+            Box::new(MetadataStrategy::new(false, DEFAULT_MTIME_TOLERANCE)),
+            0,
+            0,
+        ),
+        ("hash", Box::new(FastHashStrategy::new(false)), 3, whole),
+        ("blake3", Box::new(Blake3Strategy::new(false)), 3, whole),
+        (
+            "sampled",
+            Box::new(SampledHashStrategy::new(false, false)),
+            3,
+            2 * (sampled(10_000) + 100 + sampled(5_000)),
+        ),
+        (
+            "sampled, verified",
+            Box::new(SampledHashStrategy::new(false, true)),
+            3,
+            2 * (sampled(10_000) + 100 + sampled(5_000)) + whole,
+        ),
+    ];
+    for (name, strategy, content_pairs, bytes) in cases {
+        let estimate = estimate(temp.path(), strategy.as_ref(), &options);
+        assert_eq!(
+            estimate,
+            ComparisonEstimate {
+                pairs: 4,
+                content_pairs,
+                bytes,
+                unsized_pairs: 0,
+            },
+            "{}",
+            name
+        );
+    }
+    assert_eq!(sampled(10_000), 3_017);
+}
+
+#[test]
+fn test_estimate_matches_the_bytes_hashed() {
+    let temp = setup();
+    let options = ComparisonOptions {
+        threads: Some(2),
+        ..Default::default()
+    };
+    for strategy in [
+        Box::new(FastHashStrategy::new(false)) as Box<dyn ComparisonStrategy>,
+        Box::new(SampledHashStrategy::new(false, false)),
+    ] {
+        let estimated = estimate(temp.path(), strategy.as_ref(), &options).bytes;
+        let result = compare_directories_with_options(
+            &temp.path().join("a"),
+            &temp.path().join("b"),
+            strategy.as_ref(),
+            None,
+            &options,
+        )
+        .unwrap();
+        assert_eq!(result.stats.bytes_hashed, estimated);
+    }
+}
+
+#[test]
+fn test_planning_reads_no_contents() {
+    let temp = setup();
+    let counter = Arc::new(OpenCounter::default());
+    let strategy = FastHashStrategy::new(false).with_file_system(counter.clone());
+    let options = ComparisonOptions::default();
+    let plan = plan_comparison(
+        &temp.path().join("a"),
+        &temp.path().join("b"),
+        &strategy,
+        None,
+        &options,
+        &|_| {},
+    )
+    .unwrap();
+    assert_eq!(plan.estimate().bytes, 30_200);
+    assert_eq!(plan.pairs().len(), 4);
+    assert_eq!(plan.stats().files_a, 4);
+    assert_eq!(plan.stats().bytes_hashed, 0);
+    assert_eq!(counter.opened.load(Ordering::Relaxed), 0);
+
+    let result = plan.run(&|_| {}).unwrap();
+    assert_eq!(counter.opened.load(Ordering::Relaxed), 6);
+    assert_eq!(result.modified.len(), 3);
+    assert_eq!(result.a_only.len(), 1);
+    assert_eq!(result.b_only.len(), 1);
+    assert_eq!(result.both.len(), 1);
+}
+
+#[test]
+fn test_estimate_counts_unsized_pairs() {
+    let temp = setup();
+    let options = ComparisonOptions {
+        stat_mode: StatMode::Lazy,
+        ..Default::default()
+    };
+    let estimate = estimate(temp.path(), &FastHashStrategy::new(false), &options);
+    assert_eq!(estimate.content_pairs, 3);
+    assert_eq!(estimate.unsized_pairs, 3);
+    assert_eq!(estimate.bytes, 0);
+}
+
+#[test]
+fn test_pairs_of_different_sizes_are_not_read() {
+    let temp = setup();
+    fs::write(temp.path().join("b/big.bin"), vec![b'b'; 9_999]).unwrap();
+    let options = ComparisonOptions::default();
+    for strategy in [
+        Box::new(FastHashStrategy::new(false)) as Box<dyn ComparisonStrategy>,
+        Box::new(SampledHashStrategy::new(false, false)),
+    ] {
+        let estimate = estimate(temp.path(), strategy.as_ref(), &options);
+        assert_eq!(estimate.content_pairs, 2);
+        let result = compare_directories_with_options(
+            &temp.path().join("a"),
+            &temp.path().join("b"),
+            strategy.as_ref(),
+            None,
+            &options,
+        )
+        .unwrap();
+        assert_eq!(result.modified.len(), 3);
+        assert_eq!(result.stats.bytes_hashed, estimate.bytes);
+    }
+
+    let counter = Arc::new(OpenCounter::default());
+    let strategy = FastHashStrategy::new(false).with_file_system(counter.clone());
+    compare_directories_with_options(
+        &temp.path().join("a"),
+        &temp.path().join("b"),
+        &strategy,
+        None,
+        &options,
+    )
+    .unwrap();
+    assert_eq!(counter.opened.load(Ordering::Relaxed), 4);
+
+    // Distrusted sizes are no reason to skip a pair
+    let counter = Arc::new(OpenCounter::default());
+    let strategy = FastHashStrategy::new(false)
+        .with_distrust_sizes(true)
+        .with_file_system(counter.clone());
+    let estimate = estimate(temp.path(), &strategy, &options);
+    assert_eq!(estimate.content_pairs, 3);
+    compare_directories_with_options(
+        &temp.path().join("a"),
+        &temp.path().join("b"),
+        &strategy,
+        None,
+        &options,
+    )
+    .unwrap();
+    assert_eq!(counter.opened.load(Ordering::Relaxed), 6);
+}
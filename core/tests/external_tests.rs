@@ -0,0 +1,372 @@
+use dir_compare_core::comparison::{
+    ComparisonOptions, ComparisonResult, FastHashStrategy, WarningCause,
+    compare_directories_with_options,
+};
+use dir_compare_core::external::{
+    DEFAULT_EXTERNAL_TIMEOUT, ExternalCommandStrategy, ExternalRule, ExternalRules,
+    parse_external_rules,
+};
+use std::fs;
+use std::path::Path;
+use std::time::Duration;
+
+/// Creates `dir_a` and `dir_b` below `root` holding `files` as
+/// (name, content in A, content in B)
+fn setup(root: &Path, files: &[(&str, &str, &str)]) {
+    for (name, a, b) in files {
+        for (side, content) in [("dir_a", a), ("dir_b", b)] {
+            let path = root.join(side).join(name);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, content).unwrap();
+        }
+    }
+}
+
+fn compare(root: &Path, strategy: &ExternalCommandStrategy, threads: usize) -> ComparisonResult {
+    compare_directories_with_options(
+        &root.join("dir_a"),
+        &root.join("dir_b"),
+        strategy,
+        None,
+        &ComparisonOptions {
+            threads: Some(threads),
+            ..Default::default()
+        },
+    )
+    .unwrap()
+}
+
+/// A strategy running the `sh` script for `*.pdf` files, with `$1` and
+/// `$2` the two paths, and hashing everything else
+#[cfg(unix)]
+fn sh_strategy(script: &str, timeout: Duration, max_processes: usize) -> ExternalCommandStrategy {
+    let rule = ExternalRule::new("*.pdf", &["sh", "-c", script, "sh", "{a}", "{b}"]).unwrap();
+    let rules = ExternalRules {
+        rules: vec![rule],
+        timeout,
+        max_processes,
+    };
+    ExternalCommandStrategy::new(Box::new(FastHashStrategy::new(false)), rules)
+}
+
+fn names(pairs: &[(dir_compare_core::Entry, dir_compare_core::Entry)]) -> Vec<String> {
+    pairs
+        .iter()
+        .map(|(a, _)| a.path.display().to_string())
+        .collect()
+}
+
+#[cfg(unix)]
+#[test]
+fn test_exit_status_decides_matches() {
+    let temp = tempfile::tempdir().unwrap();
+    setup(
+        temp.path(),
+        &[
+            ("same-text.pdf", "%PDF created 2023", "%PDF created 2024"),
+            ("other.pdf", "%PDF page 1", "%PDF page 2"),
+        ],
+    );
+    let script = r#"[ "$(tail -c 6 "$1")" = "page 1" ] || { echo "page 2 differs" >&2; exit 1; }"#;
+    let strategy = sh_strategy(script, DEFAULT_EXTERNAL_TIMEOUT, 2);
+    let result = compare(temp.path(), &strategy, 1);
+
+    // The script only looks at the end of A, so contents do not decide
+    assert_eq!(names(&result.both), ["other.pdf"]);
+    assert_eq!(names(&result.modified), ["same-text.pdf"]);
+    assert_eq!(strategy.commands_run(), 2);
+    assert_eq!(result.warnings.len(), 1);
+    let warning = &result.warnings[0];
+    assert_eq!(warning.cause, WarningCause::External);
+    assert!(warning.path.ends_with("same-text.pdf"));
+    assert!(
+        warning.message.contains("exited with") && warning.message.ends_with("page 2 differs"),
+        "{}",
+        warning.message
+    );
+    assert!(warning.to_string().starts_with("External comparison of "));
+}
+
+#[cfg(unix)]
+#[test]
+fn test_silent_mismatch_has_no_warning() {
+    let temp = tempfile::tempdir().unwrap();
+    setup(temp.path(), &[("a.pdf", "1", "1")]);
+    let strategy = sh_strategy("exit 3", DEFAULT_EXTERNAL_TIMEOUT, 1);
+    let result = compare(temp.path(), &strategy, 1);
+
+    assert_eq!(names(&result.modified), ["a.pdf"]);
+    assert!(result.warnings.is_empty());
+}
+
+#[cfg(unix)]
+#[test]
+fn test_other_files_use_the_inner_strategy() {
+    let temp = tempfile::tempdir().unwrap();
+    setup(
+        temp.path(),
+        &[
+            ("same.txt", "x", "x"),
+            ("changed.txt", "x", "y"),
+            ("docs/report.pdf", "1", "2"),
+            ("report.pdf.txt", "z", "z"),
+        ],
+    );
+    let strategy = sh_strategy("exit 0", DEFAULT_EXTERNAL_TIMEOUT, 1);
+    let result = compare(temp.path(), &strategy, 2);
+
+    assert_eq!(
+        names(&result.both),
+        ["docs", "docs/report.pdf", "report.pdf.txt", "same.txt"]
+    );
+    assert_eq!(names(&result.modified), ["changed.txt"]);
+    assert_eq!(strategy.commands_run(), 1);
+}
+
+#[test]
+fn test_unmatched_patterns_start_no_process() {
+    let temp = tempfile::tempdir().unwrap();
+    setup(temp.path(), &[("a.txt", "x", "x"), ("b.txt", "x", "y")]);
+    // Running this command would fail every pair it compares
+    let rule = ExternalRule::new("*.pdf", &["/nonexistent/compare", "{a}", "{b}"]).unwrap();
+    let strategy = ExternalCommandStrategy::new(
+        Box::new(FastHashStrategy::new(false)),
+        ExternalRules {
+            rules: vec![rule],
+            ..Default::default()
+        },
+    );
+    let result = compare(temp.path(), &strategy, 2);
+
+    assert_eq!(names(&result.both), ["a.txt"]);
+    assert_eq!(names(&result.modified), ["b.txt"]);
+    assert!(result.warnings.is_empty());
+    assert_eq!(strategy.commands_run(), 0);
+}
+
+#[test]
+fn test_command_that_cannot_start() {
+    let temp = tempfile::tempdir().unwrap();
+    setup(temp.path(), &[("a.pdf", "x", "x")]);
+    let rule = ExternalRule::new("*.pdf", &["/nonexistent/compare", "{a}", "{b}"]).unwrap();
+    let strategy = ExternalCommandStrategy::new(
+        Box::new(FastHashStrategy::new(false)),
+        ExternalRules {
+            rules: vec![rule],
+            ..Default::default()
+        },
+    );
+    let result = compare(temp.path(), &strategy, 1);
+
+    assert_eq!(names(&result.modified), ["a.pdf"]);
+    assert_eq!(result.warnings.len(), 1);
+    assert_eq!(result.warnings[0].cause, WarningCause::External);
+    assert!(
+        result.warnings[0]
+            .message
+            .starts_with("could not run `/nonexistent/compare`"),
+        "{}",
+        result.warnings[0].message
+    );
+}
+
+#[cfg(unix)]
+#[test]
+fn test_slow_command_times_out() {
+    let temp = tempfile::tempdir().unwrap();
+    setup(
+        temp.path(),
+        &[("slow.pdf", "x", "x"), ("fast.pdf", "x", "x")],
+    );
+    let script = r#"case "$1" in *slow.pdf) exec sleep 10;; esac"#;
+    let strategy = sh_strategy(script, Duration::from_millis(300), 2);
+    let started = std::time::Instant::now();
+    let result = compare(temp.path(), &strategy, 2);
+
+    assert!(started.elapsed() < Duration::from_secs(5));
+    assert_eq!(names(&result.both), ["fast.pdf"]);
+    assert_eq!(names(&result.modified), ["slow.pdf"]);
+    assert_eq!(result.warnings.len(), 1);
+    assert!(
+        result.warnings[0]
+            .message
+            .contains("did not finish within 300ms and was killed"),
+        "{}",
+        result.warnings[0].message
+    );
+}
+
+#[cfg(unix)]
+#[test]
+fn test_paths_reach_the_command_as_single_arguments() {
+    let temp = tempfile::tempdir().unwrap();
+    setup(
+        temp.path(),
+        &[("my docs/写真 'quoted' $HOME.pdf", "x", "y")],
+    );
+    let rule = ExternalRule::new(
+        "*.pdf",
+        &[
+            "sh",
+            "-c",
+            r#"[ $# -eq 2 ] && [ -f "${1#--left=}" ] && [ -f "$2" ] && [ "${2##*/}" = "写真 'quoted' \$HOME.pdf" ]"#,
+            "sh",
+            "--left={a}",
+            "{b}",
+        ],
+    )
+    .unwrap();
+    let strategy = ExternalCommandStrategy::new(
+        Box::new(FastHashStrategy::new(false)),
+        ExternalRules {
+            rules: vec![rule],
+            ..Default::default()
+        },
+    );
+    let result = compare(temp.path(), &strategy, 1);
+
+    assert_eq!(
+        names(&result.both),
+        ["my docs", "my docs/写真 'quoted' $HOME.pdf"]
+    );
+}
+
+#[cfg(unix)]
+#[test]
+fn test_commands_run_within_the_process_limit() {
+    let temp = tempfile::tempdir().unwrap();
+    let files: Vec<String> = (0..6).map(|i| format!("{}.pdf", i)).collect();
+    let entries: Vec<(&str, &str, &str)> =
+        files.iter().map(|name| (name.as_str(), "x", "x")).collect();
+    setup(temp.path(), &entries);
+    // mkdir fails while another command holds the lock
+    let lock = temp.path().join("lock");
+    let script = format!(
+        "mkdir '{0}' || exit 1; sleep 0.05; rmdir '{0}'",
+        lock.display()
+    );
+    let strategy = sh_strategy(&script, DEFAULT_EXTERNAL_TIMEOUT, 1);
+    let result = compare(temp.path(), &strategy, 4);
+
+    assert_eq!(result.both.len(), 6, "{:?}", result.warnings);
+    assert_eq!(strategy.commands_run(), 6);
+}
+
+#[test]
+fn test_command_substitutes_paths_in_every_word() {
+    let rule = ExternalRule::new(
+        "*.pdf",
+        &["diff-tool", "--left={a}", "{b}", "{a}{b}", "{c}", "{"],
+    )
+    .unwrap();
+    let command = rule.command(Path::new("/x/a b.pdf"), Path::new("/y/写真.pdf"));
+    assert_eq!(command.get_program(), "diff-tool");
+    let args: Vec<_> = command.get_args().collect();
+    assert_eq!(
+        args,
+        [
+            "--left=/x/a b.pdf",
+            "/y/写真.pdf",
+            "/x/a b.pdf/y/写真.pdf",
+            "{c}",
+            "{"
+        ]
+    );
+    assert_eq!(rule.program(), "diff-tool");
+    assert_eq!(rule.pattern(), "*.pdf");
+    assert!(rule.matches(Path::new("docs/x.pdf")));
+    assert!(!rule.matches(Path::new("docs/x.pdf.txt")));
+}
+
+#[test]
+fn test_invalid_rules_are_rejected() {
+    assert!(ExternalRule::new("*.pdf", &[] as &[&str]).is_err());
+    let missing_b = ExternalRule::new("*.pdf", &["cmp", "{a}"]).unwrap_err();
+    assert!(
+        missing_b.to_string().contains("must contain {b}"),
+        "{}",
+        missing_b
+    );
+    assert!(ExternalRule::new("{x,y", &["cmp", "{a}", "{b}"]).is_err());
+}
+
+#[test]
+fn test_parse_external_rules() {
+    let rules = parse_external_rules(
+        r#"
+timeout-secs = 5
+max-processes = 3
+
+[[rule]]
+pattern = "*.pdf"
+command = "pdf-compare --mode 'ignore dates' {a} {b}"
+
+[[rule]]
+pattern = "/images/**"
+command = ["img diff", "{a}", "{b}"]
+"#,
+    )
+    .unwrap();
+    assert_eq!(rules.timeout, Duration::from_secs(5));
+    assert_eq!(rules.max_processes, 3);
+    assert_eq!(rules.rules.len(), 2);
+
+    let pdf = rules.rule_for(Path::new("docs/a.pdf")).unwrap();
+    let args: Vec<_> = pdf
+        .command(Path::new("/a"), Path::new("/b"))
+        .get_args()
+        .map(|arg| arg.to_os_string())
+        .collect();
+    assert_eq!(args, ["--mode", "ignore dates", "/a", "/b"]);
+    assert_eq!(
+        rules.rule_for(Path::new("images/x.png")).unwrap().program(),
+        "img diff"
+    );
+    assert!(rules.rule_for(Path::new("notes.txt")).is_none());
+
+    let defaults = parse_external_rules("").unwrap();
+    assert_eq!(defaults.timeout, DEFAULT_EXTERNAL_TIMEOUT);
+    assert!(defaults.max_processes >= 1);
+    assert!(defaults.rules.is_empty());
+}
+
+#[test]
+fn test_parse_external_rules_errors() {
+    for (text, expected) in [
+        (
+            "timeout-secs = 0",
+            "'timeout-secs' must be a positive integer",
+        ),
+        (
+            "max-processes = \"4\"",
+            "'max-processes' must be a positive integer",
+        ),
+        ("jobs = 4", "unknown key 'jobs'"),
+        ("rule = 1", "'rule' must be an array of tables"),
+        (
+            "[[rule]]\npattern = \"*.pdf\"\ncommand = \"cmp {a}\"",
+            "rule 1: the command for '*.pdf' must contain {b}",
+        ),
+        (
+            "[[rule]]\npattern = \"*.pdf\"\ncommand = \"cmp '{a} {b}\"",
+            "rule 1: unterminated single quote",
+        ),
+        (
+            "[[rule]]\npattern = \"*.pdf\"\ncommand = [\"cmp\", 1]",
+            "rule 1: 'command' must list strings",
+        ),
+        (
+            "[[rule]]\ncommand = \"cmp {a} {b}\"",
+            "rule 1: 'pattern' must be a string",
+        ),
+        (
+            "[[rule]]\npattern = \"*\"\ncommand = \"cmp {a} {b}\"\ntimeout = 3",
+            "rule 1: unknown key 'timeout'",
+        ),
+        ("timeout-secs = ", "line 1"),
+    ] {
+        let error = parse_external_rules(text).unwrap_err();
+        assert!(error.message.contains(expected), "{}: {}", text, error);
+        assert!(error.to_string().starts_with("Invalid external rules: "));
+    }
+}
@@ -0,0 +1,276 @@
+use dir_compare_core::comparison::{
+    ComparisonOptions, EntryFilter, FilenameOnlyStrategy, FlatComparisonOptions,
+    compare_directories_flat, compare_directories_with_options,
+};
+use dir_compare_core::{ComparisonResult, assert_result_matches};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Creates the same tree in dir_a and dir_b
+fn setup_tree() -> tempfile::TempDir {
+    let temp = tempfile::tempdir().unwrap();
+    for side in ["dir_a", "dir_b"] {
+        let root = temp.path().join(side);
+        for file in [
+            "README.md",
+            "build/out.bin",
+            "docs/guide.md",
+            "docs/api/index.md",
+            "docs/api/notes.txt",
+            "src/main.rs",
+            "src/build/gen.rs",
+            "src/scratch.tmp",
+        ] {
+            let path = root.join(file);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, file).unwrap();
+        }
+    }
+    temp
+}
+
+fn compare(root: &Path, filter: EntryFilter, ignore_file: Option<&Path>) -> ComparisonResult {
+    let options = ComparisonOptions {
+        filter: Some(filter),
+        ..Default::default()
+    };
+    compare_directories_with_options(
+        &root.join("dir_a"),
+        &root.join("dir_b"),
+        &FilenameOnlyStrategy::new(false),
+        ignore_file,
+        &options,
+    )
+    .unwrap()
+}
+
+fn filter(include: &[&str], exclude: &[&str]) -> EntryFilter {
+    EntryFilter::new(include, exclude).unwrap()
+}
+
+#[test]
+fn test_double_star_include_keeps_leading_directories() {
+    let temp = setup_tree();
+    let result = compare(temp.path(), filter(&["docs/**/*.md"], &[]), None);
+
+    assert_result_matches!(
+        result,
+        a_only: [],
+        b_only: [],
+        both: ["docs", "docs/api", "docs/api/index.md", "docs/guide.md"],
+    );
+}
+
+#[test]
+fn test_anchored_exclude_only_matches_at_root() {
+    let temp = setup_tree();
+    let result = compare(temp.path(), filter(&[], &["/build/", "docs"]), None);
+
+    assert_result_matches!(
+        result,
+        both: [
+            "README.md",
+            "src",
+            "src/build",
+            "src/build/gen.rs",
+            "src/main.rs",
+            "src/scratch.tmp",
+        ],
+    );
+}
+
+#[test]
+fn test_unanchored_exclude_matches_at_any_depth() {
+    let temp = setup_tree();
+    let result = compare(temp.path(), filter(&[], &["build/", "**/api"]), None);
+
+    assert_result_matches!(
+        result,
+        both: [
+            "README.md",
+            "docs",
+            "docs/guide.md",
+            "src",
+            "src/main.rs",
+            "src/scratch.tmp",
+        ],
+    );
+}
+
+#[test]
+fn test_excludes_apply_after_includes() {
+    let temp = setup_tree();
+    let result = compare(
+        temp.path(),
+        filter(&["docs/", "*.rs"], &["*.txt", "src/build"]),
+        None,
+    );
+
+    assert_result_matches!(
+        result,
+        both: [
+            "docs",
+            "docs/api",
+            "docs/api/index.md",
+            "docs/guide.md",
+            "src",
+            "src/main.rs",
+        ],
+    );
+}
+
+#[test]
+fn test_filter_merges_with_ignore_file() {
+    let temp = setup_tree();
+    let ignore_file = temp.path().join(".dcignore");
+    fs::write(&ignore_file, "*.tmp\napi/\n").unwrap();
+
+    // An include pattern does not bring back what the ignore file skips
+    let result = compare(
+        temp.path(),
+        filter(&["src/", "docs/"], &["/src/build/"]),
+        Some(&ignore_file),
+    );
+    assert_result_matches!(
+        result,
+        both: ["docs", "docs/guide.md", "src", "src/main.rs"],
+    );
+}
+
+#[test]
+fn test_filter_selects_only_one_side() {
+    let temp = setup_tree();
+    fs::write(temp.path().join("dir_a/src/extra.rs"), "extra").unwrap();
+    fs::write(temp.path().join("dir_b/extra.log"), "log").unwrap();
+
+    let result = compare(temp.path(), filter(&["*.rs"], &["*.log"]), None);
+    assert_result_matches!(result, a_only: ["src/extra.rs"], b_only: []);
+}
+
+#[test]
+fn test_flat_mode_applies_filter() {
+    let temp = setup_tree();
+    let options = FlatComparisonOptions {
+        filter: Some(filter(&["**/*.md"], &["docs/api/"])),
+        ..Default::default()
+    };
+    let result = compare_directories_flat(
+        &temp.path().join("dir_a"),
+        &temp.path().join("dir_b"),
+        &options,
+        None,
+    )
+    .unwrap();
+
+    let mut files: Vec<PathBuf> = result
+        .groups
+        .iter()
+        .flat_map(|group| group.files_in_a.clone())
+        .collect();
+    files.sort();
+    assert_eq!(
+        files,
+        [PathBuf::from("README.md"), PathBuf::from("docs/guide.md")]
+    );
+    assert_eq!(result.total_files_b, 2);
+}
+
+#[test]
+fn test_matches_single_paths() {
+    let filter = filter(&["src/**"], &["*.tmp"]);
+    assert!(filter.matches(Path::new("src/main.rs"), false));
+    assert!(filter.matches(Path::new("src/build"), true));
+    assert!(!filter.matches(Path::new("src/scratch.tmp"), false));
+    assert!(!filter.matches(Path::new("README.md"), false));
+    assert!(EntryFilter::default().is_empty());
+    assert!(!filter.is_empty());
+}
+
+#[test]
+fn test_invalid_glob_is_rejected() {
+    let err = EntryFilter::new(&["*.rs"], &["src/{a,b"]).unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+    assert!(err.to_string().contains("src/{a,b"), "{}", err);
+}
+
+/// Adds VCS metadata to both sides: a root `.git`, a nested repository with
+/// its own `.git` directory, a submodule `.git` file and a `.hg` directory
+fn add_vcs_metadata(root: &Path) {
+    for side in ["dir_a", "dir_b"] {
+        let side = root.join(side);
+        for file in [
+            ".git/HEAD",
+            "vendor/lib/.git/config",
+            "vendor/lib/lib.rs",
+            "vendor/sub/sub.rs",
+            "docs/.hg/store",
+        ] {
+            let path = side.join(file);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, file).unwrap();
+        }
+        fs::write(
+            side.join("vendor/sub/.git"),
+            "gitdir: ../../.git/modules/sub",
+        )
+        .unwrap();
+    }
+    fs::write(root.join("dir_a/vendor/lib/.git/config"), "changed").unwrap();
+}
+
+#[test]
+fn test_vcs_aware_excludes_metadata_and_marks_nested_repositories() {
+    let temp = setup_tree();
+    add_vcs_metadata(temp.path());
+    let result = compare(
+        temp.path(),
+        EntryFilter::default().with_vcs_aware(true),
+        None,
+    );
+
+    let paths: Vec<String> = result
+        .both
+        .iter()
+        .map(|(a, _)| a.path.to_string_lossy().into_owned())
+        .collect();
+    assert!(
+        paths
+            .iter()
+            .all(|path| !path.contains(".git") && !path.contains(".hg"))
+    );
+    assert!(result.modified.is_empty());
+
+    let nested: Vec<&str> = result
+        .both
+        .iter()
+        .filter(|(a, b)| a.nested_repository && b.nested_repository)
+        .map(|(a, _)| a.path.to_str().unwrap())
+        .collect();
+    assert_eq!(nested, ["vendor/lib", "vendor/sub"]);
+}
+
+#[test]
+fn test_vcs_aware_off_leaves_results_unchanged() {
+    let temp = setup_tree();
+    let before = compare(temp.path(), filter(&[], &["*.tmp"]), None);
+    add_vcs_metadata(temp.path());
+    let after = compare(temp.path(), filter(&[], &["*.tmp"]), None);
+
+    let plain: Vec<&PathBuf> = before.both.iter().map(|(a, _)| &a.path).collect();
+    let with_vcs: Vec<&PathBuf> = after
+        .both
+        .iter()
+        .map(|(a, _)| &a.path)
+        .filter(|path| !path.starts_with("vendor"))
+        // Hidden entries are listed, the metadata with them
+        .filter(|path| !path.iter().any(|name| name == ".git" || name == ".hg"))
+        .collect();
+    assert_eq!(plain, with_vcs);
+    assert!(
+        after
+            .both
+            .iter()
+            .all(|(a, b)| !a.nested_repository && !b.nested_repository)
+    );
+}
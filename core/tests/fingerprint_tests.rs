@@ -0,0 +1,52 @@
+use dir_compare_core::TreeFingerprint;
+use std::fs::{self, File};
+use std::io;
+use std::time::{Duration, SystemTime};
+use tempfile::TempDir;
+
+fn set_mtime(path: &std::path::Path, mtime: SystemTime) {
+    File::options()
+        .write(true)
+        .open(path)
+        .unwrap()
+        .set_modified(mtime)
+        .unwrap();
+}
+
+#[test]
+fn test_fingerprint_counts_entries_below_root() {
+    let dir = TempDir::new().unwrap();
+    fs::create_dir(dir.path().join("sub")).unwrap();
+    fs::write(dir.path().join("sub/a.txt"), "a").unwrap();
+    fs::write(dir.path().join(".hidden"), "h").unwrap();
+
+    let fingerprint = TreeFingerprint::capture(dir.path()).unwrap();
+    assert_eq!(fingerprint.entries, 3);
+    assert!(fingerprint.latest_mtime.is_some());
+    assert_eq!(TreeFingerprint::capture(dir.path()).unwrap(), fingerprint);
+}
+
+#[test]
+fn test_fingerprint_changes_with_added_and_modified_files() {
+    let dir = TempDir::new().unwrap();
+    let file = dir.path().join("a.txt");
+    fs::write(&file, "a").unwrap();
+    let before = TreeFingerprint::capture(dir.path()).unwrap();
+
+    // Later than the root, whose own time also counts
+    set_mtime(&file, SystemTime::now() + Duration::from_secs(3600));
+    let modified = TreeFingerprint::capture(dir.path()).unwrap();
+    assert_eq!(modified.entries, before.entries);
+    assert_ne!(modified, before);
+
+    fs::write(dir.path().join("b.txt"), "b").unwrap();
+    let added = TreeFingerprint::capture(dir.path()).unwrap();
+    assert_eq!(added.entries, 2);
+}
+
+#[test]
+fn test_fingerprint_of_missing_root_is_an_error() {
+    let dir = TempDir::new().unwrap();
+    let err = TreeFingerprint::capture(&dir.path().join("gone")).unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::NotFound);
+}
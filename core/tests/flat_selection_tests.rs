@@ -0,0 +1,132 @@
+use dir_compare_core::comparison::{
+    FlatComparisonOptions, FlatComparisonResult, compare_directories_flat,
+};
+use std::fs;
+use std::path::{Path, PathBuf};
+use tempfile::TempDir;
+
+/// A photo library in `a` and its copy in `b`: two photos of 1000 bytes in
+/// each, a `.JPG` only in B, an empty `.jpg` and an empty `.txt` on both
+/// sides, and a text file without an extension.
+fn setup() -> TempDir {
+    let temp = TempDir::new().unwrap();
+    let photo = |seed: u8| vec![seed; 1000];
+    for side in ["a", "b"] {
+        let dir = temp.path().join(side);
+        fs::create_dir_all(dir.join("2024")).unwrap();
+        fs::write(dir.join("2024/beach.jpg"), photo(1)).unwrap();
+        fs::write(dir.join("2024/sunset.png"), photo(2)).unwrap();
+        fs::write(dir.join("2024/empty.jpg"), "").unwrap();
+        fs::write(dir.join("notes.txt"), "").unwrap();
+        fs::write(dir.join("README"), "photos from 2024").unwrap();
+    }
+    fs::write(temp.path().join("b/2024/copy.JPG"), photo(1)).unwrap();
+    temp
+}
+
+fn compare(root: &Path, options: &FlatComparisonOptions) -> FlatComparisonResult {
+    compare_directories_flat(&root.join("a"), &root.join("b"), options, None).unwrap()
+}
+
+fn grouped_paths(result: &FlatComparisonResult) -> Vec<&PathBuf> {
+    let mut paths: Vec<&PathBuf> = result
+        .groups
+        .iter()
+        .flat_map(|group| group.files_in_a.iter().chain(&group.files_in_b))
+        .collect();
+    paths.sort();
+    paths
+}
+
+#[test]
+fn test_extensions_select_files_case_insensitively() {
+    let temp = setup();
+    let options = FlatComparisonOptions {
+        extensions: Some(vec!["jpg".to_string(), ".png".to_string()]),
+        ..Default::default()
+    };
+    let result = compare(temp.path(), &options);
+    assert_eq!(
+        grouped_paths(&result),
+        [
+            "2024/beach.jpg",
+            "2024/beach.jpg",
+            "2024/copy.JPG",
+            "2024/empty.jpg",
+            "2024/empty.jpg",
+            "2024/sunset.png",
+            "2024/sunset.png",
+        ]
+        .map(PathBuf::from)
+        .iter()
+        .collect::<Vec<_>>()
+    );
+    assert_eq!(result.total_files_a, 3);
+    assert_eq!(result.total_files_b, 4);
+    assert_eq!(result.stats.files_a, 3);
+    assert_eq!(result.stats.files_b, 4);
+    assert_eq!(result.unique_hashes, 3);
+    assert_eq!(result.duplicate_count, 3);
+}
+
+#[test]
+fn test_min_file_size_leaves_out_small_files() {
+    let temp = setup();
+    let options = FlatComparisonOptions {
+        min_file_size: 1,
+        ..Default::default()
+    };
+    let result = compare(temp.path(), &options);
+    let paths = grouped_paths(&result);
+    assert!(
+        paths
+            .iter()
+            .all(|path| !path.ends_with("empty.jpg") && !path.ends_with("notes.txt")),
+        "{:?}",
+        paths
+    );
+    assert_eq!(result.total_files_a, 3);
+    assert_eq!(result.total_files_b, 4);
+    // The empty files no longer form a duplicate group
+    assert_eq!(result.unique_hashes, 3);
+    assert_eq!(result.stats.bytes_a, 2016);
+
+    let options = FlatComparisonOptions {
+        min_file_size: 1000,
+        ..Default::default()
+    };
+    let result = compare(temp.path(), &options);
+    assert_eq!(result.total_files_a, 2);
+    assert!(
+        grouped_paths(&result)
+            .iter()
+            .all(|path| path.starts_with("2024"))
+    );
+}
+
+#[test]
+fn test_selection_happens_before_hashing() {
+    let temp = setup();
+    let options = FlatComparisonOptions {
+        use_full_hash: true,
+        extensions: Some(vec!["png".to_string()]),
+        min_file_size: 1,
+        ..Default::default()
+    };
+    let result = compare(temp.path(), &options);
+    assert_eq!(result.groups.len(), 1);
+    assert_eq!(result.groups[0].file_count, 2);
+    // Only the two PNG files were read
+    assert_eq!(result.stats.bytes_hashed, 2000);
+    assert!(result.warnings.is_empty());
+
+    let everything = compare(
+        temp.path(),
+        &FlatComparisonOptions {
+            use_full_hash: true,
+            ..Default::default()
+        },
+    );
+    assert_eq!(everything.total_files_a, 5);
+    assert_eq!(everything.total_files_b, 6);
+}
@@ -0,0 +1,130 @@
+use dir_compare_core::comparison::{
+    ComparisonOptions, FilenameOnlyStrategy, FlatComparisonOptions, TraversalOptions,
+    compare_directories_flat, compare_directories_with_options, traverse_directory_with_options,
+};
+use std::fs;
+use std::path::{Path, PathBuf};
+use tempfile::TempDir;
+
+/// Lays out the same tree in `a` and `b`: the root `.gitignore` ignores
+/// `*.log` and `build/`, and `src/.gitignore` re-includes `keep.log` and
+/// ignores `generated.rs`. No `.git` directory exists, and the hidden
+/// `.gitignore` files are left out with `ignore_hidden`.
+fn setup() -> TempDir {
+    let temp = TempDir::new().unwrap();
+    for side in ["a", "b"] {
+        let dir = temp.path().join(side);
+        fs::create_dir_all(dir.join("build")).unwrap();
+        fs::create_dir_all(dir.join("src")).unwrap();
+        fs::write(dir.join(".gitignore"), "*.log\nbuild/\n").unwrap();
+        fs::write(dir.join("src/.gitignore"), "!keep.log\ngenerated.rs\n").unwrap();
+        fs::write(dir.join("main.txt"), side).unwrap();
+        fs::write(dir.join("debug.log"), side).unwrap();
+        fs::write(dir.join("build/out.bin"), side).unwrap();
+        fs::write(dir.join("src/lib.rs"), side).unwrap();
+        fs::write(dir.join("src/keep.log"), side).unwrap();
+        fs::write(dir.join("src/drop.log"), side).unwrap();
+        fs::write(dir.join("src/generated.rs"), side).unwrap();
+    }
+    temp
+}
+
+/// Paths relative to `dir` listed with `respect_gitignore`
+fn listed(dir: &Path, respect_gitignore: bool) -> Vec<PathBuf> {
+    let options = TraversalOptions {
+        respect_gitignore,
+        ignore_hidden: true,
+        ..Default::default()
+    };
+    let mut paths: Vec<PathBuf> = traverse_directory_with_options(dir, None, &options)
+        .unwrap()
+        .into_iter()
+        .map(|entry| entry.path.strip_prefix(dir).unwrap().to_path_buf())
+        .collect();
+    paths.sort();
+    paths
+}
+
+fn paths(list: &[&str]) -> Vec<PathBuf> {
+    list.iter().map(PathBuf::from).collect()
+}
+
+#[test]
+fn test_traversal_honors_nested_gitignore_files() {
+    let temp = setup();
+    assert_eq!(
+        listed(&temp.path().join("a"), true),
+        paths(&["main.txt", "src", "src/keep.log", "src/lib.rs",])
+    );
+}
+
+#[test]
+fn test_gitignore_files_are_ignored_by_default() {
+    let temp = setup();
+    let all = listed(&temp.path().join("a"), false);
+    assert_eq!(all.len(), 9, "{:?}", all);
+    assert!(all.contains(&PathBuf::from("build/out.bin")));
+    assert!(all.contains(&PathBuf::from("src/generated.rs")));
+}
+
+#[test]
+fn test_hierarchy_comparison_respects_gitignore() {
+    let temp = setup();
+    let compare = |respect_gitignore| {
+        let options = ComparisonOptions {
+            respect_gitignore,
+            ignore_hidden: true,
+            ..Default::default()
+        };
+        compare_directories_with_options(
+            &temp.path().join("a"),
+            &temp.path().join("b"),
+            &FilenameOnlyStrategy::new(false),
+            None,
+            &options,
+        )
+        .unwrap()
+    };
+
+    let result = compare(true);
+    let mut both: Vec<_> = result.both.iter().map(|(a, _)| a.path.clone()).collect();
+    both.sort();
+    assert_eq!(
+        both,
+        paths(&["main.txt", "src", "src/keep.log", "src/lib.rs",])
+    );
+    assert_eq!(result.stats.files_a, 3);
+
+    assert_eq!(compare(false).both.len(), 9);
+}
+
+#[test]
+fn test_flat_comparison_respects_gitignore() {
+    let temp = setup();
+    let compare = |respect_gitignore| {
+        let options = FlatComparisonOptions {
+            respect_gitignore,
+            ignore_hidden: true,
+            ..Default::default()
+        };
+        compare_directories_flat(
+            &temp.path().join("a"),
+            &temp.path().join("b"),
+            &options,
+            None,
+        )
+        .unwrap()
+    };
+
+    let result = compare(true);
+    let mut files_a: Vec<_> = result
+        .groups
+        .iter()
+        .flat_map(|group| group.files_in_a.clone())
+        .collect();
+    files_a.sort();
+    assert_eq!(files_a, paths(&["main.txt", "src/keep.log", "src/lib.rs"]));
+    assert_eq!(result.stats.files_b, 3);
+
+    assert_eq!(compare(false).stats.files_b, 7);
+}
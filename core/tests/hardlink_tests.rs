@@ -0,0 +1,247 @@
+use dir_compare_core::comparison::{ComparisonResult, Entry, EntryKind};
+use dir_compare_core::hardlinks::{
+    ByteTotals, HardLinkReport, InodeKey, byte_totals, group_hard_links,
+};
+use dir_compare_core::output::{Labels, TextFormatter};
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+fn file(path: &str, size: u64) -> Entry {
+    Entry {
+        path: PathBuf::from(path),
+        abs_path: PathBuf::from(path),
+        kind: EntryKind::File,
+        size: Some(size),
+        nested_repository: false,
+        mtime: None,
+    }
+}
+
+fn dir(path: &str) -> Entry {
+    Entry {
+        path: PathBuf::from(path),
+        abs_path: PathBuf::from(path),
+        kind: EntryKind::Directory,
+        size: None,
+        nested_repository: false,
+        mtime: None,
+    }
+}
+
+fn key(ino: u64) -> InodeKey {
+    InodeKey { dev: 1, ino }
+}
+
+/// Builds a key lookup from (path, inode) pairs; unlisted paths have no key
+fn keys(pairs: &[(&str, u64)]) -> impl FnMut(&Entry) -> Option<InodeKey> {
+    let map: HashMap<PathBuf, InodeKey> = pairs
+        .iter()
+        .map(|(path, ino)| (PathBuf::from(path), key(*ino)))
+        .collect();
+    move |entry| map.get(&entry.path).copied()
+}
+
+fn paths(entries: &[Entry]) -> Vec<String> {
+    entries
+        .iter()
+        .map(|e| e.path.display().to_string())
+        .collect()
+}
+
+#[test]
+fn test_no_hard_links_keeps_every_entry() {
+    let entries = vec![file("a", 10), file("b", 20), dir("d")];
+    let groups = group_hard_links(&entries, keys(&[("a", 1), ("b", 2)]));
+
+    assert_eq!(groups.len(), 3);
+    assert!(groups.iter().all(|g| g.links.is_empty()));
+    assert_eq!(
+        byte_totals(&groups),
+        ByteTotals {
+            raw: 30,
+            unique: 30
+        }
+    );
+}
+
+#[test]
+fn test_links_grouped_under_first_path() {
+    let entries = vec![
+        file("cur/1", 100),
+        file("other", 5),
+        file("new/1", 100),
+        file("tmp/1", 100),
+    ];
+    let groups = group_hard_links(
+        &entries,
+        keys(&[("cur/1", 7), ("other", 8), ("new/1", 7), ("tmp/1", 7)]),
+    );
+
+    assert_eq!(groups.len(), 2);
+    assert_eq!(groups[0].primary.path, PathBuf::from("cur/1"));
+    assert_eq!(paths(&groups[0].links), vec!["new/1", "tmp/1"]);
+    assert_eq!(groups[1].primary.path, PathBuf::from("other"));
+
+    let totals = byte_totals(&groups);
+    assert_eq!(totals.raw, 305);
+    assert_eq!(totals.unique, 105);
+}
+
+#[test]
+fn test_same_inode_on_different_devices_not_grouped() {
+    let entries = vec![file("x", 10), file("y", 10)];
+    let mut lookup: HashMap<PathBuf, InodeKey> = HashMap::new();
+    lookup.insert(PathBuf::from("x"), InodeKey { dev: 1, ino: 5 });
+    lookup.insert(PathBuf::from("y"), InodeKey { dev: 2, ino: 5 });
+    let groups = group_hard_links(&entries, |e| lookup.get(&e.path).copied());
+
+    assert_eq!(groups.len(), 2);
+}
+
+#[test]
+fn test_entries_without_keys_and_directories_never_grouped() {
+    let entries = vec![file("a", 1), file("b", 1), dir("d1"), dir("d2")];
+    // Directories are never looked up, even if a key would be returned
+    let groups = group_hard_links(&entries, |_| None);
+    assert_eq!(groups.len(), 4);
+
+    let groups = group_hard_links(&entries, |e| {
+        assert_eq!(e.kind, EntryKind::File);
+        Some(key(1))
+    });
+    assert_eq!(groups.len(), 3);
+}
+
+#[test]
+fn test_text_output_collapses_hard_links() {
+    let result = ComparisonResult {
+        a_only: vec![
+            file("cur/1", 1024),
+            file("new/1", 1024),
+            file("new/2", 1024),
+        ],
+        b_only: vec![file("solo", 10)],
+        both: vec![],
+        modified: vec![],
+        exact_counts: None,
+        warnings: Vec::new(),
+        stats: Default::default(),
+        context: None,
+        identical_subtrees: Vec::new(),
+        mismatch_reasons: Vec::new(),
+        renamed: Vec::new(),
+    };
+    let report = HardLinkReport::with_keys(
+        &result,
+        keys(&[("cur/1", 1), ("new/1", 1), ("new/2", 1), ("solo", 2)]),
+    );
+
+    let output = TextFormatter.format_with_hard_links(&result, &Labels::default(), &report, false);
+    assert!(output.contains("A-only (1 entries):"));
+    assert!(output.contains("  cur/1 (+ 2 hard links)\n"));
+    assert!(!output.contains("new/1"));
+    assert!(output.contains("Bytes: 3.0 KiB raw, 1.0 KiB unique"));
+    assert!(output.contains("B-only (1 entries):"));
+    assert!(output.contains("  solo\n"));
+    assert!(output.contains("Bytes: 10 B raw, 10 B unique"));
+
+    let verbose = TextFormatter.format_with_hard_links(&result, &Labels::default(), &report, true);
+    assert!(verbose.contains("      = new/1\n"));
+    assert!(verbose.contains("      = new/2\n"));
+}
+
+#[cfg(unix)]
+#[test]
+fn test_real_hard_links_deduplicated_bytes() {
+    use dir_compare_core::comparison::{FilenameOnlyStrategy, compare_directories};
+    use std::fs;
+
+    let temp = tempfile::tempdir().unwrap();
+    let dir_a = temp.path().join("a");
+    let dir_b = temp.path().join("b");
+    fs::create_dir_all(dir_a.join("cur")).unwrap();
+    fs::create_dir_all(&dir_b).unwrap();
+
+    fs::write(dir_a.join("cur/msg"), vec![b'x'; 1000]).unwrap();
+    for i in 0..5 {
+        fs::hard_link(dir_a.join("cur/msg"), dir_a.join(format!("link{}", i))).unwrap();
+    }
+    fs::write(dir_a.join("unique"), vec![b'y'; 500]).unwrap();
+
+    let result =
+        compare_directories(&dir_a, &dir_b, &FilenameOnlyStrategy::new(false), None).unwrap();
+    let report = HardLinkReport::collect(&result);
+
+    let files: Vec<_> = report
+        .a_only
+        .iter()
+        .filter(|g| g.primary.kind == EntryKind::File)
+        .collect();
+    assert_eq!(files.len(), 2);
+    assert_eq!(files.iter().map(|g| g.links.len()).sum::<usize>(), 5);
+
+    let totals = byte_totals(&report.a_only);
+    assert_eq!(totals.raw, 6 * 1000 + 500);
+    assert_eq!(totals.unique, 1000 + 500);
+}
+
+#[cfg(unix)]
+#[test]
+fn test_flat_mode_hashes_each_inode_once() {
+    use dir_compare_core::comparison::{FlatComparisonOptions, compare_directories_flat};
+    use dir_compare_core::output::{
+        FlatFormatter, FlatJsonFormatter, FlatMarkdownFormatter, FlatTextFormatter,
+    };
+    use std::fs;
+
+    // A holds a photo with a hard link and a true copy; B, made like
+    // `rsync --link-dest`, links to the same photo again
+    let temp = tempfile::tempdir().unwrap();
+    let dir_a = temp.path().join("a");
+    let dir_b = temp.path().join("b");
+    fs::create_dir_all(&dir_a).unwrap();
+    fs::create_dir_all(&dir_b).unwrap();
+    fs::write(dir_a.join("photo.jpg"), vec![b'x'; 1000]).unwrap();
+    fs::hard_link(dir_a.join("photo.jpg"), dir_a.join("photo-link.jpg")).unwrap();
+    fs::hard_link(dir_a.join("photo.jpg"), dir_b.join("photo.jpg")).unwrap();
+    fs::write(dir_a.join("copy.jpg"), vec![b'x'; 1000]).unwrap();
+
+    let options = FlatComparisonOptions {
+        use_full_hash: true,
+        ..Default::default()
+    };
+    let result = compare_directories_flat(&dir_a, &dir_b, &options, None).unwrap();
+    // The photo was read once for its three links and once for the copy
+    assert_eq!(result.stats.bytes_hashed, 2000);
+    assert_eq!(result.groups.len(), 1);
+    let group = &result.groups[0];
+    assert_eq!(group.file_count, 4);
+    assert_eq!(group.hard_links.len(), 3);
+    assert_eq!(group.hardlink_group(true, "photo.jpg".as_ref()), Some(1));
+    assert_eq!(
+        group.hardlink_group(true, "photo-link.jpg".as_ref()),
+        Some(1)
+    );
+    assert_eq!(group.hardlink_group(false, "photo.jpg".as_ref()), Some(1));
+    assert_eq!(group.hardlink_group(true, "copy.jpg".as_ref()), None);
+
+    let text = FlatTextFormatter.format(&result);
+    assert!(
+        text.contains("  [A] photo-link.jpg -> (moved/copied to B) (hard link 1)\n"),
+        "{}",
+        text
+    );
+    assert!(
+        text.contains("  [B] photo.jpg <- (moved/copied from A) (hard link 1)\n"),
+        "{}",
+        text
+    );
+    assert!(text.contains("  [A] copy.jpg -> (moved/copied to B)\n"));
+    let md = FlatMarkdownFormatter.format(&result);
+    assert!(md.contains("- `photo-link.jpg` *(moved/copied to B)* *(hard link 1)*\n"));
+    let json: serde_json::Value = serde_json::from_str(&FlatJsonFormatter.format(&result)).unwrap();
+    assert_eq!(
+        json["groups"][0]["hard_links"][2],
+        serde_json::json!({"side": "b", "path": "photo.jpg", "hardlink_group": 1})
+    );
+}
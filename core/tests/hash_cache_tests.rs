@@ -0,0 +1,92 @@
+use dir_compare_core::output::BundleHtmlFormatter;
+use dir_compare_core::{
+    CachedHashStrategy, ComparisonOptions, FlatComparisonOptions, HashAlgorithm, HashCache,
+    compare_directories_flat, compare_directories_with_options,
+};
+use std::fs;
+use std::sync::Arc;
+use tempfile::TempDir;
+
+/// Two trees with a same-path match, a same-path change, a move and a file
+/// on each side only
+fn setup() -> TempDir {
+    let temp = TempDir::new().unwrap();
+    let (a, b) = (temp.path().join("a"), temp.path().join("b"));
+    fs::create_dir_all(&a).unwrap();
+    fs::create_dir_all(&b).unwrap();
+    fs::write(a.join("same.txt"), "same").unwrap();
+    fs::write(b.join("same.txt"), "same").unwrap();
+    fs::write(a.join("notes.txt"), "old").unwrap();
+    fs::write(b.join("notes.txt"), "new").unwrap();
+    fs::write(a.join("old.bin"), "moved").unwrap();
+    fs::write(b.join("new.bin"), "moved").unwrap();
+    temp
+}
+
+#[test]
+fn test_hash_cache_reads_each_file_once() {
+    let temp = setup();
+    let (a, b) = (temp.path().join("a"), temp.path().join("b"));
+    let cache = Arc::new(HashCache::new(HashAlgorithm::Blake3, true));
+
+    let strategy = CachedHashStrategy::new(false, Arc::clone(&cache));
+    let hierarchy =
+        compare_directories_with_options(&a, &b, &strategy, None, &ComparisonOptions::default())
+            .unwrap();
+    assert_eq!(hierarchy.both.len(), 1);
+    assert_eq!(hierarchy.modified.len(), 1);
+    // Only the two same-path pairs are hashed
+    assert_eq!(cache.files_hashed(), 4);
+
+    let options = FlatComparisonOptions {
+        hash_cache: Some(Arc::clone(&cache)),
+        ..Default::default()
+    };
+    let flat = compare_directories_flat(&a, &b, &options, None).unwrap();
+    assert_eq!(flat.total_files_a + flat.total_files_b, 6);
+    assert_eq!(flat.unique_hashes, 4);
+    // The flat comparison only reads the two files the hierarchy skipped
+    assert_eq!(cache.files_hashed(), 6);
+    // Hashes come from the cache's algorithm: BLAKE3 digests are 64 hex digits
+    assert!(flat.groups.iter().all(|group| group.hash.len() == 64));
+}
+
+#[test]
+fn test_hash_cache_does_not_cache_failures() {
+    let temp = TempDir::new().unwrap();
+    let path = temp.path().join("late.txt");
+    let cache = HashCache::new(HashAlgorithm::Sha256, false);
+
+    assert!(cache.hash(&path).is_err());
+    assert_eq!(cache.files_hashed(), 0);
+    fs::write(&path, "now here").unwrap();
+    let hash = cache.hash(&path).unwrap();
+    assert_eq!(cache.hash(&path).unwrap(), hash);
+    assert_eq!(cache.files_hashed(), 1);
+}
+
+#[test]
+fn test_bundle_html_embeds_both_results() {
+    let temp = setup();
+    let (a, b) = (temp.path().join("a"), temp.path().join("b"));
+    let cache = Arc::new(HashCache::new(HashAlgorithm::Sha256, false));
+    let strategy = CachedHashStrategy::new(false, Arc::clone(&cache));
+    let hierarchy =
+        compare_directories_with_options(&a, &b, &strategy, None, &ComparisonOptions::default())
+            .unwrap();
+    let options = FlatComparisonOptions {
+        hash_cache: Some(cache),
+        ..Default::default()
+    };
+    let flat = compare_directories_flat(&a, &b, &options, None).unwrap();
+
+    let html = BundleHtmlFormatter.format(&hierarchy, &flat);
+    assert!(html.starts_with("<!DOCTYPE html>"));
+    assert!(html.ends_with("</html>\n"));
+    assert!(html.contains(r#"id="hierarchy-data""#));
+    assert!(html.contains(r#"id="flat-data""#));
+    assert!(html.contains(r#"<div class="section" data-section="modified">"#));
+    assert!(html.contains(r#"<div class="section" data-status="moved">"#));
+    assert!(html.contains(r#""path": "notes.txt""#));
+    assert!(html.contains(r#""files_in_b": ["new.bin"]"#));
+}
@@ -1,7 +1,9 @@
 #[cfg(test)]
 mod tests {
-    use dir_compare_core::compare_directories;
-    use dir_compare_core::comparison::FastHashStrategy;
+    use dir_compare_core::comparison::{
+        ComparisonStrategy, Entry, EntryKind, FastHashStrategy, SampledHashStrategy,
+    };
+    use dir_compare_core::{assert_result_matches, compare_directories};
     use std::fs::{self, File};
     use std::io::Write;
     use std::path::{Path, PathBuf};
@@ -27,14 +29,9 @@ mod tests {
             create_test_dir_with_files(temp_dir.path(), "dir_b", &[("file.txt", b"same content")]);
 
         let strategy = FastHashStrategy::new(false);
-        let result = compare_directories(&dir_a, &dir_b, &strategy, None);
+        let result = compare_directories(&dir_a, &dir_b, &strategy, None).unwrap();
 
-        assert!(result.is_ok());
-        let result = result.unwrap();
-
-        assert_eq!(result.both.len(), 1, "Expected matching content to match");
-        assert_eq!(result.a_only.len(), 0);
-        assert_eq!(result.b_only.len(), 0);
+        assert_result_matches!(result, a_only: [], b_only: [], both: ["file.txt"]);
     }
 
     #[test]
@@ -46,17 +43,72 @@ mod tests {
             create_test_dir_with_files(temp_dir.path(), "dir_b", &[("file.txt", b"content B")]);
 
         let strategy = FastHashStrategy::new(false);
-        let result = compare_directories(&dir_a, &dir_b, &strategy, None);
-
-        assert!(result.is_ok());
-        let result = result.unwrap();
+        let result = compare_directories(&dir_a, &dir_b, &strategy, None).unwrap();
 
-        assert_eq!(
-            result.both.len(),
-            0,
-            "Expected different content to NOT match"
+        // Different content should not match
+        assert_result_matches!(
+            result,
+            a_only: [],
+            b_only: [],
+            both: [],
+            modified: ["file.txt"],
         );
-        assert_eq!(result.a_only.len(), 1);
-        assert_eq!(result.b_only.len(), 1);
+    }
+
+    #[test]
+    fn test_fast_hash_strategy_unreadable_files_do_not_match() {
+        // Hashes are read from the absolute paths; two files that cannot be
+        // read must not match as if their contents were equal
+        let temp_dir = tempfile::tempdir().unwrap();
+        let entry = |side: &str| Entry {
+            path: PathBuf::from("file.txt"),
+            abs_path: temp_dir.path().join(side).join("file.txt"),
+            kind: EntryKind::File,
+            size: Some(7),
+            nested_repository: false,
+            mtime: None,
+        };
+
+        let strategy = FastHashStrategy::new(false);
+        let mut warnings = Vec::new();
+        assert!(!strategy.matches_reporting(&entry("dir_a"), &entry("dir_b"), &mut warnings));
+        assert_eq!(warnings.len(), 2, "{:?}", warnings);
+    }
+
+    #[test]
+    fn test_size_mismatch_is_decided_without_hashing() {
+        // Neither file exists, so hashing either would report a warning
+        let temp_dir = tempfile::tempdir().unwrap();
+        let entry = |side: &str, size: u64| Entry {
+            path: PathBuf::from("file.txt"),
+            abs_path: temp_dir.path().join(side).join("file.txt"),
+            kind: EntryKind::File,
+            size: Some(size),
+            nested_repository: false,
+            mtime: None,
+        };
+        let strategies: Vec<(&str, Box<dyn ComparisonStrategy>)> = vec![
+            ("hash", Box::new(FastHashStrategy::new(false))),
+            ("sampled", Box::new(SampledHashStrategy::new(false, false))),
+            ("verified", Box::new(SampledHashStrategy::new(false, true))),
+        ];
+        for (name, strategy) in strategies {
+            let mut warnings = Vec::new();
+            assert!(!strategy.matches_reporting(&entry("a", 7), &entry("b", 8), &mut warnings));
+            assert!(warnings.is_empty(), "{}: {:?}", name, warnings);
+
+            assert!(!strategy.matches_reporting(&entry("a", 7), &entry("b", 7), &mut warnings));
+            assert_eq!(warnings.len(), 2, "{}", name);
+        }
+
+        let strategies: Vec<Box<dyn ComparisonStrategy>> = vec![
+            Box::new(FastHashStrategy::new(false).with_distrust_sizes(true)),
+            Box::new(SampledHashStrategy::new(false, false).with_distrust_sizes(true)),
+        ];
+        for strategy in strategies {
+            let mut warnings = Vec::new();
+            assert!(!strategy.matches_reporting(&entry("a", 7), &entry("b", 8), &mut warnings));
+            assert_eq!(warnings.len(), 2);
+        }
     }
 }
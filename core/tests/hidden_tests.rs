@@ -0,0 +1,143 @@
+use dir_compare_core::comparison::{
+    ComparisonOptions, ComparisonResult, EntryFilter, FilenameOnlyStrategy, FlatComparisonOptions,
+    TraversalOptions, compare_directories_flat, compare_directories_with_options,
+    traverse_directory_with_options,
+};
+use std::fs;
+use std::path::{Path, PathBuf};
+use tempfile::TempDir;
+
+/// A and B share `notes.txt` and `foo.d/visible.txt`. A alone holds a
+/// `.git/` repository, a `.cache/` directory and a `.bashrc`; B alone holds
+/// `.config/app.toml`, a `.swp` file and `extra.log`.
+fn setup() -> TempDir {
+    let temp = TempDir::new().unwrap();
+    let files: &[(&str, &[&str])] = &[
+        (
+            "a",
+            &[
+                ".git/HEAD",
+                ".git/objects/ab/cdef",
+                ".cache/thumb.png",
+                ".bashrc",
+            ],
+        ),
+        (
+            "b",
+            &[".config/app.toml", "foo.d/.visible.txt.swp", "extra.log"],
+        ),
+    ];
+    for (side, only) in files {
+        let dir = temp.path().join(side);
+        for file in ["notes.txt", "foo.d/visible.txt"].iter().chain(only.iter()) {
+            let path = dir.join(file);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, file).unwrap();
+        }
+    }
+    temp
+}
+
+fn compare(root: &Path, ignore_hidden: bool, filter: Option<EntryFilter>) -> ComparisonResult {
+    let options = ComparisonOptions {
+        ignore_hidden,
+        filter,
+        ..Default::default()
+    };
+    compare_directories_with_options(
+        &root.join("a"),
+        &root.join("b"),
+        &FilenameOnlyStrategy::new(false),
+        None,
+        &options,
+    )
+    .unwrap()
+}
+
+fn sorted_paths<'a>(entries: impl Iterator<Item = &'a PathBuf>) -> Vec<&'a str> {
+    let mut paths: Vec<&str> = entries.map(|path| path.to_str().unwrap()).collect();
+    paths.sort();
+    paths
+}
+
+#[test]
+fn test_hidden_entries_are_listed_by_default() {
+    let temp = setup();
+    let result = compare(temp.path(), false, None);
+    let a_only = sorted_paths(result.a_only.iter().map(|entry| &entry.path));
+    assert!(a_only.contains(&".git/objects/ab/cdef"), "{:?}", a_only);
+    assert!(a_only.contains(&".bashrc"));
+    let b_only = sorted_paths(result.b_only.iter().map(|entry| &entry.path));
+    assert!(b_only.contains(&"foo.d/.visible.txt.swp"), "{:?}", b_only);
+}
+
+#[test]
+fn test_ignore_hidden_prunes_dot_directories_and_files() {
+    let temp = setup();
+    let result = compare(temp.path(), true, None);
+    assert!(result.a_only.is_empty(), "{:?}", result.a_only);
+    assert_eq!(
+        sorted_paths(result.b_only.iter().map(|entry| &entry.path)),
+        ["extra.log"]
+    );
+    // A directory with a dot inside its name is not hidden
+    assert_eq!(
+        sorted_paths(result.both.iter().map(|(a, _)| &a.path)),
+        ["foo.d", "foo.d/visible.txt", "notes.txt"]
+    );
+}
+
+#[test]
+fn test_ignore_hidden_composes_with_filters_and_ignore_files() {
+    let temp = setup();
+    let filter = EntryFilter::new(&[], &["*.log"]).unwrap();
+    let result = compare(temp.path(), true, Some(filter));
+    assert!(result.a_only.is_empty() && result.b_only.is_empty());
+
+    let ignore_file = temp.path().join("ignore");
+    fs::write(&ignore_file, "notes.txt\n").unwrap();
+    let options = TraversalOptions {
+        ignore_hidden: true,
+        ..Default::default()
+    };
+    let dir = temp.path().join("b");
+    let listed: Vec<PathBuf> = traverse_directory_with_options(&dir, Some(&ignore_file), &options)
+        .unwrap()
+        .into_iter()
+        .map(|entry| entry.path.strip_prefix(&dir).unwrap().to_path_buf())
+        .collect();
+    assert_eq!(
+        sorted_paths(listed.iter()),
+        ["extra.log", "foo.d", "foo.d/visible.txt"]
+    );
+}
+
+#[test]
+fn test_flat_comparison_ignores_hidden_files() {
+    let temp = setup();
+    let compare = |ignore_hidden| {
+        let options = FlatComparisonOptions {
+            ignore_hidden,
+            ..Default::default()
+        };
+        compare_directories_flat(
+            &temp.path().join("a"),
+            &temp.path().join("b"),
+            &options,
+            None,
+        )
+        .unwrap()
+    };
+
+    let result = compare(true);
+    assert_eq!(result.stats.files_a, 2);
+    assert_eq!(result.stats.files_b, 3);
+    let files_a: Vec<&PathBuf> = result
+        .groups
+        .iter()
+        .flat_map(|group| group.files_in_a.iter())
+        .collect();
+    assert!(files_a.iter().all(|path| !path.starts_with(".git")));
+
+    assert_eq!(compare(false).stats.files_a, 6);
+}
@@ -0,0 +1,224 @@
+use dir_compare_core::comparison::{
+    ComparisonResult, Entry, EntryKind, FlatComparisonResult, FlatContentGroup,
+};
+use dir_compare_core::history::{
+    HISTORY_COLUMNS, HISTORY_VERSION, HistoryDelta, HistoryRecord, append_history, format_history,
+    read_history,
+};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+fn file(path: &str, size: u64) -> Entry {
+    Entry {
+        path: PathBuf::from(path),
+        abs_path: PathBuf::from(path),
+        kind: EntryKind::File,
+        size: Some(size),
+        nested_repository: false,
+        mtime: None,
+    }
+}
+
+fn record(timestamp: u64, a_only: usize, both: usize, bytes_a: u64) -> HistoryRecord {
+    HistoryRecord {
+        version: HISTORY_VERSION,
+        timestamp,
+        mode: "hierarchy".to_string(),
+        dir_a: "/data/a".to_string(),
+        dir_b: "/data/b".to_string(),
+        identical_pct: 90.0,
+        a_only,
+        b_only: 1,
+        both,
+        bytes_a,
+        bytes_b: 100,
+        duration_ms: 50,
+        modified: 0,
+    }
+}
+
+#[test]
+fn test_record_from_comparison() {
+    let result = ComparisonResult {
+        a_only: vec![file("only_a", 10)],
+        b_only: vec![],
+        both: vec![(file("same", 100), file("same", 100))],
+        modified: vec![],
+        exact_counts: None,
+        warnings: Vec::new(),
+        stats: Default::default(),
+        context: None,
+        identical_subtrees: Vec::new(),
+        mismatch_reasons: Vec::new(),
+        renamed: Vec::new(),
+    };
+    let started = UNIX_EPOCH + Duration::from_secs(1_760_000_000);
+    let record = HistoryRecord::from_comparison(
+        &result,
+        Path::new("a"),
+        Path::new("b"),
+        started,
+        Duration::from_millis(1234),
+    );
+
+    assert_eq!(record.timestamp, 1_760_000_000);
+    assert_eq!(record.mode, "hierarchy");
+    assert_eq!((record.a_only, record.b_only, record.both), (1, 0, 1));
+    assert_eq!((record.bytes_a, record.bytes_b), (110, 100));
+    assert_eq!(record.duration_ms, 1234);
+    // Two matched entries out of three
+    assert!((record.identical_pct - 200.0 / 3.0).abs() < 1e-9);
+}
+
+#[test]
+fn test_record_from_flat() {
+    let result = FlatComparisonResult {
+        groups: vec![
+            FlatContentGroup {
+                hash: "h1".to_string(),
+                size: 10,
+                file_count: 3,
+                files_in_a: vec![PathBuf::from("x"), PathBuf::from("y")],
+                files_in_b: vec![PathBuf::from("z")],
+                hard_links: Vec::new(),
+            },
+            FlatContentGroup {
+                hash: "h2".to_string(),
+                size: 5,
+                file_count: 1,
+                files_in_a: vec![PathBuf::from("lonely")],
+                files_in_b: vec![],
+                hard_links: Vec::new(),
+            },
+        ],
+        total_files_a: 3,
+        total_files_b: 1,
+        unique_hashes: 2,
+        duplicate_count: 1,
+        warnings: Vec::new(),
+        stats: Default::default(),
+        chunk_dedupe: None,
+    };
+    let record = HistoryRecord::from_flat(
+        &result,
+        Path::new("a"),
+        Path::new("b"),
+        SystemTime::now(),
+        Duration::ZERO,
+    );
+
+    assert_eq!(record.mode, "flat");
+    assert_eq!((record.a_only, record.b_only, record.both), (1, 0, 2));
+    assert_eq!((record.bytes_a, record.bytes_b), (25, 10));
+    assert!((record.identical_pct - 75.0).abs() < 1e-9);
+}
+
+#[test]
+fn test_append_two_runs_writes_header_once() {
+    let temp = tempfile::tempdir().unwrap();
+    let path = temp.path().join("history.csv");
+
+    append_history(&path, &record(100, 2, 10, 1000)).unwrap();
+    append_history(&path, &record(200, 5, 8, 1500)).unwrap();
+
+    let content = fs::read_to_string(&path).unwrap();
+    let lines: Vec<&str> = content.lines().collect();
+    assert_eq!(lines.len(), 3);
+    assert_eq!(lines[0], HISTORY_COLUMNS.join(","));
+    assert_eq!(content.matches("version,").count(), 1);
+
+    let records = read_history(&path).unwrap();
+    assert_eq!(
+        records,
+        vec![record(100, 2, 10, 1000), record(200, 5, 8, 1500)]
+    );
+}
+
+#[test]
+fn test_row_round_trips_quoted_paths() {
+    let mut original = record(1, 0, 0, 0);
+    original.dir_a = "/mnt/a, \"quoted\"".to_string();
+    original.identical_pct = 12.5;
+
+    let row = original.to_csv_row();
+    assert!(row.starts_with("2,1,hierarchy,\"/mnt/a, \"\"quoted\"\"\","));
+    assert_eq!(HistoryRecord::parse_row(&row).unwrap(), original);
+}
+
+#[test]
+fn test_parse_row_ignores_columns_from_newer_versions() {
+    let row = format!("{},extra_future_column", record(7, 1, 2, 3).to_csv_row());
+    assert_eq!(HistoryRecord::parse_row(&row).unwrap(), record(7, 1, 2, 3));
+}
+
+#[test]
+fn test_parse_row_rejects_malformed_rows() {
+    assert!(HistoryRecord::parse_row("1,2,3").is_err());
+    let row = record(1, 0, 0, 0).to_csv_row().replace(",50", ",fast");
+    assert!(
+        HistoryRecord::parse_row(&row)
+            .unwrap_err()
+            .contains("duration_ms")
+    );
+}
+
+#[test]
+fn test_read_history_skips_malformed_rows() {
+    let temp = tempfile::tempdir().unwrap();
+    let path = temp.path().join("history.csv");
+    append_history(&path, &record(1, 0, 0, 0)).unwrap();
+    let mut content = fs::read_to_string(&path).unwrap();
+    content.push_str("garbage\n");
+    fs::write(&path, content).unwrap();
+    append_history(&path, &record(2, 0, 0, 0)).unwrap();
+
+    let records = read_history(&path).unwrap();
+    assert_eq!(records.len(), 2);
+}
+
+#[test]
+fn test_delta_from_previous_run() {
+    let mut previous = record(100, 2, 10, 1000);
+    let mut current = record(200, 5, 8, 400);
+    previous.identical_pct = 95.0;
+    current.identical_pct = 92.5;
+    current.duration_ms = 30;
+
+    assert_eq!(
+        current.delta_from(&previous),
+        HistoryDelta {
+            identical_pct: -2.5,
+            a_only: 3,
+            b_only: 0,
+            both: -2,
+            bytes_a: -600,
+            bytes_b: 0,
+            duration_ms: -20,
+            modified: 0,
+        }
+    );
+}
+
+#[test]
+fn test_format_history_shows_last_runs_with_deltas() {
+    let records = vec![
+        record(100, 2, 10, 1000),
+        record(200, 5, 8, 1500),
+        record(300, 4, 9, 1500),
+    ];
+
+    let output = format_history(&records, 2);
+    assert!(output.starts_with("Timestamp"));
+    assert!(!output.contains("\n100 "));
+    assert!(output.contains("\n200 "));
+    assert!(output.contains("\n300 "));
+    // Delta of the first shown run is relative to the hidden run before it
+    assert!(output.contains("+3"));
+    assert!(output.contains("+500 B"));
+    assert!(output.contains("-1"));
+    assert_eq!(output.matches("change").count(), 2);
+
+    let single = format_history(&records[..1], 10);
+    assert!(!single.contains("change"));
+}
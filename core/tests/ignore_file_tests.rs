@@ -0,0 +1,215 @@
+use dir_compare_core::comparison::{
+    FilenameOnlyStrategy, FlatComparisonOptions, compare_directories, compare_directories_flat,
+};
+use dir_compare_core::ignore_file::IgnoreFile;
+use std::fs;
+use std::path::{Path, PathBuf};
+use tempfile::TempDir;
+
+const FILE: bool = false;
+const DIR: bool = true;
+
+/// An entry checked against the rules: path, whether it is a directory, and
+/// whether it is ignored
+type Check = (&'static str, bool, bool);
+
+/// Rules, then the entries checked against them
+const CASES: &[(&str, &[Check])] = &[
+    // Comments and blank lines
+    (
+        "# *.log\n\n   \n*.tmp\n",
+        &[("debug.log", FILE, false), ("scratch.tmp", FILE, true)],
+    ),
+    // Escaped `#` and `!`
+    (
+        "\\#notes\n\\!important\n",
+        &[
+            ("#notes", FILE, true),
+            ("!important", FILE, true),
+            ("notes", FILE, false),
+        ],
+    ),
+    // Unanchored patterns match at any depth
+    (
+        "*.log\n",
+        &[
+            ("debug.log", FILE, true),
+            ("a/b/debug.log", FILE, true),
+            ("debug.txt", FILE, false),
+        ],
+    ),
+    // A leading `/` anchors at the root
+    (
+        "/target\n",
+        &[
+            ("target", DIR, true),
+            ("target/debug/app", FILE, true),
+            ("crates/target", DIR, false),
+            ("crates/target/app", FILE, false),
+        ],
+    ),
+    // So does a `/` inside the pattern
+    (
+        "docs/*.pdf\n",
+        &[
+            ("docs/manual.pdf", FILE, true),
+            ("src/docs/manual.pdf", FILE, false),
+        ],
+    ),
+    // A trailing `/` matches directories and everything below them
+    (
+        "cache/\n",
+        &[
+            ("cache", DIR, true),
+            ("cache", FILE, false),
+            ("cache/index.bin", FILE, true),
+            ("app/cache/deep/file", FILE, true),
+        ],
+    ),
+    // Later rules override earlier ones
+    (
+        "*.log\n!keep.log\n",
+        &[("debug.log", FILE, true), ("keep.log", FILE, false)],
+    ),
+    ("!keep.log\n*.log\n", &[("keep.log", FILE, true)]),
+    // A negation re-includes a file inside an ignored directory
+    (
+        "build/\n!build/manifest.json\n",
+        &[
+            ("build", DIR, true),
+            ("build/app.o", FILE, true),
+            ("build/manifest.json", FILE, false),
+            ("build/sub/manifest.json", FILE, true),
+        ],
+    ),
+    // and a directory ignored after the negation stays ignored
+    (
+        "!build/manifest.json\nbuild/\n",
+        &[("build/manifest.json", FILE, true)],
+    ),
+    // A negated directory re-includes its contents
+    (
+        "/*\n!/src/\n",
+        &[
+            ("README.md", FILE, true),
+            ("src", DIR, false),
+            ("src/main.rs", FILE, false),
+        ],
+    ),
+];
+
+#[test]
+fn test_rules() {
+    for (rules, entries) in CASES {
+        let ignore = IgnoreFile::parse(rules).unwrap();
+        for &(path, is_dir, ignored) in *entries {
+            assert_eq!(
+                ignore.is_ignored(Path::new(path), is_dir),
+                ignored,
+                "{:?} with rules {:?}",
+                path,
+                rules
+            );
+        }
+    }
+}
+
+#[test]
+fn test_pruning() {
+    let ignore = IgnoreFile::parse("target/\nbuild/\n!build/manifest.json\nlogs/\n").unwrap();
+    // No negation follows, but one follows `build/`
+    assert!(ignore.prunes(Path::new("logs")));
+    assert!(!ignore.prunes(Path::new("build")));
+    assert!(!ignore.prunes(Path::new("target")));
+    assert!(!ignore.prunes(Path::new("src")));
+
+    let ignore = IgnoreFile::parse("target/\n").unwrap();
+    assert!(ignore.prunes(Path::new("target")));
+    assert!(ignore.prunes(Path::new("crates/app/target")));
+}
+
+#[test]
+fn test_invalid_pattern_names_its_line() {
+    let error = IgnoreFile::parse("*.tmp\nsrc/{a,b\n").unwrap_err();
+    assert_eq!(error.kind(), std::io::ErrorKind::InvalidInput);
+    assert!(error.to_string().starts_with("line 2: "), "{}", error);
+}
+
+/// A and B hold the same `build/` with a manifest, `src/main.rs` and a
+/// `notes.txt`; the ignore file lives outside both
+fn setup(rules: &str) -> (TempDir, PathBuf) {
+    let temp = TempDir::new().unwrap();
+    for side in ["a", "b"] {
+        let dir = temp.path().join(side);
+        fs::create_dir_all(dir.join("build/objects")).unwrap();
+        fs::create_dir_all(dir.join("src")).unwrap();
+        fs::write(dir.join("build/manifest.json"), side).unwrap();
+        fs::write(dir.join("build/objects/app.o"), side).unwrap();
+        fs::write(dir.join("src/main.rs"), side).unwrap();
+        fs::write(dir.join("notes.txt"), side).unwrap();
+    }
+    let ignore_file = temp.path().join(".dcignore");
+    fs::write(&ignore_file, rules).unwrap();
+    (temp, ignore_file)
+}
+
+#[test]
+fn test_comparison_reincludes_file_in_ignored_directory() {
+    let (temp, ignore_file) = setup("# build output\nbuild/\n!build/manifest.json\n/notes.txt\n");
+    let result = compare_directories(
+        &temp.path().join("a"),
+        &temp.path().join("b"),
+        &FilenameOnlyStrategy::new(false),
+        Some(&ignore_file),
+    )
+    .unwrap();
+    let mut both: Vec<_> = result.both.iter().map(|(a, _)| a.path.clone()).collect();
+    both.sort();
+    // The ignored directory stays listed as it leads to the manifest
+    let expected: Vec<PathBuf> = ["build", "build/manifest.json", "src", "src/main.rs"]
+        .iter()
+        .map(PathBuf::from)
+        .collect();
+    assert_eq!(both, expected);
+    assert!(result.a_only.is_empty() && result.b_only.is_empty());
+
+    let flat = compare_directories_flat(
+        &temp.path().join("a"),
+        &temp.path().join("b"),
+        &FlatComparisonOptions::default(),
+        Some(&ignore_file),
+    )
+    .unwrap();
+    let mut files_a: Vec<_> = flat
+        .groups
+        .iter()
+        .flat_map(|group| group.files_in_a.clone())
+        .collect();
+    files_a.sort();
+    assert_eq!(
+        files_a,
+        [
+            PathBuf::from("build/manifest.json"),
+            PathBuf::from("src/main.rs")
+        ]
+    );
+}
+
+#[test]
+fn test_ignored_directory_without_kept_entries_is_dropped() {
+    let (temp, ignore_file) = setup("build/\n!*.rs\n");
+    let result = compare_directories(
+        &temp.path().join("a"),
+        &temp.path().join("b"),
+        &FilenameOnlyStrategy::new(false),
+        Some(&ignore_file),
+    )
+    .unwrap();
+    let mut both: Vec<_> = result.both.iter().map(|(a, _)| a.path.clone()).collect();
+    both.sort();
+    let expected: Vec<PathBuf> = ["notes.txt", "src", "src/main.rs"]
+        .iter()
+        .map(PathBuf::from)
+        .collect();
+    assert_eq!(both, expected);
+}
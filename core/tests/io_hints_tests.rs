@@ -0,0 +1,294 @@
+use dir_compare_core::comparison::{
+    Blake3Strategy, ComparisonOptions, ComparisonResult, ComparisonStrategy, Entry, EntryKind,
+    FastHashStrategy, FlatComparisonOptions, SampledHashStrategy, compare_directories_flat,
+    compare_directories_with_options, extent_order,
+};
+use dir_compare_core::filesystem::{FileSystem, RealFileSystem};
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+/// How a file was opened
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Open {
+    Plain,
+    Sequential,
+}
+
+/// A filesystem recording every file opened, and reporting the physical
+/// offsets of `offsets`
+#[derive(Default)]
+struct Recorder {
+    opened: Mutex<Vec<(Open, PathBuf)>>,
+    offsets: HashMap<PathBuf, u64>,
+}
+
+impl Recorder {
+    fn opened(&self) -> Vec<(Open, PathBuf)> {
+        self.opened.lock().unwrap().clone()
+    }
+
+    fn record(&self, open: Open, path: &Path) {
+        self.opened.lock().unwrap().push((open, path.to_path_buf()));
+    }
+}
+
+impl FileSystem for Recorder {
+    fn canonicalize(&self, path: &Path) -> io::Result<PathBuf> {
+        RealFileSystem.canonicalize(path)
+    }
+
+    fn metadata(&self, path: &Path) -> io::Result<fs::Metadata> {
+        RealFileSystem.metadata(path)
+    }
+
+    fn open(&self, path: &Path) -> io::Result<Box<dyn io::Read>> {
+        self.record(Open::Plain, path);
+        RealFileSystem.open(path)
+    }
+
+    fn open_sequential(&self, path: &Path) -> io::Result<Box<dyn io::Read>> {
+        self.record(Open::Sequential, path);
+        RealFileSystem.open_sequential(path)
+    }
+
+    fn physical_offset(&self, path: &Path) -> Option<u64> {
+        self.offsets.get(path).copied()
+    }
+}
+
+/// Writes `content` as `name` in both dir_a and dir_b
+fn setup_pair(name: &str, content: &[u8]) -> tempfile::TempDir {
+    let temp = tempfile::tempdir().unwrap();
+    for side in ["dir_a", "dir_b"] {
+        fs::create_dir_all(temp.path().join(side)).unwrap();
+        fs::write(temp.path().join(side).join(name), content).unwrap();
+    }
+    temp
+}
+
+fn entry(root: &Path, side: &str, name: &str) -> Entry {
+    Entry {
+        path: PathBuf::from(name),
+        abs_path: root.join(side).join(name),
+        kind: EntryKind::File,
+        size: Some(fs::metadata(root.join(side).join(name)).unwrap().len()),
+        nested_repository: false,
+        mtime: None,
+    }
+}
+
+/// The paths of each category, in the order of the result
+fn categories(result: &ComparisonResult) -> [Vec<PathBuf>; 4] {
+    let paths = |entries: &[Entry]| entries.iter().map(|e| e.path.clone()).collect();
+    [
+        paths(&result.a_only),
+        paths(&result.b_only),
+        result
+            .modified
+            .iter()
+            .map(|(a, _)| a.path.clone())
+            .collect(),
+        result.both.iter().map(|(a, _)| a.path.clone()).collect(),
+    ]
+}
+
+#[test]
+fn test_fast_hash_requests_sequential_reads_unless_disabled() {
+    let temp = setup_pair("data.bin", b"the same content on both sides");
+    let a = entry(temp.path(), "dir_a", "data.bin");
+    let b = entry(temp.path(), "dir_b", "data.bin");
+
+    let recorder = Arc::new(Recorder::default());
+    let strategy = FastHashStrategy::new(false).with_file_system(recorder.clone());
+    assert!(strategy.matches(&a, &b));
+    assert_eq!(
+        recorder.opened(),
+        [
+            (Open::Sequential, a.abs_path.clone()),
+            (Open::Sequential, b.abs_path.clone())
+        ]
+    );
+
+    let recorder = Arc::new(Recorder::default());
+    let strategy = FastHashStrategy::new(false)
+        .with_io_hints(false)
+        .with_file_system(recorder.clone());
+    assert!(strategy.matches(&a, &b));
+    assert_eq!(
+        recorder.opened(),
+        [(Open::Plain, a.abs_path), (Open::Plain, b.abs_path)]
+    );
+}
+
+#[test]
+fn test_blake3_reads_through_the_injected_file_system() {
+    let temp = setup_pair("data.bin", b"the same content on both sides");
+    let a = entry(temp.path(), "dir_a", "data.bin");
+    let b = entry(temp.path(), "dir_b", "data.bin");
+
+    // Also with the rayon feature, which memory-maps local files instead
+    let recorder = Arc::new(Recorder::default());
+    let strategy = Blake3Strategy::new(false).with_file_system(recorder.clone());
+    assert!(strategy.matches(&a, &b));
+    assert_eq!(
+        recorder.opened(),
+        [
+            (Open::Sequential, a.abs_path.clone()),
+            (Open::Sequential, b.abs_path.clone())
+        ]
+    );
+}
+
+#[test]
+fn test_sampled_hash_hints_only_whole_file_reads() {
+    let temp = setup_pair("large.bin", &[7u8; 10_000]);
+    let a = entry(temp.path(), "dir_a", "large.bin");
+    let b = entry(temp.path(), "dir_b", "large.bin");
+
+    // Samples are read at a few offsets, so they get no hint
+    let recorder = Arc::new(Recorder::default());
+    let strategy = SampledHashStrategy::new(false, false).with_file_system(recorder.clone());
+    assert!(strategy.matches(&a, &b));
+    assert!(recorder.opened().is_empty());
+
+    // Verifying the match reads both files whole
+    let recorder = Arc::new(Recorder::default());
+    let strategy = SampledHashStrategy::new(false, true).with_file_system(recorder.clone());
+    assert!(strategy.matches(&a, &b));
+    assert_eq!(
+        recorder.opened(),
+        [
+            (Open::Sequential, a.abs_path.clone()),
+            (Open::Sequential, b.abs_path.clone())
+        ]
+    );
+
+    let recorder = Arc::new(Recorder::default());
+    let strategy = SampledHashStrategy::new(false, false)
+        .with_distrust_sizes(true)
+        .with_io_hints(false)
+        .with_file_system(recorder.clone());
+    assert!(strategy.matches(&a, &b));
+    assert_eq!(
+        recorder.opened(),
+        [(Open::Plain, a.abs_path), (Open::Plain, b.abs_path)]
+    );
+}
+
+#[test]
+fn test_extent_order_sorts_known_offsets_first_and_is_stable() {
+    let paths = ["c", "a", "b", "d", "e"].map(PathBuf::from);
+    let recorder = Recorder {
+        offsets: HashMap::from([
+            (PathBuf::from("c"), 300),
+            (PathBuf::from("b"), 100),
+            (PathBuf::from("e"), 100),
+        ]),
+        ..Default::default()
+    };
+
+    let order = extent_order(&recorder, paths.iter().map(PathBuf::as_path));
+    assert_eq!(order, [2, 4, 0, 1, 3]);
+
+    // Without offsets, the order is unchanged
+    let order = extent_order(&RealFileSystem, paths.iter().map(PathBuf::as_path));
+    assert_eq!(order, [0, 1, 2, 3, 4]);
+}
+
+#[test]
+fn test_order_by_extent_reorders_hashing_but_not_the_result() {
+    let temp = tempfile::tempdir().unwrap();
+    let (dir_a, dir_b) = (temp.path().join("dir_a"), temp.path().join("dir_b"));
+    fs::create_dir_all(&dir_a).unwrap();
+    fs::create_dir_all(&dir_b).unwrap();
+    let names = ["a.txt", "b.txt", "c.txt", "d.txt"];
+    for (index, name) in names.iter().enumerate() {
+        fs::write(dir_a.join(name), name).unwrap();
+        // Changed files keep their size, so they are hashed
+        let content = if index % 2 == 0 {
+            name.to_uppercase()
+        } else {
+            name.to_string()
+        };
+        fs::write(dir_b.join(name), content).unwrap();
+    }
+    fs::write(dir_a.join("only_a.txt"), "a").unwrap();
+    fs::write(dir_b.join("only_b.txt"), "b").unwrap();
+
+    // The files of A lie on disk in reverse name order
+    let canonical_a = dir_a.canonicalize().unwrap();
+    let offsets = names
+        .iter()
+        .enumerate()
+        .map(|(index, name)| (canonical_a.join(name), 1000 - index as u64))
+        .collect();
+    let compare = |order_by_extent: bool, threads: usize| {
+        let recorder = Arc::new(Recorder {
+            offsets: HashMap::clone(&offsets),
+            ..Default::default()
+        });
+        let strategy = FastHashStrategy::new(false).with_file_system(recorder.clone());
+        let options = ComparisonOptions {
+            threads: Some(threads),
+            order_by_extent,
+            ..Default::default()
+        };
+        let result =
+            compare_directories_with_options(&dir_a, &dir_b, &strategy, None, &options).unwrap();
+        (categories(&result), recorder.opened())
+    };
+
+    let (unordered, opened) = compare(false, 1);
+    let hashed_first: Vec<_> = opened.iter().step_by(2).map(|(_, path)| path).collect();
+    assert_eq!(hashed_first[0].file_name().unwrap(), "a.txt");
+
+    let (ordered, opened) = compare(true, 1);
+    let hashed_first: Vec<_> = opened
+        .iter()
+        .step_by(2)
+        .map(|(_, path)| path.file_name().unwrap().to_str().unwrap())
+        .collect();
+    assert_eq!(hashed_first, ["d.txt", "c.txt", "b.txt", "a.txt"]);
+    assert_eq!(ordered, unordered);
+    assert_eq!(ordered[3], [PathBuf::from("b.txt"), PathBuf::from("d.txt")]);
+
+    let (threaded, _) = compare(true, 4);
+    assert_eq!(threaded, unordered);
+}
+
+#[test]
+fn test_flat_order_by_extent_keeps_the_result() {
+    let temp = setup_pair("same.txt", b"same");
+    fs::write(temp.path().join("dir_a").join("moved.txt"), "moved").unwrap();
+    fs::write(temp.path().join("dir_b").join("renamed.txt"), "moved").unwrap();
+    let compare = |options: &FlatComparisonOptions| {
+        compare_directories_flat(
+            &temp.path().join("dir_a"),
+            &temp.path().join("dir_b"),
+            options,
+            None,
+        )
+        .unwrap()
+        .groups
+        .into_iter()
+        .map(|group| (group.hash, group.files_in_a, group.files_in_b))
+        .collect::<Vec<_>>()
+    };
+
+    let default = compare(&FlatComparisonOptions::default());
+    let ordered = compare(&FlatComparisonOptions {
+        use_full_hash: true,
+        io_hints: false,
+        order_by_extent: true,
+        ..Default::default()
+    });
+    let full_hash = compare(&FlatComparisonOptions {
+        use_full_hash: true,
+        ..Default::default()
+    });
+    assert_eq!(default.len(), 2);
+    assert_eq!(ordered, full_hash);
+}
@@ -0,0 +1,168 @@
+use dir_compare_core::comparison::{ComparisonResult, Entry, EntryKind, ResultRow};
+use dir_compare_core::output::{CsvFormatter, Formatter, Section};
+use std::path::{Path, PathBuf};
+
+fn entry(path: &str, kind: EntryKind, size: Option<u64>) -> Entry {
+    Entry {
+        path: PathBuf::from(path),
+        abs_path: PathBuf::from("/root").join(path),
+        kind,
+        size,
+        nested_repository: false,
+        mtime: None,
+    }
+}
+
+fn file(path: &str, size: u64) -> Entry {
+    entry(path, EntryKind::File, Some(size))
+}
+
+fn dir(path: &str) -> Entry {
+    entry(path, EntryKind::Directory, None)
+}
+
+/// A result with every category, whose paths interleave across categories
+fn sample_result() -> ComparisonResult {
+    ComparisonResult {
+        a_only: vec![
+            dir("docs"),
+            file("docs/old, notes.txt", 7),
+            file("m.txt", 3),
+        ],
+        b_only: vec![
+            file("a.txt", 1),
+            entry(
+                "z-link",
+                EntryKind::Symlink {
+                    target: PathBuf::from("a.txt"),
+                },
+                None,
+            ),
+        ],
+        both: vec![
+            (file("b.txt", 2), file("b.txt", 2)),
+            (file("n.txt", 4), file("n.txt", 4)),
+        ],
+        modified: vec![(file("c.txt", 5), file("c.txt", 50))],
+        exact_counts: None,
+        warnings: Vec::new(),
+        stats: Default::default(),
+        context: None,
+        identical_subtrees: Vec::new(),
+        mismatch_reasons: Vec::new(),
+        renamed: Vec::new(),
+    }
+}
+
+#[test]
+fn test_iter_all_interleaves_categories_by_path() {
+    let result = sample_result();
+    let order: Vec<(Section, &Path)> = result
+        .iter_all()
+        .map(|entry| (entry.category, entry.path()))
+        .collect();
+    assert_eq!(
+        order,
+        [
+            (Section::BOnly, Path::new("a.txt")),
+            (Section::Both, Path::new("b.txt")),
+            (Section::Modified, Path::new("c.txt")),
+            (Section::AOnly, Path::new("docs")),
+            (Section::AOnly, Path::new("docs/old, notes.txt")),
+            // Between the two matched files
+            (Section::AOnly, Path::new("m.txt")),
+            (Section::Both, Path::new("n.txt")),
+            (Section::BOnly, Path::new("z-link")),
+        ]
+    );
+    assert_eq!(result.iter_all().count(), 8);
+}
+
+#[test]
+fn test_iter_all_exposes_both_sides_of_pairs() {
+    let result = sample_result();
+    for entry in result.iter_all() {
+        match entry.category {
+            Section::AOnly => assert!(entry.a.is_some() && entry.b.is_none()),
+            Section::BOnly => assert!(entry.a.is_none() && entry.b.is_some()),
+            Section::Both | Section::Modified => {
+                assert!(entry.a.is_some() && entry.b.is_some())
+            }
+        }
+    }
+
+    let modified = result
+        .iter_all()
+        .find(|entry| entry.category == Section::Modified)
+        .unwrap();
+    assert_eq!(modified.a.unwrap().size, Some(5));
+    assert_eq!(modified.b.unwrap().size, Some(50));
+    assert_eq!(modified.entry().size, Some(5));
+    let b_only = result.iter_all().next().unwrap();
+    assert_eq!(b_only.entry().size, Some(1));
+}
+
+#[test]
+fn test_iter_all_breaks_path_ties_by_category() {
+    // The same path on one side only and in a pair, as case-insensitive
+    // comparisons can report
+    let result = ComparisonResult {
+        a_only: vec![file("x.txt", 1)],
+        b_only: vec![file("x.txt", 2)],
+        both: vec![(file("x.txt", 3), file("X.txt", 3))],
+        modified: Vec::new(),
+        renamed: Vec::new(),
+        exact_counts: None,
+        warnings: Vec::new(),
+        stats: Default::default(),
+        context: None,
+        identical_subtrees: Vec::new(),
+        mismatch_reasons: Vec::new(),
+    };
+    let categories: Vec<Section> = result.iter_all().map(|entry| entry.category).collect();
+    assert_eq!(categories, [Section::AOnly, Section::BOnly, Section::Both]);
+}
+
+#[test]
+fn test_to_rows() {
+    let rows = sample_result().to_rows();
+    assert_eq!(rows.len(), 8);
+    assert_eq!(
+        rows[2],
+        ResultRow {
+            category: Section::Modified,
+            path: PathBuf::from("c.txt"),
+            kind: EntryKind::File,
+            size: Some(5),
+            size_b: Some(50),
+        }
+    );
+    assert_eq!(
+        rows[3],
+        ResultRow {
+            category: Section::AOnly,
+            path: PathBuf::from("docs"),
+            kind: EntryKind::Directory,
+            size: None,
+            size_b: None,
+        }
+    );
+    assert_eq!(rows[0].size, Some(1));
+    assert_eq!(rows[0].size_b, None);
+}
+
+#[test]
+fn test_csv_output_is_unchanged() {
+    assert_eq!(
+        CsvFormatter.format(&sample_result()),
+        "category,path,kind,size,reason\n\
+         a_only,docs,directory,,\n\
+         a_only,\"docs/old, notes.txt\",file,7,\n\
+         a_only,m.txt,file,3,\n\
+         b_only,a.txt,file,1,\n\
+         b_only,z-link,symlink,,\n\
+         both,b.txt,file,2,\n\
+         both,n.txt,file,4,\n\
+         modified,c.txt,file,5,\n"
+    );
+}
@@ -1,7 +1,11 @@
 //! Tests for the logger module.
 
-use dir_compare_core::logger::{self, LoggerConfig, LogLevel, OutputDestination};
+use dir_compare_core::logger::{
+    self, LogBuffer, LogFormat, LogLevel, LogSink, Logger, LoggerConfig, OutputDestination,
+    TimestampFormat, format_json_record, format_timestamp,
+};
 use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, SystemTime};
 
 // Use a mutex to ensure tests don't interfere with each other
 static TEST_LOCK: OnceLock<Mutex<()>> = OnceLock::new();
@@ -52,6 +56,9 @@ fn test_logger_config_default() {
     assert_eq!(config.level, LogLevel::Info);
     assert_eq!(config.destination, OutputDestination::Stderr);
     assert!(config.format.is_none());
+    assert_eq!(config.log_format, LogFormat::Text);
+    assert_eq!(config.timestamp_format, TimestampFormat::Rfc3339);
+    assert_eq!(config.dedup_window, None);
 }
 
 #[test]
@@ -62,6 +69,10 @@ fn test_logger_set_level() {
         level: LogLevel::Info,
         destination: OutputDestination::Stdout,
         format: None,
+        log_format: LogFormat::Text,
+        timestamp_format: TimestampFormat::Rfc3339,
+        dedup_window: None,
+        color: false,
     });
 
     logger::set_level(LogLevel::Debug);
@@ -74,38 +85,64 @@ fn test_logger_set_level() {
     logger::set_level(LogLevel::Info);
 }
 
-#[test]
-fn test_logger_set_destination() {
-    let _lock = get_test_lock().lock().unwrap();
+/// Initializes the global logger to capture its lines with `level` and
+/// `format`, dropping lines captured before
+fn init_capture(level: LogLevel, format: Option<&str>) {
+    logger::init(LoggerConfig {
+        level,
+        destination: OutputDestination::Capture,
+        format: format.map(str::to_string),
+        log_format: LogFormat::Text,
+        timestamp_format: TimestampFormat::Rfc3339,
+        dedup_window: None,
+        color: false,
+    });
+    logger::take_captured();
+}
 
+/// Restores the configuration the other tests expect
+fn reset_logger() {
     logger::init(LoggerConfig {
         level: LogLevel::Info,
         destination: OutputDestination::Stdout,
-        format: None,
+        ..Default::default()
     });
+}
 
-    logger::set_destination(OutputDestination::Stderr);
-    // We can't easily verify the destination, but we can ensure it doesn't panic
+#[test]
+fn test_logger_set_destination() {
+    let _lock = get_test_lock().lock().unwrap();
+    init_capture(LogLevel::Info, Some("{message}"));
 
-    // Reset to default
-    logger::set_destination(OutputDestination::Stdout);
+    logger::info("captured");
+    logger::set_destination(OutputDestination::Stderr);
+    logger::info("written to stderr");
+    logger::set_destination(OutputDestination::Capture);
+    logger::info("captured again");
+    assert_eq!(logger::take_captured(), ["captured", "captured again"]);
+    // Taking drains the buffer
+    assert!(logger::take_captured().is_empty());
+
+    reset_logger();
 }
 
 #[test]
 fn test_logger_set_format() {
     let _lock = get_test_lock().lock().unwrap();
-
-    logger::init(LoggerConfig {
-        level: LogLevel::Info,
-        destination: OutputDestination::Stdout,
-        format: None,
-    });
+    init_capture(LogLevel::Info, None);
 
     logger::set_format(Some("{level}: {message}".to_string()));
-    // We can't easily verify the format, but we can ensure it doesn't panic
-
-    // Reset to default
+    logger::warn("formatted");
     logger::set_format(None);
+    logger::info("default");
+
+    let lines = logger::take_captured();
+    assert_eq!(lines.len(), 2);
+    assert_eq!(lines[0], "WARN: formatted");
+    assert!(lines[1].starts_with("[INFO] "), "{}", lines[1]);
+    assert!(lines[1].ends_with(" default"), "{}", lines[1]);
+
+    reset_logger();
 }
 
 #[test]
@@ -116,6 +153,10 @@ fn test_logger_methods_dont_panic() {
         level: LogLevel::Debug,
         destination: OutputDestination::Stdout,
         format: None,
+        log_format: LogFormat::Text,
+        timestamp_format: TimestampFormat::Rfc3339,
+        dedup_window: None,
+        color: false,
     });
 
     // These should not panic
@@ -128,40 +169,94 @@ fn test_logger_methods_dont_panic() {
 #[test]
 fn test_logger_level_filtering() {
     let _lock = get_test_lock().lock().unwrap();
+    init_capture(LogLevel::Warn, Some("{level} {message}"));
 
-    // Set level to Warn - only warn and error should produce output
-    logger::init(LoggerConfig {
-        level: LogLevel::Warn,
-        destination: OutputDestination::Stdout,
-        format: None,
-    });
-
-    // These should be filtered (no output)
-    logger::debug("This should be filtered");
-    logger::info("This should be filtered");
+    logger::debug("filtered debug");
+    logger::info("filtered info");
+    logger::warn("shown warn");
+    logger::error("shown error");
+    assert_eq!(
+        logger::take_captured(),
+        ["WARN shown warn", "ERROR shown error"]
+    );
 
-    // These should produce output
-    logger::warn("This should appear");
-    logger::error("This should appear");
+    logger::set_level(LogLevel::Debug);
+    logger::debug("shown debug");
+    assert_eq!(logger::take_captured(), ["DEBUG shown debug"]);
 
-    // Reset to default
-    logger::set_level(LogLevel::Info);
+    reset_logger();
 }
 
 #[test]
 fn test_logger_custom_format() {
     let _lock = get_test_lock().lock().unwrap();
+    init_capture(
+        LogLevel::Info,
+        Some("CUSTOM: {level} - {message} @ {timestamp} / {timestamp_unix}"),
+    );
+
+    logger::info("Test with custom format");
+    let lines = logger::take_captured();
+    assert_eq!(lines.len(), 1);
+    let (head, times) = lines[0].split_once(" @ ").unwrap();
+    assert_eq!(head, "CUSTOM: INFO - Test with custom format");
+    let (rfc3339, unix) = times.split_once(" / ").unwrap();
+    let rfc3339 = parse_rfc3339(rfc3339);
+    let unix = SystemTime::UNIX_EPOCH + Duration::from_secs_f64(unix.parse::<f64>().unwrap());
+    let apart = unix
+        .duration_since(rfc3339)
+        .unwrap_or_else(|e| e.duration());
+    assert!(apart < Duration::from_secs(1), "{:?}", lines[0]);
+
+    // Placeholders in the message are not substituted again
+    logger::set_format(Some("{message}".to_string()));
+    logger::info("literal {level}");
+    assert_eq!(logger::take_captured(), ["literal {level}"]);
+
+    reset_logger();
+}
 
+#[test]
+fn test_colored_levels_only_in_default_format() {
+    let _lock = get_test_lock().lock().unwrap();
     logger::init(LoggerConfig {
-        level: LogLevel::Info,
-        destination: OutputDestination::Stdout,
-        format: Some("CUSTOM: {level} - {message}".to_string()),
+        destination: OutputDestination::Capture,
+        timestamp_format: TimestampFormat::None,
+        color: true,
+        ..Default::default()
     });
+    logger::take_captured();
 
-    logger::info("Test with custom format");
+    logger::info("done");
+    logger::error("failed");
+    logger::set_format(Some("{level}: {message}".to_string()));
+    logger::warn("plain");
+    assert_eq!(
+        logger::take_captured(),
+        [
+            "[\x1b[32mINFO\x1b[0m] done",
+            "[\x1b[1;31mERROR\x1b[0m] failed",
+            "WARN: plain",
+        ]
+    );
+
+    reset_logger();
+}
 
-    // Reset to default
-    logger::set_format(None);
+#[test]
+fn test_json_lines_are_captured() {
+    let _lock = get_test_lock().lock().unwrap();
+    init_capture(LogLevel::Info, None);
+    logger::set_log_format(LogFormat::Json);
+    logger::set_timestamp_format(TimestampFormat::None);
+
+    logger::warn_with("unreadable", &[("path", "/data/a.txt")]);
+    assert_eq!(
+        logger::take_captured(),
+        [r#"{"level":"WARN","msg":"unreadable","path":"/data/a.txt"}"#]
+    );
+
+    reset_logger();
 }
 
 #[test]
@@ -171,6 +266,10 @@ fn test_output_destination_from_str() {
         level: LogLevel::Info,
         destination: OutputDestination::Stdout,
         format: None,
+        log_format: LogFormat::Text,
+        timestamp_format: TimestampFormat::Rfc3339,
+        dedup_window: None,
+        color: false,
     };
     assert_eq!(config_stdout.destination, OutputDestination::Stdout);
 
@@ -178,6 +277,10 @@ fn test_output_destination_from_str() {
         level: LogLevel::Info,
         destination: OutputDestination::Stderr,
         format: None,
+        log_format: LogFormat::Text,
+        timestamp_format: TimestampFormat::Rfc3339,
+        dedup_window: None,
+        color: false,
     };
     assert_eq!(config_stderr.destination, OutputDestination::Stderr);
 }
@@ -186,3 +289,376 @@ fn test_output_destination_from_str() {
 fn test_output_destination_default() {
     assert_eq!(OutputDestination::default(), OutputDestination::Stderr);
 }
+
+/// Messages of the records in `buffer` at `min_level` or above
+fn messages(buffer: &LogBuffer, min_level: LogLevel) -> Vec<String> {
+    buffer
+        .entries(min_level)
+        .into_iter()
+        .map(|entry| entry.message)
+        .collect()
+}
+
+/// A sink that only accepts warnings and errors
+struct WarnSink(LogBuffer);
+
+impl LogSink for WarnSink {
+    fn level(&self) -> LogLevel {
+        LogLevel::Warn
+    }
+
+    fn log(&self, level: LogLevel, timestamp: SystemTime, target: &str, message: &str) {
+        self.0.log(level, timestamp, target, message);
+    }
+}
+
+#[test]
+fn test_sinks_fan_out() {
+    let _lock = get_test_lock().lock().unwrap();
+    logger::set_level(LogLevel::Error);
+
+    let first = LogBuffer::new(100);
+    let second = LogBuffer::new(100);
+    let warn_only = LogBuffer::new(100);
+    let first_id = logger::add_sink(Box::new(first.clone()));
+    let second_id = logger::add_sink(Box::new(second.clone()));
+    let warn_id = logger::add_sink(Box::new(WarnSink(warn_only.clone())));
+
+    // Sinks see records below the destination's level
+    logger::debug("fan-out debug");
+    logger::warn("fan-out warn");
+    Logger::global().log_with_target(LogLevel::Info, "gui", "fan-out target");
+
+    assert!(logger::remove_sink(first_id));
+    assert!(!logger::remove_sink(first_id));
+    logger::info("fan-out after removal");
+    assert!(logger::remove_sink(second_id));
+    assert!(logger::remove_sink(warn_id));
+    logger::set_level(LogLevel::Info);
+
+    let ours = |buffer: &LogBuffer| -> Vec<String> {
+        messages(buffer, LogLevel::Debug)
+            .into_iter()
+            .filter(|message| message.starts_with("fan-out"))
+            .collect()
+    };
+    assert_eq!(
+        ours(&first),
+        ["fan-out debug", "fan-out warn", "fan-out target"]
+    );
+    assert_eq!(
+        ours(&second),
+        [
+            "fan-out debug",
+            "fan-out warn",
+            "fan-out target",
+            "fan-out after removal"
+        ]
+    );
+    assert_eq!(ours(&warn_only), ["fan-out warn"]);
+
+    let target = first
+        .entries(LogLevel::Debug)
+        .into_iter()
+        .find(|entry| entry.message == "fan-out target")
+        .unwrap();
+    assert_eq!(target.target, "gui");
+    assert!(target.to_string().starts_with("[INFO] "));
+    assert!(target.to_string().ends_with(" gui: fan-out target"));
+}
+
+#[test]
+fn test_sinks_receive_records_from_threads() {
+    let _lock = get_test_lock().lock().unwrap();
+    logger::set_level(LogLevel::Error);
+
+    let buffer = LogBuffer::new(1000);
+    let id = logger::add_sink(Box::new(buffer.clone()));
+    let workers: Vec<_> = (0..4)
+        .map(|worker| {
+            std::thread::spawn(move || {
+                for i in 0..50 {
+                    logger::debug(&format!("thread-record {} {}", worker, i));
+                }
+            })
+        })
+        .collect();
+    for worker in workers {
+        worker.join().unwrap();
+    }
+    logger::remove_sink(id);
+    logger::set_level(LogLevel::Info);
+
+    let count = messages(&buffer, LogLevel::Debug)
+        .iter()
+        .filter(|message| message.starts_with("thread-record"))
+        .count();
+    assert_eq!(count, 200);
+}
+
+#[test]
+fn test_log_buffer_evicts_oldest() {
+    let buffer = LogBuffer::new(3);
+    let levels = [
+        LogLevel::Debug,
+        LogLevel::Info,
+        LogLevel::Warn,
+        LogLevel::Error,
+        LogLevel::Info,
+    ];
+    for (i, level) in levels.into_iter().enumerate() {
+        buffer.log(
+            level,
+            SystemTime::UNIX_EPOCH,
+            "test",
+            &format!("record {}", i),
+        );
+    }
+
+    assert_eq!(buffer.len(), 3);
+    assert_eq!(buffer.capacity(), 3);
+    assert_eq!(
+        messages(&buffer, LogLevel::Debug),
+        ["record 2", "record 3", "record 4"]
+    );
+    assert_eq!(messages(&buffer, LogLevel::Warn), ["record 2", "record 3"]);
+    assert_eq!(messages(&buffer, LogLevel::Error), ["record 3"]);
+
+    buffer.clear();
+    assert!(buffer.is_empty());
+
+    let disabled = LogBuffer::new(0);
+    disabled.log(LogLevel::Error, SystemTime::UNIX_EPOCH, "test", "dropped");
+    assert!(disabled.is_empty());
+}
+
+#[test]
+fn test_keyed_warnings_are_capped_and_summarized() {
+    let _lock = get_test_lock().lock().unwrap();
+    logger::set_level(LogLevel::Error);
+    logger::set_dedup_window(Some(10));
+
+    let buffer = LogBuffer::new(2000);
+    let id = logger::add_sink(Box::new(buffer.clone()));
+    for i in 0..1000 {
+        logger::warn_keyed("dedup_test", &format!("dedup-test unreadable {}", i));
+    }
+    // Unkeyed warnings are never suppressed
+    for _ in 0..15 {
+        logger::warn("dedup-test unkeyed");
+    }
+    logger::flush_summaries();
+    // Nothing is left to summarize
+    logger::flush_summaries();
+    logger::remove_sink(id);
+    logger::set_dedup_window(None);
+    logger::set_level(LogLevel::Info);
+
+    let ours: Vec<String> = messages(&buffer, LogLevel::Warn)
+        .into_iter()
+        .filter(|message| message.contains("dedup-test") || message.contains("dedup_test"))
+        .collect();
+    let keyed: Vec<&String> = ours
+        .iter()
+        .filter(|message| message.starts_with("dedup-test unreadable"))
+        .collect();
+    assert_eq!(keyed.len(), 10);
+    assert_eq!(keyed[9], "dedup-test unreadable 9");
+    assert_eq!(
+        ours.iter()
+            .filter(|message| *message == "dedup-test unkeyed")
+            .count(),
+        15
+    );
+    let summaries: Vec<&String> = ours
+        .iter()
+        .filter(|message| message.starts_with("previous message repeated"))
+        .collect();
+    assert_eq!(
+        summaries,
+        ["previous message repeated 990 more times (key=dedup_test)"]
+    );
+    assert_eq!(ours.len(), 10 + 15 + 1);
+}
+
+#[test]
+fn test_keyed_warnings_without_window_are_all_logged() {
+    let _lock = get_test_lock().lock().unwrap();
+    logger::set_level(LogLevel::Error);
+    logger::set_dedup_window(None);
+
+    let buffer = LogBuffer::new(100);
+    let id = logger::add_sink(Box::new(buffer.clone()));
+    for _ in 0..30 {
+        logger::warn_keyed("no_window_test", "no-window-test warning");
+    }
+    logger::flush_summaries();
+    logger::remove_sink(id);
+    logger::set_level(LogLevel::Info);
+
+    let messages = messages(&buffer, LogLevel::Warn);
+    assert_eq!(
+        messages
+            .iter()
+            .filter(|message| *message == "no-window-test warning")
+            .count(),
+        30
+    );
+    assert!(
+        !messages
+            .iter()
+            .any(|message| message.contains("key=no_window_test"))
+    );
+}
+
+/// Days since the Unix epoch of a civil date, for checking timestamps
+/// independently of the logger's conversion
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let days_before_month = [0, 31, 59, 90, 120, 151, 181, 212, 243, 273, 304, 334];
+    let leap = |y: i64| (y % 4 == 0 && y % 100 != 0) || y % 400 == 0;
+    let mut days: i64 = (1970..year).map(|y| if leap(y) { 366 } else { 365 }).sum();
+    days += days_before_month[month as usize - 1] + day - 1;
+    if month > 2 && leap(year) {
+        days += 1;
+    }
+    days
+}
+
+/// Parses `YYYY-MM-DDTHH:MM:SS.mmmZ` back into a time
+fn parse_rfc3339(text: &str) -> SystemTime {
+    assert_eq!(text.len(), 24, "{}", text);
+    assert!(text.ends_with('Z'), "{}", text);
+    let field = |range: std::ops::Range<usize>| text[range].parse::<i64>().unwrap();
+    let days = days_from_civil(field(0..4), field(5..7), field(8..10));
+    let secs = days * 86_400 + field(11..13) * 3_600 + field(14..16) * 60 + field(17..19);
+    assert_eq!(&text[4..5], "-");
+    assert_eq!(&text[10..11], "T");
+    assert_eq!(&text[19..20], ".");
+    SystemTime::UNIX_EPOCH
+        + Duration::from_secs(secs as u64)
+        + Duration::from_millis(field(20..23) as u64)
+}
+
+#[test]
+fn test_rfc3339_timestamps_round_trip() {
+    let at = |secs: u64, millis: u64| {
+        SystemTime::UNIX_EPOCH + Duration::from_secs(secs) + Duration::from_millis(millis)
+    };
+    for (time, expected) in [
+        (at(0, 0), "1970-01-01T00:00:00.000Z"),
+        (at(1_708_099_200, 123), "2024-02-16T16:00:00.123Z"),
+        // Leap days, and the days around them
+        (at(1_709_164_799, 999), "2024-02-28T23:59:59.999Z"),
+        (at(1_709_164_800, 0), "2024-02-29T00:00:00.000Z"),
+        (at(1_709_251_200, 1), "2024-03-01T00:00:00.001Z"),
+        (at(951_782_400, 500), "2000-02-29T00:00:00.500Z"),
+        (at(4_107_542_400, 0), "2100-03-01T00:00:00.000Z"),
+        (at(4_107_542_399, 0), "2100-02-28T23:59:59.000Z"),
+        (at(94_694_399, 0), "1972-12-31T23:59:59.000Z"),
+    ] {
+        let text = format_timestamp(TimestampFormat::Rfc3339, time);
+        assert_eq!(text, expected);
+        assert_eq!(parse_rfc3339(&text), time);
+    }
+
+    // Every day of a leap and a common year parses back
+    for day in days_from_civil(2023, 1, 1)..days_from_civil(2025, 1, 1) {
+        let time = at(day as u64 * 86_400 + 45_296, 7);
+        let text = format_timestamp(TimestampFormat::Rfc3339, time);
+        assert!(text.ends_with("T12:34:56.007Z"), "{}", text);
+        assert_eq!(parse_rfc3339(&text), time);
+    }
+}
+
+#[test]
+fn test_unix_and_no_timestamps() {
+    let time = SystemTime::UNIX_EPOCH + Duration::from_micros(1_708_099_200_123_456);
+    assert_eq!(
+        format_timestamp(TimestampFormat::Unix, time),
+        "1708099200.123456"
+    );
+    assert_eq!(format_timestamp(TimestampFormat::None, time), "");
+}
+
+#[test]
+fn test_timestamp_format_from_str() {
+    assert_eq!("rfc3339".parse(), Ok(TimestampFormat::Rfc3339));
+    assert_eq!(" UNIX ".parse(), Ok(TimestampFormat::Unix));
+    assert_eq!("none".parse(), Ok(TimestampFormat::None));
+    assert_eq!(TimestampFormat::default(), TimestampFormat::Rfc3339);
+    assert!("iso".parse::<TimestampFormat>().is_err());
+}
+
+#[test]
+fn test_json_records_round_trip() {
+    let time = SystemTime::UNIX_EPOCH + Duration::from_millis(1_709_164_800_250);
+    let message = "said \"hi\" \\ then\nleft \u{1F600}\t\u{1}";
+    let path = "C:\\data\\\"写真\".jpg";
+    let line = format_json_record(
+        TimestampFormat::Rfc3339,
+        LogLevel::Warn,
+        time,
+        message,
+        &[("path", path), ("cause", "unreadable_file")],
+    );
+    assert!(!line.contains('\n'), "{}", line);
+
+    let record: serde_json::Value = serde_json::from_str(&line).unwrap();
+    assert_eq!(record["level"], "WARN");
+    assert_eq!(record["ts"], "2024-02-29T00:00:00.250Z");
+    assert_eq!(record["msg"], message);
+    assert_eq!(record["path"], path);
+    assert_eq!(record["cause"], "unreadable_file");
+    let keys: Vec<&String> = record.as_object().unwrap().keys().collect();
+    assert_eq!(keys.len(), 5);
+}
+
+#[test]
+fn test_json_record_timestamps() {
+    let time = SystemTime::UNIX_EPOCH + Duration::from_micros(1_708_099_200_123_456);
+    let unix: serde_json::Value = serde_json::from_str(&format_json_record(
+        TimestampFormat::Unix,
+        LogLevel::Info,
+        time,
+        "m",
+        &[],
+    ))
+    .unwrap();
+    assert_eq!(unix["ts"].as_f64(), Some(1_708_099_200.123_456));
+
+    let none: serde_json::Value = serde_json::from_str(&format_json_record(
+        TimestampFormat::None,
+        LogLevel::Error,
+        time,
+        "m",
+        &[],
+    ))
+    .unwrap();
+    assert_eq!(none, serde_json::json!({"level": "ERROR", "msg": "m"}));
+}
+
+#[test]
+fn test_log_format_from_str() {
+    assert_eq!("json".parse(), Ok(LogFormat::Json));
+    assert_eq!(" Text ".parse(), Ok(LogFormat::Text));
+    assert_eq!(LogFormat::default(), LogFormat::Text);
+    assert!("yaml".parse::<LogFormat>().is_err());
+}
+
+#[test]
+fn test_fields_do_not_reach_sinks() {
+    let _lock = get_test_lock().lock().unwrap();
+    let buffer = LogBuffer::new(10);
+    let id = logger::add_sink(Box::new(buffer.clone()));
+
+    logger::info_with("with fields", &[("path", "/data/a.txt")]);
+    logger::remove_sink(id);
+
+    let messages: Vec<String> = buffer
+        .entries(LogLevel::Debug)
+        .into_iter()
+        .map(|entry| entry.message)
+        .filter(|message| message.starts_with("with fields"))
+        .collect();
+    assert_eq!(messages, ["with fields"]);
+}
@@ -0,0 +1,152 @@
+use dir_compare_core::comparison::HashAlgorithm;
+use dir_compare_core::manifest::{verify_manifest, write_manifest};
+use std::fs;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use tempfile::TempDir;
+
+/// SHA-256 of `hello\n`, as `sha256sum` prints it
+const HELLO_SHA256: &str = "5891b5b522d5df086d0ff0b110fbd9d21bb4fc7163af34d08286a2e846f6be03";
+
+fn write(root: &Path, path: &str, contents: &str) {
+    let path = root.join(path);
+    fs::create_dir_all(path.parent().unwrap()).unwrap();
+    fs::write(path, contents).unwrap();
+}
+
+/// A tree with unicode names, an empty file and an empty directory
+fn setup() -> TempDir {
+    let temp = TempDir::new().unwrap();
+    write(temp.path(), "hello.txt", "hello\n");
+    write(temp.path(), "empty.txt", "");
+    write(temp.path(), "Überschrift/日本語.md", "unicode");
+    write(temp.path(), "Überschrift/café ☕.txt", "coffee");
+    fs::create_dir(temp.path().join("nothing")).unwrap();
+    temp
+}
+
+fn manifest(dir: &Path, algorithm: HashAlgorithm) -> String {
+    let mut written = Vec::new();
+    write_manifest(dir, algorithm, &mut written).unwrap();
+    String::from_utf8(written).unwrap()
+}
+
+fn paths(paths: &[&str]) -> Vec<PathBuf> {
+    paths.iter().map(PathBuf::from).collect()
+}
+
+#[test]
+fn test_round_trip_with_unicode_names_and_empty_file() {
+    let temp = setup();
+    for algorithm in [
+        HashAlgorithm::Sha256,
+        HashAlgorithm::Blake3,
+        HashAlgorithm::FxHash,
+    ] {
+        let manifest = manifest(temp.path(), algorithm);
+        assert_eq!(manifest.lines().count(), 4, "{}", manifest);
+        let result = verify_manifest(temp.path(), algorithm, manifest.as_bytes()).unwrap();
+        assert!(!result.has_differences(), "{}", result.to_text());
+        assert_eq!(
+            result.matched,
+            paths(&[
+                "empty.txt",
+                "hello.txt",
+                "Überschrift/café ☕.txt",
+                "Überschrift/日本語.md",
+            ])
+        );
+    }
+}
+
+#[test]
+fn test_lines_match_sha256sum() {
+    let temp = setup();
+    let manifest = manifest(temp.path(), HashAlgorithm::Sha256);
+    assert!(manifest.contains(&format!("{}  hello.txt\n", HELLO_SHA256)));
+    assert!(
+        manifest.contains(
+            "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855  empty.txt\n"
+        )
+    );
+    assert!(manifest.contains("  Überschrift/日本語.md\n"));
+}
+
+#[test]
+fn test_modified_missing_and_extra_files() {
+    let temp = setup();
+    let manifest = manifest(temp.path(), HashAlgorithm::Sha256);
+    write(temp.path(), "hello.txt", "goodbye\n");
+    fs::remove_file(temp.path().join("empty.txt")).unwrap();
+    write(temp.path(), "nothing/new.txt", "new");
+
+    let result = verify_manifest(temp.path(), HashAlgorithm::Sha256, manifest.as_bytes()).unwrap();
+    assert!(result.has_differences());
+    assert_eq!(result.modified, paths(&["hello.txt"]));
+    assert_eq!(result.missing, paths(&["empty.txt"]));
+    assert_eq!(result.extra, paths(&["nothing/new.txt"]));
+    assert_eq!(result.matched.len(), 2);
+    assert_eq!(
+        result.to_text(),
+        "MODIFIED hello.txt\nMISSING empty.txt\nEXTRA nothing/new.txt\n\
+         2 matched, 1 modified, 1 missing, 1 extra\n"
+    );
+    let json: serde_json::Value = serde_json::from_str(&result.to_json()).unwrap();
+    assert_eq!(json["missing"][0], "empty.txt");
+    assert_eq!(json["matched"].as_array().unwrap().len(), 2);
+}
+
+#[test]
+fn test_reads_sha256sum_variants() {
+    let temp = setup();
+    let manifest = format!(
+        "{hash}  ./hello.txt\n\n{hash} *hello.txt\n",
+        hash = HELLO_SHA256.to_uppercase()
+    );
+    let result = verify_manifest(temp.path(), HashAlgorithm::Sha256, manifest.as_bytes()).unwrap();
+    assert_eq!(result.matched, paths(&["hello.txt"]));
+}
+
+#[test]
+fn test_rejects_malformed_lines_and_outside_paths() {
+    let temp = setup();
+    for manifest in [
+        format!("{}  hello.txt\nnot a hash  x\n", HELLO_SHA256),
+        format!("{} hello.txt\n", HELLO_SHA256),
+        format!("{}  hello.txt\n", &HELLO_SHA256[..40]),
+        format!("{}  ../hello.txt\n", HELLO_SHA256),
+        format!("{}  /etc/passwd\n", HELLO_SHA256),
+        format!("\\{}  bad\\tescape\n", HELLO_SHA256),
+    ] {
+        let error =
+            verify_manifest(temp.path(), HashAlgorithm::Sha256, manifest.as_bytes()).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::InvalidData, "{}", manifest);
+        assert!(error.to_string().starts_with("manifest line "), "{}", error);
+    }
+}
+
+#[cfg(unix)]
+#[test]
+fn test_escapes_newlines_and_backslashes_like_coreutils() {
+    let temp = TempDir::new().unwrap();
+    write(temp.path(), "two\nlines.txt", "hello\n");
+    write(temp.path(), "back\\slash.txt", "hello\n");
+    let manifest = manifest(temp.path(), HashAlgorithm::Sha256);
+    assert_eq!(
+        manifest,
+        format!(
+            "\\{hash}  back\\\\slash.txt\n\\{hash}  two\\nlines.txt\n",
+            hash = HELLO_SHA256
+        )
+    );
+    let result = verify_manifest(temp.path(), HashAlgorithm::Sha256, manifest.as_bytes()).unwrap();
+    assert!(!result.has_differences(), "{}", result.to_text());
+    assert_eq!(result.matched.len(), 2);
+
+    let result = verify_manifest(temp.path(), HashAlgorithm::Sha256, &b""[..]).unwrap();
+    assert!(
+        result
+            .to_text()
+            .starts_with("\\EXTRA back\\\\slash.txt\n\\EXTRA two\\nlines.txt\n")
+    );
+}
@@ -0,0 +1,123 @@
+use dir_compare_core::comparison::{
+    ComparisonOptions, EntryKind, FastHashStrategy, FlatComparisonOptions, TraversalOptions,
+    compare_directories_flat, compare_directories_with_options, traverse_directory_with_options,
+};
+use std::fs;
+use std::path::{Path, PathBuf};
+use tempfile::TempDir;
+
+/// A and B share `top.txt` and `level1/mid.txt`; below that, A holds
+/// `level1/level2/deep.txt` and B a different `level1/level2/deep.txt` and
+/// `level1/level2/only_b.txt`
+fn setup() -> TempDir {
+    let temp = TempDir::new().unwrap();
+    for side in ["a", "b"] {
+        let dir = temp.path().join(side);
+        fs::create_dir_all(dir.join("level1/level2")).unwrap();
+        fs::write(dir.join("top.txt"), "top").unwrap();
+        fs::write(dir.join("level1/mid.txt"), "mid").unwrap();
+        fs::write(dir.join("level1/level2/deep.txt"), side).unwrap();
+    }
+    fs::write(temp.path().join("b/level1/level2/only_b.txt"), "b").unwrap();
+    temp
+}
+
+fn compare(root: &Path, max_depth: Option<usize>) -> dir_compare_core::ComparisonResult {
+    let options = ComparisonOptions {
+        max_depth,
+        ..Default::default()
+    };
+    compare_directories_with_options(
+        &root.join("a"),
+        &root.join("b"),
+        &FastHashStrategy::new(false),
+        None,
+        &options,
+    )
+    .unwrap()
+}
+
+fn both_paths(result: &dir_compare_core::ComparisonResult) -> Vec<PathBuf> {
+    let mut paths: Vec<_> = result.both.iter().map(|(a, _)| a.path.clone()).collect();
+    paths.sort();
+    paths
+}
+
+#[test]
+fn test_depth_one_lists_immediate_children() {
+    let temp = setup();
+    let options = TraversalOptions {
+        max_depth: Some(1),
+        ..Default::default()
+    };
+    let dir = temp.path().join("a");
+    let entries = traverse_directory_with_options(&dir, None, &options).unwrap();
+    let mut listed: Vec<_> = entries
+        .iter()
+        .map(|entry| {
+            (
+                entry.path.strip_prefix(&dir).unwrap().to_path_buf(),
+                entry.kind.clone(),
+            )
+        })
+        .collect();
+    listed.sort_by(|a, b| a.0.cmp(&b.0));
+    // The directory at the cutoff is listed without its contents
+    assert_eq!(
+        listed,
+        [
+            (PathBuf::from("level1"), EntryKind::Directory),
+            (PathBuf::from("top.txt"), EntryKind::File),
+        ]
+    );
+}
+
+#[test]
+fn test_files_below_cutoff_do_not_influence_the_result() {
+    let temp = setup();
+    let full = compare(temp.path(), None);
+    assert!(full.has_differences());
+
+    let shallow = compare(temp.path(), Some(2));
+    assert!(!shallow.has_differences(), "{:?}", shallow.modified);
+    assert_eq!(
+        both_paths(&shallow),
+        [
+            PathBuf::from("level1"),
+            PathBuf::from("level1/level2"),
+            PathBuf::from("level1/mid.txt"),
+            PathBuf::from("top.txt"),
+        ]
+    );
+    assert_eq!(shallow.stats.files_a, 2);
+    assert_eq!(shallow.stats.files_b, 2);
+
+    let top = compare(temp.path(), Some(1));
+    assert_eq!(
+        both_paths(&top),
+        [PathBuf::from("level1"), PathBuf::from("top.txt")]
+    );
+}
+
+#[test]
+fn test_flat_mode_respects_max_depth() {
+    let temp = setup();
+    let compare = |max_depth| {
+        let options = FlatComparisonOptions {
+            max_depth,
+            ..Default::default()
+        };
+        compare_directories_flat(
+            &temp.path().join("a"),
+            &temp.path().join("b"),
+            &options,
+            None,
+        )
+        .unwrap()
+    };
+
+    assert!(compare(None).has_differences());
+    let shallow = compare(Some(2));
+    assert!(!shallow.has_differences());
+    assert_eq!((shallow.total_files_a, shallow.total_files_b), (2, 2));
+}
@@ -0,0 +1,248 @@
+use dir_compare_core::comparison::{
+    Blake3Strategy, ComparisonResult, ComparisonStrategy, FastHashStrategy, FilenameSizeStrategy,
+    MetadataStrategy, SampledHashStrategy,
+};
+use dir_compare_core::external::{ExternalCommandStrategy, ExternalRule, ExternalRules};
+use dir_compare_core::output::{
+    Formatter, HtmlFormatter, JsonFormatter, Labels, MarkdownFormatter, TextFormatter,
+};
+use dir_compare_core::{Comparison, MismatchReason};
+use std::fs::{self, File};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+use tempfile::TempDir;
+
+fn write(root: &Path, path: &str, contents: &[u8]) {
+    let path = root.join(path);
+    fs::create_dir_all(path.parent().unwrap()).unwrap();
+    fs::write(path, contents).unwrap();
+}
+
+/// Two trees in which:
+/// - `size.bin` holds 1,024 bytes in A and 2,048 in B
+/// - `content.txt` has the same size but different content
+/// - `kind` is a file in A and a directory in B
+/// - `same.txt` is identical
+fn setup() -> TempDir {
+    let temp = TempDir::new().unwrap();
+    let (a, b) = (temp.path().join("a"), temp.path().join("b"));
+    write(&a, "size.bin", &[1; 1024]);
+    write(&b, "size.bin", &[1; 2048]);
+    write(&a, "content.txt", b"version 1");
+    write(&b, "content.txt", b"version 2");
+    write(&a, "kind", b"file");
+    fs::create_dir_all(b.join("kind")).unwrap();
+    write(&a, "same.txt", b"same");
+    write(&b, "same.txt", b"same");
+    temp
+}
+
+fn compare(temp: &TempDir, strategy: &dyn ComparisonStrategy) -> ComparisonResult {
+    Comparison::new(temp.path().join("a"), temp.path().join("b"))
+        .strategy(strategy)
+        .run()
+        .unwrap()
+}
+
+/// The reasons of `result` as (path, reason text) pairs
+fn reasons(result: &ComparisonResult) -> Vec<(String, String)> {
+    let labels = Labels::default();
+    result
+        .mismatch_reasons
+        .iter()
+        .map(|(path, reason)| (path.display().to_string(), labels.mismatch_reason(*reason)))
+        .collect()
+}
+
+fn reason(path: &str, text: &str) -> (String, String) {
+    (path.to_string(), text.to_string())
+}
+
+#[test]
+fn test_hash_strategies_report_size_content_and_kind() {
+    let temp = setup();
+    let strategies: [Box<dyn ComparisonStrategy>; 3] = [
+        Box::new(FastHashStrategy::new(false)),
+        Box::new(Blake3Strategy::new(false)),
+        Box::new(SampledHashStrategy::new(false, true)),
+    ];
+    for strategy in &strategies {
+        let result = compare(&temp, strategy.as_ref());
+
+        assert_eq!(
+            reasons(&result),
+            vec![
+                reason("content.txt", "content differs"),
+                reason("kind", "kind differs"),
+                reason("size.bin", "size 1,024 vs 2,048"),
+            ]
+        );
+    }
+}
+
+#[test]
+fn test_filename_size_strategy_reports_sizes() {
+    let temp = setup();
+    let result = compare(&temp, &FilenameSizeStrategy::new(false));
+
+    // Files of the same size match by name and size alone
+    assert_eq!(
+        reasons(&result),
+        vec![
+            reason("kind", "kind differs"),
+            reason("size.bin", "size 1,024 vs 2,048"),
+        ]
+    );
+}
+
+#[test]
+fn test_metadata_strategy_reports_modification_times() {
+    let temp = TempDir::new().unwrap();
+    let (a, b) = (temp.path().join("a"), temp.path().join("b"));
+    write(&a, "touched.txt", b"same");
+    write(&b, "touched.txt", b"same");
+    write(&a, "size.bin", &[1; 1024]);
+    write(&b, "size.bin", &[1; 2048]);
+    let earlier = SystemTime::now() - Duration::from_secs(3600);
+    File::options()
+        .write(true)
+        .open(a.join("touched.txt"))
+        .unwrap()
+        .set_modified(earlier)
+        .unwrap();
+
+    let strategy = MetadataStrategy::new(false, Duration::from_secs(2));
+    let result = compare(&temp, &strategy);
+
+    assert_eq!(
+        reasons(&result),
+        vec![
+            reason("size.bin", "size 1,024 vs 2,048"),
+            reason("touched.txt", "modification time differs"),
+        ]
+    );
+}
+
+#[cfg(unix)]
+#[test]
+fn test_symbolic_links_report_their_targets() {
+    let temp = TempDir::new().unwrap();
+    let (a, b) = (temp.path().join("a"), temp.path().join("b"));
+    fs::create_dir_all(&a).unwrap();
+    fs::create_dir_all(&b).unwrap();
+    std::os::unix::fs::symlink("one", a.join("link")).unwrap();
+    std::os::unix::fs::symlink("two", b.join("link")).unwrap();
+
+    let result = compare(&temp, &FastHashStrategy::new(false));
+
+    assert_eq!(
+        reasons(&result),
+        vec![reason("link", "link target differs")]
+    );
+}
+
+#[cfg(unix)]
+#[test]
+fn test_external_commands_report_content_or_unreadable() {
+    let temp = TempDir::new().unwrap();
+    let (a, b) = (temp.path().join("a"), temp.path().join("b"));
+    for (name, content_a, content_b) in [
+        ("differs.pdf", "%PDF 1", "%PDF 2"),
+        ("other.txt", "short", "longer text"),
+    ] {
+        write(&a, name, content_a.as_bytes());
+        write(&b, name, content_b.as_bytes());
+    }
+    write(&a, "broken.cad", b"x");
+    write(&b, "broken.cad", b"x");
+    let rules = ExternalRules {
+        rules: vec![
+            ExternalRule::new("*.pdf", &["cmp", "-s", "{a}", "{b}"]).unwrap(),
+            ExternalRule::new("*.cad", &["/nonexistent/compare", "{a}", "{b}"]).unwrap(),
+        ],
+        ..Default::default()
+    };
+    let strategy = ExternalCommandStrategy::new(Box::new(FastHashStrategy::new(false)), rules);
+    let result = compare(&temp, &strategy);
+
+    // Pairs without a rule keep the reason of the inner strategy
+    assert_eq!(
+        reasons(&result),
+        vec![
+            reason("broken.cad", "unreadable"),
+            reason("differs.pdf", "content differs"),
+            reason("other.txt", "size 5 vs 11"),
+        ]
+    );
+}
+
+#[test]
+fn test_swap_sides_swaps_sizes() {
+    let temp = setup();
+    let mut result = compare(&temp, &FastHashStrategy::new(false));
+    result.swap_sides();
+
+    assert_eq!(
+        result.mismatch_reason(Path::new("size.bin")),
+        Some(MismatchReason::SizeDiffers { a: 2048, b: 1024 })
+    );
+    assert_eq!(
+        result.mismatch_reason(&PathBuf::from("content.txt")),
+        Some(MismatchReason::ContentDiffers)
+    );
+    assert_eq!(result.mismatch_reason(Path::new("same.txt")), None);
+}
+
+#[test]
+fn test_formatters_render_reasons() {
+    let temp = setup();
+    let result = compare(&temp, &FastHashStrategy::new(false));
+
+    let text = TextFormatter.format(&result);
+    assert!(
+        text.contains("  size.bin != size.bin — size 1,024 vs 2,048\n"),
+        "{}",
+        text
+    );
+    assert!(
+        text.contains("  content.txt != content.txt — content differs\n"),
+        "{}",
+        text
+    );
+    assert!(text.contains("  same.txt == same.txt\n"), "{}", text);
+
+    let markdown = MarkdownFormatter.format(&result);
+    assert!(
+        markdown.contains("`size.bin` != `size.bin` — *size 1,024 vs 2,048*"),
+        "{}",
+        markdown
+    );
+
+    let html = HtmlFormatter.format(&result);
+    assert!(
+        html.contains("<em class=\"reason\">— size 1,024 vs 2,048</em>"),
+        "{}",
+        html
+    );
+
+    let json: serde_json::Value = serde_json::from_str(&JsonFormatter.format(&result)).unwrap();
+    let modified = json["modified"].as_array().unwrap();
+    let reasons: Vec<(&str, &str)> = modified
+        .iter()
+        .map(|pair| {
+            (
+                pair["a"]["path"].as_str().unwrap(),
+                pair["reason"].as_str().unwrap(),
+            )
+        })
+        .collect();
+    assert_eq!(
+        reasons,
+        [
+            ("content.txt", "content"),
+            ("kind", "kind"),
+            ("size.bin", "size")
+        ]
+    );
+    assert!(json["both"][0].get("reason").is_none());
+}
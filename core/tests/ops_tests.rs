@@ -0,0 +1,231 @@
+use dir_compare_core::filesystem::FileSystem;
+use dir_compare_core::ops::{ActionOutcome, ActionResults, CopyOptions, copy_file, hash_file};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// An in-memory filesystem that corrupts the first `corrupt_copies` copies
+/// and counts how often each path is opened
+#[derive(Default)]
+struct MockFileSystem {
+    files: RefCell<HashMap<PathBuf, Vec<u8>>>,
+    opens: RefCell<HashMap<PathBuf, usize>>,
+    copies: RefCell<usize>,
+    corrupt_copies: usize,
+}
+
+impl MockFileSystem {
+    fn with_file(path: &str, contents: &[u8], corrupt_copies: usize) -> Self {
+        let fs = Self {
+            corrupt_copies,
+            ..Default::default()
+        };
+        fs.files
+            .borrow_mut()
+            .insert(PathBuf::from(path), contents.to_vec());
+        fs
+    }
+
+    fn opens(&self, path: &str) -> usize {
+        self.opens
+            .borrow()
+            .get(Path::new(path))
+            .copied()
+            .unwrap_or(0)
+    }
+
+    fn exists(&self, path: &str) -> bool {
+        self.files.borrow().contains_key(Path::new(path))
+    }
+}
+
+impl FileSystem for MockFileSystem {
+    fn canonicalize(&self, path: &Path) -> io::Result<PathBuf> {
+        Ok(path.to_path_buf())
+    }
+
+    fn metadata(&self, _path: &Path) -> io::Result<std::fs::Metadata> {
+        Err(io::Error::other("metadata is not simulated"))
+    }
+
+    fn open(&self, path: &Path) -> io::Result<Box<dyn io::Read>> {
+        *self
+            .opens
+            .borrow_mut()
+            .entry(path.to_path_buf())
+            .or_default() += 1;
+        let contents = self
+            .files
+            .borrow()
+            .get(path)
+            .cloned()
+            .ok_or_else(|| io::Error::from(io::ErrorKind::NotFound))?;
+        Ok(Box::new(io::Cursor::new(contents)))
+    }
+
+    fn copy(&self, from: &Path, to: &Path) -> io::Result<u64> {
+        let mut contents = self
+            .files
+            .borrow()
+            .get(from)
+            .cloned()
+            .ok_or_else(|| io::Error::from(io::ErrorKind::NotFound))?;
+        let mut copies = self.copies.borrow_mut();
+        *copies += 1;
+        if *copies <= self.corrupt_copies {
+            contents[0] ^= 0xff;
+        }
+        let len = contents.len() as u64;
+        self.files.borrow_mut().insert(to.to_path_buf(), contents);
+        Ok(len)
+    }
+
+    fn remove_file(&self, path: &Path) -> io::Result<()> {
+        self.files
+            .borrow_mut()
+            .remove(path)
+            .map(|_| ())
+            .ok_or_else(|| io::Error::from(io::ErrorKind::NotFound))
+    }
+
+    fn create_dir_all(&self, _path: &Path) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[test]
+fn test_verified_copy_reads_destination_once() {
+    let fs = MockFileSystem::with_file("a/photo.jpg", b"jpeg bytes", 0);
+    let cached = hash_file(&fs, Path::new("a/photo.jpg")).unwrap();
+    let source_opens = fs.opens("a/photo.jpg");
+
+    let mut results = ActionResults::new();
+    copy_file(
+        &fs,
+        Path::new("a/photo.jpg"),
+        Path::new("b/photo.jpg"),
+        Some(&cached),
+        &CopyOptions::default(),
+        &mut results,
+    );
+
+    assert_eq!(results.results()[0].outcome, ActionOutcome::Copied);
+    assert_eq!(results.results()[0].verification_failures, 0);
+    assert_eq!(fs.opens("b/photo.jpg"), 1);
+    // The cached hash is reused rather than re-reading the source
+    assert_eq!(fs.opens("a/photo.jpg"), source_opens);
+    assert_eq!(results.summary(), "1 copied, 0 failed\n");
+}
+
+#[test]
+fn test_source_hashed_when_no_cached_hash() {
+    let fs = MockFileSystem::with_file("a/doc.txt", b"text", 0);
+
+    let mut results = ActionResults::new();
+    copy_file(
+        &fs,
+        Path::new("a/doc.txt"),
+        Path::new("b/doc.txt"),
+        None,
+        &CopyOptions::default(),
+        &mut results,
+    );
+
+    assert_eq!(results.results()[0].outcome, ActionOutcome::Copied);
+    assert_eq!(fs.opens("a/doc.txt"), 1);
+    assert_eq!(fs.opens("b/doc.txt"), 1);
+}
+
+#[test]
+fn test_corrupt_copy_is_retried() {
+    let fs = MockFileSystem::with_file("a/data.bin", b"payload", 1);
+
+    let mut results = ActionResults::new();
+    copy_file(
+        &fs,
+        Path::new("a/data.bin"),
+        Path::new("b/data.bin"),
+        None,
+        &CopyOptions::default(),
+        &mut results,
+    );
+
+    let result = &results.results()[0];
+    assert_eq!(result.outcome, ActionOutcome::Copied);
+    assert_eq!(result.verification_failures, 1);
+    assert_eq!(*fs.copies.borrow(), 2);
+    assert_eq!(fs.files.borrow()[Path::new("b/data.bin")], b"payload");
+    assert!(results.summary().contains("RETRIED b/data.bin"));
+}
+
+#[test]
+fn test_persistent_corruption_reported_as_failed() {
+    let fs = MockFileSystem::with_file("a/data.bin", b"payload", usize::MAX);
+
+    let mut results = ActionResults::new();
+    copy_file(
+        &fs,
+        Path::new("a/data.bin"),
+        Path::new("b/data.bin"),
+        None,
+        &CopyOptions::default(),
+        &mut results,
+    );
+
+    let result = &results.results()[0];
+    assert!(result.is_failed());
+    assert_eq!(result.verification_failures, 2);
+    assert_eq!(*fs.copies.borrow(), 2);
+    assert!(!fs.exists("b/data.bin"), "bad copy should be deleted");
+
+    let summary = results.summary();
+    assert!(summary.starts_with("0 copied, 1 failed\n"));
+    assert!(summary.contains("FAILED b/data.bin: verification failed after 2 attempts"));
+    assert_eq!(results.failed().count(), 1);
+}
+
+#[test]
+fn test_verification_can_be_disabled() {
+    let fs = MockFileSystem::with_file("a/data.bin", b"payload", usize::MAX);
+
+    let mut results = ActionResults::new();
+    copy_file(
+        &fs,
+        Path::new("a/data.bin"),
+        Path::new("b/data.bin"),
+        None,
+        &CopyOptions {
+            verify_after_copy: false,
+        },
+        &mut results,
+    );
+
+    assert_eq!(results.results()[0].outcome, ActionOutcome::Copied);
+    assert_eq!(fs.opens("a/data.bin"), 0);
+    assert_eq!(fs.opens("b/data.bin"), 0);
+}
+
+#[test]
+fn test_copy_file_on_real_filesystem() {
+    use dir_compare_core::filesystem::RealFileSystem;
+
+    let temp = tempfile::tempdir().unwrap();
+    let source = temp.path().join("a/file.txt");
+    let destination = temp.path().join("b/nested/file.txt");
+    std::fs::create_dir_all(source.parent().unwrap()).unwrap();
+    std::fs::write(&source, "content").unwrap();
+
+    let mut results = ActionResults::new();
+    copy_file(
+        &RealFileSystem,
+        &source,
+        &destination,
+        None,
+        &CopyOptions::default(),
+        &mut results,
+    );
+
+    assert_eq!(results.results()[0].outcome, ActionOutcome::Copied);
+    assert_eq!(std::fs::read_to_string(destination).unwrap(), "content");
+}
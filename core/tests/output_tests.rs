@@ -1,8 +1,12 @@
 #[cfg(test)]
 mod tests {
     use dir_compare_core::comparison::{ComparisonResult, Entry, EntryKind};
-    use dir_compare_core::output::{Formatter, HtmlFormatter, MarkdownFormatter, TextFormatter};
+    use dir_compare_core::output::{
+        ColorTextFormatter, Formatter, HtmlFormatter, MarkdownFormatter, ReportContext, Section,
+        TextFormatter, TextStyles,
+    };
     use std::path::PathBuf;
+    use std::time::{Duration, SystemTime};
 
     fn create_test_result() -> ComparisonResult {
         let mut a_only = Vec::new();
@@ -14,12 +18,16 @@ mod tests {
             abs_path: PathBuf::from("dir_a-only/"),
             kind: EntryKind::Directory,
             size: None,
+            nested_repository: false,
+            mtime: None,
         });
         a_only.push(Entry {
             path: PathBuf::from("file_a.txt"),
             abs_path: PathBuf::from("file_a.txt"),
             kind: EntryKind::File,
             size: Some(100),
+            nested_repository: false,
+            mtime: None,
         });
 
         b_only.push(Entry {
@@ -27,12 +35,16 @@ mod tests {
             abs_path: PathBuf::from("dir_b-only/"),
             kind: EntryKind::Directory,
             size: None,
+            nested_repository: false,
+            mtime: None,
         });
         b_only.push(Entry {
             path: PathBuf::from("file_b.txt"),
             abs_path: PathBuf::from("file_b.txt"),
             kind: EntryKind::File,
             size: Some(200),
+            nested_repository: false,
+            mtime: None,
         });
 
         both.push((
@@ -41,12 +53,16 @@ mod tests {
                 abs_path: PathBuf::from("common.txt"),
                 kind: EntryKind::File,
                 size: Some(150),
+                nested_repository: false,
+                mtime: None,
             },
             Entry {
                 path: PathBuf::from("common.txt"),
                 abs_path: PathBuf::from("common.txt"),
                 kind: EntryKind::File,
                 size: Some(150),
+                nested_repository: false,
+                mtime: None,
             },
         ));
 
@@ -54,6 +70,14 @@ mod tests {
             a_only,
             b_only,
             both,
+            modified: vec![],
+            exact_counts: None,
+            warnings: Vec::new(),
+            stats: Default::default(),
+            context: None,
+            identical_subtrees: Vec::new(),
+            mismatch_reasons: Vec::new(),
+            renamed: Vec::new(),
         }
     }
 
@@ -88,6 +112,14 @@ mod tests {
             a_only: Vec::new(),
             b_only: Vec::new(),
             both: Vec::new(),
+            modified: vec![],
+            exact_counts: None,
+            warnings: Vec::new(),
+            stats: Default::default(),
+            context: None,
+            identical_subtrees: Vec::new(),
+            mismatch_reasons: Vec::new(),
+            renamed: Vec::new(),
         };
         let formatter = TextFormatter;
         let output = formatter.format(&empty_result);
@@ -130,6 +162,74 @@ mod tests {
         assert!(output.contains("<h2>Both</h2>"));
     }
 
+    /// The test result with a context whose paths need escaping in HTML
+    fn result_with_context() -> ComparisonResult {
+        ComparisonResult {
+            context: Some(ReportContext {
+                dir_a: PathBuf::from("/srv/<a&b>"),
+                dir_b: PathBuf::from("/srv/\"quoted\" 'b'"),
+                strategy_name: "sampled".to_string(),
+                options: vec!["case-insensitive".to_string(), "verify".to_string()],
+                timestamp: SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_000),
+            }),
+            ..create_test_result()
+        }
+    }
+
+    #[test]
+    fn test_html_context_header_escapes_paths() {
+        let output = HtmlFormatter.format(&result_with_context());
+        assert!(output.contains("<dt>Directory A</dt><dd>/srv/&lt;a&amp;b&gt;</dd>"));
+        assert!(output.contains("<dt>Directory B</dt><dd>/srv/&quot;quoted&quot; 'b'</dd>"));
+        assert!(output.contains("<dt>Method</dt><dd>sampled (case-insensitive, verify)</dd>"));
+        assert!(output.contains("<dt>Generated</dt><dd>2023-11-14T22:13:20.000Z</dd>"));
+        assert!(!output.contains("<a&b>"));
+
+        // Without a context there is no header
+        assert!(
+            !HtmlFormatter
+                .format(&create_test_result())
+                .contains("class=\"context\"")
+        );
+    }
+
+    #[test]
+    fn test_html_filter_box_and_collapse_script() {
+        let output = HtmlFormatter.format(&create_test_result());
+        // Hidden until the script runs, so reports without scripts show no dead box
+        assert!(output.contains(
+            "<input type=\"search\" id=\"filter\" class=\"filter\" placeholder=\"Filter paths\" hidden>"
+        ));
+        let script = &output[output.find("<script>").unwrap()..];
+        assert!(script.contains("filter.hidden = false;"));
+        assert!(script.contains("classList.toggle(\"collapsed\")"));
+        assert!(output.contains(".section.collapsed > :not(h2) { display: none; }"));
+        assert!(output.ends_with("</script>\n</body>\n</html>\n"));
+    }
+
+    #[test]
+    fn test_text_and_markdown_context_header() {
+        let result = result_with_context();
+        let text = TextFormatter.format(&result);
+        assert!(text.starts_with(
+            "Directory A: /srv/<a&b>\n\
+             Directory B: /srv/\"quoted\" 'b'\n\
+             Method: sampled (case-insensitive, verify)\n\
+             Generated: 2023-11-14T22:13:20.000Z\n\n\
+             A-only (2 entries):\n"
+        ));
+
+        let md = MarkdownFormatter.format(&result);
+        assert!(md.starts_with(
+            "# Directory Comparison Report\n\n\
+             - **Directory A:** `/srv/<a&b>`\n\
+             - **Directory B:** `/srv/\"quoted\" 'b'`\n\
+             - **Method:** sampled (case-insensitive, verify)\n\
+             - **Generated:** 2023-11-14T22:13:20.000Z\n\n\
+             ## Summary\n"
+        ));
+    }
+
     #[test]
     fn test_markdown_formatter_structure() {
         let result = create_test_result();
@@ -170,12 +270,22 @@ mod tests {
             a_only: Vec::new(),
             b_only: Vec::new(),
             both: Vec::new(),
+            modified: vec![],
+            exact_counts: None,
+            warnings: Vec::new(),
+            stats: Default::default(),
+            context: None,
+            identical_subtrees: Vec::new(),
+            mismatch_reasons: Vec::new(),
+            renamed: Vec::new(),
         };
         result.a_only.push(Entry {
             path: PathBuf::from("file<with>&\"chars.txt"),
             abs_path: PathBuf::from("file<with>&\"chars.txt"),
             kind: EntryKind::File,
             size: Some(100),
+            nested_repository: false,
+            mtime: None,
         });
 
         let formatter = HtmlFormatter;
@@ -193,12 +303,22 @@ mod tests {
             a_only: Vec::new(),
             b_only: Vec::new(),
             both: Vec::new(),
+            modified: vec![],
+            exact_counts: None,
+            warnings: Vec::new(),
+            stats: Default::default(),
+            context: None,
+            identical_subtrees: Vec::new(),
+            mismatch_reasons: Vec::new(),
+            renamed: Vec::new(),
         };
         result.a_only.push(Entry {
             path: PathBuf::from("file_with_*.txt"),
             abs_path: PathBuf::from("file_with_*.txt"),
             kind: EntryKind::File,
             size: Some(100),
+            nested_repository: false,
+            mtime: None,
         });
 
         let formatter = MarkdownFormatter;
@@ -213,24 +333,38 @@ mod tests {
             a_only: Vec::new(),
             b_only: Vec::new(),
             both: Vec::new(),
+            modified: vec![],
+            exact_counts: None,
+            warnings: Vec::new(),
+            stats: Default::default(),
+            context: None,
+            identical_subtrees: Vec::new(),
+            mismatch_reasons: Vec::new(),
+            renamed: Vec::new(),
         };
         result.a_only.push(Entry {
             path: PathBuf::from("файл-тест.txt"),
             abs_path: PathBuf::from("файл-тест.txt"),
             kind: EntryKind::File,
             size: Some(100),
+            nested_repository: false,
+            mtime: None,
         });
         result.a_only.push(Entry {
             path: PathBuf::from("测试文件.txt"),
             abs_path: PathBuf::from("测试文件.txt"),
             kind: EntryKind::File,
             size: Some(200),
+            nested_repository: false,
+            mtime: None,
         });
         result.a_only.push(Entry {
             path: PathBuf::from("emoji🎉.txt"),
             abs_path: PathBuf::from("emoji🎉.txt"),
             kind: EntryKind::File,
             size: Some(50),
+            nested_repository: false,
+            mtime: None,
         });
 
         let text_formatter = TextFormatter;
@@ -247,4 +381,873 @@ mod tests {
         let md_output = md_formatter.format(&result);
         assert!(md_output.contains("файл-тест.txt"));
     }
+
+    #[test]
+    fn test_format_bytes_units() {
+        use dir_compare_core::output::format_bytes;
+        assert_eq!(format_bytes(0), "0 B");
+        assert_eq!(format_bytes(1023), "1023 B");
+        assert_eq!(format_bytes(1024), "1.0 KiB");
+        assert_eq!(format_bytes(5 * 1024 * 1024 + 200 * 1024), "5.2 MiB");
+        assert_eq!(format_bytes(2 * 1024 * 1024 * 1024), "2.0 GiB");
+    }
+
+    #[test]
+    fn test_format_diff_locations() {
+        use dir_compare_core::block_diff::{BlockDiff, DiffRegion};
+        use dir_compare_core::output::{JsonFormatter, SectionConfig, format_diff_locations};
+
+        let diff = BlockDiff {
+            regions: vec![
+                DiffRegion {
+                    start: 0,
+                    end: 1024,
+                },
+                DiffRegion {
+                    start: 4096,
+                    end: 8192,
+                },
+            ],
+            len_a: 4096,
+            len_b: 8192,
+            block_size: 1024,
+        };
+        let locations = vec![
+            (PathBuf::from("db.dump"), Ok(diff)),
+            (
+                PathBuf::from("gone.bin"),
+                Err(std::io::Error::new(std::io::ErrorKind::NotFound, "missing")),
+            ),
+        ];
+        let output = format_diff_locations(&locations);
+
+        assert!(output.contains("Difference locations (2 files):"));
+        assert!(output.contains("db.dump: 2 differing regions covering 5.0 KiB of 8.0 KiB"));
+        assert!(output.contains("0x0..0x400 (1.0 KiB)"));
+        assert!(output.contains("0x1000..0x2000 (4.0 KiB, end of file)"));
+        assert!(output.contains("gone.bin: could not analyze: missing"));
+
+        let json = JsonFormatter.format_with_diff_locations(
+            &create_test_result(),
+            &SectionConfig::default(),
+            &locations,
+        );
+        assert!(json.ends_with(
+            "  \"renamed\": [],\n  \"diff_locations\": [\n    {\"path\": \"db.dump\", \"len_a\": 4096, \"len_b\": 8192, \"regions\": [{\"start\": 0, \"end\": 1024}, {\"start\": 4096, \"end\": 8192}]},\n    {\"path\": \"gone.bin\", \"error\": \"missing\"}\n  ]\n}\n"
+        ));
+        assert!(
+            !JsonFormatter
+                .format(&create_test_result())
+                .contains("diff_locations")
+        );
+    }
+
+    #[test]
+    fn test_text_formatter_default_snapshot() {
+        let expected = "A-only (2 entries):\n\
+----------------------------------------\n\
+\x20 dir_a-only//\n\
+\x20 file_a.txt\n\
+\n\
+B-only (2 entries):\n\
+----------------------------------------\n\
+\x20 dir_b-only//\n\
+\x20 file_b.txt\n\
+\n\
+Modified (0 entries):\n\
+----------------------------------------\n\
+\n\
+Both (1 entries):\n\
+----------------------------------------\n\
+\x20 common.txt == common.txt\n\
+\n\
+A: 0 files, 0 directories, 0 B; B: 0 files, 0 directories, 0 B; 0 B hashed in 0.00s\n";
+        let result = create_test_result();
+        assert_eq!(TextFormatter.format(&result), expected);
+    }
+
+    #[test]
+    fn test_color_text_formatter_escape_sequences() {
+        assert_eq!(TextStyles::ANSI.a_only, "\x1b[31m");
+        assert_eq!(TextStyles::ANSI.b_only, "\x1b[32m");
+        assert_eq!(TextStyles::ANSI.modified, "\x1b[33m");
+        assert_eq!(TextStyles::ANSI.header, "\x1b[1m");
+        assert_eq!(TextStyles::ANSI.reset, "\x1b[0m");
+
+        let mut result = create_test_result();
+        let (a, b) = result.both[0].clone();
+        result.modified.push((a, b));
+        let output = ColorTextFormatter.format(&result);
+        assert!(output.starts_with("\x1b[1mA-only (2 entries):\x1b[0m\n"));
+        assert!(output.contains("\n  \x1b[31mfile_a.txt\x1b[0m\n"));
+        assert!(output.contains("\n  \x1b[32mfile_b.txt\x1b[0m\n"));
+        assert!(output.contains("\n  \x1b[33mcommon.txt != common.txt\x1b[0m\n"));
+        // Entries in both directories keep the terminal's color
+        assert!(output.contains("\n  common.txt == common.txt\n"));
+
+        // Without the escape sequences the text is that of TextFormatter
+        let mut stripped = output.clone();
+        for style in ["\x1b[31m", "\x1b[32m", "\x1b[33m", "\x1b[1m", "\x1b[0m"] {
+            stripped = stripped.replace(style, "");
+        }
+        assert_eq!(stripped, TextFormatter.format(&result));
+    }
+
+    #[test]
+    fn test_plain_styles_change_nothing() {
+        assert_eq!(TextStyles::PLAIN.paint(TextStyles::PLAIN.a_only, "x"), "x");
+        assert_eq!(
+            TextStyles::ANSI.paint(TextStyles::ANSI.entries(Section::Both), "x"),
+            "x"
+        );
+        let result = create_test_result();
+        assert!(!TextFormatter.format(&result).contains('\x1b'));
+    }
+
+    #[test]
+    fn test_markdown_formatter_default_snapshot() {
+        let expected = "# Directory Comparison Report\n\n\
+## Summary\n\n\
+| Category | Count |\n|---------|-------|\n\
+| A-only | 2 |\n\
+| B-only | 2 |\n\
+| Modified | 0 |\n\
+| Both | 1 |\n\n\
+## A-only\n\n\
+- `dir\\_a-only//`\n\
+- `file\\_a.txt`\n\n\
+## B-only\n\n\
+- `dir\\_b-only//`\n\
+- `file\\_b.txt`\n\n\
+## Modified\n\n\
+*No entries*\n\n\
+## Both\n\n\
+- `common.txt` == `common.txt`\n\n\
+---\n\n\
+A: 0 files, 0 directories, 0 B; B: 0 files, 0 directories, 0 B; 0 B hashed in 0.00s\n";
+        let result = create_test_result();
+        assert_eq!(MarkdownFormatter.format(&result), expected);
+    }
+
+    #[test]
+    fn test_formatters_with_custom_labels() {
+        use dir_compare_core::output::Labels;
+
+        let labels = Labels {
+            report_title: "Verzeichnisvergleich".to_string(),
+            a_only: "Nur in A".to_string(),
+            b_only: "Nur in B".to_string(),
+            both: "In beiden".to_string(),
+            entry_one: "Eintrag".to_string(),
+            entry_other: "Einträge".to_string(),
+            ..Labels::default()
+        };
+        let mut result = create_test_result();
+        result.both.clear();
+
+        let text = TextFormatter.format_with_labels(&result, &labels);
+        assert!(text.contains("Nur in A (2 Einträge):"));
+        assert!(text.contains("Nur in B (2 Einträge):"));
+        assert!(text.contains("In beiden (0 Einträge):"));
+
+        let md = MarkdownFormatter.format_with_labels(&result, &labels);
+        assert!(md.starts_with("# Verzeichnisvergleich\n"));
+        assert!(md.contains("## Nur in A"));
+        assert!(md.contains("| In beiden | 0 |"));
+    }
+
+    #[test]
+    fn test_labels_plural_rule() {
+        use dir_compare_core::output::Labels;
+
+        let labels = Labels {
+            entry_one: "entry".to_string(),
+            entry_other: "entries".to_string(),
+            ..Labels::default()
+        };
+        assert_eq!(labels.entries(0), "0 entries");
+        assert_eq!(labels.entries(1), "1 entry");
+        assert_eq!(labels.entries(2), "2 entries");
+    }
+
+    #[test]
+    fn test_json_formatter_structure() {
+        use dir_compare_core::output::JsonFormatter;
+
+        let result = ComparisonResult {
+            a_only: vec![Entry {
+                path: PathBuf::from("docs"),
+                abs_path: PathBuf::from("/a/docs"),
+                kind: EntryKind::Directory,
+                size: None,
+                nested_repository: false,
+                mtime: None,
+            }],
+            b_only: Vec::new(),
+            both: create_test_result().both,
+            modified: vec![],
+            exact_counts: None,
+            warnings: Vec::new(),
+            stats: Default::default(),
+            context: None,
+            identical_subtrees: Vec::new(),
+            mismatch_reasons: Vec::new(),
+            renamed: Vec::new(),
+        };
+
+        let output = JsonFormatter.format(&result);
+        let json: serde_json::Value = serde_json::from_str(&output).unwrap();
+
+        assert_eq!(json["a_only"][0]["path"], "docs");
+        assert_eq!(json["a_only"][0]["kind"], "directory");
+        assert!(json["a_only"][0]["size"].is_null());
+        assert_eq!(json["b_only"].as_array().unwrap().len(), 0);
+        assert_eq!(json["both"][0]["a"]["path"], "common.txt");
+        assert_eq!(json["both"][0]["b"]["kind"], "file");
+        assert_eq!(json["both"][0]["b"]["size"], 150);
+        assert!(!output.contains("/a/docs"));
+    }
+
+    #[test]
+    fn test_json_formatter_empty_result() {
+        use dir_compare_core::output::JsonFormatter;
+
+        let result = ComparisonResult {
+            a_only: Vec::new(),
+            b_only: Vec::new(),
+            both: Vec::new(),
+            modified: vec![],
+            exact_counts: None,
+            warnings: Vec::new(),
+            stats: Default::default(),
+            context: None,
+            identical_subtrees: Vec::new(),
+            mismatch_reasons: Vec::new(),
+            renamed: Vec::new(),
+        };
+        assert_eq!(
+            JsonFormatter.format(&result),
+            "{\n  \"format_version\": 10,\n  \"counts\": {\"a_only\": 0, \"b_only\": 0, \"both\": 0, \"modified\": 0, \"renamed\": 0},\n  \"stats\": {\"files_a\": 0, \"files_b\": 0, \"dirs_a\": 0, \"dirs_b\": 0, \"bytes_a\": 0, \"bytes_b\": 0, \"bytes_hashed\": 0, \"elapsed_ms\": 0},\n  \"a_only\": [],\n  \"b_only\": [],\n  \"both\": [],\n  \"modified\": [],\n  \"renamed\": []\n}\n"
+        );
+    }
+
+    #[test]
+    fn test_json_formatter_escapes_special_characters() {
+        use dir_compare_core::output::JsonFormatter;
+
+        let mut result = ComparisonResult {
+            a_only: Vec::new(),
+            b_only: Vec::new(),
+            both: Vec::new(),
+            modified: vec![],
+            exact_counts: None,
+            warnings: Vec::new(),
+            stats: Default::default(),
+            context: None,
+            identical_subtrees: Vec::new(),
+            mismatch_reasons: Vec::new(),
+            renamed: Vec::new(),
+        };
+        for name in [
+            "quote\"name.txt",
+            "back\\slash.txt",
+            "tab\tname.txt",
+            "测试🎉.txt",
+        ] {
+            result.a_only.push(Entry {
+                path: PathBuf::from(name),
+                abs_path: PathBuf::from(name),
+                kind: EntryKind::File,
+                size: Some(1),
+                nested_repository: false,
+                mtime: None,
+            });
+        }
+
+        let json: serde_json::Value = serde_json::from_str(&JsonFormatter.format(&result)).unwrap();
+        let paths: Vec<&str> = json["a_only"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|e| e["path"].as_str().unwrap())
+            .collect();
+        assert_eq!(
+            paths,
+            vec![
+                "quote\"name.txt",
+                "back\\slash.txt",
+                "tab\tname.txt",
+                "测试🎉.txt"
+            ]
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_json_formatter_lossy_paths() {
+        use dir_compare_core::output::JsonFormatter;
+        use std::ffi::OsStr;
+        use std::os::unix::ffi::OsStrExt;
+
+        let path = PathBuf::from(OsStr::from_bytes(b"bad\xffname"));
+        let result = ComparisonResult {
+            a_only: vec![Entry {
+                path: path.clone(),
+                abs_path: path,
+                kind: EntryKind::File,
+                size: Some(1),
+                nested_repository: false,
+                mtime: None,
+            }],
+            b_only: Vec::new(),
+            both: Vec::new(),
+            modified: vec![],
+            exact_counts: None,
+            warnings: Vec::new(),
+            stats: Default::default(),
+            context: None,
+            identical_subtrees: Vec::new(),
+            mismatch_reasons: Vec::new(),
+            renamed: Vec::new(),
+        };
+
+        let json: serde_json::Value = serde_json::from_str(&JsonFormatter.format(&result)).unwrap();
+        assert_eq!(json["a_only"][0]["path"], "bad\u{fffd}name");
+    }
+
+    #[test]
+    fn test_flat_json_formatter() {
+        use dir_compare_core::comparison::{FlatComparisonResult, FlatContentGroup};
+        use dir_compare_core::output::{FlatFormatter, FlatJsonFormatter};
+
+        let result = FlatComparisonResult {
+            groups: vec![FlatContentGroup {
+                hash: "abc123".to_string(),
+                size: 42,
+                file_count: 2,
+                files_in_a: vec![PathBuf::from("old/x.txt")],
+                files_in_b: vec![PathBuf::from("new/x.txt")],
+                hard_links: Vec::new(),
+            }],
+            total_files_a: 1,
+            total_files_b: 1,
+            unique_hashes: 1,
+            duplicate_count: 1,
+            warnings: Vec::new(),
+            stats: Default::default(),
+            chunk_dedupe: None,
+        };
+
+        let json: serde_json::Value =
+            serde_json::from_str(&FlatJsonFormatter.format(&result)).unwrap();
+        assert_eq!(json["format_version"], 10);
+        assert_eq!(json["total_files_a"], 1);
+        assert_eq!(json["total_files_b"], 1);
+        assert_eq!(json["unique_hashes"], 1);
+        assert_eq!(json["duplicate_count"], 1);
+        assert_eq!(json["groups"][0]["hash"], "abc123");
+        assert_eq!(json["groups"][0]["size"], 42);
+        assert_eq!(json["groups"][0]["file_count"], 2);
+        assert_eq!(json["groups"][0]["files_in_a"][0], "old/x.txt");
+        assert_eq!(json["groups"][0]["files_in_b"][0], "new/x.txt");
+    }
+
+    #[test]
+    fn test_csv_formatter_rows() {
+        use dir_compare_core::output::CsvFormatter;
+
+        let result = create_test_result();
+        let output = CsvFormatter.format(&result);
+        let lines: Vec<&str> = output.lines().collect();
+
+        assert_eq!(lines[0], "category,path,kind,size,reason");
+        assert_eq!(lines.len(), 6);
+        assert!(lines.contains(&"a_only,file_a.txt,file,100,"));
+        assert!(lines.contains(&"b_only,file_b.txt,file,200,"));
+        assert!(lines.contains(&"both,common.txt,file,150,"));
+        assert!(
+            lines
+                .iter()
+                .any(|l| l.starts_with("a_only,") && l.ends_with(",directory,,"))
+        );
+    }
+
+    #[test]
+    fn test_csv_formatter_quotes_special_paths() {
+        use dir_compare_core::output::CsvFormatter;
+
+        let mut result = ComparisonResult {
+            a_only: Vec::new(),
+            b_only: Vec::new(),
+            both: Vec::new(),
+            modified: vec![],
+            exact_counts: None,
+            warnings: Vec::new(),
+            stats: Default::default(),
+            context: None,
+            identical_subtrees: Vec::new(),
+            mismatch_reasons: Vec::new(),
+            renamed: Vec::new(),
+        };
+        for name in [
+            "report, final.txt",
+            "say \"hi\".txt",
+            "line\nbreak.txt",
+            "résumé,测试.txt",
+        ] {
+            result.a_only.push(Entry {
+                path: PathBuf::from(name),
+                abs_path: PathBuf::from(name),
+                kind: EntryKind::File,
+                size: Some(1),
+                nested_repository: false,
+                mtime: None,
+            });
+        }
+
+        let output = CsvFormatter.format(&result);
+        assert!(output.contains("a_only,\"report, final.txt\",file,1,\n"));
+        assert!(output.contains("a_only,\"say \"\"hi\"\".txt\",file,1,\n"));
+        assert!(output.contains("a_only,\"line\nbreak.txt\",file,1,\n"));
+        assert!(output.contains("a_only,\"résumé,测试.txt\",file,1,\n"));
+    }
+
+    #[test]
+    fn test_csv_formatter_reason_of_modified_rows() {
+        use dir_compare_core::MismatchReason;
+        use dir_compare_core::output::CsvFormatter;
+
+        let file = |path: &str, size| Entry {
+            path: PathBuf::from(path),
+            abs_path: PathBuf::from(path),
+            kind: EntryKind::File,
+            size: Some(size),
+            nested_repository: false,
+            mtime: None,
+        };
+        let result = ComparisonResult {
+            a_only: Vec::new(),
+            b_only: Vec::new(),
+            both: vec![(file("same.txt", 1), file("same.txt", 1))],
+            modified: vec![
+                (file("grown.txt", 2), file("grown.txt", 3)),
+                (file("edited.txt", 4), file("edited.txt", 4)),
+            ],
+            exact_counts: None,
+            warnings: Vec::new(),
+            stats: Default::default(),
+            context: None,
+            identical_subtrees: Vec::new(),
+            mismatch_reasons: vec![
+                (PathBuf::from("edited.txt"), MismatchReason::ContentDiffers),
+                (
+                    PathBuf::from("grown.txt"),
+                    MismatchReason::SizeDiffers { a: 2, b: 3 },
+                ),
+            ],
+            renamed: Vec::new(),
+        };
+
+        let output = CsvFormatter.format(&result);
+        assert!(output.contains("both,same.txt,file,1,\n"), "{}", output);
+        assert!(
+            output.contains("modified,grown.txt,file,2,size\n"),
+            "{}",
+            output
+        );
+        assert!(
+            output.contains("modified,edited.txt,file,4,content\n"),
+            "{}",
+            output
+        );
+    }
+
+    #[test]
+    fn test_flat_csv_formatter() {
+        use dir_compare_core::comparison::{FlatComparisonResult, FlatContentGroup};
+        use dir_compare_core::output::{FlatCsvFormatter, FlatFormatter};
+
+        let result = FlatComparisonResult {
+            groups: vec![
+                FlatContentGroup {
+                    hash: "aaa".to_string(),
+                    size: 10,
+                    file_count: 1,
+                    files_in_a: vec![PathBuf::from("a, b.txt")],
+                    files_in_b: vec![],
+                    hard_links: Vec::new(),
+                },
+                FlatContentGroup {
+                    hash: "bbb".to_string(),
+                    size: 20,
+                    file_count: 3,
+                    files_in_a: vec![PathBuf::from("x.txt"), PathBuf::from("y.txt")],
+                    files_in_b: vec![PathBuf::from("日本.txt")],
+                    hard_links: Vec::new(),
+                },
+            ],
+            total_files_a: 3,
+            total_files_b: 1,
+            unique_hashes: 2,
+            duplicate_count: 1,
+            warnings: Vec::new(),
+            stats: Default::default(),
+            chunk_dedupe: None,
+        };
+
+        let output = FlatCsvFormatter.format(&result);
+        assert_eq!(
+            output,
+            "hash,size,side,path,status\n\
+             aaa,10,a,\"a, b.txt\",a_only\n\
+             bbb,20,a,x.txt,duplicate\n\
+             bbb,20,a,y.txt,duplicate\n\
+             bbb,20,b,日本.txt,duplicate\n"
+        );
+    }
+
+    fn create_modified_result() -> ComparisonResult {
+        let entry = |size| Entry {
+            path: PathBuf::from("notes.txt"),
+            abs_path: PathBuf::from("notes.txt"),
+            kind: EntryKind::File,
+            size: Some(size),
+            nested_repository: false,
+            mtime: None,
+        };
+        let mut result = create_test_result();
+        result.modified.push((entry(98), entry(120)));
+        result
+    }
+
+    #[test]
+    fn test_formatters_render_modified_section() {
+        use dir_compare_core::output::{CsvFormatter, JsonFormatter};
+
+        let result = create_modified_result();
+
+        let text = TextFormatter.format(&result);
+        assert!(text.contains(
+            "Modified (1 entries):\n----------------------------------------\n  notes.txt != notes.txt\n"
+        ));
+        assert!(text.find("Modified (").unwrap() < text.find("Both (").unwrap());
+
+        let html = HtmlFormatter.format(&result);
+        assert!(html.contains("<h2>Modified</h2>"));
+        assert!(html.contains("notes.txt != notes.txt"));
+        assert!(html.contains("summary-box modified"));
+
+        let md = MarkdownFormatter.format(&result);
+        assert!(md.contains("| Modified | 1 |"));
+        assert!(md.contains("## Modified\n\n- `notes.txt` != `notes.txt`\n"));
+
+        let json: serde_json::Value = serde_json::from_str(&JsonFormatter.format(&result)).unwrap();
+        assert_eq!(json["modified"][0]["a"]["size"], 98);
+        assert_eq!(json["modified"][0]["b"]["size"], 120);
+
+        let csv = CsvFormatter.format(&result);
+        assert!(csv.ends_with("modified,notes.txt,file,98,\n"));
+    }
+
+    #[test]
+    fn test_split_modified_restores_one_sided_layout() {
+        let mut result = create_modified_result();
+        result.split_modified();
+
+        assert!(result.modified.is_empty());
+        let a_paths: Vec<_> = result.a_only.iter().map(|e| e.path.clone()).collect();
+        assert_eq!(
+            a_paths,
+            vec![
+                PathBuf::from("dir_a-only/"),
+                PathBuf::from("file_a.txt"),
+                PathBuf::from("notes.txt")
+            ]
+        );
+        assert_eq!(result.b_only.len(), 3);
+        assert_eq!(result.b_only[2].size, Some(120));
+    }
+
+    #[test]
+    fn test_sections_reordered_and_filtered() {
+        use dir_compare_core::output::{Labels, Section, SectionConfig};
+
+        let result = create_modified_result();
+        let sections = SectionConfig::new(["b-only", "different"].map(|s| s.parse().unwrap()));
+        assert_eq!(sections.sections(), &[Section::BOnly, Section::Modified]);
+
+        let text = TextFormatter.format_with_sections(&result, &Labels::default(), &sections);
+        assert!(text.starts_with("B-only ("));
+        assert!(text.find("B-only (").unwrap() < text.find("Modified (").unwrap());
+        assert!(!text.contains("A-only ("));
+        assert!(!text.contains("Both ("));
+
+        let md = MarkdownFormatter.format_with_sections(&result, &Labels::default(), &sections);
+        assert!(md.find("## B-only").unwrap() < md.find("## Modified").unwrap());
+        assert!(!md.contains("## A-only"));
+        assert!(md.contains("| A-only | 2 |"));
+        assert!(md.contains("| Both | 1 |"));
+
+        let html = HtmlFormatter.format_with_sections(&result, &Labels::default(), &sections);
+        assert!(html.find("<h2>B-only</h2>").unwrap() < html.find("<h2>Modified</h2>").unwrap());
+        assert!(!html.contains("<h2>A-only</h2>"));
+        assert!(!html.contains("<h2>Both</h2>"));
+        assert!(html.ends_with("    </script>\n</body>\n</html>\n"));
+    }
+
+    #[test]
+    fn test_sections_html_summary_keeps_all_counts() {
+        use dir_compare_core::output::{Labels, Section, SectionConfig};
+
+        let result = create_modified_result();
+        let html = HtmlFormatter.format_with_sections(
+            &result,
+            &Labels::default(),
+            &SectionConfig::new([Section::Both]),
+        );
+        for class in ["a-only", "b-only", "modified", "both"] {
+            assert!(html.contains(&format!("summary-box {}", class)));
+        }
+        let counts: Vec<&str> = html
+            .match_indices("<div class=\"count\">")
+            .map(|(i, m)| &html[i + m.len()..i + m.len() + 1])
+            .collect();
+        assert_eq!(counts, vec!["2", "2", "1", "1"]);
+    }
+
+    #[test]
+    fn test_summary_only_lists_counts_without_entries() {
+        use dir_compare_core::output::{JsonFormatter, Labels, SectionConfig};
+
+        let result = create_modified_result();
+        let labels = Labels::default();
+        let summary = SectionConfig::summary_only();
+        assert!(summary.is_summary_only() && summary.sections().is_empty());
+        assert!(!SectionConfig::default().is_summary_only());
+
+        let text = TextFormatter.format_with_sections(&result, &labels, &summary);
+        assert!(text.starts_with(
+            "A-only: 2 entries\nB-only: 2 entries\nModified: 1 entries\nBoth: 1 entries\n\nA: "
+        ));
+        let md = MarkdownFormatter.format_with_sections(&result, &labels, &summary);
+        assert!(md.contains("| Modified | 1 |"));
+        let html = HtmlFormatter.format_with_sections(&result, &labels, &summary);
+        assert!(html.contains("summary-box modified"));
+        assert!(!html.contains("id=\"filter\"") && !html.contains("<script>"));
+        assert!(html.ends_with("    </div>\n</body>\n</html>\n"));
+        let json = JsonFormatter.format_with_sections(&result, &summary);
+        for report in [&text, &md, &html, &json] {
+            for path in ["file_a.txt", "file_b.txt", "notes.txt"] {
+                assert!(!report.contains(path), "{}", report);
+            }
+        }
+
+        let json: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(json["counts"]["a_only"], 2);
+        assert_eq!(json["modified"], serde_json::json!([]));
+        assert_eq!(
+            JsonFormatter.format_with_sections(&result, &SectionConfig::default()),
+            JsonFormatter.format(&result)
+        );
+    }
+
+    #[test]
+    fn test_default_sections_match_unfiltered_output() {
+        use dir_compare_core::output::{Labels, SectionConfig};
+
+        let result = create_modified_result();
+        let labels = Labels::default();
+        let sections = SectionConfig::default();
+        assert_eq!(
+            TextFormatter.format_with_sections(&result, &labels, &sections),
+            TextFormatter.format(&result)
+        );
+        assert_eq!(
+            MarkdownFormatter.format_with_sections(&result, &labels, &sections),
+            MarkdownFormatter.format(&result)
+        );
+        assert_eq!(
+            HtmlFormatter.format_with_sections(&result, &labels, &sections),
+            HtmlFormatter.format(&result)
+        );
+    }
+
+    #[test]
+    fn test_section_rejects_unknown_name() {
+        use dir_compare_core::output::Section;
+
+        let err = "unchanged".parse::<Section>().unwrap_err();
+        assert!(err.contains("unknown section 'unchanged'"));
+    }
+
+    #[test]
+    fn test_formatters_render_symlink_targets() {
+        use dir_compare_core::output::{CsvFormatter, JsonFormatter};
+
+        let link = |target: &str| Entry {
+            path: PathBuf::from("current"),
+            abs_path: PathBuf::from("current"),
+            kind: EntryKind::Symlink {
+                target: PathBuf::from(target),
+            },
+            size: None,
+            nested_repository: false,
+            mtime: None,
+        };
+        let result = ComparisonResult {
+            a_only: vec![link("releases/v1")],
+            b_only: vec![],
+            both: vec![],
+            modified: vec![(link("v1"), link("v2"))],
+            exact_counts: None,
+            warnings: Vec::new(),
+            stats: Default::default(),
+            context: None,
+            identical_subtrees: Vec::new(),
+            mismatch_reasons: Vec::new(),
+            renamed: Vec::new(),
+        };
+
+        let text = TextFormatter.format(&result);
+        assert!(text.contains("  current -> releases/v1\n"));
+        assert!(text.contains("  current -> v1 != current -> v2\n"));
+
+        let md = MarkdownFormatter.format(&result);
+        assert!(md.contains("- `current -> releases/v1`\n"));
+        assert!(md.contains("- `current -> v1` != `current -> v2`\n"));
+
+        let html = HtmlFormatter.format(&result);
+        assert!(html.contains(r#"<li class="symlink">current -&gt; releases/v1</li>"#));
+
+        let json: serde_json::Value = serde_json::from_str(&JsonFormatter.format(&result)).unwrap();
+        assert_eq!(json["a_only"][0]["kind"], "symlink");
+        assert_eq!(json["a_only"][0]["target"], "releases/v1");
+        assert_eq!(json["modified"][0]["b"]["target"], "v2");
+
+        let csv = CsvFormatter.format(&result);
+        assert!(csv.contains("a_only,current,symlink,,\n"));
+    }
+
+    #[test]
+    fn test_formatters_mark_nested_repositories() {
+        use dir_compare_core::output::JsonFormatter;
+
+        let repo = |nested_repository| Entry {
+            path: PathBuf::from("vendor/lib"),
+            abs_path: PathBuf::from("vendor/lib"),
+            kind: EntryKind::Directory,
+            size: None,
+            nested_repository,
+            mtime: None,
+        };
+        let result = ComparisonResult {
+            a_only: vec![repo(true)],
+            b_only: vec![],
+            both: vec![(repo(true), repo(true))],
+            modified: vec![],
+            exact_counts: None,
+            warnings: Vec::new(),
+            stats: Default::default(),
+            context: None,
+            identical_subtrees: Vec::new(),
+            mismatch_reasons: Vec::new(),
+            renamed: Vec::new(),
+        };
+
+        let text = TextFormatter.format(&result);
+        assert!(
+            text.contains("  vendor/lib/ [nested repository]\n"),
+            "{}",
+            text
+        );
+        assert!(
+            text.contains("  vendor/lib == vendor/lib [nested repository]\n"),
+            "{}",
+            text
+        );
+
+        let md = MarkdownFormatter.format(&result);
+        assert!(
+            md.contains("- `vendor/lib/` *(nested repository)*\n"),
+            "{}",
+            md
+        );
+
+        let html = HtmlFormatter.format(&result);
+        assert!(
+            html.contains(r#"vendor/lib/ <em class="nested-repo">(nested repository)</em></li>"#)
+        );
+
+        let json: serde_json::Value = serde_json::from_str(&JsonFormatter.format(&result)).unwrap();
+        assert_eq!(json["a_only"][0]["nested_repository"], true);
+
+        let plain = ComparisonResult {
+            a_only: vec![repo(false)],
+            both: vec![(repo(false), repo(false))],
+            ..result
+        };
+        assert!(!TextFormatter.format(&plain).contains("nested"));
+        let json: serde_json::Value = serde_json::from_str(&JsonFormatter.format(&plain)).unwrap();
+        assert!(json["a_only"][0].get("nested_repository").is_none());
+    }
+
+    #[test]
+    fn test_flat_formatters_share_trait() {
+        use dir_compare_core::comparison::{FlatComparisonResult, FlatContentGroup};
+        use dir_compare_core::output::{
+            FlatCsvFormatter, FlatFormatter, FlatHtmlFormatter, FlatJsonFormatter,
+            FlatMarkdownFormatter, FlatTextFormatter,
+        };
+
+        let result = FlatComparisonResult {
+            groups: vec![FlatContentGroup {
+                hash: "abc123".to_string(),
+                size: 42,
+                file_count: 2,
+                files_in_a: vec![PathBuf::from("old/x.txt")],
+                files_in_b: vec![PathBuf::from("new/x.txt")],
+                hard_links: Vec::new(),
+            }],
+            total_files_a: 1,
+            total_files_b: 1,
+            unique_hashes: 1,
+            duplicate_count: 1,
+            warnings: Vec::new(),
+            stats: Default::default(),
+            chunk_dedupe: None,
+        };
+
+        let formatters: Vec<Box<dyn FlatFormatter>> = vec![
+            Box::new(FlatTextFormatter),
+            Box::new(FlatHtmlFormatter),
+            Box::new(FlatMarkdownFormatter),
+            Box::new(FlatJsonFormatter),
+            Box::new(FlatCsvFormatter),
+        ];
+        for formatter in formatters {
+            let mut streamed = Vec::new();
+            formatter.format_to(&result, &mut streamed).unwrap();
+            let output = formatter.format(&result);
+            assert!(output.contains("x.txt"), "{}", output);
+            assert_eq!(String::from_utf8(streamed).unwrap(), output);
+        }
+    }
+
+    #[test]
+    fn test_format_to_matches_format() {
+        let result = create_test_result();
+        let formatters: Vec<Box<dyn Formatter>> = vec![
+            Box::new(TextFormatter),
+            Box::new(HtmlFormatter),
+            Box::new(MarkdownFormatter),
+        ];
+        for formatter in formatters {
+            let mut streamed = Vec::new();
+            formatter.format_to(&result, &mut streamed).unwrap();
+            assert_eq!(
+                String::from_utf8(streamed).unwrap(),
+                formatter.format(&result)
+            );
+        }
+    }
 }
@@ -0,0 +1,131 @@
+use dir_compare_core::comparison::{
+    ComparisonOptions, ComparisonResult, ComparisonStrategy, Entry, FastHashStrategy,
+    compare_directories, compare_directories_with_options,
+};
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::thread::ThreadId;
+use std::time::Duration;
+
+/// Creates two trees sharing every path; every third file differs in content
+fn setup_dirs(count: usize) -> tempfile::TempDir {
+    let temp = tempfile::tempdir().unwrap();
+    for i in 0..count {
+        let rel = format!("d{}/file{}.bin", i % 7, i);
+        for side in ["a", "b"] {
+            let path = temp.path().join(side).join(&rel);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            let differs = side == "b" && i % 3 == 0;
+            let content = format!("{}-{}", i, if differs { "changed" } else { "same" });
+            fs::write(path, content.repeat(50)).unwrap();
+        }
+    }
+    fs::write(temp.path().join("a/extra_a.txt"), "a").unwrap();
+    fs::write(temp.path().join("b/extra_b.txt"), "b").unwrap();
+    temp
+}
+
+fn paths(entries: &[Entry]) -> Vec<PathBuf> {
+    entries.iter().map(|e| e.path.clone()).collect()
+}
+
+fn compare_with_threads(
+    root: &Path,
+    strategy: &dyn ComparisonStrategy,
+    threads: usize,
+) -> ComparisonResult {
+    let options = ComparisonOptions {
+        threads: Some(threads),
+        ..Default::default()
+    };
+    compare_directories_with_options(&root.join("a"), &root.join("b"), strategy, None, &options)
+        .unwrap()
+}
+
+#[test]
+fn test_parallel_results_match_single_threaded() {
+    let temp = setup_dirs(120);
+    let strategy = FastHashStrategy::new(false);
+
+    let single = compare_with_threads(temp.path(), &strategy, 1);
+    for threads in [2, 4, 16] {
+        let parallel = compare_with_threads(temp.path(), &strategy, threads);
+        assert_eq!(paths(&parallel.a_only), paths(&single.a_only));
+        assert_eq!(paths(&parallel.b_only), paths(&single.b_only));
+        let both = |r: &ComparisonResult| -> Vec<PathBuf> {
+            r.both.iter().map(|(a, _)| a.path.clone()).collect()
+        };
+        assert_eq!(both(&parallel), both(&single));
+        let modified = |r: &ComparisonResult| -> Vec<PathBuf> {
+            r.modified.iter().map(|(a, _)| a.path.clone()).collect()
+        };
+        assert_eq!(modified(&parallel), modified(&single));
+    }
+
+    // 40 changed files, plus one extra file per side
+    assert_eq!(single.modified.len(), 40);
+    assert_eq!(single.a_only.len(), 1);
+    assert_eq!(single.b_only.len(), 1);
+    let modified: Vec<PathBuf> = single
+        .modified
+        .iter()
+        .map(|(a, _)| a.path.clone())
+        .collect();
+    let mut sorted = modified.clone();
+    sorted.sort();
+    assert_eq!(modified, sorted);
+}
+
+#[test]
+fn test_default_options_match_compare_directories() {
+    let temp = setup_dirs(30);
+    let strategy = FastHashStrategy::new(false);
+
+    let default = compare_directories(
+        &temp.path().join("a"),
+        &temp.path().join("b"),
+        &strategy,
+        None,
+    )
+    .unwrap();
+    let explicit = compare_with_threads(temp.path(), &strategy, 3);
+    assert_eq!(paths(&default.a_only), paths(&explicit.a_only));
+    assert_eq!(default.both.len(), explicit.both.len());
+}
+
+/// Records which threads evaluated pairs; slow enough that work is shared
+struct ThreadRecordingStrategy {
+    threads: Mutex<HashSet<ThreadId>>,
+}
+
+impl ComparisonStrategy for ThreadRecordingStrategy {
+    fn matches(&self, _a: &Entry, _b: &Entry) -> bool {
+        self.threads
+            .lock()
+            .unwrap()
+            .insert(std::thread::current().id());
+        std::thread::sleep(Duration::from_millis(5));
+        true
+    }
+}
+
+#[test]
+fn test_pairs_evaluated_on_requested_threads() {
+    let temp = setup_dirs(40);
+
+    let strategy = ThreadRecordingStrategy {
+        threads: Mutex::new(HashSet::new()),
+    };
+    let result = compare_with_threads(temp.path(), &strategy, 4);
+    assert!(result.both.len() >= 40);
+    let used = strategy.threads.lock().unwrap().len();
+    assert!(used > 1 && used <= 4, "used {} threads", used);
+
+    let strategy = ThreadRecordingStrategy {
+        threads: Mutex::new(HashSet::new()),
+    };
+    compare_with_threads(temp.path(), &strategy, 1);
+    assert_eq!(strategy.threads.lock().unwrap().len(), 1);
+}
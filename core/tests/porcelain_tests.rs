@@ -0,0 +1,96 @@
+use dir_compare_core::comparison::{ComparisonResult, Entry, EntryKind};
+use dir_compare_core::output::{Formatter, PorcelainFormatter};
+use std::path::PathBuf;
+
+fn file(path: &str) -> Entry {
+    Entry {
+        path: PathBuf::from(path),
+        abs_path: PathBuf::from("/root").join(path),
+        kind: EntryKind::File,
+        size: Some(1),
+        nested_repository: false,
+        mtime: None,
+    }
+}
+
+fn result(a_only: &[&str], b_only: &[&str], both: &[&str], modified: &[&str]) -> ComparisonResult {
+    let pair = |path: &&str| (file(path), file(path));
+    ComparisonResult {
+        a_only: a_only.iter().map(|path| file(path)).collect(),
+        b_only: b_only.iter().map(|path| file(path)).collect(),
+        both: both.iter().map(pair).collect(),
+        modified: modified.iter().map(pair).collect(),
+        exact_counts: None,
+        warnings: Vec::new(),
+        stats: Default::default(),
+        context: None,
+        identical_subtrees: Vec::new(),
+        mismatch_reasons: Vec::new(),
+        renamed: Vec::new(),
+    }
+}
+
+fn tricky_result() -> ComparisonResult {
+    result(
+        &["plain.txt", "with space.txt"],
+        &["line\nbreak.txt", "café.txt"],
+        &["same.txt"],
+        &["quote\"and\\slash.txt", "tab\there.txt"],
+    )
+}
+
+#[test]
+fn test_porcelain_quoted_snapshot() {
+    let bytes = PorcelainFormatter::default().format_bytes(&tricky_result());
+    let expected: &[u8] = b"B \"caf\\303\\251.txt\"\n\
+B \"line\\nbreak.txt\"\n\
+A plain.txt\n\
+M \"quote\\\"and\\\\slash.txt\"\n\
+M \"tab\\there.txt\"\n\
+A \"with space.txt\"\n";
+    assert_eq!(
+        String::from_utf8_lossy(&bytes),
+        String::from_utf8_lossy(expected)
+    );
+    assert_eq!(bytes, expected);
+}
+
+#[test]
+fn test_porcelain_nul_terminated_snapshot() {
+    let formatter = PorcelainFormatter {
+        nul_terminated: true,
+    };
+    let bytes = formatter.format_bytes(&tricky_result());
+    let expected: &[u8] = "B café.txt\0\
+B line\nbreak.txt\0\
+A plain.txt\0\
+M quote\"and\\slash.txt\0\
+M tab\there.txt\0\
+A with space.txt\0"
+        .as_bytes();
+    assert_eq!(bytes, expected);
+}
+
+#[test]
+fn test_porcelain_lists_only_differences() {
+    let bytes = PorcelainFormatter::default().format_bytes(&result(&[], &[], &["same.txt"], &[]));
+    assert!(bytes.is_empty());
+
+    let bytes = PorcelainFormatter::default().format_bytes(&result(
+        &["b/only.txt"],
+        &["a.txt"],
+        &["same.txt"],
+        &["m.txt"],
+    ));
+    assert_eq!(bytes, b"B a.txt\nA b/only.txt\nM m.txt\n");
+}
+
+#[test]
+fn test_porcelain_formatter_trait_matches_bytes() {
+    let formatter = PorcelainFormatter::default();
+    let result = tricky_result();
+    assert_eq!(
+        formatter.format(&result).into_bytes(),
+        formatter.format_bytes(&result)
+    );
+}
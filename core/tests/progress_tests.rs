@@ -0,0 +1,202 @@
+use dir_compare_core::comparison::{
+    CancellationToken, ComparisonError, ComparisonOptions, FastHashStrategy, Progress,
+    ProgressPhase, Side, compare_directories_with_progress,
+};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::sync::mpsc::channel;
+
+/// An owned copy of a progress update
+#[derive(Debug, Clone, PartialEq)]
+struct Update {
+    phase: ProgressPhase,
+    current: usize,
+    total: Option<usize>,
+    side: Option<Side>,
+    path: PathBuf,
+}
+
+impl From<&Progress<'_>> for Update {
+    fn from(progress: &Progress) -> Self {
+        Self {
+            phase: progress.phase,
+            current: progress.current,
+            total: progress.total,
+            side: progress.side,
+            path: progress.current_path.to_path_buf(),
+        }
+    }
+}
+
+/// Creates dir_a with 3 files and dir_b with 2 of them plus one of its own
+fn setup_dirs() -> tempfile::TempDir {
+    let temp = tempfile::tempdir().unwrap();
+    for (side, names) in [
+        ("a", ["one.txt", "two.txt", "three.txt"]),
+        ("b", ["one.txt", "two.txt", "four.txt"]),
+    ] {
+        let dir = temp.path().join(side);
+        fs::create_dir_all(&dir).unwrap();
+        for name in names {
+            fs::write(dir.join(name), name).unwrap();
+        }
+    }
+    temp
+}
+
+fn collect_updates(root: &Path, threads: usize) -> Vec<Update> {
+    let updates = Mutex::new(Vec::new());
+    let options = ComparisonOptions {
+        threads: Some(threads),
+        ..Default::default()
+    };
+    compare_directories_with_progress(
+        &root.join("a"),
+        &root.join("b"),
+        &FastHashStrategy::new(false),
+        None,
+        &options,
+        &|progress| updates.lock().unwrap().push(Update::from(progress)),
+    )
+    .unwrap();
+    updates.into_inner().unwrap()
+}
+
+#[test]
+fn test_progress_reports_traversal_then_matching() {
+    let temp = setup_dirs();
+    let updates = collect_updates(temp.path(), 1);
+
+    let traversal: Vec<&Update> = updates
+        .iter()
+        .take_while(|u| u.phase == ProgressPhase::Traversing)
+        .collect();
+    assert_eq!(traversal.len(), 6);
+    assert!(traversal.iter().all(|u| u.total.is_none()));
+    // Each directory is counted on its own, A first
+    let counts: Vec<(Option<Side>, usize)> =
+        traversal.iter().map(|u| (u.side, u.current)).collect();
+    let (a, b) = (Some(Side::A), Some(Side::B));
+    assert_eq!(counts, vec![(a, 1), (a, 2), (a, 3), (b, 1), (b, 2), (b, 3)]);
+
+    let matching = &updates[traversal.len()..];
+    assert!(matching.iter().all(|u| u.phase == ProgressPhase::Matching));
+    let counts: Vec<usize> = matching.iter().map(|u| u.current).collect();
+    assert_eq!(counts, vec![1, 2]);
+    assert!(
+        matching
+            .iter()
+            .all(|u| u.total == Some(2) && u.side.is_none())
+    );
+    let mut paths: Vec<&Path> = matching.iter().map(|u| u.path.as_path()).collect();
+    paths.sort();
+    assert_eq!(paths, vec![Path::new("one.txt"), Path::new("two.txt")]);
+}
+
+#[test]
+fn test_progress_counts_each_pair_once_across_threads() {
+    let temp = tempfile::tempdir().unwrap();
+    for side in ["a", "b"] {
+        for i in 0..50 {
+            let path = temp.path().join(side).join(format!("f{}.txt", i));
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, format!("{}", i)).unwrap();
+        }
+    }
+
+    let updates = collect_updates(temp.path(), 4);
+    let mut counts: Vec<usize> = updates
+        .iter()
+        .filter(|u| u.phase == ProgressPhase::Matching)
+        .map(|u| u.current)
+        .collect();
+    counts.sort();
+    assert_eq!(counts, (1..=50).collect::<Vec<_>>());
+}
+
+#[test]
+fn test_progress_can_be_forwarded_from_another_thread() {
+    let temp = setup_dirs();
+    let (tx, rx) = channel();
+
+    let root = temp.path().to_path_buf();
+    std::thread::spawn(move || {
+        compare_directories_with_progress(
+            &root.join("a"),
+            &root.join("b"),
+            &FastHashStrategy::new(false),
+            None,
+            &ComparisonOptions::default(),
+            &|progress| {
+                tx.send(Update::from(progress)).unwrap();
+            },
+        )
+        .unwrap()
+    })
+    .join()
+    .unwrap();
+
+    let last = rx.iter().last().unwrap();
+    assert_eq!(last.phase, ProgressPhase::Matching);
+    assert_eq!((last.current, last.total), (2, Some(2)));
+}
+
+#[test]
+fn test_cancelled_comparison_stops_early() {
+    let temp = setup_dirs();
+    for phase in [ProgressPhase::Traversing, ProgressPhase::Matching] {
+        let cancel = CancellationToken::new();
+        let options = ComparisonOptions {
+            threads: Some(1),
+            cancel: Some(cancel.clone()),
+            ..Default::default()
+        };
+        let updates = Mutex::new(Vec::new());
+        let result = compare_directories_with_progress(
+            &temp.path().join("a"),
+            &temp.path().join("b"),
+            &FastHashStrategy::new(false),
+            None,
+            &options,
+            &|progress| {
+                updates.lock().unwrap().push(Update::from(progress));
+                if progress.phase == phase {
+                    cancel.cancel();
+                }
+            },
+        );
+
+        let Err(error) = result else {
+            panic!("comparison cancelled in {:?} should fail", phase);
+        };
+        assert!(matches!(error, ComparisonError::Cancelled), "{:?}", error);
+        let updates = updates.into_inner().unwrap();
+        let last = updates.last().unwrap();
+        assert_eq!(last.phase, phase);
+        assert_eq!(
+            last.current, 1,
+            "no entries after cancelling in {:?}",
+            phase
+        );
+    }
+}
+
+#[test]
+fn test_uncancelled_token_has_no_effect() {
+    let temp = setup_dirs();
+    let options = ComparisonOptions {
+        cancel: Some(CancellationToken::new()),
+        ..Default::default()
+    };
+    let result = compare_directories_with_progress(
+        &temp.path().join("a"),
+        &temp.path().join("b"),
+        &FastHashStrategy::new(false),
+        None,
+        &options,
+        &|_| {},
+    )
+    .unwrap();
+    assert_eq!(result.both.len(), 2);
+}
@@ -0,0 +1,207 @@
+use dir_compare_core::comparison::{ComparisonResult, FastHashStrategy};
+use dir_compare_core::output::{
+    Formatter, HtmlFormatter, JsonFormatter, MarkdownFormatter, PorcelainFormatter, SectionConfig,
+    TextFormatter,
+};
+use dir_compare_core::sync::{SyncAction, SyncDirection, plan_sync};
+use dir_compare_core::{Comparison, Entry};
+use std::fs;
+use std::path::{Path, PathBuf};
+use tempfile::TempDir;
+
+fn write(root: &Path, path: &str, contents: &str) {
+    let path = root.join(path);
+    fs::create_dir_all(path.parent().unwrap()).unwrap();
+    fs::write(path, contents).unwrap();
+}
+
+/// Two trees in which:
+/// - `old.txt` moved to `archive/new.txt`
+/// - `report.txt` was renamed to `Report final.txt`
+/// - two copies of `dup` content in A face one copy in B, which is ambiguous
+/// - an empty file in A faces an empty file in B
+/// - `gone.txt` and `added.txt` have different content of the same size
+fn setup() -> TempDir {
+    let temp = TempDir::new().unwrap();
+    let (a, b) = (temp.path().join("a"), temp.path().join("b"));
+    for dir in [&a, &b] {
+        write(dir, "kept.txt", "kept");
+    }
+    write(&a, "old.txt", "moved content");
+    write(&b, "archive/new.txt", "moved content");
+    write(&a, "report.txt", "quarterly numbers");
+    write(&b, "Report final.txt", "quarterly numbers");
+    write(&a, "dup1.txt", "dup");
+    write(&a, "dup2.txt", "dup");
+    write(&b, "dup3.txt", "dup");
+    write(&a, "empty_a.txt", "");
+    write(&b, "empty_b.txt", "");
+    write(&a, "gone.txt", "aaaa");
+    write(&b, "added.txt", "bbbb");
+    temp
+}
+
+fn compare(temp: &TempDir, detect: bool) -> ComparisonResult {
+    let strategy = FastHashStrategy::new(false);
+    Comparison::new(temp.path().join("a"), temp.path().join("b"))
+        .strategy(&strategy)
+        .detect_renames(detect)
+        .run()
+        .unwrap()
+}
+
+fn paths(entries: &[Entry]) -> Vec<PathBuf> {
+    entries.iter().map(|e| e.path.clone()).collect()
+}
+
+fn renamed(result: &ComparisonResult) -> Vec<(PathBuf, PathBuf)> {
+    result
+        .renamed
+        .iter()
+        .map(|(a, b)| (a.path.clone(), b.path.clone()))
+        .collect()
+}
+
+fn pair(a: &str, b: &str) -> (PathBuf, PathBuf) {
+    (PathBuf::from(a), PathBuf::from(b))
+}
+
+#[test]
+fn test_unique_content_matches_are_paired() {
+    let temp = setup();
+    let result = compare(&temp, true);
+
+    assert_eq!(
+        renamed(&result),
+        vec![
+            pair("old.txt", "archive/new.txt"),
+            pair("report.txt", "Report final.txt"),
+        ]
+    );
+    assert!(result.has_differences());
+}
+
+#[test]
+fn test_ambiguous_empty_and_different_files_stay_one_sided() {
+    let temp = setup();
+    let result = compare(&temp, true);
+
+    let expected_a: Vec<PathBuf> = ["dup1.txt", "dup2.txt", "empty_a.txt", "gone.txt"]
+        .iter()
+        .map(PathBuf::from)
+        .collect();
+    assert_eq!(paths(&result.a_only), expected_a);
+    let expected_b: Vec<PathBuf> = ["added.txt", "archive", "dup3.txt", "empty_b.txt"]
+        .iter()
+        .map(PathBuf::from)
+        .collect();
+    assert_eq!(paths(&result.b_only), expected_b);
+}
+
+#[test]
+fn test_detection_is_off_by_default() {
+    let temp = setup();
+    let result = compare(&temp, false);
+
+    assert!(result.renamed.is_empty());
+    assert!(paths(&result.a_only).contains(&PathBuf::from("old.txt")));
+    assert!(paths(&result.b_only).contains(&PathBuf::from("archive/new.txt")));
+}
+
+#[test]
+fn test_swap_sides_swaps_renamed_pairs() {
+    let temp = setup();
+    let mut result = compare(&temp, true);
+    result.swap_sides();
+
+    assert_eq!(
+        renamed(&result),
+        vec![
+            pair("Report final.txt", "report.txt"),
+            pair("archive/new.txt", "old.txt"),
+        ]
+    );
+}
+
+#[test]
+fn test_sync_copies_and_deletes_renamed_files() {
+    let temp = setup();
+    let result = compare(&temp, true);
+    let actions = plan_sync(&result, SyncDirection::AToB);
+
+    let copied: Vec<&Path> = actions
+        .iter()
+        .filter_map(|action| match action {
+            SyncAction::CopyToB(entry) => Some(entry.path.as_path()),
+            _ => None,
+        })
+        .collect();
+    assert!(copied.contains(&Path::new("old.txt")));
+    assert!(copied.contains(&Path::new("report.txt")));
+    let deleted: Vec<&Path> = actions
+        .iter()
+        .filter_map(|action| match action {
+            SyncAction::DeleteFromB(entry) => Some(entry.path.as_path()),
+            _ => None,
+        })
+        .collect();
+    assert!(deleted.contains(&Path::new("archive/new.txt")));
+    assert!(deleted.contains(&Path::new("Report final.txt")));
+}
+
+#[test]
+fn test_formatters_render_renamed_section() {
+    let temp = setup();
+    let result = compare(&temp, true);
+
+    let text = TextFormatter.format(&result);
+    assert!(text.contains("Renamed/Moved (2 entries)"), "{}", text);
+    assert!(text.contains("  old.txt -> archive/new.txt\n"), "{}", text);
+
+    let markdown = MarkdownFormatter.format(&result);
+    assert!(markdown.contains("Renamed/Moved"), "{}", markdown);
+    assert!(markdown.contains("old.txt"), "{}", markdown);
+
+    let html = HtmlFormatter.format(&result);
+    assert!(html.contains("Renamed/Moved"), "{}", html);
+    assert!(html.contains("-&gt;"), "{}", html);
+
+    let json: serde_json::Value = serde_json::from_str(&JsonFormatter.format(&result)).unwrap();
+    assert_eq!(json["renamed"][0]["a"]["path"], "old.txt");
+    assert_eq!(json["renamed"][0]["b"]["path"], "archive/new.txt");
+
+    let porcelain = PorcelainFormatter::default().format(&result);
+    assert!(
+        porcelain.contains("R old.txt -> archive/new.txt\n"),
+        "{}",
+        porcelain
+    );
+    assert!(
+        porcelain.contains("R report.txt -> \"Report final.txt\"\n"),
+        "{}",
+        porcelain
+    );
+    let nul = PorcelainFormatter {
+        nul_terminated: true,
+    }
+    .format(&result);
+    assert!(nul.contains("R archive/new.txt\0old.txt\0"), "{:?}", nul);
+}
+
+#[test]
+fn test_json_counts_renamed_pairs_left_out_of_a_summary() {
+    let temp = setup();
+    let result = compare(&temp, true);
+
+    let json: serde_json::Value = serde_json::from_str(&JsonFormatter.format(&result)).unwrap();
+    assert_eq!(json["counts"]["renamed"], 2);
+
+    let summary = JsonFormatter.format_with_sections(&result, &SectionConfig::summary_only());
+    let json: serde_json::Value = serde_json::from_str(&summary).unwrap();
+    assert_eq!(json["counts"]["renamed"], 2);
+    assert_eq!(json["renamed"], serde_json::json!([]));
+
+    let json: serde_json::Value =
+        serde_json::from_str(&JsonFormatter.format(&compare(&temp, false))).unwrap();
+    assert_eq!(json["counts"]["renamed"], 0);
+}
@@ -0,0 +1,189 @@
+use dir_compare_core::comparison::{
+    ComparisonResult, Entry, EntryKind, FlatComparisonResult, FlatContentGroup,
+};
+use dir_compare_core::output::{
+    FlatFormatter, FlatHtmlFormatter, FlatJsonFormatter, FlatMarkdownFormatter, FlatTextFormatter,
+    Formatter, HtmlFormatter, JsonFormatter, Labels, MarkdownFormatter, TextFormatter,
+};
+use std::path::PathBuf;
+
+fn file(path: &str, size: u64) -> Entry {
+    Entry {
+        path: PathBuf::from(path),
+        abs_path: PathBuf::from("/root").join(path),
+        kind: EntryKind::File,
+        size: Some(size),
+        nested_repository: false,
+        mtime: None,
+    }
+}
+
+fn sample_result() -> ComparisonResult {
+    ComparisonResult {
+        a_only: vec![file("old.txt", 1)],
+        b_only: vec![file("new.txt", 2), file("extra.txt", 3)],
+        both: vec![(file("same.txt", 4), file("same.txt", 4))],
+        modified: vec![(file("notes.txt", 5), file("notes.txt", 6))],
+        exact_counts: None,
+        warnings: Vec::new(),
+        stats: Default::default(),
+        context: None,
+        identical_subtrees: Vec::new(),
+        mismatch_reasons: Vec::new(),
+        renamed: Vec::new(),
+    }
+}
+
+fn sample_flat_result() -> FlatComparisonResult {
+    FlatComparisonResult {
+        groups: vec![
+            FlatContentGroup {
+                hash: "abc123".to_string(),
+                size: 42,
+                file_count: 2,
+                files_in_a: vec![PathBuf::from("old/x.txt")],
+                files_in_b: vec![PathBuf::from("new/x.txt")],
+                hard_links: Vec::new(),
+            },
+            FlatContentGroup {
+                hash: "def456".to_string(),
+                size: 7,
+                file_count: 1,
+                files_in_a: vec![PathBuf::from("gone.txt")],
+                files_in_b: vec![],
+                hard_links: Vec::new(),
+            },
+        ],
+        total_files_a: 2,
+        total_files_b: 1,
+        unique_hashes: 2,
+        duplicate_count: 1,
+        warnings: Vec::new(),
+        stats: Default::default(),
+        chunk_dedupe: None,
+    }
+}
+
+fn german() -> Labels {
+    Labels::for_language("de").unwrap()
+}
+
+#[test]
+fn test_german_text_report() {
+    let text = TextFormatter.format_with_labels(&sample_result(), &german());
+    assert!(text.starts_with("Nur in A (1 Eintrag):\n"), "{}", text);
+    assert!(text.contains("Nur in B (2 Einträge):\n"));
+    assert!(text.contains("Verändert (1 Eintrag):\n"));
+    assert!(text.contains("In beiden (1 Eintrag):\n"));
+    assert!(!text.contains("A-only"));
+}
+
+#[test]
+fn test_german_markdown_report() {
+    let md = MarkdownFormatter.format_with_labels(&sample_result(), &german());
+    assert!(md.starts_with("# Verzeichnisvergleich\n"), "{}", md);
+    assert!(md.contains("## Zusammenfassung\n"));
+    assert!(md.contains("| Kategorie | Anzahl |"));
+    assert!(md.contains("| Nur in B | 2 |"));
+    assert!(md.contains("## In beiden\n"));
+}
+
+#[test]
+fn test_german_html_report() {
+    let html = HtmlFormatter.format_with_labels(&sample_result(), &german());
+    assert!(html.contains("<html lang=\"de\">"));
+    assert!(html.contains("<title>Verzeichnisvergleich</title>"));
+    assert!(html.contains("<h2>Verändert</h2>"));
+    assert!(html.contains("<div class=\"label\">Nur in A</div>"));
+    // The stylesheet and class names stay the same
+    assert!(html.contains("summary-box a-only"));
+
+    let english = HtmlFormatter.format(&sample_result());
+    assert!(english.contains("<html lang=\"en\">"));
+}
+
+#[test]
+fn test_german_flat_reports() {
+    let result = sample_flat_result();
+    let labels = german();
+
+    let text = FlatTextFormatter.format_with_labels(&result, &labels);
+    assert!(text.starts_with("Zusammenfassung des Inhaltsvergleichs\n"));
+    assert!(text.contains("Dateien in Verzeichnis A: 2\n"));
+    assert!(text.contains("[DUPLIKAT]"));
+    assert!(text.contains("[NUR-A]"));
+    assert!(text.contains("(nach B verschoben/kopiert)"));
+
+    let md = FlatMarkdownFormatter.format_with_labels(&result, &labels);
+    assert!(md.starts_with("# Inhaltsvergleich\n"), "{}", md);
+    assert!(md.contains("| Kennzahl | Wert |"));
+    assert!(md.contains("## Inhaltsgruppen\n"));
+    assert!(md.contains("**Verzeichnis A:**"));
+
+    let html = FlatHtmlFormatter.format_with_labels(&result, &labels);
+    assert!(html.contains("<html lang=\"de\">"));
+    assert!(html.contains("<title>Inhaltsvergleich der Verzeichnisse</title>"));
+    assert!(!html.contains("Directory"));
+}
+
+#[test]
+fn test_english_labels_match_plain_format() {
+    let result = sample_result();
+    let flat = sample_flat_result();
+    let english = Labels::for_language("en").unwrap();
+    assert_eq!(
+        TextFormatter.format_with_labels(&result, &english),
+        TextFormatter.format(&result)
+    );
+    assert_eq!(
+        MarkdownFormatter.format_with_labels(&result, &english),
+        MarkdownFormatter.format(&result)
+    );
+    assert_eq!(
+        HtmlFormatter.format_with_labels(&result, &english),
+        HtmlFormatter.format(&result)
+    );
+    assert_eq!(
+        FlatTextFormatter.format_with_labels(&flat, &english),
+        FlatTextFormatter.format(&flat)
+    );
+    assert_eq!(
+        FlatMarkdownFormatter.format_with_labels(&flat, &english),
+        FlatMarkdownFormatter.format(&flat)
+    );
+    assert_eq!(
+        FlatHtmlFormatter.format_with_labels(&flat, &english),
+        FlatHtmlFormatter.format(&flat)
+    );
+}
+
+#[test]
+fn test_json_ignores_labels() {
+    let render = |labels: &Labels| {
+        let mut hierarchy = Vec::new();
+        JsonFormatter
+            .format_to_with_labels(&sample_result(), labels, &mut hierarchy)
+            .unwrap();
+        let mut flat = Vec::new();
+        FlatJsonFormatter
+            .format_to_with_labels(&sample_flat_result(), labels, &mut flat)
+            .unwrap();
+        (hierarchy, flat)
+    };
+    let english = render(&Labels::default());
+    assert_eq!(
+        english.0,
+        JsonFormatter.format(&sample_result()).into_bytes()
+    );
+    for code in ["de", "ja"] {
+        assert_eq!(render(&Labels::for_language(code).unwrap()), english);
+    }
+}
+
+#[test]
+fn test_japanese_counts_have_no_plural() {
+    let labels = Labels::for_language("ja").unwrap();
+    assert_eq!(labels.entries(1), labels.entries(2).replace('2', "1"));
+    let text = TextFormatter.format_with_labels(&sample_result(), &labels);
+    assert!(text.contains("B のみ (2 件):"), "{}", text);
+}
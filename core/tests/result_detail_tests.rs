@@ -0,0 +1,243 @@
+use dir_compare_core::comparison::{
+    CategoryCounts, ComparisonOptions, ComparisonResult, Entry, EntryKind, FilenameSizeStrategy,
+    ResultCounts, ResultDetail, compare_directories_with_options,
+};
+use dir_compare_core::output::{
+    Formatter, HtmlFormatter, JsonFormatter, MarkdownFormatter, TextFormatter, group_digits,
+};
+use dir_compare_core::{Section, assert_result_matches};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Creates five entries in each category, written in reverse order.
+///
+/// Files only in A hold 10 bytes, files only in B 20 bytes; matching files
+/// hold 3 bytes on both sides and modified files 4 bytes in A, 5 in B.
+fn setup_tree() -> tempfile::TempDir {
+    let temp = tempfile::tempdir().unwrap();
+    let (a, b) = (temp.path().join("dir_a"), temp.path().join("dir_b"));
+    fs::create_dir_all(&a).unwrap();
+    fs::create_dir_all(&b).unwrap();
+    for i in (0..5).rev() {
+        fs::write(a.join(format!("a_only_{}.txt", i)), [b'a'; 10]).unwrap();
+        fs::write(b.join(format!("b_only_{}.txt", i)), [b'b'; 20]).unwrap();
+        fs::write(a.join(format!("same_{}.txt", i)), "abc").unwrap();
+        fs::write(b.join(format!("same_{}.txt", i)), "abc").unwrap();
+        fs::write(a.join(format!("changed_{}.txt", i)), "abcd").unwrap();
+        fs::write(b.join(format!("changed_{}.txt", i)), "abcde").unwrap();
+    }
+    temp
+}
+
+fn compare(root: &Path, result_detail: ResultDetail) -> ComparisonResult {
+    let options = ComparisonOptions {
+        result_detail,
+        ..Default::default()
+    };
+    compare_directories_with_options(
+        &root.join("dir_a"),
+        &root.join("dir_b"),
+        &FilenameSizeStrategy::new(false),
+        None,
+        &options,
+    )
+    .unwrap()
+}
+
+fn expected_counts() -> ResultCounts {
+    ResultCounts {
+        a_only: CategoryCounts {
+            entries: 5,
+            bytes_a: 50,
+            bytes_b: 0,
+        },
+        b_only: CategoryCounts {
+            entries: 5,
+            bytes_a: 0,
+            bytes_b: 100,
+        },
+        both: CategoryCounts {
+            entries: 5,
+            bytes_a: 15,
+            bytes_b: 15,
+        },
+        modified: CategoryCounts {
+            entries: 5,
+            bytes_a: 20,
+            bytes_b: 25,
+        },
+    }
+}
+
+#[test]
+fn test_full_detail_keeps_every_entry() {
+    let temp = setup_tree();
+    let result = compare(temp.path(), ResultDetail::Full);
+
+    assert_eq!(result.exact_counts, None);
+    assert_eq!(result.counts(), expected_counts());
+    assert_eq!(result.a_only.len(), 5);
+    assert_eq!(result.both.len(), 5);
+    assert_eq!(result.omitted(Section::Both), 0);
+}
+
+#[test]
+fn test_counts_only_tallies_without_entries() {
+    let temp = setup_tree();
+    let result = compare(temp.path(), ResultDetail::CountsOnly);
+
+    assert!(result.a_only.is_empty());
+    assert!(result.b_only.is_empty());
+    assert!(result.both.is_empty());
+    assert!(result.modified.is_empty());
+    assert_eq!(result.exact_counts, Some(expected_counts()));
+    assert_eq!(result.counts(), expected_counts());
+    for section in Section::ALL {
+        assert_eq!(result.count(section), 5, "{:?}", section);
+        assert_eq!(result.omitted(section), 5, "{:?}", section);
+    }
+    assert!(result.has_differences());
+}
+
+#[test]
+fn test_sampled_keeps_first_entries_by_path() {
+    let temp = setup_tree();
+    let result = compare(temp.path(), ResultDetail::Sampled { per_category: 2 });
+
+    assert_result_matches!(
+        result,
+        a_only: ["a_only_0.txt", "a_only_1.txt"],
+        b_only: ["b_only_0.txt", "b_only_1.txt"],
+        both: ["same_0.txt", "same_1.txt"],
+        modified: ["changed_0.txt", "changed_1.txt"],
+    );
+    assert_eq!(result.counts(), expected_counts());
+    assert_eq!(result.omitted(Section::AOnly), 3);
+}
+
+#[test]
+fn test_sampled_with_room_to_spare_keeps_everything() {
+    let temp = setup_tree();
+    let full = compare(temp.path(), ResultDetail::Full);
+    let sampled = compare(temp.path(), ResultDetail::Sampled { per_category: 10 });
+
+    for section in Section::ALL {
+        assert_eq!(sampled.paths(section), full.paths(section));
+        assert_eq!(sampled.omitted(section), 0);
+    }
+    assert_eq!(sampled.exact_counts, Some(expected_counts()));
+}
+
+#[test]
+fn test_split_modified_moves_counts() {
+    let temp = setup_tree();
+    let mut result = compare(temp.path(), ResultDetail::CountsOnly);
+    result.split_modified();
+
+    let counts = result.counts();
+    assert_eq!(counts.modified, CategoryCounts::default());
+    assert_eq!(counts.a_only.entries, 10);
+    assert_eq!(counts.a_only.bytes_a, 70);
+    assert_eq!(counts.b_only.entries, 10);
+    assert_eq!(counts.b_only.bytes_b, 125);
+}
+
+#[test]
+fn test_parse_result_detail() {
+    assert_eq!("full".parse(), Ok(ResultDetail::Full));
+    assert_eq!("Counts".parse(), Ok(ResultDetail::CountsOnly));
+    assert_eq!(
+        "sampled:100".parse(),
+        Ok(ResultDetail::Sampled { per_category: 100 })
+    );
+    assert!("sampled:".parse::<ResultDetail>().is_err());
+    assert!("some".parse::<ResultDetail>().is_err());
+}
+
+#[test]
+fn test_group_digits() {
+    assert_eq!(group_digits(0), "0");
+    assert_eq!(group_digits(999), "999");
+    assert_eq!(group_digits(1000), "1,000");
+    assert_eq!(group_digits(4_999_900), "4,999,900");
+}
+
+fn entry(path: &str) -> Entry {
+    Entry {
+        path: PathBuf::from(path),
+        abs_path: PathBuf::from("/a").join(path),
+        kind: EntryKind::File,
+        size: Some(1),
+        nested_repository: false,
+        mtime: None,
+    }
+}
+
+/// A sampled result of 5,000,000 matches of which 100 were kept
+fn sampled_result() -> ComparisonResult {
+    let both: Vec<(Entry, Entry)> = (0..100)
+        .map(|i| {
+            let name = format!("f{:03}", i);
+            (entry(&name), entry(&name))
+        })
+        .collect();
+    ComparisonResult {
+        a_only: vec![],
+        b_only: vec![],
+        both,
+        modified: vec![],
+        exact_counts: Some(ResultCounts {
+            both: CategoryCounts {
+                entries: 5_000_000,
+                bytes_a: 5_000_000,
+                bytes_b: 5_000_000,
+            },
+            ..Default::default()
+        }),
+        warnings: Vec::new(),
+        stats: Default::default(),
+        context: None,
+        identical_subtrees: Vec::new(),
+        mismatch_reasons: Vec::new(),
+        renamed: Vec::new(),
+    }
+}
+
+#[test]
+fn test_formatters_note_omitted_entries() {
+    let result = sampled_result();
+
+    let text = TextFormatter.format(&result);
+    assert!(text.contains("Both (5000000 entries):"), "{}", text);
+    assert!(
+        text.contains("  f099 == f099\n  … plus 4,999,900 more\n"),
+        "{}",
+        text
+    );
+
+    let md = MarkdownFormatter.format(&result);
+    assert!(md.contains("| Both | 5000000 |"));
+    assert!(md.contains("- *… plus 4,999,900 more*\n"));
+
+    let html = HtmlFormatter.format(&result);
+    assert!(html.contains(r#"<div class="count">5000000</div>"#));
+    assert!(html.contains(r#"<p class="more">… plus 4,999,900 more</p>"#));
+
+    let json: serde_json::Value = serde_json::from_str(&JsonFormatter.format(&result)).unwrap();
+    assert_eq!(json["counts"]["both"], 5_000_000);
+    assert_eq!(json["both"].as_array().unwrap().len(), 100);
+}
+
+#[test]
+fn test_counts_only_sections_are_not_empty() {
+    let mut result = sampled_result();
+    result.both.clear();
+
+    let md = MarkdownFormatter.format(&result);
+    assert!(!md.contains("No matching entries"));
+    assert!(md.contains("- *… plus 5,000,000 more*\n"));
+
+    let html = HtmlFormatter.format(&result);
+    assert!(!html.contains("No matching entries"));
+    assert!(html.contains("… plus 5,000,000 more"));
+}
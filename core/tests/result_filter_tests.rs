@@ -0,0 +1,228 @@
+use dir_compare_core::comparison::{ComparisonResult, Entry, EntryFilter, EntryKind};
+use dir_compare_core::output::Section;
+use dir_compare_core::result_filter::{KindFilter, ResultFilter};
+use std::path::PathBuf;
+
+fn entry(path: &str, kind: EntryKind, size: Option<u64>) -> Entry {
+    Entry {
+        path: PathBuf::from(path),
+        abs_path: PathBuf::from("/root").join(path),
+        kind,
+        size,
+        nested_repository: false,
+        mtime: None,
+    }
+}
+
+fn file(path: &str, size: u64) -> Entry {
+    entry(path, EntryKind::File, Some(size))
+}
+
+fn dir(path: &str) -> Entry {
+    entry(path, EntryKind::Directory, None)
+}
+
+const MB: u64 = 1024 * 1024;
+
+/// A result with every kind of entry in every category
+fn sample_result() -> ComparisonResult {
+    ComparisonResult {
+        a_only: vec![
+            dir("bin"),
+            file("bin/app.dll", 12 * MB),
+            file("bin/small.dll", 2 * MB),
+            file("src/main.rs", 4000),
+        ],
+        b_only: vec![
+            file("docs/guide.md", 900),
+            entry(
+                "src/current",
+                EntryKind::Symlink {
+                    target: PathBuf::from("main.rs"),
+                },
+                None,
+            ),
+        ],
+        both: vec![
+            (dir("src"), dir("src")),
+            (file("src/lib.rs", 100), file("src/lib.rs", 100)),
+        ],
+        modified: vec![(file("src/big.dll", 5 * MB), file("src/big.dll", 20 * MB))],
+        exact_counts: None,
+        warnings: Vec::new(),
+        stats: Default::default(),
+        context: None,
+        identical_subtrees: Vec::new(),
+        mismatch_reasons: Vec::new(),
+        renamed: Vec::new(),
+    }
+}
+
+fn paths(result: &ComparisonResult) -> Vec<Vec<String>> {
+    Section::ALL
+        .iter()
+        .map(|&section| result.paths(section))
+        .collect()
+}
+
+fn patterns(include: &[&str], exclude: &[&str]) -> Option<EntryFilter> {
+    Some(EntryFilter::new(include, exclude).unwrap())
+}
+
+/// Asserts that the counts of `result` are those of its vectors
+fn assert_counts_match_vectors(result: &ComparisonResult) {
+    let counts = result.counts();
+    assert_eq!(counts.a_only.entries, result.a_only.len());
+    assert_eq!(counts.b_only.entries, result.b_only.len());
+    assert_eq!(counts.both.entries, result.both.len());
+    assert_eq!(counts.modified.entries, result.modified.len());
+    for section in Section::ALL {
+        assert_eq!(result.omitted(section), 0);
+    }
+}
+
+#[test]
+fn test_empty_filter_keeps_everything() {
+    let result = sample_result();
+    let filter = ResultFilter::default();
+    assert!(filter.is_empty());
+
+    let filtered = result.filter(&filter);
+    assert_eq!(paths(&filtered), paths(&result));
+    assert_eq!(filtered.counts(), result.counts());
+}
+
+#[test]
+fn test_glob_filter_uses_cli_syntax() {
+    let result = sample_result();
+    let filter = ResultFilter {
+        patterns: patterns(&["*.dll"], &["/bin/small.dll"]),
+        ..Default::default()
+    };
+
+    let filtered = result.filter(&filter);
+    assert_eq!(filtered.paths(Section::AOnly), ["bin/app.dll"]);
+    assert!(filtered.b_only.is_empty());
+    assert!(filtered.both.is_empty());
+    assert_eq!(filtered.paths(Section::Modified), ["src/big.dll"]);
+    assert_counts_match_vectors(&filtered);
+}
+
+#[test]
+fn test_path_prefix_filter_matches_whole_components() {
+    let mut result = sample_result();
+    result.a_only.push(file("srcs/other.rs", 1));
+    let filter = ResultFilter {
+        path_prefix: Some(PathBuf::from("src")),
+        ..Default::default()
+    };
+
+    let filtered = result.filter(&filter);
+    assert_eq!(filtered.paths(Section::AOnly), ["src/main.rs"]);
+    assert_eq!(filtered.paths(Section::BOnly), ["src/current"]);
+    assert_eq!(filtered.paths(Section::Both), ["src", "src/lib.rs"]);
+    assert_eq!(filtered.paths(Section::Modified), ["src/big.dll"]);
+}
+
+#[test]
+fn test_size_range_filter() {
+    let result = sample_result();
+    let larger = ResultFilter {
+        min_size: Some(10 * MB),
+        ..Default::default()
+    };
+    let filtered = result.filter(&larger);
+    assert_eq!(filtered.paths(Section::AOnly), ["bin/app.dll"]);
+    assert!(filtered.b_only.is_empty());
+    assert!(filtered.both.is_empty());
+    // B's side of the pair is larger, which is enough
+    assert_eq!(filtered.paths(Section::Modified), ["src/big.dll"]);
+
+    let between = ResultFilter {
+        min_size: Some(1000),
+        max_size: Some(4 * MB),
+        ..Default::default()
+    };
+    let filtered = result.filter(&between);
+    assert_eq!(
+        filtered.paths(Section::AOnly),
+        ["bin/small.dll", "src/main.rs"]
+    );
+    assert!(filtered.b_only.is_empty());
+    assert!(filtered.modified.is_empty());
+}
+
+#[test]
+fn test_kind_filter() {
+    let result = sample_result();
+    let directories = ResultFilter {
+        kind: Some(KindFilter::Directory),
+        ..Default::default()
+    };
+    let filtered = result.filter(&directories);
+    assert_eq!(filtered.paths(Section::AOnly), ["bin"]);
+    assert_eq!(filtered.paths(Section::Both), ["src"]);
+    assert!(filtered.b_only.is_empty());
+
+    let links = ResultFilter {
+        kind: Some(KindFilter::Symlink),
+        ..Default::default()
+    };
+    assert_eq!(result.filter(&links).paths(Section::BOnly), ["src/current"]);
+}
+
+#[test]
+fn test_category_filter() {
+    let result = sample_result();
+    let filter = ResultFilter {
+        sections: Some(vec![Section::BOnly, Section::Modified]),
+        ..Default::default()
+    };
+
+    let filtered = result.filter(&filter);
+    assert!(filtered.a_only.is_empty());
+    assert!(filtered.both.is_empty());
+    assert_eq!(filtered.b_only.len(), 2);
+    assert_eq!(filtered.modified.len(), 1);
+    assert_counts_match_vectors(&filtered);
+}
+
+#[test]
+fn test_combined_constraints_must_all_hold() {
+    let result = sample_result();
+    let filter = ResultFilter {
+        patterns: patterns(&["*.dll"], &[]),
+        path_prefix: Some(PathBuf::from("bin")),
+        min_size: Some(10 * MB),
+        kind: Some(KindFilter::File),
+        sections: Some(vec![Section::AOnly, Section::Modified]),
+        ..Default::default()
+    };
+
+    let filtered = result.filter(&filter);
+    assert_eq!(
+        paths(&filtered),
+        [vec!["bin/app.dll".to_string()], vec![], vec![], vec![]]
+    );
+    assert_counts_match_vectors(&filtered);
+    assert_eq!(filtered.counts().a_only.bytes_a, 12 * MB);
+}
+
+#[test]
+fn test_filter_keeps_original_and_recomputes_sampled_counts() {
+    let mut result = sample_result();
+    let mut exact = result.counts();
+    exact.a_only.entries = 100;
+    result.exact_counts = Some(exact);
+
+    let filter = ResultFilter {
+        kind: Some(KindFilter::File),
+        ..Default::default()
+    };
+    let filtered = result.filter(&filter);
+    assert_eq!(filtered.a_only.len(), 3);
+    assert_counts_match_vectors(&filtered);
+    // The original is left untouched
+    assert_eq!(result.a_only.len(), 4);
+    assert_eq!(result.count(Section::AOnly), 100);
+}
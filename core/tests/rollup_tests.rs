@@ -0,0 +1,225 @@
+use dir_compare_core::comparison::{
+    ComparisonResult, FastHashStrategy, FileWarning, ResultDetail, WarningCause,
+};
+use dir_compare_core::output::{
+    Formatter, HtmlFormatter, JsonFormatter, MarkdownFormatter, TextFormatter,
+};
+use dir_compare_core::{Comparison, Entry, IdenticalSubtree, Section};
+use std::fs;
+use std::path::{Path, PathBuf};
+use tempfile::TempDir;
+
+fn write(root: &Path, path: &str, contents: &str) {
+    let path = root.join(path);
+    fs::create_dir_all(path.parent().unwrap()).unwrap();
+    fs::write(path, contents).unwrap();
+}
+
+/// Two trees in which:
+/// - `same/` matches entirely, with a nested directory
+/// - `partial/` holds a modified file next to the identical `partial/inner/`
+/// - `mixed/` holds a file only in B
+/// - `extra/` is only in A
+/// - `empty/` matches but has nothing below it
+fn setup() -> TempDir {
+    let temp = TempDir::new().unwrap();
+    let (a, b) = (temp.path().join("a"), temp.path().join("b"));
+    for dir in [&a, &b] {
+        write(dir, "same/x.txt", "x");
+        write(dir, "same/y.txt", "y");
+        write(dir, "same/deep/z.txt", "z");
+        write(dir, "partial/ok.txt", "ok");
+        write(dir, "partial/inner/ok.txt", "ok");
+        write(dir, "mixed/common.txt", "common");
+        write(dir, "top.txt", "top");
+        fs::create_dir_all(dir.join("empty")).unwrap();
+    }
+    write(&a, "partial/changed.txt", "first");
+    write(&b, "partial/changed.txt", "other");
+    write(&b, "mixed/new.txt", "new");
+    write(&a, "extra/only.txt", "only");
+    temp
+}
+
+fn compare(temp: &TempDir) -> ComparisonResult {
+    let strategy = FastHashStrategy::new(false);
+    Comparison::new(temp.path().join("a"), temp.path().join("b"))
+        .strategy(&strategy)
+        .run()
+        .unwrap()
+}
+
+fn both_paths(result: &ComparisonResult) -> Vec<PathBuf> {
+    result.both.iter().map(|(a, _)| a.path.clone()).collect()
+}
+
+fn subtree(path: &str, files: usize, entries: usize) -> IdenticalSubtree {
+    IdenticalSubtree {
+        path: PathBuf::from(path),
+        files,
+        entries,
+    }
+}
+
+#[test]
+fn test_rollup_collapses_only_fully_matching_directories() {
+    let temp = setup();
+    let mut result = compare(&temp);
+    result.rollup();
+
+    assert_eq!(
+        result.identical_subtrees,
+        vec![subtree("partial/inner", 1, 1), subtree("same", 3, 4)]
+    );
+    let both = both_paths(&result);
+    for kept in [
+        "same",
+        "partial",
+        "partial/inner",
+        "partial/ok.txt",
+        "mixed",
+        "mixed/common.txt",
+        "empty",
+        "top.txt",
+    ] {
+        assert!(
+            both.contains(&PathBuf::from(kept)),
+            "{} in {:?}",
+            kept,
+            both
+        );
+    }
+    for rolled_up in [
+        "same/x.txt",
+        "same/deep",
+        "same/deep/z.txt",
+        "partial/inner/ok.txt",
+    ] {
+        assert!(!both.contains(&PathBuf::from(rolled_up)), "{}", rolled_up);
+    }
+    assert!(result.identical_subtree(Path::new("partial")).is_none());
+    assert!(result.identical_subtree(Path::new("mixed")).is_none());
+}
+
+#[test]
+fn test_rollup_keeps_other_categories_and_counts() {
+    let temp = setup();
+    let before = compare(&temp);
+    let mut result = compare(&temp);
+    result.rollup();
+
+    let paths = |entries: &[Entry]| entries.iter().map(|e| e.path.clone()).collect::<Vec<_>>();
+    assert_eq!(paths(&result.a_only), paths(&before.a_only));
+    assert_eq!(paths(&result.b_only), paths(&before.b_only));
+    let modified = |result: &ComparisonResult| {
+        result
+            .modified
+            .iter()
+            .map(|(a, b)| (a.path.clone(), b.path.clone()))
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(modified(&result), modified(&before));
+    assert_eq!(result.counts(), before.counts());
+    assert_eq!(result.both.len(), before.both.len() - 5);
+    assert_eq!(result.count(Section::Both), before.both.len());
+    assert_eq!(result.omitted(Section::Both), 0);
+}
+
+#[test]
+fn test_rollup_twice_changes_nothing() {
+    let temp = setup();
+    let mut result = compare(&temp);
+    result.rollup();
+    let (both, counts) = (both_paths(&result), result.counts());
+    result.rollup();
+    assert_eq!(both_paths(&result), both);
+    assert_eq!(result.counts(), counts);
+    assert_eq!(result.identical_subtrees.len(), 2);
+}
+
+#[test]
+fn test_warning_below_directory_prevents_rollup() {
+    let temp = setup();
+    let mut result = compare(&temp);
+    result.warnings.push(FileWarning {
+        path: temp.path().join("b").join("same/deep/z.txt"),
+        cause: WarningCause::Hash,
+        message: "unreadable".to_string(),
+    });
+    result.rollup();
+    assert_eq!(
+        result.identical_subtrees,
+        vec![subtree("partial/inner", 1, 1)]
+    );
+}
+
+#[test]
+fn test_partial_results_are_not_rolled_up() {
+    let temp = setup();
+    let strategy = FastHashStrategy::new(false);
+    let mut result = Comparison::new(temp.path().join("a"), temp.path().join("b"))
+        .strategy(&strategy)
+        .result_detail(ResultDetail::Sampled { per_category: 100 })
+        .run()
+        .unwrap();
+    let both = both_paths(&result);
+    result.rollup();
+    assert_eq!(both_paths(&result), both);
+    assert!(result.identical_subtrees.is_empty());
+}
+
+#[test]
+fn test_swap_sides_keeps_rolled_up_directories() {
+    let temp = setup();
+    let mut result = compare(&temp);
+    result.rollup();
+    let subtrees = result.identical_subtrees.clone();
+    result.swap_sides();
+    assert_eq!(result.identical_subtrees, subtrees);
+    assert_eq!(result.a_only.len(), 1);
+    assert_eq!(result.b_only.len(), 2);
+}
+
+#[test]
+fn test_formatters_render_rolled_up_directories() {
+    let temp = setup();
+    let mut result = compare(&temp);
+    result.rollup();
+
+    let text = TextFormatter.format(&result);
+    assert!(text.contains("  same/ (identical, 3 files)\n"), "{}", text);
+    assert!(
+        text.contains("  partial/inner/ (identical, 1 file)\n"),
+        "{}",
+        text
+    );
+    assert!(!text.contains("same/x.txt"), "{}", text);
+
+    let markdown = MarkdownFormatter.format(&result);
+    assert!(
+        markdown.contains("- `same/` *(identical, 3 files)*\n"),
+        "{}",
+        markdown
+    );
+
+    let html = HtmlFormatter.format(&result);
+    assert!(
+        html.contains(r#"same/ <em class="identical">(identical, 3 files)</em>"#),
+        "{}",
+        html
+    );
+
+    let json: serde_json::Value = serde_json::from_str(&JsonFormatter.format(&result)).unwrap();
+    let both = json["both"].as_array().unwrap();
+    let same = both
+        .iter()
+        .find(|pair| pair["a"]["path"] == "same")
+        .unwrap();
+    assert_eq!(same["identical_files"], 3);
+    let top = both
+        .iter()
+        .find(|pair| pair["a"]["path"] == "top.txt")
+        .unwrap();
+    assert!(top.get("identical_files").is_none());
+    assert_eq!(json["counts"]["both"], result.count(Section::Both));
+}
@@ -0,0 +1,171 @@
+use dir_compare_core::comparison::{
+    FilenameOnlyStrategy, FlatComparisonOptions, compare_directories_flat,
+    compare_directories_with_roots,
+};
+use dir_compare_core::filesystem::{
+    FileSystem, RealFileSystem, RootPaths, normalize_absolute, resolve_root,
+};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// A filesystem whose canonicalize always fails with the given error kind
+struct FailingCanonicalize(io::ErrorKind);
+
+impl FileSystem for FailingCanonicalize {
+    fn canonicalize(&self, _path: &Path) -> io::Result<PathBuf> {
+        Err(io::Error::new(self.0, "simulated canonicalize failure"))
+    }
+
+    fn metadata(&self, path: &Path) -> io::Result<fs::Metadata> {
+        RealFileSystem.metadata(path)
+    }
+}
+
+fn setup_dirs() -> tempfile::TempDir {
+    let temp = tempfile::tempdir().unwrap();
+    for side in ["dir_a", "dir_b"] {
+        fs::create_dir_all(temp.path().join(side).join("sub")).unwrap();
+        fs::write(temp.path().join(side).join("sub/shared.txt"), b"same").unwrap();
+    }
+    fs::write(temp.path().join("dir_a/only_a.txt"), b"a").unwrap();
+    temp
+}
+
+fn paths(entries: &[dir_compare_core::Entry]) -> Vec<PathBuf> {
+    entries.iter().map(|e| e.path.clone()).collect()
+}
+
+#[test]
+fn test_canonicalize_failure_falls_back_to_normalized_path() {
+    let temp = setup_dirs();
+    let root = temp.path().join("dir_a/sub/../.");
+
+    let fs = FailingCanonicalize(io::ErrorKind::PermissionDenied);
+    let resolved = resolve_root(&fs, &root, RootPaths::Canonicalize).unwrap();
+
+    assert_eq!(resolved, temp.path().join("dir_a"));
+}
+
+#[test]
+fn test_canonicalize_not_found_is_an_error() {
+    let temp = setup_dirs();
+    let fs = FailingCanonicalize(io::ErrorKind::NotFound);
+
+    let err = resolve_root(&fs, &temp.path().join("dir_a"), RootPaths::Canonicalize).unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::NotFound);
+}
+
+#[test]
+fn test_normalize_mode_never_canonicalizes() {
+    let temp = setup_dirs();
+    let fs = FailingCanonicalize(io::ErrorKind::Other);
+
+    let resolved = resolve_root(&fs, &temp.path().join("dir_b"), RootPaths::Normalize).unwrap();
+    assert_eq!(resolved, temp.path().join("dir_b"));
+}
+
+#[test]
+fn test_fallback_requires_existing_root() {
+    let temp = setup_dirs();
+    let fs = FailingCanonicalize(io::ErrorKind::PermissionDenied);
+
+    let err = resolve_root(&fs, &temp.path().join("missing"), RootPaths::Canonicalize).unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::NotFound);
+}
+
+#[test]
+fn test_normalize_absolute() {
+    let base = std::env::current_dir().unwrap();
+    assert_eq!(
+        normalize_absolute(Path::new("x/./y/../z")).unwrap(),
+        base.join("x/z")
+    );
+    assert_eq!(normalize_absolute(Path::new(".")).unwrap(), base);
+}
+
+#[test]
+fn test_compare_without_canonicalize_produces_relative_paths() {
+    let temp = setup_dirs();
+    let dir_a = temp.path().join("dir_b/../dir_a");
+    let dir_b = temp.path().join("./dir_b");
+
+    let result = compare_directories_with_roots(
+        &dir_a,
+        &dir_b,
+        &FilenameOnlyStrategy::new(false),
+        None,
+        RootPaths::Normalize,
+    )
+    .unwrap();
+
+    assert_eq!(paths(&result.a_only), vec![PathBuf::from("only_a.txt")]);
+    assert!(result.b_only.is_empty());
+    let both: Vec<PathBuf> = result.both.iter().map(|(a, _)| a.path.clone()).collect();
+    assert_eq!(
+        both,
+        vec![PathBuf::from("sub"), PathBuf::from("sub/shared.txt")]
+    );
+}
+
+#[cfg(unix)]
+#[test]
+fn test_compare_through_symlinked_root_without_canonicalize() {
+    let temp = setup_dirs();
+    let link = temp.path().join("link_to_a");
+    std::os::unix::fs::symlink(temp.path().join("dir_a"), &link).unwrap();
+
+    let result = compare_directories_with_roots(
+        &link,
+        &temp.path().join("dir_b"),
+        &FilenameOnlyStrategy::new(false),
+        None,
+        RootPaths::Normalize,
+    )
+    .unwrap();
+
+    assert_eq!(paths(&result.a_only), vec![PathBuf::from("only_a.txt")]);
+    assert_eq!(result.both.len(), 2);
+    assert!(result.a_only[0].abs_path.starts_with(&link));
+}
+
+#[test]
+fn test_flat_compare_without_canonicalize() {
+    let temp = setup_dirs();
+    let options = FlatComparisonOptions {
+        root_paths: RootPaths::Normalize,
+        ..Default::default()
+    };
+
+    let result = compare_directories_flat(
+        &temp.path().join("dir_b/../dir_a"),
+        &temp.path().join("dir_b"),
+        &options,
+        None,
+    )
+    .unwrap();
+
+    let mut files_a: Vec<PathBuf> = result
+        .groups
+        .iter()
+        .flat_map(|g| g.files_in_a.clone())
+        .collect();
+    files_a.sort();
+    assert_eq!(
+        files_a,
+        vec![PathBuf::from("only_a.txt"), PathBuf::from("sub/shared.txt")]
+    );
+}
+
+#[test]
+fn test_compare_missing_root_still_fails() {
+    let temp = setup_dirs();
+    let result = compare_directories_with_roots(
+        &temp.path().join("missing"),
+        &temp.path().join("dir_b"),
+        &FilenameOnlyStrategy::new(false),
+        None,
+        RootPaths::Normalize,
+    );
+    assert!(result.is_err());
+}
@@ -0,0 +1,210 @@
+use dir_compare_core::comparison::{
+    ComparisonResult, Entry, EntryKind, FlatComparisonResult, FlatContentGroup,
+};
+use dir_compare_core::output::{
+    CsvFormatter, FlatFormatter, FlatTextFormatter, Formatter, HtmlFormatter, JsonFormatter,
+    MarkdownFormatter, ReportContext, TextFormatter,
+};
+use dir_compare_core::sanitize::{
+    SanitizeMode, escape_non_ascii_json, sanitize, sanitize_flat_result, sanitize_result,
+};
+use std::path::PathBuf;
+use std::time::SystemTime;
+
+fn file(path: &str) -> Entry {
+    Entry {
+        path: PathBuf::from(path),
+        abs_path: PathBuf::from("/root").join(path),
+        kind: EntryKind::File,
+        size: Some(1),
+        nested_repository: false,
+        mtime: None,
+    }
+}
+
+/// Damaged names with a bell and a line break, CJK and accented names, and
+/// a plain one
+fn sample_result() -> ComparisonResult {
+    ComparisonResult {
+        a_only: vec![file("bell\x07.txt"), file("line\nbreak.txt")],
+        b_only: vec![file("日本語.txt")],
+        both: vec![(file("plain.txt"), file("plain.txt"))],
+        modified: vec![(file("café.txt"), file("café.txt"))],
+        exact_counts: None,
+        warnings: Vec::new(),
+        stats: Default::default(),
+        context: None,
+        identical_subtrees: Vec::new(),
+        mismatch_reasons: Vec::new(),
+        renamed: Vec::new(),
+    }
+}
+
+#[test]
+fn test_control_mode_shows_control_pictures() {
+    let result = sanitize_result(sample_result(), SanitizeMode::Control);
+    assert_eq!(result.stats.sanitized_paths, 2);
+
+    let text = TextFormatter.format(&result);
+    assert!(text.contains("  bell\u{2407}.txt\n"), "{}", text);
+    assert!(text.contains("  line\u{240a}break.txt\n"));
+    assert!(text.contains("  日本語.txt\n"));
+    assert!(text.contains("  café.txt != café.txt\n"));
+    assert!(
+        text.ends_with("; paths sanitized for output: 2\n"),
+        "{}",
+        text
+    );
+    assert!(!text.contains('\x07'));
+
+    let md = MarkdownFormatter.format(&result);
+    assert!(md.contains("bell\u{2407}.txt"));
+    assert!(md.contains("paths sanitized for output: 2"));
+    let html = HtmlFormatter.format(&result);
+    assert!(html.contains("line\u{240a}break.txt"));
+    assert!(html.contains("paths sanitized for output: 2"));
+    let csv = CsvFormatter.format(&result);
+    assert!(
+        csv.contains("a_only,line\u{240a}break.txt,file,1,\n"),
+        "{}",
+        csv
+    );
+}
+
+#[test]
+fn test_ascii_mode_escapes_and_transliterates() {
+    let result = sanitize_result(sample_result(), SanitizeMode::Ascii);
+    assert_eq!(result.stats.sanitized_paths, 4);
+
+    let text = TextFormatter.format(&result);
+    assert!(text.is_ascii(), "{}", text);
+    assert!(text.contains("  bell\\x07.txt\n"), "{}", text);
+    assert!(text.contains("  line\\x0abreak.txt\n"));
+    assert!(text.contains("  \\u{65e5}\\u{672c}\\u{8a9e}.txt\n"));
+    assert!(text.contains("  cafe.txt != cafe.txt\n"));
+    assert!(text.contains("  plain.txt == plain.txt\n"));
+    assert!(text.ends_with("; paths sanitized for output: 4\n"));
+
+    let md = MarkdownFormatter.format(&result);
+    // Markdown escapes the backslashes so they render as written
+    assert!(md.contains(r"`\\u{65e5}\\u{672c}\\u{8a9e}.txt`"), "{}", md);
+    let csv = CsvFormatter.format(&result);
+    assert!(csv.is_ascii());
+    assert!(csv.contains("b_only,\\u{65e5}\\u{672c}\\u{8a9e}.txt,file,1,\n"));
+}
+
+#[test]
+fn test_json_escapes_instead_of_transliterating() {
+    let json = JsonFormatter.format(&sample_result());
+    assert!(json.contains("\"bell\\u0007.txt\""));
+    assert!(json.contains("\"line\\nbreak.txt\""));
+
+    let ascii = escape_non_ascii_json(&json);
+    assert!(ascii.is_ascii());
+    assert!(ascii.contains("\"\\u65e5\\u672c\\u8a9e.txt\""), "{}", ascii);
+    assert!(ascii.contains("\"caf\\u00e9.txt\""));
+    let original: serde_json::Value = serde_json::from_str(&json).unwrap();
+    let escaped: serde_json::Value = serde_json::from_str(&ascii).unwrap();
+    assert_eq!(escaped, original);
+    // Characters above U+FFFF become surrogate pairs
+    assert_eq!(escape_non_ascii_json("[\"😀\"]"), "[\"\\ud83d\\ude00\"]");
+}
+
+#[test]
+fn test_default_output_unchanged() {
+    let text = TextFormatter.format(&sample_result());
+    assert!(text.contains("  bell\x07.txt\n"));
+    assert!(text.contains("  日本語.txt\n"));
+    assert!(!text.contains("sanitized"));
+
+    // Nothing to rewrite leaves the report as it was
+    let plain = || ComparisonResult {
+        a_only: vec![file("plain.txt")],
+        b_only: Vec::new(),
+        both: Vec::new(),
+        modified: Vec::new(),
+        renamed: Vec::new(),
+        exact_counts: None,
+        warnings: Vec::new(),
+        stats: Default::default(),
+        context: None,
+        identical_subtrees: Vec::new(),
+        mismatch_reasons: Vec::new(),
+    };
+    for mode in [SanitizeMode::Control, SanitizeMode::Ascii] {
+        let sanitized = sanitize_result(plain(), mode);
+        assert_eq!(sanitized.stats.sanitized_paths, 0);
+        assert_eq!(
+            TextFormatter.format(&sanitized),
+            TextFormatter.format(&plain())
+        );
+    }
+}
+
+#[test]
+fn test_flat_paths_are_sanitized() {
+    let result = FlatComparisonResult {
+        groups: vec![FlatContentGroup {
+            hash: "abc123".to_string(),
+            size: 1,
+            file_count: 2,
+            files_in_a: vec![PathBuf::from("bell\x07.txt")],
+            files_in_b: vec![PathBuf::from("日本.txt")],
+            hard_links: Vec::new(),
+        }],
+        total_files_a: 1,
+        total_files_b: 1,
+        unique_hashes: 1,
+        duplicate_count: 1,
+        warnings: Vec::new(),
+        stats: Default::default(),
+        chunk_dedupe: None,
+    };
+    let text = FlatTextFormatter.format(&sanitize_flat_result(result.clone(), SanitizeMode::Ascii));
+    assert!(text.contains("  [A] bell\\x07.txt -> "), "{}", text);
+    assert!(text.contains("  [B] \\u{65e5}\\u{672c}.txt <- "));
+    assert!(text.contains("; paths sanitized for output: 2"));
+    let text = FlatTextFormatter.format(&sanitize_flat_result(result, SanitizeMode::Control));
+    assert!(text.contains("  [B] 日本.txt <- "));
+    assert!(text.contains("; paths sanitized for output: 1"));
+}
+
+#[test]
+fn test_context_directories_are_sanitized() {
+    let result = ComparisonResult {
+        context: Some(ReportContext {
+            dir_a: PathBuf::from("/srv/café"),
+            dir_b: PathBuf::from("/srv/bell\x07"),
+            strategy_name: "hash".to_string(),
+            options: Vec::new(),
+            timestamp: SystemTime::UNIX_EPOCH,
+        }),
+        ..sample_result()
+    };
+    let result = sanitize_result(result, SanitizeMode::Ascii);
+    // The header is not counted with the entries
+    assert_eq!(result.stats.sanitized_paths, 4);
+    let text = TextFormatter.format(&result);
+    assert!(text.starts_with("Directory A: /srv/cafe\nDirectory B: /srv/bell\\x07\n"));
+}
+
+#[test]
+fn test_sanitize_characters() {
+    assert_eq!(sanitize("del\x7f", SanitizeMode::Control), "del\u{2421}");
+    assert_eq!(sanitize("nul\0", SanitizeMode::Control), "nul\u{2400}");
+    assert_eq!(
+        sanitize("next\u{85}line", SanitizeMode::Control),
+        "next\\u{85}line"
+    );
+    assert_eq!(
+        sanitize("next\u{85}line", SanitizeMode::Ascii),
+        "next\\u{85}line"
+    );
+    assert_eq!(
+        sanitize("Straße — Über", SanitizeMode::Ascii),
+        "Strasse -- Uber"
+    );
+    assert_eq!(sanitize("ok.txt", SanitizeMode::Ascii), "ok.txt");
+    assert_eq!("control".parse(), Ok(SanitizeMode::Control));
+    assert!("utf8".parse::<SanitizeMode>().is_err());
+}
@@ -0,0 +1,177 @@
+#![cfg(feature = "schema")]
+
+use dir_compare_core::block_diff::locate_differences;
+use dir_compare_core::chunk_dedupe::ChunkDedupeOptions;
+use dir_compare_core::comparison::{
+    FastHashStrategy, FlatComparisonOptions, compare_directories, compare_directories_flat,
+};
+use dir_compare_core::output::{
+    FlatFormatter, FlatJsonFormatter, Formatter, JSON_FORMAT_VERSION, JsonFormatter, SectionConfig,
+};
+use dir_compare_core::schema::{flat_json_report_schema, json_report_schema};
+use serde_json::Value;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Creates dir_a and dir_b with entries in every category
+fn setup_dirs() -> tempfile::TempDir {
+    let temp = tempfile::tempdir().unwrap();
+    let (a, b) = (temp.path().join("a"), temp.path().join("b"));
+    fs::create_dir_all(a.join("docs")).unwrap();
+    fs::create_dir_all(&b).unwrap();
+    fs::write(a.join("docs/guide.md"), "guide").unwrap();
+    fs::write(b.join("new.txt"), "new").unwrap();
+    for dir in [&a, &b] {
+        fs::write(dir.join("same.txt"), "same").unwrap();
+    }
+    fs::write(a.join("changed.txt"), "old").unwrap();
+    fs::write(b.join("changed.txt"), "new content").unwrap();
+    #[cfg(unix)]
+    for dir in [&a, &b] {
+        std::os::unix::fs::symlink("same.txt", dir.join("link")).unwrap();
+    }
+    temp
+}
+
+fn parse(json: &str) -> Value {
+    serde_json::from_str(json).unwrap()
+}
+
+/// Validates `report` against `schema`, returning the error messages
+fn validation_errors(schema: &Value, report: &Value) -> Vec<String> {
+    let validator = jsonschema::validator_for(schema).unwrap();
+    validator
+        .iter_errors(report)
+        .map(|e| format!("{} at {}", e, e.instance_path))
+        .collect()
+}
+
+fn hierarchy_report(root: &Path) -> Value {
+    let result = compare_directories(
+        &root.join("a"),
+        &root.join("b"),
+        &FastHashStrategy::new(false),
+        None,
+    )
+    .unwrap();
+    parse(&JsonFormatter.format(&result))
+}
+
+fn flat_report(root: &Path) -> Value {
+    let result = compare_directories_flat(
+        &root.join("a"),
+        &root.join("b"),
+        &FlatComparisonOptions::default(),
+        None,
+    )
+    .unwrap();
+    parse(&FlatJsonFormatter.format(&result))
+}
+
+#[test]
+fn test_schemas_are_valid_json_schema() {
+    for schema in [json_report_schema(), flat_json_report_schema()] {
+        let schema = parse(&schema);
+        assert!(jsonschema::meta::is_valid(&schema), "{:#}", schema);
+        assert_eq!(
+            schema["properties"]["format_version"]["const"],
+            JSON_FORMAT_VERSION
+        );
+    }
+}
+
+#[test]
+fn test_hierarchy_report_matches_schema() {
+    let temp = setup_dirs();
+    let report = hierarchy_report(temp.path());
+    assert_eq!(report["modified"].as_array().unwrap().len(), 1);
+
+    let schema = parse(&json_report_schema());
+    assert_eq!(validation_errors(&schema, &report), Vec::<String>::new());
+}
+
+#[test]
+fn test_hierarchy_report_with_diff_locations_matches_schema() {
+    let temp = setup_dirs();
+    let result = compare_directories(
+        &temp.path().join("a"),
+        &temp.path().join("b"),
+        &FastHashStrategy::new(false),
+        None,
+    )
+    .unwrap();
+    let mut locations = locate_differences(&result, 4, 20);
+    locations.push((
+        PathBuf::from("gone.bin"),
+        Err(io::Error::new(io::ErrorKind::NotFound, "missing")),
+    ));
+    let report = parse(&JsonFormatter.format_with_diff_locations(
+        &result,
+        &SectionConfig::default(),
+        &locations,
+    ));
+    assert_eq!(report["diff_locations"].as_array().unwrap().len(), 2);
+
+    let schema = parse(&json_report_schema());
+    assert_eq!(validation_errors(&schema, &report), Vec::<String>::new());
+}
+
+#[test]
+fn test_flat_report_matches_schema() {
+    let temp = setup_dirs();
+    let report = flat_report(temp.path());
+    assert!(!report["groups"].as_array().unwrap().is_empty());
+
+    let schema = parse(&flat_json_report_schema());
+    assert_eq!(validation_errors(&schema, &report), Vec::<String>::new());
+}
+
+#[test]
+fn test_flat_report_with_chunk_dedupe_matches_schema() {
+    let temp = setup_dirs();
+    let options = FlatComparisonOptions {
+        chunk_dedupe: Some(ChunkDedupeOptions::default()),
+        ..Default::default()
+    };
+    let result = compare_directories_flat(
+        &temp.path().join("a"),
+        &temp.path().join("b"),
+        &options,
+        None,
+    )
+    .unwrap();
+    let report = parse(&FlatJsonFormatter.format(&result));
+    assert!(
+        !report["chunk_dedupe"]["top_files_a"]
+            .as_array()
+            .unwrap()
+            .is_empty()
+    );
+
+    let schema = parse(&flat_json_report_schema());
+    assert_eq!(validation_errors(&schema, &report), Vec::<String>::new());
+}
+
+#[test]
+fn test_schema_rejects_drifted_reports() {
+    let temp = setup_dirs();
+    let schema = parse(&json_report_schema());
+
+    let mut report = hierarchy_report(temp.path());
+    report["format_version"] = (JSON_FORMAT_VERSION + 1).into();
+    assert!(!validation_errors(&schema, &report).is_empty());
+
+    let mut report = hierarchy_report(temp.path());
+    report["a_only"][0]["mtime"] = 0.into();
+    assert!(!validation_errors(&schema, &report).is_empty());
+
+    let mut report = hierarchy_report(temp.path());
+    report["both"][0]["a"]["kind"] = "fifo".into();
+    assert!(!validation_errors(&schema, &report).is_empty());
+
+    let schema = parse(&flat_json_report_schema());
+    let mut report = flat_report(temp.path());
+    report.as_object_mut().unwrap().remove("unique_hashes");
+    assert!(!validation_errors(&schema, &report).is_empty());
+}
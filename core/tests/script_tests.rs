@@ -0,0 +1,169 @@
+use dir_compare_core::comparison::{
+    FlatComparisonOptions, FlatComparisonResult, compare_directories_flat,
+};
+use dir_compare_core::output::{FlatFormatter, FlatScriptFormatter, ScriptShell};
+use std::fs;
+use std::path::Path;
+use tempfile::TempDir;
+
+fn write(root: &Path, path: &str, contents: &str) {
+    let path = root.join(path);
+    fs::create_dir_all(path.parent().unwrap()).unwrap();
+    fs::write(path, contents).unwrap();
+}
+
+fn compare(root: &Path) -> FlatComparisonResult {
+    compare_directories_flat(
+        &root.join("a"),
+        &root.join("b"),
+        &FlatComparisonOptions::default(),
+        None,
+    )
+    .unwrap()
+}
+
+/// Lists the files below `dir` with their contents, sorted by path
+fn files(dir: &Path) -> Vec<(String, String)> {
+    let mut files = Vec::new();
+    let mut pending = vec![dir.to_path_buf()];
+    while let Some(current) = pending.pop() {
+        for entry in fs::read_dir(current).unwrap() {
+            let path = entry.unwrap().path();
+            if path.is_dir() {
+                pending.push(path);
+            } else {
+                let relative = path
+                    .strip_prefix(dir)
+                    .unwrap()
+                    .to_string_lossy()
+                    .into_owned();
+                files.push((relative, fs::read_to_string(&path).unwrap()));
+            }
+        }
+    }
+    files.sort();
+    files
+}
+
+/// A reorganized copy of a tree: renames into new directories, paths with
+/// quotes, spaces, a leading dash and non-ASCII characters, a chain of moves
+/// and two files that swap places.
+fn setup() -> TempDir {
+    let temp = TempDir::new().unwrap();
+    let (a, b) = (temp.path().join("a"), temp.path().join("b"));
+    for (from, to, contents) in [
+        ("report.txt", "docs/2024/report.txt", "quarterly report"),
+        ("it's here.txt", "quotes/it's \"here\" $HOME.txt", "quoted"),
+        ("café.txt", "ünï/café — copy.txt", "unicode"),
+        ("-dash.txt", "sub/-dash.txt", "dash"),
+        ("one", "two", "first of a chain"),
+        ("two", "three", "second of a chain"),
+        ("x.txt", "y.txt", "x"),
+        ("y.txt", "x.txt", "y"),
+        ("same.txt", "same.txt", "unchanged"),
+    ] {
+        write(&a, from, contents);
+        write(&b, to, contents);
+    }
+    temp
+}
+
+#[cfg(unix)]
+#[test]
+fn test_sh_script_moves_a_into_the_layout_of_b() {
+    let temp = setup();
+    let script = FlatScriptFormatter::default().format(&compare(temp.path()));
+    assert!(script.starts_with("#!/bin/sh\nset -e\n"), "{}", script);
+    assert!(!script.contains("same.txt"));
+    assert!(script.contains("mv -- 'it'\\''s here.txt' 'quotes/it'\\''s \"here\" $HOME.txt'\n"));
+
+    let path = temp.path().join("move.sh");
+    fs::write(&path, &script).unwrap();
+    let status = std::process::Command::new("sh")
+        .arg(&path)
+        .current_dir(temp.path().join("a"))
+        .status()
+        .unwrap();
+    assert!(status.success(), "{}", script);
+    assert_eq!(
+        files(&temp.path().join("a")),
+        files(&temp.path().join("b")),
+        "{}",
+        script
+    );
+}
+
+#[test]
+fn test_moves_are_ordered_around_chains_and_cycles() {
+    let temp = setup();
+    let script = FlatScriptFormatter::default().format(&compare(temp.path()));
+    let position = |line: &str| {
+        script
+            .find(line)
+            .unwrap_or_else(|| panic!("no '{}' in\n{}", line, script))
+    };
+    // `two` moves away before `one` takes its place
+    assert!(position("mv -- 'two' 'three'\n") < position("mv -- 'one' 'two'\n"));
+    // One of the swapped files goes through a temporary name
+    let parked = script.matches(".dir-compare-move'").count();
+    assert_eq!(parked, 2, "{}", script);
+    assert!(position("mkdir -p -- 'docs/2024'\n") < position("'docs/2024/report.txt'\n"));
+}
+
+#[test]
+fn test_unsafe_and_ambiguous_groups_become_comments() {
+    let temp = TempDir::new().unwrap();
+    let (a, b) = (temp.path().join("a"), temp.path().join("b"));
+    // Two copies in A of a file moved in B
+    write(&a, "photo.jpg", "photo");
+    write(&a, "photo (1).jpg", "photo");
+    write(&b, "2024/photo.jpg", "photo");
+    // A file moved onto a path whose file in A stays put
+    write(&a, "notes.txt", "notes");
+    write(&a, "todo.txt", "todo");
+    write(&b, "todo.txt", "notes");
+    // Which in turn blocks the move onto `notes.txt`
+    write(&a, "draft.txt", "draft");
+    write(&b, "notes.txt", "draft");
+
+    let script = FlatScriptFormatter::default().format(&compare(temp.path()));
+    assert!(!script.contains("\nmv "), "{}", script);
+    assert!(script.contains("# Not moved:\n"));
+    assert!(script.contains("#   \"notes.txt\" -> \"todo.txt\": target exists in A\n"));
+    assert!(script.contains("#   \"draft.txt\" -> \"notes.txt\": target exists in A\n"));
+    assert!(script.contains("# Ambiguous, several files with the same content:\n"));
+    assert!(script.contains("#     A \"photo (1).jpg\"\n"));
+    assert!(script.contains("#     B \"2024/photo.jpg\"\n"));
+}
+
+#[test]
+fn test_powershell_script() {
+    let temp = setup();
+    let formatter = FlatScriptFormatter {
+        shell: ScriptShell::PowerShell,
+    };
+    let script = formatter.format(&compare(temp.path()));
+    assert!(script.starts_with("$ErrorActionPreference = 'Stop'\n"));
+    assert!(script.contains("New-Item -ItemType Directory -Force -Path 'docs/2024' | Out-Null\n"));
+    assert!(script.contains(
+        "Move-Item -LiteralPath 'it''s here.txt' -Destination 'quotes/it''s \"here\" $HOME.txt'\n"
+    ));
+    assert!(!script.contains("mv --"));
+}
+
+#[test]
+fn test_quoting() {
+    assert_eq!(ScriptShell::Sh.quote("plain"), "'plain'");
+    assert_eq!(ScriptShell::Sh.quote("it's"), r"'it'\''s'");
+    assert_eq!(
+        ScriptShell::Sh.quote("$(rm -rf /)\n`x`"),
+        "'$(rm -rf /)\n`x`'"
+    );
+    assert_eq!(ScriptShell::PowerShell.quote("it's"), "'it''s'");
+    assert_eq!(
+        ScriptShell::PowerShell.quote("\u{2018}x\u{2019} $y"),
+        "'\u{2018}\u{2018}x\u{2019}\u{2019} $y'"
+    );
+    assert_eq!("pwsh".parse(), Ok(ScriptShell::PowerShell));
+    assert!("bash".parse::<ScriptShell>().is_err());
+}
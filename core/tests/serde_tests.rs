@@ -0,0 +1,198 @@
+use dir_compare_core::comparison::{
+    ComparisonResult, Entry, EntryKind, FastHashStrategy, FlatComparisonResult, WarningCause,
+};
+use dir_compare_core::{ChunkDedupeOptions, Comparison, ReportContext};
+use serde_json::{Value, json};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+use tempfile::TempDir;
+
+fn write(root: &Path, path: &str, contents: &str) {
+    let path = root.join(path);
+    fs::create_dir_all(path.parent().unwrap()).unwrap();
+    fs::write(path, contents).unwrap();
+}
+
+/// Trees with an entry in every category, a unicode name and, on Unix, a
+/// symlink
+fn setup() -> TempDir {
+    let temp = TempDir::new().unwrap();
+    let (a, b) = (temp.path().join("a"), temp.path().join("b"));
+    write(&a, "same.txt", "same");
+    write(&b, "same.txt", "same");
+    write(&a, "docs/changed.txt", "old");
+    write(&b, "docs/changed.txt", "new");
+    write(&a, "only_a/日本語.txt", "a");
+    write(&b, "only_b.txt", "b");
+    #[cfg(unix)]
+    std::os::unix::fs::symlink("same.txt", a.join("link")).unwrap();
+    temp
+}
+
+/// Serializes `value` to JSON, back, and to JSON again, checking both
+/// documents are equal
+fn round_trip<T: serde::Serialize + serde::de::DeserializeOwned>(value: &T) -> (T, Value) {
+    let json = serde_json::to_string(value).unwrap();
+    let decoded: T = serde_json::from_str(&json).unwrap();
+    let first: Value = serde_json::from_str(&json).unwrap();
+    assert_eq!(serde_json::to_value(&decoded).unwrap(), first);
+    (decoded, first)
+}
+
+fn paths(entries: &[Entry]) -> Vec<&Path> {
+    entries.iter().map(|entry| entry.path.as_path()).collect()
+}
+
+#[test]
+fn test_comparison_result_round_trip() {
+    let temp = setup();
+    let strategy = FastHashStrategy::new(false);
+    let mut result = Comparison::new(temp.path().join("a"), temp.path().join("b"))
+        .strategy(&strategy)
+        .run()
+        .unwrap();
+    result.context = Some(ReportContext {
+        dir_a: temp.path().join("a"),
+        dir_b: temp.path().join("b"),
+        strategy_name: "hash".to_string(),
+        options: vec!["case-insensitive".to_string()],
+        timestamp: SystemTime::UNIX_EPOCH + Duration::from_millis(1_700_000_000_123),
+    });
+
+    let (decoded, json): (ComparisonResult, Value) = round_trip(&result);
+    assert_eq!(paths(&decoded.a_only), paths(&result.a_only));
+    assert_eq!(paths(&decoded.b_only), paths(&result.b_only));
+    assert_eq!(decoded.both.len(), result.both.len());
+    assert_eq!(decoded.modified.len(), 1);
+    assert_eq!(
+        decoded.modified[0].1.path,
+        PathBuf::from("docs/changed.txt")
+    );
+    assert_eq!(decoded.stats, result.stats);
+    assert_eq!(decoded.context, result.context);
+    for (decoded, entry) in decoded.a_only.iter().zip(&result.a_only) {
+        assert_eq!(decoded.kind, entry.kind);
+        assert_eq!(decoded.size, entry.size);
+        assert_eq!(decoded.mtime, entry.mtime);
+    }
+
+    assert_eq!(json["modified"][0][0]["path"], "docs/changed.txt");
+    assert_eq!(json["modified"][0][0]["kind"], "file");
+    assert!(
+        json["a_only"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .any(|entry| entry["path"] == "only_a/日本語.txt")
+    );
+    assert_eq!(json["exact_counts"], Value::Null);
+    assert_eq!(
+        json["context"]["timestamp"]["nanos_since_epoch"],
+        123_000_000
+    );
+}
+
+#[test]
+fn test_entry_kind_serializes_as_lowercase() {
+    assert_eq!(serde_json::to_value(EntryKind::File).unwrap(), "file");
+    assert_eq!(
+        serde_json::to_value(EntryKind::Directory).unwrap(),
+        "directory"
+    );
+    let link = EntryKind::Symlink {
+        target: PathBuf::from("../shared/config.toml"),
+    };
+    assert_eq!(
+        serde_json::to_value(&link).unwrap(),
+        json!({"symlink": {"target": "../shared/config.toml"}})
+    );
+    assert_eq!(
+        serde_json::from_value::<EntryKind>(json!("directory")).unwrap(),
+        EntryKind::Directory
+    );
+    assert!(serde_json::from_value::<EntryKind>(json!("File")).is_err());
+    assert_eq!(
+        serde_json::to_value(WarningCause::ReadLink).unwrap(),
+        "read_link"
+    );
+}
+
+#[cfg(unix)]
+#[test]
+fn test_symlink_entry_round_trip() {
+    let temp = setup();
+    let strategy = FastHashStrategy::new(false);
+    let result = Comparison::new(temp.path().join("a"), temp.path().join("b"))
+        .strategy(&strategy)
+        .run()
+        .unwrap();
+    let (decoded, json) = round_trip(&result);
+    let link = decoded
+        .a_only
+        .iter()
+        .find(|entry| entry.path == Path::new("link"))
+        .unwrap();
+    assert_eq!(
+        link.kind,
+        EntryKind::Symlink {
+            target: PathBuf::from("same.txt")
+        }
+    );
+    assert!(json["a_only"].as_array().unwrap().contains(&json!({
+        "path": "link",
+        "abs_path": link.abs_path,
+        "kind": {"symlink": {"target": "same.txt"}},
+        "size": null,
+        "nested_repository": false,
+        "mtime": null,
+    })));
+}
+
+#[test]
+fn test_flat_result_round_trip() {
+    let temp = setup();
+    let result = Comparison::new(temp.path().join("a"), temp.path().join("b"))
+        .full_hash(true)
+        .chunk_dedupe(ChunkDedupeOptions::default())
+        .run_flat()
+        .unwrap();
+
+    let (decoded, json): (FlatComparisonResult, Value) = round_trip(&result);
+    assert_eq!(decoded.groups.len(), result.groups.len());
+    for (decoded, group) in decoded.groups.iter().zip(&result.groups) {
+        assert_eq!(decoded.hash, group.hash);
+        assert_eq!(decoded.files_in_a, group.files_in_a);
+        assert_eq!(decoded.files_in_b, group.files_in_b);
+    }
+    assert_eq!(decoded.total_files_a, result.total_files_a);
+    assert_eq!(decoded.duplicate_count, result.duplicate_count);
+    assert_eq!(decoded.chunk_dedupe, result.chunk_dedupe);
+    assert!(decoded.chunk_dedupe.is_some());
+    assert!(
+        json["groups"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .any(|group| group["files_in_a"] == json!(["same.txt"])
+                && group["files_in_b"] == json!(["same.txt"]))
+    );
+}
+
+#[cfg(unix)]
+#[test]
+fn test_non_utf8_paths_fail_to_serialize() {
+    use std::ffi::OsStr;
+    use std::os::unix::ffi::OsStrExt;
+
+    let path = PathBuf::from(OsStr::from_bytes(b"bad\xffname"));
+    let entry = Entry {
+        path: path.clone(),
+        abs_path: path,
+        kind: EntryKind::File,
+        size: Some(1),
+        nested_repository: false,
+        mtime: None,
+    };
+    assert!(serde_json::to_string(&entry).is_err());
+}
@@ -0,0 +1,205 @@
+use dir_compare_core::comparison::{
+    ComparisonOptions, ComparisonResult, ComparisonStrategy, Entry, FastHashStrategy,
+    FilenameOnlyStrategy, FilenameSizeStrategy, MetadataStrategy, SampledHashStrategy, StatMode,
+    compare_directories_with_options,
+};
+use dir_compare_core::filesystem::{FileSystem, RealFileSystem};
+use dir_compare_core::output::Section;
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// A filesystem counting the metadata calls made for each path
+#[derive(Default)]
+struct StatCounter {
+    calls: Mutex<HashMap<PathBuf, usize>>,
+}
+
+impl StatCounter {
+    fn calls(&self) -> HashMap<PathBuf, usize> {
+        self.calls.lock().unwrap().clone()
+    }
+
+    fn total(&self) -> usize {
+        self.calls().values().sum()
+    }
+}
+
+impl FileSystem for StatCounter {
+    fn canonicalize(&self, path: &Path) -> io::Result<PathBuf> {
+        RealFileSystem.canonicalize(path)
+    }
+
+    fn metadata(&self, path: &Path) -> io::Result<fs::Metadata> {
+        *self
+            .calls
+            .lock()
+            .unwrap()
+            .entry(path.to_path_buf())
+            .or_default() += 1;
+        RealFileSystem.metadata(path)
+    }
+}
+
+/// [`FilenameOnlyStrategy`] listing through a [`StatCounter`]
+struct CountedFilenameOnly(Arc<StatCounter>);
+
+impl ComparisonStrategy for CountedFilenameOnly {
+    fn matches(&self, a: &Entry, b: &Entry) -> bool {
+        FilenameOnlyStrategy::new(false).matches(a, b)
+    }
+
+    fn file_system(&self) -> &dyn FileSystem {
+        self.0.as_ref()
+    }
+}
+
+/// Two trees with a file on each side only, an identical, a resized and a
+/// same-size changed file, and a nested directory
+fn setup() -> tempfile::TempDir {
+    let temp = tempfile::tempdir().unwrap();
+    for side in ["dir_a", "dir_b"] {
+        let root = temp.path().join(side);
+        fs::create_dir_all(root.join("nested")).unwrap();
+        fs::write(root.join("nested/same.txt"), "same").unwrap();
+    }
+    let (a, b) = (temp.path().join("dir_a"), temp.path().join("dir_b"));
+    fs::write(a.join("only_a.txt"), "a").unwrap();
+    fs::write(b.join("only_b.txt"), "b").unwrap();
+    fs::write(a.join("resized.txt"), "short").unwrap();
+    fs::write(b.join("resized.txt"), "much longer").unwrap();
+    fs::write(a.join("edited.txt"), "1234").unwrap();
+    fs::write(b.join("edited.txt"), "abcd").unwrap();
+    temp
+}
+
+fn compare(
+    temp: &tempfile::TempDir,
+    strategy: &dyn ComparisonStrategy,
+    stat_mode: StatMode,
+) -> ComparisonResult {
+    let options = ComparisonOptions {
+        stat_mode,
+        ..Default::default()
+    };
+    compare_directories_with_options(
+        &temp.path().join("dir_a"),
+        &temp.path().join("dir_b"),
+        strategy,
+        None,
+        &options,
+    )
+    .unwrap()
+}
+
+fn categories(result: &ComparisonResult) -> Vec<Vec<String>> {
+    Section::ALL
+        .into_iter()
+        .map(|section| result.paths(section))
+        .collect()
+}
+
+#[test]
+fn test_eager_mode_stats_each_file_once() {
+    let temp = setup();
+    for mtime in [false, true] {
+        let counter = Arc::new(StatCounter::default());
+        let strategy: Box<dyn ComparisonStrategy> = if mtime {
+            Box::new(
+                MetadataStrategy::new(false, Duration::from_secs(3600))
+                    .with_file_system(counter.clone()),
+            )
+        } else {
+            Box::new(FilenameSizeStrategy::new(false).with_file_system(counter.clone()))
+        };
+        let result = compare(&temp, strategy.as_ref(), StatMode::Eager);
+
+        // Four files in A and four in B; directories are not stat'ed
+        let calls = counter.calls();
+        assert_eq!(calls.len(), 8);
+        assert!(calls.values().all(|&count| count == 1), "{:?}", calls);
+        assert!(calls.keys().all(|path| path.is_file()));
+        assert_eq!(result.paths(Section::Modified), ["resized.txt"]);
+        assert_eq!(result.a_only[0].size, Some(1));
+        assert!(result.a_only[0].mtime.is_some());
+    }
+}
+
+#[test]
+fn test_lazy_mode_skips_metadata_for_filename_only() {
+    let temp = setup();
+    let counter = Arc::new(StatCounter::default());
+    let result = compare(&temp, &CountedFilenameOnly(counter.clone()), StatMode::Lazy);
+
+    assert_eq!(counter.total(), 0);
+    assert_eq!(result.paths(Section::AOnly), ["only_a.txt"]);
+    assert!(result.a_only[0].size.is_none());
+
+    let eager = compare(
+        &temp,
+        &CountedFilenameOnly(counter.clone()),
+        StatMode::Eager,
+    );
+    assert_eq!(categories(&result), categories(&eager));
+    assert_eq!(counter.total(), 8);
+}
+
+#[test]
+fn test_lazy_mode_stats_only_compared_pairs() {
+    let temp = setup();
+    let counter = Arc::new(StatCounter::default());
+    let strategy = FilenameSizeStrategy::new(false).with_file_system(counter.clone());
+    let result = compare(&temp, &strategy, StatMode::Lazy);
+
+    let calls = counter.calls();
+    let mut names: Vec<_> = calls
+        .keys()
+        .map(|path| path.file_name().unwrap().to_str().unwrap())
+        .collect();
+    names.sort();
+    assert_eq!(
+        names,
+        [
+            "edited.txt",
+            "edited.txt",
+            "resized.txt",
+            "resized.txt",
+            "same.txt",
+            "same.txt"
+        ]
+    );
+    assert_eq!(result.paths(Section::Modified), ["resized.txt"]);
+}
+
+#[test]
+fn test_stat_modes_agree_for_every_strategy() {
+    let temp = setup();
+    let strategies: Vec<Box<dyn ComparisonStrategy>> = vec![
+        Box::new(FilenameOnlyStrategy::new(false)),
+        Box::new(FilenameSizeStrategy::new(false)),
+        Box::new(FastHashStrategy::new(false)),
+        Box::new(SampledHashStrategy::new(false, true)),
+        Box::new(MetadataStrategy::new(false, Duration::from_secs(3600))),
+    ];
+    for strategy in &strategies {
+        let eager = compare(&temp, strategy.as_ref(), StatMode::Eager);
+        let lazy = compare(&temp, strategy.as_ref(), StatMode::Lazy);
+        assert_eq!(categories(&eager), categories(&lazy));
+        assert!(lazy.warnings.is_empty());
+    }
+}
+
+#[test]
+fn test_stat_mode_from_str() {
+    assert_eq!("lazy".parse::<StatMode>(), Ok(StatMode::Lazy));
+    assert_eq!(" Eager ".parse::<StatMode>(), Ok(StatMode::Eager));
+    assert!(
+        "never"
+            .parse::<StatMode>()
+            .unwrap_err()
+            .contains("eager or lazy")
+    );
+}
@@ -0,0 +1,186 @@
+use dir_compare_core::comparison::{
+    ComparisonOptions, ComparisonStats, FastHashStrategy, FilenameOnlyStrategy,
+    FlatComparisonOptions, SampledHashStrategy, compare_directories, compare_directories_flat,
+    compare_directories_with_options,
+};
+use dir_compare_core::output::{
+    FlatFormatter, FlatJsonFormatter, Formatter, HtmlFormatter, JsonFormatter, Labels,
+    MarkdownFormatter, TextFormatter, format_elapsed,
+};
+use std::fs;
+use std::time::Duration;
+use tempfile::TempDir;
+
+/// A holds `same.txt` (5 bytes), `docs/a.txt` (3 bytes) and `docs/`; B holds
+/// `same.txt` (5 bytes) and `other/b.txt` (4 bytes) in `other/`
+fn setup() -> TempDir {
+    let temp = TempDir::new().unwrap();
+    let (a, b) = (temp.path().join("a"), temp.path().join("b"));
+    fs::create_dir_all(a.join("docs")).unwrap();
+    fs::create_dir_all(b.join("other")).unwrap();
+    fs::write(a.join("same.txt"), "hello").unwrap();
+    fs::write(a.join("docs/a.txt"), "abc").unwrap();
+    fs::write(b.join("same.txt"), "hello").unwrap();
+    fs::write(b.join("other/b.txt"), "abcd").unwrap();
+    temp
+}
+
+fn sample_stats() -> ComparisonStats {
+    ComparisonStats {
+        files_a: 1234,
+        files_b: 1200,
+        dirs_a: 10,
+        dirs_b: 9,
+        bytes_a: 3 * 1024 * 1024,
+        bytes_b: 2048,
+        bytes_hashed: 4096,
+        elapsed: Duration::from_millis(1250),
+        sanitized_paths: 0,
+    }
+}
+
+#[test]
+fn test_hierarchy_stats_count_traversal() {
+    let temp = setup();
+    let result = compare_directories(
+        &temp.path().join("a"),
+        &temp.path().join("b"),
+        &FilenameOnlyStrategy::new(false),
+        None,
+    )
+    .unwrap();
+
+    let stats = result.stats;
+    assert_eq!((stats.files_a, stats.dirs_a, stats.bytes_a), (2, 1, 8));
+    assert_eq!((stats.files_b, stats.dirs_b, stats.bytes_b), (2, 1, 9));
+    // Names alone need no content
+    assert_eq!(stats.bytes_hashed, 0);
+}
+
+#[test]
+fn test_hierarchy_stats_count_hashed_bytes() {
+    let temp = setup();
+    for threads in [1, 4] {
+        let options = ComparisonOptions {
+            threads: Some(threads),
+            ..Default::default()
+        };
+        let result = compare_directories_with_options(
+            &temp.path().join("a"),
+            &temp.path().join("b"),
+            &FastHashStrategy::new(false),
+            None,
+            &options,
+        )
+        .unwrap();
+        // Only `same.txt` exists on both sides
+        assert_eq!(result.stats.bytes_hashed, 10, "{} threads", threads);
+    }
+
+    // Verification hashes the matching files a second time
+    let result = compare_directories(
+        &temp.path().join("a"),
+        &temp.path().join("b"),
+        &SampledHashStrategy::new(false, true),
+        None,
+    )
+    .unwrap();
+    assert_eq!(result.stats.bytes_hashed, 20);
+}
+
+#[test]
+fn test_flat_stats() {
+    let temp = setup();
+    let result = compare_directories_flat(
+        &temp.path().join("a"),
+        &temp.path().join("b"),
+        &FlatComparisonOptions::default(),
+        None,
+    )
+    .unwrap();
+
+    let stats = result.stats;
+    assert_eq!((stats.files_a, stats.dirs_a, stats.bytes_a), (2, 1, 8));
+    assert_eq!((stats.files_b, stats.dirs_b, stats.bytes_b), (2, 1, 9));
+    assert_eq!(stats.bytes_hashed, 17);
+}
+
+#[test]
+fn test_swap_sides_swaps_stats() {
+    let temp = setup();
+    let mut result = compare_directories(
+        &temp.path().join("a"),
+        &temp.path().join("b"),
+        &FilenameOnlyStrategy::new(false),
+        None,
+    )
+    .unwrap();
+    result.swap_sides();
+    assert_eq!((result.stats.bytes_a, result.stats.bytes_b), (9, 8));
+}
+
+#[test]
+fn test_format_elapsed() {
+    assert_eq!(format_elapsed(Duration::ZERO), "0.00s");
+    assert_eq!(format_elapsed(Duration::from_millis(1250)), "1.25s");
+    assert_eq!(format_elapsed(Duration::from_secs(125)), "2m 05s");
+}
+
+#[test]
+fn test_formatters_render_stats() {
+    let temp = setup();
+    let mut result = compare_directories(
+        &temp.path().join("a"),
+        &temp.path().join("b"),
+        &FilenameOnlyStrategy::new(false),
+        None,
+    )
+    .unwrap();
+    result.stats = sample_stats();
+    let footer = "A: 1,234 files, 10 directories, 3.0 MiB; \
+                  B: 1,200 files, 9 directories, 2.0 KiB; 4.0 KiB hashed in 1.25s";
+    assert_eq!(Labels::default().stats(&result.stats), footer);
+
+    let text = TextFormatter.format(&result);
+    assert!(text.ends_with(&format!("\n\n{}\n", footer)), "{}", text);
+    let markdown = MarkdownFormatter.format(&result);
+    assert!(
+        markdown.ends_with(&format!("---\n\n{}\n", footer)),
+        "{}",
+        markdown
+    );
+    let html = HtmlFormatter.format(&result);
+    assert!(
+        html.contains(&format!(r#"<p class="stats">{}</p>"#, footer)),
+        "{}",
+        html
+    );
+
+    let json: serde_json::Value = serde_json::from_str(&JsonFormatter.format(&result)).unwrap();
+    assert_eq!(
+        json["stats"],
+        serde_json::json!({
+            "files_a": 1234, "files_b": 1200, "dirs_a": 10, "dirs_b": 9,
+            "bytes_a": 3 * 1024 * 1024, "bytes_b": 2048, "bytes_hashed": 4096,
+            "elapsed_ms": 1250
+        })
+    );
+}
+
+#[test]
+fn test_flat_formatters_render_stats() {
+    let temp = setup();
+    let result = compare_directories_flat(
+        &temp.path().join("a"),
+        &temp.path().join("b"),
+        &FlatComparisonOptions::default(),
+        None,
+    )
+    .unwrap();
+
+    let json: serde_json::Value = serde_json::from_str(&FlatJsonFormatter.format(&result)).unwrap();
+    assert_eq!(json["stats"]["files_a"], 2);
+    assert_eq!(json["stats"]["bytes_hashed"], 17);
+    let footer = Labels::default().stats(&result.stats);
+    assert!(footer.contains("17 B hashed in"), "{}", footer);
+}
@@ -0,0 +1,189 @@
+use dir_compare_core::comparison::{ComparisonResult, ComparisonStrategy, compare_directories};
+use dir_compare_core::strategy_defaults::{self, StrategySettings, method_from_name, method_name};
+use dir_compare_core::{ComparisonStrategyType, OptionValue, OptionValues};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+use tempfile::TempDir;
+
+/// `dir-compare --method` names of each method, as the GUI's "Copy command"
+/// writes them
+const CLI_NAMES: [(ComparisonStrategyType, &str); 6] = [
+    (ComparisonStrategyType::Filename, "filename"),
+    (ComparisonStrategyType::FilenameSize, "size"),
+    (ComparisonStrategyType::FastHash, "hash"),
+    (ComparisonStrategyType::Blake3, "blake3"),
+    (ComparisonStrategyType::SampledHash, "sampled"),
+    (ComparisonStrategyType::Mtime, "mtime"),
+];
+
+/// A fixture on which a change to any default changes some result:
+/// - `sampled.bin` differs only between the samples, so it only matches
+///   without verification
+/// - `touched.txt` has other contents, the same size and a modification time
+///   one second later, so it only matches within the default mtime tolerance
+/// - `resized.txt` differs in size
+/// - `Photo.JPG` and `photo.jpg` only match case-insensitively
+fn setup() -> TempDir {
+    let temp = TempDir::new().unwrap();
+    let (a, b) = (temp.path().join("a"), temp.path().join("b"));
+    fs::create_dir_all(&a).unwrap();
+    fs::create_dir_all(&b).unwrap();
+
+    let mut content = vec![b'x'; 5000];
+    fs::write(a.join("sampled.bin"), &content).unwrap();
+    content[500] = b'y';
+    fs::write(b.join("sampled.bin"), &content).unwrap();
+
+    fs::write(a.join("touched.txt"), "first").unwrap();
+    fs::write(b.join("touched.txt"), "again").unwrap();
+    let epoch = SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+    for (dir, offset) in [(&a, 0), (&b, 1)] {
+        fs::File::open(dir.join("touched.txt"))
+            .unwrap()
+            .set_modified(epoch + Duration::from_secs(offset))
+            .unwrap();
+    }
+
+    fs::write(a.join("resized.txt"), "short").unwrap();
+    fs::write(b.join("resized.txt"), "much longer").unwrap();
+    fs::write(a.join("Photo.JPG"), "photo").unwrap();
+    fs::write(b.join("photo.jpg"), "photo").unwrap();
+    temp
+}
+
+/// The paths of each category, sorted, for comparing results
+fn categories(result: &ComparisonResult) -> [Vec<PathBuf>; 4] {
+    let paths = |entries: Vec<&Path>| {
+        let mut paths: Vec<PathBuf> = entries.into_iter().map(Path::to_path_buf).collect();
+        paths.sort();
+        paths
+    };
+    [
+        paths(result.a_only.iter().map(|e| e.path.as_path()).collect()),
+        paths(result.b_only.iter().map(|e| e.path.as_path()).collect()),
+        paths(result.both.iter().map(|(a, _)| a.path.as_path()).collect()),
+        paths(
+            result
+                .modified
+                .iter()
+                .map(|(a, _)| a.path.as_path())
+                .collect(),
+        ),
+    ]
+}
+
+fn compare(root: &Path, strategy: &dyn ComparisonStrategy) -> [Vec<PathBuf>; 4] {
+    let result = compare_directories(&root.join("a"), &root.join("b"), strategy, None).unwrap();
+    categories(&result)
+}
+
+/// The strategy `dir-compare --method <name>` builds without other flags
+fn cli_strategy(root: &Path, name: &str) -> Box<dyn ComparisonStrategy + Send> {
+    let method = method_from_name(name).unwrap();
+    let case_insensitive =
+        strategy_defaults::case_insensitive(None, &root.join("a"), &root.join("b"));
+    StrategySettings::new(method, case_insensitive).build()
+}
+
+/// The strategy a new GUI tab builds for `method`
+fn gui_strategy(root: &Path, method: ComparisonStrategyType) -> Box<dyn ComparisonStrategy + Send> {
+    let case_insensitive =
+        strategy_defaults::case_insensitive(None, &root.join("a"), &root.join("b"));
+    method
+        .build(case_insensitive, &method.default_options())
+        .unwrap()
+}
+
+#[test]
+fn test_cli_and_gui_defaults_agree_for_every_method() {
+    let temp = setup();
+    assert_eq!(CLI_NAMES.len(), ComparisonStrategyType::ALL.len());
+    for (method, name) in CLI_NAMES {
+        let cli = compare(temp.path(), cli_strategy(temp.path(), name).as_ref());
+        let gui = compare(temp.path(), gui_strategy(temp.path(), method).as_ref());
+        assert_eq!(cli, gui, "{:?}", method);
+    }
+}
+
+#[test]
+fn test_fixture_catches_changed_defaults() {
+    let temp = setup();
+    let root = temp.path();
+    let sampled = |verify: bool| {
+        let values: OptionValues = [("verify".to_string(), OptionValue::Bool(verify))].into();
+        let strategy = ComparisonStrategyType::SampledHash
+            .build(false, &values)
+            .unwrap();
+        compare(root, strategy.as_ref())
+    };
+    assert_ne!(sampled(true), sampled(false));
+    assert_eq!(
+        compare(root, cli_strategy(root, "sampled").as_ref()),
+        sampled(false)
+    );
+
+    let mtime = |secs: i64| {
+        let values: OptionValues = [("tolerance_secs".to_string(), OptionValue::Int(secs))].into();
+        let strategy = ComparisonStrategyType::Mtime.build(false, &values).unwrap();
+        compare(root, strategy.as_ref())
+    };
+    assert_ne!(mtime(0), mtime(1));
+    assert_eq!(
+        compare(root, cli_strategy(root, "mtime").as_ref()),
+        mtime(1)
+    );
+
+    let filename = |case_insensitive: bool| {
+        let settings = StrategySettings::new(ComparisonStrategyType::Filename, case_insensitive);
+        compare(root, settings.build().as_ref())
+    };
+    assert_ne!(filename(true), filename(false));
+}
+
+#[test]
+fn test_method_names() {
+    for (method, name) in CLI_NAMES {
+        assert_eq!(method_from_name(name), Some(method));
+        assert_eq!(method_name(method), name);
+        assert_eq!(method_from_name(&name.to_uppercase()), Some(method));
+    }
+    assert_eq!(
+        method_from_name("metadata"),
+        Some(ComparisonStrategyType::Mtime)
+    );
+    assert_eq!(method_from_name("crc32"), None);
+}
+
+#[test]
+fn test_report_context_names_method_and_options() {
+    let temp = setup();
+    let (a, b) = (temp.path().join("a"), temp.path().join("b"));
+    let before = SystemTime::now();
+    let context =
+        StrategySettings::new(ComparisonStrategyType::FastHash, false).report_context(&a, &b);
+    assert_eq!(
+        (context.dir_a.as_path(), context.dir_b.as_path()),
+        (a.as_path(), b.as_path())
+    );
+    assert_eq!(context.method(), "hash");
+    assert!(context.timestamp >= before);
+
+    let mut settings = StrategySettings::new(ComparisonStrategyType::SampledHash, true);
+    settings.verify = true;
+    settings.distrust_sizes = true;
+    let context = settings.report_context(&a, &b);
+    assert_eq!(
+        context.method(),
+        "sampled (case-insensitive, verify, distrust sizes)"
+    );
+
+    // Relative directories are made absolute
+    let values: OptionValues = [("tolerance_secs".to_string(), OptionValue::Int(5))].into();
+    let settings = ComparisonStrategyType::Mtime
+        .settings(false, &values)
+        .unwrap();
+    let context = settings.report_context(Path::new("a"), Path::new("b"));
+    assert_eq!(context.method(), "mtime (tolerance 5s)");
+    assert_eq!(context.dir_a, std::env::current_dir().unwrap().join("a"));
+}
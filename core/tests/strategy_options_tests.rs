@@ -0,0 +1,112 @@
+use dir_compare_core::{
+    ComparisonStrategyType, OptionKind, OptionValue, OptionValues, assert_result_matches,
+    compare_directories,
+};
+use std::fs;
+
+fn values(pairs: &[(&str, OptionValue)]) -> OptionValues {
+    pairs
+        .iter()
+        .map(|(name, value)| (name.to_string(), value.clone()))
+        .collect()
+}
+
+#[test]
+fn test_option_descriptors_per_strategy() {
+    for method in [
+        ComparisonStrategyType::Filename,
+        ComparisonStrategyType::FilenameSize,
+        ComparisonStrategyType::FastHash,
+        ComparisonStrategyType::Blake3,
+    ] {
+        assert!(method.options().is_empty(), "{:?}", method);
+    }
+
+    let sampled = ComparisonStrategyType::SampledHash.options();
+    assert_eq!(sampled.len(), 1);
+    assert_eq!(sampled[0].name, "verify");
+    assert_eq!(sampled[0].kind, OptionKind::Bool);
+    assert_eq!(sampled[0].default, OptionValue::Bool(false));
+
+    let mtime = ComparisonStrategyType::Mtime.options();
+    assert_eq!(mtime.len(), 1);
+    assert_eq!(mtime[0].name, "tolerance_secs");
+    assert_eq!(mtime[0].kind, OptionKind::Int { min: 0, max: 86400 });
+    assert_eq!(mtime[0].default, OptionValue::Int(1));
+}
+
+#[test]
+fn test_descriptors_are_well_formed() {
+    for method in ComparisonStrategyType::ALL {
+        for option in method.options() {
+            assert!(!option.label.is_empty() && !option.help.is_empty());
+            assert_eq!(option.validate(&option.default), Ok(()), "{}", option.name);
+        }
+        assert!(method.build(false, &method.default_options()).is_ok());
+        assert!(method.build(true, &OptionValues::new()).is_ok());
+    }
+}
+
+#[test]
+fn test_build_rejects_invalid_values() {
+    let Err(err) = ComparisonStrategyType::Mtime
+        .build(false, &values(&[("tolerance_secs", OptionValue::Int(-1))]))
+    else {
+        panic!("negative tolerance accepted");
+    };
+    assert_eq!(err.option, "tolerance_secs");
+    assert!(err.to_string().contains("outside 0..=86400"), "{}", err);
+
+    assert!(
+        ComparisonStrategyType::Mtime
+            .build(
+                false,
+                &values(&[("tolerance_secs", OptionValue::Int(86401))])
+            )
+            .is_err()
+    );
+    assert!(
+        ComparisonStrategyType::SampledHash
+            .build(false, &values(&[("verify", OptionValue::Int(1))]))
+            .is_err()
+    );
+
+    let Err(err) = ComparisonStrategyType::FastHash
+        .build(false, &values(&[("verify", OptionValue::Bool(true))]))
+    else {
+        panic!("unknown option accepted");
+    };
+    assert_eq!(err.option, "verify");
+}
+
+#[test]
+fn test_verify_option_changes_results() {
+    // Differs only in the gap between the first and second sample, see
+    // test_sampled_hash_strategy
+    let temp = tempfile::tempdir().unwrap();
+    let (a, b) = (temp.path().join("a"), temp.path().join("b"));
+    fs::create_dir_all(&a).unwrap();
+    fs::create_dir_all(&b).unwrap();
+    let mut content = vec![b'x'; 5000];
+    fs::write(a.join("large.bin"), &content).unwrap();
+    content[500] = b'y';
+    fs::write(b.join("large.bin"), &content).unwrap();
+
+    let method = ComparisonStrategyType::SampledHash;
+    let sampled = method
+        .build(false, &values(&[("verify", OptionValue::Bool(false))]))
+        .unwrap();
+    let result = compare_directories(&a, &b, sampled.as_ref(), None).unwrap();
+    assert_result_matches!(result, both: ["large.bin"], modified: []);
+
+    let verified = method
+        .build(false, &values(&[("verify", OptionValue::Bool(true))]))
+        .unwrap();
+    let result = compare_directories(&a, &b, verified.as_ref(), None).unwrap();
+    assert_result_matches!(result, both: [], modified: ["large.bin"]);
+
+    // Like the CLI, the default does not verify
+    let default = method.build(false, &method.default_options()).unwrap();
+    let result = compare_directories(&a, &b, default.as_ref(), None).unwrap();
+    assert_result_matches!(result, both: ["large.bin"], modified: []);
+}
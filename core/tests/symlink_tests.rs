@@ -0,0 +1,115 @@
+#![cfg(unix)]
+
+use dir_compare_core::comparison::{
+    ComparisonStrategy, FastHashStrategy, FilenameSizeStrategy, SampledHashStrategy,
+    compare_directories, traverse_directory,
+};
+use dir_compare_core::{EntryKind, Formatter, assert_result_matches, output::TextFormatter};
+use std::fs;
+use std::os::unix::fs::symlink;
+use std::path::{Path, PathBuf};
+
+/// Creates dir_a and dir_b, each holding `link` pointing at the given target
+fn setup_links(target_a: &str, target_b: &str) -> tempfile::TempDir {
+    let temp = tempfile::tempdir().unwrap();
+    for (side, target) in [("dir_a", target_a), ("dir_b", target_b)] {
+        let dir = temp.path().join(side);
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("one.txt"), "one").unwrap();
+        fs::write(dir.join("two.txt"), "two").unwrap();
+        symlink(target, dir.join("link")).unwrap();
+    }
+    temp
+}
+
+fn strategies() -> Vec<Box<dyn ComparisonStrategy>> {
+    vec![
+        Box::new(FilenameSizeStrategy::new(false)),
+        Box::new(FastHashStrategy::new(false)),
+        Box::new(SampledHashStrategy::new(false, true)),
+    ]
+}
+
+fn compare(root: &Path, strategy: &dyn ComparisonStrategy) -> dir_compare_core::ComparisonResult {
+    compare_directories(&root.join("dir_a"), &root.join("dir_b"), strategy, None).unwrap()
+}
+
+#[test]
+fn test_traverse_records_symlink_target() {
+    let temp = setup_links("one.txt", "one.txt");
+
+    let entries = traverse_directory(&temp.path().join("dir_a"), None).unwrap();
+    let link = entries
+        .iter()
+        .find(|e| e.path.ends_with("link"))
+        .expect("symlink should be listed");
+    assert_eq!(
+        link.kind,
+        EntryKind::Symlink {
+            target: PathBuf::from("one.txt")
+        }
+    );
+    assert_eq!(link.size, None);
+}
+
+#[test]
+fn test_symlinks_with_matching_targets_match() {
+    let temp = setup_links("one.txt", "one.txt");
+
+    for strategy in strategies() {
+        let result = compare(temp.path(), strategy.as_ref());
+        assert_result_matches!(result, both: ["link", "one.txt", "two.txt"], modified: []);
+    }
+}
+
+#[test]
+fn test_symlinks_with_different_targets_are_modified() {
+    // Both targets have the same size, so only the target tells them apart
+    let temp = setup_links("one.txt", "two.txt");
+
+    for strategy in strategies() {
+        let result = compare(temp.path(), strategy.as_ref());
+        assert_eq!(result.modified.len(), 1);
+        let (a, b) = &result.modified[0];
+        assert_eq!(a.path, Path::new("link"));
+        assert_eq!(
+            (&a.kind, &b.kind),
+            (
+                &EntryKind::Symlink {
+                    target: PathBuf::from("one.txt")
+                },
+                &EntryKind::Symlink {
+                    target: PathBuf::from("two.txt")
+                }
+            )
+        );
+    }
+}
+
+#[test]
+fn test_dangling_symlinks_compare_by_target() {
+    let temp = setup_links("missing.txt", "missing.txt");
+
+    for strategy in strategies() {
+        let result = compare(temp.path(), strategy.as_ref());
+        assert!(result.modified.is_empty());
+        assert_eq!(result.both.len(), 3);
+    }
+
+    let result = compare(temp.path(), &FastHashStrategy::new(false));
+    let text = TextFormatter.format(&result);
+    assert!(text.contains("link -> missing.txt == link -> missing.txt"));
+    assert!(!text.contains("ERROR"));
+}
+
+#[test]
+fn test_symlink_and_file_at_same_path_do_not_match() {
+    let temp = setup_links("one.txt", "one.txt");
+    let link_b = temp.path().join("dir_b/link");
+    fs::remove_file(&link_b).unwrap();
+    fs::write(&link_b, "one").unwrap();
+
+    let result = compare(temp.path(), &FastHashStrategy::new(false));
+    assert_eq!(result.modified.len(), 1);
+    assert_eq!(result.modified[0].1.kind, EntryKind::File);
+}
@@ -0,0 +1,306 @@
+use dir_compare_core::comparison::{ComparisonResult, FastHashStrategy, compare_directories};
+use dir_compare_core::filesystem::{FileSystem, RealFileSystem};
+use dir_compare_core::sync::{SyncDirection, SyncOptions, apply_sync, plan_sync};
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::time::{Duration, SystemTime};
+use tempfile::TempDir;
+
+fn write(root: &Path, path: &str, contents: &str) {
+    let path = root.join(path);
+    fs::create_dir_all(path.parent().unwrap()).unwrap();
+    fs::write(path, contents).unwrap();
+}
+
+fn set_mtime(root: &Path, path: &str, seconds: u64) {
+    fs::File::open(root.join(path))
+        .unwrap()
+        .set_modified(SystemTime::UNIX_EPOCH + Duration::from_secs(seconds))
+        .unwrap();
+}
+
+fn mtime(root: &Path, path: &str) -> SystemTime {
+    fs::metadata(root.join(path)).unwrap().modified().unwrap()
+}
+
+fn compare(root: &Path) -> ComparisonResult {
+    compare_directories(
+        &root.join("a"),
+        &root.join("b"),
+        &FastHashStrategy::new(false),
+        None,
+    )
+    .unwrap()
+}
+
+fn options(root: &Path) -> SyncOptions {
+    SyncOptions::new(root.join("a"), root.join("b"))
+}
+
+/// Lists every entry below `dir` with its contents (`None` for directories)
+/// and modification time, sorted by path
+fn snapshot(dir: &Path) -> Vec<(String, Option<String>, SystemTime)> {
+    let mut entries = Vec::new();
+    let mut pending = vec![dir.to_path_buf()];
+    while let Some(current) = pending.pop() {
+        for entry in fs::read_dir(current).unwrap() {
+            let path = entry.unwrap().path();
+            let relative = path
+                .strip_prefix(dir)
+                .unwrap()
+                .to_string_lossy()
+                .into_owned();
+            let modified = fs::metadata(&path).unwrap().modified().unwrap();
+            if path.is_dir() {
+                entries.push((relative, None, modified));
+                pending.push(path);
+            } else {
+                entries.push((relative, Some(fs::read_to_string(&path).unwrap()), modified));
+            }
+        }
+    }
+    entries.sort();
+    entries
+}
+
+/// A has a nested A-only tree and a newer `shared.txt`; B has a nested
+/// B-only tree and an older `shared.txt`; `same.txt` matches
+fn setup() -> TempDir {
+    let temp = TempDir::new().unwrap();
+    let (a, b) = (temp.path().join("a"), temp.path().join("b"));
+    write(&a, "docs/2024/q1/report.txt", "report");
+    write(&a, "docs/2024/q1/notes.txt", "notes");
+    write(&b, "old/cache/deep/blob.bin", "blob");
+    write(&b, "old/index.txt", "index");
+    write(&a, "shared.txt", "new version");
+    write(&b, "shared.txt", "old version");
+    write(&a, "same.txt", "same");
+    write(&b, "same.txt", "same");
+    set_mtime(&a, "docs/2024/q1/report.txt", 1_000_000);
+    set_mtime(&a, "shared.txt", 2_000_000);
+    set_mtime(&b, "shared.txt", 1_500_000);
+    temp
+}
+
+#[test]
+fn test_a_to_b_handles_nested_directories() {
+    let temp = setup();
+    let result = compare(temp.path());
+    let actions = plan_sync(&result, SyncDirection::AToB);
+    let described: Vec<String> = actions.iter().map(|action| action.to_string()).collect();
+    assert_eq!(
+        described,
+        [
+            "copy docs to B",
+            "copy docs/2024 to B",
+            "copy docs/2024/q1 to B",
+            "copy docs/2024/q1/notes.txt to B",
+            "copy docs/2024/q1/report.txt to B",
+            "overwrite shared.txt in B",
+            "delete old/index.txt from B",
+            "delete old/cache/deep/blob.bin from B",
+            "delete old/cache/deep from B",
+            "delete old/cache from B",
+            "delete old from B",
+        ]
+    );
+
+    let report = apply_sync(&RealFileSystem, &actions, &options(temp.path()));
+    assert_eq!(report.failed().count(), 0, "{}", report.summary());
+    assert_eq!(report.summary(), "11 applied, 0 failed\n");
+    let (a, b) = (temp.path().join("a"), temp.path().join("b"));
+    assert!(!b.join("old").exists());
+    assert_eq!(
+        fs::read_to_string(b.join("shared.txt")).unwrap(),
+        "new version"
+    );
+    // Copied files keep the modification time of their source
+    assert_eq!(
+        mtime(&b, "docs/2024/q1/report.txt"),
+        mtime(&a, "docs/2024/q1/report.txt")
+    );
+    assert_eq!(mtime(&b, "shared.txt"), mtime(&a, "shared.txt"));
+    assert!(!compare(temp.path()).has_differences());
+}
+
+#[test]
+fn test_b_to_a_and_two_way() {
+    let temp = setup();
+    let actions = plan_sync(&compare(temp.path()), SyncDirection::BToA);
+    let report = apply_sync(&RealFileSystem, &actions, &options(temp.path()));
+    assert_eq!(report.failed().count(), 0, "{}", report.summary());
+    let a = temp.path().join("a");
+    assert!(!a.join("docs").exists());
+    assert_eq!(
+        fs::read_to_string(a.join("old/cache/deep/blob.bin")).unwrap(),
+        "blob"
+    );
+    assert_eq!(
+        fs::read_to_string(a.join("shared.txt")).unwrap(),
+        "old version"
+    );
+    assert!(!compare(temp.path()).has_differences());
+
+    let temp = setup();
+    let actions = plan_sync(&compare(temp.path()), SyncDirection::TwoWay);
+    assert!(
+        !actions
+            .iter()
+            .any(|action| action.to_string().starts_with("delete"))
+    );
+    let report = apply_sync(&RealFileSystem, &actions, &options(temp.path()));
+    assert_eq!(report.failed().count(), 0, "{}", report.summary());
+    let (a, b) = (temp.path().join("a"), temp.path().join("b"));
+    // The newer file wins, and each side gains what the other had
+    assert_eq!(
+        fs::read_to_string(b.join("shared.txt")).unwrap(),
+        "new version"
+    );
+    assert!(a.join("old/cache/deep/blob.bin").exists());
+    assert!(b.join("docs/2024/q1/notes.txt").exists());
+    assert!(!compare(temp.path()).has_differences());
+}
+
+#[test]
+fn test_two_way_leaves_out_pairs_without_a_newer_side() {
+    let temp = setup();
+    let (a, b) = (temp.path().join("a"), temp.path().join("b"));
+    set_mtime(&b, "shared.txt", 2_000_000);
+    let actions = plan_sync(&compare(temp.path()), SyncDirection::TwoWay);
+    assert!(
+        !actions
+            .iter()
+            .any(|action| action.to_string().starts_with("overwrite"))
+    );
+    apply_sync(&RealFileSystem, &actions, &options(temp.path()));
+    assert_eq!(
+        fs::read_to_string(a.join("shared.txt")).unwrap(),
+        "new version"
+    );
+    assert_eq!(
+        fs::read_to_string(b.join("shared.txt")).unwrap(),
+        "old version"
+    );
+}
+
+#[test]
+fn test_dry_run_changes_nothing() {
+    let temp = setup();
+    let before = snapshot(temp.path());
+    for direction in [
+        SyncDirection::AToB,
+        SyncDirection::BToA,
+        SyncDirection::TwoWay,
+    ] {
+        let actions = plan_sync(&compare(temp.path()), direction);
+        let options = SyncOptions {
+            dry_run: true,
+            ..options(temp.path())
+        };
+        let report = apply_sync(&RealFileSystem, &actions, &options);
+        assert_eq!(report.outcomes.len(), actions.len());
+        assert_eq!(report.failed().count(), 0);
+        assert_eq!(snapshot(temp.path()), before);
+    }
+    let actions = plan_sync(&compare(temp.path()), SyncDirection::AToB);
+    let options = SyncOptions {
+        dry_run: true,
+        ..options(temp.path())
+    };
+    let summary = apply_sync(&RealFileSystem, &actions, &options).summary();
+    assert!(
+        summary.starts_with("11 actions planned, nothing changed (dry run)\n"),
+        "{}",
+        summary
+    );
+    assert!(summary.contains("  delete old/cache/deep from B\n"));
+}
+
+/// The real filesystem, refusing to write to read-only files and to
+/// change read-only directories even for root
+struct EnforcedPermissions;
+
+impl EnforcedPermissions {
+    fn check_writable(path: &Path) -> io::Result<()> {
+        match fs::metadata(path) {
+            Ok(metadata) if metadata.permissions().readonly() => {
+                Err(io::Error::from(io::ErrorKind::PermissionDenied))
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+impl FileSystem for EnforcedPermissions {
+    fn canonicalize(&self, path: &Path) -> io::Result<std::path::PathBuf> {
+        RealFileSystem.canonicalize(path)
+    }
+
+    fn metadata(&self, path: &Path) -> io::Result<fs::Metadata> {
+        RealFileSystem.metadata(path)
+    }
+
+    fn copy(&self, from: &Path, to: &Path) -> io::Result<u64> {
+        Self::check_writable(to)?;
+        Self::check_writable(to.parent().unwrap())?;
+        fs::copy(from, to)
+    }
+
+    fn remove_file(&self, path: &Path) -> io::Result<()> {
+        Self::check_writable(path.parent().unwrap())?;
+        fs::remove_file(path)
+    }
+
+    fn remove_dir(&self, path: &Path) -> io::Result<()> {
+        Self::check_writable(path.parent().unwrap())?;
+        fs::remove_dir(path)
+    }
+}
+
+fn set_readonly(path: &Path, readonly: bool) {
+    let mut permissions = fs::metadata(path).unwrap().permissions();
+    #[allow(clippy::permissions_set_readonly_false)]
+    permissions.set_readonly(readonly);
+    fs::set_permissions(path, permissions).unwrap();
+}
+
+#[test]
+fn test_read_only_targets_fail_without_stopping_the_rest() {
+    let temp = setup();
+    let b = temp.path().join("b");
+    set_readonly(&b.join("shared.txt"), true);
+    set_readonly(&b.join("old/cache/deep"), true);
+
+    let actions = plan_sync(&compare(temp.path()), SyncDirection::AToB);
+    let report = apply_sync(&EnforcedPermissions, &actions, &options(temp.path()));
+    set_readonly(&b.join("old/cache/deep"), false);
+    set_readonly(&b.join("shared.txt"), false);
+
+    let failed: Vec<String> = report
+        .failed()
+        .map(|outcome| outcome.action.to_string())
+        .collect();
+    // The blob cannot be deleted, so neither can the directories holding it
+    assert_eq!(
+        failed,
+        [
+            "overwrite shared.txt in B",
+            "delete old/cache/deep/blob.bin from B",
+            "delete old/cache/deep from B",
+            "delete old/cache from B",
+            "delete old from B",
+        ]
+    );
+    assert_eq!(
+        fs::read_to_string(b.join("shared.txt")).unwrap(),
+        "old version"
+    );
+    assert!(b.join("old/cache/deep/blob.bin").exists());
+    // The other actions still ran
+    assert!(b.join("docs/2024/q1/report.txt").exists());
+    assert!(!b.join("old/index.txt").exists());
+    let summary = report.summary();
+    assert!(summary.starts_with("6 applied, 5 failed\n"), "{}", summary);
+    assert!(summary.contains("  FAILED overwrite shared.txt in B: "));
+}
@@ -0,0 +1,142 @@
+use dir_compare_core::text_diff::{
+    BINARY_SNIFF_LEN, FileDiff, LineChange, TextDiff, diff_files, diff_lines, diff_text, is_binary,
+};
+use std::fs;
+
+/// The diff as lines prefixed like a unified diff
+fn unified(diff: &TextDiff) -> Vec<String> {
+    diff.lines
+        .iter()
+        .map(|line| {
+            let sign = match line.change {
+                LineChange::Same => ' ',
+                LineChange::Removed => '-',
+                LineChange::Added => '+',
+            };
+            format!("{}{}", sign, line.text)
+        })
+        .collect()
+}
+
+#[test]
+fn test_identical_inputs_keep_every_line() {
+    let diff = diff_text("one\ntwo\nthree\n", "one\ntwo\nthree\n", 100);
+
+    assert!(diff.is_identical());
+    assert!(!diff.truncated);
+    assert_eq!(unified(&diff), [" one", " two", " three"]);
+    let numbers: Vec<(Option<usize>, Option<usize>)> =
+        diff.lines.iter().map(|l| (l.line_a, l.line_b)).collect();
+    assert_eq!(
+        numbers,
+        [(Some(1), Some(1)), (Some(2), Some(2)), (Some(3), Some(3))]
+    );
+    assert!(diff_text("", "", 100).lines.is_empty());
+}
+
+#[test]
+fn test_insertions() {
+    let diff = diff_text("a\nc\n", "a\nb\nc\nd\n", 100);
+
+    assert_eq!(unified(&diff), [" a", "+b", " c", "+d"]);
+    assert_eq!(diff.changed_lines(), (0, 2));
+    assert_eq!(diff.lines[1].line_a, None);
+    assert_eq!(diff.lines[1].line_b, Some(2));
+    assert_eq!(
+        (diff.lines[2].line_a, diff.lines[2].line_b),
+        (Some(2), Some(3))
+    );
+}
+
+#[test]
+fn test_deletions() {
+    let diff = diff_text("header\nold 1\nold 2\nfooter\n", "header\nfooter\n", 100);
+
+    assert_eq!(unified(&diff), [" header", "-old 1", "-old 2", " footer"]);
+    assert_eq!(diff.changed_lines(), (2, 0));
+    assert_eq!(diff.lines[2].line_a, Some(3));
+    assert_eq!(diff.lines[3].line_b, Some(2));
+    assert_eq!(unified(&diff_text("gone\n", "", 100)), ["-gone"]);
+}
+
+#[test]
+fn test_changed_lines_are_removed_then_added() {
+    let lines = diff_lines(&["x", "1", "y", "2", "z"], &["x", "one", "y", "two", "z"]);
+    let changes: Vec<LineChange> = lines.iter().map(|line| line.change).collect();
+
+    use LineChange::*;
+    assert_eq!(changes, [Same, Removed, Added, Same, Removed, Added, Same]);
+}
+
+#[test]
+fn test_line_endings_count_as_changes() {
+    let diff = diff_text("a\r\nb\r\n", "a\nb\n", 100);
+    assert_eq!(diff.changed_lines(), (2, 2));
+}
+
+#[test]
+fn test_only_first_lines_are_compared() {
+    let diff = diff_text("1\n2\n3\n4\n", "1\n2\n3\nfour\n", 3);
+
+    assert!(diff.truncated);
+    assert!(diff.is_identical());
+    assert_eq!(diff.lines.len(), 3);
+    assert!(!diff_text("1\n2\n3\n", "1\n2\n3\n", 3).truncated);
+}
+
+#[test]
+fn test_is_binary_looks_for_nul_in_the_first_bytes() {
+    assert!(!is_binary(b"plain text\n"));
+    assert!(is_binary(b"PNG\0\x01"));
+    let mut late = vec![b'a'; BINARY_SNIFF_LEN];
+    late.push(0);
+    assert!(!is_binary(&late));
+}
+
+#[test]
+fn test_diff_files_reads_text_files() {
+    let temp = tempfile::tempdir().unwrap();
+    let (a, b) = (temp.path().join("a.txt"), temp.path().join("b.txt"));
+    let lines: String = (1..=10).map(|i| format!("line {}\n", i)).collect();
+    fs::write(&a, &lines).unwrap();
+    fs::write(&b, lines.replace("line 2\n", "line two\n")).unwrap();
+
+    let FileDiff::Text(diff) = diff_files(&a, &b, 5).unwrap() else {
+        panic!("expected a text diff");
+    };
+    assert!(diff.truncated);
+    assert_eq!(
+        unified(&diff),
+        [
+            " line 1",
+            "-line 2",
+            "+line two",
+            " line 3",
+            " line 4",
+            " line 5"
+        ]
+    );
+}
+
+#[test]
+fn test_diff_files_describes_binary_files() {
+    let temp = tempfile::tempdir().unwrap();
+    let (a, b) = (temp.path().join("a.bin"), temp.path().join("b.bin"));
+    fs::write(&a, b"text only").unwrap();
+    fs::write(&b, b"\0\x01\x02").unwrap();
+
+    match diff_files(&a, &b, 100).unwrap() {
+        FileDiff::Binary {
+            size_a,
+            size_b,
+            hash_a,
+            hash_b,
+        } => {
+            assert_eq!((size_a, size_b), (9, 3));
+            assert_eq!(hash_a, blake3::hash(b"text only").to_hex().as_str());
+            assert_eq!(hash_b, blake3::hash(b"\0\x01\x02").to_hex().as_str());
+        }
+        other => panic!("expected a binary diff, got {:?}", other),
+    }
+    assert!(diff_files(&a, &temp.path().join("missing"), 100).is_err());
+}
@@ -1,8 +1,13 @@
 #[cfg(test)]
 mod tests {
+    use dir_compare_core::assert_result_matches;
+    use dir_compare_core::comparison::{
+        ComparisonStrategy, DEFAULT_MTIME_TOLERANCE, Entry, EntryKind, MetadataStrategy,
+    };
     use std::fs::{self, File};
     use std::io::Write;
     use std::path::{Path, PathBuf};
+    use std::time::{Duration, SystemTime};
 
     fn create_test_dir_with_files(base: &Path, name: &str, files: &[(&str, &[u8])]) -> PathBuf {
         let dir = base.join(name);
@@ -31,14 +36,16 @@ mod tests {
         );
 
         let strategy = dir_compare_core::comparison::FilenameOnlyStrategy::new(false);
-        let result = dir_compare_core::compare_directories(&dir_a, &dir_b, &strategy, None);
-
-        assert!(result.is_ok());
-        let result = result.unwrap();
+        let result =
+            dir_compare_core::compare_directories(&dir_a, &dir_b, &strategy, None).unwrap();
 
-        assert_eq!(result.a_only.len(), 0, "Expected no A-only entries");
-        assert_eq!(result.b_only.len(), 0, "Expected no B-only entries");
-        assert_eq!(result.both.len(), 2, "Expected 2 matching entries");
+        assert_result_matches!(
+            result,
+            a_only: [],
+            b_only: [],
+            both: ["file1.txt", "file2.txt"],
+        );
+        assert!(!result.has_differences());
     }
 
     #[test]
@@ -56,23 +63,16 @@ mod tests {
         );
 
         let strategy = dir_compare_core::comparison::FilenameOnlyStrategy::new(false);
-        let result = dir_compare_core::compare_directories(&dir_a, &dir_b, &strategy, None);
-
-        assert!(result.is_ok());
-        let result = result.unwrap();
+        let result =
+            dir_compare_core::compare_directories(&dir_a, &dir_b, &strategy, None).unwrap();
 
-        assert!(
-            result
-                .a_only
-                .iter()
-                .any(|e| e.path.to_string_lossy().contains("unique_to_a"))
-        );
-        assert!(
-            result
-                .b_only
-                .iter()
-                .any(|e| e.path.to_string_lossy().contains("file5"))
+        assert_result_matches!(
+            result,
+            a_only: ["unique_to_a.txt"],
+            b_only: ["file5.txt"],
+            both: ["file1.txt"],
         );
+        assert!(result.has_differences());
     }
 
     #[test]
@@ -84,14 +84,10 @@ mod tests {
         fs::create_dir_all(&dir_b).unwrap();
 
         let strategy = dir_compare_core::comparison::FilenameOnlyStrategy::new(false);
-        let result = dir_compare_core::compare_directories(&dir_a, &dir_b, &strategy, None);
-
-        assert!(result.is_ok());
-        let result = result.unwrap();
+        let result =
+            dir_compare_core::compare_directories(&dir_a, &dir_b, &strategy, None).unwrap();
 
-        assert!(result.a_only.is_empty());
-        assert!(result.b_only.is_empty());
-        assert!(result.both.is_empty());
+        assert_result_matches!(result, a_only: [], b_only: [], both: [], modified: []);
     }
 
     #[test]
@@ -106,18 +102,101 @@ mod tests {
         );
 
         let strategy = dir_compare_core::comparison::FilenameSizeStrategy::new(false);
-        let result = dir_compare_core::compare_directories(&dir_a, &dir_b, &strategy, None);
+        let result =
+            dir_compare_core::compare_directories(&dir_a, &dir_b, &strategy, None).unwrap();
 
-        assert!(result.is_ok());
-        let result = result.unwrap();
+        // Files with different sizes should not match
+        assert_result_matches!(
+            result,
+            a_only: [],
+            b_only: [],
+            both: [],
+            modified: ["file.txt"],
+        );
+    }
 
-        assert_eq!(
-            result.both.len(),
-            0,
-            "Files with different sizes should not match"
+    /// Sets the modification time of `dir/name` to `base + offset_secs`
+    fn set_mtime(dir: &Path, name: &str, base: SystemTime, offset_secs: u64) {
+        File::options()
+            .write(true)
+            .open(dir.join(name))
+            .unwrap()
+            .set_modified(base + Duration::from_secs(offset_secs))
+            .unwrap();
+    }
+
+    #[test]
+    fn test_metadata_strategy_tolerance_window() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let files: &[(&str, &[u8])] = &[
+            ("exact.txt", b"content"),
+            ("close.txt", b"content"),
+            ("far.txt", b"content"),
+            ("resized.txt", b"content"),
+        ];
+        let dir_a = create_test_dir_with_files(temp_dir.path(), "dir_a", files);
+        let dir_b = create_test_dir_with_files(temp_dir.path(), "dir_b", files);
+        fs::write(dir_b.join("resized.txt"), b"longer content").unwrap();
+
+        let base = SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+        for (name, offset_b) in [
+            ("exact.txt", 0),
+            ("close.txt", 2),
+            ("far.txt", 3),
+            ("resized.txt", 0),
+        ] {
+            set_mtime(&dir_a, name, base, 0);
+            set_mtime(&dir_b, name, base, offset_b);
+        }
+
+        // FAT32-style 2-second tolerance
+        let strategy = MetadataStrategy::new(false, Duration::from_secs(2));
+        let result =
+            dir_compare_core::compare_directories(&dir_a, &dir_b, &strategy, None).unwrap();
+        assert_result_matches!(
+            result,
+            both: ["close.txt", "exact.txt"],
+            modified: ["far.txt", "resized.txt"],
+        );
+
+        let strategy = MetadataStrategy::new(false, DEFAULT_MTIME_TOLERANCE);
+        let result =
+            dir_compare_core::compare_directories(&dir_a, &dir_b, &strategy, None).unwrap();
+        assert_result_matches!(
+            result,
+            both: ["exact.txt"],
+            modified: ["close.txt", "far.txt", "resized.txt"],
         );
-        assert_eq!(result.a_only.len(), 1, "Expected 1 A-only entry");
-        assert_eq!(result.b_only.len(), 1, "Expected 1 B-only entry");
+    }
+
+    #[test]
+    fn test_metadata_strategy_unavailable_mtime() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let dir = create_test_dir_with_files(temp_dir.path(), "dir", &[("file.txt", b"content")]);
+        let entry = |abs_path: PathBuf| Entry {
+            path: PathBuf::from("file.txt"),
+            abs_path,
+            kind: EntryKind::File,
+            size: Some(7),
+            nested_repository: false,
+            mtime: None,
+        };
+        let present = entry(dir.join("file.txt"));
+        let missing = entry(dir.join("deleted.txt"));
+
+        let strategy = MetadataStrategy::new(false, Duration::from_secs(3600));
+        assert!(strategy.matches(&present, &present));
+        assert!(!strategy.matches(&present, &missing));
+        assert!(!strategy.matches(&missing, &present));
+        assert!(!strategy.matches(&missing, &missing));
+
+        // Directories only need matching names
+        let directory = |abs_path: PathBuf| Entry {
+            kind: EntryKind::Directory,
+            size: None,
+            ..entry(abs_path)
+        };
+        assert!(strategy.matches(&directory(dir.clone()), &directory(dir.join("gone"))));
     }
 
     #[test]
@@ -162,8 +241,8 @@ mod tests {
         let dir_a = temp_dir.path().join("dir_a");
         let dir_b = temp_dir.path().join("dir_b");
 
-        fs::create_dir_all(&dir_a.join("level1").join("level2")).unwrap();
-        fs::create_dir_all(&dir_b.join("level1").join("level2")).unwrap();
+        fs::create_dir_all(dir_a.join("level1").join("level2")).unwrap();
+        fs::create_dir_all(dir_b.join("level1").join("level2")).unwrap();
         fs::write(
             dir_a.join("level1").join("level2").join("nested.txt"),
             b"content",
@@ -176,17 +255,12 @@ mod tests {
         .unwrap();
 
         let strategy = dir_compare_core::comparison::FilenameOnlyStrategy::new(false);
-        let result = dir_compare_core::compare_directories(&dir_a, &dir_b, &strategy, None);
-
-        assert!(result.is_ok());
-        let result = result.unwrap();
+        let result =
+            dir_compare_core::compare_directories(&dir_a, &dir_b, &strategy, None).unwrap();
 
-        assert!(
-            result
-                .both
-                .iter()
-                .any(|(a, _)| a.path.to_string_lossy().contains("nested")),
-            "Expected nested.txt in both directories"
+        assert_result_matches!(
+            result,
+            both: ["level1", "level1/level2", "level1/level2/nested.txt"],
         );
     }
 
@@ -231,8 +305,9 @@ mod tests {
             0,
             "Verify on match should detect difference"
         );
-        assert_eq!(result_verify.a_only.len(), 1);
-        assert_eq!(result_verify.b_only.len(), 1);
+        assert_eq!(result_verify.modified.len(), 1);
+        assert!(result_verify.a_only.is_empty());
+        assert!(result_verify.b_only.is_empty());
 
         // 3. Small files -> Full read always
         let dir_c = create_test_dir_with_files(temp_dir.path(), "dir_c", &[("small.txt", b"abc")]);
@@ -269,8 +344,9 @@ mod tests {
             0,
             "Files with different sizes should not match (file size is part of hash)"
         );
-        assert_eq!(result.a_only.len(), 1);
-        assert_eq!(result.b_only.len(), 1);
+        assert_eq!(result.modified.len(), 1);
+        assert!(result.a_only.is_empty());
+        assert!(result.b_only.is_empty());
     }
 
     #[test]
@@ -470,6 +546,126 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_blake3_hash_fixed_values() {
+        use dir_compare_core::HashAlgorithm;
+        use dir_compare_core::comparison::{compute_full_hash, compute_sampled_hash_with};
+
+        let temp_dir = tempfile::tempdir().unwrap();
+
+        // (content, sampled hash with size prefix, full-file hash); the full
+        // hashes of "" and "abc" are the official BLAKE3 test vectors
+        let cases: [(Vec<u8>, &str, &str); 5] = [
+            (
+                vec![],
+                "71e0a99173564931c0b8acc52d2685a8e39c64dc52e3d02390fdac2a12b155cb",
+                "af1349b9f5f9a1a6a0404dea36dcc9499bcb25c9adc112b7cc9a93cae41f3262",
+            ),
+            (
+                b"abc".to_vec(),
+                "cb6e0d6b5e09059664e6f8d6b9a3cee55099a99a74c28e50dc40522db0087ea6",
+                "6437b3ac38465133ffb63b75273a8db548c558465d79db03fd359c6cd5bd9d85",
+            ),
+            (
+                vec![b'x'; 100],
+                "a290075cae1e7584c0f18902f96df78efa86d099a4891385b440393581110d6e",
+                "2caf4e0114b6034350bf2947d79fca66685c2e1e7b9e057c9e171129c045a643",
+            ),
+            (
+                vec![b'y'; 5000],
+                "0f3f63b189d2ef2554d7f88709c9b6b7710c9481e06174b7fd838a550020c097",
+                "98ff1e7e30832beed31ab4c8aa2f38afff65a29cc164e78b30bd5bbf5c28236f",
+            ),
+            (
+                vec![b'z'; 100000],
+                "8e7d0a9e7f26c6c715f51b05b0feedbfd065ddd7168f62cf216ad4d08b8a0d38",
+                "c5e657abbaef592139e68b8463e01f02cb5b7b984cc172a49803a593379855c9",
+            ),
+        ];
+        for (i, (content, sampled, full)) in cases.iter().enumerate() {
+            let path = temp_dir.path().join(format!("file_{}.bin", i));
+            fs::write(&path, content).unwrap();
+            assert_eq!(
+                compute_sampled_hash_with(&path, HashAlgorithm::Blake3),
+                *sampled,
+                "sampled hash of {} bytes",
+                content.len()
+            );
+            assert_eq!(
+                compute_full_hash(&path, HashAlgorithm::Blake3).unwrap(),
+                *full,
+                "full hash of {} bytes",
+                content.len()
+            );
+        }
+
+        // SHA-256 stays the sampled default
+        let path = temp_dir.path().join("file_2.bin");
+        assert_eq!(
+            compute_sampled_hash_with(&path, HashAlgorithm::Sha256),
+            dir_compare_core::comparison::compute_sampled_hash(&path)
+        );
+    }
+
+    #[test]
+    fn test_blake3_strategy() {
+        use dir_compare_core::Blake3Strategy;
+
+        let temp_dir = tempfile::tempdir().unwrap();
+        let dir_a = create_test_dir_with_files(
+            temp_dir.path(),
+            "dir_a",
+            &[("same.txt", b"content"), ("changed.txt", b"before")],
+        );
+        let dir_b = create_test_dir_with_files(
+            temp_dir.path(),
+            "dir_b",
+            &[("same.txt", b"content"), ("changed.txt", b"after!")],
+        );
+
+        let strategy = Blake3Strategy::new(false);
+        let result =
+            dir_compare_core::compare_directories(&dir_a, &dir_b, &strategy, None).unwrap();
+        assert_result_matches!(result, both: ["same.txt"], modified: ["changed.txt"]);
+    }
+
+    #[test]
+    fn test_flat_mode_hash_algorithms() {
+        use dir_compare_core::HashAlgorithm;
+        use dir_compare_core::comparison::{FlatComparisonOptions, compare_directories_flat};
+
+        let temp_dir = tempfile::tempdir().unwrap();
+        let dir_a = create_test_dir_with_files(
+            temp_dir.path(),
+            "dir_a",
+            &[("one.txt", b"same"), ("two.txt", b"other")],
+        );
+        let dir_b = create_test_dir_with_files(temp_dir.path(), "dir_b", &[("moved.txt", b"same")]);
+
+        for (algorithm, hex_len) in [
+            (HashAlgorithm::Sha256, 64),
+            (HashAlgorithm::Blake3, 64),
+            (HashAlgorithm::FxHash, 16),
+        ] {
+            for use_full_hash in [false, true] {
+                let options = FlatComparisonOptions {
+                    use_full_hash,
+                    hash_algorithm: algorithm,
+                    ..Default::default()
+                };
+                let result = compare_directories_flat(&dir_a, &dir_b, &options, None).unwrap();
+                assert_eq!(result.unique_hashes, 2, "{:?}", algorithm);
+                assert_eq!(result.duplicate_count, 1, "{:?}", algorithm);
+                for group in &result.groups {
+                    assert_eq!(group.hash.len(), hex_len, "{:?}", algorithm);
+                }
+            }
+        }
+
+        assert_eq!("BLAKE3".parse(), Ok(HashAlgorithm::Blake3));
+        assert!("md5".parse::<HashAlgorithm>().is_err());
+    }
+
     #[test]
     fn test_compare_with_ignore_file() {
         let temp_dir = tempfile::tempdir().unwrap();
@@ -499,27 +695,10 @@ mod tests {
             &dir_b,
             &strategy,
             Some(&ignore_file_path),
-        );
-
-        assert!(result.is_ok());
-        let result = result.unwrap();
+        )
+        .unwrap();
 
-        assert_eq!(
-            result.a_only.len(),
-            0,
-            "Expected no A-only entries after ignore"
-        );
-        assert_eq!(
-            result.b_only.len(),
-            0,
-            "Expected no B-only entries after ignore"
-        );
-        assert_eq!(
-            result.both.len(),
-            1,
-            "Expected 1 matching entry after ignore"
-        );
-        assert_eq!(result.both[0].0.path.file_name().unwrap(), "file1.txt");
+        assert_result_matches!(result, a_only: [], b_only: [], both: ["file1.txt"]);
     }
 
     // Flat mode comparison tests
@@ -566,6 +745,7 @@ mod tests {
                 "Each group should have 1 file in B"
             );
         }
+        assert!(!result.has_differences());
     }
 
     #[test]
@@ -601,6 +781,8 @@ mod tests {
         let group = &result.groups[0];
         assert_eq!(group.files_in_a.len(), 2, "Should have 2 files in A");
         assert_eq!(group.files_in_b.len(), 1, "Should have 1 file in B");
+        // file2.txt only exists in A
+        assert!(result.has_differences());
     }
 
     #[test]
@@ -610,8 +792,8 @@ mod tests {
         let dir_a = temp_dir.path().join("dir_a");
         let dir_b = temp_dir.path().join("dir_b");
 
-        fs::create_dir_all(&dir_a.join("documents")).unwrap();
-        fs::create_dir_all(&dir_b.join("archive")).unwrap();
+        fs::create_dir_all(dir_a.join("documents")).unwrap();
+        fs::create_dir_all(dir_b.join("archive")).unwrap();
 
         fs::write(dir_a.join("documents").join("report.txt"), b"same_content").unwrap();
         fs::write(dir_b.join("archive").join("report.txt"), b"same_content").unwrap();
@@ -631,6 +813,7 @@ mod tests {
         let group = &result.groups[0];
         assert!(group.files_in_a[0].to_string_lossy().contains("documents"));
         assert!(group.files_in_b[0].to_string_lossy().contains("archive"));
+        assert!(result.has_differences(), "A moved file is a difference");
     }
 
     #[test]
@@ -680,6 +863,7 @@ mod tests {
         assert_eq!(result.total_files_b, 0);
         assert_eq!(result.unique_hashes, 0);
         assert!(result.groups.is_empty());
+        assert!(!result.has_differences());
     }
 
     #[test]
@@ -693,6 +877,7 @@ mod tests {
         let options = dir_compare_core::comparison::FlatComparisonOptions {
             use_full_hash: true,
             case_insensitive: false,
+            ..Default::default()
         };
         let result = dir_compare_core::compare_directories_flat(&dir_a, &dir_b, &options, None);
 
@@ -703,4 +888,134 @@ mod tests {
         assert_eq!(result.total_files_b, 1);
         assert_eq!(result.unique_hashes, 1);
     }
+
+    #[test]
+    fn test_case_insensitive_strategy_pairs_case_variants() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let dir_a = create_test_dir_with_files(temp_dir.path(), "dir_a", &[("README.txt", b"doc")]);
+        let dir_b = create_test_dir_with_files(temp_dir.path(), "dir_b", &[("readme.txt", b"doc")]);
+
+        let sensitive = dir_compare_core::compare_directories(
+            &dir_a,
+            &dir_b,
+            &dir_compare_core::FastHashStrategy::new(false),
+            None,
+        )
+        .unwrap();
+        assert_result_matches!(sensitive, a_only: ["README.txt"], b_only: ["readme.txt"], both: []);
+
+        let insensitive = dir_compare_core::compare_directories(
+            &dir_a,
+            &dir_b,
+            &dir_compare_core::FastHashStrategy::new(true),
+            None,
+        )
+        .unwrap();
+        assert_result_matches!(insensitive, a_only: [], b_only: [], both: ["README.txt"]);
+        assert_eq!(insensitive.both[0].1.path, Path::new("readme.txt"));
+    }
+
+    /// Needs a filesystem that can hold both case variants in one directory
+    #[cfg(not(any(windows, target_os = "macos")))]
+    #[test]
+    fn test_case_insensitive_collisions_stay_unpaired() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let dir_a = create_test_dir_with_files(
+            temp_dir.path(),
+            "dir_a",
+            &[("Notes.txt", b"one"), ("notes.txt", b"two")],
+        );
+        let dir_b = create_test_dir_with_files(temp_dir.path(), "dir_b", &[("NOTES.txt", b"one")]);
+
+        let result = dir_compare_core::compare_directories(
+            &dir_a,
+            &dir_b,
+            &dir_compare_core::FastHashStrategy::new(true),
+            None,
+        )
+        .unwrap();
+        assert_result_matches!(result, a_only: ["notes.txt"], b_only: [], both: ["Notes.txt"]);
+    }
+
+    /// The relative and absolute paths of every pair, A side first
+    fn pair_paths(pairs: &[(Entry, Entry)]) -> Vec<[PathBuf; 4]> {
+        pairs
+            .iter()
+            .map(|(a, b)| {
+                [
+                    a.path.clone(),
+                    a.abs_path.clone(),
+                    b.path.clone(),
+                    b.abs_path.clone(),
+                ]
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_swap_sides_matches_comparing_b_with_a() {
+        use dir_compare_core::comparison::{
+            ComparisonOptions, ResultDetail, compare_directories_with_options,
+        };
+        use dir_compare_core::output::Section;
+
+        let temp_dir = tempfile::tempdir().unwrap();
+        let dir_a = create_test_dir_with_files(
+            temp_dir.path(),
+            "dir_a",
+            &[
+                ("only_a.txt", b"a"),
+                ("Same.txt", b"same"),
+                ("changed.txt", b"short"),
+            ],
+        );
+        let dir_b = create_test_dir_with_files(
+            temp_dir.path(),
+            "dir_b",
+            &[
+                ("only_b.txt", b"bb"),
+                ("same.txt", b"same"),
+                ("changed.txt", b"much longer"),
+            ],
+        );
+        let strategy = dir_compare_core::FastHashStrategy::new(true);
+        let compare = |a: &Path, b: &Path, result_detail: ResultDetail| {
+            let options = ComparisonOptions {
+                result_detail,
+                ..Default::default()
+            };
+            compare_directories_with_options(a, b, &strategy, None, &options).unwrap()
+        };
+
+        let mut swapped = compare(&dir_a, &dir_b, ResultDetail::Full);
+        swapped.swap_sides();
+        let reversed = compare(&dir_b, &dir_a, ResultDetail::Full);
+        for section in Section::ALL {
+            assert_eq!(swapped.paths(section), reversed.paths(section));
+        }
+        assert_eq!(pair_paths(&swapped.both), pair_paths(&reversed.both));
+        assert_eq!(
+            pair_paths(&swapped.modified),
+            pair_paths(&reversed.modified)
+        );
+        assert_eq!(swapped.counts(), reversed.counts());
+        assert_eq!(swapped.both[0].0.path, Path::new("same.txt"));
+
+        // Swapping twice restores the result
+        let original = compare(&dir_a, &dir_b, ResultDetail::Full);
+        swapped.swap_sides();
+        assert_eq!(
+            pair_paths(&swapped.modified),
+            pair_paths(&original.modified)
+        );
+        assert_eq!(swapped.paths(Section::AOnly), ["only_a.txt"]);
+
+        // Exact counts of results without entries trade sides too
+        let mut counts_only = compare(&dir_a, &dir_b, ResultDetail::CountsOnly);
+        counts_only.swap_sides();
+        let reversed_counts = compare(&dir_b, &dir_a, ResultDetail::CountsOnly);
+        assert_eq!(counts_only.exact_counts, reversed_counts.exact_counts);
+        let modified = counts_only.counts().modified;
+        assert_eq!((modified.bytes_a, modified.bytes_b), (11, 5));
+    }
 }
@@ -0,0 +1,162 @@
+use dir_compare_core::comparison::{
+    ComparisonError, ComparisonStrategy, Entry, EntryKind, FastHashStrategy, FileWarning,
+    FilenameOnlyStrategy, FlatComparisonOptions, MetadataStrategy, SampledHashStrategy,
+    WarningCause, compare_directories, compare_directories_flat,
+};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+fn setup_dirs() -> tempfile::TempDir {
+    let temp = tempfile::tempdir().unwrap();
+    for side in ["dir_a", "dir_b"] {
+        fs::create_dir_all(temp.path().join(side)).unwrap();
+        fs::write(temp.path().join(side).join("file.txt"), "content").unwrap();
+    }
+    temp
+}
+
+fn file_entry(abs_path: &Path) -> Entry {
+    Entry {
+        path: PathBuf::from("file.txt"),
+        abs_path: abs_path.to_path_buf(),
+        kind: EntryKind::File,
+        size: Some(7),
+        nested_repository: false,
+        mtime: None,
+    }
+}
+
+#[test]
+fn test_readable_tree_has_no_warnings() {
+    let temp = setup_dirs();
+    let result = compare_directories(
+        &temp.path().join("dir_a"),
+        &temp.path().join("dir_b"),
+        &FastHashStrategy::new(false),
+        None,
+    )
+    .unwrap();
+    assert!(result.warnings.is_empty(), "{:?}", result.warnings);
+}
+
+#[test]
+fn test_missing_root_is_canonicalize_error() {
+    let temp = setup_dirs();
+    let missing = temp.path().join("missing");
+    let Err(error) = compare_directories(
+        &missing,
+        &temp.path().join("dir_b"),
+        &FilenameOnlyStrategy::new(false),
+        None,
+    ) else {
+        panic!("missing root accepted");
+    };
+
+    assert!(
+        matches!(error, ComparisonError::Canonicalize { .. }),
+        "{:?}",
+        error
+    );
+    assert_eq!(error.path(), Some(missing.as_path()));
+    assert_eq!(error.io_kind(), io::ErrorKind::NotFound);
+    assert_eq!(io::Error::from(error).kind(), io::ErrorKind::NotFound);
+}
+
+#[test]
+fn test_file_root_is_traversal_error() {
+    let temp = setup_dirs();
+    let Err(error) = compare_directories(
+        &temp.path().join("dir_a/file.txt"),
+        &temp.path().join("dir_b"),
+        &FilenameOnlyStrategy::new(false),
+        None,
+    ) else {
+        panic!("file accepted as root");
+    };
+    assert!(
+        matches!(error, ComparisonError::Traversal { .. }),
+        "{:?}",
+        error
+    );
+    assert!(error.to_string().starts_with("Could not read directory"));
+}
+
+#[test]
+fn test_invalid_ignore_file_is_rejected() {
+    let temp = setup_dirs();
+    let (a, b) = (temp.path().join("dir_a"), temp.path().join("dir_b"));
+    let ignore_file = temp.path().join(".dcignore");
+    fs::write(&ignore_file, "*.tmp\nsrc/{a,b\n").unwrap();
+
+    let strategy = FilenameOnlyStrategy::new(false);
+    let Err(error) = compare_directories(&a, &b, &strategy, Some(&ignore_file)) else {
+        panic!("invalid pattern accepted");
+    };
+    assert!(
+        matches!(error, ComparisonError::IgnoreFile { .. }),
+        "{:?}",
+        error
+    );
+    assert_eq!(error.path(), Some(ignore_file.as_path()));
+
+    let missing = temp.path().join("missing.ignore");
+    let Err(error) =
+        compare_directories_flat(&a, &b, &FlatComparisonOptions::default(), Some(&missing))
+    else {
+        panic!("missing ignore file accepted");
+    };
+    assert!(
+        matches!(error, ComparisonError::IgnoreFile { .. }),
+        "{:?}",
+        error
+    );
+}
+
+#[test]
+fn test_unreadable_files_are_reported_by_strategies() {
+    let temp = setup_dirs();
+    let readable = file_entry(&temp.path().join("dir_a/file.txt"));
+    let missing_path = temp.path().join("dir_b/gone.txt");
+    let missing = file_entry(&missing_path);
+
+    let strategies: Vec<(Box<dyn ComparisonStrategy>, WarningCause)> = vec![
+        (Box::new(FastHashStrategy::new(false)), WarningCause::Hash),
+        (
+            Box::new(SampledHashStrategy::new(false, true)),
+            WarningCause::Hash,
+        ),
+        (
+            Box::new(MetadataStrategy::new(false, Duration::from_secs(1))),
+            WarningCause::Metadata,
+        ),
+    ];
+    for (strategy, cause) in strategies {
+        let mut warnings = Vec::new();
+        assert!(!strategy.matches_reporting(&readable, &missing, &mut warnings));
+        assert_eq!(warnings.len(), 1, "{:?}", warnings);
+        assert_eq!(warnings[0].path, missing_path);
+        assert_eq!(warnings[0].cause, cause);
+
+        // Without a warnings list the outcome is the same
+        assert!(!strategy.matches(&readable, &missing));
+    }
+
+    let mut warnings = Vec::new();
+    assert!(FilenameOnlyStrategy::new(false).matches_reporting(&readable, &missing, &mut warnings));
+    assert!(warnings.is_empty());
+}
+
+#[test]
+fn test_warning_display() {
+    let warning = FileWarning {
+        path: PathBuf::from("/a/data.bin"),
+        cause: WarningCause::Hash,
+        message: "Permission denied (os error 13)".to_string(),
+    };
+    assert_eq!(
+        warning.to_string(),
+        "Could not hash /a/data.bin: Permission denied (os error 13)"
+    );
+}
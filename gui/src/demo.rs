@@ -0,0 +1,167 @@
+//! A small pair of directories for the "Try a demo" button.
+//!
+//! The fixture is the standard test structure of
+//! [`write_test_dir_structure`], written below the system temporary
+//! directory. It is removed when the [`DemoFixture`] is dropped, which
+//! happens when the demo is re-run and when the application exits.
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use dir_compare_core::logger;
+
+use crate::test_utils::write_test_dir_structure;
+
+/// Two generated directories that are deleted on drop
+#[derive(Debug)]
+pub struct DemoFixture {
+    root: PathBuf,
+}
+
+impl DemoFixture {
+    /// Generates the fixture under [`std::env::temp_dir`].
+    pub fn create() -> io::Result<Self> {
+        Self::create_in(&std::env::temp_dir())
+    }
+
+    /// Generates the fixture in a directory of `parent` named after this
+    /// process, replacing whatever a previous run left there.
+    pub fn create_in(parent: &Path) -> io::Result<Self> {
+        let fixture = Self {
+            root: parent.join(format!("dir-compare-demo-{}", std::process::id())),
+        };
+        fixture.regenerate()?;
+        Ok(fixture)
+    }
+
+    /// Deletes and rewrites both directories, undoing any changes made to
+    /// them since they were generated.
+    pub fn regenerate(&self) -> io::Result<()> {
+        remove_if_present(&self.root)?;
+        fs::create_dir_all(self.dir_a())?;
+        fs::create_dir_all(self.dir_b())?;
+        write_test_dir_structure(&self.dir_a(), &self.dir_b())
+    }
+
+    /// The directory containing both sides
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// The "A" side of the demo
+    pub fn dir_a(&self) -> PathBuf {
+        self.root.join("before")
+    }
+
+    /// The "B" side of the demo
+    pub fn dir_b(&self) -> PathBuf {
+        self.root.join("after")
+    }
+}
+
+impl Drop for DemoFixture {
+    fn drop(&mut self) {
+        if let Err(e) = remove_if_present(&self.root) {
+            logger::warn(&format!(
+                "Failed to remove demo directory {}: {}",
+                self.root.display(),
+                e
+            ));
+        }
+    }
+}
+
+fn remove_if_present(path: &Path) -> io::Result<()> {
+    match fs::remove_dir_all(path) {
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(()),
+        other => other,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    /// Lists every path below `root`, relative to it and sorted
+    fn tree(root: &Path) -> Vec<(PathBuf, Option<Vec<u8>>)> {
+        let mut paths = Vec::new();
+        let mut pending = vec![root.to_path_buf()];
+        while let Some(dir) = pending.pop() {
+            for entry in fs::read_dir(&dir).unwrap() {
+                let path = entry.unwrap().path();
+                let relative = path.strip_prefix(root).unwrap().to_path_buf();
+                if path.is_dir() {
+                    pending.push(path);
+                    paths.push((relative, None));
+                } else {
+                    paths.push((relative, Some(fs::read(&path).unwrap())));
+                }
+            }
+        }
+        paths.sort();
+        paths
+    }
+
+    #[test]
+    fn test_create_writes_both_sides() {
+        let parent = TempDir::new().unwrap();
+        let fixture = DemoFixture::create_in(parent.path()).unwrap();
+
+        assert!(fixture.root().starts_with(parent.path()));
+        assert!(fixture.dir_a().join("a_only.txt").is_file());
+        assert!(fixture.dir_b().join("b_only.txt").is_file());
+        assert!(fixture.dir_a().join("common_dir/subdir").is_dir());
+    }
+
+    #[test]
+    fn test_regenerate_is_idempotent() {
+        let parent = TempDir::new().unwrap();
+        let fixture = DemoFixture::create_in(parent.path()).unwrap();
+        let original = tree(fixture.root());
+
+        fs::write(fixture.dir_a().join("stray.txt"), "stray").unwrap();
+        fs::write(fixture.dir_b().join("common.txt"), "edited").unwrap();
+        fs::remove_dir_all(fixture.dir_b().join("b_only_dir")).unwrap();
+        fixture.regenerate().unwrap();
+        assert_eq!(tree(fixture.root()), original);
+
+        fixture.regenerate().unwrap();
+        assert_eq!(tree(fixture.root()), original);
+    }
+
+    #[test]
+    fn test_create_replaces_leftovers() {
+        let parent = TempDir::new().unwrap();
+        let fixture = DemoFixture::create_in(parent.path()).unwrap();
+        let original = tree(fixture.root());
+        let leftover = fixture.root().join("leftover.txt");
+        fs::write(&leftover, "from a crashed run").unwrap();
+        std::mem::forget(fixture);
+
+        let fixture = DemoFixture::create_in(parent.path()).unwrap();
+        assert!(!leftover.exists());
+        assert_eq!(tree(fixture.root()), original);
+    }
+
+    #[test]
+    fn test_drop_removes_fixture() {
+        let parent = TempDir::new().unwrap();
+        let fixture = DemoFixture::create_in(parent.path()).unwrap();
+        let root = fixture.root().to_path_buf();
+        assert!(root.is_dir());
+
+        drop(fixture);
+        assert!(!root.exists());
+        assert_eq!(fs::read_dir(parent.path()).unwrap().count(), 0);
+    }
+
+    #[test]
+    fn test_drop_tolerates_removed_fixture() {
+        let parent = TempDir::new().unwrap();
+        let fixture = DemoFixture::create_in(parent.path()).unwrap();
+        fs::remove_dir_all(fixture.root()).unwrap();
+        drop(fixture);
+    }
+}
@@ -7,6 +7,9 @@ pub trait FileDialogProvider {
 
     /// Opens a file picker dialog and returns the selected path
     fn pick_file(&self) -> Option<PathBuf>;
+
+    /// Opens a save dialog suggesting `default_name` and returns the chosen path
+    fn save_file(&self, default_name: &str) -> Option<PathBuf>;
 }
 
 /// Native file dialog implementation using rfd
@@ -20,32 +23,76 @@ impl FileDialogProvider for NativeFileDialog {
     fn pick_file(&self) -> Option<PathBuf> {
         rfd::FileDialog::new().pick_file()
     }
+
+    fn save_file(&self, default_name: &str) -> Option<PathBuf> {
+        let dialog = rfd::FileDialog::new().set_file_name(default_name);
+        let dialog = if default_name.ends_with(".toml") {
+            dialog.add_filter("TOML", &["toml"])
+        } else {
+            dialog
+                .add_filter("HTML", &["html", "htm"])
+                .add_filter("Markdown", &["md", "markdown"])
+                .add_filter("Text", &["txt"])
+        };
+        dialog.save_file()
+    }
 }
 
-/// Mock file dialog for testing
+/// Mock file dialog for testing, answering each kind of dialog with its own
+/// path
+#[derive(Debug, Clone, Default)]
 pub struct MockFileDialog {
-    return_path: Option<PathBuf>,
+    folder: Option<PathBuf>,
+    file: Option<PathBuf>,
+    save: Option<PathBuf>,
 }
 
 impl MockFileDialog {
-    /// Creates a new mock file dialog that returns the specified path
+    /// Creates a new mock file dialog that returns the specified path from
+    /// every dialog
     pub fn new(return_path: Option<PathBuf>) -> Self {
-        Self { return_path }
+        Self {
+            folder: return_path.clone(),
+            file: return_path.clone(),
+            save: return_path,
+        }
     }
 
-    /// Sets the path that will be returned by pick_folder
+    /// Sets the path that will be returned by every dialog
     pub fn set_return_path(&mut self, path: Option<PathBuf>) {
-        self.return_path = path;
+        *self = Self::new(path);
+    }
+
+    /// Sets the path that will be returned by pick_folder
+    pub fn with_folder(mut self, path: Option<PathBuf>) -> Self {
+        self.folder = path;
+        self
+    }
+
+    /// Sets the path that will be returned by pick_file
+    pub fn with_file(mut self, path: Option<PathBuf>) -> Self {
+        self.file = path;
+        self
+    }
+
+    /// Sets the path that will be returned by save_file
+    pub fn with_save(mut self, path: Option<PathBuf>) -> Self {
+        self.save = path;
+        self
     }
 }
 
 impl FileDialogProvider for MockFileDialog {
     fn pick_folder(&self) -> Option<PathBuf> {
-        self.return_path.clone()
+        self.folder.clone()
     }
 
     fn pick_file(&self) -> Option<PathBuf> {
-        self.return_path.clone()
+        self.file.clone()
+    }
+
+    fn save_file(&self, _default_name: &str) -> Option<PathBuf> {
+        self.save.clone()
     }
 }
 
@@ -64,6 +111,7 @@ mod tests {
     fn test_mock_file_dialog_returns_none() {
         let dialog = MockFileDialog::new(None);
         assert_eq!(dialog.pick_folder(), None);
+        assert_eq!(dialog.save_file("report.html"), None);
     }
 
     #[test]
@@ -86,4 +134,17 @@ mod tests {
         dialog.set_return_path(Some(path2.clone()));
         assert_eq!(dialog.pick_folder(), Some(path2));
     }
+
+    #[test]
+    fn test_mock_file_dialog_separate_paths() {
+        let dialog = MockFileDialog::default()
+            .with_folder(Some(PathBuf::from("/folder")))
+            .with_save(Some(PathBuf::from("/report.html")));
+        assert_eq!(dialog.pick_folder(), Some(PathBuf::from("/folder")));
+        assert_eq!(dialog.pick_file(), None);
+        assert_eq!(
+            dialog.save_file("report.html"),
+            Some(PathBuf::from("/report.html"))
+        );
+    }
 }
@@ -0,0 +1,112 @@
+//! The line diff of a modified file, shown below its details.
+
+use dir_compare_core::output::format_bytes;
+use dir_compare_core::text_diff::{DiffLine, FileDiff, LineChange, TextDiff, diff_files};
+use eframe::egui;
+use std::path::{Path, PathBuf};
+
+use crate::strings::Strings;
+use crate::table_view::ResultCategory;
+
+/// Height of the scrolled list of diff lines, in points
+const DIFF_HEIGHT: f32 = 240.0;
+
+/// The diff of the files at the same relative path in A and B
+pub struct DiffView {
+    /// Path of the files relative to the compared roots
+    pub path: PathBuf,
+    /// The diff, or why the files could not be read
+    pub diff: Result<FileDiff, String>,
+}
+
+impl DiffView {
+    /// Diffs the files at `path` below `root_a` and `root_b`, comparing the
+    /// first `max_lines` lines of text files
+    pub fn load(root_a: &Path, root_b: &Path, path: &Path, max_lines: usize) -> Self {
+        Self {
+            path: path.to_path_buf(),
+            diff: diff_files(&root_a.join(path), &root_b.join(path), max_lines)
+                .map_err(|e| e.to_string()),
+        }
+    }
+}
+
+/// Shows the diff of `view`, with removed lines in the color of A and added
+/// lines in the color of B
+pub fn render_diff(ui: &mut egui::Ui, view: &DiffView, max_lines: usize, strings: &Strings) {
+    ui.separator();
+    ui.label(strings.get("diff.title"));
+    match &view.diff {
+        Err(e) => {
+            ui.colored_label(
+                egui::Color32::RED,
+                strings.format("diff.unreadable", &[("error", e)]),
+            );
+        }
+        Ok(FileDiff::Binary {
+            size_a,
+            size_b,
+            hash_a,
+            hash_b,
+        }) => {
+            ui.label(strings.get("diff.binary"));
+            egui::Grid::new("binary_diff")
+                .num_columns(3)
+                .show(ui, |ui| {
+                    for (side, size, hash) in [("A", size_a, hash_a), ("B", size_b, hash_b)] {
+                        ui.label(side);
+                        ui.label(format_bytes(*size));
+                        ui.monospace(hash);
+                        ui.end_row();
+                    }
+                });
+        }
+        Ok(FileDiff::Text(diff)) => render_text_diff(ui, diff, max_lines, strings),
+    }
+}
+
+fn render_text_diff(ui: &mut egui::Ui, diff: &TextDiff, max_lines: usize, strings: &Strings) {
+    let (removed, added) = diff.changed_lines();
+    ui.label(strings.format("diff.counts", &[("removed", &removed), ("added", &added)]));
+    if diff.truncated {
+        ui.colored_label(
+            egui::Color32::from_rgb(255, 180, 80),
+            strings.format("diff.truncated", &[("count", &max_lines)]),
+        );
+    }
+    if diff.is_identical() {
+        ui.label(strings.get("diff.no_line_changes"));
+        return;
+    }
+    let row_height = ui.text_style_height(&egui::TextStyle::Monospace);
+    egui::ScrollArea::both()
+        .max_height(DIFF_HEIGHT)
+        .auto_shrink([false, true])
+        .show_rows(ui, row_height, diff.lines.len(), |ui, rows| {
+            for line in &diff.lines[rows] {
+                ui.label(diff_line_text(line));
+            }
+        });
+}
+
+/// The line as in a unified diff, after the line numbers on both sides
+fn diff_line_text(line: &DiffLine) -> egui::RichText {
+    let number = |n: Option<usize>| n.map(|n| n.to_string()).unwrap_or_default();
+    let (sign, color) = match line.change {
+        LineChange::Same => (' ', None),
+        LineChange::Removed => ('-', Some(ResultCategory::AOnly.color())),
+        LineChange::Added => ('+', Some(ResultCategory::BOnly.color())),
+    };
+    let text = egui::RichText::new(format!(
+        "{:>5} {:>5} {}{}",
+        number(line.line_a),
+        number(line.line_b),
+        sign,
+        line.text.trim_end_matches('\r')
+    ))
+    .monospace();
+    match color {
+        Some(color) => text.color(color),
+        None => text,
+    }
+}
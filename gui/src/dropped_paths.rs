@@ -0,0 +1,99 @@
+//! Folders dragged from a file manager and dropped onto the window.
+//!
+//! A drop fills the directory fields of the active tab: the field under the
+//! pointer if there is one, otherwise directory A while it is empty or
+//! invalid and directory B after that. Dropping two folders at once fills
+//! both fields in order. Dropped files are not used, and reported for
+//! [`DROP_ERROR_DURATION`].
+
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+use crate::strings::Strings;
+use crate::tabs::TabState;
+use crate::validate_path;
+
+/// How long the message about dropped files that are not folders is shown
+pub const DROP_ERROR_DURATION: Duration = Duration::from_secs(5);
+
+/// Dropped files that are not folders, reported below the directory fields
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DropError {
+    pub message: String,
+    pub dropped_at: Instant,
+}
+
+impl DropError {
+    /// How much longer the message is shown; zero once it expired
+    pub fn remaining(&self, now: Instant) -> Duration {
+        DROP_ERROR_DURATION.saturating_sub(now.duration_since(self.dropped_at))
+    }
+}
+
+/// A directory field of a tab
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DropTarget {
+    DirA,
+    DirB,
+}
+
+impl DropTarget {
+    fn other(self) -> Self {
+        match self {
+            DropTarget::DirA => DropTarget::DirB,
+            DropTarget::DirB => DropTarget::DirA,
+        }
+    }
+
+    fn field(self, tab: &mut TabState) -> &mut String {
+        match self {
+            DropTarget::DirA => &mut tab.dir_a_path,
+            DropTarget::DirB => &mut tab.dir_b_path,
+        }
+    }
+}
+
+/// Fills the directory fields of `tab` with the folders among `paths`,
+/// dropped onto `target` or onto no field in particular.
+///
+/// A second folder goes to the other field; further folders are ignored.
+/// Returns the paths that are not folders.
+pub fn assign_dropped_paths(
+    tab: &mut TabState,
+    paths: &[PathBuf],
+    target: Option<DropTarget>,
+) -> Vec<PathBuf> {
+    let (folders, rejected): (Vec<&PathBuf>, Vec<&PathBuf>) =
+        paths.iter().partition(|path| path.is_dir());
+    let first = target.unwrap_or(if folders.len() > 1 || !validate_path(&tab.dir_a_path) {
+        DropTarget::DirA
+    } else {
+        DropTarget::DirB
+    });
+    for (folder, field) in folders.into_iter().zip([first, first.other()]) {
+        *field.field(tab) = folder.display().to_string();
+    }
+    rejected.into_iter().cloned().collect()
+}
+
+/// Applies a drop to `tab` at `now`, replacing its [`DropError`] with one
+/// listing the dropped files that are not folders, if any
+pub fn drop_paths(
+    tab: &mut TabState,
+    paths: &[PathBuf],
+    target: Option<DropTarget>,
+    strings: &Strings,
+    now: Instant,
+) {
+    let rejected = assign_dropped_paths(tab, paths, target);
+    tab.drop_error = (!rejected.is_empty()).then(|| {
+        let names: Vec<_> = rejected
+            .iter()
+            .map(|path| path.display().to_string())
+            .collect();
+        DropError {
+            message: strings.format("error.not_directory", &[("paths", &names.join(", "))]),
+            dropped_at: now,
+        }
+    });
+}
@@ -0,0 +1,60 @@
+//! Saving a comparison result as a report file.
+//!
+//! The report format follows the extension picked in the save dialog, so the
+//! GUI needs no separate format selector.
+
+use dir_compare_core::ComparisonResult;
+use dir_compare_core::output::{Formatter, HtmlFormatter, MarkdownFormatter, TextFormatter};
+use std::path::Path;
+
+use crate::strings::Strings;
+
+/// File name suggested by the save dialog
+pub const DEFAULT_REPORT_NAME: &str = "dir-compare-report.html";
+
+/// Report formats the GUI can export
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReportFormat {
+    Text,
+    Html,
+    Markdown,
+}
+
+impl ReportFormat {
+    /// Chooses the format from the extension: `.html`/`.htm` for HTML,
+    /// `.md`/`.markdown` for Markdown and text for anything else
+    pub fn from_path(path: &Path) -> Self {
+        let extension = path
+            .extension()
+            .map(|ext| ext.to_string_lossy().to_lowercase());
+        match extension.as_deref() {
+            Some("html" | "htm") => ReportFormat::Html,
+            Some("md" | "markdown") => ReportFormat::Markdown,
+            _ => ReportFormat::Text,
+        }
+    }
+
+    /// Renders `result`, using the locale's section labels where the format
+    /// supports them
+    pub fn render(self, result: &ComparisonResult, strings: &Strings) -> String {
+        match self {
+            ReportFormat::Text => {
+                TextFormatter.format_with_labels(result, &strings.report_labels())
+            }
+            ReportFormat::Html => HtmlFormatter.format(result),
+            ReportFormat::Markdown => {
+                MarkdownFormatter.format_with_labels(result, &strings.report_labels())
+            }
+        }
+    }
+}
+
+/// Writes `result` to `path` in the format given by its extension
+pub fn write_report(
+    path: &Path,
+    result: &ComparisonResult,
+    strings: &Strings,
+) -> std::io::Result<()> {
+    let report = ReportFormat::from_path(path).render(result, strings);
+    std::fs::write(path, report)
+}
@@ -0,0 +1,41 @@
+//! Revealing result entries in the system file manager.
+//!
+//! Windows Explorer and the macOS Finder select the entry in its folder;
+//! other platforms open the containing folder with `xdg-open`.
+
+use std::io;
+use std::path::Path;
+use std::process::Command;
+
+/// The command showing `path` in the platform's file manager
+pub fn reveal_command(path: &Path) -> Command {
+    if cfg!(target_os = "windows") {
+        let mut select = std::ffi::OsString::from("/select,");
+        select.push(path);
+        let mut command = Command::new("explorer");
+        command.arg(select);
+        command
+    } else if cfg!(target_os = "macos") {
+        let mut command = Command::new("open");
+        command.arg("-R").arg(path);
+        command
+    } else {
+        let mut command = Command::new("xdg-open");
+        command.arg(path.parent().unwrap_or(path));
+        command
+    }
+}
+
+/// Shows `path` in the system file manager.
+///
+/// # Errors
+///
+/// Returns [`io::ErrorKind::NotFound`] if nothing exists at `path` any more,
+/// or the error of starting the file manager. The file manager is reaped on
+/// a background thread instead of being waited for.
+pub fn open_in_file_manager(path: &Path) -> io::Result<()> {
+    std::fs::symlink_metadata(path)?;
+    let mut child = reveal_command(path).spawn()?;
+    std::thread::spawn(move || child.wait());
+    Ok(())
+}
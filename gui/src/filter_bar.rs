@@ -0,0 +1,162 @@
+//! The filter bar above the results, which narrows a finished comparison
+//! with a [`ResultFilter`] instead of running it again.
+
+use dir_compare_core::output::Section;
+use dir_compare_core::{EntryFilter, KindFilter, ResultFilter};
+use eframe::egui;
+use std::path::PathBuf;
+
+use crate::strings::Strings;
+
+/// Bytes per megabyte of the size fields
+const MB: f64 = 1024.0 * 1024.0;
+
+/// Label keys of the categories, in the order of [`Section::ALL`]
+const SECTION_KEYS: [&str; 4] = [
+    "category.a_only",
+    "category.b_only",
+    "category.modified",
+    "category.both",
+];
+
+/// A field of the filter bar holding a value that cannot be used
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FilterBarError {
+    /// The glob is not valid gitignore syntax
+    Glob(String),
+    /// A size is not a non-negative number of megabytes
+    Size(String),
+}
+
+impl FilterBarError {
+    /// Returns the localized message for the status line
+    pub fn message(&self, strings: &Strings) -> String {
+        match self {
+            FilterBarError::Glob(glob) => strings.format("filter.invalid_glob", &[("glob", glob)]),
+            FilterBarError::Size(size) => strings.format("filter.invalid_size", &[("size", size)]),
+        }
+    }
+}
+
+/// The inputs of the filter bar, as typed
+#[derive(Debug, Clone, PartialEq)]
+pub struct FilterBar {
+    /// Gitignore-style glob, as given to `--include`
+    pub glob: String,
+    /// Relative path the entries must lie under
+    pub path_prefix: String,
+    /// Smallest size in megabytes
+    pub min_size_mb: String,
+    /// Largest size in megabytes
+    pub max_size_mb: String,
+    pub kind: Option<KindFilter>,
+    /// Whether each category of [`Section::ALL`] is shown
+    pub sections: [bool; 4],
+}
+
+impl Default for FilterBar {
+    fn default() -> Self {
+        Self {
+            glob: String::new(),
+            path_prefix: String::new(),
+            min_size_mb: String::new(),
+            max_size_mb: String::new(),
+            kind: None,
+            sections: [true; 4],
+        }
+    }
+}
+
+impl FilterBar {
+    /// Builds the filter from the inputs; blank fields and a full set of
+    /// categories leave their constraint unset
+    pub fn to_filter(&self) -> Result<ResultFilter, FilterBarError> {
+        let glob = self.glob.trim();
+        let patterns = if glob.is_empty() {
+            None
+        } else {
+            let patterns = EntryFilter::new(&[glob], &[])
+                .map_err(|_| FilterBarError::Glob(glob.to_string()))?;
+            Some(patterns)
+        };
+        let path_prefix = self.path_prefix.trim().trim_matches('/');
+        let sections = (!self.sections.iter().all(|&shown| shown)).then(|| {
+            Section::ALL
+                .into_iter()
+                .zip(self.sections)
+                .filter_map(|(section, shown)| shown.then_some(section))
+                .collect()
+        });
+        Ok(ResultFilter {
+            patterns,
+            path_prefix: (!path_prefix.is_empty()).then(|| PathBuf::from(path_prefix)),
+            min_size: parse_megabytes(&self.min_size_mb)?,
+            max_size: parse_megabytes(&self.max_size_mb)?,
+            kind: self.kind,
+            sections,
+        })
+    }
+
+    /// Shows the controls in one row; returns true if any input changed
+    pub fn show(&mut self, ui: &mut egui::Ui, strings: &Strings) -> bool {
+        let before = self.clone();
+        ui.horizontal_wrapped(|ui| {
+            ui.label(strings.get("filter.glob"));
+            ui.add(
+                egui::TextEdit::singleline(&mut self.glob)
+                    .hint_text("*.dll")
+                    .desired_width(80.0),
+            );
+            ui.label(strings.get("filter.under"));
+            ui.add(
+                egui::TextEdit::singleline(&mut self.path_prefix)
+                    .hint_text("src/")
+                    .desired_width(80.0),
+            );
+            ui.label(strings.get("filter.size_mb"));
+            ui.add(egui::TextEdit::singleline(&mut self.min_size_mb).desired_width(40.0));
+            ui.label("–");
+            ui.add(egui::TextEdit::singleline(&mut self.max_size_mb).desired_width(40.0));
+            egui::ComboBox::from_id_source("filter_kind")
+                .selected_text(kind_label(strings, self.kind))
+                .show_ui(ui, |ui| {
+                    for kind in [
+                        None,
+                        Some(KindFilter::File),
+                        Some(KindFilter::Directory),
+                        Some(KindFilter::Symlink),
+                    ] {
+                        ui.selectable_value(&mut self.kind, kind, kind_label(strings, kind));
+                    }
+                });
+            for (shown, key) in self.sections.iter_mut().zip(SECTION_KEYS) {
+                ui.checkbox(shown, strings.get(key));
+            }
+            if *self != Self::default() && ui.small_button(strings.get("filter.reset")).clicked() {
+                *self = Self::default();
+            }
+        });
+        *self != before
+    }
+}
+
+/// Parses a size in megabytes; blank means no bound
+fn parse_megabytes(text: &str) -> Result<Option<u64>, FilterBarError> {
+    let text = text.trim();
+    if text.is_empty() {
+        return Ok(None);
+    }
+    match text.parse::<f64>() {
+        Ok(mb) if mb >= 0.0 && mb.is_finite() => Ok(Some((mb * MB).round() as u64)),
+        _ => Err(FilterBarError::Size(text.to_string())),
+    }
+}
+
+fn kind_label(strings: &Strings, kind: Option<KindFilter>) -> &str {
+    strings.get(match kind {
+        None => "filter.kind_any",
+        Some(KindFilter::File) => "kind.file",
+        Some(KindFilter::Directory) => "kind.directory",
+        Some(KindFilter::Symlink) => "kind.symlink",
+    })
+}
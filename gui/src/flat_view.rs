@@ -0,0 +1,171 @@
+//! Results of a flat mode comparison, which groups files by content hash
+//! instead of by path.
+
+use dir_compare_core::output::format_bytes;
+use dir_compare_core::{FlatComparisonResult, FlatContentGroup};
+use eframe::egui;
+use std::path::PathBuf;
+
+use crate::strings::Strings;
+
+/// Number of hash characters shown in a group header
+const SHORT_HASH_LEN: usize = 16;
+
+/// Color of the files of directory A, as in the HTML report
+const FILE_A_COLOR: egui::Color32 = egui::Color32::from_rgb(0xdc, 0x35, 0x45);
+/// Color of the files of directory B, as in the HTML report
+const FILE_B_COLOR: egui::Color32 = egui::Color32::from_rgb(0x28, 0xa7, 0x45);
+
+/// How the files of a content group are spread across A and B
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FlatStatus {
+    /// A single file, in A
+    AOnly,
+    /// A single file, in B
+    BOnly,
+    /// One file in each directory, under different paths
+    Moved,
+    /// Any other group of more than one file, including one file in each
+    /// directory under the same path
+    Duplicate,
+}
+
+impl FlatStatus {
+    /// All statuses, in display order
+    pub const ALL: [FlatStatus; 4] = [
+        FlatStatus::AOnly,
+        FlatStatus::BOnly,
+        FlatStatus::Moved,
+        FlatStatus::Duplicate,
+    ];
+
+    pub fn of(group: &FlatContentGroup) -> Self {
+        match (group.files_in_a.as_slice(), group.files_in_b.as_slice()) {
+            ([_], []) => FlatStatus::AOnly,
+            ([], [_]) => FlatStatus::BOnly,
+            ([a], [b]) if a != b => FlatStatus::Moved,
+            _ => FlatStatus::Duplicate,
+        }
+    }
+
+    /// Returns the color of groups with this status, that of the badges of
+    /// the HTML report
+    pub fn color(self) -> egui::Color32 {
+        match self {
+            FlatStatus::AOnly => FILE_A_COLOR,
+            FlatStatus::BOnly => FILE_B_COLOR,
+            FlatStatus::Moved => egui::Color32::from_rgb(0x17, 0xa2, 0xb8),
+            FlatStatus::Duplicate => egui::Color32::from_rgb(0xff, 0xc1, 0x07),
+        }
+    }
+
+    /// Label key of the section listing groups with this status
+    fn title_key(self) -> &'static str {
+        match self {
+            FlatStatus::AOnly => "flat.a_only",
+            FlatStatus::BOnly => "flat.b_only",
+            FlatStatus::Moved => "flat.moved",
+            FlatStatus::Duplicate => "flat.duplicate",
+        }
+    }
+}
+
+/// A flat mode result with its groups sorted by status for display
+pub struct FlatResultsCache {
+    pub result: FlatComparisonResult,
+    /// Indices into `result.groups`, in the order of [`FlatStatus::ALL`]
+    by_status: [Vec<usize>; 4],
+}
+
+impl FlatResultsCache {
+    pub fn new(result: FlatComparisonResult) -> Self {
+        let mut by_status: [Vec<usize>; 4] = Default::default();
+        for (index, group) in result.groups.iter().enumerate() {
+            by_status[FlatStatus::of(group) as usize].push(index);
+        }
+        Self { result, by_status }
+    }
+
+    /// Exchanges the files of A and B, as if B had been compared with A
+    pub fn swap_sides(&mut self) {
+        let result = &mut self.result;
+        for group in &mut result.groups {
+            std::mem::swap(&mut group.files_in_a, &mut group.files_in_b);
+            for link in &mut group.hard_links {
+                link.in_a = !link.in_a;
+            }
+        }
+        std::mem::swap(&mut result.total_files_a, &mut result.total_files_b);
+        self.by_status
+            .swap(FlatStatus::AOnly as usize, FlatStatus::BOnly as usize);
+    }
+
+    /// The groups with `status`, in hash order
+    pub fn groups(&self, status: FlatStatus) -> impl Iterator<Item = &FlatContentGroup> {
+        self.by_status[status as usize]
+            .iter()
+            .map(|&index| &self.result.groups[index])
+    }
+
+    /// The number of groups with `status`
+    pub fn count(&self, status: FlatStatus) -> usize {
+        self.by_status[status as usize].len()
+    }
+}
+
+/// The first characters of `hash`, enough to tell groups apart
+pub fn short_hash(hash: &str) -> &str {
+    hash.get(..SHORT_HASH_LEN).unwrap_or(hash)
+}
+
+/// Renders one collapsible section per status, each listing its groups
+/// with the files of A and B underneath
+pub fn render_flat_results(ui: &mut egui::Ui, cache: &FlatResultsCache, strings: &Strings) {
+    egui::ScrollArea::vertical().show(ui, |ui| {
+        for status in FlatStatus::ALL {
+            let title = strings.format(status.title_key(), &[("count", &cache.count(status))]);
+            egui::CollapsingHeader::new(egui::RichText::new(title).color(status.color()))
+                .id_source(status.title_key())
+                .show(ui, |ui| {
+                    for group in cache.groups(status) {
+                        render_group(ui, group, status, strings);
+                    }
+                });
+        }
+    });
+}
+
+fn render_group(
+    ui: &mut egui::Ui,
+    group: &FlatContentGroup,
+    status: FlatStatus,
+    strings: &Strings,
+) {
+    let header = strings.format(
+        "flat.group",
+        &[
+            ("hash", &short_hash(&group.hash)),
+            ("size", &format_bytes(group.size)),
+            ("count", &group.file_count),
+        ],
+    );
+    egui::CollapsingHeader::new(
+        egui::RichText::new(header)
+            .monospace()
+            .color(status.color()),
+    )
+    .id_source(&group.hash)
+    .show(ui, |ui| {
+        file_rows(ui, "A", &group.files_in_a, FILE_A_COLOR);
+        file_rows(ui, "B", &group.files_in_b, FILE_B_COLOR);
+    });
+}
+
+fn file_rows(ui: &mut egui::Ui, side: &str, paths: &[PathBuf], color: egui::Color32) {
+    for path in paths {
+        ui.horizontal(|ui| {
+            ui.label(egui::RichText::new(side).strong().color(color));
+            ui.label(egui::RichText::new(path.display().to_string()).color(color));
+        });
+    }
+}
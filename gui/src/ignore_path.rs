@@ -0,0 +1,61 @@
+//! Resolution of the ignore-file path typed into the GUI.
+//!
+//! The comparison runs on a worker thread, so a relative path must be turned
+//! into an absolute one before it is handed over. Relative paths are looked up
+//! in the working directory first and in directory A second, which is where
+//! per-tree ignore files such as `.dcignore` usually live.
+
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+/// An ignore file that could not be found at any candidate location.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IgnorePathError {
+    /// The path as entered
+    pub input: String,
+    /// Absolute locations that were checked, in lookup order
+    pub tried: Vec<PathBuf>,
+}
+
+impl fmt::Display for IgnorePathError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let tried: Vec<String> = self.tried.iter().map(|p| p.display().to_string()).collect();
+        write!(
+            f,
+            "Ignore file not found: {} (looked in {})",
+            self.input,
+            tried.join(", ")
+        )
+    }
+}
+
+impl std::error::Error for IgnorePathError {}
+
+/// Resolves an ignore-file path to an existing absolute file.
+///
+/// Absolute paths are used as given. Relative paths are tried against `cwd`
+/// and then against `dir_a`; an empty `dir_a` skips the fallback.
+pub fn resolve_ignore_path(
+    input: &str,
+    cwd: &Path,
+    dir_a: &str,
+) -> Result<PathBuf, IgnorePathError> {
+    let path = Path::new(input.trim());
+    let candidates = if path.is_absolute() {
+        vec![path.to_path_buf()]
+    } else {
+        let mut candidates = vec![cwd.join(path)];
+        if !dir_a.trim().is_empty() {
+            candidates.push(cwd.join(dir_a.trim()).join(path));
+        }
+        candidates
+    };
+
+    match candidates.iter().find(|candidate| candidate.is_file()) {
+        Some(found) => Ok(found.clone()),
+        None => Err(IgnorePathError {
+            input: input.trim().to_string(),
+            tried: candidates,
+        }),
+    }
+}
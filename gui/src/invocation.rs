@@ -0,0 +1,174 @@
+//! The CLI equivalent of a tab's comparison, for File → Copy invocation and
+//! File → Export settings….
+//!
+//! A [`ComparisonSetup`] holds the options a comparison of the tab would
+//! run with. [`setup_options`] translates them into the options of
+//! `dir-compare`, leaving out those that match its defaults, so both the
+//! command line and the config file written for `--config` name every
+//! other option exactly once.
+
+use dir_compare_core::ComparisonStrategyType;
+use dir_compare_core::DEFAULT_MTIME_TOLERANCE;
+use dir_compare_core::config::{ConfigEntries, ConfigValue, config_to_args, format_config};
+use dir_compare_core::strategy_options::{OptionValue, OptionValues};
+use std::path::{Path, PathBuf};
+
+use crate::ignore_path::resolve_ignore_path;
+use crate::tabs::TabState;
+
+/// Suggested name of an exported settings file
+pub const DEFAULT_SETTINGS_NAME: &str = "dir-compare.toml";
+
+/// The effective options of a tab's comparison
+#[derive(Debug, Clone, PartialEq)]
+pub struct ComparisonSetup {
+    pub dir_a: String,
+    pub dir_b: String,
+    pub method: ComparisonStrategyType,
+    pub strategy_options: OptionValues,
+    pub flat_mode: bool,
+    pub full_hash: bool,
+    pub respect_gitignore: bool,
+    pub max_depth: Option<usize>,
+    pub ignore_hidden: bool,
+    pub detect_renames: bool,
+    /// The ignore file, resolved like a comparison resolves it when found
+    pub ignore_file: Option<PathBuf>,
+}
+
+impl ComparisonSetup {
+    /// Reads the setup of `tab`, resolving a relative ignore file against
+    /// `cwd` and directory A.
+    pub fn from_tab(tab: &TabState, cwd: &Path) -> Self {
+        let ignore_file = tab
+            .ignore_file_path
+            .as_deref()
+            .filter(|input| !input.trim().is_empty())
+            .map(|input| {
+                resolve_ignore_path(input, cwd, &tab.dir_a_path)
+                    .unwrap_or_else(|_| PathBuf::from(input.trim()))
+            });
+        Self {
+            dir_a: tab.dir_a_path.clone(),
+            dir_b: tab.dir_b_path.clone(),
+            method: tab.comparison_method,
+            strategy_options: tab.strategy_options.clone(),
+            flat_mode: tab.flat_mode,
+            full_hash: tab.full_hash,
+            respect_gitignore: tab.respect_gitignore,
+            max_depth: tab.max_depth,
+            ignore_hidden: tab.ignore_hidden,
+            detect_renames: tab.detect_renames,
+            ignore_file,
+        }
+    }
+}
+
+/// Name of `method` for `dir-compare --method`
+pub fn cli_method(method: ComparisonStrategyType) -> &'static str {
+    match method {
+        ComparisonStrategyType::Filename => "filename",
+        ComparisonStrategyType::FilenameSize => "size",
+        ComparisonStrategyType::FastHash => "hash",
+        ComparisonStrategyType::Blake3 => "blake3",
+        ComparisonStrategyType::SampledHash => "sampled",
+        ComparisonStrategyType::Mtime => "mtime",
+    }
+}
+
+/// The options of `dir-compare` that reproduce `setup`, without the
+/// directories; options matching the CLI defaults are left out.
+pub fn setup_options(setup: &ComparisonSetup) -> ConfigEntries {
+    let mut options = Vec::new();
+    if setup.flat_mode {
+        // Flat mode always hashes by content, so the method does not apply
+        options.push(("flat".to_string(), ConfigValue::Bool(true)));
+        if setup.full_hash {
+            options.push(("full-hash".to_string(), ConfigValue::Bool(true)));
+        }
+    } else {
+        if setup.method != ComparisonStrategyType::SampledHash {
+            let method = cli_method(setup.method).to_string();
+            options.push(("method".to_string(), ConfigValue::String(method)));
+        }
+        let option = |name: &str| setup.strategy_options.get(name);
+        match setup.method {
+            ComparisonStrategyType::SampledHash
+                if option("verify") == Some(&OptionValue::Bool(true)) =>
+            {
+                options.push(("verify".to_string(), ConfigValue::Bool(true)));
+            }
+            ComparisonStrategyType::Mtime => {
+                if let Some(&OptionValue::Int(secs)) = option("tolerance_secs")
+                    && secs != DEFAULT_MTIME_TOLERANCE.as_secs() as i64
+                {
+                    options.push(("mtime-tolerance".to_string(), ConfigValue::Int(secs)));
+                }
+            }
+            _ => {}
+        }
+        if setup.detect_renames {
+            options.push(("detect-renames".to_string(), ConfigValue::Bool(true)));
+        }
+    }
+    if let Some(ignore_file) = &setup.ignore_file {
+        let path = ignore_file.display().to_string();
+        options.push(("ignore".to_string(), ConfigValue::String(path)));
+    }
+    if setup.respect_gitignore {
+        options.push(("respect-gitignore".to_string(), ConfigValue::Bool(true)));
+    }
+    if let Some(depth) = setup.max_depth {
+        options.push(("max-depth".to_string(), ConfigValue::Int(depth as i64)));
+    }
+    if setup.ignore_hidden {
+        options.push(("ignore-hidden".to_string(), ConfigValue::Bool(true)));
+    }
+    options
+}
+
+/// The arguments of `dir-compare` that reproduce `setup`: its options, then
+/// both directories
+pub fn cli_args(setup: &ComparisonSetup) -> Vec<String> {
+    let mut args = config_to_args(&setup_options(setup));
+    args.extend([setup.dir_a.clone(), setup.dir_b.clone()]);
+    args
+}
+
+/// The `dir-compare` command line reproducing `setup`, quoted for POSIX
+/// shells
+pub fn command_line(setup: &ComparisonSetup) -> String {
+    let mut line = String::from("dir-compare");
+    for arg in cli_args(setup) {
+        line.push(' ');
+        line.push_str(&shell_quote(&arg));
+    }
+    line
+}
+
+/// Quotes `arg` for a POSIX shell; arguments made only of characters that
+/// are never special are left as they are
+pub fn shell_quote(arg: &str) -> String {
+    let plain = !arg.is_empty()
+        && arg
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "_-+=.,/:@%".contains(c));
+    if plain {
+        arg.to_string()
+    } else {
+        format!("'{}'", arg.replace('\'', r"'\''"))
+    }
+}
+
+/// A config file for `dir-compare --config` reproducing `setup`; the
+/// directories are recorded in a comment, since config files hold options
+/// only
+pub fn settings_toml(setup: &ComparisonSetup) -> String {
+    let mut text = String::from("# dir-compare settings, for use with --config\n");
+    for (label, dir) in [("A", &setup.dir_a), ("B", &setup.dir_b)] {
+        let dir = dir.replace(['\n', '\r'], " ");
+        text.push_str(&format!("# Directory {}: {}\n", label, dir));
+    }
+    text.push_str(&format_config(&setup_options(setup)));
+    text
+}
@@ -1,12 +1,45 @@
+pub mod demo;
 pub mod dialog;
+pub mod diff_view;
+pub mod dropped_paths;
+pub mod export;
+pub mod file_manager;
+pub mod filter_bar;
+pub mod flat_view;
+pub mod ignore_path;
+pub mod invocation;
+pub mod log_panel;
+pub mod option_widgets;
+pub mod pair_settings;
+pub mod progress;
+pub mod recent;
+pub mod settings;
+pub mod staleness;
+pub mod strings;
+pub mod table_view;
+pub mod tabs;
 pub mod test_utils;
 pub mod theme;
 pub mod tree_view;
+pub mod worker;
 
 // Re-export commonly used items for tests
+pub use demo::DemoFixture;
 pub use dialog::{FileDialogProvider, MockFileDialog, NativeFileDialog};
+pub use dropped_paths::{DropTarget, assign_dropped_paths};
+pub use export::ReportFormat;
+pub use ignore_path::{IgnorePathError, resolve_ignore_path};
+pub use log_panel::LogPanel;
+pub use pair_settings::{PairKey, PairSettings, RestoreWarning, SavedPairs};
+pub use progress::{ComparisonMessage, ProgressUpdate};
+pub use recent::{RecentComparison, RecentComparisons};
+pub use settings::{Settings, ViewMode, load_settings, save_settings, update_settings};
+pub use strings::{Locale, Strings};
+pub use table_view::{ResultCategory, ResultRow, SortColumn, SortDirection, SortState};
+pub use tabs::{GlobalState, TabId, TabSender, TabState, Tabs, TreeCache};
 pub use theme::{Theme, load_theme, save_theme};
-pub use tree_view::{FileTreeNode, render_tree};
+pub use tree_view::{FileTreeNode, TreeAction, TreeExpansion, TreeRow, TreeRows, render_rows};
+pub use worker::{WorkerKind, spawn_worker};
 
 /// Validates that a path is a non-empty string pointing to an existing directory
 pub fn validate_path(path: &str) -> bool {
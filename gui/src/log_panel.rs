@@ -0,0 +1,115 @@
+//! In-app log viewer.
+//!
+//! The panel registers a [`LogBuffer`] with the core logger, so records from
+//! the UI and from comparison workers are kept even while the panel is
+//! closed. Only the most recent [`LOG_CAPACITY`] records are held.
+
+use dir_compare_core::{LogBuffer, LogEntry, LogLevel, SinkId, logger};
+use eframe::egui;
+
+use crate::strings::Strings;
+
+/// Number of records kept by the panel
+pub const LOG_CAPACITY: usize = 5000;
+
+/// Levels offered by the level filter, most verbose first
+const LEVELS: [LogLevel; 4] = [
+    LogLevel::Debug,
+    LogLevel::Info,
+    LogLevel::Warn,
+    LogLevel::Error,
+];
+
+/// State of the log panel and the sink feeding it
+pub struct LogPanel {
+    /// Whether the panel is shown
+    pub open: bool,
+    /// Records below this level are hidden
+    pub min_level: LogLevel,
+    buffer: LogBuffer,
+    sink: Option<SinkId>,
+}
+
+impl LogPanel {
+    /// Creates the panel and registers its buffer with the global logger
+    pub fn register() -> Self {
+        let buffer = LogBuffer::new(LOG_CAPACITY);
+        let sink = logger::add_sink(Box::new(buffer.clone()));
+        Self {
+            open: false,
+            min_level: LogLevel::Debug,
+            buffer,
+            sink: Some(sink),
+        }
+    }
+
+    /// The records received so far
+    pub fn buffer(&self) -> &LogBuffer {
+        &self.buffer
+    }
+
+    /// Stops receiving records; the ones already held are kept
+    pub fn unregister(&mut self) {
+        if let Some(sink) = self.sink.take() {
+            logger::remove_sink(sink);
+        }
+    }
+
+    /// Records shown with the current level filter, oldest first
+    pub fn visible_entries(&self) -> Vec<LogEntry> {
+        self.buffer.entries(self.min_level)
+    }
+
+    /// Draws the level filter, the copy and clear buttons and the records
+    pub fn show(&mut self, ui: &mut egui::Ui, strings: &Strings) {
+        let entries = self.visible_entries();
+        ui.horizontal(|ui| {
+            ui.strong(strings.get("log.title"));
+            ui.label(strings.get("log.level"));
+            egui::ComboBox::from_id_source("log_level")
+                .selected_text(self.min_level.as_str())
+                .show_ui(ui, |ui| {
+                    for level in LEVELS {
+                        ui.selectable_value(&mut self.min_level, level, level.as_str());
+                    }
+                });
+            if ui.button(strings.get("log.copy_all")).clicked() {
+                ui.output_mut(|output| output.copied_text = copy_text(&entries));
+            }
+            if ui.button(strings.get("log.clear")).clicked() {
+                self.buffer.clear();
+            }
+        });
+
+        if entries.is_empty() {
+            ui.label(strings.get("log.empty"));
+            return;
+        }
+        let row_height = ui.text_style_height(&egui::TextStyle::Monospace);
+        egui::ScrollArea::vertical()
+            .max_height(200.0)
+            .auto_shrink([false, true])
+            .stick_to_bottom(true)
+            .show_rows(ui, row_height, entries.len(), |ui, rows| {
+                for entry in &entries[rows] {
+                    let text = egui::RichText::new(entry.to_string()).monospace();
+                    ui.label(match entry.level {
+                        LogLevel::Error => text.color(egui::Color32::RED),
+                        LogLevel::Warn => text.color(egui::Color32::from_rgb(255, 165, 0)),
+                        LogLevel::Info | LogLevel::Debug => text,
+                    });
+                }
+            });
+    }
+}
+
+impl Drop for LogPanel {
+    fn drop(&mut self) {
+        self.unregister();
+    }
+}
+
+/// Joins the records into the text put on the clipboard, one per line
+pub fn copy_text(entries: &[LogEntry]) -> String {
+    entries.iter().map(|entry| format!("{}\n", entry)).collect()
+}
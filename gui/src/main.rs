@@ -1,373 +1,1422 @@
 use dir_compare_gui::{
+    demo::DemoFixture,
     dialog::{FileDialogProvider, NativeFileDialog},
-    theme::{load_theme, save_theme, Theme},
-    tree_view, validate_path,
+    diff_view,
+    dropped_paths::{self, DropTarget},
+    file_manager::open_in_file_manager,
+    flat_view,
+    ignore_path::{IgnorePathError, resolve_ignore_path},
+    invocation::{self, ComparisonSetup},
+    log_panel::LogPanel,
+    option_widgets,
+    pair_settings::{self, SavedPairs},
+    progress::{ComparisonMessage, ProgressUpdate},
+    recent::RecentComparisons,
+    settings::{Settings, ViewMode, load_settings, save_settings, update_settings},
+    staleness::{self, ComparisonSnapshot, StalenessMonitor},
+    strings::{Locale, Strings},
+    table_view::{self, ResultCategory, ResultSelection},
+    tabs::{GlobalState, TabId, TabSender, TabState, Tabs, TreeCache},
+    theme::{Theme, apply_theme, save_theme},
+    tree_view::{self, TreeAction},
+    validate_path,
+    worker::{WorkerKind, spawn_worker},
 };
 
 use dir_compare_core::{
-    compare_directories, ComparisonResult, ComparisonStrategy, ComparisonStrategyType, Entry,
-    FastHashStrategy, FilenameOnlyStrategy, FilenameSizeStrategy, SampledHashStrategy,
+    Comparison, ComparisonStats, ComparisonStrategyType, FileWarning, Progress, logger,
+    output::{format_bytes, format_elapsed},
+    strategy_defaults,
 };
 use eframe::egui;
-use std::sync::mpsc::{channel, Receiver};
-use tree_view::FileTreeNode;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::{Duration, Instant, SystemTime};
+
+/// Minimum time between two progress messages from the comparison thread
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Longest path shown under the progress bar, in characters
+const PROGRESS_PATH_CHARS: usize = 60;
+
+/// Depth offered when the depth limit is switched on
+const DEFAULT_MAX_DEPTH: usize = 2;
+
+/// System fonts providing CJK glyphs, which egui's bundled fonts lack
+const CJK_FONT_PATHS: [&str; 5] = [
+    "/usr/share/fonts/opentype/noto/NotoSansCJK-Regular.ttc",
+    "/usr/share/fonts/noto-cjk/NotoSansCJK-Regular.ttc",
+    "/usr/share/fonts/google-noto-cjk/NotoSansCJK-Regular.ttc",
+    "/System/Library/Fonts/ヒラギノ角ゴシック W3.ttc",
+    "C:\\Windows\\Fonts\\msgothic.ttc",
+];
 
 fn main() -> eframe::Result<()> {
     let options = eframe::NativeOptions {
         viewport: egui::ViewportBuilder::default()
             .with_inner_size([800.0, 600.0])
             .with_title("dir-compare"),
+        // Report the OS appearance for the System theme, also on Linux
+        follow_system_theme: true,
         ..Default::default()
     };
 
-    // Load saved theme or use System default
-    let saved_theme = load_theme().unwrap_or(Theme::System);
+    // Load the saved theme and last comparison inputs, or the defaults
+    let settings = load_settings();
 
     eframe::run_native(
         "dir-compare",
         options,
         Box::new(move |cc| {
             // Apply saved theme on startup
-            cc.egui_ctx.set_visuals(saved_theme.to_visuals());
-            Box::new(DirCompareApp::new(saved_theme))
+            cc.egui_ctx
+                .set_visuals(settings.theme.to_visuals(&cc.integration_info));
+            if settings.locale == Locale::Japanese {
+                install_cjk_fallback_font(&cc.egui_ctx);
+            }
+            Box::new(DirCompareApp::new(&settings))
         }),
     )
 }
 
-/// Application state that can be inspected and modified by tests
-pub struct AppState {
-    pub dir_a_path: String,
-    pub dir_b_path: String,
-    pub ignore_file_path: Option<String>,
-    pub comparison_method: ComparisonStrategyType,
-    pub results: Option<ComparisonResult>,
-    pub tree_cache: Option<TreeCache>,
-    pub theme: Theme,
-    pub is_comparing: bool,
-    pub comparison_receiver: Option<Receiver<Result<ComparisonResult, String>>>,
-    pub error_message: Option<String>,
+/// Adds the first available system CJK font as a fallback for all text
+fn install_cjk_fallback_font(ctx: &egui::Context) {
+    let Some(data) = CJK_FONT_PATHS
+        .iter()
+        .find_map(|path| std::fs::read(path).ok())
+    else {
+        logger::warn("No CJK font found; Japanese text may not render correctly");
+        return;
+    };
+    let mut fonts = egui::FontDefinitions::default();
+    fonts
+        .font_data
+        .insert("cjk_fallback".to_owned(), egui::FontData::from_owned(data));
+    for family in [egui::FontFamily::Proportional, egui::FontFamily::Monospace] {
+        fonts
+            .families
+            .entry(family)
+            .or_default()
+            .push("cjk_fallback".to_owned());
+    }
+    ctx.set_fonts(fonts);
 }
 
-/// Cached tree view data for displaying comparison results
-pub struct TreeCache {
-    pub a_only: Vec<FileTreeNode>,
-    pub b_only: Vec<FileTreeNode>,
-    pub both: Vec<FileTreeNode>,
+/// Application state that can be inspected and modified by tests
+pub struct AppState {
+    pub global: GlobalState,
+    pub tabs: Tabs,
+    /// Checks whether the directories of the active tab changed since its
+    /// comparison
+    pub staleness: StalenessMonitor,
+    /// Settings of recently compared directory pairs
+    pub saved_pairs: SavedPairs,
+    /// The most recent successful comparisons, listed under File → Recent
+    pub recent: RecentComparisons,
 }
 
 /// Main application struct
 pub struct DirCompareApp {
     pub state: AppState,
+    /// Whether the first-run panel is shown
+    pub show_onboarding: bool,
+    /// Directories of the last demo comparison, deleted on exit
+    demo: Option<DemoFixture>,
+    /// Log viewer, receiving records from the start
+    log_panel: LogPanel,
+    /// Folder, file and save dialogs behind every browse and export button
+    dialog: Box<dyn FileDialogProvider>,
 }
 
 impl DirCompareApp {
-    fn new(initial_theme: Theme) -> Self {
+    fn new(settings: &Settings) -> Self {
+        let mut tabs = Tabs::new();
+        settings.apply_to(tabs.active_mut());
         Self {
             state: AppState {
-                dir_a_path: String::new(),
-                dir_b_path: String::new(),
-                ignore_file_path: None,
-                comparison_method: ComparisonStrategyType::FastHash,
-                results: None,
-                tree_cache: None,
-                theme: initial_theme,
-                is_comparing: false,
-                comparison_receiver: None,
-                error_message: None,
+                global: GlobalState {
+                    diff_max_lines: settings.diff_max_lines,
+                    ..GlobalState::new(settings.theme, settings.view_mode, settings.locale)
+                },
+                tabs,
+                staleness: StalenessMonitor::new(),
+                saved_pairs: settings.pairs.clone(),
+                recent: settings.recent.clone(),
             },
+            show_onboarding: !settings.onboarding_dismissed,
+            demo: None,
+            log_panel: LogPanel::register(),
+            dialog: Box::new(NativeFileDialog),
+        }
+    }
+
+    /// Whether `tab` compares the demo directories, which are deleted on
+    /// exit
+    fn is_demo(&self, tab: &TabState) -> bool {
+        self.demo
+            .as_ref()
+            .is_some_and(|demo| std::path::Path::new(&tab.dir_a_path) == demo.dir_a())
+    }
+
+    /// Remembers the inputs of `tab`, the saved pairs, the recent
+    /// comparisons and the preferences for the next launch, unless `tab` compares the demo directories
+    fn save_settings(&self, tab: &TabState) {
+        if !self.is_demo(tab) {
+            let global = &self.state.global;
+            save_settings(&Settings {
+                pairs: self.state.saved_pairs.clone(),
+                recent: self.state.recent.clone(),
+                diff_max_lines: global.diff_max_lines,
+                view_mode: global.view_mode,
+                locale: global.locale,
+                onboarding_dismissed: !self.show_onboarding,
+                ..Settings::from_tab(tab, global.theme)
+            });
+        }
+    }
+
+    /// Generates the demo directories and compares them by filename and
+    /// size, in a new tab unless the active one is unused.
+    fn start_demo(&mut self, ctx: &egui::Context) {
+        // The previous fixture lives at the same path; it must be deleted
+        // before the new one is written
+        self.demo = None;
+        let active = self.state.tabs.active();
+        if active.is_comparing || !active.dir_a_path.is_empty() || !active.dir_b_path.is_empty() {
+            self.state.tabs.add();
         }
+        let sender = self.state.tabs.sender(self.state.tabs.active().id());
+        let tab = self.state.tabs.active_mut();
+
+        let fixture = match DemoFixture::create() {
+            Ok(fixture) => fixture,
+            Err(e) => {
+                logger::error(&format!("Failed to create demo directories: {}", e));
+                let strings = &self.state.global.strings;
+                tab.error_message = Some(strings.format("error.demo_failed", &[("error", &e)]));
+                return;
+            }
+        };
+        tab.dir_a_path = fixture.dir_a().display().to_string();
+        tab.dir_b_path = fixture.dir_b().display().to_string();
+        tab.ignore_file_path = None;
+        tab.select_method(ComparisonStrategyType::FilenameSize);
+        self.demo = Some(fixture);
+        spawn_comparison(ctx, tab, sender, None);
     }
 }
 
-impl eframe::App for DirCompareApp {
-    fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
-        // Check for results
-        if let Some(rx) = &self.state.comparison_receiver {
-            if let Ok(result) = rx.try_recv() {
-                self.state.is_comparing = false;
-                self.state.comparison_receiver = None;
-                match result {
-                    Ok(res) => {
-                        let a_only = FileTreeNode::from_entries(&res.a_only);
-                        let b_only = FileTreeNode::from_entries(&res.b_only);
-                        let both_entries: Vec<Entry> =
-                            res.both.iter().map(|(a, _)| a.clone()).collect();
-                        let both = FileTreeNode::from_entries(&both_entries);
-
-                        self.state.results = Some(res);
-                        self.state.tree_cache = Some(TreeCache {
-                            a_only,
-                            b_only,
-                            both,
-                        });
+/// A tab bar click, applied after the bar is drawn
+enum TabAction {
+    Add,
+    Switch(TabId),
+    Close(TabId),
+}
+
+/// Resolves the entered ignore file, or `None` if the field is empty.
+fn resolved_ignore_path(
+    input: Option<&str>,
+    dir_a: &str,
+) -> Option<Result<std::path::PathBuf, IgnorePathError>> {
+    let input = input?;
+    if input.trim().is_empty() {
+        return None;
+    }
+    let cwd = std::env::current_dir().unwrap_or_default();
+    Some(resolve_ignore_path(input, &cwd, dir_a))
+}
+
+/// Returns the localized name of a comparison method.
+fn method_label(strings: &Strings, method: ComparisonStrategyType) -> &str {
+    strings.get(match method {
+        ComparisonStrategyType::Filename => "method.filename",
+        ComparisonStrategyType::FilenameSize => "method.filename_size",
+        ComparisonStrategyType::FastHash => "method.fast_hash",
+        ComparisonStrategyType::Blake3 => "method.blake3",
+        ComparisonStrategyType::SampledHash => "method.sampled_hash",
+        ComparisonStrategyType::Mtime => "method.mtime",
+    })
+}
+
+/// A choice in the File → Recent menu
+enum RecentAction {
+    /// Fill the active tab with the entry at this index
+    Open(usize),
+    Clear,
+}
+
+/// Lists the recent comparisons, each with its method and age on hover
+fn recent_menu(
+    ui: &mut egui::Ui,
+    recent: &RecentComparisons,
+    strings: &Strings,
+) -> Option<RecentAction> {
+    let mut action = None;
+    let now = SystemTime::now();
+    for (index, entry) in recent.entries.iter().enumerate() {
+        let method = match pair_settings::method_by_name(&entry.method) {
+            Some(method) => method_label(strings, method),
+            None => &entry.method,
+        };
+        let age = now.duration_since(entry.compared_at()).unwrap_or_default();
+        let help = strings.format(
+            "recent.entry_help",
+            &[
+                ("method", &method),
+                ("age", &staleness::format_age(age, strings)),
+            ],
+        );
+        if ui.button(entry.label()).on_hover_text(help).clicked() {
+            action = Some(RecentAction::Open(index));
+            ui.close_menu();
+        }
+    }
+    ui.separator();
+    if ui.button(strings.get("recent.clear")).clicked() {
+        action = Some(RecentAction::Clear);
+        ui.close_menu();
+    }
+    action
+}
+
+/// Actions bound to keys, see [`handle_shortcuts`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Shortcut {
+    BrowseA,
+    BrowseB,
+    Compare,
+    Cancel,
+    Export,
+    FocusFilter,
+    Quit,
+}
+
+/// The keys of each shortcut; Ctrl is ⌘ on macOS
+const SHORTCUTS: [(Shortcut, egui::KeyboardShortcut); 8] = {
+    use egui::{Key, KeyboardShortcut, Modifiers};
+    let command = Modifiers::COMMAND;
+    [
+        (Shortcut::BrowseA, KeyboardShortcut::new(command, Key::O)),
+        (
+            Shortcut::BrowseB,
+            KeyboardShortcut::new(command.plus(Modifiers::SHIFT), Key::O),
+        ),
+        (Shortcut::Compare, KeyboardShortcut::new(command, Key::R)),
+        (
+            Shortcut::Compare,
+            KeyboardShortcut::new(Modifiers::NONE, Key::Enter),
+        ),
+        (
+            Shortcut::Cancel,
+            KeyboardShortcut::new(Modifiers::NONE, Key::Escape),
+        ),
+        (Shortcut::Export, KeyboardShortcut::new(command, Key::E)),
+        (
+            Shortcut::FocusFilter,
+            KeyboardShortcut::new(command, Key::F),
+        ),
+        (Shortcut::Quit, KeyboardShortcut::new(command, Key::Q)),
+    ]
+};
+
+/// The keys of `shortcut` as shown in menus and tooltips, such as `Ctrl+E`
+fn shortcut_text(ctx: &egui::Context, shortcut: Shortcut) -> String {
+    SHORTCUTS
+        .iter()
+        .filter(|(bound, _)| *bound == shortcut)
+        .map(|(_, keys)| ctx.format_shortcut(keys))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Ids of the path fields of directories A and B
+fn path_field_ids() -> [egui::Id; 2] {
+    [egui::Id::new("dir_a_path"), egui::Id::new("dir_b_path")]
+}
+
+/// Id of the field filtering the results tree
+fn tree_filter_id() -> egui::Id {
+    egui::Id::new("tree_filter")
+}
+
+/// The shortcut pressed this frame.
+///
+/// Modifiers must match exactly, so Ctrl+Shift+O does not also count as
+/// Ctrl+O. Enter only counts while no widget or a path field has the
+/// keyboard focus, so it does not compare while typing in a filter.
+fn pressed_shortcut(ctx: &egui::Context) -> Option<Shortcut> {
+    let focused = ctx.memory(|memory| memory.focused());
+    let enter_compares = focused.is_none_or(|id| path_field_ids().contains(&id));
+    ctx.input(|input| {
+        SHORTCUTS
+            .iter()
+            .find(|(_, keys)| {
+                input.modifiers.matches_exact(keys.modifiers)
+                    && input.key_pressed(keys.logical_key)
+                    && (keys.logical_key != egui::Key::Enter || enter_compares)
+            })
+            .map(|(shortcut, _)| *shortcut)
+    })
+}
+
+/// Whether the directories of `tab` can be compared, which enables the
+/// Compare button
+fn can_compare(tab: &TabState) -> bool {
+    validate_path(&tab.dir_a_path) && validate_path(&tab.dir_b_path) && !tab.is_comparing
+}
+
+/// Carries out the shortcut pressed this frame if its action is enabled,
+/// following the same rules as the matching button or menu item.
+///
+/// Returns the shortcut acted on. Browsing and exporting need the file
+/// dialogs of the app, so for those the caller opens the dialog.
+fn handle_shortcuts(state: &mut AppState, ctx: &egui::Context) -> Option<Shortcut> {
+    let shortcut = pressed_shortcut(ctx)?;
+    let tab = state.tabs.active();
+    let enabled = match shortcut {
+        Shortcut::BrowseA | Shortcut::BrowseB | Shortcut::Quit => true,
+        Shortcut::Compare => can_compare(tab),
+        Shortcut::Cancel => tab.cancel_comparison(),
+        Shortcut::Export => tab.results.is_some(),
+        Shortcut::FocusFilter => {
+            tab.tree_cache.is_some() && state.global.view_mode == ViewMode::Tree
+        }
+    };
+    if !enabled {
+        return None;
+    }
+    match shortcut {
+        Shortcut::Compare => {
+            let sender = state.tabs.sender(tab.id());
+            let strings = &state.global.strings;
+            start_compare(ctx, state.tabs.active_mut(), sender, strings);
+        }
+        Shortcut::FocusFilter => ctx.memory_mut(|memory| memory.request_focus(tree_filter_id())),
+        Shortcut::Quit => ctx.send_viewport_cmd(egui::ViewportCommand::Close),
+        Shortcut::BrowseA | Shortcut::BrowseB | Shortcut::Cancel | Shortcut::Export => {}
+    }
+    Some(shortcut)
+}
+
+/// Compares the directories of `tab` on a worker thread, which reports
+/// progress and the result through `sender`.
+fn spawn_comparison(
+    ctx: &egui::Context,
+    tab: &mut TabState,
+    sender: TabSender,
+    ignore_file_path: Option<PathBuf>,
+) {
+    if tab.flat_mode {
+        spawn_flat_comparison(ctx, tab, sender, ignore_file_path);
+        return;
+    }
+    let cancel = tab.start_comparison();
+
+    let dir_a = tab.dir_a_path.clone();
+    let dir_b = tab.dir_b_path.clone();
+    let method = tab.comparison_method;
+    let strategy_options = tab.strategy_options.clone();
+    let respect_gitignore = tab.respect_gitignore;
+    let max_depth = tab.max_depth;
+    let ignore_hidden = tab.ignore_hidden;
+    let detect_renames = tab.detect_renames;
+    let repaint = ctx.clone();
+
+    spawn_worker(tab, sender, WorkerKind::Hierarchy, move |sender| {
+        let case_insensitive = strategy_defaults::case_insensitive(
+            None,
+            std::path::Path::new(&dir_a),
+            std::path::Path::new(&dir_b),
+        );
+        let settings = match method.settings(case_insensitive, &strategy_options) {
+            Ok(settings) => settings,
+            Err(e) => {
+                sender.send(ComparisonMessage::Finished(Err(e.to_string())));
+                repaint.request_repaint();
+                return;
+            }
+        };
+        let strategy = settings.build();
+
+        let last_sent: Mutex<Option<Instant>> = Mutex::new(None);
+        let on_progress = |progress: &Progress| {
+            let update = ProgressUpdate::from(progress);
+            let mut last_sent = last_sent.lock().unwrap();
+            if !update.is_last() && last_sent.is_some_and(|t| t.elapsed() < PROGRESS_INTERVAL) {
+                return;
+            }
+            *last_sent = Some(Instant::now());
+            sender.send(ComparisonMessage::Progress(update));
+            repaint.request_repaint();
+        };
+        let started = Instant::now();
+        let result = Comparison::new(&dir_a, &dir_b)
+            .strategy(strategy.as_ref())
+            .ignore_file(ignore_file_path)
+            .cancel(cancel)
+            .respect_gitignore(respect_gitignore)
+            .max_depth(max_depth)
+            .ignore_hidden(ignore_hidden)
+            .detect_renames(detect_renames)
+            .progress(&on_progress)
+            .run();
+
+        logger::debug(&format!(
+            "Compared {} and {} in {:.2?}",
+            dir_a,
+            dir_b,
+            started.elapsed()
+        ));
+        if result.is_ok() {
+            send_snapshot(sender, &dir_a, &dir_b);
+        }
+        // Exported reports name the directories and the method in their header
+        let result = result.map(|mut result| {
+            result.context = Some(
+                settings.report_context(std::path::Path::new(&dir_a), std::path::Path::new(&dir_b)),
+            );
+            result
+        });
+        let message = ComparisonMessage::Finished(result.map_err(|e| e.to_string()));
+        sender.send(message);
+        repaint.request_repaint();
+    });
+}
+
+/// Groups the files of `tab` by content on a worker thread, which sends the
+/// result through `sender`; flat mode reports no progress.
+fn spawn_flat_comparison(
+    ctx: &egui::Context,
+    tab: &mut TabState,
+    sender: TabSender,
+    ignore_file_path: Option<PathBuf>,
+) {
+    tab.start_comparison();
+    // Grouping by content does not stop early
+    tab.cancel = None;
+
+    let dir_a = tab.dir_a_path.clone();
+    let dir_b = tab.dir_b_path.clone();
+    let full_hash = tab.full_hash;
+    let respect_gitignore = tab.respect_gitignore;
+    let max_depth = tab.max_depth;
+    let ignore_hidden = tab.ignore_hidden;
+    let repaint = ctx.clone();
+
+    spawn_worker(tab, sender, WorkerKind::Flat, move |sender| {
+        let started = Instant::now();
+        let result = Comparison::new(&dir_a, &dir_b)
+            .ignore_file(ignore_file_path)
+            .full_hash(full_hash)
+            .respect_gitignore(respect_gitignore)
+            .max_depth(max_depth)
+            .ignore_hidden(ignore_hidden)
+            .run_flat();
+        logger::debug(&format!(
+            "Grouped {} and {} by content in {:.2?}",
+            dir_a,
+            dir_b,
+            started.elapsed()
+        ));
+        if result.is_ok() {
+            send_snapshot(sender, &dir_a, &dir_b);
+        }
+        let message = ComparisonMessage::FinishedFlat(result.map_err(|e| e.to_string()));
+        sender.send(message);
+        repaint.request_repaint();
+    });
+}
+
+/// Fingerprints the compared roots on the worker thread, so later checks
+/// can tell whether the results went stale.
+fn send_snapshot(sender: &TabSender, dir_a: &str, dir_b: &str) {
+    let started = Instant::now();
+    let snapshot =
+        ComparisonSnapshot::capture(std::path::Path::new(dir_a), std::path::Path::new(dir_b));
+    logger::debug(&format!(
+        "Fingerprinted {} and {} in {:.2?}",
+        dir_a,
+        dir_b,
+        started.elapsed()
+    ));
+    sender.send(ComparisonMessage::Snapshot(snapshot));
+}
+
+/// Compares the directories of `tab` after resolving its ignore file,
+/// reporting an ignore file that cannot be found instead.
+fn start_compare(ctx: &egui::Context, tab: &mut TabState, sender: TabSender, strings: &Strings) {
+    let resolved = resolved_ignore_path(tab.ignore_file_path.as_deref(), &tab.dir_a_path);
+    if let Some(Err(e)) = resolved {
+        logger::error(&e.to_string());
+        tab.error_message = Some(ignore_error_message(strings, &e));
+    } else {
+        let ignore_file_path = resolved.and_then(Result::ok);
+        spawn_comparison(ctx, tab, sender, ignore_file_path);
+    }
+}
+
+/// Lists the warnings of a comparison under a collapsed header.
+fn show_warnings(ui: &mut egui::Ui, warnings: &[FileWarning], strings: &Strings) {
+    if warnings.is_empty() {
+        return;
+    }
+    let title = strings.format("status.warnings", &[("count", &warnings.len())]);
+    egui::CollapsingHeader::new(title)
+        .id_source("warnings")
+        .show(ui, |ui| {
+            egui::ScrollArea::vertical()
+                .max_height(150.0)
+                .show(ui, |ui| {
+                    for warning in warnings {
+                        ui.label(warning.to_string());
                     }
-                    Err(e) => self.state.error_message = Some(e),
+                });
+        });
+}
+
+/// Describes the bytes of both directories and how long comparing them took
+fn stats_label(strings: &Strings, stats: &ComparisonStats) -> String {
+    strings.format(
+        "status.stats",
+        &[
+            ("bytes", &format_bytes(stats.bytes_a + stats.bytes_b)),
+            ("elapsed", &format_elapsed(stats.elapsed)),
+        ],
+    )
+}
+
+/// Builds the localized message for an ignore file that was not found.
+fn ignore_error_message(strings: &Strings, error: &IgnorePathError) -> String {
+    let locations: Vec<String> = error
+        .tried
+        .iter()
+        .map(|p| p.display().to_string())
+        .collect();
+    strings.format(
+        "error.ignore_not_found",
+        &[("path", &error.input), ("locations", &locations.join(", "))],
+    )
+}
+
+impl eframe::App for DirCompareApp {
+    fn update(&mut self, ctx: &egui::Context, frame: &mut eframe::Frame) {
+        // Follow changes of the OS appearance, and undo eframe switching the
+        // visuals to it when another theme was chosen
+        apply_theme(ctx, self.state.global.theme, frame.info());
+
+        // Deliver progress and results to the tabs that started the comparisons
+        for id in self.state.tabs.receive_messages() {
+            if let Some(tab) = self.state.tabs.get(id) {
+                let succeeded = tab.error_message.is_none()
+                    && (tab.results.is_some() || tab.flat_results.is_some());
+                if succeeded && !self.is_demo(tab) {
+                    self.state.saved_pairs.remember_tab(tab);
+                    self.state.recent.record_tab(tab);
                 }
+                self.save_settings(tab);
             }
         }
+        // Keep drawing while comparing, also when no progress arrives
+        if self.state.tabs.iter().any(|tab| tab.is_comparing) {
+            ctx.request_repaint_after(PROGRESS_INTERVAL);
+        }
+
+        // Check whether the active tab's directories changed since its comparison
+        let focus_gained = ctx.input(|input| {
+            input
+                .events
+                .iter()
+                .any(|event| matches!(event, egui::Event::WindowFocused(true)))
+        });
+        let staleness = &mut self.state.staleness;
+        staleness.poll();
+        let now = Instant::now();
+        if staleness.is_due(now, focus_gained) {
+            let repaint = ctx.clone();
+            staleness.start_check(self.state.tabs.active(), now, move || {
+                repaint.request_repaint()
+            });
+        }
+        if self.state.tabs.active().snapshot.is_some() {
+            ctx.request_repaint_after(staleness::CHECK_INTERVAL);
+        }
 
+        let mut tab_action = None;
+        let mut run_demo = false;
+        let mut export = false;
+        let mut export_settings = false;
+        let mut recent_action = None;
+        let mut reveal = None;
+        match handle_shortcuts(&mut self.state, ctx) {
+            Some(Shortcut::BrowseA) => self
+                .state
+                .tabs
+                .active_mut()
+                .browse_dir_a(self.dialog.as_ref()),
+            Some(Shortcut::BrowseB) => self
+                .state
+                .tabs
+                .active_mut()
+                .browse_dir_b(self.dialog.as_ref()),
+            Some(Shortcut::Export) => export = true,
+            _ => {}
+        }
         egui::TopBottomPanel::top("top_panel").show(ctx, |ui| {
+            let strings = &self.state.global.strings;
             egui::menu::bar(ui, |ui| {
-                ui.menu_button("File", |ui| {
-                    if ui.button("Quit").clicked() {
+                ui.menu_button(strings.get("menu.file"), |ui| {
+                    let has_results = self.state.tabs.active().results.is_some();
+                    if ui
+                        .add_enabled(
+                            has_results,
+                            egui::Button::new(strings.get("menu.export"))
+                                .shortcut_text(shortcut_text(ctx, Shortcut::Export)),
+                        )
+                        .clicked()
+                    {
+                        export = true;
+                        ui.close_menu();
+                    }
+                    ui.separator();
+                    if ui
+                        .button(strings.get("menu.copy_invocation"))
+                        .on_hover_text(strings.get("menu.copy_invocation.help"))
+                        .clicked()
+                    {
+                        let cwd = std::env::current_dir().unwrap_or_default();
+                        let tab = self.state.tabs.active_mut();
+                        let setup = ComparisonSetup::from_tab(tab, &cwd);
+                        ctx.output_mut(|output| {
+                            output.copied_text = invocation::command_line(&setup)
+                        });
+                        tab.status_message =
+                            Some(strings.get("status.invocation_copied").to_string());
+                        ui.close_menu();
+                    }
+                    if ui.button(strings.get("menu.export_settings")).clicked() {
+                        export_settings = true;
+                        ui.close_menu();
+                    }
+                    let can_fill = !self.state.tabs.active().is_comparing;
+                    ui.add_enabled_ui(can_fill && !self.state.recent.is_empty(), |ui| {
+                        ui.menu_button(strings.get("menu.recent"), |ui| {
+                            recent_action = recent_menu(ui, &self.state.recent, strings);
+                        });
+                    });
+                    ui.separator();
+                    let quit = egui::Button::new(strings.get("menu.quit"))
+                        .shortcut_text(shortcut_text(ctx, Shortcut::Quit));
+                    if ui.add(quit).clicked() {
                         ctx.send_viewport_cmd(egui::ViewportCommand::Close);
                     }
                 });
-                ui.menu_button("View", |ui| {
-                    ui.menu_button("Theme", |ui| {
-                        let old_theme = self.state.theme;
+                ui.menu_button(strings.get("menu.view"), |ui| {
+                    ui.menu_button(strings.get("menu.theme"), |ui| {
+                        let old_theme = self.state.global.theme;
                         if ui
-                            .radio_value(&mut self.state.theme, Theme::Light, "Light")
+                            .radio_value(
+                                &mut self.state.global.theme,
+                                Theme::Light,
+                                strings.get("theme.light"),
+                            )
                             .clicked()
                         {
-                            ctx.set_visuals(egui::Visuals::light());
+                            apply_theme(ctx, Theme::Light, frame.info());
                             save_theme(Theme::Light);
                         }
                         if ui
-                            .radio_value(&mut self.state.theme, Theme::Dark, "Dark")
+                            .radio_value(
+                                &mut self.state.global.theme,
+                                Theme::Dark,
+                                strings.get("theme.dark"),
+                            )
                             .clicked()
                         {
-                            ctx.set_visuals(egui::Visuals::dark());
+                            apply_theme(ctx, Theme::Dark, frame.info());
                             save_theme(Theme::Dark);
                         }
                         if ui
-                            .radio_value(&mut self.state.theme, Theme::System, "System")
-                            .on_hover_text("Reset to system theme preference. May require restart on some platforms.")
+                            .radio_value(
+                                &mut self.state.global.theme,
+                                Theme::System,
+                                strings.get("theme.system"),
+                            )
+                            .on_hover_text(strings.get("theme.system_tooltip"))
                             .clicked()
                         {
-                            ctx.set_visuals(egui::Visuals::default());
+                            apply_theme(ctx, Theme::System, frame.info());
                             save_theme(Theme::System);
                         }
-                        if old_theme != self.state.theme {
+                        if old_theme != self.state.global.theme {
                             ui.close_menu();
                         }
                     });
+                    ui.menu_button(strings.get("menu.results"), |ui| {
+                        let old_mode = self.state.global.view_mode;
+                        ui.radio_value(
+                            &mut self.state.global.view_mode,
+                            ViewMode::Tree,
+                            strings.get("view.tree"),
+                        );
+                        ui.radio_value(
+                            &mut self.state.global.view_mode,
+                            ViewMode::Table,
+                            strings.get("view.table"),
+                        );
+                        if old_mode != self.state.global.view_mode {
+                            update_settings(|settings| {
+                                settings.view_mode = self.state.global.view_mode
+                            });
+                            ui.close_menu();
+                        }
+                    });
+                    if ui
+                        .checkbox(&mut self.log_panel.open, strings.get("menu.log"))
+                        .clicked()
+                    {
+                        ui.close_menu();
+                    }
+                    ui.menu_button(strings.get("menu.language"), |ui| {
+                        let old_locale = self.state.global.locale;
+                        for locale in Locale::ALL {
+                            ui.radio_value(
+                                &mut self.state.global.locale,
+                                locale,
+                                locale.display_name(),
+                            );
+                        }
+                        if old_locale != self.state.global.locale {
+                            ui.close_menu();
+                        }
+                    });
+                });
+                ui.menu_button(strings.get("menu.help"), |ui| {
+                    if ui.button(strings.get("onboarding.try_demo")).clicked() {
+                        run_demo = true;
+                        ui.close_menu();
+                    }
+                    if ui.button(strings.get("menu.show_intro")).clicked() {
+                        self.show_onboarding = true;
+                        update_settings(|settings| settings.onboarding_dismissed = false);
+                        ui.close_menu();
+                    }
                 });
             });
+
+            // Tab bar
+            ui.horizontal_wrapped(|ui| {
+                let active = self.state.tabs.active().id();
+                for tab in self.state.tabs.iter() {
+                    if tab.is_comparing {
+                        ui.spinner();
+                    }
+                    if ui
+                        .selectable_label(tab.id() == active, tab.title(strings))
+                        .clicked()
+                    {
+                        tab_action = Some(TabAction::Switch(tab.id()));
+                    }
+                    if ui
+                        .small_button("×")
+                        .on_hover_text(strings.get("tabs.close"))
+                        .clicked()
+                    {
+                        tab_action = Some(TabAction::Close(tab.id()));
+                    }
+                    ui.separator();
+                }
+                if ui
+                    .small_button("+")
+                    .on_hover_text(strings.get("tabs.add"))
+                    .clicked()
+                {
+                    tab_action = Some(TabAction::Add);
+                }
+            });
         });
 
+        match tab_action {
+            Some(TabAction::Add) => {
+                self.state.tabs.add();
+            }
+            Some(TabAction::Switch(id)) => {
+                self.state.tabs.switch(id);
+            }
+            Some(TabAction::Close(id)) => {
+                self.state.tabs.close(id);
+            }
+            None => {}
+        }
+
+        if self.show_onboarding {
+            egui::TopBottomPanel::top("onboarding_panel").show(ctx, |ui| {
+                let strings = &self.state.global.strings;
+                ui.add_space(4.0);
+                ui.strong(strings.get("onboarding.title"));
+                ui.label(strings.get("onboarding.body"));
+                ui.horizontal(|ui| {
+                    if ui
+                        .button(strings.get("onboarding.try_demo"))
+                        .on_hover_text(strings.get("onboarding.try_demo.help"))
+                        .clicked()
+                    {
+                        run_demo = true;
+                    }
+                    if ui.button(strings.get("onboarding.dismiss")).clicked() {
+                        self.show_onboarding = false;
+                        update_settings(|settings| settings.onboarding_dismissed = true);
+                    }
+                });
+                ui.add_space(4.0);
+            });
+        }
+        if run_demo {
+            self.start_demo(ctx);
+        }
+
+        // Rebuild the string table after a language switch
+        let global = &mut self.state.global;
+        if global.locale != global.strings.locale() {
+            global.strings = Strings::new(global.locale);
+            update_settings(|settings| settings.locale = global.locale);
+            if global.locale == Locale::Japanese {
+                install_cjk_fallback_font(ctx);
+            }
+        }
+
         egui::TopBottomPanel::bottom("bottom_panel").show(ctx, |ui| {
-            if let Some(results) = &self.state.results {
+            let strings = &self.state.global.strings;
+            let tab = self.state.tabs.active();
+            if let Some(results) = tab.displayed_results() {
                 ui.horizontal(|ui| {
-                    ui.label(format!("A Only: {}", results.a_only.len()));
+                    ui.label(strings.format("status.a_only", &[("count", &results.a_only.len())]));
+                    ui.separator();
+                    ui.label(strings.format("status.b_only", &[("count", &results.b_only.len())]));
                     ui.separator();
-                    ui.label(format!("B Only: {}", results.b_only.len()));
+                    ui.label(
+                        strings.format("status.modified", &[("count", &results.modified.len())]),
+                    );
                     ui.separator();
-                    ui.label(format!("Both: {}", results.both.len()));
+                    ui.label(strings.format("status.both", &[("count", &results.both.len())]));
                     ui.separator();
-                    ui.label(format!(
-                        "Total: {}",
-                        results.a_only.len() + results.b_only.len() + results.both.len()
+                    ui.label(strings.format(
+                        "status.total",
+                        &[(
+                            "count",
+                            &(results.a_only.len()
+                                + results.b_only.len()
+                                + results.modified.len()
+                                + results.both.len()),
+                        )],
                     ));
+                    ui.separator();
+                    ui.label(stats_label(strings, &results.stats));
+                    if let Some((shown, total)) =
+                        tab.tree_cache.as_ref().and_then(TreeCache::filter_counts)
+                    {
+                        ui.separator();
+                        ui.label(
+                            strings
+                                .format("status.filtered", &[("shown", &shown), ("total", &total)]),
+                        );
+                    }
+                    if let Some(message) = &tab.status_message {
+                        ui.separator();
+                        ui.label(message);
+                    }
+                });
+                show_warnings(ui, &results.warnings, strings);
+            } else if let Some(flat) = &tab.flat_results {
+                let result = &flat.result;
+                ui.horizontal(|ui| {
+                    ui.label(strings.format("status.files_a", &[("count", &result.total_files_a)]));
+                    ui.separator();
+                    ui.label(strings.format("status.files_b", &[("count", &result.total_files_b)]));
+                    ui.separator();
+                    ui.label(
+                        strings.format("status.unique_hashes", &[("count", &result.unique_hashes)]),
+                    );
+                    ui.separator();
+                    ui.label(
+                        strings.format("status.duplicates", &[("count", &result.duplicate_count)]),
+                    );
+                    ui.separator();
+                    ui.label(stats_label(strings, &result.stats));
                 });
+                show_warnings(ui, &result.warnings, strings);
             } else {
-                ui.label("Ready");
+                ui.label(strings.get("status.ready"));
             }
         });
 
+        if self.log_panel.open {
+            egui::TopBottomPanel::bottom("log_panel")
+                .resizable(true)
+                .show(ctx, |ui| {
+                    self.log_panel.show(ui, &self.state.global.strings);
+                });
+        }
+
+        let diff_max_lines = self.state.global.diff_max_lines;
+        self.state
+            .tabs
+            .active_mut()
+            .update_diff_view(diff_max_lines);
+        let tab = self.state.tabs.active();
+        let selected_row = match (&tab.tree_cache, &tab.selection) {
+            (Some(cache), Some(selection)) => table_view::find_row(&cache.rows, selection),
+            _ => None,
+        };
+        if let Some(row) = selected_row {
+            egui::TopBottomPanel::bottom("details_panel").show(ctx, |ui| {
+                let strings = &self.state.global.strings;
+                ui.label(strings.get("details.title"));
+                table_view::render_details(ui, row, strings);
+                if let Some(view) = &tab.diff_view {
+                    diff_view::render_diff(ui, view, diff_max_lines, strings);
+                }
+            });
+        }
+
+        // Folders dropped onto the window, and where the pointer was
+        let (dropped, drop_pos) = ctx.input(|input| {
+            let paths: Vec<PathBuf> = input
+                .raw
+                .dropped_files
+                .iter()
+                .filter_map(|file| file.path.clone())
+                .collect();
+            (paths, input.pointer.hover_pos())
+        });
+
         egui::CentralPanel::default().show(ctx, |ui| {
+            let global = &mut self.state.global;
+            let strings = &global.strings;
+            let sender = self.state.tabs.sender(self.state.tabs.active().id());
+            let tab = self.state.tabs.active_mut();
+            let dialog = self.dialog.as_ref();
             ui.heading("dir-compare");
 
             ui.add_space(20.0);
 
             // Directory A
+            let (mut dir_a_rect, mut dir_b_rect) = (egui::Rect::NOTHING, egui::Rect::NOTHING);
+            let path_ids = path_field_ids();
             ui.horizontal(|ui| {
-                ui.label("Directory A:");
-                ui.text_edit_singleline(&mut self.state.dir_a_path);
+                ui.label(strings.get("input.dir_a"));
+                let field = egui::TextEdit::singleline(&mut tab.dir_a_path).id(path_ids[0]);
+                dir_a_rect = ui.add(field).rect;
 
-                if ui.button("Browse...").clicked() {
-                    let dialog = NativeFileDialog;
-                    if let Some(path) = dialog.pick_folder() {
-                        self.state.dir_a_path = path.display().to_string();
-                    }
+                if ui
+                    .button(strings.get("input.browse"))
+                    .on_hover_text(shortcut_text(ctx, Shortcut::BrowseA))
+                    .clicked()
+                {
+                    tab.browse_dir_a(dialog);
                 }
 
-                if !self.state.dir_a_path.is_empty() {
-                    if validate_path(&self.state.dir_a_path) {
-                        ui.label("✅").on_hover_text("Valid directory");
+                if !tab.dir_a_path.is_empty() {
+                    if validate_path(&tab.dir_a_path) {
+                        ui.label("✅").on_hover_text(strings.get("input.valid_dir"));
                     } else {
-                        ui.label("❌").on_hover_text("Invalid directory");
+                        ui.label("❌")
+                            .on_hover_text(strings.get("input.invalid_dir"));
                     }
                 }
             });
 
-            ui.add_space(10.0);
+            // Swapping A and B
+            ui.horizontal(|ui| {
+                ui.add_enabled_ui(!tab.is_comparing, |ui| {
+                    if ui
+                        .small_button("⇄")
+                        .on_hover_text(strings.get("input.swap"))
+                        .clicked()
+                    {
+                        tab.swap_directories();
+                    }
+                });
+            });
 
             // Directory B
             ui.horizontal(|ui| {
-                ui.label("Directory B:");
-                ui.text_edit_singleline(&mut self.state.dir_b_path);
+                ui.label(strings.get("input.dir_b"));
+                let field = egui::TextEdit::singleline(&mut tab.dir_b_path).id(path_ids[1]);
+                dir_b_rect = ui.add(field).rect;
 
-                if ui.button("Browse...").clicked() {
-                    let dialog = NativeFileDialog;
-                    if let Some(path) = dialog.pick_folder() {
-                        self.state.dir_b_path = path.display().to_string();
-                    }
+                if ui
+                    .button(strings.get("input.browse"))
+                    .on_hover_text(shortcut_text(ctx, Shortcut::BrowseB))
+                    .clicked()
+                {
+                    tab.browse_dir_b(dialog);
                 }
 
-                if !self.state.dir_b_path.is_empty() {
-                    if validate_path(&self.state.dir_b_path) {
-                        ui.label("✅").on_hover_text("Valid directory");
+                if !tab.dir_b_path.is_empty() {
+                    if validate_path(&tab.dir_b_path) {
+                        ui.label("✅").on_hover_text(strings.get("input.valid_dir"));
                     } else {
-                        ui.label("❌").on_hover_text("Invalid directory");
+                        ui.label("❌")
+                            .on_hover_text(strings.get("input.invalid_dir"));
                     }
                 }
             });
 
+            // Settings remembered for this pair of directories
+            let offer = pair_settings::offer_for(&self.state.saved_pairs, tab)
+                .map(|(key, settings)| (key, settings.clone()));
+            if let Some((key, settings)) = offer {
+                ui.horizontal(|ui| {
+                    ui.label(strings.format("pair.prompt", &[("settings", &settings.summary())]));
+                    let apply = ui.button(strings.get("pair.apply")).clicked();
+                    let dismiss = ui.button(strings.get("pair.dismiss")).clicked();
+                    if apply {
+                        let warnings = settings.apply_to(tab);
+                        for warning in &warnings {
+                            logger::warn(&warning.to_string());
+                        }
+                        tab.status_message = warnings.first().map(|w| w.message(strings));
+                        tab.answered_pair = Some(key);
+                    } else if dismiss {
+                        tab.answered_pair = Some(key);
+                    }
+                });
+            }
+
+            // Dropped folders, onto the field under the pointer if any
+            if !dropped.is_empty() {
+                let target = drop_pos.and_then(|pos| {
+                    if dir_a_rect.contains(pos) {
+                        Some(DropTarget::DirA)
+                    } else if dir_b_rect.contains(pos) {
+                        Some(DropTarget::DirB)
+                    } else {
+                        None
+                    }
+                });
+                dropped_paths::drop_paths(tab, &dropped, target, strings, Instant::now());
+            }
+            if let Some(error) = &tab.drop_error {
+                let remaining = error.remaining(Instant::now());
+                if remaining.is_zero() {
+                    tab.drop_error = None;
+                } else {
+                    ui.colored_label(egui::Color32::RED, &error.message);
+                    ui.ctx().request_repaint_after(remaining);
+                }
+            }
+
             ui.add_space(10.0);
 
-            // Comparison Method
+            // Flat mode
             ui.horizontal(|ui| {
-                ui.label("Comparison Method:");
-                egui::ComboBox::from_label("")
-                    .selected_text(match self.state.comparison_method {
-                        ComparisonStrategyType::Filename => "Filename",
-                        ComparisonStrategyType::FilenameSize => "Filename & Size",
-                        ComparisonStrategyType::FastHash => "Content Hash",
-                        ComparisonStrategyType::SampledHash => "Sampled Hash",
-                    })
-                    .show_ui(ui, |ui| {
-                        ui.selectable_value(
-                            &mut self.state.comparison_method,
-                            ComparisonStrategyType::Filename,
-                            "Filename",
-                        );
-                        ui.selectable_value(
-                            &mut self.state.comparison_method,
-                            ComparisonStrategyType::FilenameSize,
-                            "Filename & Size",
-                        );
-                        ui.selectable_value(
-                            &mut self.state.comparison_method,
-                            ComparisonStrategyType::FastHash,
-                            "Content Hash",
-                        );
-                        ui.selectable_value(
-                            &mut self.state.comparison_method,
-                            ComparisonStrategyType::SampledHash,
-                            "Sampled Hash",
-                        );
-                    });
+                ui.checkbox(&mut tab.flat_mode, strings.get("input.flat_mode"))
+                    .on_hover_text(strings.get("input.flat_mode.help"));
+                if tab.flat_mode {
+                    ui.checkbox(&mut tab.full_hash, strings.get("input.full_hash"))
+                        .on_hover_text(strings.get("input.full_hash.help"));
+                }
+            });
+
+            // Comparison Method, which flat mode does not use
+            ui.add_enabled_ui(!tab.flat_mode, |ui| {
+                ui.horizontal(|ui| {
+                    ui.label(strings.get("input.method"));
+                    let mut method = tab.comparison_method;
+                    egui::ComboBox::from_label("")
+                        .selected_text(method_label(strings, method))
+                        .show_ui(ui, |ui| {
+                            for choice in ComparisonStrategyType::ALL {
+                                ui.selectable_value(
+                                    &mut method,
+                                    choice,
+                                    method_label(strings, choice),
+                                );
+                            }
+                        });
+                    tab.select_method(method);
+                });
+                option_widgets::show_strategy_options(
+                    ui,
+                    tab.comparison_method,
+                    &mut tab.strategy_options,
+                    strings,
+                );
+                ui.checkbox(&mut tab.detect_renames, strings.get("input.detect_renames"))
+                    .on_hover_text(strings.get("input.detect_renames.help"));
             });
 
             // Ignore File
             ui.horizontal(|ui| {
-                ui.label("Ignore File:");
-                let mut ignore_path_display = self
-                    .state
-                    .ignore_file_path
-                    .as_deref()
-                    .unwrap_or("")
-                    .to_string();
-                ui.text_edit_singleline(&mut ignore_path_display);
-                self.state.ignore_file_path = if ignore_path_display.is_empty() {
+                ui.label(strings.get("input.ignore_file"));
+                let mut ignore_path_display =
+                    tab.ignore_file_path.as_deref().unwrap_or("").to_string();
+                let response = ui.text_edit_singleline(&mut ignore_path_display);
+                tab.ignore_file_path = if ignore_path_display.is_empty() {
                     None
                 } else {
                     Some(ignore_path_display)
                 };
+                if let Some(resolved) =
+                    resolved_ignore_path(tab.ignore_file_path.as_deref(), &tab.dir_a_path)
+                {
+                    let tooltip = match resolved {
+                        Ok(path) => {
+                            strings.format("input.ignore_resolved", &[("path", &path.display())])
+                        }
+                        Err(e) => ignore_error_message(strings, &e),
+                    };
+                    response.on_hover_text(tooltip);
+                }
 
-                if ui.button("Browse...").clicked() {
-                    let dialog = NativeFileDialog;
-                    if let Some(path) = dialog.pick_file() {
-                        self.state.ignore_file_path = Some(path.display().to_string());
-                    }
+                if ui.button(strings.get("input.browse")).clicked() {
+                    tab.browse_ignore_file(dialog);
                 }
             });
+            ui.horizontal(|ui| {
+                ui.checkbox(
+                    &mut tab.respect_gitignore,
+                    strings.get("input.respect_gitignore"),
+                )
+                .on_hover_text(strings.get("input.respect_gitignore.help"));
+                ui.checkbox(&mut tab.ignore_hidden, strings.get("input.ignore_hidden"))
+                    .on_hover_text(strings.get("input.ignore_hidden.help"));
+                let mut limited = tab.max_depth.is_some();
+                let mut depth = tab.max_depth.unwrap_or(DEFAULT_MAX_DEPTH);
+                ui.checkbox(&mut limited, strings.get("input.max_depth"))
+                    .on_hover_text(strings.get("input.max_depth.help"));
+                ui.add_enabled(
+                    limited,
+                    egui::DragValue::new(&mut depth).clamp_range(1..=usize::MAX),
+                );
+                tab.max_depth = limited.then_some(depth);
+            });
 
             ui.add_space(20.0);
 
             // Compare Button
-            let can_compare = validate_path(&self.state.dir_a_path)
-                && validate_path(&self.state.dir_b_path)
-                && !self.state.is_comparing;
+            let mut compare = false;
+            let can_compare = can_compare(tab);
 
-            if self.state.is_comparing {
-                ui.horizontal(|ui| {
-                    ui.spinner();
-                    ui.label("Comparing...");
-                });
-            } else {
-                if ui
-                    .add_enabled(can_compare, egui::Button::new("Compare"))
-                    .clicked()
-                {
-                    self.state.is_comparing = true;
-                    self.state.results = None;
-                    self.state.tree_cache = None;
-                    self.state.error_message = None;
-                    let (tx, rx) = channel();
-                    self.state.comparison_receiver = Some(rx);
-
-                    let dir_a = self.state.dir_a_path.clone();
-                    let dir_b = self.state.dir_b_path.clone();
-                    let method = self.state.comparison_method;
-                    let ignore_file_path = self.state.ignore_file_path.clone();
-
-                    std::thread::spawn(move || {
-                        let strategy: Box<dyn ComparisonStrategy> = match method {
-                            ComparisonStrategyType::Filename => {
-                                Box::new(FilenameOnlyStrategy::new(false))
-                            }
-                            ComparisonStrategyType::FilenameSize => {
-                                Box::new(FilenameSizeStrategy::new(false))
-                            }
-                            ComparisonStrategyType::FastHash => {
-                                Box::new(FastHashStrategy::new(false))
-                            }
-                            ComparisonStrategyType::SampledHash => {
-                                Box::new(SampledHashStrategy::new(false, true))
-                            }
-                        };
-
-                        let result = compare_directories(
-                            std::path::Path::new(&dir_a),
-                            std::path::Path::new(&dir_b),
-                            strategy.as_ref(),
-                            ignore_file_path.as_deref().map(std::path::Path::new),
+            if tab.is_comparing {
+                let progress = tab.progress.as_ref();
+                match progress.and_then(|p| p.fraction().map(|f| (p, f))) {
+                    Some((progress, fraction)) => {
+                        ui.add(
+                            egui::ProgressBar::new(fraction)
+                                .text(progress.bar_text(strings))
+                                .animate(true),
                         );
-
-                        match result {
-                            Ok(res) => tx.send(Ok(res)).ok(),
-                            Err(e) => tx.send(Err(e.to_string())).ok(),
-                        };
-                    });
+                    }
+                    None => {
+                        ui.horizontal(|ui| {
+                            ui.spinner();
+                            ui.label(match progress {
+                                Some(progress) => progress.label(strings),
+                                None => strings.get("action.comparing").to_string(),
+                            });
+                        });
+                    }
                 }
+                if let Some(progress) = progress
+                    && !progress.current_path.as_os_str().is_empty()
+                {
+                    ui.label(
+                        egui::RichText::new(progress.path_label(PROGRESS_PATH_CHARS))
+                            .monospace()
+                            .small()
+                            .weak(),
+                    )
+                    .on_hover_text(progress.current_path.display().to_string());
+                }
+            } else if ui
+                .add_enabled(
+                    can_compare,
+                    egui::Button::new(strings.get("action.compare")),
+                )
+                .on_hover_text(shortcut_text(ctx, Shortcut::Compare))
+                .clicked()
+            {
+                compare = true;
             }
 
             // Error Message
-            if let Some(err) = &self.state.error_message {
-                ui.colored_label(egui::Color32::RED, format!("Error: {}", err));
+            if let Some(err) = &tab.error_message {
+                ui.colored_label(
+                    egui::Color32::RED,
+                    strings.format("error.message", &[("error", err)]),
+                );
+            }
+
+            // Stale results, with a shortcut to compare again
+            if let Some(banner) = self.state.staleness.banner_for(tab) {
+                let age = SystemTime::now()
+                    .duration_since(banner.compared_at)
+                    .unwrap_or_default();
+                ui.horizontal(|ui| {
+                    ui.colored_label(
+                        ResultCategory::Modified.color(),
+                        strings.format(
+                            "stale.banner",
+                            &[("age", &staleness::format_age(age, strings))],
+                        ),
+                    );
+                    if ui
+                        .add_enabled(can_compare, egui::Button::new(strings.get("stale.rerun")))
+                        .clicked()
+                    {
+                        compare = true;
+                    }
+                });
+            }
+            if compare {
+                start_compare(ctx, tab, sender, strings);
             }
 
-            // Results Tree
-            if let Some(cache) = &self.state.tree_cache {
+            // Results
+            let mut filter_changed = false;
+            if let Some(cache) = &mut tab.tree_cache {
                 ui.separator();
-                ui.label("Results:");
-                egui::ScrollArea::vertical().show(ui, |ui| {
-                    ui.collapsing("Only in A", |ui| {
-                        tree_view::render_tree(
-                            ui,
-                            &cache.a_only,
-                            egui::Color32::from_rgb(255, 100, 100),
-                        );
-                    });
-                    ui.collapsing("Only in B", |ui| {
-                        tree_view::render_tree(
+                ui.horizontal(|ui| {
+                    ui.label(strings.get("results.title"));
+                    let old_mode = global.view_mode;
+                    ui.selectable_value(
+                        &mut global.view_mode,
+                        ViewMode::Tree,
+                        strings.get("view.tree"),
+                    );
+                    ui.selectable_value(
+                        &mut global.view_mode,
+                        ViewMode::Table,
+                        strings.get("view.table"),
+                    );
+                    if old_mode != global.view_mode {
+                        update_settings(|settings| settings.view_mode = global.view_mode);
+                    }
+                    if ui.button(strings.get("action.export")).clicked() {
+                        export = true;
+                    }
+                });
+                filter_changed = tab.filter_bar.show(ui, strings);
+                if let Some(error) = &tab.filter_error {
+                    ui.colored_label(egui::Color32::RED, error.message(strings));
+                }
+                match global.view_mode {
+                    ViewMode::Tree => {
+                        let mut expand = None;
+                        ui.horizontal(|ui| {
+                            ui.label(strings.get("results.filter"));
+                            ui.add(
+                                egui::TextEdit::singleline(&mut tab.tree_filter)
+                                    .id(tree_filter_id())
+                                    .hint_text(strings.get("results.filter_hint")),
+                            )
+                            .on_hover_text(shortcut_text(ctx, Shortcut::FocusFilter));
+                            if !tab.tree_filter.is_empty() && ui.small_button("✖").clicked() {
+                                tab.tree_filter.clear();
+                            }
+                            ui.separator();
+                            if ui.button(strings.get("results.expand_all")).clicked() {
+                                expand = Some(true);
+                            }
+                            if ui.button(strings.get("results.collapse_all")).clicked() {
+                                expand = Some(false);
+                            }
+                        });
+                        cache.set_filter(&tab.tree_filter);
+                        match expand {
+                            Some(true) => cache.expand_all(&mut tab.tree_expansion),
+                            Some(false) => tab.tree_expansion.collapse_all(),
+                            None => {}
+                        }
+                        let rows = cache.tree_rows(&tab.tree_expansion);
+                        let action = tree_view::render_rows(
                             ui,
-                            &cache.b_only,
-                            egui::Color32::from_rgb(100, 255, 100),
+                            &rows,
+                            &mut tab.tree_expansion,
+                            tab.selection.as_ref(),
+                            strings,
                         );
-                    });
-                    ui.collapsing("In Both", |ui| {
-                        tree_view::render_tree(
+                        match action {
+                            Some((category, TreeAction::Select(path))) => {
+                                tab.selection = Some(ResultSelection { category, path });
+                            }
+                            Some((category, TreeAction::Reveal(path))) => {
+                                tab.selection = Some(ResultSelection {
+                                    category,
+                                    path: path.clone(),
+                                });
+                                reveal = Some((category, path));
+                            }
+                            Some((category, TreeAction::CopyPath(path))) => {
+                                if let Some(path) = cache.absolute_path(category, &path) {
+                                    let text = path.display().to_string();
+                                    ui.output_mut(|output| output.copied_text = text);
+                                }
+                            }
+                            None => {}
+                        }
+                    }
+                    ViewMode::Table => {
+                        table_view::render_table(
                             ui,
-                            &cache.both,
-                            egui::Color32::from_rgb(100, 200, 255),
+                            &mut cache.rows,
+                            &mut tab.sort_state,
+                            &mut tab.selection,
+                            strings,
                         );
-                    });
-                });
+                    }
+                }
+            }
+            if filter_changed {
+                tab.apply_filter_bar();
+            }
+            if let Some(flat) = &tab.flat_results {
+                ui.separator();
+                ui.label(strings.get("results.title"));
+                flat_view::render_flat_results(ui, flat, strings);
             }
         });
+
+        if export {
+            self.state
+                .tabs
+                .active_mut()
+                .export_report(self.dialog.as_ref(), &self.state.global.strings);
+        }
+        match recent_action {
+            Some(RecentAction::Open(index)) => {
+                let tab = self.state.tabs.active_mut();
+                if let Some(warning) = self.state.recent.entries[index].apply_to(tab) {
+                    tab.status_message = Some(warning.message(&self.state.global.strings));
+                }
+            }
+            Some(RecentAction::Clear) => {
+                self.state.recent.clear();
+                self.save_settings(self.state.tabs.active());
+            }
+            None => {}
+        }
+        if export_settings {
+            self.state
+                .tabs
+                .active_mut()
+                .export_settings(self.dialog.as_ref(), &self.state.global.strings);
+        }
+        if let Some((category, path)) = reveal {
+            self.state.tabs.active_mut().reveal_entry(
+                category,
+                &path,
+                open_in_file_manager,
+                &self.state.global.strings,
+            );
+        }
+    }
+
+    fn on_exit(&mut self, _gl: Option<&eframe::glow::Context>) {
+        self.save_settings(self.state.tabs.active());
+        self.demo = None;
+        self.log_panel.unregister();
     }
 }
 
@@ -417,4 +1466,140 @@ mod tests {
         // Parent directory should exist
         assert!(validate_path(".."));
     }
+
+    fn app_state() -> AppState {
+        AppState {
+            global: GlobalState::new(Theme::System, ViewMode::Tree, Locale::English),
+            tabs: Tabs::new(),
+            staleness: StalenessMonitor::new(),
+            saved_pairs: SavedPairs::default(),
+            recent: RecentComparisons::default(),
+        }
+    }
+
+    /// Runs [`handle_shortcuts`] on a frame in which `key` is pressed with
+    /// `modifiers` while `focus` has the keyboard focus
+    fn press(
+        state: &mut AppState,
+        modifiers: egui::Modifiers,
+        key: egui::Key,
+        focus: Option<egui::Id>,
+    ) -> Option<Shortcut> {
+        let ctx = egui::Context::default();
+        ctx.begin_frame(egui::RawInput {
+            modifiers,
+            events: vec![egui::Event::Key {
+                key,
+                physical_key: None,
+                pressed: true,
+                repeat: false,
+                modifiers,
+            }],
+            ..Default::default()
+        });
+        if let Some(id) = focus {
+            ctx.memory_mut(|memory| memory.request_focus(id));
+        }
+        handle_shortcuts(state, &ctx)
+    }
+
+    /// Ctrl as reported on Linux and Windows
+    const CTRL: egui::Modifiers = egui::Modifiers::CTRL.plus(egui::Modifiers::COMMAND);
+    const CTRL_SHIFT: egui::Modifiers = CTRL.plus(egui::Modifiers::SHIFT);
+
+    #[test]
+    fn test_shortcut_modifiers_must_match_exactly() {
+        let mut state = app_state();
+        let none = egui::Modifiers::NONE;
+        let o = egui::Key::O;
+        assert_eq!(press(&mut state, CTRL, o, None), Some(Shortcut::BrowseA));
+        assert_eq!(
+            press(&mut state, CTRL_SHIFT, o, None),
+            Some(Shortcut::BrowseB)
+        );
+        assert_eq!(press(&mut state, none, o, None), None);
+        let ctrl_alt = CTRL.plus(egui::Modifiers::ALT);
+        assert_eq!(press(&mut state, ctrl_alt, o, None), None);
+        let q = egui::Key::Q;
+        assert_eq!(press(&mut state, CTRL, q, None), Some(Shortcut::Quit));
+        assert_eq!(press(&mut state, none, q, None), None);
+    }
+
+    #[test]
+    fn test_compare_and_cancel_shortcuts_follow_the_buttons() {
+        let (dir_a, dir_b) = (TempDir::new().unwrap(), TempDir::new().unwrap());
+        let mut state = app_state();
+        let (none, r) = (egui::Modifiers::NONE, egui::Key::R);
+
+        // Compare is disabled without valid directories
+        assert_eq!(press(&mut state, CTRL, r, None), None);
+        assert_eq!(press(&mut state, none, egui::Key::Escape, None), None);
+
+        let tab = state.tabs.active_mut();
+        tab.dir_a_path = dir_a.path().display().to_string();
+        tab.dir_b_path = dir_b.path().display().to_string();
+        assert_eq!(press(&mut state, CTRL, r, None), Some(Shortcut::Compare));
+        assert!(state.tabs.active().is_comparing);
+
+        // Compare is disabled while comparing, Cancel is enabled
+        assert_eq!(press(&mut state, CTRL, r, None), None);
+        assert_eq!(press(&mut state, none, egui::Key::Enter, None), None);
+        assert_eq!(
+            press(&mut state, none, egui::Key::Escape, None),
+            Some(Shortcut::Cancel)
+        );
+        let cancel = state.tabs.active().cancel.as_ref().unwrap();
+        assert!(cancel.is_cancelled());
+    }
+
+    #[test]
+    fn test_enter_compares_only_from_path_fields() {
+        let (dir_a, dir_b) = (TempDir::new().unwrap(), TempDir::new().unwrap());
+        let mut state = app_state();
+        let tab = state.tabs.active_mut();
+        tab.dir_a_path = dir_a.path().display().to_string();
+        tab.dir_b_path = dir_b.path().display().to_string();
+        let (none, enter) = (egui::Modifiers::NONE, egui::Key::Enter);
+
+        assert_eq!(press(&mut state, none, enter, Some(tree_filter_id())), None);
+        assert!(!state.tabs.active().is_comparing);
+        let dir_b_field = path_field_ids()[1];
+        assert_eq!(
+            press(&mut state, none, enter, Some(dir_b_field)),
+            Some(Shortcut::Compare)
+        );
+    }
+
+    #[test]
+    fn test_export_and_filter_shortcuts_need_results() {
+        let (dir_a, dir_b) = (TempDir::new().unwrap(), TempDir::new().unwrap());
+        let mut state = app_state();
+        let (e, f) = (egui::Key::E, egui::Key::F);
+        assert_eq!(press(&mut state, CTRL, e, None), None);
+        assert_eq!(press(&mut state, CTRL, f, None), None);
+
+        let result = Comparison::new(dir_a.path(), dir_b.path()).run();
+        let tab = state.tabs.active_mut();
+        tab.handle_message(ComparisonMessage::Finished(
+            result.map_err(|e| e.to_string()),
+        ));
+        assert_eq!(press(&mut state, CTRL, e, None), Some(Shortcut::Export));
+        assert_eq!(
+            press(&mut state, CTRL, f, None),
+            Some(Shortcut::FocusFilter)
+        );
+
+        // The table has no filter field
+        state.global.view_mode = ViewMode::Table;
+        assert_eq!(press(&mut state, CTRL, f, None), None);
+    }
+
+    #[test]
+    fn test_shortcut_text_lists_every_binding() {
+        let ctx = egui::Context::default();
+        ctx.set_os(egui::os::OperatingSystem::Nix);
+        assert_eq!(shortcut_text(&ctx, Shortcut::Export), "Ctrl+E");
+        assert_eq!(shortcut_text(&ctx, Shortcut::BrowseB), "Ctrl+Shift+O");
+        assert_eq!(shortcut_text(&ctx, Shortcut::Compare), "Ctrl+R, Enter");
+    }
 }
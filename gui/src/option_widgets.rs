@@ -0,0 +1,68 @@
+//! Widgets for the options of the selected comparison strategy.
+//!
+//! The widgets are generated from the [`StrategyOption`] descriptors of the
+//! strategy, so options added in core show up without GUI changes. Labels
+//! and tooltips come from the `option.<name>` and `option.<name>.help`
+//! catalog keys, falling back to the English text of the descriptor.
+
+use dir_compare_core::{
+    ComparisonStrategyType, OptionKind, OptionValue, OptionValues, StrategyOption,
+};
+use eframe::egui;
+
+use crate::strings::Strings;
+
+/// Returns the localized label of an option
+pub fn option_label<'a>(strings: &'a Strings, option: &'a StrategyOption) -> &'a str {
+    strings.get_or(&format!("option.{}", option.name), option.label)
+}
+
+/// Returns the localized tooltip of an option
+pub fn option_help<'a>(strings: &'a Strings, option: &'a StrategyOption) -> &'a str {
+    strings.get_or(&format!("option.{}.help", option.name), option.help)
+}
+
+/// Renders one widget per option of `method`, reading and writing `values`.
+///
+/// Options missing from `values` are shown with their default.
+pub fn show_strategy_options(
+    ui: &mut egui::Ui,
+    method: ComparisonStrategyType,
+    values: &mut OptionValues,
+    strings: &Strings,
+) {
+    for option in method.options() {
+        let mut value = values
+            .get(option.name)
+            .cloned()
+            .unwrap_or_else(|| option.default.clone());
+        let label = option_label(strings, &option);
+        let help = option_help(strings, &option);
+        match (&option.kind, &mut value) {
+            (OptionKind::Bool, OptionValue::Bool(checked)) => {
+                ui.checkbox(checked, label).on_hover_text(help);
+            }
+            (OptionKind::Int { min, max }, OptionValue::Int(n)) => {
+                ui.horizontal(|ui| {
+                    ui.label(label).on_hover_text(help);
+                    ui.add(egui::DragValue::new(n).clamp_range(*min..=*max));
+                });
+            }
+            (OptionKind::Choice(choices), OptionValue::Choice(selected)) => {
+                ui.horizontal(|ui| {
+                    ui.label(label).on_hover_text(help);
+                    egui::ComboBox::from_id_source(option.name)
+                        .selected_text(selected.as_str())
+                        .show_ui(ui, |ui| {
+                            for choice in *choices {
+                                ui.selectable_value(selected, choice.to_string(), *choice);
+                            }
+                        });
+                });
+            }
+            // A value of the wrong kind is left for the factory to report
+            _ => continue,
+        }
+        values.insert(option.name.to_string(), value);
+    }
+}
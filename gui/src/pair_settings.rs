@@ -0,0 +1,339 @@
+//! Comparison settings remembered per pair of directories.
+//!
+//! After a successful comparison the method, its options, flat mode, the
+//! ignore file, whether `.gitignore` files are respected and the depth
+//! limit are saved under the [`PairKey`] of the compared directories.
+//! When both path fields of a tab name a remembered pair whose settings
+//! differ from the tab's, [`offer_for`] returns them so the window can ask
+//! whether to restore them; they are never applied without the user's
+//! consent.
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::{MAIN_SEPARATOR, Path};
+
+use dir_compare_core::ComparisonStrategyType;
+use dir_compare_core::strategy_options::{OptionValue, OptionValues};
+use serde::{Deserialize, Serialize};
+
+use crate::invocation::cli_method;
+use crate::strings::Strings;
+use crate::tabs::TabState;
+
+/// Number of pairs remembered; the least recently compared are dropped
+pub const MAX_SAVED_PAIRS: usize = 20;
+
+/// Identifies a pair of directories regardless of how their paths were
+/// typed: existing directories are canonicalized, other paths are trimmed
+/// and lose trailing separators.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PairKey {
+    pub dir_a: String,
+    pub dir_b: String,
+}
+
+impl PairKey {
+    /// The key of `dir_a` and `dir_b`; `None` while either is empty
+    pub fn new(dir_a: &str, dir_b: &str) -> Option<Self> {
+        Some(Self {
+            dir_a: normalize_dir(dir_a)?,
+            dir_b: normalize_dir(dir_b)?,
+        })
+    }
+
+    /// The key of the directories entered in `tab`
+    pub fn of_tab(tab: &TabState) -> Option<Self> {
+        Self::new(&tab.dir_a_path, &tab.dir_b_path)
+    }
+}
+
+/// Normalizes one directory of a [`PairKey`]
+fn normalize_dir(input: &str) -> Option<String> {
+    let input = input.trim();
+    if input.is_empty() {
+        return None;
+    }
+    if let Ok(canonical) = Path::new(input).canonicalize() {
+        return Some(canonical.to_string_lossy().into_owned());
+    }
+    let trimmed = input.trim_end_matches(['/', MAIN_SEPARATOR]);
+    Some(if trimmed.is_empty() { input } else { trimmed }.to_string())
+}
+
+/// A saved option value
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum SavedOptionValue {
+    Bool(bool),
+    Int(i64),
+    Choice(String),
+}
+
+impl From<&OptionValue> for SavedOptionValue {
+    fn from(value: &OptionValue) -> Self {
+        match value {
+            OptionValue::Bool(b) => SavedOptionValue::Bool(*b),
+            OptionValue::Int(n) => SavedOptionValue::Int(*n),
+            OptionValue::Choice(choice) => SavedOptionValue::Choice(choice.clone()),
+        }
+    }
+}
+
+impl From<&SavedOptionValue> for OptionValue {
+    fn from(value: &SavedOptionValue) -> Self {
+        match value {
+            SavedOptionValue::Bool(b) => OptionValue::Bool(*b),
+            SavedOptionValue::Int(n) => OptionValue::Int(*n),
+            SavedOptionValue::Choice(choice) => OptionValue::Choice(choice.clone()),
+        }
+    }
+}
+
+/// The settings of one comparison.
+///
+/// The method is kept by its CLI name, so a name that a later version no
+/// longer knows survives loading and is reported by [`PairSettings::apply_to`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PairSettings {
+    pub method: String,
+    #[serde(default)]
+    pub options: BTreeMap<String, SavedOptionValue>,
+    #[serde(default)]
+    pub flat_mode: bool,
+    #[serde(default)]
+    pub full_hash: bool,
+    #[serde(default)]
+    pub ignore_file_path: Option<String>,
+    #[serde(default)]
+    pub respect_gitignore: bool,
+    #[serde(default)]
+    pub max_depth: Option<usize>,
+    #[serde(default)]
+    pub ignore_hidden: bool,
+    #[serde(default)]
+    pub detect_renames: bool,
+}
+
+/// A setting that could not be restored as saved
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RestoreWarning {
+    /// The saved method does not exist; the tab's method was kept
+    UnknownMethod {
+        saved: String,
+        kept: ComparisonStrategyType,
+    },
+    /// A saved option is not accepted by the method; it takes its default
+    InvalidOption { option: String, message: String },
+}
+
+impl RestoreWarning {
+    /// Localized description for the status bar
+    pub fn message(&self, strings: &Strings) -> String {
+        match self {
+            RestoreWarning::UnknownMethod { saved, kept } => strings.format(
+                "pair.unknown_method",
+                &[("method", saved), ("kept", &cli_method(*kept))],
+            ),
+            RestoreWarning::InvalidOption { option, .. } => {
+                strings.format("pair.invalid_option", &[("option", option)])
+            }
+        }
+    }
+}
+
+impl fmt::Display for RestoreWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RestoreWarning::UnknownMethod { saved, kept } => write!(
+                f,
+                "Saved comparison method '{}' is not available; keeping '{}'",
+                saved,
+                cli_method(*kept)
+            ),
+            RestoreWarning::InvalidOption { option, message } => {
+                write!(f, "Saved option '{}' was reset: {}", option, message)
+            }
+        }
+    }
+}
+
+/// Finds the method with CLI name `name`
+pub fn method_by_name(name: &str) -> Option<ComparisonStrategyType> {
+    ComparisonStrategyType::ALL
+        .into_iter()
+        .find(|method| cli_method(*method) == name)
+}
+
+impl PairSettings {
+    /// The current settings of `tab`
+    pub fn from_tab(tab: &TabState) -> Self {
+        Self {
+            method: cli_method(tab.comparison_method).to_string(),
+            options: tab
+                .strategy_options
+                .iter()
+                .map(|(name, value)| (name.clone(), value.into()))
+                .collect(),
+            flat_mode: tab.flat_mode,
+            full_hash: tab.full_hash,
+            ignore_file_path: tab
+                .ignore_file_path
+                .clone()
+                .filter(|path| !path.trim().is_empty()),
+            respect_gitignore: tab.respect_gitignore,
+            max_depth: tab.max_depth,
+            ignore_hidden: tab.ignore_hidden,
+            detect_renames: tab.detect_renames,
+        }
+    }
+
+    /// Replaces the settings of `tab` with these.
+    ///
+    /// An unknown method keeps the tab's method and options, and an option
+    /// the method does not accept takes its default; each is reported.
+    pub fn apply_to(&self, tab: &mut TabState) -> Vec<RestoreWarning> {
+        let mut warnings = Vec::new();
+        match method_by_name(&self.method) {
+            Some(method) => {
+                tab.select_method(method);
+                tab.strategy_options = self.option_values(method, &mut warnings);
+            }
+            None => warnings.push(RestoreWarning::UnknownMethod {
+                saved: self.method.clone(),
+                kept: tab.comparison_method,
+            }),
+        }
+        tab.flat_mode = self.flat_mode;
+        tab.full_hash = self.full_hash;
+        tab.ignore_file_path = self.ignore_file_path.clone();
+        tab.respect_gitignore = self.respect_gitignore;
+        tab.max_depth = self.max_depth;
+        tab.ignore_hidden = self.ignore_hidden;
+        tab.detect_renames = self.detect_renames;
+        warnings
+    }
+
+    /// The saved option values of `method`, with defaults for missing and
+    /// invalid ones
+    fn option_values(
+        &self,
+        method: ComparisonStrategyType,
+        warnings: &mut Vec<RestoreWarning>,
+    ) -> OptionValues {
+        method
+            .options()
+            .into_iter()
+            .map(|option| {
+                let value = match self.options.get(option.name).map(OptionValue::from) {
+                    Some(value) => match option.validate(&value) {
+                        Ok(()) => value,
+                        Err(e) => {
+                            warnings.push(RestoreWarning::InvalidOption {
+                                option: e.option,
+                                message: e.message,
+                            });
+                            option.default
+                        }
+                    },
+                    None => option.default,
+                };
+                (option.name.to_string(), value)
+            })
+            .collect()
+    }
+
+    /// Short description for the restore prompt, such as
+    /// `sampled + verify + ignore .dir-compare-ignore`
+    pub fn summary(&self) -> String {
+        let mut parts = Vec::new();
+        if self.flat_mode {
+            parts.push("flat".to_string());
+            if self.full_hash {
+                parts.push("full-hash".to_string());
+            }
+        } else {
+            parts.push(self.method.clone());
+            for (name, value) in &self.options {
+                match value {
+                    SavedOptionValue::Bool(true) => parts.push(name.clone()),
+                    SavedOptionValue::Bool(false) => parts.push(format!("no {}", name)),
+                    SavedOptionValue::Int(n) => parts.push(format!("{} {}", name, n)),
+                    SavedOptionValue::Choice(choice) => parts.push(format!("{} {}", name, choice)),
+                }
+            }
+            if self.detect_renames {
+                parts.push("renames".to_string());
+            }
+        }
+        if let Some(ignore) = &self.ignore_file_path {
+            parts.push(format!("ignore {}", ignore));
+        }
+        if self.respect_gitignore {
+            parts.push("gitignore".to_string());
+        }
+        if let Some(depth) = self.max_depth {
+            parts.push(format!("depth {}", depth));
+        }
+        if self.ignore_hidden {
+            parts.push("no hidden".to_string());
+        }
+        parts.join(" + ")
+    }
+}
+
+/// A remembered pair with its settings
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SavedPair {
+    #[serde(flatten)]
+    pub key: PairKey,
+    pub settings: PairSettings,
+}
+
+/// The remembered pairs, most recently compared first
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct SavedPairs {
+    pub pairs: Vec<SavedPair>,
+}
+
+impl SavedPairs {
+    /// The settings saved for `key`
+    pub fn lookup(&self, key: &PairKey) -> Option<&PairSettings> {
+        self.pairs
+            .iter()
+            .find(|pair| pair.key == *key)
+            .map(|pair| &pair.settings)
+    }
+
+    /// Saves `settings` for `key`, replacing earlier ones and dropping the
+    /// oldest pair beyond [`MAX_SAVED_PAIRS`]
+    pub fn remember(&mut self, key: PairKey, settings: PairSettings) {
+        self.pairs.retain(|pair| pair.key != key);
+        self.pairs.insert(0, SavedPair { key, settings });
+        self.pairs.truncate(MAX_SAVED_PAIRS);
+    }
+
+    /// Saves the current settings of `tab` for its directories
+    pub fn remember_tab(&mut self, tab: &TabState) {
+        if let Some(key) = PairKey::of_tab(tab) {
+            self.remember(key, PairSettings::from_tab(tab));
+        }
+    }
+}
+
+/// The saved settings to offer for the directories of `tab`.
+///
+/// Only offered when both directories match a remembered pair, the saved
+/// settings differ from the tab's, and the user has not yet applied or
+/// dismissed them for this pair.
+pub fn offer_for<'a>(saved: &'a SavedPairs, tab: &TabState) -> Option<(PairKey, &'a PairSettings)> {
+    if tab.is_comparing {
+        return None;
+    }
+    let key = PairKey::of_tab(tab)?;
+    if tab.answered_pair.as_ref() == Some(&key) {
+        return None;
+    }
+    let settings = saved.lookup(&key)?;
+    (*settings != PairSettings::from_tab(tab)).then_some((key, settings))
+}
@@ -0,0 +1,107 @@
+//! Progress of a running comparison, shown while the background thread works.
+
+use dir_compare_core::output::group_digits;
+use dir_compare_core::{ComparisonResult, FlatComparisonResult, Progress, ProgressPhase, Side};
+use std::path::PathBuf;
+
+use crate::staleness::ComparisonSnapshot;
+use crate::strings::Strings;
+
+/// Messages sent from the comparison thread to the UI
+// A result is sent once per comparison, so its size on the channel does not
+// matter
+#[allow(clippy::large_enum_variant)]
+pub enum ComparisonMessage {
+    /// The comparison made progress
+    Progress(ProgressUpdate),
+    /// The fingerprints of the roots, taken right after a successful
+    /// comparison and sent before its result
+    Snapshot(ComparisonSnapshot),
+    /// The comparison finished; no further messages follow
+    Finished(Result<ComparisonResult, String>),
+    /// A flat mode comparison finished; no further messages follow
+    FinishedFlat(Result<FlatComparisonResult, String>),
+}
+
+/// Owned copy of a core progress update, so it can cross the channel
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProgressUpdate {
+    pub phase: ProgressPhase,
+    pub current: usize,
+    pub total: Option<usize>,
+    /// The directory being listed while traversing
+    pub side: Option<Side>,
+    pub current_path: PathBuf,
+}
+
+impl From<&Progress<'_>> for ProgressUpdate {
+    fn from(progress: &Progress) -> Self {
+        Self {
+            phase: progress.phase,
+            current: progress.current,
+            total: progress.total,
+            side: progress.side,
+            current_path: progress.current_path.to_path_buf(),
+        }
+    }
+}
+
+impl ProgressUpdate {
+    /// Fraction of pairs evaluated, or `None` while the total is unknown
+    pub fn fraction(&self) -> Option<f32> {
+        match self.total {
+            Some(0) => Some(1.0),
+            Some(total) => Some(self.current as f32 / total as f32),
+            None => None,
+        }
+    }
+
+    /// Returns true for the update reporting the last evaluated pair
+    pub fn is_last(&self) -> bool {
+        self.total == Some(self.current)
+    }
+
+    /// Text of the progress bar: the label followed by the percentage done
+    pub fn bar_text(&self, strings: &Strings) -> String {
+        match self.fraction() {
+            Some(fraction) => format!("{}  {:.0}%", self.label(strings), fraction * 100.0),
+            None => self.label(strings),
+        }
+    }
+
+    /// The path being compared, shortened to `max_chars` characters
+    pub fn path_label(&self, max_chars: usize) -> String {
+        truncate_middle(&self.current_path.display().to_string(), max_chars)
+    }
+
+    /// Localized description, such as "Scanning B… 120,345 entries" or
+    /// "Comparing 3/40 files"
+    pub fn label(&self, strings: &Strings) -> String {
+        match (self.phase, self.total, self.side) {
+            (ProgressPhase::Matching, Some(total), _) => strings.format(
+                "progress.matching",
+                &[("current", &self.current), ("total", &total)],
+            ),
+            (_, _, Some(side)) => strings.format(
+                "progress.scanning_side",
+                &[("side", &side), ("count", &group_digits(self.current))],
+            ),
+            _ => strings.format("progress.scanning", &[("count", &self.current)]),
+        }
+    }
+}
+
+/// Shortens `text` to `max_chars` characters by replacing its middle with
+/// `…`, keeping both the start and the file name at the end visible
+pub fn truncate_middle(text: &str, max_chars: usize) -> String {
+    let len = text.chars().count();
+    if len <= max_chars {
+        return text.to_string();
+    }
+    let kept = max_chars.saturating_sub(1);
+    let tail = kept / 2;
+    let head = kept - tail;
+    let start: String = text.chars().take(head).collect();
+    let end: String = text.chars().skip(len - tail).collect();
+    format!("{}…{}", start, end)
+}
@@ -0,0 +1,117 @@
+//! The most recent successful comparisons, listed under File → Recent.
+//!
+//! Each entry keeps the directories as they were typed, the method and when
+//! the comparison finished. Comparing a pair again moves its entry to the
+//! top instead of adding another; pairs are told apart by their
+//! [`PairKey`], so differently spelled paths to the same directories count
+//! as one pair.
+
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+
+use crate::invocation::cli_method;
+use crate::pair_settings::{PairKey, RestoreWarning, method_by_name};
+use crate::tabs::TabState;
+
+/// Number of comparisons listed; older ones are dropped
+pub const MAX_RECENT: usize = 10;
+
+/// A successful comparison of two directories.
+///
+/// The method is kept by its CLI name, like [`PairSettings`], so a name
+/// that a later version no longer knows survives loading.
+///
+/// [`PairSettings`]: crate::pair_settings::PairSettings
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RecentComparison {
+    pub dir_a: String,
+    pub dir_b: String,
+    pub method: String,
+    /// When the comparison finished, in seconds since the Unix epoch
+    pub compared_at: u64,
+}
+
+impl RecentComparison {
+    /// The comparison of the directories entered in `tab`, finished at
+    /// `compared_at`; `None` while either path is empty
+    pub fn of_tab(tab: &TabState, compared_at: SystemTime) -> Option<Self> {
+        PairKey::of_tab(tab)?;
+        Some(Self {
+            dir_a: tab.dir_a_path.trim().to_string(),
+            dir_b: tab.dir_b_path.trim().to_string(),
+            method: cli_method(tab.comparison_method).to_string(),
+            compared_at: compared_at
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs(),
+        })
+    }
+
+    /// When the comparison finished
+    pub fn compared_at(&self) -> SystemTime {
+        UNIX_EPOCH + Duration::from_secs(self.compared_at)
+    }
+
+    /// The text of the menu item, such as `/data → /backup`
+    pub fn label(&self) -> String {
+        format!("{} → {}", self.dir_a, self.dir_b)
+    }
+
+    /// Fills the path fields and the method of `tab`.
+    ///
+    /// The paths are filled even if they no longer exist; an unknown method
+    /// keeps the tab's method and is reported.
+    pub fn apply_to(&self, tab: &mut TabState) -> Option<RestoreWarning> {
+        tab.dir_a_path = self.dir_a.clone();
+        tab.dir_b_path = self.dir_b.clone();
+        match method_by_name(&self.method) {
+            Some(method) => {
+                tab.select_method(method);
+                None
+            }
+            None => Some(RestoreWarning::UnknownMethod {
+                saved: self.method.clone(),
+                kept: tab.comparison_method,
+            }),
+        }
+    }
+
+    fn key(&self) -> Option<PairKey> {
+        PairKey::new(&self.dir_a, &self.dir_b)
+    }
+}
+
+/// The recent comparisons, most recent first
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct RecentComparisons {
+    pub entries: Vec<RecentComparison>,
+}
+
+impl RecentComparisons {
+    /// Adds `entry` at the top, removing an earlier entry for the same pair
+    /// and dropping the oldest beyond [`MAX_RECENT`]
+    pub fn record(&mut self, entry: RecentComparison) {
+        let key = entry.key();
+        self.entries.retain(|recent| recent.key() != key);
+        self.entries.insert(0, entry);
+        self.entries.truncate(MAX_RECENT);
+    }
+
+    /// Records the comparison of the directories of `tab`, finished now
+    pub fn record_tab(&mut self, tab: &TabState) {
+        if let Some(entry) = RecentComparison::of_tab(tab, SystemTime::now()) {
+            self.record(entry);
+        }
+    }
+
+    /// Forgets all entries
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
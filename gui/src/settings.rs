@@ -0,0 +1,503 @@
+use std::io;
+use std::path::Path;
+
+use dir_compare_core::text_diff::DEFAULT_MAX_DIFF_LINES;
+use dir_compare_core::{ComparisonStrategyType, logger};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::pair_settings::SavedPairs;
+use crate::recent::RecentComparisons;
+use crate::strings::Locale;
+use crate::tabs::TabState;
+use crate::theme::{THEME_CONFIG_FILE, Theme, get_config_dir};
+
+const SETTINGS_CONFIG_FILE: &str = "settings.json";
+/// Where older versions saved the view mode, before it moved into the settings
+const VIEW_MODE_CONFIG_FILE: &str = "view_mode.txt";
+/// Where older versions saved the language, before it moved into the settings
+const LOCALE_CONFIG_FILE: &str = "locale.txt";
+/// Where older versions saved the onboarding flag, before it moved into the
+/// settings
+const ONBOARDING_CONFIG_FILE: &str = "onboarding.txt";
+/// Contents of the onboarding file once the first-run panel was dismissed
+const ONBOARDING_DISMISSED: &str = "dismissed";
+
+/// How comparison results are presented in the main window
+#[derive(PartialEq, Eq, Copy, Clone, Debug, Default)]
+pub enum ViewMode {
+    /// Collapsible trees, one per category
+    #[default]
+    Tree,
+    /// A single flat, sortable table of all entries
+    Table,
+}
+
+impl ViewMode {
+    /// Returns the string representation of the view mode
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ViewMode::Tree => "tree",
+            ViewMode::Table => "table",
+        }
+    }
+
+    /// Parses a view mode from its string representation
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "tree" => Some(ViewMode::Tree),
+            "table" => Some(ViewMode::Table),
+            _ => None,
+        }
+    }
+}
+
+/// The inputs of the last comparison and the appearance of the window,
+/// restored on the next launch.
+///
+/// Fields missing from the file take their defaults, and a method, theme,
+/// view mode or language name that is not known falls back to the default
+/// instead of discarding the whole file.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    pub dir_a_path: String,
+    pub dir_b_path: String,
+    pub ignore_file_path: Option<String>,
+    #[serde(with = "method_name")]
+    pub comparison_method: ComparisonStrategyType,
+    #[serde(with = "by_name")]
+    pub theme: Theme,
+    /// Settings of recently compared directory pairs
+    pub pairs: SavedPairs,
+    /// The most recent successful comparisons, listed under File → Recent
+    pub recent: RecentComparisons,
+    /// Lines of each file compared when showing the diff of a modified file
+    pub diff_max_lines: usize,
+    /// How results are shown, as trees or as one table
+    #[serde(with = "by_name")]
+    pub view_mode: ViewMode,
+    /// Language of the GUI
+    #[serde(with = "by_name")]
+    pub locale: Locale,
+    /// Whether the first-run onboarding panel was dismissed
+    pub onboarding_dismissed: bool,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            dir_a_path: String::new(),
+            dir_b_path: String::new(),
+            ignore_file_path: None,
+            comparison_method: ComparisonStrategyType::FastHash,
+            theme: Theme::System,
+            pairs: SavedPairs::default(),
+            recent: RecentComparisons::default(),
+            diff_max_lines: DEFAULT_MAX_DIFF_LINES,
+            view_mode: ViewMode::default(),
+            locale: Locale::default(),
+            onboarding_dismissed: false,
+        }
+    }
+}
+
+impl Settings {
+    /// The inputs of `tab` with `theme`, without saved pairs, recent
+    /// comparisons or the other preferences
+    pub fn from_tab(tab: &TabState, theme: Theme) -> Self {
+        Self {
+            dir_a_path: tab.dir_a_path.clone(),
+            dir_b_path: tab.dir_b_path.clone(),
+            ignore_file_path: tab.ignore_file_path.clone(),
+            comparison_method: tab.comparison_method,
+            theme,
+            ..Settings::default()
+        }
+    }
+
+    /// Fills the inputs of `tab` with the saved ones
+    pub fn apply_to(&self, tab: &mut TabState) {
+        tab.dir_a_path = self.dir_a_path.clone();
+        tab.dir_b_path = self.dir_b_path.clone();
+        tab.ignore_file_path = self.ignore_file_path.clone();
+        tab.select_method(self.comparison_method);
+    }
+}
+
+/// Serializes comparison methods by their CLI names, such as `size`
+mod method_name {
+    use super::*;
+
+    const NAMES: [(ComparisonStrategyType, &str); 6] = [
+        (ComparisonStrategyType::Filename, "filename"),
+        (ComparisonStrategyType::FilenameSize, "size"),
+        (ComparisonStrategyType::FastHash, "hash"),
+        (ComparisonStrategyType::Blake3, "blake3"),
+        (ComparisonStrategyType::SampledHash, "sampled"),
+        (ComparisonStrategyType::Mtime, "mtime"),
+    ];
+
+    pub fn serialize<S: Serializer>(
+        method: &ComparisonStrategyType,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        let (_, name) = NAMES.iter().find(|(m, _)| m == method).unwrap();
+        serializer.serialize_str(name)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<ComparisonStrategyType, D::Error> {
+        let name = String::deserialize(deserializer)?;
+        Ok(match NAMES.iter().find(|(_, n)| *n == name) {
+            Some((method, _)) => *method,
+            None => {
+                logger::warn(&format!("Unknown comparison method '{}' in settings", name));
+                Settings::default().comparison_method
+            }
+        })
+    }
+}
+
+/// A setting saved by a name such as `dark`, which falls back to a default
+/// when the name is not known
+trait NamedSetting: Sized {
+    /// What the setting is called in warnings
+    const WHAT: &str;
+    /// The value used for unknown names
+    const FALLBACK: Self;
+
+    fn name(&self) -> &'static str;
+    fn from_name(name: &str) -> Option<Self>;
+}
+
+impl NamedSetting for Theme {
+    const WHAT: &str = "theme";
+    const FALLBACK: Self = Theme::System;
+
+    fn name(&self) -> &'static str {
+        self.as_str()
+    }
+
+    fn from_name(name: &str) -> Option<Self> {
+        Theme::parse(name)
+    }
+}
+
+impl NamedSetting for ViewMode {
+    const WHAT: &str = "view mode";
+    const FALLBACK: Self = ViewMode::Tree;
+
+    fn name(&self) -> &'static str {
+        self.as_str()
+    }
+
+    fn from_name(name: &str) -> Option<Self> {
+        ViewMode::parse(name)
+    }
+}
+
+impl NamedSetting for Locale {
+    const WHAT: &str = "language";
+    const FALLBACK: Self = Locale::English;
+
+    fn name(&self) -> &'static str {
+        self.as_str()
+    }
+
+    fn from_name(name: &str) -> Option<Self> {
+        Locale::parse(name)
+    }
+}
+
+/// Serializes a [`NamedSetting`] by its name
+mod by_name {
+    use super::*;
+
+    pub fn serialize<T: NamedSetting, S: Serializer>(
+        value: &T,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(value.name())
+    }
+
+    pub fn deserialize<'de, T: NamedSetting, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<T, D::Error> {
+        let name = String::deserialize(deserializer)?;
+        Ok(T::from_name(&name).unwrap_or_else(|| {
+            logger::warn(&format!("Unknown {} '{}' in settings", T::WHAT, name));
+            T::FALLBACK
+        }))
+    }
+}
+
+/// Loads the saved settings, or the defaults if there are none
+pub fn load_settings() -> Settings {
+    get_config_dir()
+        .map(|dir| load_settings_from(&dir))
+        .unwrap_or_default()
+}
+
+/// Saves the settings to the configuration file
+pub fn save_settings(settings: &Settings) {
+    let Some(config_dir) = get_config_dir() else {
+        return;
+    };
+    if let Err(e) = save_settings_to(&config_dir, settings) {
+        logger::error(&format!("Failed to write settings: {}", e));
+    }
+}
+
+/// Changes the saved settings with `update`, keeping the others
+pub fn update_settings(update: impl FnOnce(&mut Settings)) {
+    let Some(config_dir) = get_config_dir() else {
+        return;
+    };
+    let mut settings = load_settings_from(&config_dir);
+    update(&mut settings);
+    if let Err(e) = save_settings_to(&config_dir, &settings) {
+        logger::error(&format!("Failed to write settings: {}", e));
+    }
+}
+
+/// Reads the settings from `config_dir`; a missing or invalid file gives
+/// the defaults.
+///
+/// The preferences older versions kept in one text file each are carried
+/// over once: they are written to the settings file and then removed. The
+/// theme of a `theme.txt` is only taken without a settings file, which has
+/// held the theme since.
+pub fn load_settings_from(config_dir: &Path) -> Settings {
+    let path = config_dir.join(SETTINGS_CONFIG_FILE);
+    let (settings, legacy_files) = match std::fs::read_to_string(&path) {
+        Ok(contents) => {
+            let settings = serde_json::from_str(&contents).unwrap_or_else(|e| {
+                logger::warn(&format!(
+                    "Ignoring invalid settings {}: {}",
+                    path.display(),
+                    e
+                ));
+                Settings::default()
+            });
+            (settings, &LEGACY_FILES[1..])
+        }
+        Err(e) if e.kind() == io::ErrorKind::NotFound => (Settings::default(), &LEGACY_FILES[..]),
+        Err(e) => {
+            logger::warn(&format!("Cannot read settings {}: {}", path.display(), e));
+            return Settings::default();
+        }
+    };
+    migrate_legacy_files(config_dir, settings, legacy_files)
+}
+
+/// Writes the settings to `config_dir`, creating the directory if needed
+pub fn save_settings_to(config_dir: &Path, settings: &Settings) -> io::Result<()> {
+    std::fs::create_dir_all(config_dir)?;
+    let json = serde_json::to_string_pretty(settings).map_err(io::Error::other)?;
+    std::fs::write(config_dir.join(SETTINGS_CONFIG_FILE), json)
+}
+
+/// A text file of older versions holding one setting, and how its trimmed
+/// contents apply to the settings
+type LegacyFile = (&'static str, fn(&mut Settings, &str));
+
+/// The text files of older versions, the theme first
+const LEGACY_FILES: [LegacyFile; 4] = [
+    (THEME_CONFIG_FILE, |settings, contents| {
+        settings.theme = Theme::parse(contents).unwrap_or(Theme::System)
+    }),
+    (VIEW_MODE_CONFIG_FILE, |settings, contents| {
+        settings.view_mode = ViewMode::parse(contents).unwrap_or_default()
+    }),
+    (LOCALE_CONFIG_FILE, |settings, contents| {
+        settings.locale = Locale::parse(contents).unwrap_or_default()
+    }),
+    (ONBOARDING_CONFIG_FILE, |settings, contents| {
+        settings.onboarding_dismissed = contents == ONBOARDING_DISMISSED
+    }),
+];
+
+/// Moves the settings of the `legacy_files` found in `config_dir` into
+/// `settings`, saving them and removing the files
+fn migrate_legacy_files(
+    config_dir: &Path,
+    mut settings: Settings,
+    legacy_files: &[LegacyFile],
+) -> Settings {
+    let mut migrated = Vec::new();
+    for (name, apply) in legacy_files {
+        let path = config_dir.join(name);
+        if let Ok(contents) = std::fs::read_to_string(&path) {
+            apply(&mut settings, contents.trim());
+            migrated.push(path);
+        }
+    }
+    if migrated.is_empty() {
+        return settings;
+    }
+    if let Err(e) = save_settings_to(config_dir, &settings) {
+        logger::error(&format!("Failed to write settings: {}", e));
+        return settings;
+    }
+    for path in migrated {
+        logger::info(&format!("Moved {} into settings", path.display()));
+        if let Err(e) = std::fs::remove_file(&path) {
+            logger::warn(&format!("Cannot remove {}: {}", path.display(), e));
+        }
+    }
+    settings
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::recent::RecentComparison;
+
+    #[test]
+    fn test_view_mode_parse_valid() {
+        assert_eq!(ViewMode::parse("tree"), Some(ViewMode::Tree));
+        assert_eq!(ViewMode::parse("table"), Some(ViewMode::Table));
+    }
+
+    #[test]
+    fn test_view_mode_parse_invalid() {
+        assert_eq!(ViewMode::parse(""), None);
+        assert_eq!(ViewMode::parse("Table"), None);
+        assert_eq!(ViewMode::parse("grid"), None);
+    }
+
+    #[test]
+    fn test_view_mode_roundtrip() {
+        for mode in [ViewMode::Tree, ViewMode::Table] {
+            assert_eq!(ViewMode::parse(mode.as_str()), Some(mode));
+        }
+    }
+
+    #[test]
+    fn test_settings_roundtrip() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let config_dir = temp_dir.path().join("dir-compare");
+        let mut settings = Settings {
+            dir_a_path: "/data/photos".to_string(),
+            dir_b_path: "/backup/photos".to_string(),
+            ignore_file_path: Some(".dir-compare-ignore".to_string()),
+            comparison_method: ComparisonStrategyType::Mtime,
+            theme: Theme::Dark,
+            pairs: SavedPairs::default(),
+            recent: RecentComparisons::default(),
+            diff_max_lines: 500,
+            view_mode: ViewMode::Table,
+            locale: Locale::German,
+            onboarding_dismissed: true,
+        };
+        settings.recent.record(RecentComparison {
+            dir_a: "/data/photos".to_string(),
+            dir_b: "/backup/photos".to_string(),
+            method: "mtime".to_string(),
+            compared_at: 1_700_000_000,
+        });
+
+        save_settings_to(&config_dir, &settings).unwrap();
+        assert_eq!(load_settings_from(&config_dir), settings);
+        let json = std::fs::read_to_string(config_dir.join(SETTINGS_CONFIG_FILE)).unwrap();
+        assert!(json.contains(r#""comparison_method": "mtime""#), "{}", json);
+        assert!(json.contains(r#""theme": "dark""#), "{}", json);
+        assert!(json.contains(r#""diff_max_lines": 500"#), "{}", json);
+        assert!(json.contains(r#""view_mode": "table""#), "{}", json);
+        assert!(json.contains(r#""locale": "de""#), "{}", json);
+    }
+
+    #[test]
+    fn test_missing_or_invalid_settings_give_defaults() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        assert_eq!(load_settings_from(temp_dir.path()), Settings::default());
+
+        let path = temp_dir.path().join(SETTINGS_CONFIG_FILE);
+        std::fs::write(&path, "{ not json").unwrap();
+        assert_eq!(load_settings_from(temp_dir.path()), Settings::default());
+
+        std::fs::write(&path, r#"{"dir_a_path": 42}"#).unwrap();
+        assert_eq!(load_settings_from(temp_dir.path()), Settings::default());
+    }
+
+    #[test]
+    fn test_unknown_values_fall_back_per_field() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        std::fs::write(
+            temp_dir.path().join(SETTINGS_CONFIG_FILE),
+            r#"{"dir_a_path": "/a", "comparison_method": "telepathy", "theme": "neon", "view_mode": "grid", "locale": "xx"}"#,
+        )
+        .unwrap();
+
+        let settings = load_settings_from(temp_dir.path());
+        assert_eq!(settings.dir_a_path, "/a");
+        assert_eq!(settings.dir_b_path, "");
+        assert_eq!(settings.ignore_file_path, None);
+        assert_eq!(settings.comparison_method, ComparisonStrategyType::FastHash);
+        assert_eq!(settings.theme, Theme::System);
+        assert_eq!(settings.view_mode, ViewMode::Tree);
+        assert_eq!(settings.locale, Locale::English);
+        assert!(!settings.onboarding_dismissed);
+    }
+
+    #[test]
+    fn test_theme_file_is_migrated_once() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let theme_path = temp_dir.path().join(THEME_CONFIG_FILE);
+        std::fs::write(&theme_path, "light\n").unwrap();
+
+        let settings = load_settings_from(temp_dir.path());
+        assert_eq!(settings.theme, Theme::Light);
+        assert!(!theme_path.exists());
+        assert!(temp_dir.path().join(SETTINGS_CONFIG_FILE).exists());
+
+        // The settings file wins over a theme file written afterwards
+        std::fs::write(&theme_path, "dark").unwrap();
+        assert_eq!(load_settings_from(temp_dir.path()).theme, Theme::Light);
+    }
+
+    #[test]
+    fn test_preference_files_are_migrated_into_existing_settings() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let settings = Settings {
+            dir_a_path: "/a".to_string(),
+            theme: Theme::Dark,
+            ..Settings::default()
+        };
+        save_settings_to(temp_dir.path(), &settings).unwrap();
+        let files = [
+            (VIEW_MODE_CONFIG_FILE, "table\n"),
+            (LOCALE_CONFIG_FILE, "ja"),
+            (ONBOARDING_CONFIG_FILE, ONBOARDING_DISMISSED),
+            (THEME_CONFIG_FILE, "light"),
+        ];
+        for (name, contents) in files {
+            std::fs::write(temp_dir.path().join(name), contents).unwrap();
+        }
+
+        let migrated = load_settings_from(temp_dir.path());
+        assert_eq!(
+            migrated,
+            Settings {
+                view_mode: ViewMode::Table,
+                locale: Locale::Japanese,
+                onboarding_dismissed: true,
+                ..settings
+            }
+        );
+        for (name, _) in &files[..3] {
+            assert!(!temp_dir.path().join(name).exists(), "{}", name);
+        }
+        // The settings file has held the theme since theme.txt went away
+        assert!(temp_dir.path().join(THEME_CONFIG_FILE).exists());
+        assert_eq!(load_settings_from(temp_dir.path()), migrated);
+    }
+
+    #[test]
+    fn test_onboarding_file_ignores_unknown_contents() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        std::fs::write(temp_dir.path().join(ONBOARDING_CONFIG_FILE), "maybe").unwrap();
+        assert!(!load_settings_from(temp_dir.path()).onboarding_dismissed);
+    }
+}
@@ -0,0 +1,203 @@
+//! Noticing that the compared directories changed after a comparison.
+//!
+//! Right after a comparison the worker captures a [`TreeFingerprint`] of
+//! each root. Every [`CHECK_INTERVAL`], and when the window regains focus,
+//! the roots of the active tab are fingerprinted again on a background
+//! thread; if either differs, a banner offers to run the comparison again.
+
+use dir_compare_core::{TreeFingerprint, logger};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{Receiver, TryRecvError, channel};
+use std::time::{Duration, Instant, SystemTime};
+
+use crate::strings::Strings;
+use crate::tabs::{TabId, TabState};
+
+/// Time between two checks of the active tab's directories
+pub const CHECK_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Fingerprints of both roots; `None` where a root could not be read
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RootFingerprints {
+    pub a: Option<TreeFingerprint>,
+    pub b: Option<TreeFingerprint>,
+}
+
+impl RootFingerprints {
+    pub fn capture(dir_a: &Path, dir_b: &Path) -> Self {
+        let capture = |root: &Path| match TreeFingerprint::capture(root) {
+            Ok(fingerprint) => Some(fingerprint),
+            Err(e) => {
+                logger::debug(&format!("Cannot fingerprint {}: {}", root.display(), e));
+                None
+            }
+        };
+        Self {
+            a: capture(dir_a),
+            b: capture(dir_b),
+        }
+    }
+}
+
+/// The compared directories as they were when a comparison finished
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ComparisonSnapshot {
+    pub dir_a: PathBuf,
+    pub dir_b: PathBuf,
+    pub compared_at: SystemTime,
+    pub fingerprints: RootFingerprints,
+}
+
+impl ComparisonSnapshot {
+    /// Exchanges the directories, following [`TabState::swap_directories`]
+    pub fn swap_sides(&mut self) {
+        std::mem::swap(&mut self.dir_a, &mut self.dir_b);
+        let fingerprints = &mut self.fingerprints;
+        std::mem::swap(&mut fingerprints.a, &mut fingerprints.b);
+    }
+
+    /// Fingerprints both roots; meant for the worker thread, right after
+    /// the comparison
+    pub fn capture(dir_a: &Path, dir_b: &Path) -> Self {
+        Self {
+            dir_a: dir_a.to_path_buf(),
+            dir_b: dir_b.to_path_buf(),
+            compared_at: SystemTime::now(),
+            fingerprints: RootFingerprints::capture(dir_a, dir_b),
+        }
+    }
+}
+
+/// Returns true if a root changed between the `captured` and the `current`
+/// fingerprints.
+///
+/// A root whose fingerprint is unavailable at either time never counts as
+/// changed, so an unreadable directory shows no banner.
+pub fn is_stale(captured: &RootFingerprints, current: &RootFingerprints) -> bool {
+    let changed = |then: Option<TreeFingerprint>, now: Option<TreeFingerprint>| matches!((then, now), (Some(then), Some(now)) if then != now);
+    changed(captured.a, current.a) || changed(captured.b, current.b)
+}
+
+/// A tab whose results are older than its directories
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StaleBanner {
+    pub tab: TabId,
+    /// When the stale comparison finished
+    pub compared_at: SystemTime,
+}
+
+/// A check running on a background thread
+struct PendingCheck {
+    tab: TabId,
+    snapshot: ComparisonSnapshot,
+    receiver: Receiver<RootFingerprints>,
+}
+
+/// Schedules the checks and holds the banner they raised
+#[derive(Default)]
+pub struct StalenessMonitor {
+    last_check: Option<Instant>,
+    pending: Option<PendingCheck>,
+    /// The tab found stale by the last check that found a change
+    pub stale: Option<StaleBanner>,
+}
+
+impl StalenessMonitor {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns true if a check should start: none is running, and the
+    /// window regained focus or [`CHECK_INTERVAL`] passed since the last one
+    pub fn is_due(&self, now: Instant, focus_gained: bool) -> bool {
+        self.pending.is_none()
+            && (focus_gained
+                || self
+                    .last_check
+                    .is_none_or(|last| now.duration_since(last) >= CHECK_INTERVAL))
+    }
+
+    /// Fingerprints the directories of `tab` on a background thread, calling
+    /// `done` once the fingerprints are ready; tabs without results are
+    /// skipped.
+    pub fn start_check(
+        &mut self,
+        tab: &TabState,
+        now: Instant,
+        done: impl FnOnce() + Send + 'static,
+    ) {
+        self.last_check = Some(now);
+        let Some(snapshot) = tab.snapshot.clone() else {
+            return;
+        };
+        let (sender, receiver) = channel();
+        let (dir_a, dir_b) = (snapshot.dir_a.clone(), snapshot.dir_b.clone());
+        std::thread::spawn(move || {
+            let _ = sender.send(RootFingerprints::capture(&dir_a, &dir_b));
+            done();
+        });
+        self.pending = Some(PendingCheck {
+            tab: tab.id(),
+            snapshot,
+            receiver,
+        });
+    }
+
+    /// Applies the outcome of the running check once it is done
+    pub fn poll(&mut self) {
+        let Some(pending) = &self.pending else {
+            return;
+        };
+        let current = match pending.receiver.try_recv() {
+            Ok(current) => current,
+            Err(TryRecvError::Empty) => return,
+            Err(TryRecvError::Disconnected) => {
+                self.pending = None;
+                return;
+            }
+        };
+        let PendingCheck { tab, snapshot, .. } = self.pending.take().unwrap();
+        self.record(tab, &snapshot, &current);
+    }
+
+    /// Raises the banner for `tab` if its directories changed since
+    /// `snapshot` was taken, or lowers it if it was raised for the same
+    /// comparison and they no longer differ
+    pub fn record(
+        &mut self,
+        tab: TabId,
+        snapshot: &ComparisonSnapshot,
+        current: &RootFingerprints,
+    ) {
+        let banner = StaleBanner {
+            tab,
+            compared_at: snapshot.compared_at,
+        };
+        if is_stale(&snapshot.fingerprints, current) {
+            self.stale = Some(banner);
+        } else if self.stale == Some(banner) {
+            self.stale = None;
+        }
+    }
+
+    /// The banner to show above the results of `tab`, if they are stale;
+    /// a banner raised before the tab compared again is not shown
+    pub fn banner_for(&self, tab: &TabState) -> Option<&StaleBanner> {
+        let snapshot = tab.snapshot.as_ref()?;
+        self.stale
+            .as_ref()
+            .filter(|banner| banner.tab == tab.id() && banner.compared_at == snapshot.compared_at)
+    }
+}
+
+/// Describes how long ago something happened, such as "2 minutes ago"
+pub fn format_age(elapsed: Duration, strings: &Strings) -> String {
+    let minutes = elapsed.as_secs() / 60;
+    match minutes {
+        0 => strings.get("age.just_now").to_string(),
+        1 => strings.get("age.minute").to_string(),
+        2..60 => strings.format("age.minutes", &[("count", &minutes)]),
+        60..120 => strings.get("age.hour").to_string(),
+        _ => strings.format("age.hours", &[("count", &(minutes / 60))]),
+    }
+}
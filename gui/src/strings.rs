@@ -0,0 +1,263 @@
+//! User-facing GUI strings.
+//!
+//! Each locale has a catalog in `gui/locales/<code>.txt` with one
+//! `key = value` pair per line; `#` starts a comment. Values may contain
+//! `{name}` placeholders filled in by [`Strings::format`]. Catalogs are
+//! embedded at compile time, and keys missing from a catalog fall back to
+//! the English value.
+
+use std::collections::HashMap;
+
+use dir_compare_core::{Labels, logger};
+
+const EN_CATALOG: &str = include_str!("../locales/en.txt");
+const DE_CATALOG: &str = include_str!("../locales/de.txt");
+const JA_CATALOG: &str = include_str!("../locales/ja.txt");
+
+/// Language used for the GUI
+#[derive(PartialEq, Eq, Copy, Clone, Debug, Default)]
+pub enum Locale {
+    #[default]
+    English,
+    German,
+    Japanese,
+}
+
+impl Locale {
+    /// All supported locales, in menu order
+    pub const ALL: [Locale; 3] = [Locale::English, Locale::German, Locale::Japanese];
+
+    /// Returns the language code of the locale
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Locale::English => "en",
+            Locale::German => "de",
+            Locale::Japanese => "ja",
+        }
+    }
+
+    /// Parses a locale from its language code
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "en" => Some(Locale::English),
+            "de" => Some(Locale::German),
+            "ja" => Some(Locale::Japanese),
+            _ => None,
+        }
+    }
+
+    /// Returns the name of the language in that language
+    pub fn display_name(&self) -> &'static str {
+        match self {
+            Locale::English => "English",
+            Locale::German => "Deutsch",
+            Locale::Japanese => "日本語",
+        }
+    }
+
+    /// Returns the embedded catalog source for the locale
+    pub fn catalog(&self) -> &'static str {
+        match self {
+            Locale::English => EN_CATALOG,
+            Locale::German => DE_CATALOG,
+            Locale::Japanese => JA_CATALOG,
+        }
+    }
+
+    /// Returns true if counts of exactly one take a singular noun
+    fn has_singular(&self) -> bool {
+        match self {
+            Locale::English | Locale::German => true,
+            Locale::Japanese => false,
+        }
+    }
+}
+
+/// The resolved string table for one locale
+#[derive(Debug, Clone)]
+pub struct Strings {
+    locale: Locale,
+    values: HashMap<String, String>,
+    missing: Vec<String>,
+}
+
+impl Default for Strings {
+    fn default() -> Self {
+        Self::new(Locale::default())
+    }
+}
+
+impl Strings {
+    /// Loads the embedded catalog for `locale`
+    pub fn new(locale: Locale) -> Self {
+        Self::from_catalog(locale, locale.catalog())
+    }
+
+    /// Builds a string table from catalog source, using English for any
+    /// key the catalog does not define.
+    ///
+    /// A warning is logged for each missing key.
+    pub fn from_catalog(locale: Locale, source: &str) -> Self {
+        let mut values = parse_catalog(EN_CATALOG);
+        let translated = parse_catalog(source);
+
+        let mut missing: Vec<String> = values
+            .keys()
+            .filter(|key| !translated.contains_key(*key))
+            .cloned()
+            .collect();
+        missing.sort();
+        if locale != Locale::English {
+            for key in &missing {
+                logger::warn(&format!(
+                    "Missing '{}' translation for '{}', using English",
+                    locale.as_str(),
+                    key
+                ));
+            }
+        }
+
+        values.extend(translated);
+        Self {
+            locale,
+            values,
+            missing,
+        }
+    }
+
+    /// Returns the locale of this table
+    pub fn locale(&self) -> Locale {
+        self.locale
+    }
+
+    /// Returns the keys that fell back to English, sorted
+    pub fn missing_keys(&self) -> &[String] {
+        &self.missing
+    }
+
+    /// Returns the string for `key`, or the key itself if it is unknown
+    pub fn get<'a>(&'a self, key: &'a str) -> &'a str {
+        self.values.get(key).map(String::as_str).unwrap_or(key)
+    }
+
+    /// Returns the string for `key`, or `fallback` if it is unknown
+    pub fn get_or<'a>(&'a self, key: &str, fallback: &'a str) -> &'a str {
+        self.values.get(key).map(String::as_str).unwrap_or(fallback)
+    }
+
+    /// Returns the string for `key` with `{name}` placeholders replaced
+    pub fn format(&self, key: &str, args: &[(&str, &dyn std::fmt::Display)]) -> String {
+        args.iter()
+            .fold(self.get(key).to_string(), |text, (name, value)| {
+                text.replace(&format!("{{{}}}", name), &value.to_string())
+            })
+    }
+
+    /// Formats a count followed by the locale's noun for "entries"
+    pub fn entries(&self, count: usize) -> String {
+        self.report_labels().entries(count)
+    }
+
+    /// Returns the report section labels for the text and Markdown formatters
+    pub fn report_labels(&self) -> Labels {
+        let entry_other = self.get("report.entry_other").to_string();
+        let entry_one = if self.locale.has_singular() {
+            self.get("report.entry_one").to_string()
+        } else {
+            entry_other.clone()
+        };
+        Labels {
+            report_title: self.get("report.title").to_string(),
+            summary: self.get("report.summary").to_string(),
+            category: self.get("report.category").to_string(),
+            count: self.get("report.count").to_string(),
+            a_only: self.get("report.a_only").to_string(),
+            b_only: self.get("report.b_only").to_string(),
+            both: self.get("report.both").to_string(),
+            modified: self.get("report.modified").to_string(),
+            no_entries: self.get("report.no_entries").to_string(),
+            no_matching_entries: self.get("report.no_matching_entries").to_string(),
+            entry_one,
+            entry_other,
+            more: self.get("report.more").to_string(),
+            nested_repository: self.get("report.nested_repository").to_string(),
+            stats: self.get("report.stats").to_string(),
+            ..Labels::for_language(self.locale.as_str()).unwrap_or_default()
+        }
+    }
+}
+
+/// Parses `key = value` lines, skipping blank lines and `#` comments
+fn parse_catalog(source: &str) -> HashMap<String, String> {
+    source
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .filter_map(|line| line.split_once('='))
+        .map(|(key, value)| (key.trim().to_string(), value.trim().to_string()))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_locale_roundtrip() {
+        for locale in Locale::ALL {
+            assert_eq!(Locale::parse(locale.as_str()), Some(locale));
+        }
+        assert_eq!(Locale::parse("fr"), None);
+        assert_eq!(Locale::parse("EN"), None);
+    }
+
+    #[test]
+    fn test_parse_catalog_skips_comments_and_blank_lines() {
+        let catalog = parse_catalog("# comment\n\n a.b = Hello = World \nbroken line\n");
+        assert_eq!(catalog.len(), 1);
+        assert_eq!(catalog["a.b"], "Hello = World");
+    }
+
+    #[test]
+    fn test_embedded_catalogs_are_complete() {
+        for locale in Locale::ALL {
+            let strings = Strings::new(locale);
+            assert!(
+                strings.missing_keys().is_empty(),
+                "{:?} is missing {:?}",
+                locale,
+                strings.missing_keys()
+            );
+        }
+    }
+
+    #[test]
+    fn test_unknown_key_returns_key() {
+        let strings = Strings::default();
+        assert_eq!(strings.get("no.such.key"), "no.such.key");
+    }
+
+    #[test]
+    fn test_format_placeholders() {
+        let strings = Strings::new(Locale::German);
+        assert_eq!(
+            strings.format("status.total", &[("count", &42)]),
+            "Gesamt: 42"
+        );
+    }
+
+    #[test]
+    fn test_plural_rules() {
+        let en = Strings::new(Locale::English);
+        assert_eq!(en.entries(1), "1 entry");
+        assert_eq!(en.entries(3), "3 entries");
+
+        let de = Strings::new(Locale::German);
+        assert_eq!(de.entries(1), "1 Eintrag");
+        assert_eq!(de.entries(0), "0 Einträge");
+
+        let ja = Strings::new(Locale::Japanese);
+        assert_eq!(ja.entries(1), "1 件");
+        assert_eq!(ja.entries(5), "5 件");
+    }
+}
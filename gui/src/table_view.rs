@@ -0,0 +1,452 @@
+use dir_compare_core::logger::civil_from_days;
+use dir_compare_core::{ComparisonResult, Entry, EntryKind};
+use eframe::egui;
+use std::cmp::Ordering;
+use std::path::PathBuf;
+use std::time::SystemTime;
+
+use crate::strings::Strings;
+
+const ROW_HEIGHT: f32 = 18.0;
+
+/// Column layout of the results table: sort key, header string key and width
+const COLUMNS: [(SortColumn, &str, f32); 7] = [
+    (SortColumn::Path, "column.path", 260.0),
+    (SortColumn::Category, "column.category", 70.0),
+    (SortColumn::Kind, "column.kind", 60.0),
+    (SortColumn::SizeA, "column.size_a", 80.0),
+    (SortColumn::SizeB, "column.size_b", 80.0),
+    (SortColumn::Delta, "column.delta", 80.0),
+    (SortColumn::Modified, "column.modified", 130.0),
+];
+
+/// Which section of a comparison result an entry belongs to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum ResultCategory {
+    AOnly,
+    BOnly,
+    Both,
+    Modified,
+    /// A file of B paired with the file of A it was moved from
+    Renamed,
+}
+
+impl ResultCategory {
+    /// The category of the same entry once A and B are swapped
+    pub fn swapped(self) -> Self {
+        match self {
+            ResultCategory::AOnly => ResultCategory::BOnly,
+            ResultCategory::BOnly => ResultCategory::AOnly,
+            category => category,
+        }
+    }
+
+    /// Returns the string key of the short label shown in the table and details panel
+    pub fn label_key(&self) -> &'static str {
+        match self {
+            ResultCategory::AOnly => "category.a_only",
+            ResultCategory::BOnly => "category.b_only",
+            ResultCategory::Both => "category.both",
+            ResultCategory::Modified => "category.modified",
+            ResultCategory::Renamed => "category.renamed",
+        }
+    }
+
+    /// Returns the color used for entries of this category
+    pub fn color(&self) -> egui::Color32 {
+        match self {
+            ResultCategory::AOnly => egui::Color32::from_rgb(255, 100, 100),
+            ResultCategory::BOnly => egui::Color32::from_rgb(100, 255, 100),
+            ResultCategory::Both => egui::Color32::from_rgb(100, 200, 255),
+            ResultCategory::Modified => egui::Color32::from_rgb(255, 180, 80),
+            ResultCategory::Renamed => egui::Color32::from_rgb(200, 140, 255),
+        }
+    }
+}
+
+/// Identifies a selected entry across the tree and table views
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ResultSelection {
+    pub category: ResultCategory,
+    pub path: PathBuf,
+}
+
+/// One flattened row of the results table
+#[derive(Debug, Clone)]
+pub struct ResultRow {
+    pub path: PathBuf,
+    pub category: ResultCategory,
+    pub kind: EntryKind,
+    pub size_a: Option<u64>,
+    pub size_b: Option<u64>,
+    pub mtime: Option<SystemTime>,
+}
+
+impl ResultRow {
+    fn from_entry(entry: &Entry, category: ResultCategory) -> Self {
+        let (size_a, size_b) = match category {
+            ResultCategory::BOnly => (None, entry.size),
+            _ => (entry.size, None),
+        };
+        Self {
+            path: entry.path.clone(),
+            category,
+            kind: entry.kind.clone(),
+            size_a,
+            size_b,
+            mtime: read_mtime(entry),
+        }
+    }
+
+    /// Builds a row for a same-path pair, showing both sizes and the newer mtime
+    fn from_pair(a: &Entry, b: &Entry, category: ResultCategory) -> Self {
+        let mut row = Self::from_entry(a, category);
+        row.size_b = b.size;
+        row.mtime = match (row.mtime, read_mtime(b)) {
+            (Some(ma), Some(mb)) => Some(ma.max(mb)),
+            (ma, mb) => ma.or(mb),
+        };
+        row
+    }
+
+    /// Turns the row into that of the same entry once A and B are swapped
+    pub fn swap_sides(&mut self) {
+        self.category = self.category.swapped();
+        std::mem::swap(&mut self.size_a, &mut self.size_b);
+    }
+
+    /// Size difference B - A, available only when both sizes are known
+    pub fn delta(&self) -> Option<i128> {
+        match (self.size_a, self.size_b) {
+            (Some(a), Some(b)) => Some(b as i128 - a as i128),
+            _ => None,
+        }
+    }
+
+    /// Returns the selection key for this row
+    pub fn selection(&self) -> ResultSelection {
+        ResultSelection {
+            category: self.category,
+            path: self.path.clone(),
+        }
+    }
+
+    /// Returns true if this row is the one identified by `selection`
+    pub fn is_selected(&self, selection: &ResultSelection) -> bool {
+        self.category == selection.category && self.path == selection.path
+    }
+}
+
+fn read_mtime(entry: &Entry) -> Option<SystemTime> {
+    std::fs::symlink_metadata(&entry.abs_path)
+        .and_then(|m| m.modified())
+        .ok()
+}
+
+/// Flattens a comparison result into table rows, ordered by path
+pub fn build_rows(result: &ComparisonResult) -> Vec<ResultRow> {
+    let mut rows = Vec::with_capacity(
+        result.a_only.len() + result.b_only.len() + result.both.len() + result.modified.len(),
+    );
+    rows.extend(
+        result
+            .a_only
+            .iter()
+            .map(|e| ResultRow::from_entry(e, ResultCategory::AOnly)),
+    );
+    rows.extend(
+        result
+            .b_only
+            .iter()
+            .map(|e| ResultRow::from_entry(e, ResultCategory::BOnly)),
+    );
+    rows.extend(
+        result
+            .both
+            .iter()
+            .map(|(a, b)| ResultRow::from_pair(a, b, ResultCategory::Both)),
+    );
+    rows.extend(
+        result
+            .modified
+            .iter()
+            .map(|(a, b)| ResultRow::from_pair(a, b, ResultCategory::Modified)),
+    );
+    sort_rows(&mut rows, SortState::default());
+    rows
+}
+
+/// Finds the row identified by `selection`
+pub fn find_row<'a>(rows: &'a [ResultRow], selection: &ResultSelection) -> Option<&'a ResultRow> {
+    rows.iter().find(|row| row.is_selected(selection))
+}
+
+/// Table column that rows can be sorted by
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SortColumn {
+    Path,
+    Category,
+    Kind,
+    SizeA,
+    SizeB,
+    Delta,
+    Modified,
+}
+
+/// Direction of a table sort
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SortDirection {
+    #[default]
+    Ascending,
+    Descending,
+}
+
+impl SortDirection {
+    fn apply(self, ordering: Ordering) -> Ordering {
+        match self {
+            SortDirection::Ascending => ordering,
+            SortDirection::Descending => ordering.reverse(),
+        }
+    }
+}
+
+/// Current sort column and direction of the results table
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SortState {
+    pub column: SortColumn,
+    pub direction: SortDirection,
+}
+
+impl Default for SortState {
+    fn default() -> Self {
+        Self {
+            column: SortColumn::Path,
+            direction: SortDirection::Ascending,
+        }
+    }
+}
+
+impl SortState {
+    /// Handles a click on a column header: the active column flips direction,
+    /// any other column becomes active in ascending order.
+    pub fn toggle(&mut self, column: SortColumn) {
+        if self.column == column {
+            self.direction = match self.direction {
+                SortDirection::Ascending => SortDirection::Descending,
+                SortDirection::Descending => SortDirection::Ascending,
+            };
+        } else {
+            self.column = column;
+            self.direction = SortDirection::Ascending;
+        }
+    }
+}
+
+/// Sorts rows by the given column and direction.
+///
+/// Missing values (e.g. sizes of directories or of the absent side) always
+/// sort last regardless of direction. Ties are broken by path and category so
+/// the order is identical across runs.
+pub fn sort_rows(rows: &mut [ResultRow], sort: SortState) {
+    rows.sort_by(|a, b| {
+        let primary = match sort.column {
+            SortColumn::Path => sort.direction.apply(a.path.cmp(&b.path)),
+            SortColumn::Category => sort.direction.apply(a.category.cmp(&b.category)),
+            SortColumn::Kind => sort
+                .direction
+                .apply(kind_rank(&a.kind).cmp(&kind_rank(&b.kind))),
+            SortColumn::SizeA => cmp_option(&a.size_a, &b.size_a, sort.direction),
+            SortColumn::SizeB => cmp_option(&a.size_b, &b.size_b, sort.direction),
+            SortColumn::Delta => cmp_option(&a.delta(), &b.delta(), sort.direction),
+            SortColumn::Modified => cmp_option(&a.mtime, &b.mtime, sort.direction),
+        };
+        primary
+            .then_with(|| a.path.cmp(&b.path))
+            .then_with(|| a.category.cmp(&b.category))
+    });
+}
+
+fn kind_rank(kind: &EntryKind) -> u8 {
+    match kind {
+        EntryKind::Directory => 0,
+        EntryKind::File => 1,
+        EntryKind::Symlink { .. } => 2,
+    }
+}
+
+fn cmp_option<T: Ord>(a: &Option<T>, b: &Option<T>, direction: SortDirection) -> Ordering {
+    match (a, b) {
+        (Some(a), Some(b)) => direction.apply(a.cmp(b)),
+        (Some(_), None) => Ordering::Less,
+        (None, Some(_)) => Ordering::Greater,
+        (None, None) => Ordering::Equal,
+    }
+}
+
+fn format_size(size: Option<u64>) -> String {
+    size.map(|s| s.to_string()).unwrap_or_default()
+}
+
+fn format_delta(delta: Option<i128>) -> String {
+    match delta {
+        Some(d) if d > 0 => format!("+{}", d),
+        Some(d) => d.to_string(),
+        None => String::new(),
+    }
+}
+
+/// Formats a modification time as `YYYY-MM-DD HH:MM` (UTC)
+pub fn format_mtime(mtime: Option<SystemTime>) -> String {
+    let Some(secs) = mtime
+        .and_then(|t| t.duration_since(SystemTime::UNIX_EPOCH).ok())
+        .map(|d| d.as_secs())
+    else {
+        return String::new();
+    };
+    let (year, month, day) = civil_from_days((secs / 86_400) as i64);
+    let minutes_of_day = (secs % 86_400) / 60;
+    format!(
+        "{:04}-{:02}-{:02} {:02}:{:02}",
+        year,
+        month,
+        day,
+        minutes_of_day / 60,
+        minutes_of_day % 60
+    )
+}
+
+/// Renders the sortable results table.
+///
+/// Clicking a header re-sorts `rows` in place; clicking a row updates `selected`.
+pub fn render_table(
+    ui: &mut egui::Ui,
+    rows: &mut [ResultRow],
+    sort: &mut SortState,
+    selected: &mut Option<ResultSelection>,
+    strings: &Strings,
+) {
+    ui.horizontal(|ui| {
+        for (column, title_key, width) in COLUMNS {
+            let marker = if sort.column == column {
+                match sort.direction {
+                    SortDirection::Ascending => " ▲",
+                    SortDirection::Descending => " ▼",
+                }
+            } else {
+                ""
+            };
+            let header = egui::Button::new(
+                egui::RichText::new(format!("{}{}", strings.get(title_key), marker)).strong(),
+            )
+            .frame(false);
+            if ui.add_sized([width, ROW_HEIGHT], header).clicked() {
+                sort.toggle(column);
+                sort_rows(rows, *sort);
+            }
+        }
+    });
+    ui.separator();
+
+    egui::ScrollArea::vertical()
+        .id_source("results_table")
+        .auto_shrink([false, true])
+        .show_rows(ui, ROW_HEIGHT, rows.len(), |ui, range| {
+            for row in &rows[range] {
+                let is_selected = selected.as_ref().is_some_and(|s| row.is_selected(s));
+                let cells = [
+                    strings.get(row.category.label_key()).to_string(),
+                    match row.kind {
+                        EntryKind::Directory => strings.get("kind.dir").to_string(),
+                        EntryKind::File => strings.get("kind.file").to_string(),
+                        EntryKind::Symlink { .. } => strings.get("kind.link").to_string(),
+                    },
+                    format_size(row.size_a),
+                    format_size(row.size_b),
+                    format_delta(row.delta()),
+                    format_mtime(row.mtime),
+                ];
+                ui.horizontal(|ui| {
+                    let path_text = egui::RichText::new(row.path.display().to_string())
+                        .color(row.category.color());
+                    let path_label = egui::SelectableLabel::new(is_selected, path_text);
+                    if ui
+                        .add_sized([COLUMNS[0].2, ROW_HEIGHT], path_label)
+                        .clicked()
+                    {
+                        *selected = Some(row.selection());
+                    }
+                    for (cell, (_, _, width)) in cells.iter().zip(COLUMNS.iter().skip(1)) {
+                        ui.add_sized([*width, ROW_HEIGHT], egui::Label::new(cell.as_str()));
+                    }
+                });
+            }
+        });
+}
+
+/// Renders the details of the selected row
+pub fn render_details(ui: &mut egui::Ui, row: &ResultRow, strings: &Strings) {
+    egui::Grid::new("result_details")
+        .num_columns(2)
+        .striped(true)
+        .show(ui, |ui| {
+            ui.label(strings.get("details.path"));
+            ui.label(row.path.display().to_string());
+            ui.end_row();
+            ui.label(strings.get("details.category"));
+            ui.colored_label(row.category.color(), strings.get(row.category.label_key()));
+            ui.end_row();
+            ui.label(strings.get("details.kind"));
+            ui.label(match row.kind {
+                EntryKind::Directory => strings.get("kind.directory"),
+                EntryKind::File => strings.get("kind.file"),
+                EntryKind::Symlink { .. } => strings.get("kind.symlink"),
+            });
+            ui.end_row();
+            ui.label(strings.get("details.size_a"));
+            ui.label(format_size(row.size_a));
+            ui.end_row();
+            ui.label(strings.get("details.size_b"));
+            ui.label(format_size(row.size_b));
+            ui.end_row();
+            ui.label(strings.get("details.delta"));
+            ui.label(format_delta(row.delta()));
+            ui.end_row();
+            ui.label(strings.get("details.modified"));
+            ui.label(format_mtime(row.mtime));
+            ui.end_row();
+        });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn test_format_mtime_epoch() {
+        assert_eq!(
+            format_mtime(Some(SystemTime::UNIX_EPOCH)),
+            "1970-01-01 00:00"
+        );
+    }
+
+    #[test]
+    fn test_format_mtime_leap_day() {
+        // 2024-02-29 12:34:56 UTC
+        let t = SystemTime::UNIX_EPOCH + Duration::from_secs(1_709_210_096);
+        assert_eq!(format_mtime(Some(t)), "2024-02-29 12:34");
+    }
+
+    #[test]
+    fn test_format_mtime_none() {
+        assert_eq!(format_mtime(None), "");
+    }
+
+    #[test]
+    fn test_format_delta_sign() {
+        assert_eq!(format_delta(Some(5)), "+5");
+        assert_eq!(format_delta(Some(-5)), "-5");
+        assert_eq!(format_delta(Some(0)), "0");
+        assert_eq!(format_delta(None), "");
+    }
+}
@@ -0,0 +1,931 @@
+//! Comparison tabs, each with its own inputs, worker and results.
+//!
+//! Workers send [`ComparisonMessage`]s tagged with the [`TabId`] of the tab
+//! that started them, so results land in that tab even after the user
+//! switched to another one. Closing a tab cancels its worker, and messages
+//! that still arrive for a closed tab are dropped. A tab whose worker ended
+//! without a result is failed; see [`crate::worker`].
+
+use dir_compare_core::text_diff::DEFAULT_MAX_DIFF_LINES;
+use dir_compare_core::{
+    CancellationToken, ComparisonResult, ComparisonStrategyType, Entry, EntryKind, OptionValues,
+    logger,
+};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{Receiver, Sender, channel};
+use std::time::{Duration, Instant};
+
+use crate::dialog::FileDialogProvider;
+use crate::diff_view::DiffView;
+use crate::dropped_paths::DropError;
+use crate::export::{self, DEFAULT_REPORT_NAME};
+use crate::filter_bar::{FilterBar, FilterBarError};
+use crate::flat_view::FlatResultsCache;
+use crate::invocation::{self, ComparisonSetup, DEFAULT_SETTINGS_NAME};
+use crate::pair_settings::PairKey;
+use crate::progress::{ComparisonMessage, ProgressUpdate};
+use crate::settings::ViewMode;
+use crate::staleness::ComparisonSnapshot;
+use crate::strings::{Locale, Strings};
+use crate::table_view::{self, ResultCategory, ResultRow, ResultSelection, SortState};
+use crate::theme::Theme;
+use crate::tree_view::{self, FileTreeNode, RowIndex, TreeExpansion, TreeRows};
+use crate::worker::{WORKER_INACTIVITY, WorkerWatch};
+
+/// Identifies a tab; ids are never reused, even after the tab is closed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct TabId(u64);
+
+/// Settings shared by all tabs
+pub struct GlobalState {
+    pub theme: Theme,
+    pub view_mode: ViewMode,
+    pub locale: Locale,
+    pub strings: Strings,
+    /// Lines of each file compared when showing the diff of a modified file
+    pub diff_max_lines: usize,
+}
+
+impl GlobalState {
+    pub fn new(theme: Theme, view_mode: ViewMode, locale: Locale) -> Self {
+        Self {
+            theme,
+            view_mode,
+            locale,
+            strings: Strings::new(locale),
+            diff_max_lines: DEFAULT_MAX_DIFF_LINES,
+        }
+    }
+}
+
+/// Cached tree and table data for displaying comparison results
+pub struct TreeCache {
+    pub a_only: Vec<FileTreeNode>,
+    pub b_only: Vec<FileTreeNode>,
+    pub both: Vec<FileTreeNode>,
+    pub modified: Vec<FileTreeNode>,
+    /// Files of B moved from another path in A, see
+    /// [`ComparisonResult::renamed`]
+    pub renamed: Vec<FileTreeNode>,
+    pub rows: Vec<ResultRow>,
+    /// Directory the entries of A were listed from; `None` without entries
+    pub root_a: Option<PathBuf>,
+    /// Directory the entries of B were listed from; `None` without entries
+    pub root_b: Option<PathBuf>,
+    /// Query the trees were last filtered by
+    query: String,
+    /// The trees narrowed to `query`, in the order of [`TreeCache::sections`];
+    /// `None` while no filter is active
+    filtered: Option<[Vec<FileTreeNode>; 5]>,
+    /// The trees to show flattened for the [`TreeExpansion::generation`]
+    /// they were flattened for; `None` until asked for and after the
+    /// trees to show changed
+    flat_rows: Option<(u64, Vec<RowIndex>)>,
+}
+
+impl TreeCache {
+    /// The categories of the trees of [`TreeCache::sections`], in order
+    pub const SECTIONS: [ResultCategory; 5] = [
+        ResultCategory::AOnly,
+        ResultCategory::BOnly,
+        ResultCategory::Modified,
+        ResultCategory::Both,
+        ResultCategory::Renamed,
+    ];
+
+    /// Builds the trees and the table rows, sorted by `sort_state`
+    pub fn new(result: &ComparisonResult, sort_state: SortState) -> Self {
+        let both: Vec<Entry> = result.both.iter().map(|(a, _)| a.clone()).collect();
+        let mut rows = table_view::build_rows(result);
+        table_view::sort_rows(&mut rows, sort_state);
+        let entries_a = result.a_only.iter().chain(
+            result
+                .both
+                .iter()
+                .chain(&result.modified)
+                .chain(&result.renamed)
+                .map(|(a, _)| a),
+        );
+        let entries_b = result.b_only.iter().chain(
+            result
+                .both
+                .iter()
+                .chain(&result.modified)
+                .chain(&result.renamed)
+                .map(|(_, b)| b),
+        );
+        let mut both = FileTreeNode::from_entries(&both);
+        tree_view::collapse_identical(&mut both, &result.identical_directories());
+        Self {
+            root_a: entries_a.map(root_of).next(),
+            root_b: entries_b.map(root_of).next(),
+            a_only: FileTreeNode::from_entries(&result.a_only),
+            b_only: FileTreeNode::from_entries(&result.b_only),
+            both,
+            modified: tree_view::modified_tree(&result.modified, &result.mismatch_reasons),
+            renamed: tree_view::renamed_tree(&result.renamed),
+            rows,
+            query: String::new(),
+            filtered: None,
+            flat_rows: None,
+        }
+    }
+
+    /// Updates the trees and rows for `result` after
+    /// [`ComparisonResult::swap_sides`], without listing them again.
+    ///
+    /// The A-only and B-only trees and the roots trade places and the rows
+    /// trade sides; the modified and both trees, which show A's entries, and
+    /// the renamed tree, which shows B's, are rebuilt from `result`.
+    pub fn swap_sides(&mut self, result: &ComparisonResult, sort_state: SortState) {
+        std::mem::swap(&mut self.a_only, &mut self.b_only);
+        std::mem::swap(&mut self.root_a, &mut self.root_b);
+        let a_side = |pairs: &[(Entry, Entry)]| -> Vec<Entry> {
+            pairs.iter().map(|(a, _)| a.clone()).collect()
+        };
+        self.both = FileTreeNode::from_entries(&a_side(&result.both));
+        tree_view::collapse_identical(&mut self.both, &result.identical_directories());
+        self.modified = tree_view::modified_tree(&result.modified, &result.mismatch_reasons);
+        self.renamed = tree_view::renamed_tree(&result.renamed);
+        for row in &mut self.rows {
+            row.swap_sides();
+        }
+        table_view::sort_rows(&mut self.rows, sort_state);
+        let query = std::mem::take(&mut self.query);
+        self.filtered = None;
+        self.flat_rows = None;
+        self.set_filter(&query);
+    }
+
+    /// Returns the absolute path of the entry at relative `path` in the
+    /// tree of `category`; the modified and both trees show A's entries,
+    /// the renamed tree B's
+    pub fn absolute_path(&self, category: ResultCategory, path: &Path) -> Option<PathBuf> {
+        let root = match category {
+            ResultCategory::BOnly | ResultCategory::Renamed => self.root_b.as_ref(),
+            ResultCategory::AOnly | ResultCategory::Modified | ResultCategory::Both => {
+                self.root_a.as_ref()
+            }
+        };
+        root.map(|root| root.join(path))
+    }
+
+    /// Narrows the trees to the nodes whose path contains `query`, ignoring
+    /// case; a blank query shows the full trees again.
+    ///
+    /// Filtering reuses the cached trees and only runs when the query
+    /// changed, so it can be called every frame.
+    pub fn set_filter(&mut self, query: &str) {
+        if query == self.query {
+            return;
+        }
+        self.query = query.to_string();
+        self.flat_rows = None;
+        let query = query.trim();
+        self.filtered = (!query.is_empty()).then(|| {
+            [
+                &self.a_only,
+                &self.b_only,
+                &self.modified,
+                &self.both,
+                &self.renamed,
+            ]
+            .map(|nodes| tree_view::filter_nodes(nodes, query))
+        });
+    }
+
+    /// The trees to show, filtered if a filter is active: A-only, B-only,
+    /// modified, both and renamed
+    pub fn sections(&self) -> [&[FileTreeNode]; 5] {
+        match &self.filtered {
+            Some([a_only, b_only, modified, both, renamed]) => {
+                [a_only, b_only, modified, both, renamed]
+            }
+            None => [
+                &self.a_only,
+                &self.b_only,
+                &self.modified,
+                &self.both,
+                &self.renamed,
+            ],
+        }
+    }
+
+    /// Flattens the trees to show into rows: the header of each section,
+    /// followed by its top-level nodes if the section is open in
+    /// `expansion`. The children of a folder open in `expansion` follow it,
+    /// one level deeper; closed folders are not descended into.
+    ///
+    /// The rows are kept until `expansion` or the filter changes, so this
+    /// can be called every frame.
+    pub fn tree_rows(&mut self, expansion: &TreeExpansion) -> TreeRows<'_> {
+        let generation = expansion.generation();
+        if self.flat_rows.as_ref().map(|(shown, _)| *shown) != Some(generation) {
+            let rows = tree_view::flatten_rows(&self.labelled_sections(), expansion);
+            self.flat_rows = Some((generation, rows));
+        }
+        let rows = self.flat_rows.as_ref().map_or(&[][..], |(_, rows)| rows);
+        TreeRows::new(self.labelled_sections(), rows)
+    }
+
+    /// The trees to show with their categories
+    fn labelled_sections(&self) -> [(ResultCategory, &[FileTreeNode]); 5] {
+        let sections = self.sections();
+        std::array::from_fn(|i| (Self::SECTIONS[i], sections[i]))
+    }
+
+    /// Opens every section and every folder of the trees to show
+    pub fn expand_all(&self, expansion: &mut TreeExpansion) {
+        for (category, nodes) in Self::SECTIONS.into_iter().zip(self.sections()) {
+            expansion.expand_all(category, nodes);
+        }
+    }
+
+    /// The number of nodes shown and the number of all nodes while a filter
+    /// is active
+    pub fn filter_counts(&self) -> Option<(usize, usize)> {
+        let filtered = self.filtered.as_ref()?;
+        let shown = filtered
+            .iter()
+            .map(|nodes| tree_view::count_nodes(nodes))
+            .sum();
+        let total = [
+            &self.a_only,
+            &self.b_only,
+            &self.modified,
+            &self.both,
+            &self.renamed,
+        ]
+        .iter()
+        .map(|nodes| tree_view::count_nodes(nodes))
+        .sum();
+        Some((shown, total))
+    }
+}
+
+/// The root an entry was listed from: its absolute path without the
+/// components of its relative path
+fn root_of(entry: &Entry) -> PathBuf {
+    let mut root = entry.abs_path.clone();
+    for _ in entry.path.components() {
+        root.pop();
+    }
+    root
+}
+
+/// Inputs, progress and results of one comparison tab
+pub struct TabState {
+    id: TabId,
+    pub dir_a_path: String,
+    pub dir_b_path: String,
+    pub ignore_file_path: Option<String>,
+    /// Skip the entries matched by `.gitignore` files in both trees
+    pub respect_gitignore: bool,
+    /// Deepest level listed below both folders; `None` lists every level
+    pub max_depth: Option<usize>,
+    /// Skip hidden files and directories in both trees
+    pub ignore_hidden: bool,
+    /// Pair files moved between the trees by their content
+    pub detect_renames: bool,
+    pub comparison_method: ComparisonStrategyType,
+    /// Option values of `comparison_method`
+    pub strategy_options: OptionValues,
+    /// Group files by content hash regardless of their paths, ignoring
+    /// `comparison_method`
+    pub flat_mode: bool,
+    /// Hash whole files instead of samples in flat mode
+    pub full_hash: bool,
+    /// The complete result of the last comparison, which reports export
+    pub results: Option<ComparisonResult>,
+    /// `results` narrowed by `filter_bar`; `None` while the bar is empty
+    pub filtered_results: Option<ComparisonResult>,
+    /// Trees and rows of the displayed results
+    pub tree_cache: Option<TreeCache>,
+    /// The result of the last flat mode comparison
+    pub flat_results: Option<FlatResultsCache>,
+    /// The roots as they were when the results were produced
+    pub snapshot: Option<ComparisonSnapshot>,
+    pub is_comparing: bool,
+    pub progress: Option<ProgressUpdate>,
+    pub error_message: Option<String>,
+    /// Confirmation shown in the status bar, such as the last exported report
+    pub status_message: Option<String>,
+    /// Dropped files that were not folders, shown for a few seconds
+    pub drop_error: Option<DropError>,
+    pub sort_state: SortState,
+    pub selection: Option<ResultSelection>,
+    /// The diff of the selected modified file
+    pub diff_view: Option<DiffView>,
+    /// Text the result trees are filtered by
+    pub tree_filter: String,
+    /// Open sections and folders of the result trees
+    pub tree_expansion: TreeExpansion,
+    /// Constraints narrowing the displayed results
+    pub filter_bar: FilterBar,
+    /// Why the filter bar could not be applied; the last valid filter stays
+    /// in effect
+    pub filter_error: Option<FilterBarError>,
+    /// Cancels the running comparison; `None` when idle or grouping by
+    /// content, which cannot be cancelled
+    pub cancel: Option<CancellationToken>,
+    /// The thread running the comparison; `None` when idle or when the
+    /// comparison was started without [`crate::worker::spawn_worker`]
+    pub worker: Option<WorkerWatch>,
+    /// The directory pair whose saved settings were applied or dismissed;
+    /// they are not offered again while the paths name it
+    pub answered_pair: Option<PairKey>,
+}
+
+impl TabState {
+    fn new(id: TabId) -> Self {
+        Self {
+            id,
+            dir_a_path: String::new(),
+            dir_b_path: String::new(),
+            ignore_file_path: None,
+            respect_gitignore: false,
+            max_depth: None,
+            ignore_hidden: false,
+            detect_renames: false,
+            comparison_method: ComparisonStrategyType::FastHash,
+            strategy_options: ComparisonStrategyType::FastHash.default_options(),
+            flat_mode: false,
+            full_hash: false,
+            results: None,
+            filtered_results: None,
+            tree_cache: None,
+            flat_results: None,
+            snapshot: None,
+            is_comparing: false,
+            progress: None,
+            error_message: None,
+            status_message: None,
+            drop_error: None,
+            sort_state: SortState::default(),
+            selection: None,
+            diff_view: None,
+            tree_filter: String::new(),
+            tree_expansion: TreeExpansion::default(),
+            filter_bar: FilterBar::default(),
+            filter_error: None,
+            cancel: None,
+            worker: None,
+            answered_pair: None,
+        }
+    }
+
+    pub fn id(&self) -> TabId {
+        self.id
+    }
+
+    /// Tab label, such as "photos ↔ backup", or a localized placeholder
+    /// until both directories are entered
+    pub fn title(&self, strings: &Strings) -> String {
+        let name = |path: &str| {
+            let path = Path::new(path.trim());
+            path.file_name()
+                .unwrap_or(path.as_os_str())
+                .to_string_lossy()
+                .into_owned()
+        };
+        if self.dir_a_path.trim().is_empty() || self.dir_b_path.trim().is_empty() {
+            return strings.get("tabs.new").to_string();
+        }
+        format!("{} ↔ {}", name(&self.dir_a_path), name(&self.dir_b_path))
+    }
+
+    /// Switches to another comparison method, resetting its options to
+    /// their defaults if the method changed
+    pub fn select_method(&mut self, method: ComparisonStrategyType) {
+        if method != self.comparison_method {
+            self.comparison_method = method;
+            self.strategy_options = method.default_options();
+        }
+    }
+
+    /// Clears the previous outcome and marks the tab as comparing.
+    ///
+    /// Returns the token the worker must pass to the comparison.
+    pub fn start_comparison(&mut self) -> CancellationToken {
+        let cancel = CancellationToken::new();
+        self.is_comparing = true;
+        self.results = None;
+        self.filtered_results = None;
+        self.tree_cache = None;
+        self.flat_results = None;
+        self.snapshot = None;
+        self.selection = None;
+        self.diff_view = None;
+        self.error_message = None;
+        self.status_message = None;
+        self.progress = None;
+        self.cancel = Some(cancel.clone());
+        cancel
+    }
+
+    /// Asks the running comparison to stop; its worker then reports it as
+    /// cancelled. Returns false if no comparison can be cancelled.
+    pub fn cancel_comparison(&self) -> bool {
+        match &self.cancel {
+            Some(cancel) if self.is_comparing => {
+                cancel.cancel();
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Asks `dialog` for directory A; a cancelled dialog keeps the current
+    /// path
+    pub fn browse_dir_a(&mut self, dialog: &dyn FileDialogProvider) {
+        if let Some(path) = dialog.pick_folder() {
+            self.dir_a_path = path.display().to_string();
+        }
+    }
+
+    /// Asks `dialog` for directory B; a cancelled dialog keeps the current
+    /// path
+    pub fn browse_dir_b(&mut self, dialog: &dyn FileDialogProvider) {
+        if let Some(path) = dialog.pick_folder() {
+            self.dir_b_path = path.display().to_string();
+        }
+    }
+
+    /// Asks `dialog` for the ignore file; a cancelled dialog keeps the
+    /// current one
+    pub fn browse_ignore_file(&mut self, dialog: &dyn FileDialogProvider) {
+        if let Some(path) = dialog.pick_file() {
+            self.ignore_file_path = Some(path.display().to_string());
+        }
+    }
+
+    /// Asks `dialog` for a destination and writes the comparison settings
+    /// there as a config file for `dir-compare --config`.
+    ///
+    /// The outcome is reported in `status_message` or `error_message`;
+    /// nothing happens when the dialog is cancelled.
+    pub fn export_settings(&mut self, dialog: &dyn FileDialogProvider, strings: &Strings) {
+        let Some(path) = dialog.save_file(DEFAULT_SETTINGS_NAME) else {
+            return;
+        };
+        let cwd = std::env::current_dir().unwrap_or_default();
+        let settings = invocation::settings_toml(&ComparisonSetup::from_tab(self, &cwd));
+        match std::fs::write(&path, settings) {
+            Ok(()) => {
+                logger::info(&format!("Settings written to: {}", path.display()));
+                self.error_message = None;
+                self.status_message =
+                    Some(strings.format("status.settings_exported", &[("path", &path.display())]));
+            }
+            Err(e) => {
+                logger::error(&format!(
+                    "Failed to write settings {}: {}",
+                    path.display(),
+                    e
+                ));
+                self.status_message = None;
+                self.error_message = Some(strings.format(
+                    "error.settings_export_failed",
+                    &[("path", &path.display()), ("error", &e)],
+                ));
+            }
+        }
+    }
+
+    /// Asks `dialog` for a destination and writes the results there, in the
+    /// format given by the file extension.
+    ///
+    /// The outcome is reported in `status_message` or `error_message`; nothing
+    /// happens without results or when the dialog is cancelled.
+    pub fn export_report(&mut self, dialog: &dyn FileDialogProvider, strings: &Strings) {
+        let Some(result) = &self.results else {
+            return;
+        };
+        let Some(path) = dialog.save_file(DEFAULT_REPORT_NAME) else {
+            return;
+        };
+        match export::write_report(&path, result, strings) {
+            Ok(()) => {
+                logger::info(&format!("Report written to: {}", path.display()));
+                self.error_message = None;
+                self.status_message =
+                    Some(strings.format("status.exported", &[("path", &path.display())]));
+            }
+            Err(e) => {
+                logger::error(&format!("Failed to write report {}: {}", path.display(), e));
+                self.status_message = None;
+                self.error_message = Some(strings.format(
+                    "error.export_failed",
+                    &[("path", &path.display()), ("error", &e)],
+                ));
+            }
+        }
+    }
+
+    /// Shows the entry at relative `path` of the `category` tree with
+    /// `reveal`, such as [`crate::file_manager::open_in_file_manager`].
+    ///
+    /// A failure, such as a file deleted since the comparison, is reported in
+    /// `error_message`.
+    pub fn reveal_entry(
+        &mut self,
+        category: ResultCategory,
+        path: &Path,
+        reveal: impl FnOnce(&Path) -> io::Result<()>,
+        strings: &Strings,
+    ) {
+        let Some(absolute) = self
+            .tree_cache
+            .as_ref()
+            .and_then(|cache| cache.absolute_path(category, path))
+        else {
+            return;
+        };
+        match reveal(&absolute) {
+            Ok(()) => self.error_message = None,
+            Err(e) => {
+                logger::error(&format!("Failed to reveal {}: {}", absolute.display(), e));
+                self.error_message = Some(strings.format(
+                    "error.reveal_failed",
+                    &[("path", &absolute.display()), ("error", &e)],
+                ));
+            }
+        }
+    }
+
+    /// Exchanges directories A and B.
+    ///
+    /// Results already shown are swapped in place, so they read as if B had
+    /// been compared with A; the filter bar's category toggles, the selection
+    /// and the snapshot follow. Does nothing while comparing, since the
+    /// running comparison still reports the former sides.
+    pub fn swap_directories(&mut self) {
+        if self.is_comparing {
+            return;
+        }
+        std::mem::swap(&mut self.dir_a_path, &mut self.dir_b_path);
+        for result in [&mut self.results, &mut self.filtered_results]
+            .into_iter()
+            .flatten()
+        {
+            result.swap_sides();
+        }
+        if let (Some(cache), Some(displayed)) = (
+            &mut self.tree_cache,
+            self.filtered_results.as_ref().or(self.results.as_ref()),
+        ) {
+            cache.swap_sides(displayed, self.sort_state);
+        }
+        if let Some(flat) = &mut self.flat_results {
+            flat.swap_sides();
+        }
+        self.filter_bar.sections.swap(0, 1);
+        self.tree_expansion.swap_sides();
+        if let Some(selection) = &mut self.selection {
+            selection.category = selection.category.swapped();
+        }
+        self.diff_view = None;
+        if let Some(snapshot) = &mut self.snapshot {
+            snapshot.swap_sides();
+        }
+    }
+
+    /// Loads the diff of the selected modified file, comparing the first
+    /// `max_lines` lines, unless it is loaded already; drops it when no
+    /// modified file is selected.
+    pub fn update_diff_view(&mut self, max_lines: usize) {
+        let selected = match (&self.tree_cache, &self.selection) {
+            (Some(cache), Some(selection)) => table_view::find_row(&cache.rows, selection)
+                .filter(|row| {
+                    row.category == ResultCategory::Modified && row.kind == EntryKind::File
+                })
+                .and_then(|row| Some((cache.root_a.as_ref()?, cache.root_b.as_ref()?, &row.path))),
+            _ => None,
+        };
+        match selected {
+            Some((root_a, root_b, path)) => {
+                if self
+                    .diff_view
+                    .as_ref()
+                    .is_none_or(|view| view.path != *path)
+                {
+                    self.diff_view = Some(DiffView::load(root_a, root_b, path, max_lines));
+                }
+            }
+            None => self.diff_view = None,
+        }
+    }
+
+    /// The results shown: filtered by the filter bar if it is in use
+    pub fn displayed_results(&self) -> Option<&ComparisonResult> {
+        self.filtered_results.as_ref().or(self.results.as_ref())
+    }
+
+    /// Narrows the results by the filter bar and rebuilds the trees and
+    /// rows from them, keeping the complete results for export.
+    ///
+    /// An invalid input is reported in `filter_error` and leaves the
+    /// displayed results unchanged.
+    pub fn apply_filter_bar(&mut self) {
+        let filter = match self.filter_bar.to_filter() {
+            Ok(filter) => filter,
+            Err(e) => {
+                self.filter_error = Some(e);
+                return;
+            }
+        };
+        self.filter_error = None;
+        let Some(results) = &self.results else {
+            return;
+        };
+        self.filtered_results = (!filter.is_empty()).then(|| results.filter(&filter));
+        let displayed = self.filtered_results.as_ref().unwrap_or(results);
+        let mut cache = TreeCache::new(displayed, self.sort_state);
+        cache.set_filter(&self.tree_filter);
+        self.tree_cache = Some(cache);
+    }
+
+    /// Applies a message from this tab's worker
+    pub fn handle_message(&mut self, message: ComparisonMessage) {
+        if let Some(worker) = &mut self.worker {
+            worker.last_message = Instant::now();
+        }
+        match message {
+            ComparisonMessage::Progress(update) => self.progress = Some(update),
+            ComparisonMessage::Snapshot(snapshot) => self.snapshot = Some(snapshot),
+            ComparisonMessage::Finished(result) => {
+                self.finish();
+                match result {
+                    Ok(result) => {
+                        for warning in &result.warnings {
+                            logger::warn_keyed(warning.cause.key(), &warning.to_string());
+                        }
+                        self.results = Some(result);
+                        self.apply_filter_bar();
+                    }
+                    Err(e) => self.error_message = Some(e),
+                }
+            }
+            ComparisonMessage::FinishedFlat(result) => {
+                self.finish();
+                match result {
+                    Ok(result) => {
+                        for warning in &result.warnings {
+                            logger::warn_keyed(warning.cause.key(), &warning.to_string());
+                        }
+                        self.flat_results = Some(FlatResultsCache::new(result));
+                    }
+                    Err(e) => self.error_message = Some(e),
+                }
+            }
+        }
+    }
+
+    /// Marks the tab as idle once its worker is done
+    fn finish(&mut self) {
+        self.is_comparing = false;
+        self.progress = None;
+        self.cancel = None;
+        self.worker = None;
+    }
+
+    /// Whether the tab still compares although its worker ended and sent
+    /// nothing for `inactivity`
+    fn worker_lost(&self, now: Instant, inactivity: Duration) -> bool {
+        self.is_comparing
+            && self
+                .worker
+                .as_ref()
+                .is_some_and(|worker| worker.is_lost(now, inactivity))
+    }
+}
+
+/// Sends messages from a worker thread to the tab that started it
+#[derive(Clone)]
+pub struct TabSender {
+    id: TabId,
+    sender: Sender<(TabId, ComparisonMessage)>,
+}
+
+impl TabSender {
+    /// Queues a message for the tab; returns false if the app has exited
+    pub fn send(&self, message: ComparisonMessage) -> bool {
+        self.sender.send((self.id, message)).is_ok()
+    }
+}
+
+/// The open tabs, the active one, and the channel shared by their workers.
+///
+/// There is always at least one tab.
+pub struct Tabs {
+    tabs: Vec<TabState>,
+    active: usize,
+    next_id: u64,
+    sender: Sender<(TabId, ComparisonMessage)>,
+    receiver: Receiver<(TabId, ComparisonMessage)>,
+}
+
+impl Default for Tabs {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Tabs {
+    /// Creates a single empty tab
+    pub fn new() -> Self {
+        let (sender, receiver) = channel();
+        let mut tabs = Self {
+            tabs: Vec::new(),
+            active: 0,
+            next_id: 0,
+            sender,
+            receiver,
+        };
+        tabs.add();
+        tabs
+    }
+
+    /// Opens an empty tab and makes it the active one
+    pub fn add(&mut self) -> TabId {
+        let id = TabId(self.next_id);
+        self.next_id += 1;
+        self.tabs.push(TabState::new(id));
+        self.active = self.tabs.len() - 1;
+        id
+    }
+
+    /// Closes a tab, cancelling its comparison if one is running.
+    ///
+    /// Closing the last tab leaves a new empty one. Returns false if no tab
+    /// has this id.
+    pub fn close(&mut self, id: TabId) -> bool {
+        let Some(index) = self.index_of(id) else {
+            return false;
+        };
+        let tab = self.tabs.remove(index);
+        if let Some(cancel) = &tab.cancel {
+            cancel.cancel();
+        }
+        if self.tabs.is_empty() {
+            self.add();
+        } else if index < self.active || self.active == self.tabs.len() {
+            self.active -= 1;
+        }
+        true
+    }
+
+    /// Makes a tab the active one; returns false if no tab has this id
+    pub fn switch(&mut self, id: TabId) -> bool {
+        match self.index_of(id) {
+            Some(index) => {
+                self.active = index;
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn active(&self) -> &TabState {
+        &self.tabs[self.active]
+    }
+
+    pub fn active_mut(&mut self) -> &mut TabState {
+        &mut self.tabs[self.active]
+    }
+
+    pub fn get(&self, id: TabId) -> Option<&TabState> {
+        self.tabs.iter().find(|tab| tab.id == id)
+    }
+
+    /// The open tabs, in display order
+    pub fn iter(&self) -> impl Iterator<Item = &TabState> {
+        self.tabs.iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.tabs.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tabs.is_empty()
+    }
+
+    /// Returns a sender for the worker of a tab
+    pub fn sender(&self, id: TabId) -> TabSender {
+        TabSender {
+            id,
+            sender: self.sender.clone(),
+        }
+    }
+
+    /// Delivers a worker message to its tab; returns false if the tab was
+    /// closed and the message was dropped
+    pub fn route(&mut self, id: TabId, message: ComparisonMessage) -> bool {
+        match self.tabs.iter_mut().find(|tab| tab.id == id) {
+            Some(tab) => {
+                tab.handle_message(message);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Routes every message the workers have sent since the last call, and
+    /// fails the comparisons whose worker ended without a result, after
+    /// [`WORKER_INACTIVITY`].
+    ///
+    /// Returns the open tabs whose comparison finished with results.
+    pub fn receive_messages(&mut self) -> Vec<TabId> {
+        self.poll_workers(Instant::now(), WORKER_INACTIVITY)
+    }
+
+    /// Like [`receive_messages`](Self::receive_messages), treating a worker
+    /// that ended and sent nothing for `inactivity` by `now` as lost.
+    pub fn poll_workers(&mut self, now: Instant, inactivity: Duration) -> Vec<TabId> {
+        // A worker sends its last message before it ends, so whatever a lost
+        // worker sent is queued by now and routed below
+        let lost: Vec<TabId> = self
+            .tabs
+            .iter()
+            .filter(|tab| tab.worker_lost(now, inactivity))
+            .map(|tab| tab.id)
+            .collect();
+        let mut finished = Vec::new();
+        while let Ok((id, message)) = self.receiver.try_recv() {
+            let succeeded = matches!(
+                message,
+                ComparisonMessage::Finished(Ok(_)) | ComparisonMessage::FinishedFlat(Ok(_))
+            );
+            if self.route(id, message) && succeeded {
+                finished.push(id);
+            }
+        }
+        for tab in self.tabs.iter_mut() {
+            if lost.contains(&tab.id) && tab.is_comparing {
+                let error = "Comparison failed unexpectedly: it stopped without a result";
+                logger::error(error);
+                tab.finish();
+                tab.error_message = Some(error.to_string());
+            }
+        }
+        finished
+    }
+
+    fn index_of(&self, id: TabId) -> Option<usize> {
+        self.tabs.iter().position(|tab| tab.id == id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn empty_result() -> ComparisonResult {
+        ComparisonResult {
+            a_only: Vec::new(),
+            b_only: Vec::new(),
+            both: Vec::new(),
+            modified: Vec::new(),
+            renamed: Vec::new(),
+            exact_counts: None,
+            warnings: Vec::new(),
+            stats: Default::default(),
+            context: None,
+            identical_subtrees: Vec::new(),
+            mismatch_reasons: Vec::new(),
+        }
+    }
+
+    /// The address of the cached rows, which stays the same while they are
+    /// reused
+    fn flattened(cache: &TreeCache) -> Option<*const RowIndex> {
+        cache.flat_rows.as_ref().map(|(_, rows)| rows.as_ptr())
+    }
+
+    #[test]
+    fn test_tree_rows_are_flattened_once_per_expansion() {
+        let mut cache = TreeCache::new(&empty_result(), SortState::default());
+        let mut expansion = TreeExpansion::default();
+        assert_eq!(cache.tree_rows(&expansion).len(), 5);
+        let rows = flattened(&cache);
+        cache.tree_rows(&expansion);
+        assert_eq!(flattened(&cache), rows);
+
+        expansion.toggle_section(ResultCategory::AOnly);
+        cache.tree_rows(&expansion);
+        assert_eq!(
+            cache.flat_rows.as_ref().map(|(shown, _)| *shown),
+            Some(expansion.generation())
+        );
+
+        // A changed filter changes the trees to show
+        cache.set_filter("docs");
+        assert!(flattened(&cache).is_none());
+        cache.tree_rows(&expansion);
+        assert!(flattened(&cache).is_some());
+        cache.set_filter("docs");
+        assert!(flattened(&cache).is_some());
+    }
+}
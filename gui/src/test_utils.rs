@@ -1,40 +1,49 @@
 use std::fs;
+use std::io;
+use std::path::Path;
 use tempfile::TempDir;
 
 /// Creates a standard test directory structure with various file types
 pub fn create_test_dir_structure() -> (TempDir, TempDir) {
     let dir_a = TempDir::new().expect("Failed to create temp dir A");
     let dir_b = TempDir::new().expect("Failed to create temp dir B");
+    write_test_dir_structure(dir_a.path(), dir_b.path()).unwrap();
+    (dir_a, dir_b)
+}
 
+/// Writes the standard test structure into two existing directories.
+///
+/// Besides the tests, the GUI's demo comparison uses this to generate its
+/// fixture at runtime.
+pub fn write_test_dir_structure(dir_a: &Path, dir_b: &Path) -> io::Result<()> {
     // Directory A only files
-    fs::write(dir_a.path().join("a_only.txt"), "content A").unwrap();
-    fs::create_dir(dir_a.path().join("a_only_dir")).unwrap();
-    fs::write(dir_a.path().join("a_only_dir/nested.txt"), "nested A").unwrap();
+    fs::write(dir_a.join("a_only.txt"), "content A")?;
+    fs::create_dir(dir_a.join("a_only_dir"))?;
+    fs::write(dir_a.join("a_only_dir/nested.txt"), "nested A")?;
 
     // Directory B only files
-    fs::write(dir_b.path().join("b_only.txt"), "content B").unwrap();
-    fs::create_dir(dir_b.path().join("b_only_dir")).unwrap();
-    fs::write(dir_b.path().join("b_only_dir/nested.txt"), "nested B").unwrap();
+    fs::write(dir_b.join("b_only.txt"), "content B")?;
+    fs::create_dir(dir_b.join("b_only_dir"))?;
+    fs::write(dir_b.join("b_only_dir/nested.txt"), "nested B")?;
 
     // Common files with same content
-    fs::write(dir_a.path().join("common.txt"), "common content").unwrap();
-    fs::write(dir_b.path().join("common.txt"), "common content").unwrap();
+    fs::write(dir_a.join("common.txt"), "common content")?;
+    fs::write(dir_b.join("common.txt"), "common content")?;
 
     // Files with same name but different sizes
-    fs::write(dir_a.path().join("different_size.txt"), "short").unwrap();
+    fs::write(dir_a.join("different_size.txt"), "short")?;
     fs::write(
-        dir_b.path().join("different_size.txt"),
+        dir_b.join("different_size.txt"),
         "this is a much longer content",
-    )
-    .unwrap();
+    )?;
 
     // Nested common directories
-    fs::create_dir_all(dir_a.path().join("common_dir/subdir")).unwrap();
-    fs::create_dir_all(dir_b.path().join("common_dir/subdir")).unwrap();
-    fs::write(dir_a.path().join("common_dir/file.txt"), "dir file A").unwrap();
-    fs::write(dir_b.path().join("common_dir/file.txt"), "dir file B").unwrap();
+    fs::create_dir_all(dir_a.join("common_dir/subdir"))?;
+    fs::create_dir_all(dir_b.join("common_dir/subdir"))?;
+    fs::write(dir_a.join("common_dir/file.txt"), "dir file A")?;
+    fs::write(dir_b.join("common_dir/file.txt"), "dir file B")?;
 
-    (dir_a, dir_b)
+    Ok(())
 }
 
 /// Creates empty directories for edge case testing
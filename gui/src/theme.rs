@@ -1,10 +1,10 @@
-use std::io::{Read, Write};
 use std::path::PathBuf;
 
-use dir_compare_core::logger;
+use crate::settings::{load_settings, update_settings};
 
 const APP_NAME: &str = "dir-compare";
-const THEME_CONFIG_FILE: &str = "theme.txt";
+/// Where older versions saved the theme, before it moved into the settings
+pub(crate) const THEME_CONFIG_FILE: &str = "theme.txt";
 
 /// Application theme setting
 #[derive(PartialEq, Eq, Copy, Clone, Debug)]
@@ -34,58 +34,126 @@ impl Theme {
         }
     }
 
-    /// Converts the theme to egui visuals
-    pub fn to_visuals(&self) -> egui::Visuals {
+    /// Returns the appearance of the theme; [`Theme::System`] takes the one
+    /// reported by `detector`, or dark if the OS does not tell
+    pub fn resolve(&self, detector: &dyn ThemeDetector) -> Appearance {
         match self {
-            Theme::Light => egui::Visuals::light(),
-            Theme::Dark => egui::Visuals::dark(),
-            Theme::System => egui::Visuals::default(),
+            Theme::Light => Appearance::Light,
+            Theme::Dark => Appearance::Dark,
+            Theme::System => detector.detect().unwrap_or(Appearance::Dark),
         }
     }
+
+    /// Converts the theme to egui visuals, see [`Theme::resolve`]
+    pub fn to_visuals(&self, detector: &dyn ThemeDetector) -> egui::Visuals {
+        self.resolve(detector).visuals()
+    }
 }
 
-/// Gets the configuration directory path
-fn get_config_dir() -> Option<PathBuf> {
-    dirs::config_dir().map(|dir| dir.join(APP_NAME))
+/// Whether text is dark on light or light on dark
+#[derive(PartialEq, Eq, Copy, Clone, Debug)]
+pub enum Appearance {
+    Light,
+    Dark,
+}
+
+impl Appearance {
+    /// Returns the egui visuals of the appearance
+    pub fn visuals(&self) -> egui::Visuals {
+        match self {
+            Appearance::Light => egui::Visuals::light(),
+            Appearance::Dark => egui::Visuals::dark(),
+        }
+    }
+}
+
+/// Tells which appearance the OS uses
+pub trait ThemeDetector {
+    /// The current appearance of the OS; `None` if it cannot be detected
+    fn detect(&self) -> Option<Appearance>;
+}
+
+/// eframe reports the OS appearance at startup and updates it when the OS
+/// setting changes, on platforms where winit can detect it
+impl ThemeDetector for eframe::IntegrationInfo {
+    fn detect(&self) -> Option<Appearance> {
+        self.system_theme.map(|theme| match theme {
+            eframe::Theme::Light => Appearance::Light,
+            eframe::Theme::Dark => Appearance::Dark,
+        })
+    }
+}
+
+/// Sets the visuals of `theme` unless they are already in use.
+///
+/// Called every frame, so [`Theme::System`] follows changes of the OS
+/// setting while the app runs.
+pub fn apply_theme(ctx: &egui::Context, theme: Theme, detector: &dyn ThemeDetector) {
+    let appearance = theme.resolve(detector);
+    if ctx.style().visuals.dark_mode != (appearance == Appearance::Dark) {
+        ctx.set_visuals(appearance.visuals());
+    }
 }
 
-/// Gets the theme configuration file path
-fn get_theme_config_path() -> Option<PathBuf> {
-    get_config_dir().map(|dir| dir.join(THEME_CONFIG_FILE))
+/// Gets the configuration directory path
+pub(crate) fn get_config_dir() -> Option<PathBuf> {
+    dirs::config_dir().map(|dir| dir.join(APP_NAME))
 }
 
-/// Loads the saved theme from the configuration file
+/// Loads the theme of the saved settings; `None` without a config directory
 pub fn load_theme() -> Option<Theme> {
-    let path = get_theme_config_path()?;
-    let mut file = std::fs::File::open(path).ok()?;
-    let mut contents = String::new();
-    file.read_to_string(&mut contents).ok()?;
-    Theme::parse(contents.trim())
+    get_config_dir()?;
+    Some(load_settings().theme)
 }
 
-/// Saves the theme to the configuration file
+/// Saves the theme into the settings, keeping the other settings
 pub fn save_theme(theme: Theme) {
-    if let Some(config_dir) = get_config_dir() {
-        if let Err(e) = std::fs::create_dir_all(&config_dir) {
-            logger::error(&format!("Failed to create config directory: {}", e));
-            return;
-        }
-        let path = config_dir.join(THEME_CONFIG_FILE);
-        match std::fs::File::create(&path) {
-            Ok(mut file) => {
-                if let Err(e) = file.write_all(theme.as_str().as_bytes()) {
-                    logger::error(&format!("Failed to write theme config: {}", e));
-                }
-            }
-            Err(e) => logger::error(&format!("Failed to create theme config file: {}", e)),
-        }
-    }
+    update_settings(|settings| settings.theme = theme);
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    /// Reports a fixed OS appearance
+    struct FixedDetector(Option<Appearance>);
+
+    impl ThemeDetector for FixedDetector {
+        fn detect(&self) -> Option<Appearance> {
+            self.0
+        }
+    }
+
+    #[test]
+    fn test_resolve_follows_the_detector_only_for_system() {
+        let light_os = FixedDetector(Some(Appearance::Light));
+        let dark_os = FixedDetector(Some(Appearance::Dark));
+        let unknown = FixedDetector(None);
+
+        assert_eq!(Theme::System.resolve(&light_os), Appearance::Light);
+        assert_eq!(Theme::System.resolve(&dark_os), Appearance::Dark);
+        assert_eq!(Theme::System.resolve(&unknown), Appearance::Dark);
+        for detector in [&light_os, &dark_os, &unknown] {
+            assert_eq!(Theme::Light.resolve(detector), Appearance::Light);
+            assert_eq!(Theme::Dark.resolve(detector), Appearance::Dark);
+        }
+        assert!(!Theme::System.to_visuals(&light_os).dark_mode);
+    }
+
+    #[test]
+    fn test_apply_theme_reacts_to_os_changes() {
+        let ctx = egui::Context::default();
+        apply_theme(&ctx, Theme::System, &FixedDetector(Some(Appearance::Light)));
+        assert!(!ctx.style().visuals.dark_mode);
+
+        apply_theme(&ctx, Theme::System, &FixedDetector(Some(Appearance::Dark)));
+        assert!(ctx.style().visuals.dark_mode);
+
+        // A chosen theme stays when the OS changes
+        apply_theme(&ctx, Theme::Light, &FixedDetector(Some(Appearance::Dark)));
+        assert!(!ctx.style().visuals.dark_mode);
+    }
+
     #[test]
     fn test_theme_from_str_valid() {
         assert_eq!(Theme::parse("light"), Some(Theme::Light));
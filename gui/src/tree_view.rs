@@ -1,14 +1,36 @@
-use dir_compare_core::{Entry, EntryKind};
+use dir_compare_core::output::{format_bytes, group_digits};
+use dir_compare_core::{Entry, EntryKind, IdenticalSubtree, MismatchReason};
 use eframe::egui;
-use std::collections::BTreeMap;
-use std::path::PathBuf;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use crate::strings::Strings;
+use crate::table_view::{ResultCategory, ResultSelection};
 
 #[derive(Debug, Clone)]
 pub struct FileTreeNode {
     pub name: String,
     pub path: PathBuf,
     pub kind: EntryKind,
+    /// The entry's size for files, the total of the sizes below it for
+    /// directories; `None` if no size is known
+    pub size: Option<u64>,
     pub children: Vec<FileTreeNode>,
+    /// Number of files below a directory shown without its children
+    /// because its whole subtree matched, see [`collapse_identical`]
+    pub identical_files: Option<usize>,
+    /// Path in A of a file moved to this path in B, see [`mark_renamed`]
+    pub renamed_from: Option<PathBuf>,
+    /// Why the comparison method did not match a modified entry, see
+    /// [`modified_tree`]
+    pub mismatch_reason: Option<MismatchReason>,
+    /// Size of the entry in B of a modified pair, shown next to `size`,
+    /// see [`modified_tree`]
+    pub size_b: Option<u64>,
+    /// Number of files at any depth below the node; directories and
+    /// symbolic links are not counted
+    pub descendant_file_count: usize,
 }
 
 impl FileTreeNode {
@@ -19,12 +41,130 @@ impl FileTreeNode {
         }
         builder.into_vec()
     }
+
+    /// Returns this node narrowed to the nodes whose path contains `query`,
+    /// ignoring case, together with the directories leading to them;
+    /// `None` if nothing matches. An empty query keeps every node.
+    pub fn filter(&self, query: &str) -> Option<FileTreeNode> {
+        self.filter_lowercase(&query.to_lowercase())
+    }
+
+    fn filter_lowercase(&self, query: &str) -> Option<FileTreeNode> {
+        let children: Vec<FileTreeNode> = self
+            .children
+            .iter()
+            .filter_map(|child| child.filter_lowercase(query))
+            .collect();
+        let contains = |path: &Path| path.to_string_lossy().to_lowercase().contains(query);
+        let matches = contains(&self.path) || self.renamed_from.as_deref().is_some_and(contains);
+        (matches || !children.is_empty()).then(|| FileTreeNode {
+            descendant_file_count: file_count(&children),
+            name: self.name.clone(),
+            path: self.path.clone(),
+            kind: self.kind.clone(),
+            size: self.size,
+            children,
+            identical_files: self.identical_files,
+            renamed_from: self.renamed_from.clone(),
+            mismatch_reason: self.mismatch_reason,
+            size_b: self.size_b,
+        })
+    }
+}
+
+/// Narrows each of `nodes` with [`FileTreeNode::filter`]
+pub fn filter_nodes(nodes: &[FileTreeNode], query: &str) -> Vec<FileTreeNode> {
+    nodes.iter().filter_map(|node| node.filter(query)).collect()
+}
+
+/// Removes the children of the directories of `subtrees`, sorted by path,
+/// and records the number of files below them instead
+pub fn collapse_identical(nodes: &mut [FileTreeNode], subtrees: &[IdenticalSubtree]) {
+    if subtrees.is_empty() {
+        return;
+    }
+    for node in nodes {
+        match subtrees.binary_search_by(|subtree| subtree.path.cmp(&node.path)) {
+            Ok(index) => {
+                node.children.clear();
+                node.identical_files = Some(subtrees[index].files);
+            }
+            Err(_) => collapse_identical(&mut node.children, subtrees),
+        }
+    }
+}
+
+/// Builds the tree of the files of B in `renamed`, each recording the path
+/// in A it was moved from
+pub fn renamed_tree(renamed: &[(Entry, Entry)]) -> Vec<FileTreeNode> {
+    let entries: Vec<Entry> = renamed.iter().map(|(_, b)| b.clone()).collect();
+    let mut nodes = FileTreeNode::from_entries(&entries);
+    let origins: BTreeMap<&Path, &Path> = renamed
+        .iter()
+        .map(|(a, b)| (b.path.as_path(), a.path.as_path()))
+        .collect();
+    mark_renamed(&mut nodes, &origins);
+    nodes
+}
+
+/// Records the path in A of the nodes found in `origins`, keyed by the
+/// path in B
+fn mark_renamed(nodes: &mut [FileTreeNode], origins: &BTreeMap<&Path, &Path>) {
+    for node in nodes {
+        node.renamed_from = origins
+            .get(node.path.as_path())
+            .map(|from| from.to_path_buf());
+        mark_renamed(&mut node.children, origins);
+    }
+}
+
+/// Builds the tree of the entries of A in `modified`, each recording the
+/// size of the entry in B and the reason of `reasons`, sorted by path, that
+/// it did not match
+pub fn modified_tree(
+    modified: &[(Entry, Entry)],
+    reasons: &[(PathBuf, MismatchReason)],
+) -> Vec<FileTreeNode> {
+    let entries: Vec<Entry> = modified.iter().map(|(a, _)| a.clone()).collect();
+    let mut nodes = FileTreeNode::from_entries(&entries);
+    let sizes_b: BTreeMap<&Path, Option<u64>> = modified
+        .iter()
+        .map(|(a, b)| (a.path.as_path(), b.size))
+        .collect();
+    mark_modified(&mut nodes, &sizes_b, reasons);
+    nodes
+}
+
+/// Records the size in B of the nodes found in `sizes_b` and the reason of
+/// those found in `reasons`
+fn mark_modified(
+    nodes: &mut [FileTreeNode],
+    sizes_b: &BTreeMap<&Path, Option<u64>>,
+    reasons: &[(PathBuf, MismatchReason)],
+) {
+    for node in nodes {
+        node.size_b = sizes_b.get(node.path.as_path()).copied().flatten();
+        node.mismatch_reason = reasons
+            .binary_search_by(|(path, _)| path.cmp(&node.path))
+            .ok()
+            .map(|index| reasons[index].1);
+        mark_modified(&mut node.children, sizes_b, reasons);
+    }
+}
+
+/// Counts `nodes` and all nodes below them
+pub fn count_nodes(nodes: &[FileTreeNode]) -> usize {
+    nodes
+        .iter()
+        .map(|node| 1 + count_nodes(&node.children))
+        .sum()
 }
 
 #[derive(Default)]
 struct NodeBuilder {
     children: BTreeMap<String, NodeBuilder>,
     kind: Option<EntryKind>,
+    size: Option<u64>,
     path: PathBuf,
 }
 
@@ -43,11 +183,13 @@ impl NodeBuilder {
             current = current.children.entry(name).or_insert_with(|| NodeBuilder {
                 children: BTreeMap::new(),
                 kind: None,
+                size: None,
                 path: path_accum.clone(),
             });
 
             if is_last {
                 current.kind = Some(entry.kind.clone());
+                current.size = entry.size;
             } else if current.kind.is_none() {
                 current.kind = Some(EntryKind::Directory);
             }
@@ -60,40 +202,444 @@ impl NodeBuilder {
             .map(|(name, mut node)| {
                 let kind = node.kind.take().unwrap_or(EntryKind::Directory);
                 let path = node.path.clone();
+                let own_size = node.size;
                 let children = node.into_vec();
+                let descendant_file_count = file_count(&children);
+                let size = if children.is_empty() {
+                    own_size
+                } else {
+                    total_size(&children)
+                };
                 FileTreeNode {
                     name,
                     path,
                     kind,
+                    size,
                     children,
+                    identical_files: None,
+                    renamed_from: None,
+                    mismatch_reason: None,
+                    size_b: None,
+                    descendant_file_count,
                 }
             })
             .collect()
     }
 }
 
-pub fn render_tree(ui: &mut egui::Ui, nodes: &[FileTreeNode], color: egui::Color32) {
-    for node in nodes {
-        render_node(ui, node, color);
+/// Counts the files among `nodes` and below them
+fn file_count(nodes: &[FileTreeNode]) -> usize {
+    nodes
+        .iter()
+        .map(|node| usize::from(node.kind == EntryKind::File) + node.descendant_file_count)
+        .sum()
+}
+
+/// Sums the known sizes of `nodes`; `None` if none is known
+fn total_size(nodes: &[FileTreeNode]) -> Option<u64> {
+    nodes
+        .iter()
+        .filter_map(|node| node.size)
+        .reduce(|total, size| total.saturating_add(size))
+}
+
+/// Shows `size` right-aligned in the remaining width of a row, followed by
+/// `size_b` if both are known
+fn size_label(ui: &mut egui::Ui, size: Option<u64>, size_b: Option<u64>) {
+    let text = match (size, size_b) {
+        (Some(a), Some(b)) => format!("{} → {}", format_bytes(a), format_bytes(b)),
+        (Some(size), None) => format_bytes(size),
+        (None, _) => return,
+    };
+    ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+        ui.weak(text);
+    });
+}
+
+/// What the user did with a node, identified by its relative path
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TreeAction {
+    /// Clicked the node
+    Select(PathBuf),
+    /// Double-clicked the node or chose "Show in file manager"
+    Reveal(PathBuf),
+    /// Chose "Copy path" from the context menu
+    CopyPath(PathBuf),
+}
+
+/// Source of [`TreeExpansion::generation`], shared by all expansions so
+/// that two of them only have the same generation in the default state
+static NEXT_GENERATION: AtomicU64 = AtomicU64::new(1);
+
+/// Which sections and folders of the results trees are open
+#[derive(Debug, Clone, Default)]
+pub struct TreeExpansion {
+    /// Open sections
+    sections: HashSet<ResultCategory>,
+    /// Open folders of each section, by path
+    folders: HashMap<ResultCategory, HashSet<PathBuf>>,
+    /// Changes with every change of the open sections and folders
+    generation: u64,
+}
+
+impl TreeExpansion {
+    /// Identifies the open sections and folders: rows flattened for one
+    /// generation stay valid until it changes
+    pub fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    fn changed(&mut self) {
+        self.generation = NEXT_GENERATION.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn is_section_open(&self, category: ResultCategory) -> bool {
+        self.sections.contains(&category)
+    }
+
+    /// Returns true if the folder at `path` in the tree of `category` is
+    /// open
+    pub fn is_open(&self, category: ResultCategory, path: &Path) -> bool {
+        self.folders
+            .get(&category)
+            .is_some_and(|folders| folders.contains(path))
+    }
+
+    /// Opens the section of `category` if it is closed, and closes it
+    /// otherwise
+    pub fn toggle_section(&mut self, category: ResultCategory) {
+        if !self.sections.remove(&category) {
+            self.sections.insert(category);
+        }
+        self.changed();
+    }
+
+    /// Opens the folder at `path` in the tree of `category` if it is
+    /// closed, and closes it otherwise
+    pub fn toggle(&mut self, category: ResultCategory, path: &Path) {
+        let folders = self.folders.entry(category).or_default();
+        if !folders.remove(path) {
+            folders.insert(path.to_path_buf());
+        }
+        self.changed();
+    }
+
+    /// Opens the section of `category` and every folder of its tree `nodes`
+    pub fn expand_all(&mut self, category: ResultCategory, nodes: &[FileTreeNode]) {
+        fn insert(folders: &mut HashSet<PathBuf>, nodes: &[FileTreeNode]) {
+            for node in nodes.iter().filter(|node| !node.children.is_empty()) {
+                folders.insert(node.path.clone());
+                insert(folders, &node.children);
+            }
+        }
+        self.sections.insert(category);
+        insert(self.folders.entry(category).or_default(), nodes);
+        self.changed();
+    }
+
+    /// Closes every folder; the sections stay open, so their top-level
+    /// entries stay visible
+    pub fn collapse_all(&mut self) {
+        self.folders.clear();
+        self.changed();
+    }
+
+    /// Moves the state of the A-only section to the B-only one and back,
+    /// after the directories were swapped
+    pub fn swap_sides(&mut self) {
+        let swap = |category: ResultCategory| category.swapped();
+        self.sections = self.sections.drain().map(swap).collect();
+        self.folders = self
+            .folders
+            .drain()
+            .map(|(category, folders)| (swap(category), folders))
+            .collect();
+        self.changed();
     }
 }
 
-fn render_node(ui: &mut egui::Ui, node: &FileTreeNode, color: egui::Color32) {
-    if node.children.is_empty() {
-        ui.horizontal(|ui| {
-            let icon = match node.kind {
-                EntryKind::Directory => "📁",
-                EntryKind::File => "📄",
-            };
-            ui.label(egui::RichText::new(format!("{} {}", icon, node.name)).color(color));
-        });
-    } else {
-        egui::CollapsingHeader::new(egui::RichText::new(format!("📁 {}", node.name)).color(color))
-            .id_source(&node.path)
-            .show(ui, |ui| {
-                render_tree(ui, &node.children, color);
+/// One line of the results trees flattened for display, see
+/// [`TreeRows::get`]
+#[derive(Debug, Clone, Copy)]
+pub enum TreeRow<'a> {
+    /// The header of the section of a category
+    Section(ResultCategory),
+    /// A node of the tree of `category`, `depth` levels below the section
+    Node {
+        category: ResultCategory,
+        depth: usize,
+        node: &'a FileTreeNode,
+    },
+}
+
+/// Where a flattened row is found in the trees, without borrowing them
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum RowIndex {
+    /// The header of the section at this index
+    Section(usize),
+    /// The node at `index` among the children of the node in row
+    /// `parent`, or among the top-level nodes of `section` without one
+    Node {
+        section: usize,
+        depth: usize,
+        parent: Option<usize>,
+        index: usize,
+    },
+}
+
+/// Flattens `sections` into rows: the header of each section, followed by
+/// its top-level nodes if the section is open in `expansion`. The children
+/// of a folder open in `expansion` follow it, one level deeper; closed
+/// folders are not descended into.
+pub(crate) fn flatten_rows(
+    sections: &[(ResultCategory, &[FileTreeNode])],
+    expansion: &TreeExpansion,
+) -> Vec<RowIndex> {
+    fn push_rows(
+        rows: &mut Vec<RowIndex>,
+        section: usize,
+        nodes: &[FileTreeNode],
+        depth: usize,
+        parent: Option<usize>,
+        open: Option<&HashSet<PathBuf>>,
+    ) {
+        for (index, node) in nodes.iter().enumerate() {
+            let row = rows.len();
+            rows.push(RowIndex::Node {
+                section,
+                depth,
+                parent,
+                index,
             });
+            if !node.children.is_empty() && open.is_some_and(|open| open.contains(&node.path)) {
+                push_rows(rows, section, &node.children, depth + 1, Some(row), open);
+            }
+        }
+    }
+
+    let mut rows = Vec::new();
+    for (section, &(category, nodes)) in sections.iter().enumerate() {
+        rows.push(RowIndex::Section(section));
+        if expansion.is_section_open(category) {
+            let open = expansion.folders.get(&category);
+            push_rows(&mut rows, section, nodes, 0, None, open);
+        }
     }
+    rows
+}
+
+/// The flattened rows of the results trees, see
+/// [`TreeCache::tree_rows`](crate::TreeCache::tree_rows). Only the rows
+/// asked for are looked up in the trees.
+pub struct TreeRows<'a> {
+    sections: [(ResultCategory, &'a [FileTreeNode]); 5],
+    rows: &'a [RowIndex],
+}
+
+impl<'a> TreeRows<'a> {
+    pub(crate) fn new(
+        sections: [(ResultCategory, &'a [FileTreeNode]); 5],
+        rows: &'a [RowIndex],
+    ) -> Self {
+        Self { sections, rows }
+    }
+
+    pub fn len(&self) -> usize {
+        self.rows.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rows.is_empty()
+    }
+
+    /// Returns the row at `row`
+    pub fn get(&self, row: usize) -> TreeRow<'a> {
+        match self.rows[row] {
+            RowIndex::Section(section) => TreeRow::Section(self.sections[section].0),
+            RowIndex::Node {
+                section,
+                depth,
+                parent,
+                index,
+            } => {
+                let siblings = match parent.map(|parent| self.get(parent)) {
+                    Some(TreeRow::Node { node, .. }) => &node.children,
+                    _ => self.sections[section].1,
+                };
+                TreeRow::Node {
+                    category: self.sections[section].0,
+                    depth,
+                    node: &siblings[index],
+                }
+            }
+        }
+    }
+
+    /// Returns all rows in order
+    pub fn iter(&self) -> impl Iterator<Item = TreeRow<'a>> + '_ {
+        (0..self.len()).map(|row| self.get(row))
+    }
+}
+
+/// Returns the string key of the title of the section of `category`
+pub fn section_title_key(category: ResultCategory) -> &'static str {
+    match category {
+        ResultCategory::AOnly => "section.a_only",
+        ResultCategory::BOnly => "section.b_only",
+        ResultCategory::Modified => "section.modified",
+        ResultCategory::Both => "section.both",
+        ResultCategory::Renamed => "section.renamed",
+    }
+}
+
+/// Width of the arrow opening and closing a section or folder, in points
+const TOGGLE_WIDTH: f32 = 14.0;
+
+/// Shows the arrow of an open or closed section or folder; returns true if
+/// it was clicked
+fn toggle(ui: &mut egui::Ui, open: bool) -> bool {
+    let arrow = egui::Label::new(if open { "▼" } else { "▶" }).sense(egui::Sense::click());
+    let height = ui.spacing().interact_size.y;
+    ui.add_sized([TOGGLE_WIDTH, height], arrow).clicked()
+}
+
+/// Renders the rows of the results trees and returns what the user did
+/// with a node this frame, with the category of its tree.
+///
+/// Only the rows scrolled into view are looked up and laid out, so the
+/// cost of a frame does not grow with the number of rows. The node at the path of
+/// `selection` in its category is highlighted. Clicking an arrow opens or
+/// closes its section or folder in `expansion`, which the rows show from
+/// the next frame on.
+pub fn render_rows(
+    ui: &mut egui::Ui,
+    rows: &TreeRows,
+    expansion: &mut TreeExpansion,
+    selection: Option<&ResultSelection>,
+    strings: &Strings,
+) -> Option<(ResultCategory, TreeAction)> {
+    let mut action = None;
+    let row_height = ui.spacing().interact_size.y;
+    egui::ScrollArea::vertical().show_rows(ui, row_height, rows.len(), |ui, visible| {
+        for row in visible {
+            ui.horizontal(|ui| {
+                ui.set_height(row_height);
+                match rows.get(row) {
+                    TreeRow::Section(category) => {
+                        render_section(ui, category, expansion, strings);
+                    }
+                    TreeRow::Node {
+                        category,
+                        depth,
+                        node,
+                    } => {
+                        let selected = selection
+                            .filter(|selection| selection.category == category)
+                            .map(|selection| selection.path.as_path());
+                        let node_action =
+                            render_node(ui, node, category, depth, selected, expansion, strings);
+                        if let Some(node_action) = node_action {
+                            action = Some((category, node_action));
+                        }
+                    }
+                }
+            });
+        }
+    });
+    action
+}
+
+/// Shows the header of a section, which opens or closes it when clicked
+fn render_section(
+    ui: &mut egui::Ui,
+    category: ResultCategory,
+    expansion: &mut TreeExpansion,
+    strings: &Strings,
+) {
+    let open = expansion.is_section_open(category);
+    let title =
+        egui::Label::new(strings.get(section_title_key(category))).sense(egui::Sense::click());
+    if toggle(ui, open) | ui.add(title).clicked() {
+        expansion.toggle_section(category);
+        ui.ctx().request_repaint();
+    }
+}
+
+/// Turns the response of a node's label into the action it triggered,
+/// adding the node's context menu
+fn node_action(
+    response: egui::Response,
+    node: &FileTreeNode,
+    strings: &Strings,
+) -> Option<TreeAction> {
+    let mut action = if response.double_clicked() {
+        Some(TreeAction::Reveal(node.path.clone()))
+    } else if response.clicked() {
+        Some(TreeAction::Select(node.path.clone()))
+    } else {
+        None
+    };
+    response.context_menu(|ui| {
+        if ui.button(strings.get("tree.copy_path")).clicked() {
+            action = Some(TreeAction::CopyPath(node.path.clone()));
+            ui.close_menu();
+        }
+        if ui.button(strings.get("tree.reveal")).clicked() {
+            action = Some(TreeAction::Reveal(node.path.clone()));
+            ui.close_menu();
+        }
+    });
+    action
+}
+
+/// Shows one node, indented by `depth` and colored by `category`; folders
+/// with children get an arrow opening and closing them in `expansion`, and
+/// directories the number of files below them
+fn render_node(
+    ui: &mut egui::Ui,
+    node: &FileTreeNode,
+    category: ResultCategory,
+    depth: usize,
+    selected: Option<&Path>,
+    expansion: &mut TreeExpansion,
+    strings: &Strings,
+) -> Option<TreeAction> {
+    ui.add_space(ui.spacing().indent * (depth + 1) as f32);
+    if node.children.is_empty() {
+        ui.add_space(TOGGLE_WIDTH + ui.spacing().item_spacing.x);
+    } else if toggle(ui, expansion.is_open(category, &node.path)) {
+        expansion.toggle(category, &node.path);
+        ui.ctx().request_repaint();
+    }
+    let label = match &node.kind {
+        EntryKind::Directory => format!("📁 {}", node.name),
+        EntryKind::File => format!("📄 {}", node.name),
+        EntryKind::Symlink { target } => {
+            format!("🔗 {} -> {}", node.name, target.display())
+        }
+    };
+    let text = egui::RichText::new(label).color(category.color());
+    let is_selected = selected == Some(node.path.as_path());
+    let mut response = ui.selectable_label(is_selected, text);
+    if let Some(reason) = node.mismatch_reason {
+        response = response.on_hover_text(strings.report_labels().mismatch_reason(reason));
+    }
+    match node.identical_files {
+        Some(files) => {
+            ui.weak(format!("({})", strings.report_labels().identical(files)));
+        }
+        None if node.kind == EntryKind::Directory => {
+            ui.weak(format!("({})", group_digits(node.descendant_file_count)));
+        }
+        None => {}
+    }
+    if let Some(from) = &node.renamed_from {
+        ui.weak(format!("<- {}", from.display()));
+    }
+    // Folders show their total size; B's size is only known for entries
+    let size_b = node.size_b.filter(|_| node.children.is_empty());
+    size_label(ui, node.size, size_b);
+    node_action(response, node, strings)
 }
 
 #[cfg(test)]
@@ -107,9 +653,22 @@ mod tests {
             kind,
             abs_path: PathBuf::from(path),
             size: Some(0),
+            nested_repository: false,
+            mtime: None,
+        }
+    }
+
+    fn sized_entry(path: &str, kind: EntryKind, size: Option<u64>) -> Entry {
+        Entry {
+            size,
+            ..create_entry(path, kind)
         }
     }
 
+    fn child<'a>(node: &'a FileTreeNode, name: &str) -> &'a FileTreeNode {
+        node.children.iter().find(|n| n.name == name).unwrap()
+    }
+
     #[test]
     fn test_from_entries_empty() {
         let entries: Vec<Entry> = vec![];
@@ -187,4 +746,248 @@ mod tests {
         assert_eq!(current.children[0].name, "file.txt");
         assert_eq!(current.children[0].kind, EntryKind::File);
     }
+
+    #[test]
+    fn test_from_entries_aggregates_sizes() {
+        let entries = vec![
+            sized_entry("dir", EntryKind::Directory, None),
+            sized_entry("dir/a.txt", EntryKind::File, Some(100)),
+            sized_entry("dir/sub/b.txt", EntryKind::File, Some(20)),
+            sized_entry("dir/sub/deeper/c.txt", EntryKind::File, Some(3)),
+            sized_entry("top.txt", EntryKind::File, Some(7)),
+        ];
+        let nodes = FileTreeNode::from_entries(&entries);
+
+        let dir = nodes.iter().find(|n| n.name == "dir").unwrap();
+        assert_eq!(dir.size, Some(123));
+        let sub = child(dir, "sub");
+        assert_eq!(sub.size, Some(23));
+        assert_eq!(child(sub, "deeper").size, Some(3));
+        assert_eq!(child(dir, "a.txt").size, Some(100));
+        assert_eq!(
+            nodes.iter().find(|n| n.name == "top.txt").unwrap().size,
+            Some(7)
+        );
+    }
+
+    #[test]
+    fn test_descendant_file_count_counts_files_at_all_depths() {
+        let entries = vec![
+            create_entry("src", EntryKind::Directory),
+            create_entry("src/main.rs", EntryKind::File),
+            create_entry("src/util", EntryKind::Directory),
+            create_entry("src/util/a.rs", EntryKind::File),
+            create_entry("src/util/b.rs", EntryKind::File),
+            create_entry("src/util/deep/c.rs", EntryKind::File),
+            create_entry("src/empty", EntryKind::Directory),
+            create_entry("top.txt", EntryKind::File),
+        ];
+        let nodes = FileTreeNode::from_entries(&entries);
+
+        let src = nodes.iter().find(|n| n.name == "src").unwrap();
+        assert_eq!(src.descendant_file_count, 4);
+        let util = child(src, "util");
+        assert_eq!(util.descendant_file_count, 3);
+        assert_eq!(child(util, "deep").descendant_file_count, 1);
+        assert_eq!(child(src, "empty").descendant_file_count, 0);
+        // Files count nothing below themselves
+        assert_eq!(child(src, "main.rs").descendant_file_count, 0);
+        assert_eq!(
+            nodes
+                .iter()
+                .find(|n| n.name == "top.txt")
+                .unwrap()
+                .descendant_file_count,
+            0
+        );
+    }
+
+    #[test]
+    fn test_descendant_file_count_skips_directories_and_links() {
+        let symlink = EntryKind::Symlink {
+            target: PathBuf::from("target"),
+        };
+        let entries = vec![
+            create_entry("mixed/empty_a", EntryKind::Directory),
+            create_entry("mixed/empty_b", EntryKind::Directory),
+            create_entry("mixed/link", symlink),
+            create_entry("mixed/file.txt", EntryKind::File),
+            create_entry("mixed/sub/empty", EntryKind::Directory),
+        ];
+        let nodes = FileTreeNode::from_entries(&entries);
+
+        assert_eq!(nodes[0].descendant_file_count, 1);
+        assert_eq!(child(&nodes[0], "sub").descendant_file_count, 0);
+    }
+
+    #[test]
+    fn test_filter_recounts_files() {
+        let entries = vec![
+            create_entry("docs/guide.md", EntryKind::File),
+            create_entry("docs/api.md", EntryKind::File),
+            create_entry("docs/api/index.html", EntryKind::File),
+        ];
+        let nodes = FileTreeNode::from_entries(&entries);
+        assert_eq!(nodes[0].descendant_file_count, 3);
+
+        let filtered = filter_nodes(&nodes, "api");
+        assert_eq!(filtered[0].descendant_file_count, 2);
+    }
+
+    #[test]
+    fn test_filter_keeps_ancestors_of_matches() {
+        let entries = vec![
+            create_entry("src", EntryKind::Directory),
+            create_entry("src/main.rs", EntryKind::File),
+            create_entry("src/util", EntryKind::Directory),
+            create_entry("src/util/Parser.rs", EntryKind::File),
+            create_entry("src/util/lexer.rs", EntryKind::File),
+            create_entry("README.md", EntryKind::File),
+        ];
+        let nodes = FileTreeNode::from_entries(&entries);
+
+        let filtered = filter_nodes(&nodes, "PARSER");
+        assert_eq!(filtered.len(), 1);
+        let src = &filtered[0];
+        assert_eq!(src.name, "src");
+        assert_eq!(src.children.len(), 1);
+        let util = child(src, "util");
+        assert_eq!(util.children.len(), 1);
+        assert_eq!(util.children[0].path, PathBuf::from("src/util/Parser.rs"));
+        assert_eq!(count_nodes(&filtered), 3);
+
+        assert!(nodes[0].filter("missing").is_none());
+    }
+
+    #[test]
+    fn test_filter_matching_directory_keeps_its_contents() {
+        let entries = vec![
+            create_entry("docs", EntryKind::Directory),
+            create_entry("docs/guide.md", EntryKind::File),
+            create_entry("docs/api.md", EntryKind::File),
+        ];
+        let nodes = FileTreeNode::from_entries(&entries);
+
+        let filtered = filter_nodes(&nodes, "docs");
+        assert_eq!(count_nodes(&filtered), 3);
+    }
+
+    #[test]
+    fn test_empty_filter_keeps_everything() {
+        let entries = vec![
+            create_entry("a", EntryKind::Directory),
+            create_entry("a/b.txt", EntryKind::File),
+            create_entry("c.txt", EntryKind::File),
+        ];
+        let nodes = FileTreeNode::from_entries(&entries);
+
+        let filtered = filter_nodes(&nodes, "");
+        assert_eq!(count_nodes(&filtered), count_nodes(&nodes));
+        assert_eq!(count_nodes(&nodes), 3);
+    }
+
+    #[test]
+    fn test_from_entries_without_sizes() {
+        let symlink = EntryKind::Symlink {
+            target: PathBuf::from("target"),
+        };
+        let entries = vec![
+            sized_entry("empty", EntryKind::Directory, None),
+            sized_entry("links/link", symlink.clone(), None),
+            sized_entry("mixed/link", symlink, None),
+            sized_entry("mixed/file.txt", EntryKind::File, Some(5)),
+        ];
+        let nodes = FileTreeNode::from_entries(&entries);
+
+        let node = |name: &str| nodes.iter().find(|n| n.name == name).unwrap();
+        assert_eq!(node("empty").size, None);
+        assert_eq!(node("links").size, None);
+        assert_eq!(child(node("links"), "link").size, None);
+        // Unknown sizes are skipped, not counted as zero
+        assert_eq!(node("mixed").size, Some(5));
+    }
+
+    #[test]
+    fn test_collapse_identical_keeps_size_and_counts_files() {
+        let entries = vec![
+            sized_entry("dir", EntryKind::Directory, None),
+            sized_entry("dir/same", EntryKind::Directory, None),
+            sized_entry("dir/same/a.txt", EntryKind::File, Some(3)),
+            sized_entry("dir/same/b.txt", EntryKind::File, Some(4)),
+            sized_entry("dir/other.txt", EntryKind::File, Some(1)),
+        ];
+        let mut nodes = FileTreeNode::from_entries(&entries);
+        let subtrees = [IdenticalSubtree {
+            path: PathBuf::from("dir/same"),
+            files: 2,
+            entries: 2,
+        }];
+        collapse_identical(&mut nodes, &subtrees);
+
+        let same = child(&nodes[0], "same");
+        assert!(same.children.is_empty());
+        assert_eq!(same.identical_files, Some(2));
+        assert_eq!(same.size, Some(7));
+        assert_eq!(nodes[0].identical_files, None);
+        assert_eq!(child(&nodes[0], "other.txt").identical_files, None);
+    }
+
+    #[test]
+    fn test_renamed_tree_shows_new_paths_with_their_origin() {
+        let renamed = vec![
+            (
+                create_entry("old.txt", EntryKind::File),
+                create_entry("archive/new.txt", EntryKind::File),
+            ),
+            (
+                create_entry("b.txt", EntryKind::File),
+                create_entry("a.txt", EntryKind::File),
+            ),
+        ];
+        let nodes = renamed_tree(&renamed);
+
+        assert_eq!(nodes.len(), 2);
+        assert_eq!(nodes[0].name, "a.txt");
+        assert_eq!(nodes[0].renamed_from, Some(PathBuf::from("b.txt")));
+        assert_eq!(nodes[1].renamed_from, None);
+        let moved = child(&nodes[1], "new.txt");
+        assert_eq!(moved.renamed_from, Some(PathBuf::from("old.txt")));
+        assert_eq!(filter_nodes(&nodes, "OLD").len(), 1);
+    }
+
+    #[test]
+    fn test_modified_tree_records_mismatch_reasons() {
+        let modified = vec![
+            (
+                sized_entry("dir/a.txt", EntryKind::File, Some(1024)),
+                sized_entry("dir/a.txt", EntryKind::File, Some(2048)),
+            ),
+            (
+                create_entry("b.txt", EntryKind::File),
+                create_entry("b.txt", EntryKind::File),
+            ),
+        ];
+        let reasons = vec![
+            (PathBuf::from("b.txt"), MismatchReason::ContentDiffers),
+            (
+                PathBuf::from("dir/a.txt"),
+                MismatchReason::SizeDiffers { a: 1024, b: 2048 },
+            ),
+        ];
+        let nodes = modified_tree(&modified, &reasons);
+
+        assert_eq!(nodes[0].size_b, Some(0));
+        assert_eq!(nodes[1].size_b, None);
+        assert_eq!(child(&nodes[1], "a.txt").size_b, Some(2048));
+
+        assert_eq!(
+            nodes[0].mismatch_reason,
+            Some(MismatchReason::ContentDiffers)
+        );
+        assert_eq!(nodes[1].mismatch_reason, None);
+        assert_eq!(
+            child(&nodes[1], "a.txt").mismatch_reason,
+            Some(MismatchReason::SizeDiffers { a: 1024, b: 2048 })
+        );
+    }
 }
@@ -0,0 +1,106 @@
+//! Worker threads running a tab's comparison, and recovery when one dies.
+//!
+//! [`spawn_worker`] runs a comparison under `catch_unwind`, so a panic
+//! reaches the tab as a failed comparison instead of leaving it comparing
+//! forever. The tab also watches its worker through a [`WorkerWatch`]: a
+//! worker that ended without a final message is noticed by
+//! [`Tabs::poll_workers`](crate::tabs::Tabs::poll_workers) once the tab
+//! heard nothing from it for an inactivity window.
+
+use dir_compare_core::logger;
+use std::any::Any;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::{Arc, Weak};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+use crate::progress::ComparisonMessage;
+use crate::tabs::{TabSender, TabState};
+
+/// How long a tab waits for a message after its worker ended before it
+/// gives up on the comparison
+pub const WORKER_INACTIVITY: Duration = Duration::from_secs(1);
+
+/// The kind of comparison a worker runs, which decides the message a panic
+/// is reported with
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WorkerKind {
+    Hierarchy,
+    Flat,
+}
+
+/// A tab's view of its running worker
+#[derive(Debug, Clone)]
+pub struct WorkerWatch {
+    /// Upgradable while the worker thread runs
+    alive: Weak<()>,
+    /// When the tab last heard from the worker, or started it
+    pub last_message: Instant,
+}
+
+impl WorkerWatch {
+    /// Returns a watch and the token the worker holds until it ends
+    pub fn new(now: Instant) -> (Self, Arc<()>) {
+        let token = Arc::new(());
+        let watch = Self {
+            alive: Arc::downgrade(&token),
+            last_message: now,
+        };
+        (watch, token)
+    }
+
+    /// Whether the worker thread still runs
+    pub fn is_alive(&self) -> bool {
+        self.alive.strong_count() > 0
+    }
+
+    /// Whether the worker ended and nothing arrived from it for
+    /// `inactivity`, so no result will come
+    pub fn is_lost(&self, now: Instant, inactivity: Duration) -> bool {
+        !self.is_alive() && now.saturating_duration_since(self.last_message) >= inactivity
+    }
+}
+
+/// Runs `body` on a worker thread for `tab`, which must have started its
+/// comparison, and returns the thread's handle.
+///
+/// `body` reports through the sender it is given. If it panics, the tab
+/// receives a failed [`ComparisonMessage::Finished`] or
+/// [`ComparisonMessage::FinishedFlat`] with the panic message.
+pub fn spawn_worker<F>(
+    tab: &mut TabState,
+    sender: TabSender,
+    kind: WorkerKind,
+    body: F,
+) -> JoinHandle<()>
+where
+    F: FnOnce(&TabSender) + Send + 'static,
+{
+    let (watch, token) = WorkerWatch::new(Instant::now());
+    tab.worker = Some(watch);
+    std::thread::spawn(move || {
+        let _token = token;
+        if let Err(payload) = panic::catch_unwind(AssertUnwindSafe(|| body(&sender))) {
+            let error = format!(
+                "Comparison failed unexpectedly: {}",
+                panic_message(payload.as_ref())
+            );
+            logger::error(&error);
+            sender.send(match kind {
+                WorkerKind::Hierarchy => ComparisonMessage::Finished(Err(error)),
+                WorkerKind::Flat => ComparisonMessage::FinishedFlat(Err(error)),
+            });
+        }
+    })
+}
+
+/// The text of a panic payload, as passed to `panic!`
+pub fn panic_message(payload: &(dyn Any + Send)) -> String {
+    if let Some(message) = payload.downcast_ref::<&str>() {
+        message.to_string()
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        message.clone()
+    } else {
+        "unknown panic".to_string()
+    }
+}
@@ -1,7 +1,11 @@
 use dir_compare_core::{
     ComparisonStrategyType, FastHashStrategy, FilenameOnlyStrategy, FilenameSizeStrategy,
-    SampledHashStrategy, compare_directories,
+    OptionValue, SampledHashStrategy, assert_result_matches, compare_directories,
 };
+use dir_compare_gui::option_widgets::option_label;
+use dir_compare_gui::progress::ComparisonMessage;
+use dir_compare_gui::strings::{Locale, Strings};
+use dir_compare_gui::tabs::Tabs;
 use dir_compare_gui::test_utils::create_test_dir_structure;
 use std::path::Path;
 
@@ -31,16 +35,15 @@ fn test_filename_size_comparison_strategy() {
     let (dir_a, dir_b) = create_test_dir_structure();
 
     let strategy = FilenameSizeStrategy::new(false);
-    let result = compare_directories(dir_a.path(), dir_b.path(), &strategy, None);
-
-    assert!(result.is_ok());
-    let comparison = result.unwrap();
-
-    // With FilenameSizeStrategy, files with same name but different sizes
-    // should appear in different categories (a_only or b_only)
-    // We just verify the comparison completed without errors
-    let total_count = comparison.a_only.len() + comparison.b_only.len() + comparison.both.len();
-    assert!(total_count > 0, "Comparison should return results");
+    let comparison = compare_directories(dir_a.path(), dir_b.path(), &strategy, None).unwrap();
+
+    // Files with the same name but different sizes are modified; the two
+    // common_dir/file.txt versions have equal sizes and match
+    assert_result_matches!(
+        comparison,
+        both: ["common.txt", "common_dir", "common_dir/file.txt", "common_dir/subdir"],
+        modified: ["different_size.txt"],
+    );
 }
 
 #[test]
@@ -48,21 +51,15 @@ fn test_content_hash_comparison_strategy() {
     let (dir_a, dir_b) = create_test_dir_structure();
 
     let strategy = FastHashStrategy::new(false);
-    let result = compare_directories(dir_a.path(), dir_b.path(), &strategy, None);
-
-    assert!(result.is_ok());
-    let comparison = result.unwrap();
-
-    // Common.txt has same content in both directories
-    let has_common_file = comparison.both.iter().any(|(a, _b)| {
-        a.path
-            .file_name()
-            .map(|n| n == "common.txt")
-            .unwrap_or(false)
-    });
-    assert!(
-        has_common_file,
-        "Files with identical content should be in 'both'"
+    let comparison = compare_directories(dir_a.path(), dir_b.path(), &strategy, None).unwrap();
+
+    // Only files with identical content are in 'both'
+    assert_result_matches!(
+        comparison,
+        a_only: ["a_only.txt", "a_only_dir", "a_only_dir/nested.txt"],
+        b_only: ["b_only.txt", "b_only_dir", "b_only_dir/nested.txt"],
+        both: ["common.txt", "common_dir", "common_dir/subdir"],
+        modified: ["common_dir/file.txt", "different_size.txt"],
     );
 }
 
@@ -139,17 +136,19 @@ fn test_comparison_method_selection_mapping() {
         ComparisonStrategyType::Filename,
         ComparisonStrategyType::FilenameSize,
         ComparisonStrategyType::FastHash,
+        ComparisonStrategyType::Blake3,
         ComparisonStrategyType::SampledHash,
+        ComparisonStrategyType::Mtime,
     ];
 
     // Just verify all variants are accessible
-    assert_eq!(methods.len(), 4);
+    assert_eq!(methods.len(), 6);
 }
 
 #[test]
 fn test_comparison_state_management() {
     // Test that simulates the UI state changes during comparison
-    // In the real GUI, these would be fields in AppState
+    // In the real GUI, these would be fields in TabState
 
     // Initial state
     let mut results_available = false;
@@ -221,3 +220,75 @@ fn test_async_comparison_channel() {
     assert!(result.is_ok());
     assert_eq!(result.unwrap().unwrap(), "Comparison complete");
 }
+
+#[test]
+fn test_strategy_options_are_localized() {
+    for locale in Locale::ALL {
+        let strings = Strings::new(locale);
+        for method in ComparisonStrategyType::ALL {
+            for option in method.options() {
+                for key in [
+                    format!("option.{}", option.name),
+                    format!("option.{}.help", option.name),
+                ] {
+                    assert_ne!(strings.get(&key), key, "{:?}", locale);
+                }
+            }
+        }
+    }
+    let strings = Strings::new(Locale::German);
+    let verify = &ComparisonStrategyType::SampledHash.options()[0];
+    assert_eq!(option_label(&strings, verify), "Treffer prüfen");
+}
+
+#[test]
+fn test_selecting_a_method_resets_its_options() {
+    let mut tabs = Tabs::new();
+    let tab = tabs.active_mut();
+    tab.select_method(ComparisonStrategyType::Mtime);
+    assert_eq!(
+        tab.strategy_options.get("tolerance_secs"),
+        Some(&OptionValue::Int(1))
+    );
+    tab.strategy_options
+        .insert("tolerance_secs".to_string(), OptionValue::Int(30));
+
+    // Reselecting the same method keeps the edited value
+    tab.select_method(ComparisonStrategyType::Mtime);
+    assert_eq!(
+        tab.strategy_options.get("tolerance_secs"),
+        Some(&OptionValue::Int(30))
+    );
+
+    tab.select_method(ComparisonStrategyType::SampledHash);
+    assert_eq!(
+        tab.strategy_options,
+        ComparisonStrategyType::SampledHash.default_options()
+    );
+}
+
+#[test]
+fn test_invalid_option_is_shown_in_tab() {
+    let mut tabs = Tabs::new();
+    let id = tabs.active().id();
+    let tab = tabs.active_mut();
+    tab.select_method(ComparisonStrategyType::Mtime);
+    tab.strategy_options
+        .insert("tolerance_secs".to_string(), OptionValue::Int(-5));
+    tab.start_comparison();
+
+    // What the worker sends when the factory rejects the options
+    let Err(err) = tab.comparison_method.build(false, &tab.strategy_options) else {
+        panic!("out-of-range tolerance accepted");
+    };
+    tabs.route(id, ComparisonMessage::Finished(Err(err.to_string())));
+
+    let tab = tabs.active();
+    assert!(!tab.is_comparing);
+    assert!(
+        tab.error_message
+            .as_deref()
+            .unwrap()
+            .contains("Invalid option 'tolerance_secs'")
+    );
+}
@@ -239,7 +239,7 @@ fn test_comparison_with_very_long_filenames() {
     let dir_b = TempDir::new().unwrap();
 
     // Create files with long names
-    let long_name: String = std::iter::repeat('a').take(200).collect();
+    let long_name: String = "a".repeat(200);
     std::fs::write(dir_a.path().join(&long_name), "content a").unwrap();
     std::fs::write(dir_b.path().join(&long_name), "content b").unwrap();
 
@@ -1,4 +1,5 @@
 use dir_compare_gui::dialog::{FileDialogProvider, MockFileDialog};
+use dir_compare_gui::tabs::Tabs;
 use std::path::PathBuf;
 use tempfile::TempDir;
 
@@ -131,3 +132,38 @@ fn test_ignore_file_selection() {
     let result = dialog.pick_file();
     assert_eq!(result, Some(test_path));
 }
+
+#[test]
+fn test_browse_buttons_use_the_matching_dialog() {
+    let dialog = MockFileDialog::default()
+        .with_folder(Some(PathBuf::from("/picked/folder")))
+        .with_file(Some(PathBuf::from("/picked/.dirignore")));
+    let mut tabs = Tabs::new();
+    let tab = tabs.active_mut();
+
+    tab.browse_dir_a(&dialog);
+    tab.browse_ignore_file(&dialog);
+    assert_eq!(tab.dir_a_path, "/picked/folder");
+    assert_eq!(tab.dir_b_path, "");
+    assert_eq!(tab.ignore_file_path.as_deref(), Some("/picked/.dirignore"));
+
+    tab.browse_dir_b(&dialog);
+    assert_eq!(tab.dir_b_path, "/picked/folder");
+}
+
+#[test]
+fn test_cancelled_browse_keeps_the_paths() {
+    let mut tabs = Tabs::new();
+    let tab = tabs.active_mut();
+    tab.dir_a_path = "/kept/a".to_string();
+    tab.ignore_file_path = Some("/kept/.dirignore".to_string());
+
+    // Only a save dialog would answer, which browsing never opens
+    let dialog = MockFileDialog::default().with_save(Some(PathBuf::from("/report.html")));
+    tab.browse_dir_a(&dialog);
+    tab.browse_dir_b(&dialog);
+    tab.browse_ignore_file(&dialog);
+    assert_eq!(tab.dir_a_path, "/kept/a");
+    assert_eq!(tab.dir_b_path, "");
+    assert_eq!(tab.ignore_file_path.as_deref(), Some("/kept/.dirignore"));
+}
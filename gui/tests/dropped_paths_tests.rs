@@ -0,0 +1,172 @@
+use dir_compare_gui::dropped_paths::{
+    DROP_ERROR_DURATION, DropTarget, assign_dropped_paths, drop_paths,
+};
+use dir_compare_gui::strings::{Locale, Strings};
+use dir_compare_gui::tabs::{TabState, Tabs};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+use tempfile::TempDir;
+
+/// Three folders and a file, dropped from a file manager
+struct Dropped {
+    _temp: TempDir,
+    first: PathBuf,
+    second: PathBuf,
+    third: PathBuf,
+    file: PathBuf,
+}
+
+fn dropped() -> Dropped {
+    let temp = TempDir::new().unwrap();
+    let folder = |name: &str| {
+        let path = temp.path().join(name);
+        fs::create_dir(&path).unwrap();
+        path
+    };
+    let (first, second, third) = (folder("first"), folder("second"), folder("third"));
+    let file = temp.path().join("notes.txt");
+    fs::write(&file, "not a folder").unwrap();
+    Dropped {
+        first,
+        second,
+        third,
+        file,
+        _temp: temp,
+    }
+}
+
+fn paths(tab: &TabState) -> (&str, &str) {
+    (&tab.dir_a_path, &tab.dir_b_path)
+}
+
+fn text(path: &Path) -> String {
+    path.display().to_string()
+}
+
+#[test]
+fn test_single_folder_fills_a_then_b() {
+    let drop = dropped();
+    let mut tabs = Tabs::new();
+    let tab = tabs.active_mut();
+
+    assert!(assign_dropped_paths(tab, std::slice::from_ref(&drop.first), None).is_empty());
+    assert_eq!(paths(tab), (text(&drop.first).as_str(), ""));
+
+    assign_dropped_paths(tab, std::slice::from_ref(&drop.second), None);
+    assert_eq!(
+        paths(tab),
+        (text(&drop.first).as_str(), text(&drop.second).as_str())
+    );
+
+    // With both entered, further drops replace B
+    assign_dropped_paths(tab, std::slice::from_ref(&drop.third), None);
+    assert_eq!(
+        paths(tab),
+        (text(&drop.first).as_str(), text(&drop.third).as_str())
+    );
+}
+
+#[test]
+fn test_single_folder_replaces_an_invalid_a() {
+    let drop = dropped();
+    let mut tabs = Tabs::new();
+    let tab = tabs.active_mut();
+    tab.dir_a_path = text(&drop.first.join("missing"));
+    tab.dir_b_path = text(&drop.second);
+
+    assign_dropped_paths(tab, std::slice::from_ref(&drop.third), None);
+    assert_eq!(
+        paths(tab),
+        (text(&drop.third).as_str(), text(&drop.second).as_str())
+    );
+}
+
+#[test]
+fn test_two_folders_fill_both_in_order() {
+    let drop = dropped();
+    let mut tabs = Tabs::new();
+    let tab = tabs.active_mut();
+    tab.dir_a_path = text(&drop.third);
+
+    let folders = [drop.first.clone(), drop.second.clone(), drop.third.clone()];
+    assert!(assign_dropped_paths(tab, &folders, None).is_empty());
+    assert_eq!(
+        paths(tab),
+        (text(&drop.first).as_str(), text(&drop.second).as_str())
+    );
+}
+
+#[test]
+fn test_drop_onto_a_field_targets_it() {
+    let drop = dropped();
+    let mut tabs = Tabs::new();
+    let tab = tabs.active_mut();
+
+    // B is filled although A is empty
+    assign_dropped_paths(
+        tab,
+        std::slice::from_ref(&drop.first),
+        Some(DropTarget::DirB),
+    );
+    assert_eq!(paths(tab), ("", text(&drop.first).as_str()));
+
+    // A second folder goes to the other field
+    let folders = [drop.second.clone(), drop.third.clone()];
+    assign_dropped_paths(tab, &folders, Some(DropTarget::DirB));
+    assert_eq!(
+        paths(tab),
+        (text(&drop.third).as_str(), text(&drop.second).as_str())
+    );
+
+    assign_dropped_paths(
+        tab,
+        std::slice::from_ref(&drop.first),
+        Some(DropTarget::DirA),
+    );
+    assert_eq!(
+        paths(tab),
+        (text(&drop.first).as_str(), text(&drop.second).as_str())
+    );
+}
+
+#[test]
+fn test_files_are_rejected() {
+    let drop = dropped();
+    let mut tabs = Tabs::new();
+    let tab = tabs.active_mut();
+
+    let rejected = assign_dropped_paths(tab, &[drop.file.clone(), drop.first.clone()], None);
+    assert_eq!(rejected, [drop.file]);
+    assert_eq!(paths(tab), (text(&drop.first).as_str(), ""));
+
+    // A missing path is no folder either
+    let missing = drop.first.join("missing");
+    let rejected = assign_dropped_paths(tab, std::slice::from_ref(&missing), None);
+    assert_eq!(rejected, [missing]);
+    assert_eq!(paths(tab), (text(&drop.first).as_str(), ""));
+}
+
+#[test]
+fn test_drop_error_names_the_files_and_expires() {
+    let drop = dropped();
+    let strings = Strings::new(Locale::English);
+    let mut tabs = Tabs::new();
+    let tab = tabs.active_mut();
+    let now = Instant::now();
+
+    drop_paths(tab, std::slice::from_ref(&drop.file), None, &strings, now);
+    let error = tab.drop_error.clone().unwrap();
+    assert!(error.message.contains("notes.txt"), "{}", error.message);
+    assert_eq!(error.remaining(now), DROP_ERROR_DURATION);
+    assert!(
+        error
+            .remaining(now + DROP_ERROR_DURATION + Duration::from_secs(1))
+            .is_zero()
+    );
+    assert_eq!(paths(tab), ("", ""));
+
+    // A drop of folders only clears the message
+    drop_paths(tab, std::slice::from_ref(&drop.first), None, &strings, now);
+    assert!(tab.drop_error.is_none());
+}
@@ -0,0 +1,176 @@
+use dir_compare_core::output::{Formatter, HtmlFormatter, MarkdownFormatter, TextFormatter};
+use dir_compare_core::{ComparisonResult, FilenameOnlyStrategy, compare_directories};
+use dir_compare_gui::dialog::MockFileDialog;
+use dir_compare_gui::export::ReportFormat;
+use dir_compare_gui::strings::{Locale, Strings};
+use dir_compare_gui::tabs::Tabs;
+use std::fs;
+use std::path::Path;
+use tempfile::TempDir;
+
+/// Compares two fresh directories holding the given file names
+fn comparison(a: &[&str], b: &[&str]) -> ComparisonResult {
+    let dir_a = TempDir::new().unwrap();
+    let dir_b = TempDir::new().unwrap();
+    for name in a {
+        fs::write(dir_a.path().join(name), "content").unwrap();
+    }
+    for name in b {
+        fs::write(dir_b.path().join(name), "content").unwrap();
+    }
+    compare_directories(
+        dir_a.path(),
+        dir_b.path(),
+        &FilenameOnlyStrategy::new(false),
+        None,
+    )
+    .unwrap()
+}
+
+/// Tabs whose active tab holds a finished comparison
+fn tabs_with_results() -> Tabs {
+    let mut tabs = Tabs::new();
+    tabs.active_mut().results = Some(comparison(&["a.txt", "same.txt"], &["b.txt", "same.txt"]));
+    tabs
+}
+
+#[test]
+fn test_report_format_from_extension() {
+    let cases = [
+        ("report.html", ReportFormat::Html),
+        ("REPORT.HTM", ReportFormat::Html),
+        ("report.md", ReportFormat::Markdown),
+        ("report.markdown", ReportFormat::Markdown),
+        ("report.txt", ReportFormat::Text),
+        ("report", ReportFormat::Text),
+    ];
+    for (path, format) in cases {
+        assert_eq!(ReportFormat::from_path(Path::new(path)), format, "{}", path);
+    }
+}
+
+#[test]
+fn test_export_writes_format_by_extension() {
+    let out = TempDir::new().unwrap();
+    let strings = Strings::new(Locale::English);
+    let mut tabs = tabs_with_results();
+    let expected = {
+        let result = tabs.active().results.as_ref().unwrap();
+        [
+            ("report.html", HtmlFormatter.format(result)),
+            (
+                "report.md",
+                MarkdownFormatter.format_with_labels(result, &strings.report_labels()),
+            ),
+            (
+                "report.txt",
+                TextFormatter.format_with_labels(result, &strings.report_labels()),
+            ),
+        ]
+    };
+
+    for (name, content) in expected {
+        let path = out.path().join(name);
+        let dialog = MockFileDialog::new(Some(path.clone()));
+        tabs.active_mut().export_report(&dialog, &strings);
+
+        assert_eq!(fs::read_to_string(&path).unwrap(), content, "{}", name);
+        let tab = tabs.active();
+        assert_eq!(tab.error_message, None);
+        assert_eq!(
+            tab.status_message.as_deref(),
+            Some(format!("Report saved to {}", path.display()).as_str())
+        );
+    }
+}
+
+#[test]
+fn test_export_uses_locale_labels() {
+    let out = TempDir::new().unwrap();
+    let strings = Strings::new(Locale::German);
+    let mut tabs = tabs_with_results();
+    let path = out.path().join("bericht.md");
+
+    tabs.active_mut()
+        .export_report(&MockFileDialog::new(Some(path.clone())), &strings);
+
+    let result = tabs.active().results.as_ref().unwrap();
+    assert_eq!(
+        fs::read_to_string(&path).unwrap(),
+        MarkdownFormatter.format_with_labels(result, &strings.report_labels())
+    );
+}
+
+#[test]
+fn test_export_cancelled_or_without_results_does_nothing() {
+    let out = TempDir::new().unwrap();
+    let strings = Strings::new(Locale::English);
+    let path = out.path().join("report.html");
+
+    let mut tabs = tabs_with_results();
+    tabs.active_mut()
+        .export_report(&MockFileDialog::new(None), &strings);
+    assert_eq!(tabs.active().status_message, None);
+    assert_eq!(tabs.active().error_message, None);
+
+    let mut tabs = Tabs::new();
+    tabs.active_mut()
+        .export_report(&MockFileDialog::new(Some(path.clone())), &strings);
+    assert!(!path.exists());
+    assert_eq!(tabs.active().status_message, None);
+}
+
+#[test]
+fn test_export_failure_sets_error_message() {
+    let out = TempDir::new().unwrap();
+    let strings = Strings::new(Locale::English);
+    let mut tabs = tabs_with_results();
+    let path = out.path().join("missing").join("report.html");
+
+    tabs.active_mut()
+        .export_report(&MockFileDialog::new(Some(path.clone())), &strings);
+
+    let tab = tabs.active();
+    assert_eq!(tab.status_message, None);
+    let error = tab.error_message.as_deref().unwrap();
+    assert!(
+        error.starts_with(&format!("Could not write report {}: ", path.display())),
+        "{}",
+        error
+    );
+}
+
+#[test]
+fn test_export_settings_writes_a_config_file() {
+    let out = TempDir::new().unwrap();
+    let strings = Strings::new(Locale::English);
+    let path = out.path().join("dir-compare.toml");
+
+    // Settings need no results
+    let mut tabs = Tabs::new();
+    let tab = tabs.active_mut();
+    tab.dir_a_path = "/data/a".to_string();
+    tab.flat_mode = true;
+    let dialog = MockFileDialog::default().with_save(Some(path.clone()));
+    tab.export_settings(&dialog, &strings);
+
+    let text = fs::read_to_string(&path).unwrap();
+    assert!(text.contains("# Directory A: /data/a"));
+    assert!(text.contains("flat = true"));
+    assert_eq!(
+        tab.status_message,
+        Some(format!("Settings saved to {}", path.display()))
+    );
+
+    tab.export_settings(
+        &MockFileDialog::new(Some(out.path().join("missing/x.toml"))),
+        &strings,
+    );
+    assert_eq!(tab.status_message, None);
+    assert!(
+        tab.error_message
+            .as_deref()
+            .unwrap()
+            .starts_with("Could not write settings")
+    );
+}
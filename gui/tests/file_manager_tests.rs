@@ -0,0 +1,120 @@
+use dir_compare_core::{FilenameOnlyStrategy, compare_directories};
+use dir_compare_gui::file_manager::{open_in_file_manager, reveal_command};
+use dir_compare_gui::progress::ComparisonMessage;
+use dir_compare_gui::strings::{Locale, Strings};
+use dir_compare_gui::table_view::ResultCategory;
+use dir_compare_gui::tabs::Tabs;
+use std::cell::RefCell;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use tempfile::TempDir;
+
+/// Tabs whose active tab holds the comparison of `dir_a` and `dir_b`
+fn tabs_with_results(dir_a: &Path, dir_b: &Path) -> Tabs {
+    let result =
+        compare_directories(dir_a, dir_b, &FilenameOnlyStrategy::new(false), None).unwrap();
+    let mut tabs = Tabs::new();
+    tabs.active_mut().start_comparison();
+    tabs.active_mut()
+        .handle_message(ComparisonMessage::Finished(Ok(result)));
+    tabs
+}
+
+fn setup_dirs() -> (TempDir, TempDir) {
+    let dir_a = TempDir::new().unwrap();
+    let dir_b = TempDir::new().unwrap();
+    fs::create_dir(dir_a.path().join("sub")).unwrap();
+    fs::write(dir_a.path().join("sub/only_a.txt"), "a").unwrap();
+    fs::write(dir_b.path().join("only_b.txt"), "b").unwrap();
+    fs::write(dir_a.path().join("shared.txt"), "same").unwrap();
+    fs::write(dir_b.path().join("shared.txt"), "same").unwrap();
+    (dir_a, dir_b)
+}
+
+#[test]
+fn test_tree_paths_resolve_against_their_root() {
+    let (dir_a, dir_b) = setup_dirs();
+    let tabs = tabs_with_results(dir_a.path(), dir_b.path());
+    let cache = tabs.active().tree_cache.as_ref().unwrap();
+    let root_a = fs::canonicalize(dir_a.path()).unwrap();
+    let root_b = fs::canonicalize(dir_b.path()).unwrap();
+
+    assert_eq!(
+        cache.absolute_path(ResultCategory::AOnly, Path::new("sub/only_a.txt")),
+        Some(root_a.join("sub/only_a.txt"))
+    );
+    assert_eq!(
+        cache.absolute_path(ResultCategory::BOnly, Path::new("only_b.txt")),
+        Some(root_b.join("only_b.txt"))
+    );
+    assert_eq!(
+        cache.absolute_path(ResultCategory::Both, Path::new("shared.txt")),
+        Some(root_a.join("shared.txt"))
+    );
+}
+
+#[test]
+fn test_reveal_entry_passes_absolute_path() {
+    let (dir_a, dir_b) = setup_dirs();
+    let mut tabs = tabs_with_results(dir_a.path(), dir_b.path());
+    let strings = Strings::new(Locale::English);
+    let revealed = RefCell::new(None::<PathBuf>);
+
+    tabs.active_mut().reveal_entry(
+        ResultCategory::BOnly,
+        Path::new("only_b.txt"),
+        |path| {
+            *revealed.borrow_mut() = Some(path.to_path_buf());
+            Ok(())
+        },
+        &strings,
+    );
+
+    let root_b = fs::canonicalize(dir_b.path()).unwrap();
+    assert_eq!(revealed.into_inner(), Some(root_b.join("only_b.txt")));
+    assert!(tabs.active().error_message.is_none());
+}
+
+#[test]
+fn test_reveal_deleted_file_reports_error() {
+    let (dir_a, dir_b) = setup_dirs();
+    let mut tabs = tabs_with_results(dir_a.path(), dir_b.path());
+    let strings = Strings::new(Locale::English);
+    fs::remove_file(dir_a.path().join("sub/only_a.txt")).unwrap();
+
+    tabs.active_mut().reveal_entry(
+        ResultCategory::AOnly,
+        Path::new("sub/only_a.txt"),
+        open_in_file_manager,
+        &strings,
+    );
+
+    let error = tabs.active().error_message.clone().unwrap();
+    assert!(error.starts_with("Could not show "), "{}", error);
+    assert!(error.contains("only_a.txt"), "{}", error);
+}
+
+#[test]
+fn test_open_in_file_manager_missing_path() {
+    let dir = TempDir::new().unwrap();
+    let err = open_in_file_manager(&dir.path().join("gone.txt")).unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::NotFound);
+}
+
+#[test]
+fn test_reveal_command_targets_platform_file_manager() {
+    let path = Path::new("/data/photos/img 1.jpg");
+    let command = reveal_command(path);
+    let args: Vec<_> = command.get_args().collect();
+    if cfg!(target_os = "windows") {
+        assert_eq!(command.get_program(), "explorer");
+        assert_eq!(args, ["/select,/data/photos/img 1.jpg"]);
+    } else if cfg!(target_os = "macos") {
+        assert_eq!(command.get_program(), "open");
+        assert_eq!(args, ["-R", "/data/photos/img 1.jpg"]);
+    } else {
+        assert_eq!(command.get_program(), "xdg-open");
+        assert_eq!(args, ["/data/photos"]);
+    }
+}
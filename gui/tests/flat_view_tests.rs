@@ -0,0 +1,155 @@
+use dir_compare_core::{FlatComparisonOptions, FlatContentGroup, compare_directories_flat};
+use dir_compare_gui::flat_view::{FlatResultsCache, FlatStatus, short_hash};
+use dir_compare_gui::progress::ComparisonMessage;
+use dir_compare_gui::tabs::Tabs;
+use std::fs;
+use std::path::PathBuf;
+use tempfile::TempDir;
+
+fn group(files_in_a: &[&str], files_in_b: &[&str]) -> FlatContentGroup {
+    FlatContentGroup {
+        hash: "0123456789abcdef0123456789abcdef".to_string(),
+        size: 4,
+        file_count: files_in_a.len() + files_in_b.len(),
+        files_in_a: files_in_a.iter().map(PathBuf::from).collect(),
+        files_in_b: files_in_b.iter().map(PathBuf::from).collect(),
+        hard_links: Vec::new(),
+    }
+}
+
+/// Groups two directories by content: one file per status
+fn flat_comparison() -> dir_compare_core::FlatComparisonResult {
+    let dir_a = TempDir::new().unwrap();
+    let dir_b = TempDir::new().unwrap();
+    fs::write(dir_a.path().join("only_a.txt"), "alpha").unwrap();
+    fs::write(dir_b.path().join("only_b.txt"), "beta").unwrap();
+    fs::write(dir_a.path().join("old_name.txt"), "moved").unwrap();
+    fs::write(dir_b.path().join("new_name.txt"), "moved").unwrap();
+    fs::write(dir_a.path().join("copy1.txt"), "twice").unwrap();
+    fs::write(dir_a.path().join("copy2.txt"), "twice").unwrap();
+    compare_directories_flat(
+        dir_a.path(),
+        dir_b.path(),
+        &FlatComparisonOptions::default(),
+        None,
+    )
+    .unwrap()
+}
+
+#[test]
+fn test_flat_status_of_group() {
+    assert_eq!(FlatStatus::of(&group(&["a.txt"], &[])), FlatStatus::AOnly);
+    assert_eq!(FlatStatus::of(&group(&[], &["b.txt"])), FlatStatus::BOnly);
+    assert_eq!(
+        FlatStatus::of(&group(&["old.txt"], &["new.txt"])),
+        FlatStatus::Moved
+    );
+    assert_eq!(
+        FlatStatus::of(&group(&["same.txt"], &["same.txt"])),
+        FlatStatus::Duplicate
+    );
+    assert_eq!(
+        FlatStatus::of(&group(&["x.txt", "y.txt"], &[])),
+        FlatStatus::Duplicate
+    );
+    assert_eq!(
+        FlatStatus::of(&group(&["x.txt"], &["x.txt", "z.txt"])),
+        FlatStatus::Duplicate
+    );
+}
+
+#[test]
+fn test_flat_results_cache_groups_by_status() {
+    let cache = FlatResultsCache::new(flat_comparison());
+    let files = |status: FlatStatus| -> Vec<(Vec<PathBuf>, Vec<PathBuf>)> {
+        cache
+            .groups(status)
+            .map(|group| (group.files_in_a.clone(), group.files_in_b.clone()))
+            .collect()
+    };
+
+    assert_eq!(
+        files(FlatStatus::AOnly),
+        [(vec![PathBuf::from("only_a.txt")], vec![])]
+    );
+    assert_eq!(
+        files(FlatStatus::BOnly),
+        [(vec![], vec![PathBuf::from("only_b.txt")])]
+    );
+    assert_eq!(
+        files(FlatStatus::Moved),
+        [(
+            vec![PathBuf::from("old_name.txt")],
+            vec![PathBuf::from("new_name.txt")]
+        )]
+    );
+    assert_eq!(cache.count(FlatStatus::Duplicate), 1);
+    let duplicate = cache.groups(FlatStatus::Duplicate).next().unwrap();
+    let mut copies = duplicate.files_in_a.clone();
+    copies.sort();
+    assert_eq!(
+        copies,
+        [PathBuf::from("copy1.txt"), PathBuf::from("copy2.txt")]
+    );
+    assert_eq!(cache.result.total_files_a, 4);
+    assert_eq!(cache.result.total_files_b, 2);
+}
+
+#[test]
+fn test_flat_results_cache_swap_sides() {
+    let mut cache = FlatResultsCache::new(flat_comparison());
+    cache.swap_sides();
+
+    let only = |status: FlatStatus| {
+        let group = cache.groups(status).next().unwrap();
+        (group.files_in_a.clone(), group.files_in_b.clone())
+    };
+    assert_eq!(
+        only(FlatStatus::AOnly),
+        (vec![PathBuf::from("only_b.txt")], vec![])
+    );
+    assert_eq!(
+        only(FlatStatus::BOnly),
+        (vec![], vec![PathBuf::from("only_a.txt")])
+    );
+    assert_eq!(
+        only(FlatStatus::Moved),
+        (
+            vec![PathBuf::from("new_name.txt")],
+            vec![PathBuf::from("old_name.txt")]
+        )
+    );
+    assert_eq!(cache.result.total_files_a, 2);
+    assert_eq!(cache.result.total_files_b, 4);
+}
+
+#[test]
+fn test_short_hash_truncates_long_hashes_only() {
+    assert_eq!(
+        short_hash("0123456789abcdef0123456789abcdef"),
+        "0123456789abcdef"
+    );
+    assert_eq!(short_hash("abc"), "abc");
+}
+
+#[test]
+fn test_tab_shows_flat_results() {
+    let mut tabs = Tabs::new();
+    let tab = tabs.active_mut();
+    tab.flat_mode = true;
+    tab.start_comparison();
+    tab.handle_message(ComparisonMessage::FinishedFlat(Ok(flat_comparison())));
+
+    assert!(!tab.is_comparing);
+    assert!(tab.results.is_none());
+    assert!(tab.tree_cache.is_none());
+    let flat = tab.flat_results.as_ref().unwrap();
+    assert_eq!(flat.count(FlatStatus::Moved), 1);
+
+    // A new comparison clears the previous flat results
+    tab.start_comparison();
+    assert!(tab.flat_results.is_none());
+    tab.handle_message(ComparisonMessage::FinishedFlat(Err("boom".to_string())));
+    assert_eq!(tab.error_message.as_deref(), Some("boom"));
+    assert!(tab.flat_results.is_none());
+}
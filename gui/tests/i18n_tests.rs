@@ -0,0 +1,98 @@
+use dir_compare_core::output::{MarkdownFormatter, TextFormatter};
+use dir_compare_core::{ComparisonResult, Entry, EntryKind};
+use dir_compare_gui::strings::{Locale, Strings};
+use std::path::PathBuf;
+
+fn create_entry(path: &str) -> Entry {
+    Entry {
+        path: PathBuf::from(path),
+        kind: EntryKind::File,
+        abs_path: PathBuf::from(path),
+        size: None,
+        nested_repository: false,
+        mtime: None,
+    }
+}
+
+fn create_result() -> ComparisonResult {
+    ComparisonResult {
+        a_only: vec![create_entry("a.txt")],
+        b_only: vec![create_entry("b.txt"), create_entry("c.txt")],
+        both: vec![],
+        modified: vec![],
+        exact_counts: None,
+        warnings: Vec::new(),
+        stats: Default::default(),
+        context: None,
+        identical_subtrees: Vec::new(),
+        mismatch_reasons: Vec::new(),
+        renamed: Vec::new(),
+    }
+}
+
+#[test]
+fn test_german_catalog_translates_report_headers() {
+    let strings = Strings::new(Locale::German);
+    let labels = strings.report_labels();
+    let result = create_result();
+
+    let markdown = MarkdownFormatter.format_with_labels(&result, &labels);
+    assert!(markdown.starts_with("# Verzeichnisvergleich\n"));
+    assert!(markdown.contains("## Zusammenfassung"));
+    assert!(markdown.contains("| Kategorie | Anzahl |"));
+    assert!(markdown.contains("## Nur in A"));
+    assert!(markdown.contains("## Nur in B"));
+    assert!(markdown.contains("## In beiden"));
+    assert!(markdown.contains("*Keine übereinstimmenden Einträge*"));
+    assert!(!markdown.contains("Summary"));
+
+    let text = TextFormatter.format_with_labels(&result, &labels);
+    assert!(text.contains("Nur in A (1 Eintrag):"));
+    assert!(text.contains("Nur in B (2 Einträge):"));
+    assert!(text.contains("In beiden (0 Einträge):"));
+}
+
+#[test]
+fn test_german_catalog_translates_gui_strings() {
+    let strings = Strings::new(Locale::German);
+    assert_eq!(strings.get("section.a_only"), "Nur in A");
+    assert_eq!(strings.get("action.compare"), "Vergleichen");
+    assert_eq!(
+        strings.format("status.a_only", &[("count", &3)]),
+        "Nur A: 3"
+    );
+}
+
+#[test]
+fn test_missing_keys_fall_back_to_english() {
+    let strings = Strings::from_catalog(Locale::German, "action.compare = Vergleichen\n");
+
+    assert_eq!(strings.get("action.compare"), "Vergleichen");
+    assert_eq!(strings.get("section.a_only"), "Only in A");
+    assert_eq!(strings.get("report.summary"), "Summary");
+    assert!(
+        strings
+            .missing_keys()
+            .iter()
+            .any(|key| key == "section.a_only")
+    );
+    assert!(
+        !strings
+            .missing_keys()
+            .iter()
+            .any(|key| key == "action.compare")
+    );
+}
+
+#[test]
+fn test_english_strings_match_previous_labels() {
+    let strings = Strings::new(Locale::English);
+    assert_eq!(strings.get("section.a_only"), "Only in A");
+    assert_eq!(strings.get("section.b_only"), "Only in B");
+    assert_eq!(strings.get("section.both"), "In Both");
+    assert_eq!(strings.get("input.browse"), "Browse...");
+    assert_eq!(
+        strings.format("error.message", &[("error", &"boom")]),
+        "Error: boom"
+    );
+}
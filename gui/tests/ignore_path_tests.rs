@@ -0,0 +1,105 @@
+use dir_compare_gui::ignore_path::resolve_ignore_path;
+use std::fs;
+use tempfile::TempDir;
+
+/// Creates a working directory and a directory A, each with its own files
+fn setup() -> (TempDir, TempDir) {
+    let cwd = TempDir::new().unwrap();
+    let dir_a = TempDir::new().unwrap();
+    fs::write(cwd.path().join("cwd.ignore"), "*.log\n").unwrap();
+    fs::write(dir_a.path().join(".dcignore"), "*.tmp\n").unwrap();
+    (cwd, dir_a)
+}
+
+#[test]
+fn test_absolute_path_used_as_given() {
+    let (cwd, dir_a) = setup();
+    let absolute = dir_a.path().join(".dcignore");
+
+    let resolved = resolve_ignore_path(
+        absolute.to_str().unwrap(),
+        cwd.path(),
+        dir_a.path().to_str().unwrap(),
+    )
+    .unwrap();
+    assert_eq!(resolved, absolute);
+}
+
+#[test]
+fn test_relative_path_found_in_working_directory() {
+    let (cwd, dir_a) = setup();
+
+    let resolved =
+        resolve_ignore_path("cwd.ignore", cwd.path(), dir_a.path().to_str().unwrap()).unwrap();
+    assert_eq!(resolved, cwd.path().join("cwd.ignore"));
+}
+
+#[test]
+fn test_working_directory_takes_precedence_over_dir_a() {
+    let (cwd, dir_a) = setup();
+    fs::write(cwd.path().join(".dcignore"), "*.bak\n").unwrap();
+
+    let resolved =
+        resolve_ignore_path(".dcignore", cwd.path(), dir_a.path().to_str().unwrap()).unwrap();
+    assert_eq!(resolved, cwd.path().join(".dcignore"));
+}
+
+#[test]
+fn test_relative_path_falls_back_to_dir_a() {
+    let (cwd, dir_a) = setup();
+
+    let resolved =
+        resolve_ignore_path(" .dcignore ", cwd.path(), dir_a.path().to_str().unwrap()).unwrap();
+    assert_eq!(resolved, dir_a.path().join(".dcignore"));
+    assert!(resolved.is_absolute());
+}
+
+#[test]
+fn test_missing_file_reports_every_location() {
+    let (cwd, dir_a) = setup();
+
+    let error = resolve_ignore_path("missing.ignore", cwd.path(), dir_a.path().to_str().unwrap())
+        .unwrap_err();
+    assert_eq!(error.input, "missing.ignore");
+    assert_eq!(
+        error.tried,
+        vec![
+            cwd.path().join("missing.ignore"),
+            dir_a.path().join("missing.ignore")
+        ]
+    );
+    assert!(
+        error
+            .to_string()
+            .starts_with("Ignore file not found: missing.ignore")
+    );
+
+    // Without directory A only the working directory is tried
+    let error = resolve_ignore_path("missing.ignore", cwd.path(), "").unwrap_err();
+    assert_eq!(error.tried.len(), 1);
+}
+
+#[test]
+fn test_missing_absolute_path_is_an_error() {
+    let (cwd, dir_a) = setup();
+    let absolute = cwd.path().join("gone.ignore");
+
+    let error = resolve_ignore_path(
+        absolute.to_str().unwrap(),
+        cwd.path(),
+        dir_a.path().to_str().unwrap(),
+    )
+    .unwrap_err();
+    assert_eq!(error.tried, vec![absolute]);
+}
+
+#[test]
+fn test_directory_is_not_an_ignore_file() {
+    let (cwd, dir_a) = setup();
+    let error = resolve_ignore_path(
+        dir_a.path().to_str().unwrap(),
+        cwd.path(),
+        dir_a.path().to_str().unwrap(),
+    );
+    assert!(error.is_err());
+}
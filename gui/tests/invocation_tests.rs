@@ -0,0 +1,254 @@
+use dir_compare_core::ComparisonStrategyType;
+use dir_compare_core::config::{ConfigValue, parse_config};
+use dir_compare_core::strategy_options::OptionValue;
+use dir_compare_gui::invocation::{
+    ComparisonSetup, cli_args, command_line, settings_toml, setup_options, shell_quote,
+};
+use dir_compare_gui::tabs::Tabs;
+use std::fs;
+use std::path::{Path, PathBuf};
+use tempfile::TempDir;
+
+fn setup(method: ComparisonStrategyType) -> ComparisonSetup {
+    ComparisonSetup {
+        dir_a: "/data/a".to_string(),
+        dir_b: "/data/b".to_string(),
+        method,
+        strategy_options: method.default_options(),
+        flat_mode: false,
+        full_hash: false,
+        respect_gitignore: false,
+        max_depth: None,
+        ignore_hidden: false,
+        detect_renames: false,
+        ignore_file: None,
+    }
+}
+
+/// Every option name of `args`, which must each appear once
+fn option_names(args: &[String]) -> Vec<&str> {
+    let names: Vec<&str> = args
+        .iter()
+        .filter_map(|arg| arg.strip_prefix("--"))
+        .collect();
+    for name in &names {
+        assert_eq!(
+            names.iter().filter(|other| *other == name).count(),
+            1,
+            "{:?}",
+            args
+        );
+    }
+    names
+}
+
+#[test]
+fn test_shell_quote() {
+    assert_eq!(shell_quote("/data/photos-2024"), "/data/photos-2024");
+    assert_eq!(shell_quote("--method"), "--method");
+    assert_eq!(shell_quote(""), "''");
+    assert_eq!(shell_quote("/my photos"), "'/my photos'");
+    assert_eq!(shell_quote("it's"), r"'it'\''s'");
+    assert_eq!(shell_quote(r#"say "hi""#), r#"'say "hi"'"#);
+    assert_eq!(shell_quote("/daten/Bücher"), "'/daten/Bücher'");
+    assert_eq!(shell_quote("/写真"), "'/写真'");
+    assert_eq!(shell_quote("$HOME/*.txt"), "'$HOME/*.txt'");
+}
+
+#[test]
+fn test_command_line_quotes_paths() {
+    let mut setup = setup(ComparisonStrategyType::SampledHash);
+    setup.dir_a = "/home/me/My Photos".to_string();
+    setup.dir_b = "/mnt/backup/Ann's \"best\" 写真".to_string();
+    assert_eq!(
+        command_line(&setup),
+        r#"dir-compare '/home/me/My Photos' '/mnt/backup/Ann'\''s "best" 写真'"#
+    );
+}
+
+#[test]
+fn test_default_options_are_left_out() {
+    // Sampled hashing without verification is the CLI default too
+    let mut sampled = setup(ComparisonStrategyType::SampledHash);
+    assert_eq!(cli_args(&sampled), ["/data/a", "/data/b"]);
+    sampled
+        .strategy_options
+        .insert("verify".to_string(), OptionValue::Bool(true));
+    assert_eq!(cli_args(&sampled), ["--verify", "/data/a", "/data/b"]);
+
+    let mtime = setup(ComparisonStrategyType::Mtime);
+    assert_eq!(
+        cli_args(&mtime),
+        ["--method", "mtime", "/data/a", "/data/b"]
+    );
+}
+
+#[test]
+fn test_every_non_default_option_once() {
+    let mut mtime = setup(ComparisonStrategyType::Mtime);
+    mtime
+        .strategy_options
+        .insert("tolerance_secs".to_string(), OptionValue::Int(5));
+    mtime.ignore_file = Some(PathBuf::from("/data/.dcignore"));
+    let args = cli_args(&mtime);
+    assert_eq!(option_names(&args), ["method", "mtime-tolerance", "ignore"]);
+    assert_eq!(
+        args,
+        [
+            "--method",
+            "mtime",
+            "--mtime-tolerance",
+            "5",
+            "--ignore",
+            "/data/.dcignore",
+            "/data/a",
+            "/data/b"
+        ]
+    );
+
+    for method in ComparisonStrategyType::ALL {
+        let args = cli_args(&setup(method));
+        option_names(&args);
+        assert_eq!(&args[args.len() - 2..], ["/data/a", "/data/b"]);
+    }
+}
+
+#[test]
+fn test_flat_mode_drops_the_method() {
+    let mut flat = setup(ComparisonStrategyType::Mtime);
+    flat.flat_mode = true;
+    assert_eq!(cli_args(&flat), ["--flat", "/data/a", "/data/b"]);
+
+    flat.full_hash = true;
+    assert_eq!(option_names(&cli_args(&flat)), ["flat", "full-hash"]);
+}
+
+#[test]
+fn test_respect_gitignore_applies_to_both_modes() {
+    let mut hierarchy = setup(ComparisonStrategyType::SampledHash);
+    hierarchy.respect_gitignore = true;
+    assert_eq!(option_names(&cli_args(&hierarchy)), ["respect-gitignore"]);
+
+    hierarchy.flat_mode = true;
+    assert_eq!(
+        option_names(&cli_args(&hierarchy)),
+        ["flat", "respect-gitignore"]
+    );
+}
+
+#[test]
+fn test_max_depth_is_passed_on() {
+    let mut setup = setup(ComparisonStrategyType::Filename);
+    setup.max_depth = Some(2);
+    assert_eq!(
+        cli_args(&setup),
+        [
+            "--method",
+            "filename",
+            "--max-depth",
+            "2",
+            "/data/a",
+            "/data/b"
+        ]
+    );
+}
+
+#[test]
+fn test_ignore_hidden_is_passed_on() {
+    let mut setup = setup(ComparisonStrategyType::Filename);
+    setup.ignore_hidden = true;
+    assert_eq!(
+        cli_args(&setup),
+        [
+            "--method",
+            "filename",
+            "--ignore-hidden",
+            "/data/a",
+            "/data/b"
+        ]
+    );
+}
+
+#[test]
+fn test_detect_renames_is_passed_on_in_hierarchy_mode_only() {
+    let mut setup = setup(ComparisonStrategyType::SampledHash);
+    setup.detect_renames = true;
+    assert_eq!(cli_args(&setup), ["--detect-renames", "/data/a", "/data/b"]);
+    setup.flat_mode = true;
+    assert_eq!(cli_args(&setup), ["--flat", "/data/a", "/data/b"]);
+}
+
+#[test]
+fn test_settings_round_trip_through_the_config_parser() {
+    let mut setups = vec![
+        setup(ComparisonStrategyType::SampledHash),
+        setup(ComparisonStrategyType::Filename),
+    ];
+    let mut mtime = setup(ComparisonStrategyType::Mtime);
+    mtime
+        .strategy_options
+        .insert("tolerance_secs".to_string(), OptionValue::Int(3));
+    mtime.ignore_file = Some(PathBuf::from("/data/it's \"quoted\" 写真.ignore"));
+    mtime.dir_a = "/data/line\nbreak".to_string();
+    setups.push(mtime);
+    let mut flat = setup(ComparisonStrategyType::Blake3);
+    flat.flat_mode = true;
+    flat.full_hash = true;
+    flat.respect_gitignore = true;
+    flat.max_depth = Some(3);
+    flat.ignore_hidden = true;
+    setups.push(flat);
+
+    for setup in setups {
+        let text = settings_toml(&setup);
+        let parsed = parse_config(&text).unwrap_or_else(|e| panic!("{}\n{}", e, text));
+
+        // Config files list their options by name
+        let mut expected = setup_options(&setup);
+        expected.sort_by(|(a, _), (b, _)| a.cmp(b));
+        assert_eq!(parsed, expected, "{}", text);
+    }
+}
+
+#[test]
+fn test_settings_record_the_directories_in_comments() {
+    let text = settings_toml(&setup(ComparisonStrategyType::Blake3));
+    assert!(text.contains("# Directory A: /data/a\n"));
+    assert!(text.contains("# Directory B: /data/b\n"));
+    assert_eq!(
+        parse_config(&text).unwrap(),
+        [(
+            "method".to_string(),
+            ConfigValue::String("blake3".to_string())
+        )]
+    );
+}
+
+#[test]
+fn test_setup_from_tab_resolves_the_ignore_file() {
+    let temp = TempDir::new().unwrap();
+    let dir_a = temp.path().join("a");
+    fs::create_dir(&dir_a).unwrap();
+    fs::write(dir_a.join(".dcignore"), "*.tmp\n").unwrap();
+
+    let mut tabs = Tabs::new();
+    let tab = tabs.active_mut();
+    tab.dir_a_path = dir_a.display().to_string();
+    tab.dir_b_path = "/data/b".to_string();
+    tab.ignore_file_path = Some(".dcignore".to_string());
+    tab.flat_mode = true;
+
+    let setup = ComparisonSetup::from_tab(tab, Path::new("/nonexistent"));
+    assert_eq!(setup.ignore_file, Some(dir_a.join(".dcignore")));
+    assert!(setup.flat_mode);
+    assert_eq!(setup.dir_b, "/data/b");
+
+    // A file that cannot be found is kept as entered
+    tab.ignore_file_path = Some(" missing.ignore ".to_string());
+    let setup = ComparisonSetup::from_tab(tab, Path::new("/nonexistent"));
+    assert_eq!(setup.ignore_file, Some(PathBuf::from("missing.ignore")));
+
+    tab.ignore_file_path = Some("  ".to_string());
+    let setup = ComparisonSetup::from_tab(tab, Path::new("/nonexistent"));
+    assert_eq!(setup.ignore_file, None);
+}
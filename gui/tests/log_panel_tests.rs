@@ -0,0 +1,91 @@
+use dir_compare_core::{FastHashStrategy, LogEntry, LogLevel, compare_directories, logger};
+use dir_compare_gui::log_panel::{LOG_CAPACITY, LogPanel, copy_text};
+use dir_compare_gui::progress::ComparisonMessage;
+use dir_compare_gui::tabs::Tabs;
+use std::time::{Duration, SystemTime};
+
+/// Messages the panel shows that start with `prefix`; other tests log through
+/// the same global logger
+fn shown(panel: &LogPanel, prefix: &str) -> Vec<String> {
+    panel
+        .visible_entries()
+        .into_iter()
+        .map(|entry| entry.message)
+        .filter(|message| message.starts_with(prefix))
+        .collect()
+}
+
+#[test]
+fn test_log_panel_collects_and_filters() {
+    let mut panel = LogPanel::register();
+    assert_eq!(panel.buffer().capacity(), LOG_CAPACITY);
+
+    logger::debug("panel-test debug");
+    logger::error("panel-test error");
+    assert_eq!(
+        shown(&panel, "panel-test"),
+        ["panel-test debug", "panel-test error"]
+    );
+
+    panel.min_level = LogLevel::Warn;
+    assert_eq!(shown(&panel, "panel-test"), ["panel-test error"]);
+
+    panel.unregister();
+    logger::error("panel-test after unregister");
+    assert_eq!(shown(&panel, "panel-test"), ["panel-test error"]);
+}
+
+#[test]
+fn test_copy_text_has_one_line_per_record() {
+    let entry = |level, message: &str| LogEntry {
+        level,
+        timestamp: SystemTime::UNIX_EPOCH + Duration::from_micros(1_500_000),
+        target: "dir_compare".to_string(),
+        message: message.to_string(),
+    };
+    let entries = [
+        entry(LogLevel::Warn, "first"),
+        entry(LogLevel::Info, "second"),
+    ];
+    assert_eq!(
+        copy_text(&entries),
+        "[WARN] 1970-01-01T00:00:01.500Z dir_compare: first\n[INFO] 1970-01-01T00:00:01.500Z dir_compare: second\n"
+    );
+    assert_eq!(copy_text(&[]), "");
+}
+
+#[cfg(unix)]
+#[test]
+fn test_comparison_warnings_reach_log_panel() {
+    let dir_a = tempfile::TempDir::new().unwrap();
+    let dir_b = tempfile::TempDir::new().unwrap();
+    // A socket is listed like a file but cannot be opened, even by root
+    let _sockets = [
+        std::os::unix::net::UnixListener::bind(dir_a.path().join("unreadable.sock")).unwrap(),
+        std::os::unix::net::UnixListener::bind(dir_b.path().join("unreadable.sock")).unwrap(),
+    ];
+
+    let panel = LogPanel::register();
+    let result = compare_directories(
+        dir_a.path(),
+        dir_b.path(),
+        &FastHashStrategy::new(false),
+        None,
+    )
+    .unwrap();
+    assert!(!result.warnings.is_empty());
+    let expected: Vec<String> = result.warnings.iter().map(|w| w.to_string()).collect();
+
+    let mut tabs = Tabs::new();
+    let id = tabs.active().id();
+    tabs.route(id, ComparisonMessage::Finished(Ok(result)));
+
+    let warnings: Vec<String> = panel
+        .buffer()
+        .entries(LogLevel::Warn)
+        .into_iter()
+        .map(|entry| entry.message)
+        .filter(|message| message.contains("unreadable.sock"))
+        .collect();
+    assert_eq!(warnings, expected);
+}
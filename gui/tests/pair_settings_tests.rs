@@ -0,0 +1,263 @@
+use dir_compare_core::ComparisonStrategyType;
+use dir_compare_core::strategy_options::OptionValue;
+use dir_compare_gui::pair_settings::{
+    MAX_SAVED_PAIRS, PairKey, PairSettings, RestoreWarning, SavedOptionValue, SavedPairs, offer_for,
+};
+use dir_compare_gui::{Settings, Tabs};
+use tempfile::TempDir;
+
+/// Two existing directories, `a` and `b`
+fn pair_dirs() -> (TempDir, String, String) {
+    let temp = TempDir::new().unwrap();
+    std::fs::create_dir(temp.path().join("a")).unwrap();
+    std::fs::create_dir(temp.path().join("b")).unwrap();
+    let path = |name: &str| temp.path().join(name).display().to_string();
+    let (a, b) = (path("a"), path("b"));
+    (temp, a, b)
+}
+
+/// Sampled hashing without verification, and an ignore file
+fn sampled_settings() -> PairSettings {
+    PairSettings {
+        method: "sampled".to_string(),
+        options: [("verify".to_string(), SavedOptionValue::Bool(false))].into(),
+        flat_mode: false,
+        full_hash: false,
+        ignore_file_path: Some(".dir-compare-ignore".to_string()),
+        respect_gitignore: false,
+        max_depth: None,
+        ignore_hidden: false,
+        detect_renames: false,
+    }
+}
+
+#[test]
+fn test_pair_key_normalization() {
+    let (temp, a, b) = pair_dirs();
+    let key = PairKey::new(&a, &b).unwrap();
+
+    let sep = std::path::MAIN_SEPARATOR;
+    let spelled = temp.path().join("a").join("..").join("a");
+    let spelled = format!("  {}{}  ", spelled.display(), sep);
+    assert_eq!(PairKey::new(&spelled, &b), Some(key.clone()));
+    // The order of the directories matters
+    assert_ne!(PairKey::new(&b, &a), Some(key));
+
+    // Paths that do not exist are compared as typed, without trailing
+    // separators
+    assert_eq!(
+        PairKey::new("/missing/a/", " /missing/b"),
+        PairKey::new("/missing/a", "/missing/b")
+    );
+    assert_eq!(PairKey::new("", &b), None);
+    assert_eq!(PairKey::new(&a, "   "), None);
+}
+
+#[test]
+fn test_offer_requires_both_paths_to_match() {
+    let (temp, a, b) = pair_dirs();
+    let mut saved = SavedPairs::default();
+    saved.remember(PairKey::new(&a, &b).unwrap(), sampled_settings());
+
+    let mut tabs = Tabs::new();
+    let tab = tabs.active_mut();
+    tab.dir_a_path = a.clone();
+    assert!(offer_for(&saved, tab).is_none());
+
+    // Only one path matches
+    std::fs::create_dir(temp.path().join("c")).unwrap();
+    tab.dir_b_path = temp.path().join("c").display().to_string();
+    assert!(offer_for(&saved, tab).is_none());
+
+    tab.dir_b_path = format!("{}/", b);
+    let (key, settings) = offer_for(&saved, tab).unwrap();
+    assert_eq!(key, PairKey::new(&a, &b).unwrap());
+    assert_eq!(*settings, sampled_settings());
+}
+
+#[test]
+fn test_offer_is_not_repeated() {
+    let (_temp, a, b) = pair_dirs();
+    let mut saved = SavedPairs::default();
+    let mut tabs = Tabs::new();
+    let tab = tabs.active_mut();
+    tab.dir_a_path = a.clone();
+    tab.dir_b_path = b.clone();
+
+    // Settings equal to the current ones are not offered
+    saved.remember_tab(tab);
+    assert!(offer_for(&saved, tab).is_none());
+
+    saved.remember(PairKey::of_tab(tab).unwrap(), sampled_settings());
+    assert!(offer_for(&saved, tab).is_some());
+    tab.is_comparing = true;
+    assert!(offer_for(&saved, tab).is_none());
+    tab.is_comparing = false;
+
+    // Dismissing keeps the current selections
+    tab.answered_pair = PairKey::of_tab(tab);
+    assert!(offer_for(&saved, tab).is_none());
+    assert_eq!(tab.comparison_method, ComparisonStrategyType::FastHash);
+    assert_eq!(tab.ignore_file_path, None);
+
+    // Another pair is offered again
+    tab.dir_a_path = b.clone();
+    tab.dir_b_path = a.clone();
+    saved.remember(PairKey::of_tab(tab).unwrap(), sampled_settings());
+    assert!(offer_for(&saved, tab).is_some());
+}
+
+#[test]
+fn test_apply_restores_settings() {
+    let mut tabs = Tabs::new();
+    let tab = tabs.active_mut();
+    tab.flat_mode = true;
+
+    let warnings = sampled_settings().apply_to(tab);
+    assert!(warnings.is_empty(), "{:?}", warnings);
+    assert_eq!(tab.comparison_method, ComparisonStrategyType::SampledHash);
+    assert_eq!(
+        tab.strategy_options.get("verify"),
+        Some(&OptionValue::Bool(false))
+    );
+    assert!(!tab.flat_mode);
+    assert_eq!(tab.ignore_file_path.as_deref(), Some(".dir-compare-ignore"));
+    assert_eq!(PairSettings::from_tab(tab), sampled_settings());
+}
+
+#[test]
+fn test_apply_unknown_method_keeps_current_method() {
+    let mut tabs = Tabs::new();
+    let tab = tabs.active_mut();
+    tab.select_method(ComparisonStrategyType::Mtime);
+    tab.strategy_options
+        .insert("tolerance_secs".to_string(), OptionValue::Int(7));
+    let settings = PairSettings {
+        method: "telepathy".to_string(),
+        ..sampled_settings()
+    };
+
+    let warnings = settings.apply_to(tab);
+    assert_eq!(
+        warnings,
+        vec![RestoreWarning::UnknownMethod {
+            saved: "telepathy".to_string(),
+            kept: ComparisonStrategyType::Mtime,
+        }]
+    );
+    assert_eq!(
+        warnings[0].to_string(),
+        "Saved comparison method 'telepathy' is not available; keeping 'mtime'"
+    );
+    assert_eq!(tab.comparison_method, ComparisonStrategyType::Mtime);
+    assert_eq!(
+        tab.strategy_options.get("tolerance_secs"),
+        Some(&OptionValue::Int(7))
+    );
+    // The other settings are still restored
+    assert_eq!(tab.ignore_file_path.as_deref(), Some(".dir-compare-ignore"));
+}
+
+#[test]
+fn test_apply_invalid_option_takes_default() {
+    let mut tabs = Tabs::new();
+    let tab = tabs.active_mut();
+    let settings = PairSettings {
+        method: "mtime".to_string(),
+        options: [("tolerance_secs".to_string(), SavedOptionValue::Int(-1))].into(),
+        ..sampled_settings()
+    };
+
+    let warnings = settings.apply_to(tab);
+    assert!(
+        matches!(&warnings[..], [RestoreWarning::InvalidOption { option, .. }] if option == "tolerance_secs"),
+        "{:?}",
+        warnings
+    );
+    assert_eq!(
+        tab.strategy_options,
+        ComparisonStrategyType::Mtime.default_options()
+    );
+}
+
+#[test]
+fn test_summary() {
+    assert_eq!(
+        sampled_settings().summary(),
+        "sampled + no verify + ignore .dir-compare-ignore"
+    );
+    let renames = PairSettings {
+        detect_renames: true,
+        ..sampled_settings()
+    };
+    assert_eq!(
+        renames.summary(),
+        "sampled + no verify + renames + ignore .dir-compare-ignore"
+    );
+    let flat = PairSettings {
+        flat_mode: true,
+        full_hash: true,
+        ignore_file_path: None,
+        ..sampled_settings()
+    };
+    assert_eq!(flat.summary(), "flat + full-hash");
+    let gitignore = PairSettings {
+        respect_gitignore: true,
+        max_depth: Some(2),
+        ..flat
+    };
+    assert_eq!(
+        gitignore.summary(),
+        "flat + full-hash + gitignore + depth 2"
+    );
+    let hidden = PairSettings {
+        ignore_hidden: true,
+        ..gitignore
+    };
+    assert_eq!(
+        hidden.summary(),
+        "flat + full-hash + gitignore + depth 2 + no hidden"
+    );
+}
+
+#[test]
+fn test_remember_keeps_most_recent_pairs() {
+    let mut saved = SavedPairs::default();
+    let key = |n: usize| PairKey::new(&format!("/a{}", n), "/b").unwrap();
+    for n in 0..MAX_SAVED_PAIRS + 2 {
+        saved.remember(key(n), sampled_settings());
+    }
+    assert_eq!(saved.pairs.len(), MAX_SAVED_PAIRS);
+    assert!(saved.lookup(&key(0)).is_none());
+    assert!(saved.lookup(&key(2)).is_some());
+
+    let flat = PairSettings {
+        flat_mode: true,
+        ..sampled_settings()
+    };
+    saved.remember(key(5), flat.clone());
+    assert_eq!(saved.pairs.len(), MAX_SAVED_PAIRS);
+    assert_eq!(saved.pairs[0].key, key(5));
+    assert_eq!(saved.lookup(&key(5)), Some(&flat));
+}
+
+#[test]
+fn test_saved_pairs_survive_settings_file() {
+    let mut settings = Settings::default();
+    settings.pairs.remember(
+        PairKey::new("/data", "/backup").unwrap(),
+        sampled_settings(),
+    );
+    let json = serde_json::to_string(&settings).unwrap();
+    assert_eq!(serde_json::from_str::<Settings>(&json).unwrap(), settings);
+
+    // A method unknown to this version is kept until it is applied
+    let json = json.replace(r#""method":"sampled""#, r#""method":"telepathy""#);
+    let loaded: Settings = serde_json::from_str(&json).unwrap();
+    let key = PairKey::new("/data", "/backup").unwrap();
+    assert_eq!(loaded.pairs.lookup(&key).unwrap().method, "telepathy");
+
+    // Files of older versions have no pairs
+    let loaded: Settings = serde_json::from_str(r#"{"dir_a_path": "/a"}"#).unwrap();
+    assert!(loaded.pairs.pairs.is_empty());
+}
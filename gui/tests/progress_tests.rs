@@ -0,0 +1,101 @@
+use dir_compare_core::{Progress, ProgressPhase, Side};
+use dir_compare_gui::progress::{ProgressUpdate, truncate_middle};
+use dir_compare_gui::strings::{Locale, Strings};
+use std::path::Path;
+
+fn update(phase: ProgressPhase, current: usize, total: Option<usize>) -> ProgressUpdate {
+    ProgressUpdate::from(&Progress {
+        phase,
+        current,
+        total,
+        side: None,
+        current_path: Path::new("docs/readme.md"),
+    })
+}
+
+fn scanning(side: Side, current: usize) -> ProgressUpdate {
+    ProgressUpdate {
+        side: Some(side),
+        ..update(ProgressPhase::Traversing, current, None)
+    }
+}
+
+#[test]
+fn test_progress_update_copies_core_progress() {
+    let update = update(ProgressPhase::Matching, 3, Some(40));
+    assert_eq!(update.current_path, Path::new("docs/readme.md"));
+    assert_eq!((update.current, update.total), (3, Some(40)));
+}
+
+#[test]
+fn test_fraction_only_known_while_matching() {
+    assert_eq!(update(ProgressPhase::Traversing, 10, None).fraction(), None);
+    assert_eq!(
+        update(ProgressPhase::Matching, 10, Some(40)).fraction(),
+        Some(0.25)
+    );
+    assert_eq!(
+        update(ProgressPhase::Matching, 0, Some(0)).fraction(),
+        Some(1.0)
+    );
+}
+
+#[test]
+fn test_is_last() {
+    assert!(update(ProgressPhase::Matching, 40, Some(40)).is_last());
+    assert!(!update(ProgressPhase::Matching, 39, Some(40)).is_last());
+    assert!(!update(ProgressPhase::Traversing, 40, None).is_last());
+}
+
+#[test]
+fn test_progress_labels() {
+    let strings = Strings::new(Locale::English);
+    assert_eq!(
+        update(ProgressPhase::Traversing, 12, None).label(&strings),
+        "Scanning: 12 entries"
+    );
+    assert_eq!(
+        update(ProgressPhase::Matching, 3, Some(40)).label(&strings),
+        "Comparing: 3/40 files"
+    );
+
+    assert_eq!(
+        scanning(Side::B, 120_345).label(&strings),
+        "Scanning B… 120,345 entries"
+    );
+
+    let strings = Strings::new(Locale::German);
+    assert_eq!(
+        update(ProgressPhase::Matching, 3, Some(40)).label(&strings),
+        "Vergleiche: 3/40 Dateien"
+    );
+    assert_eq!(
+        scanning(Side::A, 12).label(&strings),
+        "Durchsuche A… 12 Einträge"
+    );
+}
+
+#[test]
+fn test_bar_text_adds_percentage() {
+    let strings = Strings::new(Locale::English);
+    assert_eq!(
+        update(ProgressPhase::Matching, 10, Some(40)).bar_text(&strings),
+        "Comparing: 10/40 files  25%"
+    );
+    assert_eq!(
+        update(ProgressPhase::Traversing, 12, None).bar_text(&strings),
+        "Scanning: 12 entries"
+    );
+}
+
+#[test]
+fn test_truncate_middle() {
+    assert_eq!(truncate_middle("docs/readme.md", 20), "docs/readme.md");
+    assert_eq!(truncate_middle("docs/readme.md", 14), "docs/readme.md");
+    assert_eq!(truncate_middle("docs/readme.md", 9), "docs…e.md");
+    assert_eq!(truncate_middle("dossiers/ärger.md", 8), "doss….md");
+    assert_eq!(
+        update(ProgressPhase::Matching, 1, Some(2)).path_label(7),
+        "doc….md"
+    );
+}
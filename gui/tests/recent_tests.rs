@@ -0,0 +1,112 @@
+use dir_compare_core::ComparisonStrategyType;
+use dir_compare_gui::recent::{MAX_RECENT, RecentComparison, RecentComparisons};
+use dir_compare_gui::{RestoreWarning, Settings, Tabs};
+use std::time::{Duration, UNIX_EPOCH};
+
+fn entry(dir_a: &str, dir_b: &str, method: &str) -> RecentComparison {
+    RecentComparison {
+        dir_a: dir_a.to_string(),
+        dir_b: dir_b.to_string(),
+        method: method.to_string(),
+        compared_at: 1_700_000_000,
+    }
+}
+
+fn labels(recent: &RecentComparisons) -> Vec<String> {
+    recent.entries.iter().map(RecentComparison::label).collect()
+}
+
+#[test]
+fn test_record_moves_repeated_pairs_to_the_top() {
+    let mut recent = RecentComparisons::default();
+    recent.record(entry("/photos", "/backup/photos", "hash"));
+    recent.record(entry("/music", "/backup/music", "size"));
+    // The same pair, spelled with a trailing separator and another method
+    recent.record(entry("/photos/", "/backup/photos", "blake3"));
+
+    assert_eq!(
+        labels(&recent),
+        ["/photos/ → /backup/photos", "/music → /backup/music"]
+    );
+    assert_eq!(recent.entries[0].method, "blake3");
+
+    // The order of the directories matters
+    recent.record(entry("/backup/music", "/music", "size"));
+    assert_eq!(recent.entries.len(), 3);
+}
+
+#[test]
+fn test_record_keeps_the_most_recent_entries() {
+    let mut recent = RecentComparisons::default();
+    for n in 0..MAX_RECENT + 3 {
+        recent.record(entry(&format!("/a{}", n), "/b", "hash"));
+    }
+    assert_eq!(recent.entries.len(), MAX_RECENT);
+    assert_eq!(recent.entries[0].dir_a, format!("/a{}", MAX_RECENT + 2));
+    assert_eq!(recent.entries[MAX_RECENT - 1].dir_a, "/a3");
+
+    recent.clear();
+    assert!(recent.is_empty());
+}
+
+#[test]
+fn test_record_tab_takes_the_tab_inputs() {
+    let mut tabs = Tabs::new();
+    let tab = tabs.active_mut();
+    let mut recent = RecentComparisons::default();
+    recent.record_tab(tab);
+    assert!(recent.is_empty(), "empty paths are not recorded");
+
+    tab.dir_a_path = " /data ".to_string();
+    tab.dir_b_path = "/backup".to_string();
+    tab.select_method(ComparisonStrategyType::Mtime);
+    recent.record_tab(tab);
+
+    let recorded = &recent.entries[0];
+    assert_eq!(recorded.label(), "/data → /backup");
+    assert_eq!(recorded.method, "mtime");
+    assert!(recorded.compared_at() > UNIX_EPOCH + Duration::from_secs(1_700_000_000));
+}
+
+#[test]
+fn test_apply_fills_paths_and_method() {
+    let mut tabs = Tabs::new();
+    let tab = tabs.active_mut();
+
+    // Paths that no longer exist are filled all the same
+    let warning = entry("/missing/a", "/missing/b", "sampled").apply_to(tab);
+    assert_eq!(warning, None);
+    assert_eq!(tab.dir_a_path, "/missing/a");
+    assert_eq!(tab.dir_b_path, "/missing/b");
+    assert_eq!(tab.comparison_method, ComparisonStrategyType::SampledHash);
+
+    let warning = entry("/x", "/y", "telepathy").apply_to(tab);
+    assert_eq!(
+        warning,
+        Some(RestoreWarning::UnknownMethod {
+            saved: "telepathy".to_string(),
+            kept: ComparisonStrategyType::SampledHash,
+        })
+    );
+    assert_eq!(tab.dir_a_path, "/x");
+}
+
+#[test]
+fn test_recent_comparisons_survive_settings_file() {
+    let mut settings = Settings::default();
+    settings.recent.record(entry("/data", "/backup", "hash"));
+    settings
+        .recent
+        .record(entry("/does/not/exist", "/nor/this", "telepathy"));
+    let json = serde_json::to_string(&settings).unwrap();
+    assert!(
+        json.contains(r#""recent":[{"dir_a":"/does/not/exist""#),
+        "{}",
+        json
+    );
+    assert_eq!(serde_json::from_str::<Settings>(&json).unwrap(), settings);
+
+    // Files of older versions have no history
+    let loaded: Settings = serde_json::from_str(r#"{"dir_a_path": "/a"}"#).unwrap();
+    assert!(loaded.recent.is_empty());
+}
@@ -1,5 +1,9 @@
-use dir_compare_core::{EntryKind, FilenameOnlyStrategy, compare_directories};
-use dir_compare_gui::tree_view::FileTreeNode;
+use dir_compare_core::{
+    ComparisonResult, Entry, EntryKind, FilenameOnlyStrategy, MismatchReason, compare_directories,
+};
+use dir_compare_gui::tree_view::{FileTreeNode, count_nodes};
+use dir_compare_gui::{SortState, TreeCache};
+use std::path::PathBuf;
 use tempfile::TempDir;
 
 /// Helper function to create test directory structure
@@ -211,3 +215,68 @@ fn test_tree_node_paths_are_correct() {
         );
     }
 }
+
+fn file(path: &str, size: u64) -> Entry {
+    Entry {
+        path: PathBuf::from(path),
+        abs_path: PathBuf::from("/root").join(path),
+        kind: EntryKind::File,
+        size: Some(size),
+        nested_repository: false,
+        mtime: None,
+    }
+}
+
+#[test]
+fn test_tree_cache_builds_all_four_categories() {
+    let result = ComparisonResult {
+        a_only: vec![file("gone.txt", 1)],
+        b_only: vec![file("added.txt", 2), file("new/deep.txt", 3)],
+        both: vec![(file("same.txt", 4), file("same.txt", 4))],
+        modified: vec![
+            (file("docs/notes.txt", 98), file("docs/notes.txt", 120)),
+            (file("edited.txt", 5), file("edited.txt", 5)),
+        ],
+        exact_counts: None,
+        warnings: Vec::new(),
+        stats: Default::default(),
+        context: None,
+        identical_subtrees: Vec::new(),
+        mismatch_reasons: vec![
+            (
+                PathBuf::from("docs/notes.txt"),
+                MismatchReason::SizeDiffers { a: 98, b: 120 },
+            ),
+            (PathBuf::from("edited.txt"), MismatchReason::ContentDiffers),
+        ],
+        renamed: Vec::new(),
+    };
+    let cache = TreeCache::new(&result, SortState::default());
+
+    let [a_only, b_only, modified, both, renamed] = cache.sections();
+    assert_eq!(count_nodes(a_only), 1);
+    assert_eq!(count_nodes(b_only), 3);
+    assert_eq!(count_nodes(both), 1);
+    assert!(renamed.is_empty());
+
+    // The modified tree shows A's entries with B's size next to them
+    assert_eq!(modified.len(), 2);
+    let notes = &modified[0].children[0];
+    assert_eq!(notes.path, PathBuf::from("docs/notes.txt"));
+    assert_eq!((notes.size, notes.size_b), (Some(98), Some(120)));
+    assert_eq!(
+        notes.mismatch_reason,
+        Some(MismatchReason::SizeDiffers { a: 98, b: 120 })
+    );
+    assert_eq!(modified[1].name, "edited.txt");
+    assert_eq!(
+        modified[1].mismatch_reason,
+        Some(MismatchReason::ContentDiffers)
+    );
+
+    // Each pair is one entry, so the total stays the sum of the categories
+    let total =
+        result.a_only.len() + result.b_only.len() + result.modified.len() + result.both.len();
+    assert_eq!(total, 6);
+    assert_eq!(cache.rows.len(), total);
+}
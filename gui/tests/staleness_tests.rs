@@ -0,0 +1,129 @@
+use dir_compare_core::TreeFingerprint;
+use dir_compare_gui::progress::ComparisonMessage;
+use dir_compare_gui::staleness::{
+    CHECK_INTERVAL, ComparisonSnapshot, RootFingerprints, StalenessMonitor, format_age, is_stale,
+};
+use dir_compare_gui::strings::{Locale, Strings};
+use dir_compare_gui::tabs::Tabs;
+use std::path::PathBuf;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+fn fingerprint(entries: u64, mtime_secs: u64) -> Option<TreeFingerprint> {
+    Some(TreeFingerprint {
+        entries,
+        latest_mtime: Some(UNIX_EPOCH + Duration::from_secs(mtime_secs)),
+    })
+}
+
+fn roots(a: Option<TreeFingerprint>, b: Option<TreeFingerprint>) -> RootFingerprints {
+    RootFingerprints { a, b }
+}
+
+fn snapshot(fingerprints: RootFingerprints) -> ComparisonSnapshot {
+    ComparisonSnapshot {
+        dir_a: PathBuf::from("/a"),
+        dir_b: PathBuf::from("/b"),
+        compared_at: UNIX_EPOCH + Duration::from_secs(1000),
+        fingerprints,
+    }
+}
+
+#[test]
+fn test_unchanged_roots_are_not_stale() {
+    let captured = roots(fingerprint(3, 100), fingerprint(5, 200));
+    assert!(!is_stale(&captured, &captured.clone()));
+}
+
+#[test]
+fn test_changed_a_is_stale() {
+    let captured = roots(fingerprint(3, 100), fingerprint(5, 200));
+    assert!(is_stale(
+        &captured,
+        &roots(fingerprint(4, 100), fingerprint(5, 200))
+    ));
+    assert!(is_stale(
+        &captured,
+        &roots(fingerprint(3, 150), fingerprint(5, 200))
+    ));
+}
+
+#[test]
+fn test_changed_b_is_stale() {
+    let captured = roots(fingerprint(3, 100), fingerprint(5, 200));
+    assert!(is_stale(
+        &captured,
+        &roots(fingerprint(3, 100), fingerprint(5, 250))
+    ));
+}
+
+#[test]
+fn test_unavailable_fingerprint_is_not_stale() {
+    let captured = roots(fingerprint(3, 100), None);
+    assert!(!is_stale(
+        &captured,
+        &roots(fingerprint(3, 100), fingerprint(9, 900))
+    ));
+    let captured = roots(fingerprint(3, 100), fingerprint(5, 200));
+    assert!(!is_stale(&captured, &roots(None, fingerprint(5, 200))));
+}
+
+#[test]
+fn test_banner_follows_the_tab_snapshot() {
+    let mut tabs = Tabs::new();
+    let captured = snapshot(roots(fingerprint(1, 100), fingerprint(1, 100)));
+    let tab = tabs.active_mut();
+    tab.handle_message(ComparisonMessage::Snapshot(captured.clone()));
+    let id = tab.id();
+
+    let mut monitor = StalenessMonitor::new();
+    monitor.record(id, &captured, &captured.fingerprints);
+    assert!(monitor.banner_for(tabs.active()).is_none());
+
+    monitor.record(
+        id,
+        &captured,
+        &roots(fingerprint(2, 100), fingerprint(1, 100)),
+    );
+    let banner = monitor.banner_for(tabs.active()).unwrap();
+    assert_eq!(banner.compared_at, captured.compared_at);
+
+    // Comparing again hides the banner of the previous results
+    tabs.active_mut().start_comparison();
+    assert!(tabs.active().snapshot.is_none());
+    assert!(monitor.banner_for(tabs.active()).is_none());
+}
+
+#[test]
+fn test_checks_are_due_after_interval_or_focus() {
+    let mut tabs = Tabs::new();
+    let mut monitor = StalenessMonitor::new();
+    let start = Instant::now();
+    assert!(monitor.is_due(start, false));
+
+    // A tab without results starts no check but still waits for the interval
+    monitor.start_check(tabs.active_mut(), start, || {});
+    assert!(!monitor.is_due(start + Duration::from_secs(1), false));
+    assert!(monitor.is_due(start + Duration::from_secs(1), true));
+    assert!(monitor.is_due(start + CHECK_INTERVAL, false));
+}
+
+#[test]
+fn test_format_age() {
+    let strings = Strings::new(Locale::English);
+    let age = |secs| format_age(Duration::from_secs(secs), &strings);
+    assert_eq!(age(10), "just now");
+    assert_eq!(age(90), "1 minute ago");
+    assert_eq!(age(150), "2 minutes ago");
+    assert_eq!(age(3600), "1 hour ago");
+    assert_eq!(age(3 * 3600 + 59), "3 hours ago");
+}
+
+#[test]
+fn test_snapshot_capture_fingerprints_both_roots() {
+    let dir = tempfile::TempDir::new().unwrap();
+    let before = SystemTime::now();
+    let snapshot = ComparisonSnapshot::capture(dir.path(), &dir.path().join("missing"));
+    assert!(snapshot.compared_at >= before);
+    assert!(snapshot.fingerprints.a.is_some());
+    assert!(snapshot.fingerprints.b.is_none());
+}
@@ -0,0 +1,324 @@
+use dir_compare_core::{ComparisonResult, Entry, EntryKind};
+use dir_compare_gui::table_view::{
+    ResultCategory, ResultRow, SortColumn, SortDirection, SortState, build_rows, sort_rows,
+};
+use std::path::PathBuf;
+use std::time::{Duration, SystemTime};
+
+fn create_entry(path: &str, kind: EntryKind, size: Option<u64>) -> Entry {
+    Entry {
+        path: PathBuf::from(path),
+        kind,
+        abs_path: PathBuf::from(path),
+        size,
+        nested_repository: false,
+        mtime: None,
+    }
+}
+
+fn create_row(
+    path: &str,
+    category: ResultCategory,
+    size_a: Option<u64>,
+    size_b: Option<u64>,
+) -> ResultRow {
+    ResultRow {
+        path: PathBuf::from(path),
+        category,
+        kind: EntryKind::File,
+        size_a,
+        size_b,
+        mtime: None,
+    }
+}
+
+fn paths(rows: &[ResultRow]) -> Vec<String> {
+    rows.iter().map(|r| r.path.display().to_string()).collect()
+}
+
+fn sorted(mut rows: Vec<ResultRow>, column: SortColumn, direction: SortDirection) -> Vec<String> {
+    sort_rows(&mut rows, SortState { column, direction });
+    paths(&rows)
+}
+
+#[test]
+fn test_build_rows_empty_result() {
+    let result = ComparisonResult {
+        a_only: vec![],
+        b_only: vec![],
+        both: vec![],
+        modified: vec![],
+        exact_counts: None,
+        warnings: Vec::new(),
+        stats: Default::default(),
+        context: None,
+        identical_subtrees: Vec::new(),
+        mismatch_reasons: Vec::new(),
+        renamed: Vec::new(),
+    };
+    assert!(build_rows(&result).is_empty());
+}
+
+#[test]
+fn test_build_rows_categories_and_sizes() {
+    let result = ComparisonResult {
+        a_only: vec![create_entry("a.txt", EntryKind::File, Some(10))],
+        b_only: vec![create_entry("b.txt", EntryKind::File, Some(20))],
+        both: vec![(
+            create_entry("c.txt", EntryKind::File, Some(30)),
+            create_entry("c.txt", EntryKind::File, Some(35)),
+        )],
+        modified: vec![],
+        exact_counts: None,
+        warnings: Vec::new(),
+        stats: Default::default(),
+        context: None,
+        identical_subtrees: Vec::new(),
+        mismatch_reasons: Vec::new(),
+        renamed: Vec::new(),
+    };
+    let rows = build_rows(&result);
+
+    assert_eq!(paths(&rows), vec!["a.txt", "b.txt", "c.txt"]);
+
+    assert_eq!(rows[0].category, ResultCategory::AOnly);
+    assert_eq!((rows[0].size_a, rows[0].size_b), (Some(10), None));
+    assert_eq!(rows[0].delta(), None);
+
+    assert_eq!(rows[1].category, ResultCategory::BOnly);
+    assert_eq!((rows[1].size_a, rows[1].size_b), (None, Some(20)));
+
+    assert_eq!(rows[2].category, ResultCategory::Both);
+    assert_eq!((rows[2].size_a, rows[2].size_b), (Some(30), Some(35)));
+    assert_eq!(rows[2].delta(), Some(5));
+}
+
+#[test]
+fn test_build_rows_modified_pairs() {
+    let result = ComparisonResult {
+        a_only: vec![],
+        b_only: vec![],
+        both: vec![],
+        modified: vec![(
+            create_entry("d.txt", EntryKind::File, Some(40)),
+            create_entry("d.txt", EntryKind::File, Some(25)),
+        )],
+        exact_counts: None,
+        warnings: Vec::new(),
+        stats: Default::default(),
+        context: None,
+        identical_subtrees: Vec::new(),
+        mismatch_reasons: Vec::new(),
+        renamed: Vec::new(),
+    };
+    let rows = build_rows(&result);
+
+    assert_eq!(paths(&rows), vec!["d.txt"]);
+    assert_eq!(rows[0].category, ResultCategory::Modified);
+    assert_eq!((rows[0].size_a, rows[0].size_b), (Some(40), Some(25)));
+    assert_eq!(rows[0].delta(), Some(-15));
+    assert_eq!(rows[0].category.label_key(), "category.modified");
+}
+
+#[test]
+fn test_build_rows_reads_mtime_from_disk() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let file = temp_dir.path().join("file.txt");
+    std::fs::write(&file, "content").unwrap();
+
+    let mut entry = create_entry("file.txt", EntryKind::File, Some(7));
+    entry.abs_path = file;
+    let result = ComparisonResult {
+        a_only: vec![entry],
+        b_only: vec![],
+        both: vec![],
+        modified: vec![],
+        exact_counts: None,
+        warnings: Vec::new(),
+        stats: Default::default(),
+        context: None,
+        identical_subtrees: Vec::new(),
+        mismatch_reasons: Vec::new(),
+        renamed: Vec::new(),
+    };
+
+    let rows = build_rows(&result);
+    assert!(rows[0].mtime.is_some());
+}
+
+#[test]
+fn test_sort_by_path() {
+    let rows = vec![
+        create_row("b", ResultCategory::Both, None, None),
+        create_row("a", ResultCategory::Both, None, None),
+        create_row("c", ResultCategory::Both, None, None),
+    ];
+    assert_eq!(
+        sorted(rows.clone(), SortColumn::Path, SortDirection::Ascending),
+        vec!["a", "b", "c"]
+    );
+    assert_eq!(
+        sorted(rows, SortColumn::Path, SortDirection::Descending),
+        vec!["c", "b", "a"]
+    );
+}
+
+#[test]
+fn test_sort_by_category() {
+    let rows = vec![
+        create_row("x", ResultCategory::Both, None, None),
+        create_row("y", ResultCategory::AOnly, None, None),
+        create_row("z", ResultCategory::BOnly, None, None),
+    ];
+    assert_eq!(
+        sorted(rows.clone(), SortColumn::Category, SortDirection::Ascending),
+        vec!["y", "z", "x"]
+    );
+    assert_eq!(
+        sorted(rows, SortColumn::Category, SortDirection::Descending),
+        vec!["x", "z", "y"]
+    );
+}
+
+#[test]
+fn test_sort_by_kind() {
+    let mut dir = create_row("b_dir", ResultCategory::Both, None, None);
+    dir.kind = EntryKind::Directory;
+    let rows = vec![create_row("a_file", ResultCategory::Both, None, None), dir];
+    assert_eq!(
+        sorted(rows.clone(), SortColumn::Kind, SortDirection::Ascending),
+        vec!["b_dir", "a_file"]
+    );
+    assert_eq!(
+        sorted(rows, SortColumn::Kind, SortDirection::Descending),
+        vec!["a_file", "b_dir"]
+    );
+}
+
+#[test]
+fn test_sort_by_size_a_none_last() {
+    let rows = vec![
+        create_row("none", ResultCategory::BOnly, None, Some(1)),
+        create_row("big", ResultCategory::AOnly, Some(300), None),
+        create_row("small", ResultCategory::AOnly, Some(100), None),
+    ];
+    assert_eq!(
+        sorted(rows.clone(), SortColumn::SizeA, SortDirection::Ascending),
+        vec!["small", "big", "none"]
+    );
+    assert_eq!(
+        sorted(rows, SortColumn::SizeA, SortDirection::Descending),
+        vec!["big", "small", "none"]
+    );
+}
+
+#[test]
+fn test_sort_by_size_b_none_last() {
+    let rows = vec![
+        create_row("none", ResultCategory::AOnly, Some(1), None),
+        create_row("big", ResultCategory::BOnly, None, Some(300)),
+        create_row("small", ResultCategory::BOnly, None, Some(100)),
+    ];
+    assert_eq!(
+        sorted(rows.clone(), SortColumn::SizeB, SortDirection::Ascending),
+        vec!["small", "big", "none"]
+    );
+    assert_eq!(
+        sorted(rows, SortColumn::SizeB, SortDirection::Descending),
+        vec!["big", "small", "none"]
+    );
+}
+
+#[test]
+fn test_sort_by_delta_none_last() {
+    let rows = vec![
+        create_row("none", ResultCategory::AOnly, Some(1), None),
+        create_row("shrunk", ResultCategory::Both, Some(100), Some(40)),
+        create_row("grew", ResultCategory::Both, Some(100), Some(150)),
+    ];
+    assert_eq!(
+        sorted(rows.clone(), SortColumn::Delta, SortDirection::Ascending),
+        vec!["shrunk", "grew", "none"]
+    );
+    assert_eq!(
+        sorted(rows, SortColumn::Delta, SortDirection::Descending),
+        vec!["grew", "shrunk", "none"]
+    );
+}
+
+#[test]
+fn test_sort_by_modified_none_last() {
+    let mut old = create_row("old", ResultCategory::Both, None, None);
+    old.mtime = Some(SystemTime::UNIX_EPOCH + Duration::from_secs(1_000));
+    let mut new = create_row("new", ResultCategory::Both, None, None);
+    new.mtime = Some(SystemTime::UNIX_EPOCH + Duration::from_secs(2_000));
+    let rows = vec![
+        create_row("none", ResultCategory::Both, None, None),
+        new,
+        old,
+    ];
+    assert_eq!(
+        sorted(rows.clone(), SortColumn::Modified, SortDirection::Ascending),
+        vec!["old", "new", "none"]
+    );
+    assert_eq!(
+        sorted(rows, SortColumn::Modified, SortDirection::Descending),
+        vec!["new", "old", "none"]
+    );
+}
+
+#[test]
+fn test_sort_ties_broken_by_path() {
+    let rows = vec![
+        create_row("c", ResultCategory::AOnly, Some(5), None),
+        create_row("a", ResultCategory::AOnly, Some(5), None),
+        create_row("b", ResultCategory::AOnly, Some(5), None),
+    ];
+    assert_eq!(
+        sorted(rows.clone(), SortColumn::SizeA, SortDirection::Ascending),
+        vec!["a", "b", "c"]
+    );
+    assert_eq!(
+        sorted(rows, SortColumn::SizeA, SortDirection::Descending),
+        vec!["a", "b", "c"]
+    );
+}
+
+#[test]
+fn test_sort_is_stable_across_runs() {
+    let rows = vec![
+        create_row("same", ResultCategory::BOnly, None, Some(2)),
+        create_row("same", ResultCategory::AOnly, Some(1), None),
+    ];
+    let state = SortState {
+        column: SortColumn::Kind,
+        direction: SortDirection::Ascending,
+    };
+
+    let mut first = rows.clone();
+    sort_rows(&mut first, state);
+    let mut second = rows;
+    second.reverse();
+    sort_rows(&mut second, state);
+
+    let categories = |rows: &[ResultRow]| rows.iter().map(|r| r.category).collect::<Vec<_>>();
+    assert_eq!(categories(&first), categories(&second));
+    assert_eq!(
+        categories(&first),
+        vec![ResultCategory::AOnly, ResultCategory::BOnly]
+    );
+}
+
+#[test]
+fn test_sort_state_toggle() {
+    let mut state = SortState::default();
+    assert_eq!(state.column, SortColumn::Path);
+    assert_eq!(state.direction, SortDirection::Ascending);
+
+    state.toggle(SortColumn::Path);
+    assert_eq!(state.direction, SortDirection::Descending);
+
+    state.toggle(SortColumn::SizeA);
+    assert_eq!(state.column, SortColumn::SizeA);
+    assert_eq!(state.direction, SortDirection::Ascending);
+}
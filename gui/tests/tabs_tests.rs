@@ -0,0 +1,466 @@
+use dir_compare_core::output::Section;
+use dir_compare_core::text_diff::FileDiff;
+use dir_compare_core::{
+    ComparisonResult, FastHashStrategy, FilenameOnlyStrategy, Progress, ProgressPhase, Side,
+    assert_result_matches, compare_directories,
+};
+use dir_compare_gui::filter_bar::{FilterBar, FilterBarError};
+use dir_compare_gui::progress::{ComparisonMessage, ProgressUpdate};
+use dir_compare_gui::strings::{Locale, Strings};
+use dir_compare_gui::table_view::{ResultCategory, ResultSelection};
+use dir_compare_gui::tabs::Tabs;
+use dir_compare_gui::tree_view::FileTreeNode;
+use std::fs;
+use std::path::{Path, PathBuf};
+use tempfile::TempDir;
+
+/// Compares two fresh directories holding the given file names
+fn comparison(a: &[&str], b: &[&str]) -> ComparisonResult {
+    let dir_a = TempDir::new().unwrap();
+    let dir_b = TempDir::new().unwrap();
+    for name in a {
+        fs::write(dir_a.path().join(name), "content").unwrap();
+    }
+    for name in b {
+        fs::write(dir_b.path().join(name), "content").unwrap();
+    }
+    compare_directories(
+        dir_a.path(),
+        dir_b.path(),
+        &FilenameOnlyStrategy::new(false),
+        None,
+    )
+    .unwrap()
+}
+
+fn progress(current: usize) -> ComparisonMessage {
+    ComparisonMessage::Progress(ProgressUpdate::from(&Progress {
+        phase: ProgressPhase::Matching,
+        current,
+        total: Some(10),
+        side: None,
+        current_path: Path::new("file.txt"),
+    }))
+}
+
+fn scanning(side: Side, current: usize) -> ComparisonMessage {
+    ComparisonMessage::Progress(ProgressUpdate::from(&Progress {
+        phase: ProgressPhase::Traversing,
+        current,
+        total: None,
+        side: Some(side),
+        current_path: Path::new("file.txt"),
+    }))
+}
+
+#[test]
+fn test_new_tabs_become_active() {
+    let mut tabs = Tabs::new();
+    let first = tabs.active().id();
+    let second = tabs.add();
+
+    assert_eq!(tabs.len(), 2);
+    assert_eq!(tabs.active().id(), second);
+    assert_ne!(first, second);
+    assert!(tabs.switch(first));
+    assert_eq!(tabs.active().id(), first);
+}
+
+#[test]
+fn test_messages_are_routed_to_their_tab() {
+    let mut tabs = Tabs::new();
+    let first = tabs.active().id();
+    tabs.active_mut().start_comparison();
+    let second = tabs.add();
+    tabs.active_mut().start_comparison();
+
+    // Workers send from their own threads while the second tab is active
+    let sender = tabs.sender(first);
+    std::thread::spawn(move || {
+        sender.send(progress(7));
+        sender.send(ComparisonMessage::Finished(Ok(comparison(&["a.txt"], &[]))));
+    })
+    .join()
+    .unwrap();
+    tabs.sender(second).send(progress(3));
+    tabs.receive_messages();
+
+    let first_tab = tabs.get(first).unwrap();
+    assert!(!first_tab.is_comparing);
+    assert!(first_tab.progress.is_none());
+    assert!(first_tab.tree_cache.is_some());
+    assert_result_matches!(first_tab.results.as_ref().unwrap(), a_only: ["a.txt"]);
+
+    let second_tab = tabs.get(second).unwrap();
+    assert!(second_tab.is_comparing);
+    assert_eq!(second_tab.progress.as_ref().unwrap().current, 3);
+    assert!(second_tab.results.is_none());
+    assert_eq!(tabs.active().id(), second);
+}
+
+#[test]
+fn test_progress_moves_from_scanning_to_comparing_to_results() {
+    let strings = Strings::new(Locale::English);
+    let mut tabs = Tabs::new();
+    let id = tabs.active().id();
+    tabs.active_mut().start_comparison();
+    let label = |tabs: &Tabs| tabs.active().progress.as_ref().unwrap().label(&strings);
+
+    tabs.sender(id).send(scanning(Side::A, 1_500));
+    tabs.receive_messages();
+    assert_eq!(label(&tabs), "Scanning A… 1,500 entries");
+
+    // Only the latest update is shown
+    tabs.sender(id).send(scanning(Side::B, 10));
+    tabs.sender(id).send(scanning(Side::B, 120_345));
+    tabs.receive_messages();
+    assert_eq!(label(&tabs), "Scanning B… 120,345 entries");
+
+    tabs.sender(id).send(progress(4));
+    tabs.receive_messages();
+    assert_eq!(label(&tabs), "Comparing: 4/10 files");
+    assert!(tabs.active().is_comparing);
+
+    tabs.sender(id)
+        .send(ComparisonMessage::Finished(Ok(comparison(&["a.txt"], &[]))));
+    assert_eq!(tabs.receive_messages(), vec![id]);
+    let tab = tabs.active();
+    assert!(!tab.is_comparing);
+    assert!(tab.progress.is_none());
+    assert!(tab.results.is_some());
+}
+
+#[test]
+fn test_selecting_a_modified_file_loads_its_diff() {
+    let (dir_a, dir_b) = (TempDir::new().unwrap(), TempDir::new().unwrap());
+    fs::write(dir_a.path().join("notes.txt"), "one\ntwo\n").unwrap();
+    fs::write(dir_b.path().join("notes.txt"), "one\n2\n").unwrap();
+    fs::write(dir_a.path().join("same.txt"), "same").unwrap();
+    fs::write(dir_b.path().join("same.txt"), "same").unwrap();
+    let result = compare_directories(
+        dir_a.path(),
+        dir_b.path(),
+        &FastHashStrategy::new(false),
+        None,
+    )
+    .unwrap();
+    let mut tabs = Tabs::new();
+    let id = tabs.active().id();
+    tabs.active_mut().start_comparison();
+    tabs.route(id, ComparisonMessage::Finished(Ok(result)));
+    let tab = tabs.active_mut();
+
+    tab.selection = Some(ResultSelection {
+        category: ResultCategory::Modified,
+        path: PathBuf::from("notes.txt"),
+    });
+    tab.update_diff_view(100);
+    let view = tab.diff_view.as_ref().unwrap();
+    assert_eq!(view.path, Path::new("notes.txt"));
+    let Ok(FileDiff::Text(diff)) = &view.diff else {
+        panic!("expected a text diff");
+    };
+    assert_eq!(diff.changed_lines(), (1, 1));
+
+    // Entries that are not modified files show no diff
+    tab.selection = Some(ResultSelection {
+        category: ResultCategory::Both,
+        path: PathBuf::from("same.txt"),
+    });
+    tab.update_diff_view(100);
+    assert!(tab.diff_view.is_none());
+}
+
+#[test]
+fn test_failed_comparison_sets_tab_error() {
+    let mut tabs = Tabs::new();
+    let id = tabs.active().id();
+    tabs.active_mut().start_comparison();
+
+    assert!(tabs.route(id, ComparisonMessage::Finished(Err("denied".to_string()))));
+    let tab = tabs.active();
+    assert!(!tab.is_comparing);
+    assert!(tab.cancel.is_none());
+    assert_eq!(tab.error_message.as_deref(), Some("denied"));
+}
+
+#[test]
+fn test_closing_tab_cancels_its_worker() {
+    let mut tabs = Tabs::new();
+    let running = tabs.active().id();
+    let cancel = tabs.active_mut().start_comparison();
+    let other = tabs.add();
+    let other_cancel = tabs.active_mut().start_comparison();
+
+    assert!(tabs.close(running));
+    assert!(cancel.is_cancelled());
+    assert!(!other_cancel.is_cancelled());
+    assert_eq!(tabs.len(), 1);
+    assert_eq!(tabs.active().id(), other);
+
+    // The worker of the closed tab may still finish; its result is dropped
+    tabs.sender(running).send(ComparisonMessage::Finished(Err(
+        "comparison cancelled".into()
+    )));
+    tabs.receive_messages();
+    assert!(!tabs.route(running, progress(1)));
+    assert!(tabs.active().is_comparing);
+    assert!(tabs.active().error_message.is_none());
+}
+
+#[test]
+fn test_two_tabs_hold_different_results() {
+    let mut tabs = Tabs::new();
+    let first = tabs.active().id();
+    tabs.active_mut().start_comparison();
+    let second = tabs.add();
+    tabs.active_mut().start_comparison();
+
+    tabs.route(
+        second,
+        ComparisonMessage::Finished(Ok(comparison(&["x.txt"], &["x.txt", "y.txt"]))),
+    );
+    tabs.route(
+        first,
+        ComparisonMessage::Finished(Ok(comparison(&["x.txt", "z.txt"], &[]))),
+    );
+
+    let first = tabs.get(first).unwrap();
+    assert_result_matches!(
+        first.results.as_ref().unwrap(),
+        a_only: ["x.txt", "z.txt"],
+        b_only: [],
+        both: [],
+    );
+    assert_eq!(first.tree_cache.as_ref().unwrap().rows.len(), 2);
+    let second = tabs.get(second).unwrap();
+    assert_result_matches!(
+        second.results.as_ref().unwrap(),
+        a_only: [],
+        b_only: ["y.txt"],
+        both: ["x.txt"],
+    );
+    assert_eq!(second.tree_cache.as_ref().unwrap().rows.len(), 2);
+}
+
+#[test]
+fn test_closing_tabs_keeps_a_valid_active_tab() {
+    let mut tabs = Tabs::new();
+    let first = tabs.active().id();
+    let second = tabs.add();
+    let third = tabs.add();
+
+    tabs.switch(second);
+    assert!(tabs.close(first));
+    assert_eq!(tabs.active().id(), second);
+    assert!(tabs.close(second));
+    assert_eq!(tabs.active().id(), third);
+    assert!(!tabs.close(second), "already closed");
+
+    // Closing the last tab opens an empty one
+    assert!(tabs.close(third));
+    assert_eq!(tabs.len(), 1);
+    assert_ne!(tabs.active().id(), third);
+    assert!(tabs.active().dir_a_path.is_empty());
+}
+
+#[test]
+fn test_tab_title() {
+    let strings = Strings::new(Locale::English);
+    let mut tabs = Tabs::new();
+    assert_eq!(tabs.active().title(&strings), "New comparison");
+
+    let tab = tabs.active_mut();
+    tab.dir_a_path = "/srv/photos".to_string();
+    tab.dir_b_path = "/mnt/mirror/photos/".to_string();
+    assert_eq!(tab.title(&strings), "photos ↔ photos");
+}
+
+#[test]
+fn test_tree_filter_narrows_cached_trees() {
+    let mut tabs = Tabs::new();
+    let tab = tabs.active_mut();
+    tab.tree_filter = "REPORT".to_string();
+    tab.start_comparison();
+    tab.handle_message(ComparisonMessage::Finished(Ok(comparison(
+        &["report.txt", "notes.txt", "shared.txt"],
+        &["old_report.txt", "shared.txt"],
+    ))));
+
+    // A filter typed before the comparison applies to the new trees
+    let cache = tab.tree_cache.as_mut().unwrap();
+    let names = |nodes: &[FileTreeNode]| -> Vec<String> {
+        nodes.iter().map(|node| node.name.clone()).collect()
+    };
+    let [a_only, b_only, modified, both, _] = cache.sections();
+    assert_eq!(names(a_only), ["report.txt"]);
+    assert_eq!(names(b_only), ["old_report.txt"]);
+    assert!(modified.is_empty());
+    assert!(both.is_empty());
+    assert_eq!(cache.filter_counts(), Some((2, 4)));
+
+    cache.set_filter("");
+    let [a_only, b_only, _, both, _] = cache.sections();
+    assert_eq!(names(a_only), ["notes.txt", "report.txt"]);
+    assert_eq!(names(b_only), ["old_report.txt"]);
+    assert_eq!(names(both), ["shared.txt"]);
+    assert_eq!(cache.filter_counts(), None);
+}
+
+#[test]
+fn test_filter_bar_narrows_displayed_results() {
+    let mut tabs = Tabs::new();
+    let tab = tabs.active_mut();
+    tab.start_comparison();
+    tab.handle_message(ComparisonMessage::Finished(Ok(comparison(
+        &["app.dll", "notes.txt", "shared.txt"],
+        &["lib.dll", "shared.txt"],
+    ))));
+    assert!(tab.filtered_results.is_none());
+
+    tab.filter_bar.glob = "*.dll".to_string();
+    tab.filter_bar.sections = [true, false, true, true];
+    tab.apply_filter_bar();
+
+    let displayed = tab.displayed_results().unwrap();
+    assert_eq!(displayed.paths(Section::AOnly), ["app.dll"]);
+    assert!(displayed.b_only.is_empty());
+    assert!(displayed.both.is_empty());
+    // The finished result is kept for exporting and for clearing the filter
+    assert_eq!(tab.results.as_ref().unwrap().a_only.len(), 2);
+    let [a_only, b_only, _, both, _] = tab.tree_cache.as_ref().unwrap().sections();
+    assert_eq!(a_only.len(), 1);
+    assert!(b_only.is_empty());
+    assert!(both.is_empty());
+
+    tab.filter_bar = FilterBar::default();
+    tab.apply_filter_bar();
+    assert!(tab.filtered_results.is_none());
+    assert_eq!(tab.displayed_results().unwrap().a_only.len(), 2);
+}
+
+#[test]
+fn test_filter_bar_invalid_size_keeps_previous_view() {
+    let strings = Strings::new(Locale::English);
+    let mut tabs = Tabs::new();
+    let tab = tabs.active_mut();
+    tab.start_comparison();
+    tab.handle_message(ComparisonMessage::Finished(Ok(comparison(
+        &["a.txt"],
+        &["b.txt"],
+    ))));
+
+    tab.filter_bar.min_size_mb = "ten".to_string();
+    tab.apply_filter_bar();
+
+    let error = tab.filter_error.as_ref().unwrap();
+    assert_eq!(error, &FilterBarError::Size("ten".to_string()));
+    assert!(error.message(&strings).contains("ten"));
+    assert_eq!(tab.displayed_results().unwrap().a_only.len(), 1);
+
+    tab.filter_bar.min_size_mb = "0.5".to_string();
+    tab.apply_filter_bar();
+    assert!(tab.filter_error.is_none());
+    // The files hold a few bytes, below half a megabyte
+    assert!(tab.displayed_results().unwrap().a_only.is_empty());
+}
+
+#[test]
+fn test_swap_directories_swaps_results_in_place() {
+    let mut tabs = Tabs::new();
+    let tab = tabs.active_mut();
+    tab.dir_a_path = "/data/photos".to_string();
+    tab.dir_b_path = "/backup/photos".to_string();
+    tab.start_comparison();
+    tab.handle_message(ComparisonMessage::Finished(Ok(comparison(
+        &["only_a.txt", "shared.txt"],
+        &["only_b.txt", "extra_b.txt", "shared.txt"],
+    ))));
+    // Hide the A-only entries and select a B-only one
+    tab.filter_bar.sections = [false, true, true, true];
+    tab.apply_filter_bar();
+    let row = tab
+        .tree_cache
+        .as_ref()
+        .unwrap()
+        .rows
+        .iter()
+        .find(|row| row.path == Path::new("only_b.txt"));
+    tab.selection = Some(row.unwrap().selection());
+    let root_b = tab.tree_cache.as_ref().unwrap().root_b.clone();
+
+    tab.swap_directories();
+
+    assert_eq!(tab.dir_a_path, "/backup/photos");
+    assert_eq!(tab.dir_b_path, "/data/photos");
+    let results = tab.results.as_ref().unwrap();
+    assert_eq!(results.paths(Section::AOnly), ["extra_b.txt", "only_b.txt"]);
+    assert_eq!(results.paths(Section::BOnly), ["only_a.txt"]);
+    // The same entries stay hidden, now on the B side
+    assert_eq!(tab.filter_bar.sections, [true, false, true, true]);
+    let displayed = tab.displayed_results().unwrap();
+    assert_eq!(displayed.a_only.len(), 2);
+    assert!(displayed.b_only.is_empty());
+
+    let cache = tab.tree_cache.as_ref().unwrap();
+    assert_eq!(cache.root_a, root_b);
+    let selection = tab.selection.as_ref().unwrap();
+    assert_eq!(selection.category, ResultCategory::AOnly);
+    let row = cache.rows.iter().find(|row| row.is_selected(selection));
+    assert!(row.unwrap().size_a.is_some());
+
+    // The trees and rows are those a fresh build would show
+    let swapped = cache.sections().map(|nodes| nodes.to_vec());
+    let swapped_rows: Vec<_> = cache
+        .rows
+        .iter()
+        .map(|row| (row.path.clone(), row.category, row.size_a, row.size_b))
+        .collect();
+    tab.apply_filter_bar();
+    let rebuilt = tab.tree_cache.as_ref().unwrap();
+    let names = |nodes: &[FileTreeNode]| -> Vec<String> {
+        nodes.iter().map(|node| node.name.clone()).collect()
+    };
+    for (swapped, rebuilt) in swapped.iter().zip(rebuilt.sections()) {
+        assert_eq!(names(swapped), names(rebuilt));
+    }
+    let rebuilt_rows: Vec<_> = rebuilt
+        .rows
+        .iter()
+        .map(|row| (row.path.clone(), row.category, row.size_a, row.size_b))
+        .collect();
+    assert_eq!(swapped_rows, rebuilt_rows);
+}
+
+#[test]
+fn test_swap_directories_waits_for_running_comparison() {
+    let mut tabs = Tabs::new();
+    let tab = tabs.active_mut();
+    tab.dir_a_path = "/a".to_string();
+    tab.dir_b_path = "/b".to_string();
+    tab.start_comparison();
+
+    tab.swap_directories();
+    assert_eq!(tab.dir_a_path, "/a");
+
+    tab.handle_message(ComparisonMessage::Finished(Err("boom".to_string())));
+    tab.swap_directories();
+    assert_eq!(tab.dir_a_path, "/b");
+    assert!(tab.results.is_none());
+}
+
+#[test]
+fn test_receive_messages_reports_successful_comparisons() {
+    let mut tabs = Tabs::new();
+    let first = tabs.active().id();
+    let second = tabs.add();
+    let failed = tabs.add();
+    tabs.sender(first)
+        .send(ComparisonMessage::Finished(Ok(comparison(&["a.txt"], &[]))));
+    tabs.sender(second).send(progress(1));
+    tabs.sender(failed)
+        .send(ComparisonMessage::Finished(Err("boom".to_string())));
+
+    assert_eq!(tabs.receive_messages(), [first]);
+    assert!(tabs.receive_messages().is_empty());
+}
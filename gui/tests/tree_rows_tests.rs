@@ -0,0 +1,236 @@
+use dir_compare_core::{ComparisonResult, Entry, EntryKind};
+use dir_compare_gui::tree_view::{TreeExpansion, TreeRow, TreeRows, count_nodes, render_rows};
+use dir_compare_gui::{Locale, ResultCategory, SortState, Strings, TreeCache};
+use eframe::egui;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+fn file(path: &str) -> Entry {
+    Entry {
+        path: PathBuf::from(path),
+        kind: EntryKind::File,
+        abs_path: PathBuf::from("/root").join(path),
+        size: Some(1),
+        nested_repository: false,
+        mtime: None,
+    }
+}
+
+fn result(a_only: Vec<Entry>, b_only: Vec<Entry>) -> ComparisonResult {
+    ComparisonResult {
+        a_only,
+        b_only,
+        both: Vec::new(),
+        modified: Vec::new(),
+        renamed: Vec::new(),
+        exact_counts: None,
+        warnings: Vec::new(),
+        stats: Default::default(),
+        context: None,
+        identical_subtrees: Vec::new(),
+        mismatch_reasons: Vec::new(),
+    }
+}
+
+/// A-only `docs/api/ref.md`, `docs/guide.md` and `top.txt`, B-only `new.txt`
+fn cache() -> TreeCache {
+    let result = result(
+        vec![
+            file("docs/api/ref.md"),
+            file("docs/guide.md"),
+            file("top.txt"),
+        ],
+        vec![file("new.txt")],
+    );
+    TreeCache::new(&result, SortState::default())
+}
+
+/// The rows as text: sections in brackets, nodes indented by their depth
+fn describe(rows: &TreeRows) -> Vec<String> {
+    rows.iter()
+        .map(|row| match row {
+            TreeRow::Section(category) => format!("[{:?}]", category),
+            TreeRow::Node { depth, node, .. } => format!("{}{}", "  ".repeat(depth), node.name),
+        })
+        .collect()
+}
+
+const CLOSED_SECTIONS: [&str; 5] = ["[AOnly]", "[BOnly]", "[Modified]", "[Both]", "[Renamed]"];
+
+#[test]
+fn test_tree_rows_start_with_closed_sections() {
+    let mut cache = cache();
+    let rows = cache.tree_rows(&TreeExpansion::default());
+    assert_eq!(describe(&rows), CLOSED_SECTIONS);
+}
+
+#[test]
+fn test_tree_rows_descend_into_open_folders_only() {
+    let mut cache = cache();
+    let mut expansion = TreeExpansion::default();
+    expansion.toggle_section(ResultCategory::AOnly);
+    assert_eq!(
+        describe(&cache.tree_rows(&expansion)),
+        [
+            "[AOnly]",
+            "docs",
+            "top.txt",
+            "[BOnly]",
+            "[Modified]",
+            "[Both]",
+            "[Renamed]"
+        ]
+    );
+
+    // An open folder inside a closed one stays hidden
+    expansion.toggle(ResultCategory::AOnly, Path::new("docs/api"));
+    assert_eq!(cache.tree_rows(&expansion).len(), 7);
+
+    expansion.toggle(ResultCategory::AOnly, Path::new("docs"));
+    let rows = cache.tree_rows(&expansion);
+    assert_eq!(
+        describe(&rows)[..6],
+        [
+            "[AOnly]",
+            "docs",
+            "  api",
+            "    ref.md",
+            "  guide.md",
+            "top.txt"
+        ]
+    );
+    // Folders are opened per section
+    assert!(!expansion.is_open(ResultCategory::BOnly, Path::new("docs")));
+    match rows.get(3) {
+        TreeRow::Node { category, node, .. } => {
+            assert_eq!(category, ResultCategory::AOnly);
+            assert_eq!(node.path, PathBuf::from("docs/api/ref.md"));
+        }
+        TreeRow::Section(_) => panic!("expected a node"),
+    }
+
+    expansion.toggle(ResultCategory::AOnly, Path::new("docs"));
+    assert_eq!(cache.tree_rows(&expansion).len(), 7);
+}
+
+#[test]
+fn test_expand_all_and_collapse_all() {
+    let mut cache = cache();
+    let mut expansion = TreeExpansion::default();
+    cache.expand_all(&mut expansion);
+    let [a_only, b_only, ..] = cache.sections();
+    let nodes = count_nodes(a_only) + count_nodes(b_only);
+    assert_eq!(cache.tree_rows(&expansion).len(), 5 + nodes);
+
+    // Collapsing keeps the sections open
+    expansion.collapse_all();
+    assert_eq!(
+        describe(&cache.tree_rows(&expansion)),
+        [
+            "[AOnly]",
+            "docs",
+            "top.txt",
+            "[BOnly]",
+            "new.txt",
+            "[Modified]",
+            "[Both]",
+            "[Renamed]"
+        ]
+    );
+
+    // Expanding while filtering opens the folders leading to the matches
+    cache.set_filter("ref");
+    let mut expansion = TreeExpansion::default();
+    cache.expand_all(&mut expansion);
+    assert_eq!(
+        describe(&cache.tree_rows(&expansion)),
+        [
+            "[AOnly]",
+            "docs",
+            "  api",
+            "    ref.md",
+            "[BOnly]",
+            "[Modified]",
+            "[Both]",
+            "[Renamed]"
+        ]
+    );
+}
+
+#[test]
+fn test_expansion_follows_swapped_sides() {
+    let mut expansion = TreeExpansion::default();
+    expansion.toggle_section(ResultCategory::AOnly);
+    expansion.toggle(ResultCategory::AOnly, Path::new("docs"));
+    expansion.toggle_section(ResultCategory::Both);
+    expansion.swap_sides();
+
+    assert!(expansion.is_section_open(ResultCategory::BOnly));
+    assert!(!expansion.is_section_open(ResultCategory::AOnly));
+    assert!(expansion.is_section_open(ResultCategory::Both));
+    assert!(expansion.is_open(ResultCategory::BOnly, Path::new("docs")));
+    assert!(!expansion.is_open(ResultCategory::AOnly, Path::new("docs")));
+}
+
+/// 500,000 files in 100 folders of 50 subfolders each
+fn large_result() -> ComparisonResult {
+    let a_only = (0..500_000)
+        .map(|i| {
+            file(&format!(
+                "dir{:03}/sub{:02}/file{:06}.txt",
+                i / 5_000,
+                i / 100 % 50,
+                i
+            ))
+        })
+        .collect();
+    result(a_only, Vec::new())
+}
+
+#[test]
+#[ignore = "benchmark: builds 500,000 entries; run with --release -- --ignored"]
+fn test_tree_rows_scroll_smoothly_with_500k_entries() {
+    let mut cache = TreeCache::new(&large_result(), SortState::default());
+    let mut expansion = TreeExpansion::default();
+    cache.expand_all(&mut expansion);
+    assert_eq!(
+        cache.tree_rows(&expansion).len(),
+        5 + 100 + 100 * 50 + 500_000
+    );
+
+    // Expanding again flattens the rows again on the next frame
+    cache.expand_all(&mut expansion);
+    let started = Instant::now();
+    cache.tree_rows(&expansion);
+    let flatten = started.elapsed();
+
+    // Scroll through the list, drawing each frame without a window
+    let strings = Strings::new(Locale::English);
+    let ctx = egui::Context::default();
+    let screen = egui::Rect::from_min_size(egui::Pos2::ZERO, egui::vec2(800.0, 600.0));
+    let frames = 60;
+    let mut slowest = Duration::ZERO;
+    for frame in 0..frames {
+        let input = egui::RawInput {
+            screen_rect: Some(screen),
+            events: vec![
+                egui::Event::PointerMoved(screen.center()),
+                egui::Event::Scroll(egui::vec2(0.0, -50_000.0 * frame as f32)),
+            ],
+            ..Default::default()
+        };
+        let started = Instant::now();
+        let _ = ctx.run(input, |ctx| {
+            egui::CentralPanel::default().show(ctx, |ui| {
+                // As in the app, the rows are asked for every frame
+                let rows = cache.tree_rows(&expansion);
+                render_rows(ui, &rows, &mut expansion, None, &strings);
+            });
+        });
+        slowest = slowest.max(started.elapsed());
+    }
+
+    println!("flattened in {:?}, slowest frame {:?}", flatten, slowest);
+    assert!(flatten < Duration::from_millis(100), "{:?}", flatten);
+    assert!(slowest < Duration::from_millis(16), "{:?}", slowest);
+}
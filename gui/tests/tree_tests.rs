@@ -8,6 +8,8 @@ fn create_entry(path: &str, kind: EntryKind) -> Entry {
         kind,
         abs_path: PathBuf::from(path),
         size: Some(0),
+        nested_repository: false,
+        mtime: None,
     }
 }
 
@@ -0,0 +1,127 @@
+use dir_compare_core::ComparisonResult;
+use dir_compare_gui::progress::ComparisonMessage;
+use dir_compare_gui::tabs::{TabSender, Tabs};
+use dir_compare_gui::worker::{WORKER_INACTIVITY, WorkerKind, WorkerWatch, spawn_worker};
+use std::time::{Duration, Instant};
+
+fn empty_result() -> ComparisonResult {
+    ComparisonResult {
+        a_only: Vec::new(),
+        b_only: Vec::new(),
+        both: Vec::new(),
+        modified: Vec::new(),
+        renamed: Vec::new(),
+        exact_counts: None,
+        warnings: Vec::new(),
+        stats: Default::default(),
+        context: None,
+        identical_subtrees: Vec::new(),
+        mismatch_reasons: Vec::new(),
+    }
+}
+
+/// Starts a comparison in the active tab whose worker runs `body`, and waits
+/// for the worker to end
+fn run_worker<F>(tabs: &mut Tabs, kind: WorkerKind, body: F)
+where
+    F: FnOnce(&TabSender) + Send + 'static,
+{
+    let id = tabs.active().id();
+    let sender = tabs.sender(id);
+    let tab = tabs.active_mut();
+    tab.start_comparison();
+    spawn_worker(tab, sender, kind, body).join().unwrap();
+}
+
+#[test]
+fn test_panicking_worker_fails_its_tab() {
+    for kind in [WorkerKind::Hierarchy, WorkerKind::Flat] {
+        let mut tabs = Tabs::new();
+        run_worker(&mut tabs, kind, |_| panic!("index out of bounds"));
+
+        assert!(tabs.receive_messages().is_empty());
+        let tab = tabs.active();
+        assert!(!tab.is_comparing);
+        assert!(tab.worker.is_none());
+        assert_eq!(
+            tab.error_message.as_deref(),
+            Some("Comparison failed unexpectedly: index out of bounds")
+        );
+    }
+}
+
+#[test]
+fn test_silent_worker_is_recovered_after_inactivity() {
+    let mut tabs = Tabs::new();
+    run_worker(&mut tabs, WorkerKind::Hierarchy, |_| {});
+
+    // Within the window the tab keeps waiting for a message
+    tabs.poll_workers(Instant::now(), WORKER_INACTIVITY);
+    assert!(tabs.active().is_comparing);
+
+    tabs.poll_workers(Instant::now() + WORKER_INACTIVITY, WORKER_INACTIVITY);
+    let tab = tabs.active();
+    assert!(!tab.is_comparing);
+    assert!(
+        tab.error_message
+            .as_deref()
+            .is_some_and(|error| error.starts_with("Comparison failed unexpectedly")),
+        "{:?}",
+        tab.error_message
+    );
+}
+
+#[test]
+fn test_running_worker_is_not_recovered() {
+    let mut tabs = Tabs::new();
+    let id = tabs.active().id();
+    let sender = tabs.sender(id);
+    let (release, wait) = std::sync::mpsc::channel::<()>();
+    let tab = tabs.active_mut();
+    tab.start_comparison();
+    let handle = spawn_worker(tab, sender, WorkerKind::Hierarchy, move |sender| {
+        wait.recv().unwrap();
+        sender.send(ComparisonMessage::Finished(Ok(empty_result())));
+    });
+
+    tabs.poll_workers(Instant::now() + Duration::from_secs(60), WORKER_INACTIVITY);
+    assert!(tabs.active().is_comparing);
+    assert!(tabs.active().error_message.is_none());
+
+    release.send(()).unwrap();
+    handle.join().unwrap();
+    // The final message is routed even when the worker counts as lost
+    let finished = tabs.poll_workers(Instant::now() + Duration::from_secs(60), WORKER_INACTIVITY);
+    assert_eq!(finished, vec![id]);
+    assert!(tabs.active().results.is_some());
+    assert!(tabs.active().error_message.is_none());
+}
+
+#[test]
+fn test_completed_worker_is_unaffected() {
+    let mut tabs = Tabs::new();
+    let id = tabs.active().id();
+    run_worker(&mut tabs, WorkerKind::Hierarchy, |sender| {
+        sender.send(ComparisonMessage::Finished(Ok(empty_result())));
+    });
+
+    assert_eq!(tabs.receive_messages(), vec![id]);
+    tabs.poll_workers(Instant::now() + WORKER_INACTIVITY, WORKER_INACTIVITY);
+    let tab = tabs.active();
+    assert!(!tab.is_comparing);
+    assert!(tab.results.is_some());
+    assert!(tab.error_message.is_none());
+}
+
+#[test]
+fn test_worker_watch() {
+    let now = Instant::now();
+    let (watch, token) = WorkerWatch::new(now);
+    assert!(watch.is_alive());
+    assert!(!watch.is_lost(now + WORKER_INACTIVITY, WORKER_INACTIVITY));
+
+    drop(token);
+    assert!(!watch.is_alive());
+    assert!(!watch.is_lost(now, WORKER_INACTIVITY));
+    assert!(watch.is_lost(now + WORKER_INACTIVITY, WORKER_INACTIVITY));
+}